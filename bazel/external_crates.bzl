@@ -697,6 +697,9 @@ def external_crates_repository(name, static_openssl, cargo_lockfile, lockfile):
             "pico-args": crate.spec(
                 version = "^0.3",
             ),
+            "pkcs11": crate.spec(
+                version = "^0.5.0",
+            ),
             "pkg-config": crate.spec(
                 version = "^0.3",
             ),
@@ -763,6 +766,13 @@ def external_crates_repository(name, static_openssl, cargo_lockfile, lockfile):
             "quickcheck": crate.spec(
                 version = "^1.0.3",
             ),
+            "quinn": crate.spec(
+                version = "^0.7.2",
+                default_features = False,
+                features = [
+                    "tls-rustls",
+                ],
+            ),
             "quote": crate.spec(
                 version = "^1.0",
             ),
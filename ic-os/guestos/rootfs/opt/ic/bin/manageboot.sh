@@ -112,6 +112,13 @@ Usage:
       has been confirmed previously already (safe to call as many times
       as you like, will not iniate I/O if nothing to be written).
 
+    rollback
+      Abandon the newly installed upgrade and reboot into the previous
+      system. This is only legal while the first boot of the upgrade has
+      not been confirmed yet: the bootloader then falls back to the
+      previous installation on its own, so all that is left to do is to
+      reboot.
+
     current
       Output currently booted system (A or B) on stdout and exit.
 
@@ -180,7 +187,9 @@ case "${ACTION}" in
 
     upgrade-commit) ;&
 
-    confirm)
+    confirm) ;&
+
+    rollback)
         # Re-execute script as root (unless root already) for operations that
         # require privilege.
         if [ $(id -u) != 0 ]; then
@@ -239,6 +248,15 @@ case "${ACTION}" in
             write_grubenv "${GRUBENV_FILE}"
         fi
         ;;
+    rollback)
+        if [ "$boot_cycle" != "failsafe_check" ]; then
+            echo "Cannot roll back a system that has been committed as stable." >&2
+            exit 1
+        fi
+
+        sync
+        reboot
+        ;;
     current)
         echo "${CURRENT_ALTERNATIVE}"
         ;;
@@ -4,21 +4,38 @@ use ic_artifact_pool::{
     consensus_pool::{PoolSectionOps, UncachedConsensusPoolImpl},
 };
 use ic_config::artifact_pool::ArtifactPoolConfig;
-use ic_interfaces::consensus_pool::*;
+use ic_interfaces::{
+    consensus_pool::*,
+    crypto::{MultiSigVerifier, ThresholdSigVerifierByPublicKey},
+};
 use ic_logger::{LoggerImpl, ReplicaLogger};
 use ic_metrics::MetricsRegistry;
+use ic_protobuf::types::v1 as pb;
+use ic_registry_client::client::RegistryClientImpl;
+use ic_registry_local_store::LocalStoreImpl;
 use ic_types::{
-    consensus::{certification::CertificationMessage, CatchUpPackage, ConsensusMessageHashable},
+    consensus::{
+        certification::CertificationMessage, ecdsa::EcdsaPayload, Block, BlockPayload,
+        BlockProposal, CatchUpContentProtobufBytes, CatchUpPackage, ConsensusMessageHashable,
+        Finalization, FinalizationContent, HasHeight, Notarization, NotarizationContent,
+    },
+    crypto::{
+        crypto_hash, CombinedThresholdSig, CombinedThresholdSigOf, CryptoHashOf, CryptoHashable,
+    },
     time::current_time,
+    Height, NodeId, PrincipalId, RegistryVersion, SubnetId,
 };
 use prost::Message;
 use serde::{Deserialize, Serialize};
 use serde_bytes_repr::{ByteFmtDeserializer, ByteFmtSerializer};
 use serde_json::{Deserializer, Serializer};
+use std::collections::{BTreeMap, BTreeSet};
 use std::convert::TryFrom;
 use std::io::BufRead;
 use std::io::Write;
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
 
 fn main() {
     let mut app = Command::new("ic-consensus-pool-util")
@@ -50,6 +67,77 @@ fn main() {
                         .takes_value(true),
                 ),
         )
+        .subcommand(with_height_range_args(
+            Command::new("list")
+                .about("List artifacts by height and type")
+                .arg(
+                    Arg::new("artifact")
+                        .short('a')
+                        .long("artifact")
+                        .value_name("NAME")
+                        .help("Artifact name")
+                        .multiple_occurrences(true)
+                        .multiple_values(true)
+                        .takes_value(true),
+                ),
+        ))
+        .subcommand(with_height_range_args(
+            Command::new("export-chain")
+                .about("Export the finalized block chain to a JSON or CBOR archive")
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Output filename")
+                        .required(true)
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("Archive format")
+                        .possible_values(["json", "cbor"])
+                        .default_value("json")
+                        .takes_value(true),
+                ),
+        ))
+        .subcommand(with_height_range_args(
+            Command::new("verify")
+                .about(
+                    "Verify the finalized block chain, its notarizations and \
+                         finalizations against a CatchUpPackage and a registry local store",
+                )
+                .arg(
+                    Arg::new("registry-local-store")
+                        .long("registry-local-store")
+                        .value_name("DIR")
+                        .help("Path to the registry local store")
+                        .required(true)
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::new("cup")
+                        .long("cup")
+                        .value_name("FILE")
+                        .help(
+                            "CatchUpPackage protobuf file to start from, as written by \
+                                 export-cup-proto (defaults to the highest CUP in the pool)",
+                        )
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::new("subnet-id")
+                        .long("subnet-id")
+                        .value_name("SUBNET_ID")
+                        .help("Subnet id used to verify the signature of the CatchUpPackage")
+                        .takes_value(true),
+                ),
+        ))
+        .subcommand(with_height_range_args(
+            Command::new("payloads").about("Print payload summaries of finalized blocks"),
+        ))
         .arg(arg!(<PATH>       "PATH to the consensus pool directory"));
     let mut help = Vec::new();
     app.write_help(&mut help)
@@ -64,6 +152,14 @@ fn main() {
         import(path)
     } else if let Some(matches) = matches.subcommand_matches("export-cup-proto") {
         export_cup_proto(path, matches)
+    } else if let Some(matches) = matches.subcommand_matches("list") {
+        list(path, matches)
+    } else if let Some(matches) = matches.subcommand_matches("export-chain") {
+        export_chain(path, matches)
+    } else if let Some(matches) = matches.subcommand_matches("verify") {
+        verify(path, matches)
+    } else if let Some(matches) = matches.subcommand_matches("payloads") {
+        payloads(path, matches)
     } else {
         eprintln!(
             "{}",
@@ -72,6 +168,24 @@ fn main() {
     }
 }
 
+fn with_height_range_args(command: Command<'static>) -> Command<'static> {
+    command
+        .arg(
+            Arg::new("from")
+                .long("from")
+                .value_name("HEIGHT")
+                .help("Lowest height to include")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("to")
+                .long("to")
+                .value_name("HEIGHT")
+                .help("Highest height to include")
+                .takes_value(true),
+        )
+}
+
 const ALL_ARTIFACT_NAMES: [&str; 13] = [
     "RandomBeacon",
     "Finalization",
@@ -272,3 +386,720 @@ fn export_cup_proto(path: &str, matches: &clap::ArgMatches) {
     file.write_all(&buf)
         .unwrap_or_else(|err| panic!("Cannot write to file {}: {:?}", filename, err));
 }
+
+fn parse_height_range(matches: &clap::ArgMatches) -> HeightRange {
+    let parse = |name: &str, default: u64| {
+        matches.value_of(name).map_or(default, |h| {
+            h.parse::<u64>()
+                .unwrap_or_else(|err| panic!("Invalid height '{}': {:?}", h, err))
+        })
+    };
+    HeightRange::new(
+        Height::from(parse("from", 0)),
+        Height::from(parse("to", u64::MAX)),
+    )
+}
+
+fn artifact_id_lines<T: ConsensusMessageHashable>(
+    name: &str,
+    artifacts: Box<dyn Iterator<Item = T>>,
+) -> Vec<String> {
+    artifacts
+        .map(|x| {
+            let id = x.get_id();
+            format!(
+                "{}\t{}\t{}",
+                id.height,
+                name,
+                hex::encode(&id.hash.digest().0)
+            )
+        })
+        .collect()
+}
+
+fn certification_id_lines<T: CryptoHashable + HasHeight>(
+    name: &str,
+    artifacts: Box<dyn Iterator<Item = T>>,
+) -> Vec<String> {
+    artifacts
+        .map(|x| {
+            format!(
+                "{}\t{}\t{}",
+                x.height(),
+                name,
+                hex::encode(&crypto_hash(&x).get_ref().0)
+            )
+        })
+        .collect()
+}
+
+/// Returns one line per validated artifact of the given types in the height
+/// range, holding its height, artifact name and hash separated by tabs.
+fn list_artifact_ids(path: &str, artifacts: &[&str], range: &HeightRange) -> Vec<String> {
+    let range = || HeightRange::new(range.min, range.max);
+
+    let consensus_pool = open_consensus_pool(path, true);
+    let certification_pool = open_certification_pool(path, true);
+    let validated = consensus_pool.validated();
+
+    let mut lines = Vec::new();
+    for artifact in artifacts {
+        let artifact = *artifact;
+        lines.extend(match artifact {
+            "RandomBeacon" => artifact_id_lines(
+                artifact,
+                validated.random_beacon().get_by_height_range(range()),
+            ),
+            "Finalization" => artifact_id_lines(
+                artifact,
+                validated.finalization().get_by_height_range(range()),
+            ),
+            "Notarization" => artifact_id_lines(
+                artifact,
+                validated.notarization().get_by_height_range(range()),
+            ),
+            "BlockProposal" => artifact_id_lines(
+                artifact,
+                validated.block_proposal().get_by_height_range(range()),
+            ),
+            "RandomBeaconShare" => artifact_id_lines(
+                artifact,
+                validated.random_beacon_share().get_by_height_range(range()),
+            ),
+            "NotarizationShare" => artifact_id_lines(
+                artifact,
+                validated.notarization_share().get_by_height_range(range()),
+            ),
+            "FinalizationShare" => artifact_id_lines(
+                artifact,
+                validated.finalization_share().get_by_height_range(range()),
+            ),
+            "RandomTape" => artifact_id_lines(
+                artifact,
+                validated.random_tape().get_by_height_range(range()),
+            ),
+            "RandomTapeShare" => artifact_id_lines(
+                artifact,
+                validated.random_tape_share().get_by_height_range(range()),
+            ),
+            "CatchUpPackage" => artifact_id_lines(
+                artifact,
+                validated.catch_up_package().get_by_height_range(range()),
+            ),
+            "CatchUpPackageShare" => artifact_id_lines(
+                artifact,
+                validated
+                    .catch_up_package_share()
+                    .get_by_height_range(range()),
+            ),
+            "Certification" => certification_id_lines(
+                artifact,
+                certification_pool
+                    .persistent_pool
+                    .certifications()
+                    .get_by_height_range(range()),
+            ),
+            "CertificationShare" => certification_id_lines(
+                artifact,
+                certification_pool
+                    .persistent_pool
+                    .certification_shares()
+                    .get_by_height_range(range()),
+            ),
+            _ => unreachable!("Unsupported artifact name: {}", artifact),
+        });
+    }
+    lines
+}
+
+fn list(path: &str, matches: &clap::ArgMatches) {
+    let artifacts = match matches.values_of("artifact") {
+        Some(names) => parse_artifact_names(&names.collect::<Vec<&str>>()),
+        None => ALL_ARTIFACT_NAMES.to_vec(),
+    };
+    let range = parse_height_range(matches);
+    for line in list_artifact_ids(path, &artifacts, &range) {
+        println!("{}", line);
+    }
+}
+
+/// A finalized block together with the notarization and finalization found for
+/// it in the pool. Not every finalized height necessarily has a finalization of
+/// its own, because finalizing a block implicitly finalizes all its ancestors.
+#[derive(Serialize)]
+struct ChainEntry {
+    proposal: BlockProposal,
+    notarization: Option<Notarization>,
+    finalization: Option<Finalization>,
+}
+
+impl ChainEntry {
+    fn height(&self) -> Height {
+        self.proposal.content.as_ref().height
+    }
+}
+
+/// A portable archive of a finalized block chain, anchored at a CatchUpPackage.
+#[derive(Serialize)]
+struct ChainArchive {
+    cup: CatchUpPackage,
+    blocks: Vec<ChainEntry>,
+}
+
+/// Returns the finalized chain on top of the given CUP, ordered by increasing
+/// height. The chain is built by following the parent hashes backwards from the
+/// highest finalized block, and ends early (above the CUP height) if a block is
+/// missing from the pool.
+fn finalized_chain(pool: &UncachedConsensusPoolImpl, cup: &CatchUpPackage) -> Vec<ChainEntry> {
+    let validated = pool.validated();
+    let cup_height = cup.height();
+    let tip = match validated.finalization().get_highest_iter().next() {
+        Some(finalization) if finalization.content.height > cup_height => finalization,
+        _ => return Vec::new(),
+    };
+
+    let mut chain = Vec::new();
+    let mut next_hash = tip.content.block.clone();
+    let mut height = tip.content.height;
+    while height > cup_height {
+        let proposal = match validated
+            .block_proposal()
+            .get_by_height(height)
+            .find(|proposal| proposal.content.get_hash() == &next_hash)
+        {
+            Some(proposal) => proposal,
+            None => {
+                eprintln!(
+                    "Finalized block at height {} is missing from the pool, \
+                     the chain is truncated",
+                    height
+                );
+                break;
+            }
+        };
+        let notarization = validated
+            .notarization()
+            .get_by_height(height)
+            .find(|notarization| notarization.content.block == next_hash);
+        let finalization = validated
+            .finalization()
+            .get_by_height(height)
+            .find(|finalization| finalization.content.block == next_hash);
+        next_hash = proposal.content.as_ref().parent.clone();
+        chain.push(ChainEntry {
+            proposal,
+            notarization,
+            finalization,
+        });
+        height = height.decrement();
+    }
+    chain.reverse();
+    chain
+}
+
+fn read_cup_proto(filename: &str) -> pb::CatchUpPackage {
+    let bytes = std::fs::read(filename)
+        .unwrap_or_else(|err| panic!("Cannot read file {}: {:?}", filename, err));
+    pb::CatchUpPackage::decode(bytes.as_slice())
+        .unwrap_or_else(|err| panic!("Error decoding protobuf: {:?}", err))
+}
+
+/// Returns the archive of the finalized chain on top of the highest CUP in the
+/// pool, restricted to the given height range, or `None` if there are no
+/// finalized blocks in the range.
+fn chain_archive(path: &str, range: &HeightRange) -> Option<ChainArchive> {
+    let consensus_pool = open_consensus_pool(path, true);
+    let cup_proto = consensus_pool.validated().highest_catch_up_package_proto();
+    let cup = CatchUpPackage::try_from(&cup_proto).unwrap_or_else(|err| panic!("{}", err));
+    let blocks = finalized_chain(&consensus_pool, &cup)
+        .into_iter()
+        .filter(|entry| range.min <= entry.height() && entry.height() <= range.max)
+        .collect::<Vec<_>>();
+    if blocks.is_empty() {
+        return None;
+    }
+    Some(ChainArchive { cup, blocks })
+}
+
+fn write_chain_archive<W: Write>(archive: &ChainArchive, format: Option<&str>, writer: &mut W) {
+    match format {
+        Some("cbor") => serde_cbor::to_writer(writer, archive)
+            .unwrap_or_else(|err| panic!("Error encoding CBOR: {:?}", err)),
+        _ => writer
+            .write_all(to_string(archive).as_bytes())
+            .unwrap_or_else(|err| panic!("Error writing JSON: {:?}", err)),
+    }
+}
+
+fn export_chain(path: &str, matches: &clap::ArgMatches) {
+    let filename = matches
+        .value_of("output")
+        .expect("Expect an output filename");
+    let range = parse_height_range(matches);
+    let archive = match chain_archive(path, &range) {
+        Some(archive) => archive,
+        None => {
+            eprintln!("No finalized blocks found in the given range");
+            return;
+        }
+    };
+
+    let mut file = std::fs::File::create(filename)
+        .unwrap_or_else(|err| panic!("Cannot open file {} for write: {:?}", filename, err));
+    write_chain_archive(&archive, matches.value_of("format"), &mut file);
+    println!(
+        "Exported {} finalized blocks between heights {} and {}",
+        archive.blocks.len(),
+        archive.blocks[0].height(),
+        archive.blocks[archive.blocks.len() - 1].height()
+    );
+}
+
+/// Returns the registry version that was used to sign and verify artifacts at
+/// `height`, given the DKG summary block of the interval that contains it.
+fn registry_version_at_height(summary_block: &Block, height: Height) -> Option<RegistryVersion> {
+    let dkg_summary = &summary_block.payload.as_ref().as_summary().dkg;
+    if dkg_summary.current_interval_includes(height) {
+        Some(dkg_summary.registry_version)
+    } else if dkg_summary.next_interval_includes(height) {
+        Some(summary_block.context.registry_version)
+    } else {
+        None
+    }
+}
+
+fn unique_signers(signers: &[NodeId]) -> Result<BTreeSet<NodeId>, String> {
+    let unique_signers: BTreeSet<_> = signers.iter().cloned().collect();
+    if unique_signers.len() != signers.len() {
+        return Err("repeated signers".to_string());
+    }
+    Ok(unique_signers)
+}
+
+/// Verifies the integrity of a single chain entry: the block and payload
+/// hashes, the link to the parent block and the multi-signatures of the
+/// notarization and the finalization.
+fn verify_chain_entry<C>(
+    crypto: &C,
+    entry: &ChainEntry,
+    parent_hash: &CryptoHashOf<Block>,
+    registry_version: RegistryVersion,
+) -> Result<(), String>
+where
+    C: MultiSigVerifier<NotarizationContent> + MultiSigVerifier<FinalizationContent> + ?Sized,
+{
+    let block_hash = entry.proposal.content.get_hash();
+    let block = entry.proposal.content.as_ref();
+    if &crypto_hash(block) != block_hash {
+        return Err("block hash mismatch".to_string());
+    }
+    let payload: &BlockPayload = block.payload.as_ref();
+    if &crypto_hash(payload) != block.payload.get_hash() {
+        return Err("payload hash mismatch".to_string());
+    }
+    if &block.parent != parent_hash {
+        return Err("block does not extend its parent".to_string());
+    }
+    if let Some(notarization) = &entry.notarization {
+        crypto
+            .verify_multi_sig_combined(
+                &notarization.signature.signature,
+                &notarization.content,
+                unique_signers(&notarization.signature.signers)?,
+                registry_version,
+            )
+            .map_err(|err| format!("invalid notarization signature: {}", err))?;
+    }
+    if let Some(finalization) = &entry.finalization {
+        crypto
+            .verify_multi_sig_combined(
+                &finalization.signature.signature,
+                &finalization.content,
+                unique_signers(&finalization.signature.signers)?,
+                registry_version,
+            )
+            .map_err(|err| format!("invalid finalization signature: {}", err))?;
+    }
+    Ok(())
+}
+
+/// The outcome of verifying a finalized chain on top of a CUP.
+#[derive(Default)]
+struct VerificationReport {
+    verified: usize,
+    failures: Vec<(Height, String)>,
+}
+
+/// Verifies the entries of `chain` that lie in the height range, following the
+/// chain upwards from `cup` to track the parent hashes and the DKG summaries.
+fn verify_chain<C>(
+    crypto: &C,
+    cup: &CatchUpPackage,
+    chain: &[ChainEntry],
+    range: &HeightRange,
+) -> VerificationReport
+where
+    C: MultiSigVerifier<NotarizationContent> + MultiSigVerifier<FinalizationContent> + ?Sized,
+{
+    let mut report = VerificationReport::default();
+    let mut summary_block = cup.content.block.get_value().clone();
+    let mut parent_hash = cup.content.block.get_hash().clone();
+    for entry in chain {
+        let height = entry.height();
+        if range.min <= height && height <= range.max {
+            let result = registry_version_at_height(&summary_block, height)
+                .ok_or_else(|| "no DKG summary covers this height".to_string())
+                .and_then(|registry_version| {
+                    verify_chain_entry(crypto, entry, &parent_hash, registry_version)
+                });
+            match result {
+                Ok(()) => report.verified += 1,
+                Err(err) => report.failures.push((height, err)),
+            }
+        }
+        let block = entry.proposal.content.as_ref();
+        if block.payload.is_summary() {
+            summary_block = block.clone();
+        }
+        parent_hash = entry.proposal.content.get_hash().clone();
+    }
+    report
+}
+
+fn verify(path: &str, matches: &clap::ArgMatches) {
+    let local_store_path = matches
+        .value_of("registry-local-store")
+        .expect("Expect a registry local store path");
+    let range = parse_height_range(matches);
+    let consensus_pool = open_consensus_pool(path, true);
+    let cup_proto = match matches.value_of("cup") {
+        Some(filename) => read_cup_proto(filename),
+        None => consensus_pool.validated().highest_catch_up_package_proto(),
+    };
+    let cup = CatchUpPackage::try_from(&cup_proto).unwrap_or_else(|err| panic!("{}", err));
+
+    let data_provider = Arc::new(LocalStoreImpl::new(local_store_path));
+    let registry = Arc::new(RegistryClientImpl::new(data_provider, None));
+    registry
+        .poll_once()
+        .expect("Couldn't poll the registry data provider");
+    let crypto = ic_crypto_for_verification_only::new(registry);
+
+    let mut failures = 0;
+    match matches.value_of("subnet-id") {
+        Some(_) if cup_proto.signature.is_empty() => {
+            println!("CUP at height {} is unsigned (genesis)", cup.height())
+        }
+        Some(subnet_id) => {
+            let subnet_id = SubnetId::from(
+                PrincipalId::from_str(subnet_id)
+                    .unwrap_or_else(|err| panic!("Invalid subnet id {}: {:?}", subnet_id, err)),
+            );
+            match crypto.verify_combined_threshold_sig_by_public_key(
+                &CombinedThresholdSigOf::new(CombinedThresholdSig(cup_proto.signature.clone())),
+                &CatchUpContentProtobufBytes(cup_proto.content.clone()),
+                subnet_id,
+                cup.content.block.get_value().context.registry_version,
+            ) {
+                Ok(()) => println!("CUP at height {} has a valid signature", cup.height()),
+                Err(err) => {
+                    failures += 1;
+                    println!("CUP at height {}: invalid signature: {}", cup.height(), err)
+                }
+            }
+        }
+        None => println!("No subnet id given, skipping verification of the CUP signature"),
+    }
+
+    let chain = finalized_chain(&consensus_pool, &cup);
+    let report = verify_chain(&crypto, &cup, &chain, &range);
+    for (height, err) in &report.failures {
+        println!("Block at height {}: {}", height, err);
+    }
+    failures += report.failures.len();
+
+    println!(
+        "Verified {} finalized blocks on top of the CUP at height {}, {} failures",
+        report.verified,
+        cup.height(),
+        failures
+    );
+    if failures > 0 {
+        std::process::exit(1);
+    }
+}
+
+/// A summary of the contents of a block payload.
+#[derive(Serialize)]
+struct PayloadSummary {
+    height: u64,
+    payload_type: String,
+    ingress_messages: usize,
+    /// The size in bytes of the stream slice payload from each remote subnet.
+    xnet_slices: BTreeMap<String, usize>,
+    bitcoin_responses: usize,
+    canister_http_responses: usize,
+    dkg_dealings: usize,
+    dkg_configs: usize,
+    ecdsa: Option<EcdsaPayloadSummary>,
+}
+
+#[derive(Serialize)]
+struct EcdsaPayloadSummary {
+    signature_agreements: usize,
+    ongoing_signatures: usize,
+    available_quadruples: usize,
+    quadruples_in_creation: usize,
+    idkg_transcripts: usize,
+    ongoing_xnet_reshares: usize,
+    xnet_reshare_agreements: usize,
+}
+
+impl From<&EcdsaPayload> for EcdsaPayloadSummary {
+    fn from(payload: &EcdsaPayload) -> Self {
+        Self {
+            signature_agreements: payload.signature_agreements.len(),
+            ongoing_signatures: payload.ongoing_signatures.len(),
+            available_quadruples: payload.available_quadruples.len(),
+            quadruples_in_creation: payload.quadruples_in_creation.len(),
+            idkg_transcripts: payload.idkg_transcripts.len(),
+            ongoing_xnet_reshares: payload.ongoing_xnet_reshares.len(),
+            xnet_reshare_agreements: payload.xnet_reshare_agreements.len(),
+        }
+    }
+}
+
+impl From<&Block> for PayloadSummary {
+    fn from(block: &Block) -> Self {
+        let mut summary = PayloadSummary {
+            height: block.height.get(),
+            payload_type: block.payload.payload_type().to_string(),
+            ingress_messages: 0,
+            xnet_slices: BTreeMap::new(),
+            bitcoin_responses: 0,
+            canister_http_responses: 0,
+            dkg_dealings: 0,
+            dkg_configs: 0,
+            ecdsa: None,
+        };
+        match block.payload.as_ref() {
+            BlockPayload::Summary(payload) => {
+                summary.dkg_configs = payload.dkg.configs.len();
+                summary.ecdsa = payload.ecdsa.as_ref().map(EcdsaPayloadSummary::from);
+            }
+            BlockPayload::Data(payload) => {
+                let batch = &payload.batch;
+                summary.ingress_messages = batch.ingress.message_count();
+                summary.xnet_slices = batch
+                    .xnet
+                    .stream_slices
+                    .iter()
+                    .map(|(subnet_id, slice)| (subnet_id.to_string(), slice.payload.len()))
+                    .collect();
+                summary.bitcoin_responses = batch.self_validating.get().len();
+                summary.canister_http_responses = batch.canister_http.num_responses();
+                summary.dkg_dealings = payload.dealings.messages.len();
+                summary.ecdsa = payload.ecdsa.as_ref().map(EcdsaPayloadSummary::from);
+            }
+        }
+        summary
+    }
+}
+
+/// Returns the payload summaries of the finalized blocks in the height range.
+fn payload_summaries(path: &str, range: &HeightRange) -> Vec<PayloadSummary> {
+    let consensus_pool = open_consensus_pool(path, true);
+    let cup_proto = consensus_pool.validated().highest_catch_up_package_proto();
+    let cup = CatchUpPackage::try_from(&cup_proto).unwrap_or_else(|err| panic!("{}", err));
+    finalized_chain(&consensus_pool, &cup)
+        .iter()
+        .filter(|entry| range.min <= entry.height() && entry.height() <= range.max)
+        .map(|entry| PayloadSummary::from(entry.proposal.content.as_ref()))
+        .collect()
+}
+
+fn payloads(path: &str, matches: &clap::ArgMatches) {
+    let range = parse_height_range(matches);
+    for summary in payload_summaries(path, &range) {
+        println!(
+            "{}",
+            serde_json::to_string(&summary).expect("Failed to serialize to JSON")
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_test_artifact_pool::consensus_pool::TestConsensusPool;
+    use ic_test_utilities::{
+        crypto::CryptoReturningOk,
+        state_manager::FakeStateManager,
+        types::ids::{node_test_id, subnet_test_id},
+        FastForwardTimeSource,
+    };
+    use ic_test_utilities_registry::{setup_registry, SubnetRecordBuilder};
+
+    const DKG_INTERVAL_LENGTH: u64 = 3;
+
+    /// Creates a persistent pool holding a finalized chain of `height` blocks on
+    /// top of the genesis CUP and runs `test` with the path of the pool.
+    fn with_fixture_pool<T>(height: u64, test: impl FnOnce(&str) -> T) -> T {
+        let tempdir = tempfile::Builder::new()
+            .prefix("consensus-pool-util")
+            .tempdir()
+            .unwrap();
+        let path = tempdir.path().to_str().unwrap().to_string();
+        {
+            let subnet_id = subnet_test_id(1);
+            let committee = vec![node_test_id(0)];
+            let subnet_records = vec![(
+                1,
+                SubnetRecordBuilder::from(&committee)
+                    .with_dkg_interval_length(DKG_INTERVAL_LENGTH)
+                    .build(),
+            )];
+            let mut pool = TestConsensusPool::new(
+                subnet_id,
+                ArtifactPoolConfig::new(tempdir.path().to_path_buf()),
+                FastForwardTimeSource::new(),
+                setup_registry(subnet_id, subnet_records),
+                Arc::new(CryptoReturningOk::default()),
+                Arc::new(FakeStateManager::new()),
+                None,
+            );
+            assert_eq!(
+                pool.advance_round_normal_operation_no_cup_n(height),
+                Height::from(height)
+            );
+            // The subcommands open the certification pool read-only, which
+            // requires the database to exist.
+            open_certification_pool(&path, false);
+        }
+        test(&path)
+    }
+
+    fn range(min: u64, max: u64) -> HeightRange {
+        HeightRange::new(Height::from(min), Height::from(max))
+    }
+
+    fn heights(chain: &[ChainEntry]) -> Vec<u64> {
+        chain.iter().map(|entry| entry.height().get()).collect()
+    }
+
+    #[test]
+    fn list_returns_artifacts_in_height_range() {
+        with_fixture_pool(6, |path| {
+            let lines = list_artifact_ids(path, &["Finalization", "BlockProposal"], &range(2, 3));
+            let entries = lines
+                .iter()
+                .map(|line| {
+                    let fields = line.split('\t').collect::<Vec<_>>();
+                    assert_eq!(fields.len(), 3, "Unexpected line {}", line);
+                    assert_eq!(fields[2].len(), 64, "Unexpected hash in line {}", line);
+                    (fields[0].to_string(), fields[1].to_string())
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(
+                entries,
+                vec![
+                    ("2".to_string(), "Finalization".to_string()),
+                    ("3".to_string(), "Finalization".to_string()),
+                    ("2".to_string(), "BlockProposal".to_string()),
+                    ("3".to_string(), "BlockProposal".to_string()),
+                ]
+            );
+
+            let lines = list_artifact_ids(path, &["CatchUpPackage"], &range(0, u64::MAX));
+            assert_eq!(lines.len(), 1);
+            assert!(lines[0].starts_with("0\tCatchUpPackage\t"));
+        })
+    }
+
+    #[test]
+    fn export_chain_writes_finalized_blocks_in_range() {
+        with_fixture_pool(6, |path| {
+            let archive = chain_archive(path, &range(2, 5)).expect("Missing chain archive");
+            assert_eq!(heights(&archive.blocks), vec![2, 3, 4, 5]);
+            assert!(archive
+                .blocks
+                .iter()
+                .all(|entry| entry.notarization.is_some() && entry.finalization.is_some()));
+
+            let mut json = Vec::new();
+            write_chain_archive(&archive, Some("json"), &mut json);
+            let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+            assert_eq!(json["blocks"].as_array().unwrap().len(), 4);
+            assert!(json["cup"].is_object());
+
+            let mut cbor = Vec::new();
+            write_chain_archive(&archive, Some("cbor"), &mut cbor);
+            match serde_cbor::from_slice(&cbor).unwrap() {
+                serde_cbor::Value::Map(map) => {
+                    match &map[&serde_cbor::Value::Text("blocks".to_string())] {
+                        serde_cbor::Value::Array(blocks) => assert_eq!(blocks.len(), 4),
+                        value => panic!("Unexpected blocks {:?}", value),
+                    }
+                }
+                value => panic!("Unexpected archive {:?}", value),
+            }
+
+            assert!(chain_archive(path, &range(7, u64::MAX)).is_none());
+        })
+    }
+
+    #[test]
+    fn verify_accepts_chain_and_detects_missing_parent() {
+        with_fixture_pool(6, |path| {
+            let pool = open_consensus_pool(path, true);
+            let cup = CatchUpPackage::try_from(&pool.validated().highest_catch_up_package_proto())
+                .unwrap();
+            let mut chain = finalized_chain(&pool, &cup);
+            assert_eq!(heights(&chain), vec![1, 2, 3, 4, 5, 6]);
+            let crypto = CryptoReturningOk::default();
+
+            let report = verify_chain(&crypto, &cup, &chain, &range(0, u64::MAX));
+            assert_eq!(report.verified, 6);
+            assert!(report.failures.is_empty());
+
+            let report = verify_chain(&crypto, &cup, &chain, &range(2, 3));
+            assert_eq!(report.verified, 2);
+            assert!(report.failures.is_empty());
+
+            // Dropping the block at height 2 breaks the link of its child.
+            chain.remove(1);
+            let report = verify_chain(&crypto, &cup, &chain, &range(0, u64::MAX));
+            assert_eq!(report.verified, 4);
+            assert_eq!(
+                report.failures,
+                vec![(
+                    Height::from(3),
+                    "block does not extend its parent".to_string()
+                )]
+            );
+        })
+    }
+
+    #[test]
+    fn payloads_summarizes_finalized_blocks() {
+        with_fixture_pool(6, |path| {
+            let summaries = payload_summaries(path, &range(3, 5));
+            let payload_types = summaries
+                .iter()
+                .map(|summary| (summary.height, summary.payload_type.as_str()))
+                .collect::<Vec<_>>();
+            // The DKG summary block starts the second interval.
+            assert_eq!(
+                payload_types,
+                vec![
+                    (3, "batch_and_dealings"),
+                    (DKG_INTERVAL_LENGTH + 1, "summary"),
+                    (5, "batch_and_dealings"),
+                ]
+            );
+            assert!(summaries
+                .iter()
+                .all(|summary| summary.ingress_messages == 0 && summary.xnet_slices.is_empty()));
+        })
+    }
+}
@@ -568,6 +568,8 @@ mod test {
                 idkg_transcripts: BTreeMap::new(),
                 ongoing_xnet_reshares: BTreeMap::new(),
                 xnet_reshare_agreements: BTreeMap::new(),
+                ongoing_vetkd_requests: BTreeMap::new(),
+                vetkd_agreements: BTreeMap::new(),
                 key_transcript: ecdsa::EcdsaKeyTranscript {
                     current: None,
                     next_in_creation: ecdsa::KeyTranscriptCreation::Begin,
@@ -24,7 +24,7 @@ use ic_types::consensus::{
     catchup::CUPWithOriginalProtobuf,
     ecdsa::{
         ecdsa_msg_id, EcdsaComplaint, EcdsaMessage, EcdsaMessageType, EcdsaOpening, EcdsaPrefixOf,
-        EcdsaSigShare, EcdsaStats, EcdsaStatsNoOp,
+        EcdsaSigShare, EcdsaStats, EcdsaStatsNoOp, VetKdKeyShare,
     },
 };
 use ic_types::crypto::canister_threshold_sig::idkg::{IDkgDealingSupport, SignedIDkgDealing};
@@ -253,6 +253,19 @@ impl EcdsaPoolSection for InMemoryEcdsaPoolSection {
         let object_pool = self.get_pool(EcdsaMessageType::Opening);
         object_pool.iter_by_prefix(prefix)
     }
+
+    fn vetkd_key_shares(&self) -> Box<dyn Iterator<Item = (EcdsaMessageId, VetKdKeyShare)> + '_> {
+        let object_pool = self.get_pool(EcdsaMessageType::VetKdKeyShare);
+        object_pool.iter()
+    }
+
+    fn vetkd_key_shares_by_prefix(
+        &self,
+        prefix: EcdsaPrefixOf<VetKdKeyShare>,
+    ) -> Box<dyn Iterator<Item = (EcdsaMessageId, VetKdKeyShare)> + '_> {
+        let object_pool = self.get_pool(EcdsaMessageType::VetKdKeyShare);
+        object_pool.iter_by_prefix(prefix)
+    }
 }
 
 impl MutableEcdsaPoolSection for InMemoryEcdsaPoolSection {
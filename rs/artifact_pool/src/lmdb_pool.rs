@@ -21,7 +21,7 @@ use ic_types::{
         dkg,
         ecdsa::{
             ecdsa_msg_id, EcdsaComplaint, EcdsaMessage, EcdsaMessageType, EcdsaOpening,
-            EcdsaPrefix, EcdsaPrefixOf, EcdsaSigShare,
+            EcdsaPrefix, EcdsaPrefixOf, EcdsaSigShare, VetKdKeyShare,
         },
         BlockPayload, BlockProposal, CatchUpPackage, CatchUpPackageShare, ConsensusMessage,
         ConsensusMessageHash, ConsensusMessageHashable, Finalization, FinalizationShare, HasHeight,
@@ -1580,6 +1580,7 @@ impl PersistentEcdsaPoolSection {
             EcdsaMessageType::SigShare => TypeKey::new("ECI"),
             EcdsaMessageType::Complaint => TypeKey::new("ECC"),
             EcdsaMessageType::Opening => TypeKey::new("ECO"),
+            EcdsaMessageType::VetKdKeyShare => TypeKey::new("ECV"),
         }
     }
 }
@@ -1664,6 +1665,19 @@ impl EcdsaPoolSection for PersistentEcdsaPoolSection {
         let message_db = self.get_message_db(EcdsaMessageType::Opening);
         message_db.iter(Some(prefix))
     }
+
+    fn vetkd_key_shares(&self) -> Box<dyn Iterator<Item = (EcdsaMessageId, VetKdKeyShare)> + '_> {
+        let message_db = self.get_message_db(EcdsaMessageType::VetKdKeyShare);
+        message_db.iter(None)
+    }
+
+    fn vetkd_key_shares_by_prefix(
+        &self,
+        prefix: EcdsaPrefixOf<VetKdKeyShare>,
+    ) -> Box<dyn Iterator<Item = (EcdsaMessageId, VetKdKeyShare)> + '_> {
+        let message_db = self.get_message_db(EcdsaMessageType::VetKdKeyShare);
+        message_db.iter(Some(prefix))
+    }
 }
 
 impl MutableEcdsaPoolSection for PersistentEcdsaPoolSection {
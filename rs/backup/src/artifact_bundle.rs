@@ -0,0 +1,486 @@
+//! Compressed bundles of consensus artifacts pruned from the backup spool.
+//!
+//! The spool of a subnet is laid out as `<replica_version>/<bucket>/<height>/`.
+//! Heights that are no longer needed to replay from the retained checkpoints
+//! are packed into zstd-compressed tar bundles, one per replica version and
+//! height bucket. Each bundle keeps the spool layout, so unpacking it into the
+//! spool directory of the subnet restores the artifacts in place, and comes
+//! with a manifest listing the hashes of the bundle and of all packed files.
+use ic_crypto_sha::Sha256;
+use ic_recovery::command_helper::exec_cmd;
+use ic_types::SubnetId;
+use serde::{Deserialize, Serialize};
+use slog::{debug, info, Logger};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{create_dir_all, read_dir, remove_dir_all, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const CUP_FILE_NAME: &str = "catch_up_package.bin";
+const BUNDLE_EXTENSION: &str = "tar.zst";
+const MANIFEST_EXTENSION: &str = "manifest.json";
+
+/// Describes the content of a single artifact bundle.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub subnet_id: String,
+    pub replica_version: String,
+    pub from_height: u64,
+    pub to_height: u64,
+    pub bundle_size: u64,
+    pub bundle_sha256: String,
+    pub files: Vec<FileEntry>,
+}
+
+/// A file packed into a bundle, with its path relative to the subnet spool.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileEntry {
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// The directory holding the artifact bundles of the given subnet.
+pub fn bundles_dir(cold_storage_dir: &Path, subnet_id: &SubnetId) -> PathBuf {
+    cold_storage_dir.join(format!("{}/bundles", subnet_id))
+}
+
+/// Returns the height directories of the spool, grouped by replica version.
+pub(crate) fn collect_spool_heights(
+    spool_dir: &Path,
+) -> Result<BTreeMap<String, BTreeMap<u64, PathBuf>>, String> {
+    let mut heights = BTreeMap::new();
+    for version_dir in sub_dirs(spool_dir)? {
+        let replica_version = dir_name(&version_dir)?;
+        let version_heights: &mut BTreeMap<u64, PathBuf> =
+            heights.entry(replica_version).or_default();
+        for bucket_dir in sub_dirs(&version_dir)? {
+            for height_dir in sub_dirs(&bucket_dir)? {
+                if let Ok(height) = dir_name(&height_dir)?.parse::<u64>() {
+                    version_heights.insert(height, height_dir);
+                }
+            }
+        }
+    }
+    Ok(heights)
+}
+
+/// Returns all heights in the spool for which a CUP was backed up.
+pub(crate) fn collect_cup_heights(
+    spool_heights: &BTreeMap<String, BTreeMap<u64, PathBuf>>,
+) -> BTreeSet<u64> {
+    spool_heights
+        .values()
+        .flat_map(|heights| heights.iter())
+        .filter(|(_, dir)| dir.join(CUP_FILE_NAME).exists())
+        .map(|(height, _)| *height)
+        .collect()
+}
+
+/// Returns the lowest height that has to stay in the spool, so that a replay
+/// can start from any of the last `checkpoints_to_keep` CUP heights that were
+/// already replayed. Returns `None` if there are not enough such CUPs yet.
+pub(crate) fn retention_height(
+    cup_heights: &BTreeSet<u64>,
+    last_checkpoint: u64,
+    checkpoints_to_keep: usize,
+) -> Option<u64> {
+    let retained = cup_heights
+        .range(..=last_checkpoint)
+        .rev()
+        .take(checkpoints_to_keep)
+        .collect::<Vec<_>>();
+    if checkpoints_to_keep == 0 || retained.len() < checkpoints_to_keep {
+        return None;
+    }
+    retained.last().map(|height| **height)
+}
+
+/// Packs the given height directories of one replica version into a bundle in
+/// `bundles_dir` and writes its manifest next to it. The height directories
+/// are left untouched.
+pub(crate) fn create_bundle(
+    log: &Logger,
+    subnet_id: &SubnetId,
+    spool_dir: &Path,
+    replica_version: &str,
+    heights: &BTreeMap<u64, PathBuf>,
+    bundles_dir: &Path,
+    work_dir: &Path,
+) -> Result<PathBuf, String> {
+    let (from_height, to_height) = match (heights.keys().next(), heights.keys().next_back()) {
+        (Some(from), Some(to)) => (*from, *to),
+        _ => return Err("No heights to bundle".to_string()),
+    };
+    create_dir_all(bundles_dir)
+        .map_err(|err| format!("Error creating directory {:?}: {:?}", bundles_dir, err))?;
+    create_dir_all(work_dir)
+        .map_err(|err| format!("Error creating directory {:?}: {:?}", work_dir, err))?;
+
+    let mut files = Vec::new();
+    let mut height_paths = String::new();
+    for dir in heights.values() {
+        collect_files(spool_dir, dir, &mut files)?;
+        height_paths.push_str(&relative_path(spool_dir, dir)?);
+        height_paths.push('\n');
+    }
+    let files_from = work_dir.join(format!("{}_{}.list", replica_version, from_height));
+    std::fs::write(&files_from, height_paths)
+        .map_err(|err| format!("Error writing file list: {:?}", err))?;
+
+    let bundle_name = format!(
+        "{}_{:012}_{:012}.{}",
+        replica_version, from_height, to_height, BUNDLE_EXTENSION
+    );
+    let bundle = bundles_dir.join(&bundle_name);
+    let mut cmd = Command::new("tar");
+    cmd.arg("--zstd").arg("-cf").arg(&bundle);
+    cmd.arg("-C").arg(spool_dir);
+    cmd.arg("-T").arg(&files_from);
+    debug!(log, "Will execute: {:?}", cmd);
+    let result = exec_cmd(&mut cmd).map_err(|err| format!("Error packing artifacts: {:?}", err));
+    let _ = std::fs::remove_file(&files_from);
+    result?;
+
+    let (bundle_size, bundle_sha256) = hash_file(&bundle)?;
+    let manifest = BundleManifest {
+        subnet_id: subnet_id.to_string(),
+        replica_version: replica_version.to_string(),
+        from_height,
+        to_height,
+        bundle_size,
+        bundle_sha256,
+        files,
+    };
+    let json = serde_json::to_string_pretty(&manifest)
+        .map_err(|err| format!("Error serializing manifest: {:?}", err))?;
+    let mut file = File::create(manifest_path(&bundle))
+        .map_err(|err| format!("Error creating manifest file: {:?}", err))?;
+    file.write_all(json.as_bytes())
+        .map_err(|err| format!("Error writing manifest: {:?}", err))?;
+    info!(
+        log,
+        "Bundled heights {}..={} of replica version {} into {}",
+        from_height,
+        to_height,
+        replica_version,
+        bundle_name
+    );
+    Ok(bundle)
+}
+
+/// Verifies the bundle described by the manifest at `manifest_path` by checking
+/// the hash of the bundle and, after unpacking it into `work_dir`, the hashes
+/// of all packed files.
+pub fn verify_bundle(log: &Logger, manifest_path: &Path, work_dir: &Path) -> Result<(), String> {
+    let json = std::fs::read_to_string(manifest_path)
+        .map_err(|err| format!("Error reading manifest {:?}: {:?}", manifest_path, err))?;
+    let manifest: BundleManifest = serde_json::from_str(&json)
+        .map_err(|err| format!("Error parsing manifest {:?}: {:?}", manifest_path, err))?;
+    let bundle = bundle_path(manifest_path);
+    let (bundle_size, bundle_sha256) = hash_file(&bundle)?;
+    if bundle_size != manifest.bundle_size || bundle_sha256 != manifest.bundle_sha256 {
+        return Err(format!("Bundle {:?} doesn't match its manifest", bundle));
+    }
+
+    let unpack_dir = work_dir.join(format!(
+        "verify_{}_{}",
+        manifest.replica_version, manifest.from_height
+    ));
+    if unpack_dir.exists() {
+        remove_dir_all(&unpack_dir)
+            .map_err(|err| format!("Error cleaning {:?}: {:?}", unpack_dir, err))?;
+    }
+    create_dir_all(&unpack_dir)
+        .map_err(|err| format!("Error creating directory {:?}: {:?}", unpack_dir, err))?;
+    let mut cmd = Command::new("tar");
+    cmd.arg("--zstd").arg("-xf").arg(&bundle);
+    cmd.arg("-C").arg(&unpack_dir);
+    debug!(log, "Will execute: {:?}", cmd);
+    let result = exec_cmd(&mut cmd)
+        .map_err(|err| format!("Error unpacking bundle {:?}: {:?}", bundle, err))
+        .and_then(|_| {
+            let mut files = Vec::new();
+            collect_files(&unpack_dir, &unpack_dir, &mut files)?;
+            let expected = manifest
+                .files
+                .iter()
+                .map(|entry| (&entry.path, entry))
+                .collect::<BTreeMap<_, _>>();
+            let unpacked = files
+                .iter()
+                .map(|entry| (&entry.path, entry))
+                .collect::<BTreeMap<_, _>>();
+            if expected != unpacked {
+                return Err(format!(
+                    "Content of bundle {:?} doesn't match its manifest",
+                    bundle
+                ));
+            }
+            Ok(())
+        });
+    remove_dir_all(&unpack_dir)
+        .map_err(|err| format!("Error cleaning {:?}: {:?}", unpack_dir, err))?;
+    result
+}
+
+/// Verifies all bundles in `bundles_dir`. Returns the number of verified bundles,
+/// or the errors of all bundles failing the verification.
+pub fn verify_bundles(
+    log: &Logger,
+    bundles_dir: &Path,
+    work_dir: &Path,
+) -> Result<usize, Vec<String>> {
+    let manifests = read_dir(bundles_dir)
+        .map_err(|err| {
+            vec![format!(
+                "Error reading directory {:?}: {}",
+                bundles_dir, err
+            )]
+        })?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.to_string_lossy().ends_with(MANIFEST_EXTENSION))
+        .collect::<BTreeSet<_>>();
+    let errors = manifests
+        .iter()
+        .filter_map(|manifest| {
+            debug!(log, "Verifying bundle of manifest {:?}", manifest);
+            verify_bundle(log, manifest, work_dir).err()
+        })
+        .collect::<Vec<_>>();
+    if errors.is_empty() {
+        Ok(manifests.len())
+    } else {
+        Err(errors)
+    }
+}
+
+fn manifest_path(bundle: &Path) -> PathBuf {
+    let mut path = bundle.as_os_str().to_os_string();
+    path.push(format!(".{}", MANIFEST_EXTENSION));
+    PathBuf::from(path)
+}
+
+fn bundle_path(manifest: &Path) -> PathBuf {
+    let path = manifest.to_string_lossy();
+    PathBuf::from(
+        path.strip_suffix(&format!(".{}", MANIFEST_EXTENSION))
+            .unwrap_or(&path),
+    )
+}
+
+fn hash_file(path: &Path) -> Result<(u64, String), String> {
+    let mut file =
+        File::open(path).map_err(|err| format!("Error opening file {:?}: {:?}", path, err))?;
+    let mut hasher = Sha256::new();
+    let size = std::io::copy(&mut file, &mut hasher)
+        .map_err(|err| format!("Error reading file {:?}: {:?}", path, err))?;
+    Ok((size, hex::encode(hasher.finish())))
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<FileEntry>) -> Result<(), String> {
+    let mut entries = read_dir(dir)
+        .map_err(|err| format!("Error reading directory {:?}: {}", dir, err))?
+        .flatten()
+        .map(|entry| entry.path())
+        .collect::<Vec<_>>();
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            collect_files(root, &path, files)?;
+        } else {
+            let (size, sha256) = hash_file(&path)?;
+            files.push(FileEntry {
+                path: relative_path(root, &path)?,
+                size,
+                sha256,
+            });
+        }
+    }
+    Ok(())
+}
+
+fn relative_path(root: &Path, path: &Path) -> Result<String, String> {
+    path.strip_prefix(root)
+        .map(|path| path.to_string_lossy().to_string())
+        .map_err(|err| format!("Path {:?} is not inside {:?}: {:?}", path, root, err))
+}
+
+fn sub_dirs(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    Ok(read_dir(dir)
+        .map_err(|err| format!("Error reading directory {:?}: {}", dir, err))?
+        .flatten()
+        .filter(|e| e.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        .map(|e| e.path())
+        .collect())
+}
+
+fn dir_name(dir: &Path) -> Result<String, String> {
+    dir.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Invalid directory {:?}", dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_types::PrincipalId;
+    use std::fs::OpenOptions;
+
+    fn logger() -> Logger {
+        Logger::root(slog::Discard, slog::o!())
+    }
+
+    /// Creates a spool with one height directory per given height, each holding
+    /// an artifact file, and with a CUP file at each of the given CUP heights.
+    fn create_spool(spool_dir: &Path, heights: &[u64], cup_heights: &[u64]) {
+        for height in heights {
+            let dir = spool_dir.join(format!("v1/{}/{}", height / 10 * 10, height));
+            create_dir_all(&dir).unwrap();
+            std::fs::write(
+                dir.join("finalization.bin"),
+                format!("finalization {}", height),
+            )
+            .unwrap();
+            if cup_heights.contains(height) {
+                std::fs::write(dir.join(CUP_FILE_NAME), format!("cup {}", height)).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn retention_height_keeps_last_replayed_checkpoints() {
+        let cup_heights = BTreeSet::from([0, 100, 200, 300]);
+        assert_eq!(retention_height(&cup_heights, 300, 2), Some(200));
+        assert_eq!(retention_height(&cup_heights, 250, 2), Some(100));
+        assert_eq!(retention_height(&cup_heights, 300, 4), Some(0));
+        assert_eq!(retention_height(&cup_heights, 300, 5), None);
+        assert_eq!(retention_height(&cup_heights, 300, 0), None);
+    }
+
+    #[test]
+    fn collects_spool_and_cup_heights() {
+        let tmp = tempfile::tempdir().unwrap();
+        create_spool(tmp.path(), &[1, 2, 10, 11], &[10]);
+
+        let spool_heights = collect_spool_heights(tmp.path()).unwrap();
+        assert_eq!(
+            spool_heights["v1"].keys().cloned().collect::<Vec<_>>(),
+            vec![1, 2, 10, 11]
+        );
+        assert_eq!(collect_cup_heights(&spool_heights), BTreeSet::from([10]));
+    }
+
+    #[test]
+    fn bundle_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let spool_dir = tmp.path().join("spool");
+        let bundles_dir = tmp.path().join("bundles");
+        let work_dir = tmp.path().join("work");
+        let subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(1));
+        create_spool(&spool_dir, &[1, 2, 3], &[]);
+        let heights = collect_spool_heights(&spool_dir)
+            .unwrap()
+            .remove("v1")
+            .unwrap();
+
+        let bundle = create_bundle(
+            &logger(),
+            &subnet_id,
+            &spool_dir,
+            "v1",
+            &heights,
+            &bundles_dir,
+            &work_dir,
+        )
+        .unwrap();
+        assert!(bundle.ends_with(format!("v1_{:012}_{:012}.tar.zst", 1, 3)));
+        // The spool is left untouched.
+        assert_eq!(collect_spool_heights(&spool_dir).unwrap()["v1"], heights);
+
+        let manifest: BundleManifest =
+            serde_json::from_str(&std::fs::read_to_string(manifest_path(&bundle)).unwrap())
+                .unwrap();
+        assert_eq!(manifest.subnet_id, subnet_id.to_string());
+        assert_eq!((manifest.from_height, manifest.to_height), (1, 3));
+        assert_eq!(
+            manifest
+                .files
+                .iter()
+                .map(|entry| entry.path.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "v1/0/1/finalization.bin",
+                "v1/0/2/finalization.bin",
+                "v1/0/3/finalization.bin"
+            ]
+        );
+        assert_eq!(verify_bundles(&logger(), &bundles_dir, &work_dir), Ok(1));
+
+        // Unpacking the bundle restores the artifacts at their spool location.
+        let restore_dir = tmp.path().join("restore");
+        create_dir_all(&restore_dir).unwrap();
+        let mut cmd = Command::new("tar");
+        cmd.arg("--zstd").arg("-xf").arg(&bundle);
+        cmd.arg("-C").arg(&restore_dir);
+        exec_cmd(&mut cmd).unwrap();
+        for height in 1..=3 {
+            let path = format!("v1/0/{}/finalization.bin", height);
+            assert_eq!(
+                std::fs::read(restore_dir.join(&path)).unwrap(),
+                std::fs::read(spool_dir.join(&path)).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn verification_fails_on_tampered_bundle_or_manifest() {
+        let tmp = tempfile::tempdir().unwrap();
+        let spool_dir = tmp.path().join("spool");
+        let bundles_dir = tmp.path().join("bundles");
+        let work_dir = tmp.path().join("work");
+        let subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(1));
+        create_spool(&spool_dir, &[1, 2], &[]);
+        let heights = collect_spool_heights(&spool_dir)
+            .unwrap()
+            .remove("v1")
+            .unwrap();
+        let bundle = create_bundle(
+            &logger(),
+            &subnet_id,
+            &spool_dir,
+            "v1",
+            &heights,
+            &bundles_dir,
+            &work_dir,
+        )
+        .unwrap();
+        let manifest = manifest_path(&bundle);
+        let json = std::fs::read_to_string(&manifest).unwrap();
+
+        // A file hash in the manifest doesn't match the packed file.
+        let mut tampered: BundleManifest = serde_json::from_str(&json).unwrap();
+        tampered.files[0].sha256 = hex::encode([0; 32]);
+        std::fs::write(&manifest, serde_json::to_string(&tampered).unwrap()).unwrap();
+        assert!(verify_bundle(&logger(), &manifest, &work_dir).is_err());
+
+        // The bundle was modified after the manifest was written.
+        std::fs::write(&manifest, &json).unwrap();
+        assert_eq!(verify_bundle(&logger(), &manifest, &work_dir), Ok(()));
+        OpenOptions::new()
+            .append(true)
+            .open(&bundle)
+            .unwrap()
+            .write_all(b"garbage")
+            .unwrap();
+        assert!(verify_bundle(&logger(), &manifest, &work_dir).is_err());
+        assert_eq!(
+            verify_bundles(&logger(), &bundles_dir, &work_dir)
+                .unwrap_err()
+                .len(),
+            1
+        );
+    }
+}
@@ -1,3 +1,6 @@
+use crate::artifact_bundle::{
+    bundles_dir, collect_cup_heights, collect_spool_heights, create_bundle, retention_height,
+};
 use crate::notification_client::NotificationClient;
 use crate::util::{block_on, sleep_secs};
 use ic_recovery::command_helper::exec_cmd;
@@ -40,6 +43,7 @@ pub struct BackupHelper {
     pub artifacts_guard: Mutex<bool>,
     pub daily_replays: usize,
     pub do_cold_storage: bool,
+    pub checkpoints_to_keep: Option<usize>,
     pub thread_id: u32,
     pub log: Logger,
 }
@@ -117,6 +121,10 @@ impl BackupHelper {
         )
     }
 
+    fn cold_storage_bundles_dir(&self) -> PathBuf {
+        create_if_not_exists(bundles_dir(&self.cold_storage_dir, &self.subnet_id))
+    }
+
     fn trash_dir(&self) -> PathBuf {
         create_if_not_exists(self.root_dir.join("trash"))
     }
@@ -841,6 +849,70 @@ impl BackupHelper {
         );
         Ok(())
     }
+
+    /// Removes from the spool all heights that are not needed to replay from
+    /// the last `checkpoints_to_keep` replayed checkpoints. If cold storage is
+    /// enabled for the subnet, the removed heights are first packed into
+    /// compressed bundles, one per replica version and height bucket.
+    /// Returns the number of pruned heights together with the lowest height
+    /// that was kept.
+    pub fn prune_spool(&self) -> Result<Option<(usize, u64)>, String> {
+        let checkpoints_to_keep = match self.checkpoints_to_keep {
+            Some(checkpoints_to_keep) => checkpoints_to_keep,
+            None => return Ok(None),
+        };
+        let _guard = self
+            .artifacts_guard
+            .lock()
+            .expect("artifacts mutex lock failed");
+        let spool_dir = self.spool_dir();
+        if !spool_dir.exists() {
+            return Ok(None);
+        }
+        let spool_heights = collect_spool_heights(&spool_dir)?;
+        let cup_heights = collect_cup_heights(&spool_heights);
+        let keep_from = match retention_height(
+            &cup_heights,
+            self.last_state_checkpoint(),
+            checkpoints_to_keep,
+        ) {
+            Some(height) => height,
+            None => return Ok(None),
+        };
+
+        let mut pruned_heights = 0;
+        for (replica_version, heights) in spool_heights {
+            let mut buckets: BTreeMap<u64, BTreeMap<u64, PathBuf>> = BTreeMap::new();
+            for (height, dir) in heights.into_iter().filter(|(h, _)| *h < keep_from) {
+                buckets
+                    .entry(height / BUCKET_SIZE * BUCKET_SIZE)
+                    .or_default()
+                    .insert(height, dir);
+            }
+            for (bucket, heights) in buckets {
+                if self.do_cold_storage {
+                    create_bundle(
+                        &self.log,
+                        &self.subnet_id,
+                        &spool_dir,
+                        &replica_version,
+                        &heights,
+                        &self.cold_storage_bundles_dir(),
+                        &self.work_dir(),
+                    )?;
+                }
+                for dir in heights.values() {
+                    remove_dir_all(dir)
+                        .map_err(|err| format!("Error deleting {:?}: {:?}", dir, err))?;
+                }
+                pruned_heights += heights.len();
+                let bucket_dir = spool_dir.join(format!("{}/{}", replica_version, bucket));
+                remove_if_empty(&bucket_dir)?;
+            }
+            remove_if_empty(&spool_dir.join(&replica_version))?;
+        }
+        Ok(Some((pruned_heights, keep_from)))
+    }
 }
 
 fn into_replica_version(log: &Logger, spool_dir: &DirEntry) -> Option<ReplicaVersion> {
@@ -912,9 +984,149 @@ fn last_checkpoint(dir: &Path) -> u64 {
     last_dir_height(&dir.join("checkpoints"), 16)
 }
 
+fn remove_if_empty(dir: &Path) -> Result<(), String> {
+    let is_empty = read_dir(dir)
+        .map(|mut entries| entries.next().is_none())
+        .unwrap_or(false);
+    if is_empty {
+        remove_dir_all(dir).map_err(|err| format!("Error deleting {:?}: {:?}", dir, err))?;
+    }
+    Ok(())
+}
+
 fn create_if_not_exists(dir: PathBuf) -> PathBuf {
     if !dir.exists() {
         create_dir_all(&dir).unwrap_or_else(|e| panic!("Failure creating directory {dir:?}: {e}"));
     }
     dir
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::artifact_bundle::verify_bundles;
+    use ic_registry_local_store::LocalStoreImpl;
+    use ic_types::PrincipalId;
+
+    const CUP_HEIGHTS: [u64; 3] = [100, 200, 300];
+
+    fn logger() -> Logger {
+        Logger::root(slog::Discard, slog::o!())
+    }
+
+    /// Returns a backup helper rooted in `root_dir`, whose spool holds heights
+    /// 95 to 305 with a CUP at each of `CUP_HEIGHTS`, and whose state has a
+    /// checkpoint at `last_checkpoint`.
+    fn backup_helper(
+        root_dir: &Path,
+        last_checkpoint: u64,
+        checkpoints_to_keep: Option<usize>,
+        do_cold_storage: bool,
+    ) -> BackupHelper {
+        let subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(1));
+        let data_provider = Arc::new(LocalStoreImpl::new(
+            root_dir.join("ic_registry_local_store"),
+        ));
+        let helper = BackupHelper {
+            subnet_id,
+            initial_replica_version: ReplicaVersion::default(),
+            root_dir: root_dir.to_path_buf(),
+            excluded_dirs: vec![],
+            ssh_private_key: String::new(),
+            registry_client: Arc::new(RegistryClientImpl::new(data_provider, None)),
+            notification_client: NotificationClient {
+                push_metrics: false,
+                backup_instance: "test".to_string(),
+                slack_token: String::new(),
+                subnet: subnet_id.to_string(),
+                log: logger(),
+            },
+            downloads_guard: Arc::new(Mutex::new(true)),
+            disk_threshold_warn: 75,
+            cold_storage_dir: root_dir.join("cold_storage"),
+            versions_hot: 2,
+            artifacts_guard: Mutex::new(true),
+            daily_replays: 0,
+            do_cold_storage,
+            checkpoints_to_keep,
+            thread_id: 0,
+            log: logger(),
+        };
+        for height in 95..=305 {
+            let dir = helper.spool_dir().join(format!(
+                "v1/{}/{}",
+                height / BUCKET_SIZE * BUCKET_SIZE,
+                height
+            ));
+            create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("finalization.bin"), height.to_string()).unwrap();
+            if CUP_HEIGHTS.contains(&height) {
+                std::fs::write(dir.join("catch_up_package.bin"), height.to_string()).unwrap();
+            }
+        }
+        create_dir_all(
+            helper
+                .state_dir()
+                .join(format!("checkpoints/{:016x}", last_checkpoint)),
+        )
+        .unwrap();
+        helper
+    }
+
+    fn spool_heights(helper: &BackupHelper) -> Vec<u64> {
+        crate::artifact_bundle::collect_spool_heights(&helper.spool_dir())
+            .unwrap()
+            .values()
+            .flat_map(|heights| heights.keys().cloned())
+            .collect()
+    }
+
+    #[test]
+    fn prune_spool_keeps_heights_of_retained_checkpoints() {
+        let tmp = tempfile::tempdir().unwrap();
+        let helper = backup_helper(tmp.path(), 300, Some(2), false);
+
+        assert_eq!(helper.prune_spool(), Ok(Some((105, 200))));
+        assert_eq!(spool_heights(&helper), (200..=305).collect::<Vec<_>>());
+        assert!(!bundles_dir(&helper.cold_storage_dir, &helper.subnet_id).exists());
+
+        // Pruning again has nothing left to remove.
+        assert_eq!(helper.prune_spool(), Ok(Some((0, 200))));
+    }
+
+    #[test]
+    fn prune_spool_only_counts_replayed_checkpoints() {
+        let tmp = tempfile::tempdir().unwrap();
+        // The CUP at height 300 wasn't replayed yet.
+        let helper = backup_helper(tmp.path(), 250, Some(2), false);
+
+        assert_eq!(helper.prune_spool(), Ok(Some((5, 100))));
+        assert_eq!(spool_heights(&helper), (100..=305).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn prune_spool_does_nothing_without_enough_checkpoints() {
+        let tmp = tempfile::tempdir().unwrap();
+        let helper = backup_helper(tmp.path(), 300, Some(4), false);
+        assert_eq!(helper.prune_spool(), Ok(None));
+        assert_eq!(spool_heights(&helper), (95..=305).collect::<Vec<_>>());
+
+        let helper = backup_helper(tmp.path(), 300, None, false);
+        assert_eq!(helper.prune_spool(), Ok(None));
+        assert_eq!(spool_heights(&helper), (95..=305).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn prune_spool_bundles_pruned_heights_into_cold_storage() {
+        let tmp = tempfile::tempdir().unwrap();
+        let helper = backup_helper(tmp.path(), 300, Some(1), true);
+
+        assert_eq!(helper.prune_spool(), Ok(Some((205, 300))));
+        assert_eq!(spool_heights(&helper), (300..=305).collect::<Vec<_>>());
+        let bundles_dir = bundles_dir(&helper.cold_storage_dir, &helper.subnet_id);
+        assert_eq!(
+            verify_bundles(&helper.log, &bundles_dir, &helper.work_dir()),
+            Ok(1)
+        );
+    }
+}
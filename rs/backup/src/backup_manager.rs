@@ -21,6 +21,7 @@ use tokio::runtime::Handle;
 
 use crate::util::{block_on, sleep_secs};
 use crate::{
+    artifact_bundle::{bundles_dir, verify_bundles},
     backup_helper::BackupHelper,
     cmd::BackupArgs,
     config::{ColdStorage, Config, SubnetConfig},
@@ -139,6 +140,7 @@ impl BackupManager {
                 artifacts_guard: Mutex::new(true),
                 daily_replays,
                 do_cold_storage,
+                checkpoints_to_keep: s.checkpoints_to_keep,
                 thread_id: s.thread_id,
                 log: log.clone(),
             };
@@ -170,6 +172,39 @@ impl BackupManager {
         info!(log, "Configuration updated...");
     }
 
+    pub fn verify(log: Logger, config_file: PathBuf) {
+        let config = Config::load_config(config_file).expect("Config file can't be loaded");
+        let cold_storage_dir = match config.cold_storage {
+            Some(cs) => cs.cold_storage_dir,
+            None => panic!("Cold storage is not configured"),
+        };
+        let work_dir = config.root_dir.join("work_dir/verify");
+        let mut failures = 0;
+        for s in config.subnets {
+            let bundles_dir = bundles_dir(&cold_storage_dir, &s.subnet_id);
+            if !bundles_dir.exists() {
+                info!(log, "No bundles found for subnet {}", s.subnet_id);
+                continue;
+            }
+            match verify_bundles(&log, &bundles_dir, &work_dir) {
+                Ok(verified) => info!(
+                    log,
+                    "Verified {} bundles of subnet {}", verified, s.subnet_id
+                ),
+                Err(errors) => {
+                    failures += errors.len();
+                    for err in errors {
+                        error!(log, "Subnet {}: {}", s.subnet_id, err);
+                    }
+                }
+            }
+        }
+        if failures > 0 {
+            error!(log, "Verification failed for {} bundles", failures);
+            std::process::exit(1);
+        }
+    }
+
     pub fn init(log: Logger, config_file: PathBuf) {
         let config = BackupManager::init_config(config_file);
         BackupManager::init_copy_states(log, config);
@@ -257,6 +292,7 @@ impl BackupManager {
                 replay_period_secs,
                 thread_id,
                 disable_cold_storage: false,
+                checkpoints_to_keep: None,
             })
         }
 
@@ -452,6 +488,27 @@ fn cold_store(m: Arc<BackupManager>) {
                 .push_metrics_version(m.version);
 
             let subnet_id = &b.backup_helper.subnet_id;
+            match b.backup_helper.prune_spool() {
+                Ok(Some((pruned_heights, keep_from))) if pruned_heights > 0 => {
+                    let action_text = if b.backup_helper.do_cold_storage {
+                        "Bundled and pruned"
+                    } else {
+                        "Pruned"
+                    };
+                    b.backup_helper.notification_client.message_slack(format!(
+                        "✅ {} {} heights of subnet {:?} below height *{}*",
+                        action_text, pruned_heights, subnet_id, keep_from
+                    ));
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    let msg = format!("Error pruning the spool of subnet {}: {:?}", subnet_id, err);
+                    error!(m.log, "{}", msg);
+                    b.backup_helper
+                        .notification_client
+                        .report_failure_slack(msg);
+                }
+            }
             match b.backup_helper.need_cold_storage_move() {
                 Ok(need) => {
                     if !need {
@@ -24,4 +24,6 @@ pub enum SubCommand {
     Init,
     /// Upgrade the backup config file
     Upgrade,
+    /// Verify the compressed artifact bundles against their manifests
+    Verify,
 }
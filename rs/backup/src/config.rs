@@ -17,6 +17,10 @@ pub struct SubnetConfig {
     pub replay_period_secs: u64,
     pub thread_id: u32,
     pub disable_cold_storage: bool,
+    /// If set, keep in the spool only the artifacts needed to replay from the
+    /// last `checkpoints_to_keep` replayed checkpoints and bundle the rest.
+    #[serde(default)]
+    pub checkpoints_to_keep: Option<usize>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -55,6 +59,16 @@ impl ConfigValidate for Config {
         if self.disk_threshold_warn > 100 {
             return Err("Disk threshhold warning value is > 100".to_string());
         }
+        if let Some(subnet) = self
+            .subnets
+            .iter()
+            .find(|subnet| subnet.checkpoints_to_keep == Some(0))
+        {
+            return Err(format!(
+                "At least one checkpoint has to be kept for subnet {}",
+                subnet.subnet_id
+            ));
+        }
         // we accept no subnets in the config at the initial stage only
         if self.subnets.is_empty() && self.slack_token != "<INSERT SLACK TOKEN>" {
             return Err("No subnet configured for backup!".to_string());
@@ -1,3 +1,4 @@
+pub mod artifact_bundle;
 pub mod backup_helper;
 pub mod backup_manager;
 pub mod cmd;
@@ -40,7 +40,8 @@ use tokio::task::spawn_blocking;
 //         "sync_period_secs": 1800,
 //         "replay_period_secs": 7200,
 //         "thread_id": 0,
-//         "disable_cold_storage": false
+//         "disable_cold_storage": false,
+//         "checkpoints_to_keep": 3
 //       },
 //       {
 //         "subnet_id": "qwzvq-hye2n-7o7ey-gllix-3bgyy-lfopp-q22hm-oaoez-yqtyi-qz64d-vqe",
@@ -76,6 +77,7 @@ async fn main() {
         match args.subcmd {
             Some(SubCommand::Init) => BackupManager::init(log, args.config_file),
             Some(SubCommand::Upgrade) => BackupManager::upgrade(log, args.config_file),
+            Some(SubCommand::Verify) => BackupManager::verify(log, args.config_file),
             _ => {
                 let bm = BackupManager::new(log, args, &rt);
                 Arc::new(bm).do_backups();
@@ -107,6 +107,17 @@ pub fn build_dashboard() -> Vec<u8> {
                     </thead>
                     <tbody>{}</tbody>
                 </table>
+                <h3>UTXO distribution</h3>
+                <table>
+                    <thead>
+                        <tr>
+                            <th>Value range (BTC)</th>
+                            <th>Count</th>
+                            <th>Total (BTC)</th>
+                        </tr>
+                    </thead>
+                    <tbody>{}</tbody>
+                </table>
                 <h3>Available utxos</h3>
                 <table>
                     <thead>
@@ -160,6 +171,7 @@ pub fn build_dashboard() -> Vec<u8> {
         build_requests_in_flight_tx(),
         build_submitted_transactions(),
         build_finalized_requests(),
+        build_utxo_distribution(),
         build_available_utxos(),
         build_unconfirmed_change(),
         build_account_to_utxos_table(),
@@ -241,6 +253,14 @@ pub fn build_metadata() -> String {
                         <th>Total BTC managed</th>
                         <td>{}</td>
                     </tr>
+                    <tr>
+                        <th>UTXO consolidation threshold</th>
+                        <td>{}</td>
+                    </tr>
+                    <tr>
+                        <th>UTXO consolidation max fee (millisatoshi/vbyte)</th>
+                        <td>{}</td>
+                    </tr>
                 </tbody>
             </table>",
             s.btc_network,
@@ -253,7 +273,9 @@ pub fn build_metadata() -> String {
             s.min_confirmations,
             s.ledger_id,
             DisplayAmount(s.retrieve_btc_min_amount),
-            DisplayAmount(get_total_btc_managed())
+            DisplayAmount(get_total_btc_managed()),
+            s.utxo_consolidation_threshold,
+            s.utxo_consolidation_max_fee_per_vbyte,
         )
     })
 }
@@ -410,6 +432,63 @@ pub fn build_available_utxos() -> String {
     })
 }
 
+pub fn build_utxo_distribution() -> String {
+    // Bucket upper bounds in satoshi: 0.00001, 0.0001, ..., 10 BTC.
+    const BUCKET_BOUNDS: [u64; 7] = [
+        1_000,
+        10_000,
+        100_000,
+        1_000_000,
+        10_000_000,
+        100_000_000,
+        1_000_000_000,
+    ];
+
+    with_utf8_buffer(|buf| {
+        state::read_state(|s| {
+            let mut counts = [0usize; BUCKET_BOUNDS.len() + 1];
+            let mut totals = [0u64; BUCKET_BOUNDS.len() + 1];
+            for utxo in &s.available_utxos {
+                let bucket = BUCKET_BOUNDS
+                    .iter()
+                    .position(|bound| utxo.value < *bound)
+                    .unwrap_or(BUCKET_BOUNDS.len());
+                counts[bucket] += 1;
+                totals[bucket] += utxo.value;
+            }
+
+            for (i, (count, total)) in counts.iter().zip(totals.iter()).enumerate() {
+                let range = match i {
+                    0 => format!("< {}", DisplayAmount(BUCKET_BOUNDS[0])),
+                    i if i == BUCKET_BOUNDS.len() => {
+                        format!("≥ {}", DisplayAmount(BUCKET_BOUNDS[i - 1]))
+                    }
+                    i => format!(
+                        "{} – {}",
+                        DisplayAmount(BUCKET_BOUNDS[i - 1]),
+                        DisplayAmount(BUCKET_BOUNDS[i])
+                    ),
+                };
+                writeln!(
+                    buf,
+                    "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    range,
+                    count,
+                    DisplayAmount(*total)
+                )
+                .unwrap();
+            }
+            writeln!(
+                buf,
+                "<tr><td style='text-align: right;'><b>Total</b></td><td>{}</td><td>{}</td></tr>",
+                s.available_utxos.len(),
+                DisplayAmount(totals.iter().sum::<u64>())
+            )
+            .unwrap();
+        })
+    })
+}
+
 pub fn build_unconfirmed_change() -> String {
     with_utf8_buffer(|buf| {
         state::read_state(|s| {
@@ -123,6 +123,8 @@ mod tests {
             max_time_in_queue_nanos: 0,
             min_confirmations: None,
             mode: crate::state::Mode::GeneralAvailability,
+            utxo_consolidation_threshold: None,
+            utxo_consolidation_max_fee_per_vbyte: None,
         }
     }
 
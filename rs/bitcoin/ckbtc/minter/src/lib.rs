@@ -43,6 +43,29 @@ pub const MINTER_FEE_PER_INPUT: u64 = 246;
 pub const MINTER_FEE_PER_OUTPUT: u64 = 7;
 pub const MINTER_FEE_CONSTANT: u64 = 52;
 
+/// The minimum time the minter waits for a transaction confirmation before
+/// replacing the transaction with a new one paying a higher fee.
+pub const MIN_RESUBMISSION_DELAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The minimum fee rate increment (in millisatoshi per vbyte) for replacement
+/// transactions.  BIP-125 requires a replacement to pay for its own bandwidth
+/// at the minimum relay fee rate, which is 1 satoshi per vbyte by default.
+pub const MIN_RELAY_FEE_PER_VBYTE: MillisatoshiPerByte = 1_000;
+
+/// The maximum number of UTXOs the minter merges in a single consolidation
+/// transaction.
+pub const MAX_UTXOS_PER_CONSOLIDATION: usize = 100;
+
+/// The maximum number of steps the branch-and-bound coin selection explores
+/// before falling back to the greedy algorithm.
+const MAX_BNB_ITERATIONS: usize = 100_000;
+
+/// Having a sequence number lower than (0xffffffff - 1) signals the use of replacement by fee.
+/// It allows us to increase the fee of a transaction already sent to the mempool.
+/// The rbf option is used in `resubmit_transactions`.
+/// https://github.com/bitcoin/bips/blob/master/bip-0125.mediawiki
+const SEQUENCE_RBF_ENABLED: u32 = 0xfffffffd;
+
 #[derive(CandidType, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub struct ECDSAPublicKey {
     pub public_key: Vec<u8>,
@@ -330,6 +353,7 @@ async fn submit_pending_requests() {
                                     used_utxos,
                                     change_output: Some(req.change_output),
                                     submitted_at: ic_cdk::api::time(),
+                                    fee_per_vbyte: Some(fee_millisatoshi_per_vbyte),
                                 },
                             );
                         });
@@ -407,9 +431,13 @@ async fn finalize_requests() {
     // Transactions whose change outpoint is present in the newly fetched UTXOs
     // can be finalized.  Note that all new minter transactions must have a
     // change output because minter always charges a fee for converting tokens.
+    //
+    // We also check the transactions that we replaced: one of them might still
+    // make it into the blockchain instead of its replacement.
     let confirmed_transactions: Vec<_> = state::read_state(|s| {
         s.submitted_transactions
             .iter()
+            .chain(s.stuck_transactions.iter())
             .filter_map(|tx| {
                 tx.change_output.as_ref().and_then(|out| {
                     new_utxos
@@ -429,6 +457,322 @@ async fn finalize_requests() {
     });
 }
 
+/// Replaces submitted transactions that did not get confirmed for a long time
+/// with transactions spending the same UTXOs but paying a higher fee.
+///
+/// All our transactions signal replace-by-fee (BIP-125), so the Bitcoin
+/// network accepts the replacement if it pays a higher fee rate than the
+/// original transaction.
+async fn resubmit_transactions() {
+    let now = ic_cdk::api::time();
+
+    let transactions_to_resubmit = state::read_state(|s| {
+        s.transactions_to_resubmit(now, MIN_RESUBMISSION_DELAY.as_nanos() as u64)
+    });
+
+    if transactions_to_resubmit.is_empty() {
+        return;
+    }
+
+    let ecdsa_public_key = match state::read_state(|s| s.ecdsa_public_key.clone()) {
+        Some(key) => key,
+        None => {
+            log!(
+                P0,
+                "unreachable: have submitted transactions but the ECDSA key is not initialized",
+            );
+            return;
+        }
+    };
+
+    let fee_millisatoshi_per_vbyte = match estimate_fee_per_vbyte().await {
+        Some(fee) => fee,
+        None => return,
+    };
+
+    let (key_name, btc_network) = state::read_state(|s| (s.ecdsa_key_name.clone(), s.btc_network));
+
+    let main_account = Account {
+        owner: ic_cdk::id().into(),
+        subaccount: None,
+    };
+    let main_address = address::account_to_bitcoin_address(&ecdsa_public_key, &main_account);
+
+    for submitted_tx in transactions_to_resubmit {
+        let old_txid = submitted_tx.txid;
+
+        let tx_fee_per_vbyte = match submitted_tx.fee_per_vbyte {
+            Some(prev_fee) => fee_millisatoshi_per_vbyte.max(prev_fee + MIN_RELAY_FEE_PER_VBYTE),
+            None => fee_millisatoshi_per_vbyte,
+        };
+
+        // Consolidation transactions do not serve any requests.
+        let build_result = if submitted_tx.requests.is_empty() {
+            build_consolidation_transaction(
+                &submitted_tx.used_utxos,
+                main_address.clone(),
+                tx_fee_per_vbyte,
+            )
+        } else {
+            let outputs = submitted_tx
+                .requests
+                .iter()
+                .map(|req| (req.address.clone(), req.amount))
+                .collect();
+
+            build_unsigned_transaction_from_inputs(
+                &submitted_tx.used_utxos,
+                outputs,
+                main_address.clone(),
+                tx_fee_per_vbyte,
+            )
+        };
+
+        let (unsigned_tx, change_output) = match build_result {
+            Ok(tx) => tx,
+            Err(err) => {
+                log!(
+                    P0,
+                    "[resubmit_transactions]: failed to build a replacement for transaction {}: {:?}",
+                    tx::DisplayTxid(&old_txid),
+                    err
+                );
+                continue;
+            }
+        };
+
+        let new_txid = unsigned_tx.txid();
+        let outpoint_account = state::read_state(|s| filter_output_accounts(s, &unsigned_tx));
+
+        log!(
+            P1,
+            "[resubmit_transactions]: replacing transaction {} with {} (fee: {} millisatoshi/vbyte)",
+            tx::DisplayTxid(&old_txid),
+            tx::DisplayTxid(&new_txid),
+            tx_fee_per_vbyte
+        );
+
+        let signed_tx = match sign_transaction(
+            key_name.clone(),
+            &ecdsa_public_key,
+            &outpoint_account,
+            unsigned_tx,
+        )
+        .await
+        {
+            Ok(tx) => tx,
+            Err(err) => {
+                log!(
+                    P0,
+                    "[resubmit_transactions]: failed to sign a replacement for transaction {}: {}",
+                    tx::DisplayTxid(&old_txid),
+                    err
+                );
+                continue;
+            }
+        };
+
+        match management::send_transaction(&signed_tx, btc_network).await {
+            Ok(()) => {
+                log!(
+                    P1,
+                    "[resubmit_transactions]: successfully sent transaction {}",
+                    tx::DisplayTxid(&new_txid),
+                );
+
+                state::mutate_state(|s| {
+                    state::audit::replace_transaction(
+                        s,
+                        old_txid,
+                        state::SubmittedBtcTransaction {
+                            requests: submitted_tx.requests,
+                            txid: new_txid,
+                            used_utxos: submitted_tx.used_utxos,
+                            change_output: Some(change_output),
+                            submitted_at: ic_cdk::api::time(),
+                            fee_per_vbyte: Some(tx_fee_per_vbyte),
+                        },
+                    );
+                });
+            }
+            Err(err) => {
+                log!(
+                    P0,
+                    "[resubmit_transactions]: failed to send a replacement for transaction {}: {}",
+                    tx::DisplayTxid(&old_txid),
+                    err
+                );
+            }
+        }
+    }
+}
+
+/// Merges the minter's smallest UTXOs into a single output if the minter has
+/// too many UTXOs and the network fees are low.
+///
+/// Every UTXO the minter owns increases the cost of a future withdrawal that
+/// spends it.  Merging small UTXOs while the fees are low makes withdrawals
+/// cheaper when the fees go up.
+async fn consolidate_utxos() {
+    let (threshold, max_fee, utxo_count, has_pending_consolidation) = state::read_state(|s| {
+        (
+            s.utxo_consolidation_threshold,
+            s.utxo_consolidation_max_fee_per_vbyte,
+            s.available_utxos.len() as u64,
+            s.submitted_transactions
+                .iter()
+                .any(|tx| tx.requests.is_empty()),
+        )
+    });
+
+    if utxo_count <= threshold || has_pending_consolidation {
+        return;
+    }
+
+    updates::get_btc_address::init_ecdsa_public_key().await;
+
+    let ecdsa_public_key = match state::read_state(|s| s.ecdsa_public_key.clone()) {
+        Some(key) => key,
+        None => {
+            log!(
+                P0,
+                "unreachable: have UTXOs to consolidate but the ECDSA key is not initialized",
+            );
+            return;
+        }
+    };
+
+    let fee_millisatoshi_per_vbyte = match estimate_fee_per_vbyte().await {
+        Some(fee) => fee,
+        None => return,
+    };
+
+    if fee_millisatoshi_per_vbyte > max_fee {
+        log!(
+            P1,
+            "[consolidate_utxos]: skipping consolidation of {} UTXOs: the median fee {} millisatoshi/vbyte is above {}",
+            utxo_count,
+            fee_millisatoshi_per_vbyte,
+            max_fee
+        );
+        return;
+    }
+
+    let main_account = Account {
+        owner: ic_cdk::id().into(),
+        subaccount: None,
+    };
+    let main_address = address::account_to_bitcoin_address(&ecdsa_public_key, &main_account);
+
+    let maybe_sign_request = state::mutate_state(|s| {
+        let utxos =
+            select_utxos_to_consolidate(&mut s.available_utxos, s.utxo_consolidation_threshold);
+        if utxos.is_empty() {
+            return None;
+        }
+
+        match build_consolidation_transaction(&utxos, main_address, fee_millisatoshi_per_vbyte) {
+            Ok((unsigned_tx, change_output)) => Some(SignTxRequest {
+                key_name: s.ecdsa_key_name.clone(),
+                ecdsa_public_key,
+                change_output,
+                outpoint_account: filter_output_accounts(s, &unsigned_tx),
+                network: s.btc_network,
+                unsigned_tx,
+                requests: vec![],
+                utxos,
+            }),
+            Err(err) => {
+                log!(
+                    P0,
+                    "[consolidate_utxos]: failed to build a consolidation transaction: {:?}",
+                    err
+                );
+                for utxo in utxos {
+                    s.available_utxos.insert(utxo);
+                }
+                None
+            }
+        }
+    });
+
+    let req = match maybe_sign_request {
+        Some(req) => req,
+        None => return,
+    };
+
+    // This guard returns the UTXOs back to the available set if the signing
+    // or sending fails or panics.
+    let utxos_guard = guard(req.utxos, |utxos| {
+        state::mutate_state(|s| {
+            for utxo in utxos {
+                assert!(s.available_utxos.insert(utxo));
+            }
+        })
+    });
+
+    let txid = req.unsigned_tx.txid();
+
+    log!(
+        P1,
+        "[consolidate_utxos]: merging {} UTXOs in transaction {}",
+        utxos_guard.len(),
+        tx::DisplayTxid(&txid)
+    );
+
+    let signed_tx = match sign_transaction(
+        req.key_name,
+        &req.ecdsa_public_key,
+        &req.outpoint_account,
+        req.unsigned_tx,
+    )
+    .await
+    {
+        Ok(tx) => tx,
+        Err(err) => {
+            log!(
+                P0,
+                "[consolidate_utxos]: failed to sign a consolidation transaction: {}",
+                err
+            );
+            return;
+        }
+    };
+
+    match management::send_transaction(&signed_tx, req.network).await {
+        Ok(()) => {
+            log!(
+                P1,
+                "[consolidate_utxos]: successfully sent transaction {}",
+                tx::DisplayTxid(&txid),
+            );
+
+            let used_utxos = ScopeGuard::into_inner(utxos_guard);
+
+            state::mutate_state(|s| {
+                state::audit::sent_transaction(
+                    s,
+                    state::SubmittedBtcTransaction {
+                        requests: vec![],
+                        txid,
+                        used_utxos,
+                        change_output: Some(req.change_output),
+                        submitted_at: ic_cdk::api::time(),
+                        fee_per_vbyte: Some(fee_millisatoshi_per_vbyte),
+                    },
+                );
+            });
+        }
+        Err(err) => {
+            log!(
+                P0,
+                "[consolidate_utxos]: failed to send a consolidation transaction: {}",
+                err
+            );
+        }
+    }
+}
+
 /// Builds the minimal OutPoint -> Account map required to sign a transaction.
 fn filter_output_accounts(
     state: &state::CkBtcMinterState,
@@ -484,6 +828,124 @@ fn greedy(target: u64, available_utxos: &mut BTreeSet<Utxo>) -> Vec<Utxo> {
     solution
 }
 
+/// Selects a subset of UTXOs with the specified total target value and removes
+/// the selected UTXOs from the available set.
+///
+/// Since the receivers pay for every transaction input, the function looks
+/// for the solution with the fewest inputs, preferring the solution with the
+/// smallest total value among solutions of the same size.  The search is a
+/// depth-first branch-and-bound over the UTXOs sorted by value.  If the search
+/// exceeds its iteration budget without finding a solution, the function falls
+/// back to the [greedy] algorithm.
+///
+/// If there are no UTXOs matching the criteria, returns an empty vector.
+///
+/// PROPERTY: sum(u.value for u in available_set) ≥ target ⇒ !solution.is_empty()
+/// POSTCONDITION: !solution.is_empty() ⇒ sum(u.value for u in solution) ≥ target
+/// POSTCONDITION:  solution.is_empty() ⇒ available_utxos did not change.
+fn select_utxos(target: u64, available_utxos: &mut BTreeSet<Utxo>) -> Vec<Utxo> {
+    match branch_and_bound(target, available_utxos, MAX_BNB_ITERATIONS) {
+        Some(solution) => {
+            for utxo in solution.iter() {
+                assert!(available_utxos.remove(utxo));
+            }
+            solution
+        }
+        None => greedy(target, available_utxos),
+    }
+}
+
+/// Searches for the subset of UTXOs covering the target with the fewest
+/// elements and the smallest total value.  Returns the best solution found
+/// within the specified number of iterations, or None if the search found no
+/// solution.
+fn branch_and_bound(
+    target: u64,
+    available_utxos: &BTreeSet<Utxo>,
+    max_iterations: usize,
+) -> Option<Vec<Utxo>> {
+    if target == 0 {
+        return None;
+    }
+
+    let mut utxos: Vec<&Utxo> = available_utxos.iter().collect();
+    utxos.sort_by(|l, r| r.value.cmp(&l.value));
+
+    // suffix_sums[i] is the total value of utxos[i..].
+    let mut suffix_sums = vec![0u64; utxos.len() + 1];
+    for i in (0..utxos.len()).rev() {
+        suffix_sums[i] = suffix_sums[i + 1].saturating_add(utxos[i].value);
+    }
+
+    if suffix_sums[0] < target {
+        return None;
+    }
+
+    let mut best: Option<(usize, u64, Vec<usize>)> = None;
+    let mut selected: Vec<usize> = vec![];
+    let mut selected_value = 0u64;
+    let mut next = 0;
+
+    for _ in 0..max_iterations {
+        let backtrack = if selected_value >= target {
+            let improves = match &best {
+                Some((count, value, _)) => (selected.len(), selected_value) < (*count, *value),
+                None => true,
+            };
+            if improves {
+                best = Some((selected.len(), selected_value, selected.clone()));
+            }
+            true
+        } else if next >= utxos.len() || selected_value + suffix_sums[next] < target {
+            // The remaining UTXOs cannot cover the target.
+            true
+        } else {
+            // Adding one more input cannot produce a smaller solution.
+            matches!(&best, Some((count, _, _)) if selected.len() + 1 > *count)
+        };
+
+        if backtrack {
+            // Drop the last selected UTXO and explore the branch without it.
+            match selected.pop() {
+                Some(i) => {
+                    selected_value -= utxos[i].value;
+                    next = i + 1;
+                }
+                None => break,
+            }
+        } else {
+            selected.push(next);
+            selected_value += utxos[next].value;
+            next += 1;
+        }
+    }
+
+    best.map(|(_, _, indices)| indices.into_iter().map(|i| utxos[i].clone()).collect())
+}
+
+/// Selects the UTXOs to merge in a consolidation transaction: the smallest
+/// available UTXOs, at most [MAX_UTXOS_PER_CONSOLIDATION] of them.
+///
+/// Returns an empty vector if the minter has no more than `threshold`
+/// available UTXOs.  Removes the selected UTXOs from the available set.
+pub fn select_utxos_to_consolidate(
+    available_utxos: &mut BTreeSet<Utxo>,
+    threshold: u64,
+) -> Vec<Utxo> {
+    if (available_utxos.len() as u64) <= threshold || available_utxos.len() < 2 {
+        return vec![];
+    }
+
+    let mut utxos: Vec<Utxo> = available_utxos.iter().cloned().collect();
+    utxos.sort_by_key(|u| u.value);
+    utxos.truncate(MAX_UTXOS_PER_CONSOLIDATION);
+
+    for utxo in utxos.iter() {
+        assert!(available_utxos.remove(utxo));
+    }
+    utxos
+}
+
 /// Gathers ECDSA signatures for all the inputs in the specified unsigned
 /// transaction.
 ///
@@ -616,15 +1078,9 @@ pub fn build_unsigned_transaction(
 ) -> Result<(tx::UnsignedTransaction, state::ChangeOutput, Vec<Utxo>), BuildTxError> {
     assert!(!outputs.is_empty());
 
-    /// Having a sequence number lower than (0xffffffff - 1) signals the use of replacement by fee.
-    /// It allows us to increase the fee of a transaction already sent to the mempool.
-    /// The rbf option is used in `resubmit_retrieve_btc`.
-    /// https://github.com/bitcoin/bips/blob/master/bip-0125.mediawiki
-    const SEQUENCE_RBF_ENABLED: u32 = 0xfffffffd;
-
     let amount = outputs.iter().map(|(_, amount)| amount).sum::<u64>();
 
-    let input_utxos = greedy(amount, minter_utxos);
+    let input_utxos = select_utxos(amount, minter_utxos);
 
     if input_utxos.is_empty() {
         return Err(BuildTxError::NotEnoughFunds);
@@ -638,11 +1094,41 @@ pub fn build_unsigned_transaction(
         }
     });
 
-    let inputs_value = utxos_guard.iter().map(|u| u.value).sum::<u64>();
+    let (unsigned_tx, change_output) =
+        build_unsigned_transaction_from_inputs(&utxos_guard, outputs, main_address, fee_per_vbyte)?;
+
+    Ok((
+        unsigned_tx,
+        change_output,
+        ScopeGuard::into_inner(utxos_guard),
+    ))
+}
+
+/// Builds a transaction that moves BTC to the specified destination accounts
+/// spending exactly the specified UTXOs.  The receivers pay the fee.
+///
+/// We use this function directly to build replacement transactions that must
+/// spend the same inputs as the transactions they replace.
+///
+/// # Panics
+///
+/// This function panics if the `outputs` vector is empty or if the inputs do
+/// not cover the total amount of the outputs.
+pub fn build_unsigned_transaction_from_inputs(
+    input_utxos: &[Utxo],
+    outputs: Vec<(BitcoinAddress, Satoshi)>,
+    main_address: BitcoinAddress,
+    fee_per_vbyte: u64,
+) -> Result<(tx::UnsignedTransaction, state::ChangeOutput), BuildTxError> {
+    assert!(!outputs.is_empty());
+
+    let amount = outputs.iter().map(|(_, amount)| amount).sum::<u64>();
+
+    let inputs_value = input_utxos.iter().map(|u| u.value).sum::<u64>();
 
-    debug_assert!(inputs_value >= amount);
+    assert!(inputs_value >= amount);
 
-    let minter_fee = MINTER_FEE_PER_INPUT * utxos_guard.len() as u64
+    let minter_fee = MINTER_FEE_PER_INPUT * input_utxos.len() as u64
         + MINTER_FEE_PER_OUTPUT * (outputs.len() + 1) as u64
         + MINTER_FEE_CONSTANT;
 
@@ -670,7 +1156,7 @@ pub fn build_unsigned_transaction(
     );
 
     let mut unsigned_tx = tx::UnsignedTransaction {
-        inputs: utxos_guard
+        inputs: input_utxos
             .iter()
             .map(|utxo| tx::UnsignedInput {
                 previous_output: utxo.outpoint.clone(),
@@ -708,11 +1194,55 @@ pub fn build_unsigned_transaction(
         fee + unsigned_tx.outputs.iter().map(|u| u.value).sum::<u64>()
     );
 
-    Ok((
-        unsigned_tx,
-        change_output,
-        ScopeGuard::into_inner(utxos_guard),
-    ))
+    Ok((unsigned_tx, change_output))
+}
+
+/// Builds a transaction that merges the specified UTXOs into a single output
+/// owned by the minter's main address.  The minter pays the fee.
+///
+/// # Panics
+///
+/// This function panics if the `input_utxos` slice is empty.
+pub fn build_consolidation_transaction(
+    input_utxos: &[Utxo],
+    main_address: BitcoinAddress,
+    fee_per_vbyte: u64,
+) -> Result<(tx::UnsignedTransaction, state::ChangeOutput), BuildTxError> {
+    assert!(!input_utxos.is_empty());
+
+    let inputs_value = input_utxos.iter().map(|u| u.value).sum::<u64>();
+
+    let mut unsigned_tx = tx::UnsignedTransaction {
+        inputs: input_utxos
+            .iter()
+            .map(|utxo| tx::UnsignedInput {
+                previous_output: utxo.outpoint.clone(),
+                value: utxo.value,
+                sequence: SEQUENCE_RBF_ENABLED,
+            })
+            .collect(),
+        outputs: vec![tx::TxOut {
+            address: main_address,
+            value: inputs_value,
+        }],
+        lock_time: 0,
+    };
+
+    let tx_vsize = fake_sign(&unsigned_tx).vsize();
+    let fee = (tx_vsize as u64 * fee_per_vbyte) / 1000;
+
+    if fee >= inputs_value {
+        return Err(BuildTxError::AmountTooLow);
+    }
+
+    unsigned_tx.outputs[0].value = inputs_value - fee;
+
+    let change_output = state::ChangeOutput {
+        vout: 0,
+        value: inputs_value - fee,
+    };
+
+    Ok((unsigned_tx, change_output))
 }
 
 /// Distributes an amount across the specified number of shares as fairly as
@@ -751,7 +1281,11 @@ pub fn timer() {
             ic_cdk::spawn(async {
                 let _guard = match crate::guard::TimerLogicGuard::new() {
                     Some(guard) => guard,
-                    None => return,
+                    None => {
+                        // UTXO consolidation is running, try again soon.
+                        schedule_after(INTERVAL_PROCESSING, TaskType::ProcessLogic);
+                        return;
+                    }
                 };
 
                 let _enqueue_followup_guard = guard((), |_| {
@@ -760,6 +1294,27 @@ pub fn timer() {
 
                 submit_pending_requests().await;
                 finalize_requests().await;
+                resubmit_transactions().await;
+            });
+        }
+        TaskType::ConsolidateUtxos => {
+            ic_cdk::spawn(async {
+                const CONSOLIDATION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+                let _guard = match crate::guard::TimerLogicGuard::new() {
+                    Some(guard) => guard,
+                    None => {
+                        // Another task is running, try again soon.
+                        schedule_after(INTERVAL_PROCESSING, TaskType::ConsolidateUtxos);
+                        return;
+                    }
+                };
+
+                let _enqueue_followup_guard = guard((), |_| {
+                    schedule_after(CONSOLIDATION_INTERVAL, TaskType::ConsolidateUtxos)
+                });
+
+                consolidate_utxos().await;
             });
         }
         TaskType::RefreshFeePercentiles => {
@@ -807,7 +1362,7 @@ pub fn estimate_fee(
             // should get the exact number of inputs that the minter
             // will use.
             let mut utxos = available_utxos.clone();
-            let selected_utxos = greedy(amount, &mut utxos);
+            let selected_utxos = select_utxos(amount, &mut utxos);
 
             if !selected_utxos.is_empty() {
                 selected_utxos.len() as u64
@@ -8,6 +8,14 @@ use serde::Serialize;
 
 pub const DEFAULT_MIN_CONFIRMATIONS: u32 = 6;
 
+/// The default number of available UTXOs above which the minter starts
+/// consolidating small UTXOs.
+pub const DEFAULT_UTXO_CONSOLIDATION_THRESHOLD: u64 = 1_000;
+
+/// The default maximum median fee (in millisatoshi per vbyte) at which the
+/// minter consolidates UTXOs.
+pub const DEFAULT_UTXO_CONSOLIDATION_MAX_FEE_PER_VBYTE: u64 = 5_000;
+
 #[derive(CandidType, serde::Deserialize)]
 pub enum MinterArg {
     Init(InitArgs),
@@ -41,6 +49,16 @@ pub struct InitArgs {
     /// Flag that indicates if the minter is in read-only mode.
     #[serde(default)]
     pub mode: Mode,
+
+    /// The number of available UTXOs above which the minter merges its
+    /// smallest UTXOs into a single output.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub utxo_consolidation_threshold: Option<u64>,
+
+    /// The maximum median fee (in millisatoshi per vbyte) at which the minter
+    /// consolidates UTXOs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub utxo_consolidation_max_fee_per_vbyte: Option<u64>,
 }
 
 pub fn init(args: InitArgs) {
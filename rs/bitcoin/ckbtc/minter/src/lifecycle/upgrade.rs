@@ -25,6 +25,16 @@ pub struct UpgradeArgs {
     /// The mode in which the minter is running.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mode: Option<Mode>,
+
+    /// The number of available UTXOs above which the minter merges its
+    /// smallest UTXOs into a single output.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub utxo_consolidation_threshold: Option<u64>,
+
+    /// The maximum median fee (in millisatoshi per vbyte) at which the minter
+    /// consolidates UTXOs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub utxo_consolidation_max_fee_per_vbyte: Option<u64>,
 }
 
 pub fn post_upgrade(upgrade_args: Option<UpgradeArgs>) {
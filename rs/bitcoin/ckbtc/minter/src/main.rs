@@ -30,6 +30,7 @@ fn init(args: MinterArg) {
             lifecycle::init::init(args);
             schedule_now(TaskType::ProcessLogic);
             schedule_now(TaskType::RefreshFeePercentiles);
+            schedule_now(TaskType::ConsolidateUtxos);
 
             #[cfg(feature = "self_check")]
             ok_or_die(check_invariants())
@@ -102,6 +103,7 @@ fn post_upgrade(minter_arg: Option<MinterArg>) {
     }
     lifecycle::upgrade::post_upgrade(upgrade_arg);
     schedule_now(TaskType::ProcessLogic);
+    schedule_now(TaskType::ConsolidateUtxos);
 }
 
 #[candid_method(update)]
@@ -82,6 +82,11 @@ pub struct SubmittedBtcTransaction {
     /// The tx output from the submitted transaction that the minter owns.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub change_output: Option<ChangeOutput>,
+    /// The fee rate (in millisatoshi per vbyte) that the minter used to
+    /// build the transaction.  None for transactions submitted before the
+    /// minter started tracking fees.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee_per_vbyte: Option<u64>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -212,6 +217,21 @@ pub struct CkBtcMinterState {
     /// BTC transactions waiting for finalization.
     pub submitted_transactions: Vec<SubmittedBtcTransaction>,
 
+    /// Transactions that likely didn't make it into the mempool or got stuck
+    /// there because of a low fee.  The minter replaced each of them with a
+    /// transaction spending the same inputs with a higher fee.
+    #[serde(default)]
+    pub stuck_transactions: Vec<SubmittedBtcTransaction>,
+
+    /// Maps the txid of a stuck transaction to the txid of its replacement.
+    #[serde(default)]
+    pub replacement_txid: BTreeMap<[u8; 32], [u8; 32]>,
+
+    /// Maps the txid of a replacement transaction to the txid of the
+    /// transaction it replaced.
+    #[serde(default)]
+    pub rev_replacement_txid: BTreeMap<[u8; 32], [u8; 32]>,
+
     /// Finalized retrieve_btc requests for which we received enough confirmations.
     pub finalized_requests: VecDeque<FinalizedBtcRetrieval>,
 
@@ -254,6 +274,14 @@ pub struct CkBtcMinterState {
     /// The mode in which the minter runs.
     pub mode: Mode,
 
+    /// The number of available UTXOs above which the minter consolidates
+    /// its smallest UTXOs.
+    pub utxo_consolidation_threshold: u64,
+
+    /// The maximum median fee (in millisatoshi per vbyte) at which the minter
+    /// consolidates UTXOs.
+    pub utxo_consolidation_max_fee_per_vbyte: u64,
+
     pub last_fee_per_vbyte: Vec<u64>,
 }
 
@@ -268,6 +296,8 @@ impl CkBtcMinterState {
             max_time_in_queue_nanos,
             min_confirmations,
             mode,
+            utxo_consolidation_threshold,
+            utxo_consolidation_max_fee_per_vbyte,
         }: InitArgs,
     ) {
         self.btc_network = btc_network;
@@ -279,6 +309,12 @@ impl CkBtcMinterState {
         if let Some(min_confirmations) = min_confirmations {
             self.min_confirmations = min_confirmations;
         }
+        if let Some(threshold) = utxo_consolidation_threshold {
+            self.utxo_consolidation_threshold = threshold;
+        }
+        if let Some(max_fee) = utxo_consolidation_max_fee_per_vbyte {
+            self.utxo_consolidation_max_fee_per_vbyte = max_fee;
+        }
     }
 
     pub fn upgrade(
@@ -288,6 +324,8 @@ impl CkBtcMinterState {
             max_time_in_queue_nanos,
             min_confirmations,
             mode,
+            utxo_consolidation_threshold,
+            utxo_consolidation_max_fee_per_vbyte,
         }: UpgradeArgs,
     ) {
         if let Some(retrieve_btc_min_amount) = retrieve_btc_min_amount {
@@ -311,6 +349,12 @@ impl CkBtcMinterState {
         if let Some(mode) = mode {
             self.mode = mode;
         }
+        if let Some(threshold) = utxo_consolidation_threshold {
+            self.utxo_consolidation_threshold = threshold;
+        }
+        if let Some(max_fee) = utxo_consolidation_max_fee_per_vbyte {
+            self.utxo_consolidation_max_fee_per_vbyte = max_fee;
+        }
     }
 
     pub fn check_invariants(&self) -> Result<(), String> {
@@ -341,6 +385,21 @@ impl CkBtcMinterState {
             }
         }
 
+        for (old_txid, new_txid) in self.replacement_txid.iter() {
+            ensure_eq!(
+                self.rev_replacement_txid.get(new_txid),
+                Some(old_txid),
+                "replacement maps are inconsistent"
+            );
+            ensure!(
+                self.stuck_transactions
+                    .iter()
+                    .any(|tx| &tx.txid == old_txid),
+                "replaced transaction {} is not in the stuck list",
+                crate::tx::DisplayTxid(old_txid)
+            );
+        }
+
         for (l, r) in self
             .pending_retrieve_btc_requests
             .iter()
@@ -492,31 +551,113 @@ impl CkBtcMinterState {
         }
     }
 
+    /// Finalizes the retrieve_btc requests of the specified transaction.
+    ///
+    /// The transaction can be either the latest transaction in a replacement
+    /// chain or any of the stuck transactions it replaced.  Since all
+    /// transactions in the chain spend the same inputs, only one of them can
+    /// make it into the blockchain, so we drop the whole chain.
     fn finalize_transaction(&mut self, txid: &[u8; 32]) {
-        if let Some(pos) = self
+        let finalized_tx = if let Some(pos) = self
             .submitted_transactions
             .iter()
-            .position(|req| &req.txid == txid)
+            .position(|tx| &tx.txid == txid)
         {
-            let submitted_tx = self.submitted_transactions.swap_remove(pos);
-            for utxo in submitted_tx.used_utxos.iter() {
-                self.forget_utxo(utxo);
-            }
-            self.finalized_requests_count += submitted_tx.requests.len() as u64;
-            for request in submitted_tx.requests {
-                self.push_finalized_request(FinalizedBtcRetrieval {
-                    request,
-                    state: FinalizedStatus::Confirmed { txid: *txid },
-                });
-            }
+            self.submitted_transactions.swap_remove(pos)
+        } else if let Some(pos) = self
+            .stuck_transactions
+            .iter()
+            .position(|tx| &tx.txid == txid)
+        {
+            self.stuck_transactions.swap_remove(pos)
         } else {
             ic_cdk::trap(&format!(
                 "Attempted to finalized a non-existent transaction {}",
                 crate::tx::DisplayTxid(txid)
             ));
+        };
+
+        self.forget_replacement_chain(txid);
+
+        for utxo in finalized_tx.used_utxos.iter() {
+            self.forget_utxo(utxo);
+        }
+        self.finalized_requests_count += finalized_tx.requests.len() as u64;
+        for request in finalized_tx.requests {
+            self.push_finalized_request(FinalizedBtcRetrieval {
+                request,
+                state: FinalizedStatus::Confirmed { txid: *txid },
+            });
         }
     }
 
+    /// Removes all transactions in the replacement chain of the specified
+    /// transaction (except the transaction itself) from the state.
+    fn forget_replacement_chain(&mut self, txid: &[u8; 32]) {
+        let mut chain = vec![];
+
+        let mut prev = *txid;
+        while let Some(older) = self.rev_replacement_txid.remove(&prev) {
+            self.replacement_txid.remove(&older);
+            chain.push(older);
+            prev = older;
+        }
+
+        let mut next = *txid;
+        while let Some(newer) = self.replacement_txid.remove(&next) {
+            self.rev_replacement_txid.remove(&newer);
+            chain.push(newer);
+            next = newer;
+        }
+
+        self.stuck_transactions
+            .retain(|tx| !chain.contains(&tx.txid));
+        self.submitted_transactions
+            .retain(|tx| !chain.contains(&tx.txid));
+    }
+
+    /// Replaces a submitted transaction that got stuck with a new transaction
+    /// spending the same inputs.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if there is no submitted transaction with the
+    /// specified txid.
+    fn replace_transaction(&mut self, old_txid: &[u8; 32], new_tx: SubmittedBtcTransaction) {
+        assert_ne!(old_txid, &new_tx.txid);
+
+        let pos = self
+            .submitted_transactions
+            .iter()
+            .position(|tx| &tx.txid == old_txid)
+            .unwrap_or_else(|| {
+                panic!(
+                    "BUG: attempted to replace a non-existent transaction {}",
+                    crate::tx::DisplayTxid(old_txid)
+                )
+            });
+
+        let old_tx = self.submitted_transactions.swap_remove(pos);
+        self.stuck_transactions.push(old_tx);
+        self.replacement_txid.insert(*old_txid, new_tx.txid);
+        self.rev_replacement_txid.insert(new_tx.txid, *old_txid);
+        self.submitted_transactions.push(new_tx);
+    }
+
+    /// Returns submitted transactions that did not get confirmed for longer
+    /// than the specified duration.
+    pub fn transactions_to_resubmit(
+        &self,
+        now: u64,
+        delay_nanos: u64,
+    ) -> Vec<SubmittedBtcTransaction> {
+        self.submitted_transactions
+            .iter()
+            .filter(|tx| tx.submitted_at.saturating_add(delay_nanos) < now)
+            .cloned()
+            .collect()
+    }
+
     /// Removes a pending retrive_btc request with the specified block index.
     fn remove_pending_request(&mut self, block_index: u64) -> Option<RetrieveBtcRequest> {
         match self
@@ -649,6 +790,16 @@ impl CkBtcMinterState {
         let other_txs = as_sorted_vec(other.submitted_transactions.iter().cloned(), |tx| tx.txid);
         ensure_eq!(my_txs, other_txs, "submitted_transactions do not match");
 
+        let my_stuck = as_sorted_vec(self.stuck_transactions.iter().cloned(), |tx| tx.txid);
+        let other_stuck = as_sorted_vec(other.stuck_transactions.iter().cloned(), |tx| tx.txid);
+        ensure_eq!(my_stuck, other_stuck, "stuck_transactions do not match");
+
+        ensure_eq!(
+            self.replacement_txid,
+            other.replacement_txid,
+            "replacement_txid maps do not match"
+        );
+
         let my_requests = as_sorted_vec(self.pending_retrieve_btc_requests.iter().cloned(), |r| {
             r.block_index
         });
@@ -688,6 +839,9 @@ impl From<InitArgs> for CkBtcMinterState {
             pending_retrieve_btc_requests: Default::default(),
             requests_in_flight: Default::default(),
             submitted_transactions: Default::default(),
+            stuck_transactions: Default::default(),
+            replacement_txid: Default::default(),
+            rev_replacement_txid: Default::default(),
             finalized_requests: VecDeque::with_capacity(MAX_FINALIZED_REQUESTS),
             finalized_requests_count: 0,
             tokens_minted: 0,
@@ -699,6 +853,12 @@ impl From<InitArgs> for CkBtcMinterState {
             finalized_utxos: Default::default(),
             is_timer_running: false,
             mode: args.mode,
+            utxo_consolidation_threshold: args
+                .utxo_consolidation_threshold
+                .unwrap_or(crate::lifecycle::init::DEFAULT_UTXO_CONSOLIDATION_THRESHOLD),
+            utxo_consolidation_max_fee_per_vbyte: args
+                .utxo_consolidation_max_fee_per_vbyte
+                .unwrap_or(crate::lifecycle::init::DEFAULT_UTXO_CONSOLIDATION_MAX_FEE_PER_VBYTE),
             last_fee_per_vbyte: vec![1; 100],
         }
     }
@@ -75,6 +75,33 @@ pub enum Event {
         /// The IC time at which the minter submitted the transaction.
         #[serde(rename = "submitted_at")]
         submitted_at: u64,
+        /// The fee per vbyte (in millisatoshi) that we used for the transaction.
+        #[serde(rename = "fee")]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        fee_per_vbyte: Option<u64>,
+    },
+
+    /// Indicates that the minter sent out a new transaction to replace an
+    /// older transaction that did not get confirmed in time.  The new
+    /// transaction spends the same UTXOs and serves the same requests as the
+    /// old one, but pays a higher fee.
+    #[serde(rename = "replaced_transaction")]
+    ReplacedBtcTransaction {
+        /// The Txid of the old Bitcoin transaction.
+        #[serde(rename = "old_txid")]
+        old_txid: [u8; 32],
+        /// The Txid of the new Bitcoin transaction.
+        #[serde(rename = "new_txid")]
+        new_txid: [u8; 32],
+        /// The output with the minter's change.
+        #[serde(rename = "change_output")]
+        change_output: ChangeOutput,
+        /// The IC time at which the minter submitted the new transaction.
+        #[serde(rename = "submitted_at")]
+        submitted_at: u64,
+        /// The fee per vbyte (in millisatoshi) of the new transaction.
+        #[serde(rename = "fee")]
+        fee_per_vbyte: u64,
     },
 
     /// Indicates that the minter received enough confirmations for a bitcoin
@@ -138,6 +165,7 @@ pub fn replay(mut events: impl Iterator<Item = Event>) -> Result<CkBtcMinterStat
                 utxos,
                 change_output,
                 submitted_at,
+                fee_per_vbyte,
             } => {
                 let mut retrieve_btc_requests = Vec::with_capacity(request_block_indices.len());
                 for block_index in request_block_indices {
@@ -158,8 +186,42 @@ pub fn replay(mut events: impl Iterator<Item = Event>) -> Result<CkBtcMinterStat
                     used_utxos: utxos,
                     change_output,
                     submitted_at,
+                    fee_per_vbyte,
                 });
             }
+            Event::ReplacedBtcTransaction {
+                old_txid,
+                new_txid,
+                change_output,
+                submitted_at,
+                fee_per_vbyte,
+            } => {
+                let (requests, used_utxos) = match state
+                    .submitted_transactions
+                    .iter()
+                    .find(|tx| tx.txid == old_txid)
+                {
+                    Some(tx) => (tx.requests.clone(), tx.used_utxos.clone()),
+                    None => {
+                        return Err(ReplayLogError::InconsistentLog(format!(
+                            "Cannot replace a non-existent transaction {}",
+                            crate::tx::DisplayTxid(&old_txid)
+                        )))
+                    }
+                };
+
+                state.replace_transaction(
+                    &old_txid,
+                    SubmittedBtcTransaction {
+                        requests,
+                        txid: new_txid,
+                        used_utxos,
+                        change_output: Some(change_output),
+                        submitted_at,
+                        fee_per_vbyte: Some(fee_per_vbyte),
+                    },
+                );
+            }
             Event::ConfirmedBtcTransaction { txid } => {
                 state.finalize_transaction(&txid);
             }
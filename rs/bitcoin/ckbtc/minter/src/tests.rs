@@ -1,7 +1,9 @@
 use crate::MINTER_FEE_CONSTANT;
 use crate::{
-    address::BitcoinAddress, build_unsigned_transaction, estimate_fee, fake_sign, greedy,
-    signature::EncodedSignature, tx, BuildTxError,
+    address::BitcoinAddress, build_consolidation_transaction, build_unsigned_transaction,
+    build_unsigned_transaction_from_inputs, estimate_fee, fake_sign, greedy, select_utxos,
+    select_utxos_to_consolidate, signature::EncodedSignature, tx, BuildTxError,
+    MAX_UTXOS_PER_CONSOLIDATION, MIN_RELAY_FEE_PER_VBYTE,
 };
 use crate::{
     lifecycle::init::InitArgs,
@@ -161,6 +163,68 @@ fn greedy_smoke_test() {
     assert_eq!(res[1].value, 6_u64);
 }
 
+#[test]
+fn select_utxos_prefers_fewer_inputs_and_smaller_excess() {
+    let mut utxos: BTreeSet<Utxo> = [4, 6, 7, 9]
+        .into_iter()
+        .map(dummy_utxo_from_value)
+        .collect();
+
+    // greedy() takes 9 and then the smallest UTXO covering the rest.
+    assert_eq!(
+        greedy(10, &mut utxos.clone())
+            .iter()
+            .map(|u| u.value)
+            .collect::<Vec<_>>(),
+        vec![9, 4]
+    );
+
+    let mut solution: Vec<_> = select_utxos(10, &mut utxos)
+        .iter()
+        .map(|u| u.value)
+        .collect();
+    solution.sort();
+    assert_eq!(solution, vec![4, 6]);
+    assert_eq!(utxos.len(), 2);
+}
+
+#[test]
+fn test_build_consolidation_transaction() {
+    let utxos: Vec<Utxo> = (1..=10u64)
+        .map(|i| dummy_utxo_from_value(i * 1_000))
+        .collect();
+    let minter_addr = BitcoinAddress::P2wpkhV0([0; 20]);
+    let fee_per_vbyte = 2_000;
+
+    let (tx, change_output) =
+        build_consolidation_transaction(&utxos, minter_addr.clone(), fee_per_vbyte)
+            .expect("failed to build a consolidation transaction");
+
+    let inputs_value = utxos.iter().map(|u| u.value).sum::<u64>();
+    let fee = fake_sign(&tx).vsize() as u64 * fee_per_vbyte / 1000;
+
+    assert_eq!(tx.inputs.len(), utxos.len());
+    assert_eq!(
+        tx.outputs,
+        vec![tx::TxOut {
+            address: minter_addr.clone(),
+            value: inputs_value - fee,
+        }]
+    );
+    assert_eq!(
+        change_output,
+        ChangeOutput {
+            vout: 0,
+            value: inputs_value - fee
+        }
+    );
+
+    assert_eq!(
+        build_consolidation_transaction(&utxos[..2], minter_addr, 1_000_000),
+        Err(BuildTxError::AmountTooLow)
+    );
+}
+
 #[test]
 fn test_min_change_amount() {
     let mut available_utxos = BTreeSet::new();
@@ -262,6 +326,131 @@ fn test_no_zero_outputs() {
     assert_eq!(available_utxos.len(), 1);
 }
 
+#[test]
+fn test_build_replacement_transaction() {
+    let mut available_utxos: BTreeSet<Utxo> = (1..4u64)
+        .map(|i| dummy_utxo_from_value(i * 100_000))
+        .collect();
+
+    let minter_addr = BitcoinAddress::P2wpkhV0([0; 20]);
+    let out_addr = BitcoinAddress::P2wpkhV0([1; 20]);
+    let outputs = vec![(out_addr.clone(), 250_000)];
+
+    let (tx, change_output, used_utxos) = build_unsigned_transaction(
+        &mut available_utxos,
+        outputs.clone(),
+        minter_addr.clone(),
+        5_000,
+    )
+    .expect("failed to build a transaction");
+
+    let (new_tx, new_change_output) = build_unsigned_transaction_from_inputs(
+        &used_utxos,
+        outputs,
+        minter_addr,
+        5_000 + MIN_RELAY_FEE_PER_VBYTE,
+    )
+    .expect("failed to build a replacement transaction");
+
+    assert_ne!(tx.txid(), new_tx.txid());
+    assert_eq!(change_output, new_change_output);
+    assert_eq!(tx.inputs, new_tx.inputs);
+    assert!(new_tx
+        .inputs
+        .iter()
+        .all(|input| input.sequence < 0xfffffffe));
+
+    let old_fee = tx.inputs.iter().map(|i| i.value).sum::<u64>()
+        - tx.outputs.iter().map(|o| o.value).sum::<u64>();
+    let new_fee = new_tx.inputs.iter().map(|i| i.value).sum::<u64>()
+        - new_tx.outputs.iter().map(|o| o.value).sum::<u64>();
+    let vsize = fake_sign(&new_tx).vsize() as u64;
+    assert!(new_fee >= old_fee + vsize * MIN_RELAY_FEE_PER_VBYTE / 1000);
+    assert_eq!(new_tx.outputs[0].address, out_addr);
+    assert!(new_tx.outputs[0].value < tx.outputs[0].value);
+}
+
+#[test]
+fn test_replay_replaced_transaction() {
+    use crate::state::eventlog::{replay, Event};
+
+    let account = Account {
+        owner: PrincipalId::new_user_test_id(1),
+        subaccount: None,
+    };
+    let utxo = dummy_utxo_from_value(1_000_000);
+    let request = RetrieveBtcRequest {
+        amount: 500_000,
+        address: BitcoinAddress::P2wpkhV0([1; 20]),
+        block_index: 1,
+        received_at: 0,
+    };
+    let change_output = ChangeOutput {
+        vout: 1,
+        value: 500_000,
+    };
+    let (old_txid, new_txid) = ([1; 32], [2; 32]);
+
+    let events = vec![
+        Event::Init(InitArgs {
+            btc_network: Network::Regtest,
+            ecdsa_key_name: "".to_string(),
+            retrieve_btc_min_amount: 0,
+            ledger_id: CanisterId::from_u64(42),
+            max_time_in_queue_nanos: 0,
+            min_confirmations: None,
+            mode: Mode::GeneralAvailability,
+            utxo_consolidation_threshold: None,
+            utxo_consolidation_max_fee_per_vbyte: None,
+        }),
+        Event::ReceivedUtxos {
+            mint_txid: None,
+            to_account: account,
+            utxos: vec![utxo.clone()],
+        },
+        Event::AcceptedRetrieveBtcRequest(request.clone()),
+        Event::SentBtcTransaction {
+            request_block_indices: vec![request.block_index],
+            txid: old_txid,
+            utxos: vec![utxo],
+            change_output: Some(change_output.clone()),
+            submitted_at: 0,
+            fee_per_vbyte: Some(5_000),
+        },
+        Event::ReplacedBtcTransaction {
+            old_txid,
+            new_txid,
+            change_output,
+            submitted_at: 1,
+            fee_per_vbyte: 6_000,
+        },
+    ];
+
+    let state = replay(events.clone().into_iter()).expect("failed to replay events");
+    state.check_invariants().expect("invariant check failed");
+    assert_eq!(
+        state.retrieve_btc_status(request.block_index),
+        RetrieveBtcStatus::Submitted { txid: new_txid }
+    );
+    assert_eq!(state.stuck_transactions.len(), 1);
+    assert_eq!(state.transactions_to_resubmit(2, 0).len(), 1);
+    assert!(state.transactions_to_resubmit(2, 1).is_empty());
+
+    // The original transaction can still make it into the blockchain.
+    let mut events = events;
+    events.push(Event::ConfirmedBtcTransaction { txid: old_txid });
+    let state = replay(events.into_iter()).expect("failed to replay events");
+    state.check_invariants().expect("invariant check failed");
+    assert_eq!(
+        state.retrieve_btc_status(request.block_index),
+        RetrieveBtcStatus::Confirmed { txid: old_txid }
+    );
+    assert!(state.submitted_transactions.is_empty());
+    assert!(state.stuck_transactions.is_empty());
+    assert!(state.replacement_txid.is_empty());
+    assert!(state.rev_replacement_txid.is_empty());
+}
+
 #[test]
 fn blocklist_is_sorted() {
     use crate::blocklist::BTC_ADDRESS_BLOCKLIST;
@@ -454,6 +643,96 @@ proptest! {
         prop_assert_eq!(utxos, original_utxos);
     }
 
+    #[test]
+    fn select_utxos_solution_properties(
+        values in pvec(1u64..1_000_000_000, 1..20),
+        target in 1u64..1_000_000_000,
+    ) {
+        let mut utxos: BTreeSet<Utxo> = values
+            .into_iter()
+            .map(dummy_utxo_from_value)
+            .collect();
+
+        let total = utxos.iter().map(|u| u.value).sum::<u64>();
+
+        if total < target {
+            utxos.insert(dummy_utxo_from_value(target - total));
+        }
+
+        let original_utxos = utxos.clone();
+        let greedy_solution = greedy(target, &mut utxos.clone());
+
+        let solution = select_utxos(target, &mut utxos);
+
+        prop_assert!(
+            !solution.is_empty(),
+            "select_utxos() must always find a solution given enough available UTXOs"
+        );
+
+        prop_assert!(
+            solution.iter().map(|u| u.value).sum::<u64>() >= target,
+            "select_utxos() must reach the specified target amount"
+        );
+
+        prop_assert!(
+            solution.len() <= greedy_solution.len(),
+            "select_utxos() must not use more inputs than greedy()"
+        );
+
+        prop_assert!(
+            solution.iter().all(|u| original_utxos.contains(u)),
+            "select_utxos() must select utxos from the available set"
+        );
+
+        prop_assert!(
+            solution.iter().all(|u| !utxos.contains(u)),
+            "select_utxos() must remove found UTXOs from the available set"
+        );
+
+        prop_assert_eq!(utxos.len() + solution.len(), original_utxos.len());
+    }
+
+    #[test]
+    fn select_utxos_does_not_modify_input_when_fails(
+        values in pvec(1u64..1_000_000_000, 1..20),
+    ) {
+        let mut utxos: BTreeSet<Utxo> = values
+            .into_iter()
+            .map(dummy_utxo_from_value)
+            .collect();
+
+        let total = utxos.iter().map(|u| u.value).sum::<u64>();
+
+        let original_utxos = utxos.clone();
+        let solution = select_utxos(total + 1, &mut utxos);
+
+        prop_assert!(solution.is_empty());
+        prop_assert_eq!(utxos, original_utxos);
+    }
+
+    #[test]
+    fn consolidation_selects_smallest_utxos(
+        values in btree_set(1u64..1_000_000_000, 2..300),
+        threshold in 0..300u64,
+    ) {
+        let mut utxos: BTreeSet<Utxo> = values
+            .into_iter()
+            .map(dummy_utxo_from_value)
+            .collect();
+        let original_len = utxos.len();
+
+        let selected = select_utxos_to_consolidate(&mut utxos, threshold);
+
+        if original_len as u64 <= threshold {
+            prop_assert!(selected.is_empty());
+            prop_assert_eq!(utxos.len(), original_len);
+        } else {
+            prop_assert_eq!(selected.len(), original_len.min(MAX_UTXOS_PER_CONSOLIDATION));
+            let max_selected = selected.iter().map(|u| u.value).max().unwrap();
+            prop_assert!(utxos.iter().all(|u| u.value > max_selected));
+        }
+    }
+
     #[test]
     fn unsigned_tx_encoding_model(
         inputs in pvec(arb_unsigned_input(5_000u64..1_000_000_000), 1..20),
@@ -705,6 +984,8 @@ proptest! {
             max_time_in_queue_nanos: 0,
             min_confirmations: None,
             mode: Mode::GeneralAvailability,
+            utxo_consolidation_threshold: None,
+            utxo_consolidation_max_fee_per_vbyte: None,
         });
         for (utxo, acc_idx) in utxos_acc_idx {
             state.add_utxos(accounts[acc_idx], vec![utxo]);
@@ -727,6 +1008,8 @@ proptest! {
             max_time_in_queue_nanos: 0,
             min_confirmations: None,
             mode: Mode::GeneralAvailability,
+            utxo_consolidation_threshold: None,
+            utxo_consolidation_max_fee_per_vbyte: None,
         });
 
         let mut available_amount = 0;
@@ -188,6 +188,7 @@ impl From<StreamHeaderV6> for StreamHeader {
             end: header.end.into(),
             signals_end: header.signals_end.into(),
             reject_signals: Default::default(),
+            capacity_bytes: None,
         }
     }
 }
@@ -40,6 +40,7 @@ use std::collections::{BTreeMap, VecDeque};
 ///     end: 25.into(),
 ///     signals_end: 256.into(),
 ///     reject_signals: VecDeque::new(),
+///     capacity_bytes: None,
 /// }
 /// ```
 ///
@@ -62,6 +63,7 @@ fn canonical_encoding_stream_header() {
             end: 25.into(),
             signals_end: 256.into(),
             reject_signals: VecDeque::new(),
+            capacity_bytes: None,
         };
 
         assert_eq!(
@@ -79,6 +81,7 @@ fn canonical_encoding_stream_header() {
 ///     end: 25.into(),
 ///     signals_end: 256.into(),
 ///     reject_signals: vec![249.into(), 250.into(), 252.into()].into(),
+///     capacity_bytes: None,
 /// }
 /// ```
 ///
@@ -107,6 +110,7 @@ fn canonical_encoding_stream_header_v8_plus() {
             end: 25.into(),
             signals_end: 256.into(),
             reject_signals: vec![249.into(), 250.into(), 252.into()].into(),
+            capacity_bytes: None,
         };
 
         assert_eq!(
@@ -116,6 +120,71 @@ fn canonical_encoding_stream_header_v8_plus() {
     }
 }
 
+/// Canonical CBOR encoding (with certification versions 12 and up) of:
+///
+/// ```no_run
+/// StreamHeader {
+///     begin: 23.into(),
+///     end: 25.into(),
+///     signals_end: 256.into(),
+///     reject_signals: VecDeque::new(),
+///     capacity_bytes: Some(1024),
+/// }
+/// ```
+///
+/// Expected:
+///
+/// ```text
+/// A4         # map(4)
+///    00      # field_index(StreamHeader::begin)
+///    17      # unsigned(23)
+///    01      # field_index(StreamHeader::end)
+///    18 19   # unsigned(25)
+///    02      # field_index(StreamHeader::signals_end)
+///    19 0100 # unsigned(256)
+///    04      # field_index(StreamHeader::capacity_bytes)
+///    19 0400 # unsigned(1024)
+/// ```
+#[test]
+fn canonical_encoding_stream_header_v12_plus() {
+    for certification_version in
+        all_supported_versions().filter(|v| v >= &CertificationVersion::V12)
+    {
+        let header = StreamHeader {
+            begin: 23.into(),
+            end: 25.into(),
+            signals_end: 256.into(),
+            reject_signals: VecDeque::new(),
+            capacity_bytes: Some(1024),
+        };
+
+        assert_eq!(
+            "A4 00 17 01 18 19 02 19 01 00 04 19 04 00",
+            as_hex(&encode_stream_header(&header, certification_version))
+        );
+    }
+}
+
+/// The capacity is not encoded with certification versions below 12.
+#[test]
+fn canonical_encoding_stream_header_capacity_pre_v12() {
+    for certification_version in all_supported_versions().filter(|v| v < &CertificationVersion::V12)
+    {
+        let header = StreamHeader {
+            begin: 23.into(),
+            end: 25.into(),
+            signals_end: 256.into(),
+            reject_signals: VecDeque::new(),
+            capacity_bytes: Some(1024),
+        };
+
+        assert_eq!(
+            "A3 00 17 01 18 19 02 19 01 00",
+            as_hex(&encode_stream_header(&header, certification_version))
+        );
+    }
+}
+
 /// Canonical CBOR encoding of:
 ///
 /// ```no_run
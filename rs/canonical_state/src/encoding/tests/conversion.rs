@@ -27,6 +27,7 @@ fn convert_stream_header_with_invalid_signals() {
         end: 25,
         signals_end: 256,
         reject_signal_deltas: vec![300, 50, 6],
+        capacity_bytes: None,
     };
     match ic_types::xnet::StreamHeader::try_from(header_with_invalid_signals) {
         Ok(ctx) => panic!("Expected Err(_), got Ok({:?})", ctx),
@@ -21,6 +21,11 @@ pub fn stream_header(certification_version: CertificationVersion) -> StreamHeade
         } else {
             vec![10.into(), 200.into(), 250.into()].into()
         },
+        capacity_bytes: if certification_version < CertificationVersion::V12 {
+            None
+        } else {
+            Some(1 << 20)
+        },
     }
 }
 
@@ -38,6 +38,10 @@ pub struct StreamHeader {
     /// Note that `signals_end` is NOT part of the reject signals.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub reject_signal_deltas: Vec<u64>,
+    /// Byte size of messages the producing subnet is willing to accept beyond
+    /// `signals_end`, if advertised.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capacity_bytes: Option<u64>,
 }
 
 /// Canonical representation of `ic_types::messages::RequestOrResponse`.
@@ -153,11 +157,20 @@ impl From<(&ic_types::xnet::StreamHeader, CertificationVersion)> for StreamHeade
             next_index = *stream_index;
         }
 
+        // Replicas with certification version < 12 do not encode the capacity,
+        // as it could not be decoded by remote subnets that do not support it yet.
+        let capacity_bytes = if certification_version >= CertificationVersion::V12 {
+            header.capacity_bytes
+        } else {
+            None
+        };
+
         Self {
             begin: header.begin.get(),
             end: header.end.get(),
             signals_end: header.signals_end.get(),
             reject_signal_deltas,
+            capacity_bytes,
         }
     }
 }
@@ -185,6 +198,7 @@ impl TryFrom<StreamHeader> for ic_types::xnet::StreamHeader {
             end: header.end.into(),
             signals_end: header.signals_end.into(),
             reject_signals,
+            capacity_bytes: header.capacity_bytes,
         })
     }
 }
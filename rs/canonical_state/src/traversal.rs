@@ -387,6 +387,7 @@ mod tests {
             end: StreamIndex::from(4),
             signals_end: StreamIndex::new(11),
             reject_signals: VecDeque::new(),
+            capacity_bytes: None,
         };
 
         let stream = Stream::new(
@@ -618,6 +619,7 @@ mod tests {
                 subnet_type: SubnetType::Application,
                 subnet_features: SubnetFeatures::default(),
                 ecdsa_keys_held: BTreeSet::new(),
+                vetkd_keys_held: BTreeSet::new(),
             },
             subnet_test_id(1) => SubnetTopology {
                 public_key: vec![5, 6, 7, 8],
@@ -625,6 +627,7 @@ mod tests {
                 subnet_type: SubnetType::Application,
                 subnet_features: SubnetFeatures::default(),
                 ecdsa_keys_held: BTreeSet::new(),
+                vetkd_keys_held: BTreeSet::new(),
             }
         };
         fn id_range(from: u64, to: u64) -> CanisterIdRange {
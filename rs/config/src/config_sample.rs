@@ -133,6 +133,12 @@ pub const SAMPLE_CONFIG: &str = r#"
         // - EXAMPLE: csp_vault_type: { unix_socket: "/some/path/to/socket" },
         //   CspVault is run as a separate process, which can be reached via a Unix socket.
         csp_vault_type: { unix_socket: "/some/path/to/socket" },
+        // If set, the node signing and TLS secret keys are held in a PKCS#11 token.
+        // EXAMPLE: hsm: {
+        //   pkcs11_module_path: "/usr/lib/softhsm/libsofthsm2.so",
+        //   slot_id: 0,
+        //   pin_file: "/var/lib/ic/crypto/hsm_pin",
+        // },
     },
     // ========================================
     // Configuration of the message scheduling.
@@ -59,6 +59,22 @@ pub struct CryptoConfig {
     )]
     pub crypto_root: PathBuf,
     pub csp_vault_type: CspVaultType,
+    /// If set, the node signing and TLS secret keys are held in the given
+    /// PKCS#11 token rather than in the secret key store on the file system.
+    /// All other secret keys remain in the secret key store.
+    #[cfg_attr(test, proptest(value = "None"))]
+    pub hsm: Option<HsmConfig>,
+}
+
+/// Configuration of a PKCS#11 token, e.g., a hardware security module.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct HsmConfig {
+    /// Path to the PKCS#11 module (a shared library) provided by the token vendor.
+    pub pkcs11_module_path: PathBuf,
+    /// Slot in which the token resides.
+    pub slot_id: u64,
+    /// Path to a file containing the user PIN of the token.
+    pub pin_file: PathBuf,
 }
 
 impl Default for CryptoConfig {
@@ -66,6 +82,7 @@ impl Default for CryptoConfig {
         Self {
             crypto_root: PathBuf::from(CRYPTO_ROOT_DEFAULT_PATH),
             csp_vault_type: CspVaultType::InReplica,
+            hsm: None,
         }
     }
 }
@@ -76,6 +93,7 @@ impl CryptoConfig {
         Self {
             crypto_root,
             csp_vault_type: CspVaultType::InReplica,
+            hsm: None,
         }
     }
 
@@ -85,6 +103,7 @@ impl CryptoConfig {
         Self {
             crypto_root,
             csp_vault_type: CspVaultType::UnixSocket(socket_path),
+            hsm: None,
         }
     }
 
@@ -121,6 +121,10 @@ const MAX_PAUSED_EXECUTIONS: usize = 4;
 /// cover the cost of the subnet.
 pub const ECDSA_SIGNATURE_FEE: Cycles = Cycles::new(10 * B as u128);
 
+/// Deriving an encrypted vetKD key takes a round of threshold BLS shares, which
+/// is cheaper than a threshold ECDSA signature.
+pub const VETKD_FEE: Cycles = Cycles::new(5 * B as u128);
+
 /// Default subnet size which is used to scale cycles cost according to a subnet replication factor.
 ///
 /// All initial costs were calculated with the assumption that a subnet had 13 replicas.
@@ -374,6 +378,9 @@ pub struct CyclesAccountManagerConfig {
     /// Amount to charge for an ECDSA signature.
     pub ecdsa_signature_fee: Cycles,
 
+    /// Amount to charge for a vetKD encrypted key.
+    pub vetkd_fee: Cycles,
+
     /// Baseline cost to charge for HTTP request.
     pub http_request_baseline_fee: Cycles,
 
@@ -405,6 +412,7 @@ impl CyclesAccountManagerConfig {
             gib_storage_per_second_fee: Cycles::new(127_000),
             duration_between_allocation_charges: Duration::from_secs(10),
             ecdsa_signature_fee: ECDSA_SIGNATURE_FEE,
+            vetkd_fee: VETKD_FEE,
             http_request_baseline_fee: Cycles::new(400_000_000),
             http_request_per_byte_fee: Cycles::new(100_000),
         }
@@ -433,6 +441,7 @@ impl CyclesAccountManagerConfig {
             /// - zero cost if called from NNS subnet
             /// - non-zero cost if called from any other subnet which is not NNS subnet
             ecdsa_signature_fee: ECDSA_SIGNATURE_FEE,
+            vetkd_fee: VETKD_FEE,
             http_request_baseline_fee: Cycles::new(0),
             http_request_per_byte_fee: Cycles::new(0),
         }
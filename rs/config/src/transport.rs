@@ -14,6 +14,11 @@ pub struct TransportConfig {
     /// Transport creates 'max_streams' logical streams/channels between two peers.
     /// Channel ids should be within [0..max_streams).
     pub max_streams: usize,
+
+    /// If true, peers are connected over QUIC instead of TCP/TLS. Every
+    /// channel is then carried on its own QUIC stream, so that a busy channel
+    /// does not delay the messages on the others.
+    pub use_quic: bool,
 }
 
 impl Default for TransportConfig {
@@ -23,6 +28,7 @@ impl Default for TransportConfig {
             node_ip: String::default(),
             listening_port: u16::default(),
             max_streams: 1,
+            use_quic: false,
         }
     }
 }
@@ -1,6 +1,7 @@
 //! This module encapsulates all components required for establishing of a
 //! distributed consensus.
 
+mod adaptive_delay;
 pub mod batch_delivery;
 pub(crate) mod block_maker;
 mod catchup_package_maker;
@@ -26,6 +27,7 @@ pub mod validator;
 #[cfg(all(test, feature = "proptest"))]
 mod proptests;
 
+pub use adaptive_delay::AdaptiveDelay;
 pub use block_maker::SubnetRecords;
 pub use crypto::ConsensusCrypto;
 pub use membership::Membership;
@@ -166,6 +168,8 @@ impl ConsensusImpl {
             logger.clone(),
         ));
 
+        let adaptive_delay = Arc::new(AdaptiveDelay::new(metrics_registry.clone()));
+
         let current_time = time_source.get_relative_time();
         let mut last_invoked: BTreeMap<ConsensusSubcomponent, Time> = BTreeMap::new();
         last_invoked.insert(ConsensusSubcomponent::Notary, current_time);
@@ -188,6 +192,7 @@ impl ConsensusImpl {
                 state_manager.clone(),
                 metrics_registry.clone(),
                 logger.clone(),
+                adaptive_delay.clone(),
             ),
             finalizer: Finalizer::new(
                 replica_config.clone(),
@@ -233,6 +238,7 @@ impl ConsensusImpl {
                 stable_registry_version_age,
                 metrics_registry.clone(),
                 logger.clone(),
+                adaptive_delay.clone(),
             ),
             validator: Validator::new(
                 replica_config.clone(),
@@ -246,6 +252,7 @@ impl ConsensusImpl {
                 logger.clone(),
                 ValidatorMetrics::new(metrics_registry.clone()),
                 Arc::clone(&time_source),
+                adaptive_delay,
             ),
             aggregator: ShareAggregator::new(
                 membership,
@@ -0,0 +1,288 @@
+//! Adaptive adjustment of the notarization delay settings.
+//!
+//! The subnet record specifies a fixed `unit_delay` and `initial_notary_delay`,
+//! which have to be chosen conservatively for the slowest expected network
+//! conditions. If the subnet record enables adaptive notarization delays, both
+//! delays are scaled down based on statistics gathered from a fixed window of
+//! finalized blocks:
+//!
+//! * the fraction of blocks made by a rank-0 block maker,
+//! * the fraction of blocks with a non-empty payload, and
+//! * the average interval between consecutive blocks, which approximates the
+//!   round-trip time needed to get a block notarized.
+//!
+//! The observation window only depends on the height of the round, and the
+//! statistics only depend on the finalized chain, so all replicas that have the
+//! window in their validated pool compute the same delays. The switch is read
+//! from the subnet record at the registry version of the round, so it changes
+//! at the same height on all replicas. The adjusted delays never exceed the
+//! registry values. Whenever the window is not available (e.g. right after a
+//! catch-up package), the registry values are used as is.
+use crate::consensus::{metrics::AdaptiveDelayMetrics, pool_reader::PoolReader};
+use ic_metrics::MetricsRegistry;
+use ic_registry_client_helpers::subnet::NotarizationDelaySettings;
+use ic_types::{
+    consensus::{Block, Rank},
+    Height,
+};
+use std::sync::RwLock;
+use std::time::Duration;
+
+/// The number of finalized blocks the statistics are computed over.
+pub const ADAPTIVE_DELAY_WINDOW: u64 = 50;
+
+/// The lower bound of the delay factor, in permille of the registry values.
+const MIN_DELAY_FACTOR_PERMILLE: u64 = 250;
+
+/// The rank-0 success rate, in permille, above which delays start to shrink.
+const RANK_ZERO_THRESHOLD_PERMILLE: u64 = 900;
+
+/// Statistics of the finalized blocks in one observation window.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct WindowStats {
+    /// Permille of blocks that were made by a rank-0 block maker.
+    rank_zero_permille: u64,
+    /// Permille of blocks with a non-empty payload.
+    fullness_permille: u64,
+    /// Average time between two consecutive blocks.
+    block_interval: Duration,
+}
+
+/// Adjusts the notarization delay settings used by the block maker, the
+/// notary and the validator when adaptive delays are enabled in the subnet
+/// record.
+pub struct AdaptiveDelay {
+    // Statistics of the most recently used window, keyed by its end height.
+    cache: RwLock<Option<(Height, WindowStats)>>,
+    metrics: AdaptiveDelayMetrics,
+}
+
+impl AdaptiveDelay {
+    pub fn new(metrics_registry: MetricsRegistry) -> Self {
+        Self {
+            cache: RwLock::new(None),
+            metrics: AdaptiveDelayMetrics::new(metrics_registry),
+        }
+    }
+
+    /// Return the notarization delay settings to use for the round at
+    /// `height`. If adaptive delays are disabled, or the observation window for
+    /// `height` is not available, the registry `settings` are returned
+    /// unchanged.
+    pub fn adjust(
+        &self,
+        pool: &PoolReader<'_>,
+        height: Height,
+        settings: NotarizationDelaySettings,
+    ) -> NotarizationDelaySettings {
+        if !settings.adaptive {
+            return settings;
+        }
+        let stats = match self.window_stats(pool, height) {
+            Some(stats) => stats,
+            None => {
+                self.metrics.window_unavailable.inc();
+                return settings;
+            }
+        };
+        let factor = delay_factor_permille(&stats);
+        let adjusted = adjust_settings(&settings, &stats, factor);
+        self.metrics.report_adjusted_delays(
+            factor as f64 / 1000.0,
+            adjusted.unit_delay,
+            adjusted.initial_notary_delay,
+        );
+        adjusted
+    }
+
+    fn window_stats(&self, pool: &PoolReader<'_>, height: Height) -> Option<WindowStats> {
+        let end = window_end(height)?;
+        if let Some((cached_end, stats)) = &*self.cache.read().unwrap() {
+            if *cached_end == end {
+                return Some(stats.clone());
+            }
+        }
+        let stats = compute_window_stats(pool, end)?;
+        self.metrics
+            .rank_zero_ratio
+            .set(stats.rank_zero_permille as f64 / 1000.0);
+        self.metrics
+            .payload_fullness
+            .set(stats.fullness_permille as f64 / 1000.0);
+        self.metrics
+            .block_interval
+            .set(stats.block_interval.as_secs_f64());
+        *self.cache.write().unwrap() = Some((end, stats.clone()));
+        Some(stats)
+    }
+}
+
+/// Return the end height of the observation window for the round at `height`,
+/// which is the highest multiple of [`ADAPTIVE_DELAY_WINDOW`] that is at least
+/// [`ADAPTIVE_DELAY_WINDOW`] below `height`. The gap leaves enough room for
+/// the window to be finalized. Return `None` if the window would start below
+/// genesis.
+fn window_end(height: Height) -> Option<Height> {
+    let end = height.get().checked_sub(ADAPTIVE_DELAY_WINDOW)? / ADAPTIVE_DELAY_WINDOW
+        * ADAPTIVE_DELAY_WINDOW;
+    (end >= ADAPTIVE_DELAY_WINDOW).then(|| Height::from(end))
+}
+
+/// Compute the statistics of the finalized blocks in the window ending at
+/// `end`, or return `None` if not all of them are available in the pool.
+fn compute_window_stats(pool: &PoolReader<'_>, end: Height) -> Option<WindowStats> {
+    let start = Height::from(end.get() - ADAPTIVE_DELAY_WINDOW);
+    let end_block = pool.get_finalized_block(end)?;
+    // The block at `start` is only used as the reference point of the first
+    // block interval.
+    let blocks: Vec<Block> = pool.get_range(end_block, start, end).collect();
+    // The chain below the catch-up package is not available, in which case we
+    // would only get a part of the window.
+    if blocks.len() as u64 != ADAPTIVE_DELAY_WINDOW + 1 {
+        return None;
+    }
+    let observed = &blocks[..blocks.len() - 1];
+    let count = observed.len() as u64;
+    let rank_zero = observed.iter().filter(|b| b.rank == Rank(0)).count() as u64;
+    let non_empty = observed
+        .iter()
+        .filter(|b| !b.payload.as_ref().is_empty())
+        .count() as u64;
+    let newest = blocks[0].context.time.as_nanos_since_unix_epoch();
+    let oldest = blocks[blocks.len() - 1]
+        .context
+        .time
+        .as_nanos_since_unix_epoch();
+    Some(WindowStats {
+        rank_zero_permille: rank_zero * 1000 / count,
+        fullness_permille: non_empty * 1000 / count,
+        block_interval: Duration::from_nanos(newest.saturating_sub(oldest) / count),
+    })
+}
+
+/// Return the factor, in permille, applied to the registry delays. The factor
+/// shrinks linearly from 1000 at [`RANK_ZERO_THRESHOLD_PERMILLE`] down to its
+/// floor at a rank-0 success rate of 100%. The floor rises with the payload
+/// fullness, because large payloads take longer to propagate.
+fn delay_factor_permille(stats: &WindowStats) -> u64 {
+    let floor = MIN_DELAY_FACTOR_PERMILLE
+        + (1000 - MIN_DELAY_FACTOR_PERMILLE) * stats.fullness_permille / 2000;
+    let excess = stats
+        .rank_zero_permille
+        .saturating_sub(RANK_ZERO_THRESHOLD_PERMILLE);
+    1000 - (1000 - floor) * excess / (1000 - RANK_ZERO_THRESHOLD_PERMILLE)
+}
+
+/// Scale the registry `settings` by `factor` permille. The unit delay is not
+/// reduced below the observed block interval: a block maker of higher rank
+/// must not preempt a rank-0 block that is still on its way to be notarized.
+fn adjust_settings(
+    settings: &NotarizationDelaySettings,
+    stats: &WindowStats,
+    factor: u64,
+) -> NotarizationDelaySettings {
+    let scale = |delay: Duration| delay * factor as u32 / 1000;
+    let unit_delay = scale(settings.unit_delay).max(stats.block_interval.min(settings.unit_delay));
+    NotarizationDelaySettings {
+        unit_delay,
+        initial_notary_delay: scale(settings.initial_notary_delay),
+        adaptive: settings.adaptive,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::mocks::{dependencies, Dependencies};
+
+    fn registry_settings(adaptive: bool) -> NotarizationDelaySettings {
+        NotarizationDelaySettings {
+            unit_delay: Duration::from_millis(1000),
+            initial_notary_delay: Duration::from_millis(600),
+            adaptive,
+        }
+    }
+
+    #[test]
+    fn test_window_end() {
+        assert_eq!(window_end(Height::from(0)), None);
+        assert_eq!(window_end(Height::from(99)), None);
+        assert_eq!(window_end(Height::from(100)), Some(Height::from(50)));
+        assert_eq!(window_end(Height::from(149)), Some(Height::from(50)));
+        assert_eq!(window_end(Height::from(150)), Some(Height::from(100)));
+    }
+
+    #[test]
+    fn test_delay_factor() {
+        let stats = |rank_zero_permille, fullness_permille| WindowStats {
+            rank_zero_permille,
+            fullness_permille,
+            block_interval: Duration::ZERO,
+        };
+        // Frequent rank-0 failures keep the registry delays.
+        assert_eq!(delay_factor_permille(&stats(500, 0)), 1000);
+        assert_eq!(delay_factor_permille(&stats(900, 0)), 1000);
+        // Only rank-0 blocks shrink delays down to the floor.
+        assert_eq!(delay_factor_permille(&stats(1000, 0)), 250);
+        assert_eq!(delay_factor_permille(&stats(950, 0)), 625);
+        // Full payloads raise the floor.
+        assert_eq!(delay_factor_permille(&stats(1000, 1000)), 625);
+    }
+
+    #[test]
+    fn test_adjust_settings_bounds() {
+        let settings = registry_settings(true);
+        let stats = WindowStats {
+            rank_zero_permille: 1000,
+            fullness_permille: 0,
+            block_interval: Duration::from_millis(400),
+        };
+        let adjusted = adjust_settings(&settings, &stats, 250);
+        // The unit delay does not drop below the observed block interval.
+        assert_eq!(adjusted.unit_delay, Duration::from_millis(400));
+        assert_eq!(adjusted.initial_notary_delay, Duration::from_millis(150));
+
+        // Slow rounds never push the delays above the registry values.
+        let stats = WindowStats {
+            block_interval: Duration::from_secs(5),
+            ..stats
+        };
+        let adjusted = adjust_settings(&settings, &stats, 1000);
+        assert_eq!(adjusted, settings);
+    }
+
+    #[test]
+    fn test_adjust_from_pool() {
+        ic_test_utilities::artifact_pool_config::with_test_pool_config(|pool_config| {
+            let Dependencies { mut pool, .. } = dependencies(pool_config, 3);
+            pool.advance_round_normal_operation_no_cup_n(150);
+            let pool_reader = PoolReader::new(&pool);
+            let adaptive_delay = AdaptiveDelay::new(MetricsRegistry::new());
+
+            // If the subnet record disables adaptive delays, the registry
+            // settings are always used.
+            let settings = registry_settings(false);
+            assert_eq!(
+                adaptive_delay.adjust(&pool_reader, Height::from(151), settings.clone()),
+                settings
+            );
+
+            // Without a complete window, the registry settings are used.
+            let settings = registry_settings(true);
+            assert_eq!(
+                adaptive_delay.adjust(&pool_reader, Height::from(60), settings.clone()),
+                settings
+            );
+
+            // All blocks in the window are of rank 0, so delays shrink, but
+            // stay within the registry bounds.
+            let adjusted = adaptive_delay.adjust(&pool_reader, Height::from(151), settings.clone());
+            assert!(adjusted.unit_delay <= settings.unit_delay);
+            assert!(adjusted.initial_notary_delay < settings.initial_notary_delay);
+            assert_eq!(
+                adaptive_delay.adjust(&pool_reader, Height::from(151), settings),
+                adjusted
+            );
+        });
+    }
+}
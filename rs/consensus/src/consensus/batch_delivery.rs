@@ -11,19 +11,23 @@ use crate::consensus::{
 use crate::ecdsa::utils::EcdsaBlockReaderImpl;
 use ic_artifact_pool::consensus_pool::build_consensus_block_chain;
 use ic_crypto::get_tecdsa_master_public_key;
-use ic_ic00_types::{EcdsaKeyId, SetupInitialDKGResponse};
+use ic_ic00_types::{EcdsaKeyId, SetupInitialDKGResponse, VetKdKeyId};
 use ic_interfaces::messaging::{MessageRouting, MessageRoutingError};
 use ic_interfaces_registry::RegistryClient;
 use ic_logger::{debug, error, info, trace, warn, ReplicaLogger};
 use ic_protobuf::log::consensus_log_entry::v1::ConsensusLogEntry;
 use ic_protobuf::registry::crypto::v1::PublicKey as PublicKeyProto;
 use ic_protobuf::registry::subnet::v1::InitialNiDkgTranscriptRecord;
+use ic_registry_client_helpers::subnet::SubnetRegistry;
 use ic_types::{
     canister_http::*,
     consensus::ecdsa::{CompletedSignature, EcdsaBlockReader},
     crypto::{
         canister_threshold_sig::MasterEcdsaPublicKey,
-        threshold_sig::ni_dkg::{NiDkgId, NiDkgTag, NiDkgTranscript},
+        threshold_sig::{
+            ni_dkg::{NiDkgId, NiDkgTag, NiDkgTranscript},
+            ThresholdSigPublicKey,
+        },
     },
     messages::{CallbackId, Response},
     ReplicaVersion,
@@ -120,6 +124,19 @@ pub fn deliver_batches(
                     }
                 };
 
+                let vetkd_subnet_public_key =
+                    match get_vetkd_subnet_public_key(&block, pool, registry_client, subnet_id) {
+                        Ok(maybe_key) => maybe_key,
+                        Err(e) => {
+                            warn!(
+                                every_n_seconds => 5,
+                                log,
+                                "Do not deliver height {:?}: {}", h, e
+                            );
+                            return Ok(last_delivered_batch_height);
+                        }
+                    };
+
                 let block_stats = BlockStats::from(&block);
 
                 // This flag can only be true, if we've called deliver_batches with a height
@@ -135,6 +152,7 @@ pub fn deliver_batches(
                     },
                     randomness,
                     ecdsa_subnet_public_keys: ecdsa_subnet_public_key.into_iter().collect(),
+                    vetkd_subnet_public_keys: vetkd_subnet_public_key.into_iter().collect(),
                     registry_version: block.context.registry_version,
                     time: block.context.time,
                     consensus_responses,
@@ -233,10 +251,45 @@ pub fn get_ecdsa_subnet_public_key(
     Ok(ecdsa_subnet_public_key)
 }
 
+/// Returns the vetKD key of the subnet, i.e. the public key of the current
+/// transcript with the `VetKd` tag, if vetKD is enabled on the subnet.
+///
+/// As vetKD requests are processed in the ECDSA payload, the key is only
+/// returned for blocks with an ECDSA payload. Otherwise execution rejects
+/// vetKD requests, which consensus could not answer.
+pub fn get_vetkd_subnet_public_key(
+    block: &Block,
+    pool: &PoolReader<'_>,
+    registry_client: &dyn RegistryClient,
+    subnet_id: SubnetId,
+) -> Result<Option<(VetKdKeyId, ThresholdSigPublicKey)>, String> {
+    if block.payload.as_ref().as_ecdsa().is_none() {
+        return Ok(None);
+    }
+    let summary_block = pool
+        .dkg_summary_block_for_finalized_height(block.height)
+        .ok_or_else(|| {
+            format!(
+                "Failed to find dkg summary block for height {}",
+                block.height
+            )
+        })?;
+    let summary = &summary_block.payload.as_ref().as_summary().dkg;
+    let transcript = match summary.current_transcripts().get(&NiDkgTag::VetKd) {
+        Some(transcript) => transcript,
+        None => return Ok(None),
+    };
+    let vetkd_config = registry_client
+        .get_vetkd_config(subnet_id, summary.registry_version)
+        .map_err(|err| format!("Failed to get the vetKD config: {:?}", err))?;
+    Ok(vetkd_config.map(|config| (config.key_id, ThresholdSigPublicKey::from(transcript))))
+}
+
 /// This function creates responses to the system calls that are redirected to
 /// consensus. There are two types of calls being handled here:
 /// - Initial NiDKG transcript creation, where a response may come from summary payloads.
 /// - Threshold ECDSA signature creation, where a response may come from from data payloads.
+/// - vetKD encrypted key creation, where a response may come from data payloads.
 /// - CanisterHttpResponse handling, where a response to a canister http request may come from data payloads.
 pub fn generate_responses_to_subnet_calls(block: &Block, log: &ReplicaLogger) -> Vec<Response> {
     let mut consensus_responses = Vec::<Response>::new();
@@ -257,6 +310,7 @@ pub fn generate_responses_to_subnet_calls(block: &Block, log: &ReplicaLogger) ->
         if let Some(payload) = &block_payload.ecdsa {
             consensus_responses.append(&mut generate_responses_to_sign_with_ecdsa_calls(payload));
             consensus_responses.append(&mut generate_responses_to_initial_dealings_calls(payload));
+            consensus_responses.append(&mut generate_responses_to_vetkd_calls(payload));
         }
 
         consensus_responses.append(
@@ -372,6 +426,12 @@ pub fn generate_responses_to_setup_initial_dkg_calls(
                     }
                     transcript_results.high_threshold = value;
                 }
+                NiDkgTag::VetKd => {
+                    error!(
+                        log,
+                        "Unexpected vetKD transcript for new subnet for {}", callback_id
+                    );
+                }
             }
         };
         match transcripts.get_mut(callback_id) {
@@ -474,6 +534,19 @@ pub fn generate_responses_to_sign_with_ecdsa_calls(
     consensus_responses
 }
 
+/// Creates responses to `VetKdEncryptedKeyArgs` system calls with the
+/// combined encrypted keys.
+fn generate_responses_to_vetkd_calls(ecdsa_payload: &ecdsa::EcdsaPayload) -> Vec<Response> {
+    ecdsa_payload
+        .vetkd_agreements
+        .values()
+        .filter_map(|completed| match completed {
+            CompletedSignature::Unreported(response) => Some(response.clone()),
+            CompletedSignature::ReportedToExecution => None,
+        })
+        .collect()
+}
+
 /// Creates responses to `ComputeInitialEcdsaDealingsArgs` system calls with the initial
 /// dealings.
 fn generate_responses_to_initial_dealings_calls(
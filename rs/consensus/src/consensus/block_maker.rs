@@ -1,6 +1,7 @@
 #![deny(missing_docs)]
 use crate::{
     consensus::{
+        adaptive_delay::AdaptiveDelay,
         membership::Membership,
         metrics::{BlockMakerMetrics, EcdsaPayloadMetrics},
         payload_builder::PayloadBuilder,
@@ -88,6 +89,7 @@ pub struct BlockMaker {
     // block. The older is the version, the higher is the probability, that it's universally
     // available across the subnet.
     stable_registry_version_age: Duration,
+    adaptive_delay: Arc<AdaptiveDelay>,
 }
 
 impl BlockMaker {
@@ -106,6 +108,7 @@ impl BlockMaker {
         stable_registry_version_age: Duration,
         metrics_registry: MetricsRegistry,
         log: ReplicaLogger,
+        adaptive_delay: Arc<AdaptiveDelay>,
     ) -> Self {
         Self {
             time_source,
@@ -121,6 +124,7 @@ impl BlockMaker {
             metrics: BlockMakerMetrics::new(metrics_registry.clone()),
             ecdsa_payload_metrics: EcdsaPayloadMetrics::new(metrics_registry),
             stable_registry_version_age,
+            adaptive_delay,
         }
     }
 
@@ -145,6 +149,7 @@ impl BlockMaker {
                         height,
                         rank,
                         self.time_source.as_ref(),
+                        &self.adaptive_delay,
                     )
                 {
                     self.propose_block(pool, rank, parent).map(|proposal| {
@@ -724,6 +729,7 @@ mod tests {
                 Duration::from_millis(0),
                 MetricsRegistry::new(),
                 no_op_logger(),
+                Arc::new(AdaptiveDelay::new(MetricsRegistry::new())),
             );
 
             // Check first block is created immediately because rank 1 has to wait.
@@ -801,6 +807,7 @@ mod tests {
                 Duration::from_millis(0),
                 MetricsRegistry::new(),
                 no_op_logger(),
+                Arc::new(AdaptiveDelay::new(MetricsRegistry::new())),
             );
             let run_block_maker = || {
                 let reader = PoolReader::new(&pool);
@@ -929,6 +936,7 @@ mod tests {
                 Duration::from_millis(0),
                 MetricsRegistry::new(),
                 no_op_logger(),
+                Arc::new(AdaptiveDelay::new(MetricsRegistry::new())),
             );
 
             // Skip the first DKG interval
@@ -971,6 +979,7 @@ mod tests {
                 Duration::from_millis(0),
                 MetricsRegistry::new(),
                 no_op_logger(),
+                Arc::new(AdaptiveDelay::new(MetricsRegistry::new())),
             );
 
             // Check CUP block is made.
@@ -1046,6 +1055,7 @@ mod tests {
                 Duration::from_millis(0),
                 MetricsRegistry::new(),
                 no_op_logger(),
+                Arc::new(AdaptiveDelay::new(MetricsRegistry::new())),
             );
 
             let delay = Duration::from_millis(1000);
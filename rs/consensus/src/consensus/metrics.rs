@@ -12,7 +12,7 @@ use ic_types::{
     CountBytes,
 };
 use prometheus::{
-    GaugeVec, Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
+    Gauge, GaugeVec, Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
 };
 use std::sync::RwLock;
 
@@ -25,6 +25,7 @@ pub(crate) const CRITICAL_ERROR_PAYLOAD_TOO_LARGE: &str = "consensus_payload_too
 pub(crate) const CRITICAL_ERROR_VALIDATION_NOT_PASSED: &str = "consensus_validation_not_passed";
 pub(crate) const CRITICAL_ERROR_SUBNET_RECORD_ISSUE: &str = "consensus_subnet_record_issue";
 pub(crate) const CRITICAL_ERROR_ECDSA_KEY_TRANSCRIPT_MISSING: &str = "ecdsa_key_transcript_missing";
+pub(crate) const CRITICAL_ERROR_ECDSA_KEY_PUBLIC_KEY_CHANGED: &str = "ecdsa_key_public_key_changed";
 pub(crate) const CRITICAL_ERROR_ECDSA_RETAIN_ACTIVE_TRANSCRIPTS: &str =
     "ecdsa_retain_active_transcripts_error";
 
@@ -385,6 +386,63 @@ impl NotaryMetrics {
     }
 }
 
+pub struct AdaptiveDelayMetrics {
+    pub delay_factor: Gauge,
+    pub adjusted_delays: GaugeVec,
+    pub rank_zero_ratio: Gauge,
+    pub block_interval: Gauge,
+    pub payload_fullness: Gauge,
+    pub window_unavailable: IntCounter,
+}
+
+impl AdaptiveDelayMetrics {
+    pub fn new(metrics_registry: MetricsRegistry) -> Self {
+        Self {
+            delay_factor: metrics_registry.gauge(
+                "consensus_adaptive_delay_factor",
+                "The factor applied to the registry notarization delay settings in adaptive mode",
+            ),
+            adjusted_delays: metrics_registry.gauge_vec(
+                "consensus_adaptive_delay_seconds",
+                "The notarization delay settings after adaptive adjustment, in seconds",
+                &["delay"],
+            ),
+            rank_zero_ratio: metrics_registry.gauge(
+                "consensus_adaptive_delay_rank_zero_ratio",
+                "The fraction of finalized blocks of rank 0 in the last observation window",
+            ),
+            block_interval: metrics_registry.gauge(
+                "consensus_adaptive_delay_block_interval_seconds",
+                "The average interval between finalized blocks in the last observation window, in seconds",
+            ),
+            payload_fullness: metrics_registry.gauge(
+                "consensus_adaptive_delay_payload_fullness",
+                "The fraction of finalized blocks with a non-empty payload in the last observation window",
+            ),
+            window_unavailable: metrics_registry.int_counter(
+                "consensus_adaptive_delay_window_unavailable",
+                "The number of times the registry delays were used because the observation window was not available",
+            ),
+        }
+    }
+
+    /// Report the delay settings that result from the adaptive adjustment.
+    pub fn report_adjusted_delays(
+        &self,
+        factor: f64,
+        unit_delay: std::time::Duration,
+        initial_notary_delay: std::time::Duration,
+    ) {
+        self.delay_factor.set(factor);
+        self.adjusted_delays
+            .with_label_values(&["unit_delay"])
+            .set(unit_delay.as_secs_f64());
+        self.adjusted_delays
+            .with_label_values(&["initial_notary_delay"])
+            .set(initial_notary_delay.as_secs_f64());
+    }
+}
+
 pub struct PayloadBuilderMetrics {
     pub get_payload_duration: Histogram,
     pub validate_payload_duration: Histogram,
@@ -706,6 +764,8 @@ pub struct EcdsaPayloadMetrics {
     pub transcript_builder_duration: HistogramVec,
     /// Critical error for failure to create/reshare key transcript
     pub critical_error_ecdsa_key_transcript_missing: IntCounter,
+    /// Critical error for a reshared key transcript with a different public key
+    pub critical_error_ecdsa_key_public_key_changed: IntCounter,
 }
 
 impl EcdsaPayloadMetrics {
@@ -741,6 +801,8 @@ impl EcdsaPayloadMetrics {
             ),
             critical_error_ecdsa_key_transcript_missing: metrics_registry
                 .error_counter(CRITICAL_ERROR_ECDSA_KEY_TRANSCRIPT_MISSING),
+            critical_error_ecdsa_key_public_key_changed: metrics_registry
+                .error_counter(CRITICAL_ERROR_ECDSA_KEY_PUBLIC_KEY_CHANGED),
         }
     }
 
@@ -24,6 +24,7 @@
 //!   latest round, which would break security if it has already finality-signed
 //!   for that round.
 use crate::consensus::{
+    adaptive_delay::AdaptiveDelay,
     membership::{Membership, MembershipError},
     metrics::NotaryMetrics,
     pool_reader::PoolReader,
@@ -47,6 +48,7 @@ pub struct Notary {
     state_manager: Arc<dyn StateManager<State = ReplicatedState>>,
     log: ReplicaLogger,
     metrics: NotaryMetrics,
+    adaptive_delay: Arc<AdaptiveDelay>,
 }
 
 impl Notary {
@@ -58,6 +60,7 @@ impl Notary {
         state_manager: Arc<dyn StateManager<State = ReplicatedState>>,
         metrics_registry: MetricsRegistry,
         log: ReplicaLogger,
+        adaptive_delay: Arc<AdaptiveDelay>,
     ) -> Notary {
         Notary {
             time_source,
@@ -67,6 +70,7 @@ impl Notary {
             state_manager,
             log,
             metrics: NotaryMetrics::new(metrics_registry),
+            adaptive_delay,
         }
     }
 
@@ -112,6 +116,7 @@ impl Notary {
             &self.log,
             height,
             rank,
+            &self.adaptive_delay,
         )?;
         if let Some(start_time) = pool.get_round_start_time(height) {
             let now = self.time_source.get_relative_time();
@@ -280,6 +285,7 @@ mod tests {
             pool.insert_validated(block.clone());
 
             let metrics_registry = MetricsRegistry::new();
+            let adaptive_delay = Arc::new(AdaptiveDelay::new(metrics_registry.clone()));
 
             let notary = Notary::new(
                 Arc::clone(&time_source) as Arc<_>,
@@ -289,6 +295,7 @@ mod tests {
                 state_manager.clone(),
                 metrics_registry,
                 no_op_logger(),
+                adaptive_delay.clone(),
             );
             // Time has not expired for rank 0 initially
             let run_notary = |pool: &dyn ConsensusPool| {
@@ -308,6 +315,7 @@ mod tests {
                             &no_op_logger(),
                             Height::from(1),
                             Rank(0),
+                            &adaptive_delay,
                         )
                         .unwrap(),
                 )
@@ -357,6 +365,7 @@ mod tests {
                             &no_op_logger(),
                             Height::from(1),
                             Rank(9),
+                            &adaptive_delay,
                         )
                         .unwrap(),
                 )
@@ -374,6 +383,7 @@ mod tests {
                             &no_op_logger(),
                             Height::from(1),
                             twenty_block.rank(),
+                            &adaptive_delay,
                         )
                         .unwrap(),
                 )
@@ -1,5 +1,7 @@
 //! Consensus utility functions
-use crate::consensus::{membership::Membership, pool_reader::PoolReader, prelude::*};
+use crate::consensus::{
+    adaptive_delay::AdaptiveDelay, membership::Membership, pool_reader::PoolReader, prelude::*,
+};
 use ic_interfaces::consensus::{PayloadTransientError, PayloadValidationError};
 use ic_interfaces::validation::ValidationError;
 use ic_interfaces::{consensus_pool::ConsensusPoolCache, time_source::TimeSource};
@@ -99,7 +101,9 @@ pub fn is_root_subnet(
 }
 
 /// Return true if the time since round start is greater than the required block
-/// maker delay for the given rank.
+/// maker delay for the given rank, after adjusting the notarization delay
+/// settings with `adaptive_delay`.
+#[allow(clippy::too_many_arguments)]
 pub fn is_time_to_make_block(
     log: &ReplicaLogger,
     registry_client: &dyn RegistryClient,
@@ -108,14 +112,17 @@ pub fn is_time_to_make_block(
     height: Height,
     rank: Rank,
     time_source: &dyn TimeSource,
+    adaptive_delay: &AdaptiveDelay,
 ) -> bool {
     let registry_version = match pool.registry_version(height) {
         Some(rv) => rv,
         _ => return false,
     };
     let block_maker_delay =
-        match get_block_maker_delay(log, registry_client, subnet_id, registry_version, rank) {
-            Some(delay) => delay,
+        match get_notarization_delay_settings(log, registry_client, subnet_id, registry_version) {
+            Some(settings) => {
+                adaptive_delay.adjust(pool, height, settings).unit_delay * rank.0 as u32
+            }
             _ => return false,
         };
     match pool.get_round_start_time(height) {
@@ -128,7 +135,8 @@ pub fn is_time_to_make_block(
 /// notarize, adjusted by a multiplier depending the gap between finalized and
 /// notarized heights, and adjusted by how far the certified height lags behind
 /// the finalized height. Use membership and height to determine the
-/// notarization settings that should be used.
+/// notarization settings that should be used, and `adaptive_delay` to adjust
+/// them.
 pub fn get_adjusted_notary_delay(
     membership: &Membership,
     pool: &PoolReader<'_>,
@@ -136,14 +144,16 @@ pub fn get_adjusted_notary_delay(
     log: &ReplicaLogger,
     height: Height,
     rank: Rank,
+    adaptive_delay: &AdaptiveDelay,
 ) -> Option<Duration> {
+    let settings = get_notarization_delay_settings(
+        log,
+        &*membership.registry_client,
+        membership.subnet_id,
+        pool.registry_version(height)?,
+    )?;
     Some(get_adjusted_notary_delay_from_settings(
-        get_notarization_delay_settings(
-            log,
-            &*membership.registry_client,
-            membership.subnet_id,
-            pool.registry_version(height)?,
-        )?,
+        adaptive_delay.adjust(pool, height, settings),
         pool,
         state_manager,
         rank,
@@ -590,6 +600,7 @@ mod tests {
             let settings = NotarizationDelaySettings {
                 unit_delay: Duration::from_secs(1),
                 initial_notary_delay: Duration::from_secs(0),
+                adaptive: false,
             };
             let crate::consensus::mocks::Dependencies {
                 mut pool,
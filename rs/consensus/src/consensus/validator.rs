@@ -4,6 +4,7 @@
 
 use crate::{
     consensus::{
+        adaptive_delay::AdaptiveDelay,
         membership::{Membership, MembershipError},
         metrics::ValidatorMetrics,
         payload_builder::PayloadBuilder,
@@ -533,6 +534,7 @@ pub struct Validator {
     metrics: ValidatorMetrics,
     schedule: RoundRobin,
     time_source: Arc<dyn TimeSource>,
+    adaptive_delay: Arc<AdaptiveDelay>,
 }
 
 impl Validator {
@@ -550,6 +552,7 @@ impl Validator {
         log: ReplicaLogger,
         metrics: ValidatorMetrics,
         time_source: Arc<dyn TimeSource>,
+        adaptive_delay: Arc<AdaptiveDelay>,
     ) -> Validator {
         Validator {
             replica_config,
@@ -564,6 +567,7 @@ impl Validator {
             metrics,
             schedule: RoundRobin::default(),
             time_source,
+            adaptive_delay,
         }
     }
 
@@ -848,6 +852,7 @@ impl Validator {
                     proposal.height(),
                     proposal.rank(),
                     self.time_source.as_ref(),
+                    &self.adaptive_delay,
                 ) {
                     continue;
                 }
@@ -1765,6 +1770,7 @@ pub mod test {
                 no_op_logger(),
                 ValidatorMetrics::new(MetricsRegistry::new()),
                 Arc::clone(&time_source) as Arc<_>,
+                Arc::new(AdaptiveDelay::new(MetricsRegistry::new())),
             );
 
             let pool_reader = PoolReader::new(&pool);
@@ -1816,6 +1822,7 @@ pub mod test {
                 no_op_logger(),
                 ValidatorMetrics::new(MetricsRegistry::new()),
                 Arc::clone(&time_source) as Arc<_>,
+                Arc::new(AdaptiveDelay::new(MetricsRegistry::new())),
             );
 
             // With no existing Notarization for `block`, the Finalization in the
@@ -1905,6 +1912,7 @@ pub mod test {
                 no_op_logger(),
                 ValidatorMetrics::new(MetricsRegistry::new()),
                 Arc::clone(&time_source) as Arc<_>,
+                Arc::new(AdaptiveDelay::new(MetricsRegistry::new())),
             );
 
             // Put a random tape share in the unvalidated pool
@@ -1985,6 +1993,7 @@ pub mod test {
                 no_op_logger(),
                 ValidatorMetrics::new(MetricsRegistry::new()),
                 Arc::clone(&time_source) as Arc<_>,
+                Arc::new(AdaptiveDelay::new(MetricsRegistry::new())),
             );
 
             // Put a random tape share in the unvalidated pool
@@ -2144,6 +2153,7 @@ pub mod test {
                 no_op_logger(),
                 ValidatorMetrics::new(MetricsRegistry::new()),
                 Arc::clone(&time_source) as Arc<_>,
+                Arc::new(AdaptiveDelay::new(MetricsRegistry::new())),
             );
 
             // ensure that the validator initially does not validate anything, as it is not
@@ -2231,6 +2241,7 @@ pub mod test {
                 no_op_logger(),
                 ValidatorMetrics::new(MetricsRegistry::new()),
                 Arc::clone(&time_source) as Arc<_>,
+                Arc::new(AdaptiveDelay::new(MetricsRegistry::new())),
             );
 
             let mut test_block = pool.make_next_block();
@@ -2326,6 +2337,7 @@ pub mod test {
                 no_op_logger(),
                 ValidatorMetrics::new(MetricsRegistry::new()),
                 Arc::clone(&time_source) as Arc<_>,
+                Arc::new(AdaptiveDelay::new(MetricsRegistry::new())),
             );
 
             let mut parent_block = make_next_block(&pool, membership.as_ref(), &subnet_members);
@@ -2445,6 +2457,7 @@ pub mod test {
                 no_op_logger(),
                 ValidatorMetrics::new(MetricsRegistry::new()),
                 Arc::clone(&time_source) as Arc<_>,
+                Arc::new(AdaptiveDelay::new(MetricsRegistry::new())),
             );
 
             // Construct a block with certified height 1 (which can't yet be verified
@@ -2515,6 +2528,7 @@ pub mod test {
                 no_op_logger(),
                 ValidatorMetrics::new(MetricsRegistry::new()),
                 Arc::clone(&time_source) as Arc<_>,
+                Arc::new(AdaptiveDelay::new(MetricsRegistry::new())),
             );
             // Construct a block with a time greater than the current consensus time, which
             // should not be validated yet.
@@ -2598,6 +2612,7 @@ pub mod test {
                 no_op_logger(),
                 ValidatorMetrics::new(MetricsRegistry::new()),
                 Arc::clone(&time_source) as Arc<_>,
+                Arc::new(AdaptiveDelay::new(MetricsRegistry::new())),
             );
 
             // The notarization should be marked invalid
@@ -2682,6 +2697,7 @@ pub mod test {
                 no_op_logger(),
                 ValidatorMetrics::new(MetricsRegistry::new()),
                 Arc::clone(&time_source) as Arc<_>,
+                Arc::new(AdaptiveDelay::new(MetricsRegistry::new())),
             );
 
             // Only one notarization is emitted in the ChangeSet.
@@ -2758,6 +2774,7 @@ pub mod test {
                 no_op_logger(),
                 ValidatorMetrics::new(MetricsRegistry::new()),
                 Arc::clone(&time_source) as Arc<_>,
+                Arc::new(AdaptiveDelay::new(MetricsRegistry::new())),
             );
 
             // Only one finalization is emitted in the ChangeSet.
@@ -2823,6 +2840,7 @@ pub mod test {
                 no_op_logger(),
                 ValidatorMetrics::new(MetricsRegistry::new()),
                 Arc::clone(&time_source) as Arc<_>,
+                Arc::new(AdaptiveDelay::new(MetricsRegistry::new())),
             );
 
             let mut changeset = validator.on_state_change(&PoolReader::new(&pool));
@@ -2882,6 +2900,7 @@ pub mod test {
                 no_op_logger(),
                 ValidatorMetrics::new(MetricsRegistry::new()),
                 Arc::clone(&time_source) as Arc<_>,
+                Arc::new(AdaptiveDelay::new(MetricsRegistry::new())),
             );
 
             // First ensure that we require the parent block
@@ -106,6 +106,7 @@ pub enum TransientError {
     DkgVerifyDealingError(DkgVerifyDealingError),
     FailedToGetDkgIntervalSettingFromRegistry(RegistryClientError),
     FailedToGetSubnetMemberListFromRegistry(RegistryClientError),
+    FailedToGetVetKdConfigFromRegistry(RegistryClientError),
     MissingDkgStartBlock,
 }
 
@@ -661,20 +662,29 @@ fn create_summary_payload(
     // the next transcripts, as they are the newest ones.
     // If `next_transcripts` does not contain the required transcripts (due to
     // failed DKGs in the past interval) we reshare the current transcripts.
-    let reshared_transcripts = if next_transcripts.contains_key(&NiDkgTag::LowThreshold)
+    let mut reshared_transcripts = if next_transcripts.contains_key(&NiDkgTag::LowThreshold)
         && next_transcripts.contains_key(&NiDkgTag::HighThreshold)
     {
-        &next_transcripts
+        next_transcripts.clone()
     } else {
-        &current_transcripts
+        current_transcripts.clone()
     };
+    // The vetKD key must not change once it was created, so its transcript is always reshared
+    // from the newest vetKD transcript, independently of the outcome of the other DKGs.
+    if let Some(transcript) = next_transcripts
+        .get(&NiDkgTag::VetKd)
+        .or_else(|| current_transcripts.get(&NiDkgTag::VetKd))
+    {
+        reshared_transcripts.insert(NiDkgTag::VetKd, transcript.clone());
+    }
 
     configs.append(&mut get_configs_for_local_transcripts(
         subnet_id,
         get_node_list(subnet_id, registry_client, registry_version)?,
         height,
-        reshared_transcripts,
+        &reshared_transcripts,
         registry_version,
+        is_vetkd_enabled(registry_client, registry_version, subnet_id)?,
     )?);
 
     Ok(Summary::new(
@@ -797,6 +807,17 @@ fn validate_dealings_payload(
     Ok(())
 }
 
+fn is_vetkd_enabled(
+    registry_client: &dyn RegistryClient,
+    version: RegistryVersion,
+    subnet_id: SubnetId,
+) -> Result<bool, TransientError> {
+    Ok(registry_client
+        .get_vetkd_config(subnet_id, version)
+        .map_err(TransientError::FailedToGetVetKdConfigFromRegistry)?
+        .is_some())
+}
+
 fn get_node_list(
     subnet_id: SubnetId,
     registry_client: &dyn RegistryClient,
@@ -815,16 +836,19 @@ fn get_node_list(
         .collect())
 }
 
-/// Creates DKG configs for the local subnet for the next DKG intervals.
+/// Creates DKG configs for the local subnet for the next DKG intervals. The config
+/// for the vetKD transcript is only created if vetKD is enabled on the subnet.
 pub fn get_configs_for_local_transcripts(
     subnet_id: SubnetId,
     node_ids: BTreeSet<NodeId>,
     start_block_height: Height,
     reshared_transcripts: &BTreeMap<NiDkgTag, NiDkgTranscript>,
     registry_version: RegistryVersion,
+    vetkd_enabled: bool,
 ) -> Result<Vec<NiDkgConfig>, TransientError> {
     let mut new_configs = Vec::new();
-    for tag in TAGS.iter() {
+    let vetkd_tag = vetkd_enabled.then_some(NiDkgTag::VetKd);
+    for tag in TAGS.iter().chain(vetkd_tag.iter()) {
         let dkg_id = NiDkgId {
             start_block_height,
             dealer_subnet: subnet_id,
@@ -833,8 +857,8 @@ pub fn get_configs_for_local_transcripts(
         };
         let (dealers, resharing_transcript) = match tag {
             NiDkgTag::LowThreshold => (node_ids.clone(), None),
-            NiDkgTag::HighThreshold => {
-                let resharing_transcript = reshared_transcripts.get(&NiDkgTag::HighThreshold);
+            NiDkgTag::HighThreshold | NiDkgTag::VetKd => {
+                let resharing_transcript = reshared_transcripts.get(tag);
                 (
                     resharing_transcript
                         .map(|transcript| transcript.committee.get().clone())
@@ -1377,6 +1401,8 @@ fn get_dkg_summary_from_cup_contents(
         // the recovered NNS so that the DKG configs point to the correct registry version and new
         // dealings can be created in the first DKG interval.
         registry_version_of_original_registry.unwrap_or(registry_version),
+        is_vetkd_enabled(registry, registry_version, subnet_id)
+            .expect("Could not retrieve the vetKD config for the genesis summary"),
     )
     .expect("Couldn't generate configs for the genesis summary");
     // For the first 2 intervals we use the length value contained in the
@@ -1831,7 +1857,7 @@ mod tests {
                         conf.threshold().get().get(),
                         match *tag {
                             NiDkgTag::LowThreshold => 3,
-                            NiDkgTag::HighThreshold => 5,
+                            NiDkgTag::HighThreshold | NiDkgTag::VetKd => 5,
                         }
                     );
 
@@ -1968,6 +1994,7 @@ mod tests {
             .into_iter()
             .collect(),
             registry_version,
+            false,
         )
         .unwrap_or_else(|err| panic!("Couldn't create configs: {:?}", err));
 
@@ -2032,6 +2059,61 @@ mod tests {
         }
     }
 
+    // Tests that the vetKD config is only created if vetKD is enabled, and that it
+    // reshares the vetKD transcript, so that the vetKD key does not change.
+    #[test]
+    fn test_get_configs_for_local_transcripts_with_vetkd() {
+        let prev_committee: Vec<_> = (10..21).map(node_test_id).collect();
+        let reshared_transcript = NiDkgTranscript::dummy_transcript_for_tests_with_params(
+            prev_committee.clone(),
+            NiDkgTag::VetKd,
+            NiDkgTag::VetKd.threshold_for_subnet_of_size(prev_committee.len()) as u32,
+            888,
+        );
+        let receivers: BTreeSet<_> = (3..8).map(node_test_id).collect();
+        let start_block_height = Height::from(777);
+        let subnet_id = subnet_test_id(123);
+        let registry_version = RegistryVersion::from(888);
+        let reshared_transcripts = vec![(NiDkgTag::VetKd, reshared_transcript.clone())]
+            .into_iter()
+            .collect();
+
+        let configs = get_configs_for_local_transcripts(
+            subnet_id,
+            receivers.clone(),
+            start_block_height,
+            &reshared_transcripts,
+            registry_version,
+            false,
+        )
+        .unwrap();
+        assert!(configs
+            .iter()
+            .all(|config| config.dkg_id().dkg_tag != NiDkgTag::VetKd));
+
+        let configs = get_configs_for_local_transcripts(
+            subnet_id,
+            receivers.clone(),
+            start_block_height,
+            &reshared_transcripts,
+            registry_version,
+            true,
+        )
+        .unwrap();
+        assert_eq!(configs.len(), 3);
+        let config = configs
+            .iter()
+            .find(|config| config.dkg_id().dkg_tag == NiDkgTag::VetKd)
+            .expect("No vetKD config was created");
+        assert_eq!(config.receivers().get(), &receivers);
+        assert_eq!(config.threshold().get().get(), 3);
+        assert_eq!(
+            config.dealers().get(),
+            &prev_committee.into_iter().collect::<BTreeSet<_>>()
+        );
+        assert_eq!(config.resharing_transcript(), &Some(reshared_transcript));
+    }
+
     // Creates a summary from registry and tests that all fields of the summary
     // contain the expected contents.
     #[test]
@@ -2100,7 +2182,7 @@ mod tests {
                     conf.threshold().get().get(),
                     match *tag {
                         NiDkgTag::LowThreshold => 3,
-                        NiDkgTag::HighThreshold => 5,
+                        NiDkgTag::HighThreshold | NiDkgTag::VetKd => 5,
                     }
                 );
             }
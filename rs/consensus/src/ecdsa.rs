@@ -194,7 +194,7 @@ use ic_metrics::MetricsRegistry;
 use ic_types::{
     artifact::{EcdsaMessageAttribute, EcdsaMessageId, Priority, PriorityFn},
     artifact_kind::EcdsaArtifact,
-    consensus::ecdsa::{EcdsaBlockReader, RequestId},
+    consensus::ecdsa::{EcdsaBlockReader, PseudoRandomId, RequestId},
     crypto::canister_threshold_sig::idkg::IDkgTranscriptId,
     malicious_flags::MaliciousFlags,
     Height, NodeId, SubnetId,
@@ -421,6 +421,7 @@ struct EcdsaPriorityFnArgs {
     finalized_height: Height,
     requested_transcripts: BTreeSet<IDkgTranscriptId>,
     requested_signatures: BTreeSet<RequestId>,
+    requested_vetkd_keys: BTreeSet<PseudoRandomId>,
     active_transcripts: BTreeSet<IDkgTranscriptId>,
 }
 
@@ -436,6 +437,11 @@ impl EcdsaPriorityFnArgs {
             requested_signatures.insert(*request_id);
         }
 
+        let mut requested_vetkd_keys = BTreeSet::new();
+        for (pseudo_random_id, _) in block_reader.requested_vetkd_keys() {
+            requested_vetkd_keys.insert(*pseudo_random_id);
+        }
+
         let mut active_transcripts = BTreeSet::new();
         for transcript_ref in block_reader.active_transcripts() {
             active_transcripts.insert(transcript_ref.transcript_id);
@@ -445,6 +451,7 @@ impl EcdsaPriorityFnArgs {
             finalized_height: block_reader.tip_height(),
             requested_transcripts,
             requested_signatures,
+            requested_vetkd_keys,
             active_transcripts,
         }
     }
@@ -534,6 +541,15 @@ fn compute_priority(
                 Priority::Stash
             }
         }
+        EcdsaMessageAttribute::VetKdKeyShare(pseudo_random_id) => {
+            // The attribute carries no height, so shares for requests we
+            // don't know yet may be from a node ahead of us.
+            if args.requested_vetkd_keys.contains(pseudo_random_id) {
+                Priority::Fetch
+            } else {
+                Priority::Stash
+            }
+        }
     }
 }
 
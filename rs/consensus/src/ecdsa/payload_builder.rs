@@ -4,16 +4,19 @@
 
 use super::pre_signer::{EcdsaTranscriptBuilder, EcdsaTranscriptBuilderImpl};
 use super::signer::{EcdsaSignatureBuilder, EcdsaSignatureBuilderImpl};
-use super::utils::EcdsaBlockReaderImpl;
+use super::utils::{algorithm_for_key_id, inspect_ecdsa_initializations, EcdsaBlockReaderImpl};
 use crate::consensus::{
     crypto::ConsensusCrypto,
-    metrics::{EcdsaPayloadMetrics, CRITICAL_ERROR_ECDSA_KEY_TRANSCRIPT_MISSING},
+    metrics::{
+        EcdsaPayloadMetrics, CRITICAL_ERROR_ECDSA_KEY_PUBLIC_KEY_CHANGED,
+        CRITICAL_ERROR_ECDSA_KEY_TRANSCRIPT_MISSING,
+    },
     pool_reader::PoolReader,
 };
 use ic_artifact_pool::consensus_pool::build_consensus_block_chain;
-use ic_crypto::{get_mega_pubkey, MegaKeyFromRegistryError};
+use ic_crypto::{get_mega_pubkey, get_tecdsa_master_public_key, MegaKeyFromRegistryError};
 use ic_error_types::RejectCode;
-use ic_ic00_types::{EcdsaKeyId, Payload, SignWithECDSAReply};
+use ic_ic00_types::{EcdsaKeyId, Payload, SignWithECDSAReply, VetKdEncryptedKeyResult};
 use ic_interfaces::{consensus_pool::ConsensusBlockChain, ecdsa::EcdsaPool};
 use ic_interfaces_registry::RegistryClient;
 use ic_interfaces_state_manager::{StateManager, StateManagerError};
@@ -38,6 +41,8 @@ use ic_types::{
             idkg::{IDkgTranscript, InitialIDkgDealings},
             ExtendedDerivationPath,
         },
+        threshold_sig::ni_dkg::{NiDkgId, NiDkgTag},
+        vetkd::{vetkd_derivation_path, VetKdArgs},
         AlgorithmId,
     },
     messages::{CallbackId, RejectContext},
@@ -168,6 +173,8 @@ pub fn make_bootstrap_summary(
         idkg_transcripts: BTreeMap::new(),
         ongoing_xnet_reshares: BTreeMap::new(),
         xnet_reshare_agreements: BTreeMap::new(),
+        ongoing_vetkd_requests: BTreeMap::new(),
+        vetkd_agreements: BTreeMap::new(),
         key_transcript: ecdsa::EcdsaKeyTranscript {
             current: None,
             next_in_creation: ecdsa::KeyTranscriptCreation::Begin,
@@ -277,21 +284,36 @@ pub(crate) fn create_summary_payload(
     let ecdsa_payload = parent_block.payload.as_ref().as_data().ecdsa.as_ref();
     if ecdsa_payload.is_none() {
         // Parent block doesn't have ECDSA payload and feature is enabled.
-        // Create the bootstrap summary block, and create a new key for the given key_id.
-        //
-        // This is safe because registry's do_update_subnet already ensures that only
-        // fresh key_id can be assigned to an existing subnet.
+        // Create the bootstrap summary block.
         //
-        // Keys already held by existing subnets can only be re-shared when creating
-        // a new subnet, which means the genesis summary ECDSA payload is not empty
-        // and we won't reach here.
+        // Registry's do_update_subnet ensures that a key_id already held by another
+        // subnet is only assigned to an existing subnet together with the initial
+        // dealings that reshare it to this subnet, which are then found in the CUP
+        // contents. Otherwise a new key is created for the given key_id.
         let key_id = ecdsa_config.key_ids[0].clone();
-        info!(
-            log,
-            "Start to create ECDSA key {} on subnet {} at height {}", key_id, subnet_id, height
-        );
+        let initial_dealings = get_initial_dealings_for_key(
+            subnet_id,
+            &key_id,
+            curr_interval_registry_version,
+            registry_client,
+            &log,
+        )?;
+        if initial_dealings.is_some() {
+            info!(
+                log,
+                "Start to reshare ECDSA key {} to subnet {} at height {}",
+                key_id,
+                subnet_id,
+                height
+            );
+        } else {
+            info!(
+                log,
+                "Start to create ECDSA key {} on subnet {} at height {}", key_id, subnet_id, height
+            );
+        }
 
-        return make_bootstrap_summary(subnet_id, key_id, height, None, &log);
+        return make_bootstrap_summary(subnet_id, key_id, height, initial_dealings, &log);
     }
     let ecdsa_payload = ecdsa_payload.unwrap();
 
@@ -316,6 +338,34 @@ pub(crate) fn create_summary_payload(
     )
 }
 
+/// Returns the initial dealings for the given key from the subnet's CUP contents,
+/// if the key is reshared to this subnet from another subnet.
+fn get_initial_dealings_for_key(
+    subnet_id: SubnetId,
+    key_id: &EcdsaKeyId,
+    registry_version: RegistryVersion,
+    registry_client: &dyn RegistryClient,
+    log: &ReplicaLogger,
+) -> Result<Option<InitialIDkgDealings>, RegistryClientError> {
+    let cup_contents = match registry_client
+        .get_cup_contents(subnet_id, registry_version)?
+        .value
+    {
+        Some(cup_contents) => cup_contents,
+        None => return Ok(None),
+    };
+    match inspect_ecdsa_initializations(&cup_contents.ecdsa_initializations) {
+        Ok(Some((initialized_key_id, dealings))) if initialized_key_id == *key_id => {
+            Ok(Some(dealings))
+        }
+        Ok(_) => Ok(None),
+        Err(err) => {
+            warn!(log, "{}", err);
+            Ok(None)
+        }
+    }
+}
+
 fn create_summary_payload_helper(
     subnet_id: SubnetId,
     registry_client: &dyn RegistryClient,
@@ -416,6 +466,10 @@ fn create_summary_payload_helper(
             ecdsa_payload.ongoing_xnet_reshares.clone()
         },
         xnet_reshare_agreements: ecdsa_payload.xnet_reshare_agreements.clone(),
+        // The NI-DKG transcript changes with every summary, so requests in
+        // progress are restarted by the next data block using the new one.
+        ongoing_vetkd_requests: BTreeMap::new(),
+        vetkd_agreements: ecdsa_payload.vetkd_agreements.clone(),
         key_transcript: ecdsa::EcdsaKeyTranscript {
             current: if created.is_none() {
                 // Keep using previous key transcript if the next hasn't been created
@@ -618,6 +672,14 @@ pub(crate) fn create_data_payload(
             "xnet_reshare_agreements",
             ecdsa_payload.xnet_reshare_agreements.len() as i64,
         );
+        ecdsa_payload_metrics.payload_metrics_set(
+            "ongoing_vetkd_requests",
+            ecdsa_payload.ongoing_vetkd_requests.len() as i64,
+        );
+        ecdsa_payload_metrics.payload_metrics_set(
+            "vetkd_agreements",
+            ecdsa_payload.vetkd_agreements.len() as i64,
+        );
     };
     Ok(new_payload)
 }
@@ -673,12 +735,30 @@ pub(crate) fn create_data_payload_helper(
         .metadata
         .subnet_call_context_manager
         .ecdsa_dealings_contexts;
+    let vetkd_contexts = &state
+        .get_ref()
+        .metadata
+        .subnet_call_context_manager
+        .vetkd_encrypted_key_contexts;
+
+    // A subnet without a key transcript whose CUP contents hold initial dealings
+    // for the key is receiving the key from another subnet.
+    let key_is_reshared = ecdsa_payload.key_transcript.current.is_none()
+        && get_initial_dealings_for_key(
+            subnet_id,
+            &ecdsa_payload.key_transcript.key_id,
+            curr_interval_registry_version,
+            registry_client,
+            &log,
+        )?
+        .is_some();
 
     create_data_payload_helper_2(
         &mut ecdsa_payload,
         height,
         context.time,
         &ecdsa_config,
+        key_is_reshared,
         next_interval_registry_version,
         &receivers,
         all_signing_requests,
@@ -689,14 +769,48 @@ pub(crate) fn create_data_payload_helper(
         ecdsa_payload_metrics,
         log,
     )?;
+    // Encrypted keys are derived from the vetKD NI-DKG transcript of the
+    // current interval. Until the subnet has one, no new requests are started.
+    let vetkd_config =
+        registry_client.get_vetkd_config(subnet_id, curr_interval_registry_version)?;
+    let ni_dkg_id = summary
+        .dkg
+        .current_transcripts()
+        .get(&NiDkgTag::VetKd)
+        .map(|transcript| transcript.dkg_id);
+    update_vetkd_requests(
+        vetkd_contexts,
+        ni_dkg_id,
+        get_request_expiry_time(
+            vetkd_config.and_then(|config| config.request_timeout_ns),
+            context.time,
+        ),
+        signature_builder,
+        &mut ecdsa_payload,
+        ecdsa_payload_metrics,
+    );
     Ok(Some(ecdsa_payload))
 }
 
+/// Returns the time before which requests are considered expired, if a
+/// request timeout is configured.
+fn get_request_expiry_time(request_timeout_ns: Option<u64>, context_time: Time) -> Option<Time> {
+    request_timeout_ns.and_then(|t| {
+        let timeout = Duration::from_nanos(t);
+        if context_time.as_nanos_since_unix_epoch() >= t {
+            Some(context_time - timeout)
+        } else {
+            None
+        }
+    })
+}
+
 pub(crate) fn create_data_payload_helper_2(
     ecdsa_payload: &mut ecdsa::EcdsaPayload,
     height: Height,
     context_time: Time,
     ecdsa_config: &EcdsaConfig,
+    key_is_reshared: bool,
     next_interval_registry_version: RegistryVersion,
     receivers: &[NodeId],
     all_signing_requests: &BTreeMap<CallbackId, SignWithEcdsaContext>,
@@ -723,14 +837,8 @@ pub(crate) fn create_data_payload_helper_2(
     let current_key_transcript = ecdsa_payload.key_transcript.current.as_ref().cloned();
 
     let valid_keys: BTreeSet<_> = ecdsa_config.key_ids.iter().cloned().collect();
-    let request_expiry_time = ecdsa_config.signature_request_timeout_ns.and_then(|t| {
-        let timeout = Duration::from_nanos(t);
-        if context_time.as_nanos_since_unix_epoch() >= t {
-            Some(context_time - timeout)
-        } else {
-            None
-        }
-    });
+    let request_expiry_time =
+        get_request_expiry_time(ecdsa_config.signature_request_timeout_ns, context_time);
     update_signature_agreements(all_signing_requests, signature_builder, ecdsa_payload);
     let new_signing_requests = get_signing_requests(
         height,
@@ -760,10 +868,14 @@ pub(crate) fn create_data_payload_helper_2(
         receivers,
         next_interval_registry_version,
         current_key_transcript.as_ref(),
+        &ecdsa_payload.key_transcript.key_id,
+        key_is_reshared,
         &mut ecdsa_payload.key_transcript.next_in_creation,
         &mut ecdsa_payload.uid_generator,
+        block_reader,
         transcript_builder,
         height,
+        ecdsa_payload_metrics,
         log.clone(),
     )? {
         new_transcripts.push(new_transcript);
@@ -801,6 +913,7 @@ fn new_random_config(
     subnet_nodes: &[NodeId],
     summary_registry_version: RegistryVersion,
     uid_generator: &mut ecdsa::EcdsaUIDGenerator,
+    algorithm_id: AlgorithmId,
 ) -> Result<ecdsa::RandomTranscriptParams, EcdsaPayloadError> {
     let transcript_id = uid_generator.next_transcript_id();
     let dealers = subnet_nodes.iter().copied().collect::<BTreeSet<_>>();
@@ -810,7 +923,7 @@ fn new_random_config(
         dealers,
         receivers,
         summary_registry_version,
-        AlgorithmId::ThresholdEcdsaSecp256k1,
+        algorithm_id,
     ))
 }
 
@@ -844,11 +957,14 @@ fn make_new_quadruples_if_needed_helper(
     let unassigned_quadruples = ecdsa_payload.unassigned_quadruple_ids().count();
     let quadruples_to_create = ecdsa_config.quadruples_to_create_in_advance as usize;
     if quadruples_to_create > unassigned_quadruples {
+        let algorithm_id = algorithm_for_key_id(&ecdsa_payload.key_transcript.key_id);
         let quadruples_in_creation = &mut ecdsa_payload.quadruples_in_creation;
         let uid_generator = &mut ecdsa_payload.uid_generator;
         for _ in 0..(quadruples_to_create - unassigned_quadruples) {
-            let kappa_config = new_random_config(subnet_nodes, registry_version, uid_generator)?;
-            let lambda_config = new_random_config(subnet_nodes, registry_version, uid_generator)?;
+            let kappa_config =
+                new_random_config(subnet_nodes, registry_version, uid_generator, algorithm_id)?;
+            let lambda_config =
+                new_random_config(subnet_nodes, registry_version, uid_generator, algorithm_id)?;
             quadruples_in_creation.insert(
                 uid_generator.next_quadruple_id(),
                 ecdsa::QuadrupleInCreation::new(kappa_config, lambda_config),
@@ -1140,6 +1256,99 @@ pub(crate) fn update_signature_agreements(
     }
 }
 
+/// Update vetKD requests and agreements in the data payload by:
+/// - dropping agreements that don't have a [VetKdEncryptedKeyContext] anymore,
+///   and setting the remaining ones to "Reported"
+/// - rejecting expired requests
+/// - adding new agreements as "Unreported" by combining encrypted key shares
+///   in the ECDSA pool
+/// - starting to work on new requests, using the given vetKD NI-DKG
+///   transcript, if there is one.
+pub(crate) fn update_vetkd_requests(
+    vetkd_contexts: &BTreeMap<CallbackId, VetKdEncryptedKeyContext>,
+    ni_dkg_id: Option<NiDkgId>,
+    request_expiry_time: Option<Time>,
+    signature_builder: &dyn EcdsaSignatureBuilder,
+    payload: &mut ecdsa::EcdsaPayload,
+    ecdsa_payload_metrics: Option<&EcdsaPayloadMetrics>,
+) {
+    let all_random_ids = vetkd_contexts
+        .values()
+        .map(|context| context.pseudo_random_id)
+        .collect::<BTreeSet<_>>();
+    payload.vetkd_agreements = payload
+        .vetkd_agreements
+        .keys()
+        .filter(|random_id| all_random_ids.contains(*random_id))
+        .map(|random_id| (*random_id, ecdsa::CompletedSignature::ReportedToExecution))
+        .collect();
+    payload
+        .ongoing_vetkd_requests
+        .retain(|random_id, _| all_random_ids.contains(random_id));
+
+    // Contexts are traversed in the order of their callback ids, i.e. in
+    // the order the requests were created.
+    for (callback_id, context) in vetkd_contexts.iter() {
+        let random_id = context.pseudo_random_id;
+        if payload.vetkd_agreements.contains_key(&random_id) {
+            continue;
+        }
+        let make_response = |response_payload| ic_types::messages::Response {
+            originator: context.request.sender,
+            respondent: ic_types::CanisterId::ic_00(),
+            originator_reply_callback: *callback_id,
+            // Execution burns the fee before pushing the context, so
+            // any remaining cycles can be refunded to the canister.
+            refund: context.request.payment,
+            response_payload,
+        };
+
+        if let Some(expiry) = request_expiry_time {
+            if context.batch_time < expiry {
+                let response = make_response(ic_types::messages::Payload::Reject(RejectContext {
+                    code: RejectCode::CanisterError,
+                    message: "vetKD request expired".to_string(),
+                }));
+                payload
+                    .vetkd_agreements
+                    .insert(random_id, ecdsa::CompletedSignature::Unreported(response));
+                payload.ongoing_vetkd_requests.remove(&random_id);
+                if let Some(metrics) = ecdsa_payload_metrics {
+                    metrics.payload_errors_inc("expired_vetkd_requests");
+                }
+                continue;
+            }
+        }
+
+        if payload.ongoing_vetkd_requests.contains_key(&random_id) {
+            if let Some(key) = signature_builder.get_completed_vetkd_key(&random_id) {
+                let response = make_response(ic_types::messages::Payload::Data(
+                    VetKdEncryptedKeyResult {
+                        encrypted_key: key.encrypted_key,
+                    }
+                    .encode(),
+                ));
+                payload.ongoing_vetkd_requests.remove(&random_id);
+                payload
+                    .vetkd_agreements
+                    .insert(random_id, ecdsa::CompletedSignature::Unreported(response));
+            }
+        } else if let Some(ni_dkg_id) = ni_dkg_id {
+            let args = VetKdArgs {
+                ni_dkg_id,
+                derivation_path: vetkd_derivation_path(
+                    context.request.sender.get(),
+                    &context.key_id,
+                    &context.derivation_path,
+                ),
+                derivation_id: context.derivation_id.clone(),
+                encryption_public_key: context.encryption_public_key.clone(),
+            };
+            payload.ongoing_vetkd_requests.insert(random_id, args);
+        }
+    }
+}
+
 /// For every new signing request, we only start to work on them if
 /// their matched quadruple has been fully produced.
 pub(crate) fn update_ongoing_signatures(
@@ -1176,14 +1385,22 @@ pub(crate) fn update_ongoing_signatures(
 ///
 /// Note that when creating next key transcript we must use the registry version
 /// that is going to be put into the next DKG summary.
+///
+/// `key_is_reshared` tells whether the subnet has no key transcript yet and is
+/// to receive the key from the initial dealings of another subnet, in which
+/// case a new key is never created.
 fn update_next_key_transcript(
     receivers: &[NodeId],
     registry_version: RegistryVersion,
     current_key_transcript: Option<&ecdsa::UnmaskedTranscriptWithAttributes>,
+    key_id: &EcdsaKeyId,
+    key_is_reshared: bool,
     next_key_transcript_creation: &mut ecdsa::KeyTranscriptCreation,
     uid_generator: &mut ecdsa::EcdsaUIDGenerator,
+    block_reader: &dyn EcdsaBlockReader,
     transcript_cache: &dyn EcdsaTranscriptBuilder,
     height: Height,
+    ecdsa_payload_metrics: Option<&EcdsaPayloadMetrics>,
     log: ReplicaLogger,
 ) -> Result<Option<IDkgTranscript>, EcdsaPayloadError> {
     let mut new_transcript = None;
@@ -1215,6 +1432,12 @@ fn update_next_key_transcript(
             if let Some(transcript) =
                 transcript_cache.get_completed_transcript(config.as_ref().transcript_id)
             {
+                if !is_public_key_unchanged(config.as_ref(), &transcript, block_reader)? {
+                    report_public_key_changed(&transcript, height, ecdsa_payload_metrics, &log);
+                    // Keep using the current key transcript, and start resharing it again.
+                    *next_key_transcript_creation = ecdsa::KeyTranscriptCreation::Begin;
+                    return Ok(None);
+                }
                 info!(
                     log,
                     "ECDSA key transcript created from ReshareOfUnmasked {:?} registry_version {:?} height = {}",
@@ -1228,6 +1451,10 @@ fn update_next_key_transcript(
                 new_transcript = Some(transcript);
             }
         }
+        (None, ecdsa::KeyTranscriptCreation::Begin) if key_is_reshared => {
+            // Resharing the key from the initial dealings failed. The key is held by
+            // another subnet, so it must not be replaced by a new key.
+        }
         (None, ecdsa::KeyTranscriptCreation::Begin) => {
             // The first ECDSA key transcript has to be created, starting from a random
             // config. Here receivers and dealers are the same set.
@@ -1240,7 +1467,7 @@ fn update_next_key_transcript(
                     dealers_set,
                     receivers_set,
                     registry_version,
-                    AlgorithmId::ThresholdEcdsaSecp256k1,
+                    algorithm_for_key_id(key_id),
                 ),
             );
         }
@@ -1286,6 +1513,14 @@ fn update_next_key_transcript(
             if let Some(transcript) =
                 transcript_cache.get_completed_transcript(config.as_ref().transcript_id)
             {
+                if !is_public_key_unchanged(config.as_ref(), &transcript, block_reader)? {
+                    // The initial dealings cannot be retried, so the key is not adopted,
+                    // and the transcript creation is cleared instead of being checked
+                    // again in every block.
+                    report_public_key_changed(&transcript, height, ecdsa_payload_metrics, &log);
+                    *next_key_transcript_creation = ecdsa::KeyTranscriptCreation::Begin;
+                    return Ok(None);
+                }
                 // next_unused_transcript_id is not updated, since the transcript_id specified
                 // by the reshared param will be used.
                 info!(
@@ -1314,6 +1549,47 @@ fn update_next_key_transcript(
     Ok(new_transcript)
 }
 
+/// Returns true if the given transcript, created by resharing an unmasked key
+/// transcript according to the given params, has the same public key as the
+/// reshared key transcript.
+fn is_public_key_unchanged(
+    params: &ecdsa::IDkgTranscriptParamsRef,
+    transcript: &IDkgTranscript,
+    block_reader: &dyn EcdsaBlockReader,
+) -> Result<bool, EcdsaPayloadError> {
+    let reshared_transcript = match &params.operation_type_ref {
+        ecdsa::IDkgTranscriptOperationRef::ReshareOfUnmasked(unmasked) => {
+            block_reader.transcript(unmasked.as_ref())?
+        }
+        _ => return Ok(false),
+    };
+    match (
+        get_tecdsa_master_public_key(&reshared_transcript),
+        get_tecdsa_master_public_key(transcript),
+    ) {
+        (Ok(reshared_public_key), Ok(public_key)) => Ok(reshared_public_key == public_key),
+        _ => Ok(false),
+    }
+}
+
+fn report_public_key_changed(
+    transcript: &IDkgTranscript,
+    height: Height,
+    ecdsa_payload_metrics: Option<&EcdsaPayloadMetrics>,
+    log: &ReplicaLogger,
+) {
+    if let Some(metrics) = ecdsa_payload_metrics {
+        metrics.critical_error_ecdsa_key_public_key_changed.inc();
+    }
+    error!(
+        log,
+        "{}: Reshared ECDSA key transcript {:?} does not have the public key of the original key transcript, height = {:?}",
+        CRITICAL_ERROR_ECDSA_KEY_PUBLIC_KEY_CHANGED,
+        transcript.transcript_id,
+        height,
+    );
+}
+
 /// Update the quadruples in the payload by:
 /// - making new configs when pre-conditions are met;
 /// - gathering ready results (new transcripts) from ecdsa pool;
@@ -1748,6 +2024,7 @@ mod tests {
         generate_key_transcript, run_idkg_and_create_and_verify_transcript,
         CanisterThresholdSigTestEnvironment,
     };
+    use ic_ic00_types::{VetKdCurve, VetKdKeyId};
     use ic_interfaces_registry::RegistryValue;
     use ic_logger::replica_logger::no_op_logger;
     use ic_protobuf::types::v1 as pb;
@@ -1771,6 +2048,8 @@ mod tests {
     use ic_types::crypto::canister_threshold_sig::{
         idkg::IDkgTranscriptId, ThresholdEcdsaCombinedSignature,
     };
+    use ic_types::crypto::threshold_sig::ni_dkg::NiDkgTargetSubnet;
+    use ic_types::crypto::vetkd::VetKdEncryptedKey;
     use ic_types::crypto::{CryptoHash, CryptoHashOf};
     use ic_types::{messages::CallbackId, Height, RegistryVersion};
     use std::collections::BTreeSet;
@@ -1862,10 +2141,20 @@ mod tests {
         uid_generator: &mut ecdsa::EcdsaUIDGenerator,
         quadruples_in_creation: &mut BTreeMap<ecdsa::QuadrupleId, ecdsa::QuadrupleInCreation>,
     ) -> (ecdsa::RandomTranscriptParams, ecdsa::RandomTranscriptParams) {
-        let kappa_config_ref =
-            new_random_config(subnet_nodes, registry_version, uid_generator).unwrap();
-        let lambda_config_ref =
-            new_random_config(subnet_nodes, registry_version, uid_generator).unwrap();
+        let kappa_config_ref = new_random_config(
+            subnet_nodes,
+            registry_version,
+            uid_generator,
+            AlgorithmId::ThresholdEcdsaSecp256k1,
+        )
+        .unwrap();
+        let lambda_config_ref = new_random_config(
+            subnet_nodes,
+            registry_version,
+            uid_generator,
+            AlgorithmId::ThresholdEcdsaSecp256k1,
+        )
+        .unwrap();
         quadruples_in_creation.insert(
             uid_generator.next_quadruple_id(),
             ecdsa::QuadrupleInCreation::new(kappa_config_ref.clone(), lambda_config_ref.clone()),
@@ -2339,10 +2628,14 @@ mod tests {
             &subnet_nodes,
             registry_version,
             None,
+            &payload.key_transcript.key_id,
+            false,
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
+            &block_reader,
             &transcript_builder,
             cur_height,
+            None,
             no_op_logger(),
         );
         matches!(result, Ok(None));
@@ -2372,10 +2665,14 @@ mod tests {
             &subnet_nodes,
             registry_version,
             None,
+            &payload.key_transcript.key_id,
+            false,
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
+            &block_reader,
             &transcript_builder,
             cur_height,
+            None,
             no_op_logger(),
         );
         let completed_transcript = result.unwrap().unwrap();
@@ -2411,10 +2708,14 @@ mod tests {
             &subnet_nodes,
             registry_version,
             None,
+            &payload.key_transcript.key_id,
+            false,
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
+            &block_reader,
             &transcript_builder,
             cur_height,
+            None,
             no_op_logger(),
         );
         let completed_transcript = result.unwrap().unwrap();
@@ -2448,10 +2749,14 @@ mod tests {
             &subnet_nodes,
             registry_version,
             Some(&current_key_transcript),
+            &payload.key_transcript.key_id,
+            false,
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
+            &block_reader,
             &transcript_builder,
             cur_height,
+            None,
             no_op_logger(),
         );
         matches!(result, Ok(None));
@@ -2482,10 +2787,14 @@ mod tests {
             &subnet_nodes,
             registry_version,
             Some(&current_key_transcript),
+            &payload.key_transcript.key_id,
+            false,
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
+            &block_reader,
             &transcript_builder,
             cur_height,
+            None,
             no_op_logger(),
         );
         let completed_transcript = result.unwrap().unwrap();
@@ -2532,10 +2841,14 @@ mod tests {
             &subnet_nodes,
             registry_version,
             None,
+            &payload.key_transcript.key_id,
+            false,
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
+            &block_reader,
             &transcript_builder,
             cur_height,
+            None,
             no_op_logger(),
         );
         matches!(result, Ok(None));
@@ -2565,10 +2878,14 @@ mod tests {
             &subnet_nodes,
             registry_version,
             None,
+            &payload.key_transcript.key_id,
+            false,
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
+            &block_reader,
             &transcript_builder,
             cur_height,
+            None,
             no_op_logger(),
         );
         let completed_transcript = result.unwrap().unwrap();
@@ -2604,10 +2921,14 @@ mod tests {
             &subnet_nodes,
             registry_version,
             None,
+            &payload.key_transcript.key_id,
+            false,
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
+            &block_reader,
             &transcript_builder,
             cur_height,
+            None,
             no_op_logger(),
         );
         let completed_transcript = result.unwrap().unwrap();
@@ -2653,10 +2974,14 @@ mod tests {
             &subnet_nodes,
             registry_version,
             None,
+            &payload.key_transcript.key_id,
+            false,
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
+            &block_reader,
             &transcript_builder,
             cur_height,
+            None,
             no_op_logger(),
         );
         matches!(result, Ok(None));
@@ -2691,10 +3016,14 @@ mod tests {
             &target_subnet_nodes,
             registry_version,
             None,
+            &payload.key_transcript.key_id,
+            false,
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
+            &block_reader,
             &transcript_builder,
             cur_height,
+            None,
             no_op_logger(),
         );
         let completed_transcript = result.unwrap().unwrap();
@@ -2715,6 +3044,161 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_ecdsa_update_next_key_transcript_rejects_changed_public_key() {
+        let num_of_nodes = 4;
+        let subnet_id = subnet_test_id(1);
+        let env = CanisterThresholdSigTestEnvironment::new(num_of_nodes);
+        let registry_version = env.newest_registry_version;
+        let subnet_nodes = env.receivers().into_iter().collect::<Vec<_>>();
+        let mut block_reader = TestEcdsaBlockReader::new();
+        let transcript_builder = TestEcdsaTranscriptBuilder::new();
+        let algorithm = AlgorithmId::ThresholdEcdsaSecp256k1;
+
+        // 1. Start resharing the current key transcript
+        let cur_height = Height::new(10);
+        let key_transcript = generate_key_transcript(&env, algorithm);
+        let key_transcript_ref =
+            ecdsa::UnmaskedTranscript::try_from((cur_height, &key_transcript)).unwrap();
+        block_reader.add_transcript(*key_transcript_ref.as_ref(), key_transcript.clone());
+        let current_key_transcript = ecdsa::UnmaskedTranscriptWithAttributes::new(
+            key_transcript.to_attributes(),
+            key_transcript_ref,
+        );
+        let mut payload = empty_ecdsa_data_payload(subnet_id);
+        payload.key_transcript.next_in_creation = ecdsa::KeyTranscriptCreation::Begin;
+        let result = update_next_key_transcript(
+            &subnet_nodes,
+            registry_version,
+            Some(&current_key_transcript),
+            &payload.key_transcript.key_id,
+            false,
+            &mut payload.key_transcript.next_in_creation,
+            &mut payload.uid_generator,
+            &block_reader,
+            &transcript_builder,
+            cur_height,
+            None,
+            no_op_logger(),
+        );
+        assert!(matches!(result, Ok(None)));
+        let reshare_transcript_id = match &payload.key_transcript.next_in_creation {
+            ecdsa::KeyTranscriptCreation::ReshareOfUnmaskedParams(param) => {
+                param.as_ref().transcript_id
+            }
+            _ => panic!(
+                "Unexpected state: {:?}",
+                payload.key_transcript.next_in_creation
+            ),
+        };
+
+        // 2. The reshare completes with the transcript of a different key, which must
+        // not be adopted. Resharing of the current key transcript starts over instead.
+        let cur_height = Height::new(20);
+        let other_key_transcript = generate_key_transcript(&env, algorithm);
+        transcript_builder.add_transcript(reshare_transcript_id, other_key_transcript);
+        let result = update_next_key_transcript(
+            &subnet_nodes,
+            registry_version,
+            Some(&current_key_transcript),
+            &payload.key_transcript.key_id,
+            false,
+            &mut payload.key_transcript.next_in_creation,
+            &mut payload.uid_generator,
+            &block_reader,
+            &transcript_builder,
+            cur_height,
+            None,
+            no_op_logger(),
+        );
+        assert!(matches!(result, Ok(None)));
+        assert_eq!(
+            payload.key_transcript.next_in_creation,
+            ecdsa::KeyTranscriptCreation::Begin
+        );
+    }
+
+    #[test]
+    fn test_ecdsa_update_next_key_transcript_clears_xnet_reshare_with_changed_public_key() {
+        let num_of_nodes = 4;
+        let subnet_id = subnet_test_id(1);
+        let env = CanisterThresholdSigTestEnvironment::new(num_of_nodes);
+        let registry_version = env.newest_registry_version;
+        let subnet_nodes = env.receivers().into_iter().collect::<Vec<_>>();
+        let mut block_reader = TestEcdsaBlockReader::new();
+        let transcript_builder = TestEcdsaTranscriptBuilder::new();
+        let algorithm = AlgorithmId::ThresholdEcdsaSecp256k1;
+
+        // 1. Bootstrap from initial dealings resharing the key of another subnet
+        let cur_height = Height::new(10);
+        let key_transcript = generate_key_transcript(&env, algorithm);
+        let reshare_params = create_reshare_unmasked_transcript_param(
+            &key_transcript,
+            &subnet_nodes,
+            registry_version,
+        );
+        let (params, transcript) =
+            ecdsa::unpack_reshare_of_unmasked_params(cur_height, &reshare_params).unwrap();
+        block_reader.add_transcript(
+            ecdsa::TranscriptRef::new(cur_height, transcript.transcript_id),
+            transcript,
+        );
+        let mut payload = empty_ecdsa_data_payload(subnet_id);
+        payload.key_transcript.next_in_creation =
+            ecdsa::KeyTranscriptCreation::XnetReshareOfUnmaskedParams((
+                Box::new(dummy_initial_idkg_dealing_for_tests()),
+                params,
+            ));
+
+        // 2. The reshare completes with the transcript of a different key, which must
+        // not be adopted. The transcript creation is cleared.
+        let cur_height = Height::new(20);
+        let other_key_transcript = generate_key_transcript(&env, algorithm);
+        transcript_builder.add_transcript(reshare_params.transcript_id(), other_key_transcript);
+        let result = update_next_key_transcript(
+            &subnet_nodes,
+            registry_version,
+            None,
+            &payload.key_transcript.key_id,
+            true,
+            &mut payload.key_transcript.next_in_creation,
+            &mut payload.uid_generator,
+            &block_reader,
+            &transcript_builder,
+            cur_height,
+            None,
+            no_op_logger(),
+        );
+        assert!(matches!(result, Ok(None)));
+        assert_eq!(
+            payload.key_transcript.next_in_creation,
+            ecdsa::KeyTranscriptCreation::Begin
+        );
+
+        // 3. The key held by the other subnet is not replaced by a new key.
+        let cur_height = Height::new(30);
+        let result = update_next_key_transcript(
+            &subnet_nodes,
+            registry_version,
+            None,
+            &payload.key_transcript.key_id,
+            true,
+            &mut payload.key_transcript.next_in_creation,
+            &mut payload.uid_generator,
+            &block_reader,
+            &transcript_builder,
+            cur_height,
+            None,
+            no_op_logger(),
+        );
+        assert!(matches!(result, Ok(None)));
+        assert_eq!(
+            payload.key_transcript.next_in_creation,
+            ecdsa::KeyTranscriptCreation::Begin
+        );
+        assert_eq!(payload.iter_transcript_configs_in_creation().count(), 0);
+    }
+
     #[test]
     fn test_ecdsa_update_signature_agreements() {
         let subnet_id = subnet_test_id(0);
@@ -2866,6 +3350,7 @@ mod tests {
             Height::from(5),
             mock_time(),
             &EcdsaConfig::default(),
+            false,
             RegistryVersion::from(9),
             &[node_test_id(0)],
             &sign_with_ecdsa_contexts,
@@ -2888,6 +3373,7 @@ mod tests {
             Height::from(5),
             mock_time(),
             &EcdsaConfig::default(),
+            false,
             RegistryVersion::from(9),
             &[node_test_id(0)],
             &sign_with_ecdsa_contexts,
@@ -2905,6 +3391,136 @@ mod tests {
         assert!(response2.is_empty());
     }
 
+    #[test]
+    fn test_ecdsa_update_vetkd_requests() {
+        let subnet_id = subnet_test_id(0);
+        let ni_dkg_id = NiDkgId {
+            start_block_height: Height::from(0),
+            dealer_subnet: subnet_id,
+            dkg_tag: NiDkgTag::VetKd,
+            target_subnet: NiDkgTargetSubnet::Local,
+        };
+        let make_context = |pseudo_random_id, batch_time| VetKdEncryptedKeyContext {
+            request: RequestBuilder::new().build(),
+            key_id: VetKdKeyId {
+                curve: VetKdCurve::Bls12_381_G2,
+                name: "some_key".to_string(),
+            },
+            derivation_path: vec![vec![1, 2, 3]],
+            derivation_id: vec![4, 5, 6],
+            encryption_public_key: vec![7; 48],
+            pseudo_random_id,
+            batch_time,
+        };
+        let mut contexts = BTreeMap::new();
+        contexts.insert(CallbackId::from(1), make_context([1; 32], mock_time()));
+        contexts.insert(CallbackId::from(2), make_context([2; 32], mock_time()));
+        let mut ecdsa_payload = empty_ecdsa_payload(subnet_id);
+        let mut signature_builder = TestEcdsaSignatureBuilder::new();
+
+        // Without a vetKD transcript, no request is started.
+        update_vetkd_requests(
+            &contexts,
+            None,
+            None,
+            &signature_builder,
+            &mut ecdsa_payload,
+            None,
+        );
+        assert!(ecdsa_payload.ongoing_vetkd_requests.is_empty());
+        assert!(ecdsa_payload.vetkd_agreements.is_empty());
+
+        // New requests start with the given transcript and the derivation
+        // path of the caller.
+        update_vetkd_requests(
+            &contexts,
+            Some(ni_dkg_id),
+            None,
+            &signature_builder,
+            &mut ecdsa_payload,
+            None,
+        );
+        assert_eq!(ecdsa_payload.ongoing_vetkd_requests.len(), 2);
+        assert!(ecdsa_payload.vetkd_agreements.is_empty());
+        let args = ecdsa_payload.ongoing_vetkd_requests.get(&[1; 32]).unwrap();
+        assert_eq!(args.ni_dkg_id, ni_dkg_id);
+        assert_eq!(
+            args.derivation_path,
+            vetkd_derivation_path(
+                contexts[&CallbackId::from(1)].request.sender.get(),
+                &contexts[&CallbackId::from(1)].key_id,
+                &contexts[&CallbackId::from(1)].derivation_path,
+            )
+        );
+
+        // A completed key turns into an unreported agreement.
+        signature_builder.vetkd_keys.insert(
+            [1; 32],
+            VetKdEncryptedKey {
+                encrypted_key: vec![8; 192],
+            },
+        );
+        update_vetkd_requests(
+            &contexts,
+            Some(ni_dkg_id),
+            None,
+            &signature_builder,
+            &mut ecdsa_payload,
+            None,
+        );
+        assert_eq!(ecdsa_payload.ongoing_vetkd_requests.len(), 1);
+        match ecdsa_payload.vetkd_agreements.get(&[1; 32]) {
+            Some(ecdsa::CompletedSignature::Unreported(response)) => {
+                assert_eq!(response.originator_reply_callback, CallbackId::from(1));
+                assert_eq!(
+                    response.response_payload,
+                    ic_types::messages::Payload::Data(
+                        VetKdEncryptedKeyResult {
+                            encrypted_key: vec![8; 192],
+                        }
+                        .encode()
+                    )
+                );
+            }
+            other => panic!("Unexpected agreement: {:?}", other),
+        }
+
+        // The agreement is only delivered once, and dropped together with
+        // its context. The remaining request expires.
+        contexts.remove(&CallbackId::from(1));
+        update_vetkd_requests(
+            &contexts,
+            Some(ni_dkg_id),
+            Some(mock_time() + Duration::from_secs(1)),
+            &signature_builder,
+            &mut ecdsa_payload,
+            None,
+        );
+        assert!(ecdsa_payload.ongoing_vetkd_requests.is_empty());
+        assert_eq!(ecdsa_payload.vetkd_agreements.len(), 1);
+        assert!(matches!(
+            ecdsa_payload.vetkd_agreements.get(&[2; 32]),
+            Some(ecdsa::CompletedSignature::Unreported(
+                ic_types::messages::Response {
+                    response_payload: ic_types::messages::Payload::Reject(_),
+                    ..
+                }
+            ))
+        ));
+        update_vetkd_requests(
+            &contexts,
+            Some(ni_dkg_id),
+            Some(mock_time() + Duration::from_secs(1)),
+            &signature_builder,
+            &mut ecdsa_payload,
+            None,
+        );
+        assert!(matches!(
+            ecdsa_payload.vetkd_agreements.get(&[2; 32]),
+            Some(ecdsa::CompletedSignature::ReportedToExecution)
+        ));
+    }
+
     #[test]
     fn test_ecdsa_update_quadruples_in_creation() {
         let num_of_nodes = 4;
@@ -3906,6 +4522,7 @@ mod tests {
                 Height::from(2),
                 mock_time(),
                 &ecdsa_config,
+                false,
                 registry_version,
                 &node_ids,
                 &BTreeMap::default(),
@@ -42,13 +42,16 @@ use ic_types::{
         ecdsa::{EcdsaBlockReader, TranscriptRef},
         Block, BlockPayload, HasHeight,
     },
-    crypto::canister_threshold_sig::{
-        error::{
-            IDkgVerifyInitialDealingsError, IDkgVerifyTranscriptError,
-            ThresholdEcdsaVerifyCombinedSignatureError,
+    crypto::{
+        canister_threshold_sig::{
+            error::{
+                IDkgVerifyInitialDealingsError, IDkgVerifyTranscriptError,
+                ThresholdEcdsaVerifyCombinedSignatureError,
+            },
+            idkg::{IDkgTranscript, IDkgTranscriptId, InitialIDkgDealings, SignedIDkgDealing},
+            ThresholdEcdsaCombinedSignature,
         },
-        idkg::{IDkgTranscript, IDkgTranscriptId, InitialIDkgDealings, SignedIDkgDealing},
-        ThresholdEcdsaCombinedSignature,
+        vetkd::{VetKdEncryptedKey, VetKdKeyVerificationError},
     },
     registry::RegistryClientError,
     Height, RegistryVersion, SubnetId,
@@ -75,6 +78,7 @@ pub enum PermanentError {
     ThresholdEcdsaSigInputsError(ecdsa::ThresholdEcdsaSigInputsError),
     TranscriptParamsError(ecdsa::TranscriptParamsError),
     ThresholdEcdsaVerifyCombinedSignatureError(ThresholdEcdsaVerifyCombinedSignatureError),
+    VetKdKeyVerificationError(VetKdKeyVerificationError),
     IDkgVerifyTranscriptError(IDkgVerifyTranscriptError),
     IDkgVerifyInitialDealingsError(IDkgVerifyInitialDealingsError),
     MegaKeyFromRegistryError(MegaKeyFromRegistryError),
@@ -93,6 +97,8 @@ pub enum PermanentError {
     NewTranscriptHeightMismatch(IDkgTranscriptId),
     NewSignatureUnexpected(ecdsa::PseudoRandomId),
     NewSignatureMissingInput(ecdsa::PseudoRandomId),
+    NewVetKdKeyUnexpected(ecdsa::PseudoRandomId),
+    NewVetKdKeyMissingInput(ecdsa::PseudoRandomId),
     XNetReshareAgreementWithoutRequest(ecdsa::EcdsaReshareRequest),
     XNetReshareRequestDisappeared(ecdsa::EcdsaReshareRequest),
     DecodingError(String),
@@ -373,11 +379,17 @@ pub fn validate_data_payload(
         || validate_new_signature_agreements(crypto, &block_reader, &prev_payload, curr_payload),
         metrics,
     )?;
+    let vetkd_keys = timed_call(
+        "validate_new_vetkd_agreements",
+        || validate_new_vetkd_agreements(crypto, &prev_payload, curr_payload),
+        metrics,
+    )?;
 
     let builder = CachedBuilder {
         transcripts,
         dealings,
         signatures,
+        vetkd_keys,
     };
 
     let ecdsa_payload = create_data_payload_helper(
@@ -406,6 +418,7 @@ struct CachedBuilder {
     transcripts: BTreeMap<IDkgTranscriptId, IDkgTranscript>,
     dealings: BTreeMap<IDkgTranscriptId, Vec<SignedIDkgDealing>>,
     signatures: BTreeMap<ecdsa::PseudoRandomId, ThresholdEcdsaCombinedSignature>,
+    vetkd_keys: BTreeMap<ecdsa::PseudoRandomId, VetKdEncryptedKey>,
 }
 
 impl EcdsaTranscriptBuilder for CachedBuilder {
@@ -428,6 +441,13 @@ impl EcdsaSignatureBuilder for CachedBuilder {
     ) -> Option<ThresholdEcdsaCombinedSignature> {
         self.signatures.get(&request_id.pseudo_random_id).cloned()
     }
+
+    fn get_completed_vetkd_key(
+        &self,
+        pseudo_random_id: &ecdsa::PseudoRandomId,
+    ) -> Option<VetKdEncryptedKey> {
+        self.vetkd_keys.get(pseudo_random_id).cloned()
+    }
 }
 
 // Validate transcript references
@@ -575,6 +595,42 @@ fn validate_new_signature_agreements(
     Ok(new_signatures)
 }
 
+// Validate new vetKD agreements in the current payload.
+// - New encrypted keys must belong to an ongoing request in the parent payload.
+// - New encrypted keys must verify against the arguments of that request.
+fn validate_new_vetkd_agreements(
+    crypto: &dyn ConsensusCrypto,
+    prev_payload: &ecdsa::EcdsaPayload,
+    curr_payload: &ecdsa::EcdsaPayload,
+) -> Result<BTreeMap<ecdsa::PseudoRandomId, VetKdEncryptedKey>, EcdsaValidationError> {
+    use PermanentError::*;
+    let mut new_keys = BTreeMap::new();
+    for (random_id, completed) in curr_payload.vetkd_agreements.iter() {
+        if let ecdsa::CompletedSignature::Unreported(response) = completed {
+            if let ic_types::messages::Payload::Data(data) = &response.response_payload {
+                use ic_ic00_types::{Payload, VetKdEncryptedKeyResult};
+                let reply = VetKdEncryptedKeyResult::decode(data)
+                    .map_err(|err| PermanentError::DecodingError(format!("{:?}", err)))?;
+                let key = VetKdEncryptedKey {
+                    encrypted_key: reply.encrypted_key,
+                };
+                if prev_payload.vetkd_agreements.get(random_id).is_some() {
+                    return Err(NewVetKdKeyUnexpected(*random_id).into());
+                }
+                let args = prev_payload
+                    .ongoing_vetkd_requests
+                    .get(random_id)
+                    .ok_or(NewVetKdKeyMissingInput(*random_id))?;
+                crypto
+                    .verify_encrypted_key(&key, args)
+                    .map_err(VetKdKeyVerificationError)?;
+                new_keys.insert(*random_id, key);
+            }
+        }
+    }
+    Ok(new_keys)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -9,20 +9,22 @@ use crate::ecdsa::complaints::EcdsaTranscriptLoader;
 use crate::ecdsa::utils::{load_transcripts, EcdsaBlockReaderImpl};
 use ic_interfaces::consensus_pool::ConsensusBlockCache;
 use ic_interfaces::crypto::{
-    ErrorReproducibility, ThresholdEcdsaSigVerifier, ThresholdEcdsaSigner,
+    ErrorReproducibility, ThresholdEcdsaSigVerifier, ThresholdEcdsaSigner, VetKdProtocol,
 };
 use ic_interfaces::ecdsa::{EcdsaChangeAction, EcdsaChangeSet, EcdsaPool};
 use ic_logger::{debug, warn, ReplicaLogger};
 use ic_metrics::MetricsRegistry;
 use ic_types::artifact::EcdsaMessageId;
 use ic_types::consensus::ecdsa::{
-    sig_share_prefix, EcdsaBlockReader, EcdsaMessage, EcdsaSigShare, EcdsaStats, RequestId,
-    ThresholdEcdsaSigInputsRef,
+    sig_share_prefix, vetkd_key_share_prefix, EcdsaBlockReader, EcdsaMessage, EcdsaSigShare,
+    EcdsaStats, PseudoRandomId, RequestId, ThresholdEcdsaSigInputsRef, VetKdKeyShare,
 };
 use ic_types::crypto::canister_threshold_sig::{
     error::ThresholdEcdsaCombineSigSharesError, ThresholdEcdsaCombinedSignature,
     ThresholdEcdsaSigInputs, ThresholdEcdsaSigShare,
 };
+use ic_types::crypto::threshold_sig::ni_dkg::NiDkgId;
+use ic_types::crypto::vetkd::{VetKdArgs, VetKdEncryptedKey, VetKdKeyShareCombinationError};
 use ic_types::{Height, NodeId};
 
 use prometheus::IntCounterVec;
@@ -171,6 +173,89 @@ impl EcdsaSignerImpl {
         ret
     }
 
+    /// Generates encrypted key shares for the newly added vetKD requests.
+    fn send_vetkd_key_shares(
+        &self,
+        ecdsa_pool: &dyn EcdsaPool,
+        block_reader: &dyn EcdsaBlockReader,
+    ) -> EcdsaChangeSet {
+        block_reader
+            .requested_vetkd_keys()
+            .filter(|(pseudo_random_id, args)| {
+                !self.signer_has_issued_vetkd_key_share(
+                    ecdsa_pool,
+                    &self.node_id,
+                    pseudo_random_id,
+                    &args.ni_dkg_id,
+                )
+            })
+            .flat_map(|(pseudo_random_id, args)| {
+                self.crypto_create_vetkd_key_share(pseudo_random_id, args)
+            })
+            .collect()
+    }
+
+    /// Processes the received encrypted key shares
+    fn validate_vetkd_key_shares(
+        &self,
+        ecdsa_pool: &dyn EcdsaPool,
+        block_reader: &dyn EcdsaBlockReader,
+    ) -> EcdsaChangeSet {
+        let requested = block_reader
+            .requested_vetkd_keys()
+            .collect::<BTreeMap<_, _>>();
+
+        // Collection of validated shares
+        let mut validated_key_shares = BTreeSet::new();
+
+        let mut ret = Vec::new();
+        for (id, share) in ecdsa_pool.unvalidated().vetkd_key_shares() {
+            // Remove the duplicate entries
+            let key = (share.pseudo_random_id, share.signer_id);
+            if validated_key_shares.contains(&key) {
+                self.metrics
+                    .sign_errors_inc("duplicate_vetkd_key_shares_in_batch");
+                ret.push(EcdsaChangeAction::HandleInvalid(
+                    id,
+                    format!("Duplicate share in unvalidated batch: {}", share),
+                ));
+                continue;
+            }
+
+            match requested.get(&share.pseudo_random_id) {
+                // The share was created with an outdated transcript, drop it
+                Some(args) if args.ni_dkg_id != share.ni_dkg_id => {
+                    ret.push(EcdsaChangeAction::RemoveUnvalidated(id))
+                }
+                Some(args) => {
+                    if self.signer_has_issued_vetkd_key_share(
+                        ecdsa_pool,
+                        &share.signer_id,
+                        &share.pseudo_random_id,
+                        &share.ni_dkg_id,
+                    ) {
+                        // The node already sent a valid share for this request
+                        self.metrics.sign_errors_inc("duplicate_vetkd_key_share");
+                        ret.push(EcdsaChangeAction::HandleInvalid(
+                            id,
+                            format!("Duplicate share: {}", share),
+                        ))
+                    } else {
+                        let action = self.crypto_verify_vetkd_key_share(&id, args, &share);
+                        if let Some(EcdsaChangeAction::MoveToValidated(_)) = action {
+                            validated_key_shares.insert(key);
+                        }
+                        ret.append(&mut action.into_iter().collect());
+                    }
+                }
+                // The request is either completed, or not yet known to us.
+                // The purge below takes care of the former.
+                None => {}
+            }
+        }
+        ret
+    }
+
     /// Purges the entries no longer needed from the artifact pool
     fn purge_artifacts(
         &self,
@@ -203,6 +288,33 @@ impl EcdsaSignerImpl {
             .collect();
         ret.append(&mut action);
 
+        let vetkd_in_progress = block_reader
+            .requested_vetkd_keys()
+            .map(|(pseudo_random_id, args)| (*pseudo_random_id, args.ni_dkg_id))
+            .collect::<BTreeSet<_>>();
+
+        // Unvalidated vetKD key shares.
+        let mut action = ecdsa_pool
+            .unvalidated()
+            .vetkd_key_shares()
+            .filter(|(_, share)| {
+                self.should_purge_vetkd_key_share(share, current_height, &vetkd_in_progress)
+            })
+            .map(|(id, _)| EcdsaChangeAction::RemoveUnvalidated(id))
+            .collect();
+        ret.append(&mut action);
+
+        // Validated vetKD key shares.
+        let mut action = ecdsa_pool
+            .validated()
+            .vetkd_key_shares()
+            .filter(|(_, share)| {
+                self.should_purge_vetkd_key_share(share, current_height, &vetkd_in_progress)
+            })
+            .map(|(id, _)| EcdsaChangeAction::RemoveValidated(id))
+            .collect();
+        ret.append(&mut action);
+
         ret
     }
 
@@ -306,6 +418,114 @@ impl EcdsaSignerImpl {
         )
     }
 
+    /// Helper to create the encrypted key share
+    fn crypto_create_vetkd_key_share(
+        &self,
+        pseudo_random_id: &PseudoRandomId,
+        args: &VetKdArgs,
+    ) -> EcdsaChangeSet {
+        VetKdProtocol::create_encrypted_key_share(&*self.crypto, args.clone()).map_or_else(
+            |error| {
+                warn!(
+                    self.log,
+                    "Failed to create vetKD key share: pseudo_random_id = {}, {:?}",
+                    hex::encode(pseudo_random_id),
+                    error
+                );
+                self.metrics.sign_errors_inc("create_vetkd_key_share");
+                Default::default()
+            },
+            |share| {
+                let key_share = VetKdKeyShare {
+                    signer_id: self.node_id,
+                    pseudo_random_id: *pseudo_random_id,
+                    ni_dkg_id: args.ni_dkg_id,
+                    share,
+                };
+                self.metrics.sign_metrics_inc("vetkd_key_shares_sent");
+                vec![EcdsaChangeAction::AddToValidated(
+                    EcdsaMessage::VetKdKeyShare(key_share),
+                )]
+            },
+        )
+    }
+
+    /// Helper to verify the encrypted key share
+    fn crypto_verify_vetkd_key_share(
+        &self,
+        id: &EcdsaMessageId,
+        args: &VetKdArgs,
+        share: &VetKdKeyShare,
+    ) -> Option<EcdsaChangeAction> {
+        VetKdProtocol::verify_encrypted_key_share(
+            &*self.crypto,
+            share.signer_id,
+            &share.share,
+            args,
+        )
+        .map_or_else(
+            |error| {
+                if error.is_reproducible() {
+                    self.metrics
+                        .sign_errors_inc("verify_vetkd_key_share_permanent");
+                    Some(EcdsaChangeAction::HandleInvalid(
+                        id.clone(),
+                        format!(
+                            "Share validation(permanent error): {}, error = {:?}",
+                            share, error
+                        ),
+                    ))
+                } else {
+                    // Defer in case of transient errors
+                    debug!(
+                        self.log,
+                        "Share validation(transient error): {}, error = {:?}", share, error
+                    );
+                    self.metrics
+                        .sign_errors_inc("verify_vetkd_key_share_transient");
+                    None
+                }
+            },
+            |()| {
+                self.metrics.sign_metrics_inc("vetkd_key_shares_received");
+                Some(EcdsaChangeAction::MoveToValidated(id.clone()))
+            },
+        )
+    }
+
+    /// Checks if the signer node has already issued an encrypted key share
+    /// for the vetKD request
+    fn signer_has_issued_vetkd_key_share(
+        &self,
+        ecdsa_pool: &dyn EcdsaPool,
+        signer_id: &NodeId,
+        pseudo_random_id: &PseudoRandomId,
+        ni_dkg_id: &NiDkgId,
+    ) -> bool {
+        let prefix = vetkd_key_share_prefix(pseudo_random_id, signer_id);
+        ecdsa_pool
+            .validated()
+            .vetkd_key_shares_by_prefix(prefix)
+            .any(|(_, share)| {
+                share.pseudo_random_id == *pseudo_random_id
+                    && share.signer_id == *signer_id
+                    && share.ni_dkg_id == *ni_dkg_id
+            })
+    }
+
+    /// Checks if the encrypted key share should be purged. Shares of requests
+    /// we don't know yet are kept, unless they were created with a transcript
+    /// older than the tip.
+    fn should_purge_vetkd_key_share(
+        &self,
+        share: &VetKdKeyShare,
+        current_height: Height,
+        in_progress: &BTreeSet<(PseudoRandomId, NiDkgId)>,
+    ) -> bool {
+        share.ni_dkg_id.start_block_height <= current_height
+            && !in_progress.contains(&(share.pseudo_random_id, share.ni_dkg_id))
+    }
+
     /// Checks if the signer node has already issued a signature share for the
     /// request
     fn signer_has_issued_signature_share(
@@ -391,6 +611,21 @@ impl EcdsaSigner for EcdsaSignerImpl {
             )
         };
 
+        let send_vetkd_key_shares = || {
+            timed_call(
+                "send_vetkd_key_shares",
+                || self.send_vetkd_key_shares(ecdsa_pool, &block_reader),
+                &metrics.on_state_change_duration,
+            )
+        };
+        let validate_vetkd_key_shares = || {
+            timed_call(
+                "validate_vetkd_key_shares",
+                || self.validate_vetkd_key_shares(ecdsa_pool, &block_reader),
+                &metrics.on_state_change_duration,
+            )
+        };
+
         let purge_artifacts = || {
             timed_call(
                 "purge_artifacts",
@@ -399,9 +634,11 @@ impl EcdsaSigner for EcdsaSignerImpl {
             )
         };
 
-        let calls: [&'_ dyn Fn() -> EcdsaChangeSet; 3] = [
+        let calls: [&'_ dyn Fn() -> EcdsaChangeSet; 5] = [
             &send_signature_shares,
             &validate_signature_shares,
+            &send_vetkd_key_shares,
+            &validate_vetkd_key_shares,
             &purge_artifacts,
         ];
         self.schedule.call_next(&calls)
@@ -415,6 +652,13 @@ pub(crate) trait EcdsaSignatureBuilder {
         &self,
         request_id: &RequestId,
     ) -> Option<ThresholdEcdsaCombinedSignature>;
+
+    /// Returns the specified vetKD encrypted key if it can be successfully
+    /// combined from the current encrypted key shares in the ECDSA pool
+    fn get_completed_vetkd_key(
+        &self,
+        pseudo_random_id: &PseudoRandomId,
+    ) -> Option<VetKdEncryptedKey>;
 }
 
 pub(crate) struct EcdsaSignatureBuilderImpl<'a> {
@@ -520,6 +764,48 @@ impl<'a> EcdsaSignatureBuilder for EcdsaSignatureBuilderImpl<'a> {
             self.ecdsa_pool.stats(),
         )
     }
+
+    fn get_completed_vetkd_key(
+        &self,
+        pseudo_random_id: &PseudoRandomId,
+    ) -> Option<VetKdEncryptedKey> {
+        let (_, args) = self
+            .block_reader
+            .requested_vetkd_keys()
+            .find(|(cur_id, _)| *cur_id == pseudo_random_id)?;
+
+        // Collect the encrypted key shares for the request.
+        let mut key_shares = BTreeMap::new();
+        for (_, share) in self.ecdsa_pool.validated().vetkd_key_shares() {
+            if share.pseudo_random_id == *pseudo_random_id && share.ni_dkg_id == args.ni_dkg_id {
+                key_shares.insert(share.signer_id, share.share);
+            }
+        }
+
+        VetKdProtocol::combine_encrypted_key_shares(self.crypto, &key_shares, args).map_or_else(
+            |error| {
+                match error {
+                    VetKdKeyShareCombinationError::UnsatisfiedReconstructionThreshold {
+                        ..
+                    } => (),
+                    _ => {
+                        warn!(
+                            self.log,
+                            "Failed to combine vetKD key shares: pseudo_random_id = {}, {:?}",
+                            hex::encode(pseudo_random_id),
+                            error
+                        );
+                        self.metrics.payload_errors_inc("combine_vetkd_key_share");
+                    }
+                };
+                None
+            },
+            |key| {
+                self.metrics.payload_metrics_inc("vetkd_keys_completed");
+                Some(key)
+            },
+        )
+    }
 }
 
 /// Specifies how to handle a received share
@@ -1,18 +1,20 @@
 //! Common utils for the ECDSA implementation.
 
 use crate::ecdsa::complaints::{EcdsaTranscriptLoader, TranscriptLoadStatus};
-use ic_ic00_types::EcdsaKeyId;
+use ic_ic00_types::{EcdsaCurve, EcdsaKeyId};
 use ic_interfaces::consensus_pool::ConsensusBlockChain;
 use ic_interfaces::ecdsa::{EcdsaChangeAction, EcdsaChangeSet, EcdsaPool};
 use ic_protobuf::registry::subnet::v1 as pb;
 use ic_types::consensus::ecdsa::{EcdsaBlockReader, TranscriptRef};
 use ic_types::consensus::ecdsa::{
-    EcdsaMessage, EcdsaPayload, IDkgTranscriptParamsRef, RequestId, ThresholdEcdsaSigInputsRef,
-    TranscriptLookupError,
+    EcdsaMessage, EcdsaPayload, IDkgTranscriptParamsRef, PseudoRandomId, RequestId,
+    ThresholdEcdsaSigInputsRef, TranscriptLookupError,
 };
 use ic_types::crypto::canister_threshold_sig::idkg::{
     IDkgTranscript, IDkgTranscriptOperation, InitialIDkgDealings,
 };
+use ic_types::crypto::vetkd::VetKdArgs;
+use ic_types::crypto::AlgorithmId;
 use ic_types::Height;
 use std::collections::BTreeSet;
 use std::convert::TryInto;
@@ -58,6 +60,14 @@ impl EcdsaBlockReader for EcdsaBlockReaderImpl {
             })
     }
 
+    fn requested_vetkd_keys(&self) -> Box<dyn Iterator<Item = (&PseudoRandomId, &VetKdArgs)> + '_> {
+        self.tip_ecdsa_payload
+            .as_ref()
+            .map_or(Box::new(std::iter::empty()), |payload| {
+                Box::new(payload.ongoing_vetkd_requests.iter())
+            })
+    }
+
     fn active_transcripts(&self) -> BTreeSet<TranscriptRef> {
         self.tip_ecdsa_payload
             .as_ref()
@@ -158,6 +168,14 @@ pub(crate) fn transcript_op_summary(op: &IDkgTranscriptOperation) -> String {
     }
 }
 
+/// The IDKG algorithm used for the transcripts of the given ECDSA key.
+pub(crate) fn algorithm_for_key_id(key_id: &EcdsaKeyId) -> AlgorithmId {
+    match key_id.curve {
+        EcdsaCurve::Secp256k1 => AlgorithmId::ThresholdEcdsaSecp256k1,
+        EcdsaCurve::Secp256r1 => AlgorithmId::ThresholdEcdsaSecp256r1,
+    }
+}
+
 /// Inspect ecdsa_initializations field in the CUPContent.
 /// Return key_id and dealings.
 pub(crate) fn inspect_ecdsa_initializations(
@@ -230,9 +248,9 @@ pub(crate) mod test_utils {
         EcdsaBlockReader, EcdsaComplaint, EcdsaComplaintContent, EcdsaKeyTranscript, EcdsaMessage,
         EcdsaOpening, EcdsaOpeningContent, EcdsaPayload, EcdsaReshareRequest, EcdsaSigShare,
         EcdsaUIDGenerator, IDkgTranscriptAttributes, IDkgTranscriptParamsRef,
-        KeyTranscriptCreation, MaskedTranscript, PreSignatureQuadrupleRef, RequestId,
-        ReshareOfMaskedParams, ThresholdEcdsaSigInputsRef, TranscriptLookupError, TranscriptRef,
-        UnmaskedTranscript,
+        KeyTranscriptCreation, MaskedTranscript, PreSignatureQuadrupleRef, PseudoRandomId,
+        RequestId, ReshareOfMaskedParams, ThresholdEcdsaSigInputsRef, TranscriptLookupError,
+        TranscriptRef, UnmaskedTranscript,
     };
     use ic_types::crypto::canister_threshold_sig::idkg::{
         IDkgComplaint, IDkgDealing, IDkgDealingSupport, IDkgMaskedTranscriptOrigin, IDkgOpening,
@@ -242,6 +260,7 @@ pub(crate) mod test_utils {
     use ic_types::crypto::canister_threshold_sig::{
         ExtendedDerivationPath, ThresholdEcdsaCombinedSignature, ThresholdEcdsaSigShare,
     };
+    use ic_types::crypto::vetkd::{VetKdArgs, VetKdEncryptedKey};
     use ic_types::crypto::AlgorithmId;
     use ic_types::malicious_behaviour::MaliciousBehaviour;
     use ic_types::signature::*;
@@ -282,6 +301,7 @@ pub(crate) mod test_utils {
         source_subnet_xnet_transcripts: Vec<IDkgTranscriptParamsRef>,
         target_subnet_xnet_transcripts: Vec<IDkgTranscriptParamsRef>,
         requested_signatures: Vec<(RequestId, ThresholdEcdsaSigInputsRef)>,
+        requested_vetkd_keys: Vec<(PseudoRandomId, VetKdArgs)>,
         idkg_transcripts: BTreeMap<TranscriptRef, IDkgTranscript>,
     }
 
@@ -364,6 +384,14 @@ pub(crate) mod test_utils {
             self
         }
 
+        pub(crate) fn with_requested_vetkd_keys(
+            mut self,
+            requested_vetkd_keys: Vec<(PseudoRandomId, VetKdArgs)>,
+        ) -> Self {
+            self.requested_vetkd_keys = requested_vetkd_keys;
+            self
+        }
+
         pub(crate) fn without_idkg_transcripts(mut self) -> Self {
             self.idkg_transcripts = BTreeMap::new();
             self
@@ -397,6 +425,12 @@ pub(crate) mod test_utils {
             )
         }
 
+        fn requested_vetkd_keys(
+            &self,
+        ) -> Box<dyn Iterator<Item = (&PseudoRandomId, &VetKdArgs)> + '_> {
+            Box::new(self.requested_vetkd_keys.iter().map(|(id, args)| (id, args)))
+        }
+
         fn source_subnet_xnet_transcripts(
             &self,
         ) -> Box<dyn Iterator<Item = &IDkgTranscriptParamsRef> + '_> {
@@ -546,12 +580,14 @@ pub(crate) mod test_utils {
 
     pub(crate) struct TestEcdsaSignatureBuilder {
         pub(crate) signatures: BTreeMap<RequestId, ThresholdEcdsaCombinedSignature>,
+        pub(crate) vetkd_keys: BTreeMap<PseudoRandomId, VetKdEncryptedKey>,
     }
 
     impl TestEcdsaSignatureBuilder {
         pub(crate) fn new() -> Self {
             Self {
                 signatures: BTreeMap::new(),
+                vetkd_keys: BTreeMap::new(),
             }
         }
     }
@@ -563,6 +599,13 @@ pub(crate) mod test_utils {
         ) -> Option<ThresholdEcdsaCombinedSignature> {
             self.signatures.get(request_id).cloned()
         }
+
+        fn get_completed_vetkd_key(
+            &self,
+            pseudo_random_id: &PseudoRandomId,
+        ) -> Option<VetKdEncryptedKey> {
+            self.vetkd_keys.get(pseudo_random_id).cloned()
+        }
     }
 
     // Sets up the dependencies and creates the pre signer
@@ -1256,6 +1299,8 @@ pub(crate) mod test_utils {
             idkg_transcripts: BTreeMap::new(),
             ongoing_xnet_reshares: BTreeMap::new(),
             xnet_reshare_agreements: BTreeMap::new(),
+            ongoing_vetkd_requests: BTreeMap::new(),
+            vetkd_agreements: BTreeMap::new(),
             key_transcript: EcdsaKeyTranscript {
                 current: None,
                 next_in_creation: KeyTranscriptCreation::Begin,
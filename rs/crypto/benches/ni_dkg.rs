@@ -208,6 +208,7 @@ impl TestCase {
         let tag_name = match self.dkg_tag {
             NiDkgTag::LowThreshold => "low",
             NiDkgTag::HighThreshold => "high",
+            NiDkgTag::VetKd => "vetkd",
         };
         format!(
             "crypto_nidkg_{}_nodes_{}_dealers_{}",
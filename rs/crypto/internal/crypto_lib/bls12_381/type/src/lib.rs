@@ -237,6 +237,33 @@ impl Scalar {
         }
     }
 
+    /// Hash into the scalar field
+    ///
+    /// This follows the hash_to_field construction of
+    /// draft-irtf-cfrg-hash-to-curve-16 using expand_message_xmd with
+    /// SHA-256, producing a single field element.
+    ///
+    /// # Arguments
+    /// * `domain_sep` - some protocol specific domain seperator
+    /// * `input` - the input which will be hashed
+    pub fn hash(domain_sep: &[u8], input: &[u8]) -> Self {
+        // L = ceil((ceil(log2(p)) + k) / 8) = ceil((255 + 128) / 8) = 48
+        const HASH_TO_FIELD_BYTES: usize = 48;
+
+        let mut be_bytes = expand_message_xmd(input, domain_sep, HASH_TO_FIELD_BYTES);
+
+        let mut le_bytes = [0u8; 64];
+        for i in 0..HASH_TO_FIELD_BYTES {
+            le_bytes[i] = be_bytes[HASH_TO_FIELD_BYTES - i - 1];
+        }
+        // le_bytes[48..64] left as zero
+
+        let s = ic_bls12_381::Scalar::from_bytes_wide(&le_bytes);
+        be_bytes.zeroize();
+        le_bytes.zeroize();
+        Self::new(s)
+    }
+
     /// Return several random scalars
     pub fn batch_random<R: RngCore + CryptoRng>(rng: &mut R, count: usize) -> Vec<Self> {
         let mut result = Vec::with_capacity(count);
@@ -1851,6 +1878,59 @@ impl From<G2Projective> for G2Prepared {
     }
 }
 
+/// expand_message_xmd from draft-irtf-cfrg-hash-to-curve-16 using SHA-256
+///
+/// # Panics
+/// * If `domain_sep` is longer than 255 bytes
+/// * If more than 8160 bytes of output are requested
+fn expand_message_xmd(msg: &[u8], domain_sep: &[u8], len_in_bytes: usize) -> Vec<u8> {
+    use sha2::{Digest, Sha256};
+
+    const B_IN_BYTES: usize = 32;
+    const R_IN_BYTES: usize = 64;
+
+    let ell = (len_in_bytes + B_IN_BYTES - 1) / B_IN_BYTES;
+    assert!(ell <= 255, "expand_message_xmd output too long");
+    assert!(domain_sep.len() <= 255, "expand_message_xmd DST too long");
+
+    let dst_len = [domain_sep.len() as u8];
+
+    let b_0 = Sha256::new()
+        .chain([0u8; R_IN_BYTES])
+        .chain(msg)
+        .chain((len_in_bytes as u16).to_be_bytes())
+        .chain([0u8])
+        .chain(domain_sep)
+        .chain(dst_len)
+        .finalize();
+
+    let mut output = Vec::with_capacity(ell * B_IN_BYTES);
+    let mut b_i = Sha256::new()
+        .chain(b_0)
+        .chain([1u8])
+        .chain(domain_sep)
+        .chain(dst_len)
+        .finalize();
+    output.extend_from_slice(&b_i);
+
+    for i in 2..=ell {
+        let mut xored = [0u8; B_IN_BYTES];
+        for j in 0..B_IN_BYTES {
+            xored[j] = b_0[j] ^ b_i[j];
+        }
+        b_i = Sha256::new()
+            .chain(xored)
+            .chain([i as u8])
+            .chain(domain_sep)
+            .chain(dst_len)
+            .finalize();
+        output.extend_from_slice(&b_i);
+    }
+
+    output.truncate(len_in_bytes);
+    output
+}
+
 /// Perform BLS signature verification
 ///
 /// The naive version of this function requires two pairings, but it
@@ -29,3 +29,25 @@ macro_rules! window_extraction_works_correctly_init {
 
 // initialize the test for all input bit-lengths
 window_extraction_works_correctly_init![1, 2, 3, 4, 5, 6, 7, 8];
+
+#[test]
+fn expand_message_xmd_matches_draft_test_vectors() {
+    // Test vectors from draft-irtf-cfrg-hash-to-curve-16, appendix K.1
+    let dst = b"QUUX-V01-CS02-with-expander-SHA256-128";
+
+    assert_eq!(
+        hex::encode(crate::expand_message_xmd(b"", dst, 0x20)),
+        "68a985b87eb6b46952128911f2a4412bbc302a9d759667f87f7a21d803f07235"
+    );
+    assert_eq!(
+        hex::encode(crate::expand_message_xmd(b"abc", dst, 0x20)),
+        "d8ccab23b5985ccea865c6c97b6e5b8350e794e603b4b97902f53a8a0d605615"
+    );
+    assert_eq!(
+        hex::encode(crate::expand_message_xmd(b"", dst, 0x80)),
+        "af84c27ccfd45d41914fdff5df25293e221afc53d8ad2ac06d5e3e29485dadbe\
+         e0d121587713a3e0dd4d5e69e93eb7cd4f5df4cd103e188cf60cb02edc3edf18\
+         eda8576c412b18ffb658e3dd6ec849469b979d444cf7b26911a08e63cf31f9dc\
+         c541708d3491184472c2c29bb749d4286b004ceb5ee6b9a7fa5b646c993f0ced"
+    );
+}
@@ -326,6 +326,22 @@ fn test_scalar_inverse() {
     }
 }
 
+#[test]
+fn test_scalar_hash_is_expected_value() {
+    scalar_test_encoding(
+        Scalar::hash(b"test-dst", b"input"),
+        "1672221177e05a2a24e6f4d934a3557037f7b160ab1d8974f477c6fbfc817432",
+    );
+}
+
+#[test]
+fn test_scalar_hash_separates_domains_and_inputs() {
+    let s = Scalar::hash(b"dst", b"input");
+    assert_eq!(s, Scalar::hash(b"dst", b"input"));
+    assert_ne!(s, Scalar::hash(b"dst2", b"input"));
+    assert_ne!(s, Scalar::hash(b"dst", b"input2"));
+}
+
 #[test]
 fn test_impl_debugs() {
     assert_eq!(
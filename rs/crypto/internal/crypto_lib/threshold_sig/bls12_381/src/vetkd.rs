@@ -0,0 +1,463 @@
+//! Verifiably encrypted threshold key derivation (vetKD)
+//!
+//! A subnet holds a threshold-shared master secret key `s` with master
+//! public key `mpk = g2*s`. For a caller (e.g. a canister) and a derivation
+//! path, the derived secret key is `s + delta` and the derived public key is
+//! `dpk = mpk + g2*delta`, where `delta` is obtained by hashing the caller and
+//! the derivation path.
+//!
+//! The key that is derived for a derivation id `did` is the BLS signature
+//! `k = H(dpk || did)*(s + delta)`. It is never revealed in the clear to the
+//! nodes: each node encrypts its share of `k` under a transport public key
+//! `tpk = g1*tsk` chosen by the recipient, using a randomized ElGamal-like
+//! encryption that is additively homomorphic. This allows combining a
+//! threshold number of encrypted key shares into an encryption of `k`, which
+//! only the holder of the transport secret key `tsk` can decrypt.
+//!
+//! Both encrypted key shares and combined encrypted keys are publicly
+//! verifiable using pairings.
+
+use crate::types::{PublicCoefficients, SecretKey, SecretKeyBytes};
+use ic_crypto_internal_bls12_381_type::{
+    verify_bls_signature, G1Affine, G1Projective, G2Affine, G2Prepared, G2Projective, Gt, Scalar,
+};
+use ic_types::NodeIndex;
+use rand::{CryptoRng, RngCore};
+use std::collections::BTreeMap;
+
+#[cfg(test)]
+mod tests;
+
+const DOMAIN_SEP_DERIVATION_PATH: &[u8; 32] = b"ic-vetkd-bls12-381-g2-derivation";
+const DOMAIN_SEP_DERIVED_KEY_INPUT: &[u8; 36] = b"ic-vetkd-bls12-381-g2-derived-key-in";
+
+/// A derivation path: the caller and an additional list of byte strings
+///
+/// The derivation path is mapped to a scalar offset that is added to the
+/// master secret key.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DerivationPath {
+    delta: Scalar,
+}
+
+impl DerivationPath {
+    /// Create a derivation path for `caller` with the extra path elements `path`
+    pub fn new(caller: &[u8], path: &[Vec<u8>]) -> Self {
+        // Each element is length-prefixed so that distinct paths are
+        // encoded as distinct byte strings
+        let mut encoding = Vec::with_capacity(
+            8 * (path.len() + 2) + caller.len() + path.iter().map(|p| p.len()).sum::<usize>(),
+        );
+        encoding.extend_from_slice(&(caller.len() as u64).to_be_bytes());
+        encoding.extend_from_slice(caller);
+        encoding.extend_from_slice(&(path.len() as u64).to_be_bytes());
+        for elem in path {
+            encoding.extend_from_slice(&(elem.len() as u64).to_be_bytes());
+            encoding.extend_from_slice(elem);
+        }
+
+        Self {
+            delta: Scalar::hash(&DOMAIN_SEP_DERIVATION_PATH[..], &encoding),
+        }
+    }
+
+    fn delta(&self) -> &Scalar {
+        &self.delta
+    }
+}
+
+/// Derives the public key for `derivation_path` from `master_public_key`
+pub fn derive_public_key(
+    master_public_key: &G2Affine,
+    derivation_path: &DerivationPath,
+) -> G2Affine {
+    (G2Projective::from(master_public_key) + G2Affine::generator() * derivation_path.delta())
+        .to_affine()
+}
+
+/// Hashes the derived public key and the derivation id to the message that
+/// the derived key is a BLS signature on
+fn derived_key_message(derived_public_key: &G2Affine, derivation_id: &[u8]) -> G1Affine {
+    let mut input = Vec::with_capacity(G2Affine::BYTES + derivation_id.len());
+    input.extend_from_slice(&derived_public_key.serialize());
+    input.extend_from_slice(derivation_id);
+    G1Affine::hash(&DOMAIN_SEP_DERIVED_KEY_INPUT[..], &input)
+}
+
+/// Checks `e(c1, g2) == e(g1, c2)`, i.e. that both ciphertext randomizers
+/// use the same discrete logarithm
+fn randomizers_are_consistent(c1: &G1Affine, c2: &G2Affine) -> bool {
+    let c2_prepared = G2Prepared::from(c2);
+    Gt::multipairing(&[
+        (c1, G2Prepared::neg_generator()),
+        (G1Affine::generator(), &c2_prepared),
+    ])
+    .is_identity()
+}
+
+/// Checks `e(c3, g2) == e(tpk, c2) * e(msg, pk)`
+fn payload_is_consistent(
+    c2: &G2Affine,
+    c3: &G1Affine,
+    transport_public_key: &TransportPublicKey,
+    msg: &G1Affine,
+    public_key: &G2Affine,
+) -> bool {
+    let c2_prepared = G2Prepared::from(c2);
+    let pk_prepared = G2Prepared::from(public_key);
+    let neg_c3 = c3.neg();
+    Gt::multipairing(&[
+        (&neg_c3, G2Prepared::generator()),
+        (transport_public_key.point(), &c2_prepared),
+        (msg, &pk_prepared),
+    ])
+    .is_identity()
+}
+
+/// Error while deserializing a transport public key
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TransportPublicKeyDeserializationError {
+    /// The key is not a valid encoding of a point in G1
+    InvalidPublicKey,
+}
+
+/// A transport public key `tpk = g1*tsk`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TransportPublicKey {
+    pt: G1Affine,
+}
+
+impl TransportPublicKey {
+    /// The length of the serialized encoding of this type
+    pub const BYTES: usize = G1Affine::BYTES;
+
+    /// Deserialize a transport public key
+    ///
+    /// The identity element is rejected, since encrypting to it would reveal
+    /// the derived key.
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, TransportPublicKeyDeserializationError> {
+        let pt = G1Affine::deserialize(&bytes)
+            .map_err(|_| TransportPublicKeyDeserializationError::InvalidPublicKey)?;
+        if pt.is_identity() {
+            return Err(TransportPublicKeyDeserializationError::InvalidPublicKey);
+        }
+        Ok(Self { pt })
+    }
+
+    /// Serialize this transport public key
+    pub fn serialize(&self) -> [u8; Self::BYTES] {
+        self.pt.serialize()
+    }
+
+    fn point(&self) -> &G1Affine {
+        &self.pt
+    }
+}
+
+/// A transport secret key, used by the recipient to decrypt an encrypted key
+///
+/// Nodes never hold a transport secret key. This type is intended for use by
+/// clients and in tests.
+pub struct TransportSecretKey {
+    sk: Scalar,
+}
+
+impl TransportSecretKey {
+    /// Generate a new random transport secret key
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        Self {
+            sk: Scalar::random(rng),
+        }
+    }
+
+    /// Return the public key corresponding to this secret key
+    pub fn public_key(&self) -> TransportPublicKey {
+        TransportPublicKey {
+            pt: (G1Affine::generator() * &self.sk).to_affine(),
+        }
+    }
+
+    /// Decrypt an encrypted key
+    ///
+    /// Returns `None` if the encrypted key is invalid or if the decrypted key
+    /// is not a valid BLS signature under the derived public key, e.g.
+    /// because the key was encrypted to a different transport public key.
+    pub fn decrypt(
+        &self,
+        encrypted_key: &EncryptedKey,
+        master_public_key: &G2Affine,
+        derivation_path: &DerivationPath,
+        derivation_id: &[u8],
+    ) -> Option<G1Affine> {
+        if !randomizers_are_consistent(&encrypted_key.c1, &encrypted_key.c2) {
+            return None;
+        }
+
+        // k = c3 - c1*tsk
+        let key = (G1Projective::from(&encrypted_key.c3) - &encrypted_key.c1 * &self.sk)
+            .to_affine();
+
+        let derived_public_key = derive_public_key(master_public_key, derivation_path);
+        let msg = derived_key_message(&derived_public_key, derivation_id);
+        if verify_bls_signature(&key, &derived_public_key, &msg) {
+            Some(key)
+        } else {
+            None
+        }
+    }
+}
+
+/// Error while deserializing an encrypted key or an encrypted key share
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum EncryptedKeyDeserializationError {
+    /// The encoding has the wrong length
+    InvalidLength,
+    /// One of the components is not a valid encoding of a group element
+    InvalidComponent,
+}
+
+/// Serialize the three ciphertext components as `c1 || c2 || c3`
+fn serialize_ciphertext(c1: &G1Affine, c2: &G2Affine, c3: &G1Affine) -> [u8; CIPHERTEXT_BYTES] {
+    let mut output = [0u8; CIPHERTEXT_BYTES];
+    output[..G1Affine::BYTES].copy_from_slice(&c1.serialize());
+    output[G1Affine::BYTES..G1Affine::BYTES + G2Affine::BYTES].copy_from_slice(&c2.serialize());
+    output[G1Affine::BYTES + G2Affine::BYTES..].copy_from_slice(&c3.serialize());
+    output
+}
+
+fn deserialize_ciphertext(
+    bytes: &[u8],
+) -> Result<(G1Affine, G2Affine, G1Affine), EncryptedKeyDeserializationError> {
+    if bytes.len() != CIPHERTEXT_BYTES {
+        return Err(EncryptedKeyDeserializationError::InvalidLength);
+    }
+    let c2_end = G1Affine::BYTES + G2Affine::BYTES;
+    let c1 = G1Affine::deserialize(&&bytes[..G1Affine::BYTES]);
+    let c2 = G2Affine::deserialize(&&bytes[G1Affine::BYTES..c2_end]);
+    let c3 = G1Affine::deserialize(&&bytes[c2_end..]);
+    match (c1, c2, c3) {
+        (Ok(c1), Ok(c2), Ok(c3)) => Ok((c1, c2, c3)),
+        _ => Err(EncryptedKeyDeserializationError::InvalidComponent),
+    }
+}
+
+const CIPHERTEXT_BYTES: usize = 2 * G1Affine::BYTES + G2Affine::BYTES;
+
+/// Error while creating an encrypted key share
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum EncryptedKeyShareCreationError {
+    /// The secret key share is not a valid encoding of a scalar
+    MalformedSecretKeyShare,
+}
+
+/// A node's share of an encrypted key
+///
+/// The ciphertext is `(c1, c2, c3) = (g1*r, g2*r, tpk*r + H(dpk || did)*(s_i + delta))`
+/// where `s_i` is the node's share of the master secret key.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EncryptedKeyShare {
+    c1: G1Affine,
+    c2: G2Affine,
+    c3: G1Affine,
+}
+
+impl EncryptedKeyShare {
+    /// The length of the serialized encoding of this type
+    pub const BYTES: usize = CIPHERTEXT_BYTES;
+
+    /// Create an encrypted key share
+    ///
+    /// # Arguments
+    /// * `master_public_key` - the public key of the threshold-shared master key
+    /// * `master_secret_key_share` - this node's share `s_i` of the master secret key
+    /// * `transport_public_key` - the public key to encrypt the share to
+    /// * `derivation_path` - the derivation path of the derived key
+    /// * `derivation_id` - the id of the key to derive
+    ///
+    /// # Errors
+    /// * `EncryptedKeyShareCreationError::MalformedSecretKeyShare` if the
+    ///   secret key share is not a valid scalar
+    pub fn create<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        master_public_key: &G2Affine,
+        master_secret_key_share: &SecretKeyBytes,
+        transport_public_key: &TransportPublicKey,
+        derivation_path: &DerivationPath,
+        derivation_id: &[u8],
+    ) -> Result<Self, EncryptedKeyShareCreationError> {
+        let master_secret_key_share = SecretKey::try_from(master_secret_key_share)
+            .map_err(|_| EncryptedKeyShareCreationError::MalformedSecretKeyShare)?;
+        let derived_public_key = derive_public_key(master_public_key, derivation_path);
+        let msg = derived_key_message(&derived_public_key, derivation_id);
+
+        let r = Scalar::random(rng);
+        let derived_secret_key_share = &master_secret_key_share + derivation_path.delta();
+
+        let c1 = (G1Affine::generator() * &r).to_affine();
+        let c2 = (G2Affine::generator() * &r).to_affine();
+        let c3 = (transport_public_key.point() * &r + &msg * &derived_secret_key_share).to_affine();
+
+        Ok(Self { c1, c2, c3 })
+    }
+
+    /// Verify this encrypted key share
+    ///
+    /// # Arguments
+    /// * `master_public_key` - the public key of the threshold-shared master key
+    /// * `master_public_key_share` - the public key of the creator's share of
+    ///   the master key, i.e., `g2*s_i`
+    /// * `transport_public_key` - the public key the share was encrypted to
+    /// * `derivation_path` - the derivation path of the derived key
+    /// * `derivation_id` - the id of the derived key
+    pub fn is_valid(
+        &self,
+        master_public_key: &G2Affine,
+        master_public_key_share: &G2Affine,
+        transport_public_key: &TransportPublicKey,
+        derivation_path: &DerivationPath,
+        derivation_id: &[u8],
+    ) -> bool {
+        let derived_public_key = derive_public_key(master_public_key, derivation_path);
+        let msg = derived_key_message(&derived_public_key, derivation_id);
+        let derived_public_key_share = derive_public_key(master_public_key_share, derivation_path);
+
+        randomizers_are_consistent(&self.c1, &self.c2)
+            && payload_is_consistent(
+                &self.c2,
+                &self.c3,
+                transport_public_key,
+                &msg,
+                &derived_public_key_share,
+            )
+    }
+
+    /// Serialize this encrypted key share
+    pub fn serialize(&self) -> [u8; Self::BYTES] {
+        serialize_ciphertext(&self.c1, &self.c2, &self.c3)
+    }
+
+    /// Deserialize an encrypted key share
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, EncryptedKeyDeserializationError> {
+        let (c1, c2, c3) = deserialize_ciphertext(bytes)?;
+        Ok(Self { c1, c2, c3 })
+    }
+}
+
+/// Error while combining encrypted key shares
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EncryptedKeyCombinationError {
+    /// Fewer shares than the reconstruction threshold were provided
+    InsufficientShares {
+        /// The number of shares provided
+        num_shares: usize,
+        /// The reconstruction threshold
+        threshold: usize,
+    },
+    /// Interpolation failed, e.g. because of duplicate node indices
+    InterpolationFailed,
+    /// The combined encrypted key is invalid, which implies that at least
+    /// one of the shares was invalid
+    InvalidShares,
+}
+
+/// An encrypted key, i.e., the combination of a threshold number of
+/// encrypted key shares
+///
+/// The ciphertext is `(c1, c2, c3) = (g1*r, g2*r, tpk*r + H(dpk || did)*(s + delta))`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EncryptedKey {
+    c1: G1Affine,
+    c2: G2Affine,
+    c3: G1Affine,
+}
+
+impl EncryptedKey {
+    /// The length of the serialized encoding of this type
+    pub const BYTES: usize = CIPHERTEXT_BYTES;
+
+    /// Combine encrypted key shares
+    ///
+    /// The first `reconstruction_threshold` shares (in the order of the node
+    /// indices) are combined. The result is verified, so that an error is
+    /// returned if any of the combined shares was invalid.
+    pub fn combine(
+        shares: &BTreeMap<NodeIndex, EncryptedKeyShare>,
+        reconstruction_threshold: usize,
+        master_public_key: &G2Affine,
+        transport_public_key: &TransportPublicKey,
+        derivation_path: &DerivationPath,
+        derivation_id: &[u8],
+    ) -> Result<Self, EncryptedKeyCombinationError> {
+        if shares.len() < reconstruction_threshold {
+            return Err(EncryptedKeyCombinationError::InsufficientShares {
+                num_shares: shares.len(),
+                threshold: reconstruction_threshold,
+            });
+        }
+
+        let shares: Vec<_> = shares.iter().take(reconstruction_threshold).collect();
+        let x: Vec<Scalar> = shares
+            .iter()
+            .map(|(index, _)| crate::crypto::x_for_index(**index))
+            .collect();
+        let coefficients = PublicCoefficients::lagrange_coefficients_at_zero(&x)
+            .map_err(|_| EncryptedKeyCombinationError::InterpolationFailed)?;
+
+        let c1: Vec<G1Affine> = shares.iter().map(|(_, share)| share.c1.clone()).collect();
+        let c2: Vec<G2Affine> = shares.iter().map(|(_, share)| share.c2.clone()).collect();
+        let c3: Vec<G1Affine> = shares.iter().map(|(_, share)| share.c3.clone()).collect();
+
+        let encrypted_key = Self {
+            c1: G1Projective::muln_affine_vartime(&c1, &coefficients).to_affine(),
+            c2: G2Projective::muln_affine_vartime(&c2, &coefficients).to_affine(),
+            c3: G1Projective::muln_affine_vartime(&c3, &coefficients).to_affine(),
+        };
+
+        if encrypted_key.is_valid(
+            master_public_key,
+            transport_public_key,
+            derivation_path,
+            derivation_id,
+        ) {
+            Ok(encrypted_key)
+        } else {
+            Err(EncryptedKeyCombinationError::InvalidShares)
+        }
+    }
+
+    /// Verify this encrypted key
+    ///
+    /// This checks that the encrypted key is an encryption, to
+    /// `transport_public_key`, of the key derived from `master_public_key`
+    /// for `derivation_path` and `derivation_id`.
+    pub fn is_valid(
+        &self,
+        master_public_key: &G2Affine,
+        transport_public_key: &TransportPublicKey,
+        derivation_path: &DerivationPath,
+        derivation_id: &[u8],
+    ) -> bool {
+        let derived_public_key = derive_public_key(master_public_key, derivation_path);
+        let msg = derived_key_message(&derived_public_key, derivation_id);
+
+        randomizers_are_consistent(&self.c1, &self.c2)
+            && payload_is_consistent(
+                &self.c2,
+                &self.c3,
+                transport_public_key,
+                &msg,
+                &derived_public_key,
+            )
+    }
+
+    /// Serialize this encrypted key
+    pub fn serialize(&self) -> [u8; Self::BYTES] {
+        serialize_ciphertext(&self.c1, &self.c2, &self.c3)
+    }
+
+    /// Deserialize an encrypted key
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, EncryptedKeyDeserializationError> {
+        let (c1, c2, c3) = deserialize_ciphertext(bytes)?;
+        Ok(Self { c1, c2, c3 })
+    }
+}
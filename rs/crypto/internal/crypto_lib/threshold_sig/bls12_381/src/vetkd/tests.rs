@@ -0,0 +1,354 @@
+#![allow(clippy::unwrap_used)]
+//! vetKD tests
+
+use super::*;
+use crate::crypto;
+use ic_crypto_internal_seed::Seed;
+use ic_crypto_test_utils_reproducible_rng::reproducible_rng;
+use ic_types::NumberOfNodes;
+use rand::Rng;
+
+struct TestSetup {
+    master_public_key: G2Affine,
+    master_public_key_shares: Vec<G2Affine>,
+    master_secret_key_shares: Vec<SecretKeyBytes>,
+    threshold: usize,
+}
+
+impl TestSetup {
+    fn new<R: RngCore + CryptoRng>(rng: &mut R, threshold: u32, nodes: u32) -> Self {
+        let (public_coefficients, master_secret_key_shares) = crypto::generate_threshold_key(
+            Seed::from_rng(rng),
+            NumberOfNodes::from(threshold),
+            NumberOfNodes::from(nodes),
+        )
+        .unwrap();
+        let master_public_key_shares = (0..nodes)
+            .map(|index| crypto::individual_public_key(&public_coefficients, index).0.to_affine())
+            .collect();
+        Self {
+            master_public_key: crypto::combined_public_key(&public_coefficients)
+                .0
+                .to_affine(),
+            master_public_key_shares,
+            master_secret_key_shares: master_secret_key_shares
+                .iter()
+                .map(SecretKeyBytes::from)
+                .collect(),
+            threshold: threshold as usize,
+        }
+    }
+
+    fn create_shares<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        transport_public_key: &TransportPublicKey,
+        derivation_path: &DerivationPath,
+        derivation_id: &[u8],
+    ) -> BTreeMap<NodeIndex, EncryptedKeyShare> {
+        self.master_secret_key_shares
+            .iter()
+            .enumerate()
+            .map(|(index, secret_key_share)| {
+                let share = EncryptedKeyShare::create(
+                    rng,
+                    &self.master_public_key,
+                    secret_key_share,
+                    transport_public_key,
+                    derivation_path,
+                    derivation_id,
+                )
+                .unwrap();
+                (index as NodeIndex, share)
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn should_derive_and_decrypt_key_with_any_subset_of_shares() {
+    let rng = &mut reproducible_rng();
+    let setup = TestSetup::new(rng, 3, 5);
+    let transport_secret_key = TransportSecretKey::generate(rng);
+    let transport_public_key = transport_secret_key.public_key();
+    let derivation_path = DerivationPath::new(b"canister", &[b"path".to_vec()]);
+    let derivation_id = b"user@example.com";
+
+    let shares = setup.create_shares(rng, &transport_public_key, &derivation_path, derivation_id);
+
+    for (index, share) in &shares {
+        assert!(share.is_valid(
+            &setup.master_public_key,
+            &setup.master_public_key_shares[*index as usize],
+            &transport_public_key,
+            &derivation_path,
+            derivation_id,
+        ));
+    }
+
+    let mut derived_keys = Vec::new();
+    for skip in 0..shares.len() {
+        let subset: BTreeMap<_, _> = shares
+            .iter()
+            .skip(skip)
+            .chain(shares.iter().take(skip))
+            .take(setup.threshold)
+            .map(|(index, share)| (*index, share.clone()))
+            .collect();
+
+        let encrypted_key = EncryptedKey::combine(
+            &subset,
+            setup.threshold,
+            &setup.master_public_key,
+            &transport_public_key,
+            &derivation_path,
+            derivation_id,
+        )
+        .unwrap();
+
+        let key = transport_secret_key
+            .decrypt(
+                &encrypted_key,
+                &setup.master_public_key,
+                &derivation_path,
+                derivation_id,
+            )
+            .expect("failed to decrypt key");
+        derived_keys.push(key);
+    }
+
+    // The derived key is unique, regardless of the shares that were combined
+    assert!(derived_keys.windows(2).all(|keys| keys[0] == keys[1]));
+}
+
+#[test]
+fn should_derive_different_keys_for_different_inputs() {
+    let rng = &mut reproducible_rng();
+    let setup = TestSetup::new(rng, 2, 3);
+    let transport_secret_key = TransportSecretKey::generate(rng);
+    let transport_public_key = transport_secret_key.public_key();
+
+    let mut derive = |path: &DerivationPath, did: &[u8]| {
+        let shares = setup.create_shares(rng, &transport_public_key, path, did);
+        let encrypted_key = EncryptedKey::combine(
+            &shares,
+            setup.threshold,
+            &setup.master_public_key,
+            &transport_public_key,
+            path,
+            did,
+        )
+        .unwrap();
+        transport_secret_key
+            .decrypt(&encrypted_key, &setup.master_public_key, path, did)
+            .unwrap()
+    };
+
+    let path = DerivationPath::new(b"canister", &[]);
+    let key = derive(&path, b"id");
+    assert_ne!(key, derive(&path, b"other id"));
+    assert_ne!(key, derive(&DerivationPath::new(b"canister2", &[]), b"id"));
+    assert_ne!(
+        key,
+        derive(&DerivationPath::new(b"canister", &[b"id".to_vec()]), b"id")
+    );
+}
+
+#[test]
+fn should_distinguish_derivation_paths_with_same_concatenation() {
+    assert_ne!(
+        DerivationPath::new(b"ab", &[b"c".to_vec()]),
+        DerivationPath::new(b"a", &[b"bc".to_vec()])
+    );
+    assert_ne!(
+        DerivationPath::new(b"a", &[b"b".to_vec(), b"c".to_vec()]),
+        DerivationPath::new(b"a", &[b"bc".to_vec()])
+    );
+}
+
+#[test]
+fn should_reject_share_verified_against_wrong_inputs() {
+    let rng = &mut reproducible_rng();
+    let setup = TestSetup::new(rng, 2, 3);
+    let transport_public_key = TransportSecretKey::generate(rng).public_key();
+    let other_transport_public_key = TransportSecretKey::generate(rng).public_key();
+    let derivation_path = DerivationPath::new(b"canister", &[]);
+    let derivation_id = b"id";
+
+    let shares = setup.create_shares(rng, &transport_public_key, &derivation_path, derivation_id);
+    let share = &shares[&0];
+
+    // wrong node
+    assert!(!share.is_valid(
+        &setup.master_public_key,
+        &setup.master_public_key_shares[1],
+        &transport_public_key,
+        &derivation_path,
+        derivation_id,
+    ));
+    // wrong transport public key
+    assert!(!share.is_valid(
+        &setup.master_public_key,
+        &setup.master_public_key_shares[0],
+        &other_transport_public_key,
+        &derivation_path,
+        derivation_id,
+    ));
+    // wrong derivation path
+    assert!(!share.is_valid(
+        &setup.master_public_key,
+        &setup.master_public_key_shares[0],
+        &transport_public_key,
+        &DerivationPath::new(b"other canister", &[]),
+        derivation_id,
+    ));
+    // wrong derivation id
+    assert!(!share.is_valid(
+        &setup.master_public_key,
+        &setup.master_public_key_shares[0],
+        &transport_public_key,
+        &derivation_path,
+        b"other id",
+    ));
+}
+
+#[test]
+fn should_fail_to_combine_invalid_or_insufficient_shares() {
+    let rng = &mut reproducible_rng();
+    let setup = TestSetup::new(rng, 2, 3);
+    let transport_public_key = TransportSecretKey::generate(rng).public_key();
+    let derivation_path = DerivationPath::new(b"canister", &[]);
+    let derivation_id = b"id";
+
+    let mut shares =
+        setup.create_shares(rng, &transport_public_key, &derivation_path, derivation_id);
+
+    let mut single_share = shares.clone();
+    single_share.retain(|index, _| *index == 0);
+    assert_eq!(
+        EncryptedKey::combine(
+            &single_share,
+            setup.threshold,
+            &setup.master_public_key,
+            &transport_public_key,
+            &derivation_path,
+            derivation_id,
+        ),
+        Err(EncryptedKeyCombinationError::InsufficientShares {
+            num_shares: 1,
+            threshold: 2
+        })
+    );
+
+    // A share for a different derivation id
+    let other_shares = setup.create_shares(rng, &transport_public_key, &derivation_path, b"x");
+    shares.insert(1, other_shares[&1].clone());
+    assert_eq!(
+        EncryptedKey::combine(
+            &shares,
+            setup.threshold,
+            &setup.master_public_key,
+            &transport_public_key,
+            &derivation_path,
+            derivation_id,
+        ),
+        Err(EncryptedKeyCombinationError::InvalidShares)
+    );
+}
+
+#[test]
+fn should_not_decrypt_with_wrong_transport_secret_key() {
+    let rng = &mut reproducible_rng();
+    let setup = TestSetup::new(rng, 1, 1);
+    let transport_public_key = TransportSecretKey::generate(rng).public_key();
+    let derivation_path = DerivationPath::new(b"canister", &[]);
+
+    let shares = setup.create_shares(rng, &transport_public_key, &derivation_path, b"id");
+    let encrypted_key = EncryptedKey::combine(
+        &shares,
+        setup.threshold,
+        &setup.master_public_key,
+        &transport_public_key,
+        &derivation_path,
+        b"id",
+    )
+    .unwrap();
+
+    assert!(TransportSecretKey::generate(rng)
+        .decrypt(
+            &encrypted_key,
+            &setup.master_public_key,
+            &derivation_path,
+            b"id"
+        )
+        .is_none());
+}
+
+#[test]
+fn should_serialize_and_deserialize_encrypted_keys() {
+    let rng = &mut reproducible_rng();
+    let setup = TestSetup::new(rng, 2, 2);
+    let transport_public_key = TransportSecretKey::generate(rng).public_key();
+    let derivation_path = DerivationPath::new(b"canister", &[]);
+    let derivation_id = rng.gen::<[u8; 32]>();
+
+    let shares = setup.create_shares(rng, &transport_public_key, &derivation_path, &derivation_id);
+    for share in shares.values() {
+        let bytes = share.serialize();
+        assert_eq!(EncryptedKeyShare::deserialize(&bytes), Ok(share.clone()));
+    }
+
+    let encrypted_key = EncryptedKey::combine(
+        &shares,
+        setup.threshold,
+        &setup.master_public_key,
+        &transport_public_key,
+        &derivation_path,
+        &derivation_id,
+    )
+    .unwrap();
+    let bytes = encrypted_key.serialize();
+    assert_eq!(EncryptedKey::deserialize(&bytes), Ok(encrypted_key));
+
+    assert_eq!(
+        EncryptedKey::deserialize(&bytes[1..]),
+        Err(EncryptedKeyDeserializationError::InvalidLength)
+    );
+    assert_eq!(
+        EncryptedKey::deserialize(&[0xff; EncryptedKey::BYTES]),
+        Err(EncryptedKeyDeserializationError::InvalidComponent)
+    );
+
+    assert_eq!(
+        TransportPublicKey::deserialize(&transport_public_key.serialize()),
+        Ok(transport_public_key)
+    );
+    assert_eq!(
+        TransportPublicKey::deserialize(&G1Affine::identity().serialize()),
+        Err(TransportPublicKeyDeserializationError::InvalidPublicKey)
+    );
+}
+
+#[test]
+fn should_fail_to_create_share_with_malformed_secret_key() {
+    let rng = &mut reproducible_rng();
+    let setup = TestSetup::new(rng, 1, 1);
+    let transport_public_key = TransportSecretKey::generate(rng).public_key();
+    let malformed_secret_key = SecretKeyBytes(
+        ic_crypto_secrets_containers::SecretArray::new_and_dont_zeroize_argument(
+            &[0xff; SecretKeyBytes::SIZE],
+        ),
+    );
+
+    assert_eq!(
+        EncryptedKeyShare::create(
+            rng,
+            &setup.master_public_key,
+            &malformed_secret_key,
+            &transport_public_key,
+            &DerivationPath::new(b"canister", &[]),
+            b"id",
+        ),
+        Err(EncryptedKeyShareCreationError::MalformedSecretKeyShare)
+    );
+}
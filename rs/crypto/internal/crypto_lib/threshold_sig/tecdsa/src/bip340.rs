@@ -0,0 +1,422 @@
+//! Threshold BIP340 Schnorr signatures over secp256k1
+//!
+//! The signing key and the presignature (the nonce `k`) are both unmasked
+//! IDKG transcripts, created using the same dealing machinery as threshold
+//! ECDSA. Since a Schnorr signature is linear in the key and the nonce, no
+//! multiplication transcripts are needed; each signer simply publishes
+//! `s_i = k_i + e*x_i`, which can be checked against the public commitments
+//! of the two transcripts.
+//!
+//! BIP340 requires that both the public key and the nonce commitment have an
+//! even y coordinate. Since the (derived) public key and the rerandomized
+//! presignature are public, every party knows in advance whether the
+//! respective secret must be negated, and applies that negation to its own
+//! share.
+use crate::*;
+use ic_crypto_sha::Sha256;
+
+/// Size in bytes of a BIP340 (x-only) public key
+pub const BIP340_PUBLIC_KEY_BYTES: usize = 32;
+
+/// Size in bytes of a BIP340 signature
+pub const BIP340_SIGNATURE_BYTES: usize = 64;
+
+fn bip340_tagged_hash(tag: &str, inputs: &[&[u8]]) -> [u8; 32] {
+    let tag_hash = Sha256::hash(tag.as_bytes());
+    let mut sha256 = Sha256::new();
+    sha256.write(&tag_hash);
+    sha256.write(&tag_hash);
+    for input in inputs {
+        sha256.write(input);
+    }
+    sha256.finish()
+}
+
+fn has_even_y(pt: &EccPoint) -> ThresholdEcdsaResult<bool> {
+    Ok(pt.affine_y()?.sign() == 0)
+}
+
+/// Return the x-only encoding of the point, as used by BIP340
+fn x_only_bytes(pt: &EccPoint) -> ThresholdEcdsaResult<Vec<u8>> {
+    Ok(pt.affine_x()?.as_bytes())
+}
+
+/// Return the point with x coordinate `x` and an even y coordinate
+///
+/// This is the `lift_x` function of BIP340
+fn lift_x(x: &[u8]) -> ThresholdEcdsaResult<EccPoint> {
+    if x.len() != BIP340_PUBLIC_KEY_BYTES {
+        return Err(ThresholdEcdsaError::InvalidPoint);
+    }
+    let mut sec1 = Vec::with_capacity(1 + x.len());
+    sec1.push(0x02);
+    sec1.extend_from_slice(x);
+    EccPoint::deserialize(EccCurveType::K256, &sec1)
+}
+
+fn bip340_challenge(
+    nonce_x: &[u8],
+    public_key_x: &[u8],
+    message: &[u8],
+) -> ThresholdEcdsaResult<EccScalar> {
+    let e = bip340_tagged_hash("BIP0340/challenge", &[nonce_x, public_key_x, message]);
+    EccScalar::from_bytes_wide(EccCurveType::K256, &e)
+}
+
+/// Returns the value `x` or `-x` such that `x*pt` has an even y coordinate
+fn negate_if_odd_y(x: EccScalar, pt: &EccPoint) -> ThresholdEcdsaResult<EccScalar> {
+    if has_even_y(pt)? {
+        Ok(x)
+    } else {
+        Ok(x.negate())
+    }
+}
+
+/// Returns `pt` or its negation, whichever has an even y coordinate
+fn point_with_even_y(pt: &EccPoint) -> ThresholdEcdsaResult<EccPoint> {
+    if has_even_y(pt)? {
+        Ok(pt.clone())
+    } else {
+        Ok(pt.negate())
+    }
+}
+
+struct Bip340RerandomizedPresig {
+    key_tweak: EccScalar,
+    randomizer: EccScalar,
+    derived_public_key: EccPoint,
+    randomized_pre_sig: EccPoint,
+    challenge: EccScalar,
+}
+
+impl Bip340RerandomizedPresig {
+    fn compute(
+        message: &[u8],
+        randomness: &Randomness,
+        derivation_path: &DerivationPath,
+        key_transcript: &IDkgTranscriptInternal,
+        presig_transcript: &IDkgTranscriptInternal,
+    ) -> ThresholdEcdsaResult<Self> {
+        let pre_sig = match &presig_transcript.combined_commitment {
+            CombinedCommitment::ByInterpolation(PolynomialCommitment::Simple(c)) => {
+                c.constant_term()
+            }
+            _ => return Err(ThresholdEcdsaError::UnexpectedCommitmentType),
+        };
+
+        let master_public_key = match &key_transcript.combined_commitment {
+            CombinedCommitment::ByInterpolation(PolynomialCommitment::Simple(c)) => {
+                c.constant_term()
+            }
+            _ => return Err(ThresholdEcdsaError::UnexpectedCommitmentType),
+        };
+
+        if pre_sig.curve_type() != EccCurveType::K256
+            || master_public_key.curve_type() != EccCurveType::K256
+        {
+            return Err(ThresholdEcdsaError::CurveMismatch);
+        }
+
+        let (key_tweak, _chain_key) = derivation_path.derive_tweak(&master_public_key)?;
+        let derived_public_key = master_public_key.add_points(&EccPoint::mul_by_g(&key_tweak)?)?;
+
+        let mut ro = ro::RandomOracle::new("ic-crypto-bip340-rerandomize-presig");
+        ro.add_bytestring("randomness", &randomness.get())?;
+        ro.add_bytestring("message", message)?;
+        ro.add_point("pre_sig", &pre_sig)?;
+        ro.add_scalar("key_tweak", &key_tweak)?;
+        let randomizer = ro.output_scalar(EccCurveType::K256)?;
+
+        let randomized_pre_sig = pre_sig.add_points(&EccPoint::mul_by_g(&randomizer)?)?;
+
+        let challenge = bip340_challenge(
+            &x_only_bytes(&randomized_pre_sig)?,
+            &x_only_bytes(&derived_public_key)?,
+            message,
+        )?;
+
+        Ok(Self {
+            key_tweak,
+            randomizer,
+            derived_public_key,
+            randomized_pre_sig,
+            challenge,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ThresholdBip340SignatureShareInternal {
+    s: EccScalar,
+}
+
+impl ThresholdBip340SignatureShareInternal {
+    pub(crate) fn new(
+        derivation_path: &DerivationPath,
+        message: &[u8],
+        randomness: Randomness,
+        key_transcript: &IDkgTranscriptInternal,
+        key_opening: &CommitmentOpening,
+        presig_transcript: &IDkgTranscriptInternal,
+        presig_opening: &CommitmentOpening,
+    ) -> ThresholdEcdsaResult<Self> {
+        let rerandomized = Bip340RerandomizedPresig::compute(
+            message,
+            &randomness,
+            derivation_path,
+            key_transcript,
+            presig_transcript,
+        )?;
+
+        let key_share = match key_opening {
+            CommitmentOpening::Simple(s) => s,
+            _ => return Err(ThresholdEcdsaError::UnexpectedCommitmentType),
+        };
+
+        let presig_share = match presig_opening {
+            CommitmentOpening::Simple(s) => s,
+            _ => return Err(ThresholdEcdsaError::UnexpectedCommitmentType),
+        };
+
+        // Since the Lagrange coefficients at zero sum to one, adding the
+        // (public) tweak and randomizer to each share adds them to the
+        // shared secrets.
+        let tweaked_key_share = negate_if_odd_y(
+            key_share.add(&rerandomized.key_tweak)?,
+            &rerandomized.derived_public_key,
+        )?;
+        let randomized_presig_share = negate_if_odd_y(
+            presig_share.add(&rerandomized.randomizer)?,
+            &rerandomized.randomized_pre_sig,
+        )?;
+
+        let s = randomized_presig_share.add(&rerandomized.challenge.mul(&tweaked_key_share)?)?;
+
+        Ok(Self { s })
+    }
+
+    /// Verify a BIP340 signature share
+    ///
+    /// The share of signer `j` is valid iff `s_j*G` equals the (sign
+    /// adjusted) evaluations at `j` of the presignature and key commitments,
+    /// offset by the public randomizer and key tweak respectively.
+    pub fn verify(
+        &self,
+        derivation_path: &DerivationPath,
+        message: &[u8],
+        randomness: Randomness,
+        signer_index: NodeIndex,
+        key_transcript: &IDkgTranscriptInternal,
+        presig_transcript: &IDkgTranscriptInternal,
+    ) -> ThresholdEcdsaResult<()> {
+        let rerandomized = Bip340RerandomizedPresig::compute(
+            message,
+            &randomness,
+            derivation_path,
+            key_transcript,
+            presig_transcript,
+        )?;
+
+        let key_j = key_transcript
+            .evaluate_at(signer_index)?
+            .add_points(&EccPoint::mul_by_g(&rerandomized.key_tweak)?)?;
+        let presig_j = presig_transcript
+            .evaluate_at(signer_index)?
+            .add_points(&EccPoint::mul_by_g(&rerandomized.randomizer)?)?;
+
+        let key_j = if has_even_y(&rerandomized.derived_public_key)? {
+            key_j
+        } else {
+            key_j.negate()
+        };
+        let presig_j = if has_even_y(&rerandomized.randomized_pre_sig)? {
+            presig_j
+        } else {
+            presig_j.negate()
+        };
+
+        let expected = presig_j.add_points(&key_j.scalar_mul(&rerandomized.challenge)?)?;
+
+        if EccPoint::mul_by_g(&self.s)? != expected {
+            return Err(ThresholdEcdsaError::InvalidSignatureShare);
+        }
+
+        Ok(())
+    }
+
+    pub fn serialize(&self) -> ThresholdEcdsaResult<Vec<u8>> {
+        serde_cbor::to_vec(self)
+            .map_err(|e| ThresholdEcdsaError::SerializationError(format!("{}", e)))
+    }
+
+    pub fn deserialize(raw: &[u8]) -> ThresholdEcdsaResult<Self> {
+        serde_cbor::from_slice::<Self>(raw)
+            .map_err(|e| ThresholdEcdsaError::SerializationError(format!("{}", e)))
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ThresholdBip340CombinedSignatureInternal {
+    r: EccPoint,
+    s: EccScalar,
+}
+
+impl ThresholdBip340CombinedSignatureInternal {
+    /// Serialize in the 64 byte format specified by BIP340
+    pub fn serialize(&self) -> ThresholdEcdsaResult<Vec<u8>> {
+        let mut sig = Vec::with_capacity(BIP340_SIGNATURE_BYTES);
+        sig.extend_from_slice(&x_only_bytes(&self.r)?);
+        sig.extend_from_slice(&self.s.serialize());
+        Ok(sig)
+    }
+
+    pub fn deserialize(bytes: &[u8]) -> ThresholdEcdsaResult<Self> {
+        if bytes.len() != BIP340_SIGNATURE_BYTES {
+            return Err(ThresholdEcdsaError::SerializationError(
+                "Bad signature length".to_string(),
+            ));
+        }
+
+        let r = lift_x(&bytes[..32])?;
+        let s = EccScalar::deserialize(EccCurveType::K256, &bytes[32..])?;
+
+        Ok(Self { r, s })
+    }
+
+    pub(crate) fn new(
+        derivation_path: &DerivationPath,
+        message: &[u8],
+        randomness: Randomness,
+        key_transcript: &IDkgTranscriptInternal,
+        presig_transcript: &IDkgTranscriptInternal,
+        reconstruction_threshold: NumberOfNodes,
+        sig_shares: &BTreeMap<NodeIndex, ThresholdBip340SignatureShareInternal>,
+    ) -> ThresholdEcdsaResult<Self> {
+        let reconstruction_threshold = reconstruction_threshold.get() as usize;
+        if sig_shares.len() < reconstruction_threshold {
+            return Err(ThresholdEcdsaError::InsufficientDealings);
+        }
+
+        let rerandomized = Bip340RerandomizedPresig::compute(
+            message,
+            &randomness,
+            derivation_path,
+            key_transcript,
+            presig_transcript,
+        )?;
+
+        let mut x_values = Vec::with_capacity(reconstruction_threshold);
+        let mut samples = Vec::with_capacity(reconstruction_threshold);
+
+        for (index, sig_share) in sig_shares.iter().take(reconstruction_threshold) {
+            x_values.push(*index);
+            samples.push(sig_share.s.clone());
+        }
+
+        let coefficients = LagrangeCoefficients::at_zero(EccCurveType::K256, &x_values)?;
+        let s = coefficients.interpolate_scalar(&samples)?;
+
+        Ok(Self {
+            r: point_with_even_y(&rerandomized.randomized_pre_sig)?,
+            s,
+        })
+    }
+
+    /// Verify a threshold BIP340 signature
+    ///
+    /// Besides the BIP340 verification equation against the derived public
+    /// key, this also checks that the signature was generated using the
+    /// provided presignature transcript and randomness.
+    pub fn verify(
+        &self,
+        derivation_path: &DerivationPath,
+        message: &[u8],
+        randomness: Randomness,
+        presig_transcript: &IDkgTranscriptInternal,
+        key_transcript: &IDkgTranscriptInternal,
+    ) -> ThresholdEcdsaResult<()> {
+        let rerandomized = Bip340RerandomizedPresig::compute(
+            message,
+            &randomness,
+            derivation_path,
+            key_transcript,
+            presig_transcript,
+        )?;
+
+        if self.r != point_with_even_y(&rerandomized.randomized_pre_sig)? {
+            return Err(ThresholdEcdsaError::InvalidSignature);
+        }
+
+        verify_bip340_signature(
+            &x_only_bytes(&rerandomized.derived_public_key)?,
+            message,
+            &self.serialize()?,
+        )
+    }
+}
+
+/// Verify a BIP340 signature
+///
+/// `public_key` is the 32 byte x-only public key and `signature` the 64 byte
+/// signature, both as specified in BIP340.
+pub fn verify_bip340_signature(
+    public_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> ThresholdEcdsaResult<()> {
+    if signature.len() != BIP340_SIGNATURE_BYTES {
+        return Err(ThresholdEcdsaError::InvalidSignature);
+    }
+
+    let pk = lift_x(public_key)?;
+
+    // BIP340 requires r < p and s < n; both are checked by the parsing.
+    let _r = EccFieldElement::from_bytes(EccCurveType::K256, &signature[..32])
+        .map_err(|_| ThresholdEcdsaError::InvalidSignature)?;
+    let s = EccScalar::deserialize(EccCurveType::K256, &signature[32..])
+        .map_err(|_| ThresholdEcdsaError::InvalidSignature)?;
+
+    let e = bip340_challenge(&signature[..32], public_key, message)?;
+
+    // R = s*G - e*P
+    let r = EccPoint::mul_2_points(
+        &EccPoint::generator_g(EccCurveType::K256),
+        &s,
+        &pk,
+        &e.negate(),
+    )?;
+
+    if r.is_infinity()? || !has_even_y(&r)? || x_only_bytes(&r)? != signature[..32] {
+        return Err(ThresholdEcdsaError::InvalidSignature);
+    }
+
+    Ok(())
+}
+
+/// A public key derived for BIP340 signing
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Bip340PublicKey {
+    /// The 32 byte x-only encoding of the derived key
+    pub public_key: Vec<u8>,
+    pub chain_key: Vec<u8>,
+}
+
+/// Returns the BIP340 public key derived from the master public key (a SEC1
+/// encoded secp256k1 point) according to `derivation_path`
+///
+/// The derivation is the same as for threshold ECDSA, so a canister using
+/// the same derivation path with both schemes obtains the same underlying
+/// point.
+pub fn derive_public_key(
+    master_public_key: &[u8],
+    derivation_path: &DerivationPath,
+) -> ThresholdEcdsaResult<Bip340PublicKey> {
+    let master_public_key = EccPoint::deserialize(EccCurveType::K256, master_public_key)?;
+    let (key_tweak, chain_key) = derivation_path.derive_tweak(&master_public_key)?;
+    let public_key = master_public_key.add_points(&EccPoint::mul_by_g(&key_tweak)?)?;
+
+    Ok(Bip340PublicKey {
+        public_key: x_only_bytes(&public_key)?,
+        chain_key,
+    })
+}
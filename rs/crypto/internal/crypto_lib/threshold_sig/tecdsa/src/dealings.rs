@@ -189,8 +189,11 @@ impl IDkgDealingInternal {
             ));
         }
 
+        // The recipients' MEGa keys must all be on the same curve, but that
+        // curve may differ from the curve the secret is shared on
+        let key_curve = recipients[0].curve_type();
         for recipient in recipients {
-            if recipient.curve_type() != curve {
+            if recipient.curve_type() != key_curve {
                 return Err(ThresholdEcdsaError::InvalidRecipients);
             }
         }
@@ -318,8 +321,10 @@ impl IDkgDealingInternal {
         })
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn publicly_verify(
         &self,
+        key_curve: EccCurveType,
         curve_type: EccCurveType,
         transcript_type: &IDkgTranscriptOperationInternal,
         reconstruction_threshold: NumberOfNodes,
@@ -349,7 +354,7 @@ impl IDkgDealingInternal {
                 self.commitment
                     .verify_is(PolynomialCommitmentType::Pedersen, curve_type)?;
                 self.ciphertext
-                    .verify_is(MEGaCiphertextType::Pairs, curve_type)?;
+                    .verify_is(MEGaCiphertextType::Pairs, key_curve, curve_type)?;
                 // no ZK proof for this transcript type
                 Ok(())
             }
@@ -361,7 +366,7 @@ impl IDkgDealingInternal {
                     .verify_is(PolynomialCommitmentType::Simple, curve_type)?;
                 previous_commitment.verify_is(PolynomialCommitmentType::Pedersen, curve_type)?;
                 self.ciphertext
-                    .verify_is(MEGaCiphertextType::Single, curve_type)?;
+                    .verify_is(MEGaCiphertextType::Single, key_curve, curve_type)?;
 
                 proof.verify(
                     &previous_commitment.evaluate_at(dealer_index)?,
@@ -377,7 +382,7 @@ impl IDkgDealingInternal {
                     .verify_is(PolynomialCommitmentType::Simple, curve_type)?;
                 previous_commitment.verify_is(PolynomialCommitmentType::Simple, curve_type)?;
                 self.ciphertext
-                    .verify_is(MEGaCiphertextType::Single, curve_type)?;
+                    .verify_is(MEGaCiphertextType::Single, key_curve, curve_type)?;
 
                 match previous_commitment {
                     PolynomialCommitment::Pedersen(_) => {
@@ -399,7 +404,7 @@ impl IDkgDealingInternal {
                 self.commitment
                     .verify_is(PolynomialCommitmentType::Pedersen, curve_type)?;
                 self.ciphertext
-                    .verify_is(MEGaCiphertextType::Pairs, curve_type)?;
+                    .verify_is(MEGaCiphertextType::Pairs, key_curve, curve_type)?;
                 lhs.verify_is(PolynomialCommitmentType::Simple, curve_type)?;
                 rhs.verify_is(PolynomialCommitmentType::Pedersen, curve_type)?;
 
@@ -426,7 +431,7 @@ impl IDkgDealingInternal {
         dealer_index: NodeIndex,
         recipient_index: NodeIndex,
     ) -> ThresholdEcdsaResult<()> {
-        if private_key.curve_type() != curve_type || public_key.curve_type() != curve_type {
+        if private_key.curve_type() != public_key.curve_type() {
             return Err(ThresholdEcdsaError::CurveMismatch);
         }
 
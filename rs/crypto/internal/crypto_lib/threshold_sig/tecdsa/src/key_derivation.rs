@@ -73,13 +73,20 @@ impl DerivationPath {
     ///
     /// Extended to support larger inputs, which is needed for
     /// deriving the canister public key
+    ///
+    /// BIP32 itself is only defined for secp256k1. For secp256r1 the same
+    /// construction is used, with the key offset taken modulo the order
+    /// of the P-256 group instead.
     fn bip32_ckdpub(
         public_key: &EccPoint,
         chain_key: &[u8],
         index: &DerivationIndex,
     ) -> ThresholdEcdsaResult<(EccPoint, Vec<u8>, EccScalar)> {
-        // BIP32 is only defined for secp256k1
-        if public_key.curve_type() != EccCurveType::K256 {
+        // BIP32 is only defined for secp256k1, see above for secp256r1
+        if !matches!(
+            public_key.curve_type(),
+            EccCurveType::K256 | EccCurveType::P256
+        ) {
             return Err(ThresholdEcdsaError::CurveMismatch);
         }
 
@@ -134,7 +141,7 @@ impl DerivationPath {
 
         let curve_type = master_public_key.curve_type();
 
-        if curve_type == EccCurveType::K256 {
+        if curve_type == EccCurveType::K256 || curve_type == EccCurveType::P256 {
             let mut derived_key = master_public_key.clone();
             let mut derived_chain_key = chain_code.to_vec();
             let mut derived_offset = EccScalar::zero(curve_type);
@@ -150,7 +157,7 @@ impl DerivationPath {
 
             Ok((derived_offset, derived_chain_key))
         } else {
-            // Key derivation is not currently defined for curves other than secp256k1
+            // Key derivation is not currently defined for other curves
             Err(ThresholdEcdsaError::InvalidArguments(format!(
                 "Currently key derivation not defined for {}",
                 curve_type
@@ -69,6 +69,17 @@
 //! * Generation and verification of signature shares
 //! * Generation and verification of combined signatures
 //!
+//! ## Protocol: Threshold BIP340 Schnorr Signatures
+//!
+//! File: `bip340.rs`
+//!
+//! Schnorr signatures as specified in BIP340, computed from an unmasked key
+//! transcript and an unmasked presignature transcript. Both transcripts are
+//! generated with the same dealings as used for threshold ECDSA, and keys are
+//! derived in the same way as for ECDSA. Only the cryptographic primitive is
+//! provided so far: consensus does not create Schnorr presignatures yet, and
+//! the management canister does not offer Schnorr signing.
+//!
 //! ## Protocol: Multi-encryption gadget (MEGa)
 //!
 //! File: `mega.rs`
@@ -79,6 +90,11 @@
 //! [`RandomOracle`](#utility-functions-random-oracle) is used to
 //! generate the additive masking values.
 //!
+//! The MEGa keys and the encrypted values need not be on the same curve.
+//! Node MEGa keys are always secp256k1 ([`MEGA_KEY_CURVE`]), while secrets
+//! for secp256r1 transcripts are encrypted as P-256 scalars, with the
+//! masking values derived directly in the P-256 scalar field.
+//!
 //! ## Protocol: Polynomial Arithmetic and Commitments
 //!
 //! File: `poly.rs`
@@ -246,6 +262,7 @@ pub enum ThresholdEcdsaError {
 
 pub type ThresholdEcdsaResult<T> = std::result::Result<T, ThresholdEcdsaError>;
 
+pub mod bip340;
 mod complaints;
 mod dealings;
 mod fe;
@@ -269,8 +286,15 @@ pub use crate::poly::*;
 pub use crate::transcript::*;
 
 pub use crate::key_derivation::{DerivationIndex, DerivationPath};
+pub use bip340::{ThresholdBip340CombinedSignatureInternal, ThresholdBip340SignatureShareInternal};
 pub use sign::{ThresholdEcdsaCombinedSigInternal, ThresholdEcdsaSigShareInternal};
 
+/// The curve of the MEGa encryption keys that nodes register
+///
+/// Dealings are always encrypted to keys on this curve, regardless of
+/// the curve on which the dealt secret is shared.
+pub const MEGA_KEY_CURVE: EccCurveType = EccCurveType::K256;
+
 /// Create MEGa encryption keypair
 pub fn gen_keypair(
     curve_type: EccCurveType,
@@ -316,10 +340,20 @@ pub fn create_dealing(
     seed: Seed,
 ) -> Result<IDkgDealingInternal, IdkgCreateDealingInternalError> {
     let curve = match algorithm_id {
-        AlgorithmId::ThresholdEcdsaSecp256k1 => Ok(EccCurveType::K256),
+        AlgorithmId::ThresholdEcdsaSecp256k1 | AlgorithmId::ThresholdSchnorrBip340 => {
+            Ok(EccCurveType::K256)
+        }
+        AlgorithmId::ThresholdEcdsaSecp256r1 => Ok(EccCurveType::P256),
         _ => Err(IdkgCreateDealingInternalError::UnsupportedAlgorithm),
     }?;
 
+    if recipients
+        .iter()
+        .any(|recipient| recipient.curve_type() != MEGA_KEY_CURVE)
+    {
+        return Err(IdkgCreateDealingInternalError::InvalidRecipients);
+    }
+
     IDkgDealingInternal::new(
         shares,
         curve,
@@ -367,7 +401,10 @@ pub fn create_transcript(
     operation_mode: &IDkgTranscriptOperationInternal,
 ) -> Result<IDkgTranscriptInternal, IDkgCreateTranscriptInternalError> {
     let curve = match algorithm_id {
-        AlgorithmId::ThresholdEcdsaSecp256k1 => Ok(EccCurveType::K256),
+        AlgorithmId::ThresholdEcdsaSecp256k1 | AlgorithmId::ThresholdSchnorrBip340 => {
+            Ok(EccCurveType::K256)
+        }
+        AlgorithmId::ThresholdEcdsaSecp256r1 => Ok(EccCurveType::P256),
         _ => Err(IDkgCreateTranscriptInternalError::UnsupportedAlgorithm),
     }?;
 
@@ -564,12 +601,16 @@ pub fn publicly_verify_dealing(
     associated_data: &[u8],
 ) -> Result<(), IDkgVerifyDealingInternalError> {
     let curve = match algorithm_id {
-        AlgorithmId::ThresholdEcdsaSecp256k1 => Ok(EccCurveType::K256),
+        AlgorithmId::ThresholdEcdsaSecp256k1 | AlgorithmId::ThresholdSchnorrBip340 => {
+            Ok(EccCurveType::K256)
+        }
+        AlgorithmId::ThresholdEcdsaSecp256r1 => Ok(EccCurveType::P256),
         _ => Err(IDkgVerifyDealingInternalError::UnsupportedAlgorithm),
     }?;
 
     dealing
         .publicly_verify(
+            MEGA_KEY_CURVE,
             curve,
             transcript_type,
             reconstruction_threshold,
@@ -596,10 +637,17 @@ pub fn privately_verify_dealing(
     recipient_index: NodeIndex,
 ) -> Result<(), IDkgVerifyDealingInternalError> {
     let curve = match algorithm_id {
-        AlgorithmId::ThresholdEcdsaSecp256k1 => Ok(EccCurveType::K256),
+        AlgorithmId::ThresholdEcdsaSecp256k1 | AlgorithmId::ThresholdSchnorrBip340 => {
+            Ok(EccCurveType::K256)
+        }
+        AlgorithmId::ThresholdEcdsaSecp256r1 => Ok(EccCurveType::P256),
         _ => Err(IDkgVerifyDealingInternalError::UnsupportedAlgorithm),
     }?;
 
+    if private_key.curve_type() != MEGA_KEY_CURVE {
+        return Err(ThresholdEcdsaError::CurveMismatch.into());
+    }
+
     dealing
         .privately_verify(
             curve,
@@ -660,6 +708,9 @@ fn signature_parameters(algorithm_id: AlgorithmId) -> Option<(EccCurveType, usiz
         AlgorithmId::ThresholdEcdsaSecp256k1 => {
             Some((EccCurveType::K256, EccCurveType::K256.scalar_bytes()))
         }
+        AlgorithmId::ThresholdEcdsaSecp256r1 => {
+            Some((EccCurveType::P256, EccCurveType::P256.scalar_bytes()))
+        }
         _ => None,
     }
 }
@@ -808,6 +859,7 @@ pub fn combine_sig_shares(
 ) -> Result<ThresholdEcdsaCombinedSigInternal, ThresholdEcdsaCombineSigSharesInternalError> {
     let curve_type = match algorithm_id {
         AlgorithmId::ThresholdEcdsaSecp256k1 => EccCurveType::K256,
+        AlgorithmId::ThresholdEcdsaSecp256r1 => EccCurveType::P256,
         _ => return Err(ThresholdEcdsaCombineSigSharesInternalError::UnsupportedAlgorithm),
     };
 
@@ -922,6 +974,219 @@ pub fn derive_public_key(
     )?)
 }
 
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ThresholdBip340GenerateSigShareInternalError {
+    UnsupportedAlgorithm,
+    InconsistentCommitments,
+    InternalError(String),
+}
+
+impl From<ThresholdEcdsaError> for ThresholdBip340GenerateSigShareInternalError {
+    fn from(e: ThresholdEcdsaError) -> Self {
+        match e {
+            ThresholdEcdsaError::CurveMismatch => Self::InconsistentCommitments,
+            ThresholdEcdsaError::InvalidCommitment => Self::InconsistentCommitments,
+            x => Self::InternalError(format!("{:?}", x)),
+        }
+    }
+}
+
+/// Create a new threshold BIP340 signature share
+///
+/// The presig_transcript is the (unmasked) transcript of the nonce, and
+/// key_opening and presig_opening are our openings of the key and
+/// presignature transcripts.
+///
+/// Unlike ECDSA, the message is not hashed by the caller; BIP340 hashes it
+/// together with the nonce and the public key.
+#[allow(clippy::too_many_arguments)]
+pub fn sign_share_bip340(
+    derivation_path: &DerivationPath,
+    message: &[u8],
+    nonce: Randomness,
+    key_transcript: &IDkgTranscriptInternal,
+    key_opening: &CommitmentOpening,
+    presig_transcript: &IDkgTranscriptInternal,
+    presig_opening: &CommitmentOpening,
+    algorithm_id: AlgorithmId,
+) -> Result<ThresholdBip340SignatureShareInternal, ThresholdBip340GenerateSigShareInternalError> {
+    if algorithm_id != AlgorithmId::ThresholdSchnorrBip340 {
+        return Err(ThresholdBip340GenerateSigShareInternalError::UnsupportedAlgorithm);
+    }
+
+    ThresholdBip340SignatureShareInternal::new(
+        derivation_path,
+        message,
+        nonce,
+        key_transcript,
+        key_opening,
+        presig_transcript,
+        presig_opening,
+    )
+    .map_err(|e| e.into())
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ThresholdBip340VerifySigShareInternalError {
+    UnsupportedAlgorithm,
+    InconsistentCommitments,
+    InvalidSignatureShare,
+    InternalError(String),
+}
+
+impl From<ThresholdEcdsaError> for ThresholdBip340VerifySigShareInternalError {
+    fn from(e: ThresholdEcdsaError) -> Self {
+        match e {
+            ThresholdEcdsaError::CurveMismatch => Self::InconsistentCommitments,
+            ThresholdEcdsaError::InvalidCommitment => Self::InconsistentCommitments,
+            ThresholdEcdsaError::InvalidSignatureShare => Self::InvalidSignatureShare,
+            x => Self::InternalError(format!("{:?}", x)),
+        }
+    }
+}
+
+/// Verify a threshold BIP340 signature share
+///
+/// The values provided must be consistent with when the signature share
+/// was created
+#[allow(clippy::too_many_arguments)]
+pub fn verify_signature_share_bip340(
+    sig_share: &ThresholdBip340SignatureShareInternal,
+    derivation_path: &DerivationPath,
+    message: &[u8],
+    randomness: Randomness,
+    signer_index: NodeIndex,
+    key_transcript: &IDkgTranscriptInternal,
+    presig_transcript: &IDkgTranscriptInternal,
+    algorithm_id: AlgorithmId,
+) -> Result<(), ThresholdBip340VerifySigShareInternalError> {
+    if algorithm_id != AlgorithmId::ThresholdSchnorrBip340 {
+        return Err(ThresholdBip340VerifySigShareInternalError::UnsupportedAlgorithm);
+    }
+
+    sig_share
+        .verify(
+            derivation_path,
+            message,
+            randomness,
+            signer_index,
+            key_transcript,
+            presig_transcript,
+        )
+        .map_err(|e| e.into())
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ThresholdBip340CombineSigSharesInternalError {
+    UnsupportedAlgorithm,
+    InconsistentCommitments,
+    InsufficientShares,
+    InternalError(String),
+}
+
+impl From<ThresholdEcdsaError> for ThresholdBip340CombineSigSharesInternalError {
+    fn from(e: ThresholdEcdsaError) -> Self {
+        match e {
+            ThresholdEcdsaError::CurveMismatch => Self::InconsistentCommitments,
+            ThresholdEcdsaError::InvalidCommitment => Self::InconsistentCommitments,
+            ThresholdEcdsaError::InsufficientDealings => Self::InsufficientShares,
+            x => Self::InternalError(format!("{:?}", x)),
+        }
+    }
+}
+
+/// Combine sufficient signature shares into a BIP340 signature
+///
+/// The signature shares must be verified prior to use, and there must
+/// be at least reconstruction_threshold many of them.
+#[allow(clippy::too_many_arguments)]
+pub fn combine_sig_shares_bip340(
+    derivation_path: &DerivationPath,
+    message: &[u8],
+    randomness: Randomness,
+    key_transcript: &IDkgTranscriptInternal,
+    presig_transcript: &IDkgTranscriptInternal,
+    reconstruction_threshold: NumberOfNodes,
+    sig_shares: &BTreeMap<NodeIndex, ThresholdBip340SignatureShareInternal>,
+    algorithm_id: AlgorithmId,
+) -> Result<ThresholdBip340CombinedSignatureInternal, ThresholdBip340CombineSigSharesInternalError>
+{
+    if algorithm_id != AlgorithmId::ThresholdSchnorrBip340 {
+        return Err(ThresholdBip340CombineSigSharesInternalError::UnsupportedAlgorithm);
+    }
+
+    ThresholdBip340CombinedSignatureInternal::new(
+        derivation_path,
+        message,
+        randomness,
+        key_transcript,
+        presig_transcript,
+        reconstruction_threshold,
+        sig_shares,
+    )
+    .map_err(|e| e.into())
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ThresholdBip340VerifySignatureInternalError {
+    InvalidSignature,
+    UnsupportedAlgorithm,
+    InconsistentCommitments,
+    InternalError(String),
+}
+
+impl From<ThresholdEcdsaError> for ThresholdBip340VerifySignatureInternalError {
+    fn from(e: ThresholdEcdsaError) -> Self {
+        match e {
+            ThresholdEcdsaError::CurveMismatch => Self::InconsistentCommitments,
+            ThresholdEcdsaError::InvalidCommitment => Self::InconsistentCommitments,
+            ThresholdEcdsaError::InvalidSignature => Self::InvalidSignature,
+            x => Self::InternalError(format!("{:?}", x)),
+        }
+    }
+}
+
+/// Verify a threshold BIP340 signature
+///
+/// In addition to checking the BIP340 signature against the public key
+/// associated with `derivation_path`, this also verifies that the signature
+/// was generated with the provided presignature transcript and randomness.
+pub fn verify_threshold_signature_bip340(
+    signature: &ThresholdBip340CombinedSignatureInternal,
+    derivation_path: &DerivationPath,
+    message: &[u8],
+    randomness: Randomness,
+    presig_transcript: &IDkgTranscriptInternal,
+    key_transcript: &IDkgTranscriptInternal,
+    algorithm_id: AlgorithmId,
+) -> Result<(), ThresholdBip340VerifySignatureInternalError> {
+    if algorithm_id != AlgorithmId::ThresholdSchnorrBip340 {
+        return Err(ThresholdBip340VerifySignatureInternalError::UnsupportedAlgorithm);
+    }
+
+    signature
+        .verify(
+            derivation_path,
+            message,
+            randomness,
+            presig_transcript,
+            key_transcript,
+        )
+        .map_err(|e| e.into())
+}
+
+/// Returns the BIP340 public key derived from `master_public_key`, which is
+/// the SEC1 encoding of the constant term of the key transcript.
+pub fn derive_bip340_public_key(
+    master_public_key: &[u8],
+    derivation_path: &DerivationPath,
+) -> Result<bip340::Bip340PublicKey, ThresholdEcdsaDerivePublicKeyError> {
+    Ok(bip340::derive_public_key(
+        master_public_key,
+        derivation_path,
+    )?)
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum IDkgGenerateComplaintsInternalError {
     InvalidArguments(String),
@@ -204,29 +204,32 @@ impl MEGaCiphertext {
     /// Simple type verification for MEGa ciphertexts
     ///
     /// Verifies that the ciphertext is of the expected type (single or pairs)
-    /// and is for the expected curve.
+    /// and is for the expected curves. The ephemeral key and proof of
+    /// possession are on the curve of the recipients' MEGa keys, while the
+    /// encrypted values are scalars of the curve of the shared secret, which
+    /// need not be the same.
     pub fn verify_is(
         &self,
         ctype: MEGaCiphertextType,
-        curve: EccCurveType,
+        key_curve: EccCurveType,
+        plaintext_curve: EccCurveType,
     ) -> ThresholdEcdsaResult<()> {
-        if self.ephemeral_key().curve_type() != curve {
+        if self.ephemeral_key().curve_type() != key_curve {
             return Err(ThresholdEcdsaError::CurveMismatch);
         }
 
-        if self.pop_public_key().curve_type() != curve {
+        if self.pop_public_key().curve_type() != key_curve {
             return Err(ThresholdEcdsaError::CurveMismatch);
         }
-        if self.pop_proof().curve_type()? != curve {
+        if self.pop_proof().curve_type()? != key_curve {
             return Err(ThresholdEcdsaError::CurveMismatch);
         }
 
         let curves_ok = match self {
-            MEGaCiphertext::Single(c) => c.ctexts.iter().all(|x| x.curve_type() == curve),
-            MEGaCiphertext::Pairs(c) => c
-                .ctexts
-                .iter()
-                .all(|(x, y)| x.curve_type() == curve && y.curve_type() == curve),
+            MEGaCiphertext::Single(c) => c.ctexts.iter().all(|x| x.curve_type() == plaintext_curve),
+            MEGaCiphertext::Pairs(c) => c.ctexts.iter().all(|(x, y)| {
+                x.curve_type() == plaintext_curve && y.curve_type() == plaintext_curve
+            }),
         };
 
         if !curves_ok {
@@ -306,10 +309,28 @@ impl From<MEGaCiphertextPair> for MEGaCiphertext {
     }
 }
 
+/// Check that all recipient keys are on the same curve, returning that curve
+fn check_recipients(recipients: &[MEGaPublicKey]) -> ThresholdEcdsaResult<EccCurveType> {
+    let key_curve = recipients
+        .first()
+        .ok_or_else(|| {
+            ThresholdEcdsaError::InvalidArguments("Must have at least one recipient".to_string())
+        })?
+        .curve_type();
+
+    for recipient in recipients {
+        if recipient.curve_type() != key_curve {
+            return Err(ThresholdEcdsaError::CurveMismatch);
+        }
+    }
+
+    Ok(key_curve)
+}
+
 fn check_plaintexts(
     plaintexts: &[EccScalar],
     recipients: &[MEGaPublicKey],
-) -> ThresholdEcdsaResult<EccCurveType> {
+) -> ThresholdEcdsaResult<(EccCurveType, EccCurveType)> {
     if plaintexts.len() != recipients.len() {
         return Err(ThresholdEcdsaError::InvalidArguments(
             "Must be as many plaintexts as recipients".to_string(),
@@ -330,19 +351,15 @@ fn check_plaintexts(
         }
     }
 
-    for recipient in recipients {
-        if recipient.curve_type() != curve_type {
-            return Err(ThresholdEcdsaError::CurveMismatch);
-        }
-    }
+    let key_curve = check_recipients(recipients)?;
 
-    Ok(curve_type)
+    Ok((key_curve, curve_type))
 }
 
 fn check_plaintexts_pair(
     plaintexts: &[(EccScalar, EccScalar)],
     recipients: &[MEGaPublicKey],
-) -> ThresholdEcdsaResult<EccCurveType> {
+) -> ThresholdEcdsaResult<(EccCurveType, EccCurveType)> {
     if plaintexts.len() != recipients.len() {
         return Err(ThresholdEcdsaError::InvalidArguments(
             "Must be as many plaintexts as recipients".to_string(),
@@ -363,17 +380,14 @@ fn check_plaintexts_pair(
         }
     }
 
-    for recipient in recipients {
-        if recipient.curve_type() != curve_type {
-            return Err(ThresholdEcdsaError::CurveMismatch);
-        }
-    }
+    let key_curve = check_recipients(recipients)?;
 
-    Ok(curve_type)
+    Ok((key_curve, curve_type))
 }
 
 fn mega_hash_to_scalars(
     ctype: MEGaCiphertextType,
+    plaintext_curve: EccCurveType,
     dealer_index: NodeIndex,
     recipient_index: NodeIndex,
     associated_data: &[u8],
@@ -381,8 +395,6 @@ fn mega_hash_to_scalars(
     ephemeral_key: &EccPoint,
     shared_secret: &EccPoint,
 ) -> ThresholdEcdsaResult<Vec<EccScalar>> {
-    let curve_type = public_key.curve_type();
-
     let count = match ctype {
         MEGaCiphertextType::Single => 1,
         MEGaCiphertextType::Pairs => 2,
@@ -395,7 +407,7 @@ fn mega_hash_to_scalars(
     ro.add_point("public_key", public_key)?;
     ro.add_point("ephemeral_key", ephemeral_key)?;
     ro.add_point("shared_secret", shared_secret)?;
-    ro.output_scalars(curve_type, count)
+    ro.output_scalars(plaintext_curve, count)
 }
 
 /// Compute the Proof Of Possession (PoP) base element
@@ -487,12 +499,12 @@ impl MEGaCiphertextSingle {
         dealer_index: NodeIndex,
         associated_data: &[u8],
     ) -> ThresholdEcdsaResult<Self> {
-        let curve_type = check_plaintexts(plaintexts, recipients)?;
+        let (key_curve, plaintext_curve) = check_plaintexts(plaintexts, recipients)?;
 
         let ctype = MEGaCiphertextType::Single;
 
         let (beta, v, pop_public_key, pop_proof) =
-            compute_eph_key_and_pop(ctype, curve_type, seed, associated_data, dealer_index)?;
+            compute_eph_key_and_pop(ctype, key_curve, seed, associated_data, dealer_index)?;
 
         let mut ctexts = Vec::with_capacity(recipients.len());
 
@@ -501,6 +513,7 @@ impl MEGaCiphertextSingle {
 
             let hm = mega_hash_to_scalars(
                 ctype,
+                plaintext_curve,
                 dealer_index,
                 index as NodeIndex,
                 associated_data,
@@ -553,6 +566,7 @@ impl MEGaCiphertextSingle {
 
         let hm = mega_hash_to_scalars(
             MEGaCiphertextType::Single,
+            self.ctexts[recipient_index as usize].curve_type(),
             dealer_index,
             recipient_index,
             associated_data,
@@ -598,12 +612,12 @@ impl MEGaCiphertextPair {
         dealer_index: NodeIndex,
         associated_data: &[u8],
     ) -> ThresholdEcdsaResult<Self> {
-        let curve_type = check_plaintexts_pair(plaintexts, recipients)?;
+        let (key_curve, plaintext_curve) = check_plaintexts_pair(plaintexts, recipients)?;
 
         let ctype = MEGaCiphertextType::Pairs;
 
         let (beta, v, pop_public_key, pop_proof) =
-            compute_eph_key_and_pop(ctype, curve_type, seed, associated_data, dealer_index)?;
+            compute_eph_key_and_pop(ctype, key_curve, seed, associated_data, dealer_index)?;
 
         let mut ctexts = Vec::with_capacity(recipients.len());
 
@@ -612,6 +626,7 @@ impl MEGaCiphertextPair {
 
             let hm = mega_hash_to_scalars(
                 ctype,
+                plaintext_curve,
                 dealer_index,
                 index as NodeIndex,
                 associated_data,
@@ -665,6 +680,7 @@ impl MEGaCiphertextPair {
 
         let hm = mega_hash_to_scalars(
             MEGaCiphertextType::Pairs,
+            self.ctexts[recipient_index as usize].0.curve_type(),
             dealer_index,
             recipient_index,
             associated_data,
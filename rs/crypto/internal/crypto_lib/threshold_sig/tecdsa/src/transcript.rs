@@ -404,7 +404,8 @@ impl CommitmentOpening {
         secret_key: &MEGaPrivateKey,
         public_key: &MEGaPublicKey,
     ) -> Result<Self, IDkgComputeSecretSharesInternalError> {
-        let curve = secret_key.curve_type();
+        // The secret may be on a different curve than our MEGa key
+        let curve = transcript_commitment.commitment().curve_type();
         let mut openings = Vec::with_capacity(verified_dealings.len());
 
         for (dealer_index, dealing) in verified_dealings {
@@ -482,7 +483,8 @@ impl CommitmentOpening {
         secret_key: &MEGaPrivateKey,
         public_key: &MEGaPublicKey,
     ) -> Result<Self, IDkgComputeSecretSharesInternalError> {
-        let curve = secret_key.curve_type();
+        // The secret may be on a different curve than our MEGa key
+        let curve = transcript_commitment.commitment().curve_type();
         let mut openings = Vec::with_capacity(verified_dealings.len());
 
         for (dealer_index, dealing) in verified_dealings {
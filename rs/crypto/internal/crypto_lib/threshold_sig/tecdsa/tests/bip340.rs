@@ -0,0 +1,280 @@
+use assert_matches::assert_matches;
+use ic_crypto_internal_threshold_sig_ecdsa::*;
+use ic_crypto_test_utils_reproducible_rng::reproducible_rng;
+use ic_types::crypto::AlgorithmId;
+use ic_types::*;
+use rand::Rng;
+use std::collections::BTreeMap;
+
+mod test_utils;
+
+use crate::test_utils::*;
+
+const ALG: AlgorithmId = AlgorithmId::ThresholdSchnorrBip340;
+
+struct Bip340SignatureProtocol {
+    key: ProtocolRound,
+    presig: ProtocolRound,
+    threshold: usize,
+    receivers: usize,
+}
+
+impl Bip340SignatureProtocol {
+    fn new(receivers: usize, threshold: usize) -> Result<Self, ThresholdEcdsaError> {
+        let seed = Seed::from_rng(&mut reproducible_rng());
+        let setup = ProtocolSetup::new(EccCurveType::K256, receivers, threshold, seed)?;
+
+        let key = ProtocolRound::random(&setup, receivers, 0)?;
+        let key = ProtocolRound::reshare_of_masked(&setup, &key, receivers, 0)?;
+
+        let presig = ProtocolRound::random(&setup, receivers, 0)?;
+        let presig = ProtocolRound::reshare_of_masked(&setup, &presig, receivers, 0)?;
+
+        Ok(Self {
+            key,
+            presig,
+            threshold,
+            receivers,
+        })
+    }
+
+    fn generate_shares(
+        &self,
+        path: &DerivationPath,
+        message: &[u8],
+        randomness: Randomness,
+    ) -> BTreeMap<NodeIndex, ThresholdBip340SignatureShareInternal> {
+        let mut shares = BTreeMap::new();
+
+        for node_index in 0..self.receivers {
+            let share = sign_share_bip340(
+                path,
+                message,
+                randomness,
+                &self.key.transcript,
+                &self.key.openings[node_index],
+                &self.presig.transcript,
+                &self.presig.openings[node_index],
+                ALG,
+            )
+            .expect("Failed to create sig share");
+
+            verify_signature_share_bip340(
+                &share,
+                path,
+                message,
+                randomness,
+                node_index as NodeIndex,
+                &self.key.transcript,
+                &self.presig.transcript,
+                ALG,
+            )
+            .expect("Signature share verification failed");
+
+            shares.insert(node_index as NodeIndex, share);
+        }
+
+        shares
+    }
+
+    fn combine(
+        &self,
+        path: &DerivationPath,
+        message: &[u8],
+        randomness: Randomness,
+        shares: &BTreeMap<NodeIndex, ThresholdBip340SignatureShareInternal>,
+    ) -> Result<
+        ThresholdBip340CombinedSignatureInternal,
+        ThresholdBip340CombineSigSharesInternalError,
+    > {
+        combine_sig_shares_bip340(
+            path,
+            message,
+            randomness,
+            &self.key.transcript,
+            &self.presig.transcript,
+            NumberOfNodes::from(self.threshold as u32),
+            shares,
+            ALG,
+        )
+    }
+}
+
+fn random_path() -> DerivationPath {
+    let mut rng = reproducible_rng();
+    DerivationPath::new_bip32(&[rng.gen::<u32>() >> 1, rng.gen::<u32>() >> 1])
+}
+
+#[test]
+fn should_verify_bip340_test_vector() {
+    // Test vector 0 from BIP340
+    let pk = hex::decode("F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9")
+        .expect("invalid hex");
+    let msg = [0u8; 32];
+    let sig = hex::decode("E907831F80848D1069A5371B402410364BDF1C5F8307B0084C55F1CE2DCA821525F66A4A85EA8B71E482A74F382D2CE5EBEEE8FDB2172F477DF4900D310536C0").expect("invalid hex");
+
+    assert!(bip340::verify_bip340_signature(&pk, &msg, &sig).is_ok());
+
+    let mut bad_sig = sig.clone();
+    bad_sig[63] ^= 1;
+    assert_matches!(
+        bip340::verify_bip340_signature(&pk, &msg, &bad_sig),
+        Err(ThresholdEcdsaError::InvalidSignature)
+    );
+
+    let mut bad_msg = msg;
+    bad_msg[0] ^= 1;
+    assert_matches!(
+        bip340::verify_bip340_signature(&pk, &bad_msg, &sig),
+        Err(ThresholdEcdsaError::InvalidSignature)
+    );
+}
+
+#[test]
+fn should_create_and_verify_threshold_bip340_signatures() -> Result<(), ThresholdEcdsaError> {
+    let mut rng = reproducible_rng();
+
+    for (receivers, threshold) in [(1, 1), (4, 2), (5, 3)] {
+        let protocol = Bip340SignatureProtocol::new(receivers, threshold)?;
+
+        let path = random_path();
+        let message = rng.gen::<[u8; 32]>();
+        let randomness = Randomness::from(rng.gen::<[u8; 32]>());
+
+        let shares = protocol.generate_shares(&path, &message, randomness);
+
+        let sig = protocol
+            .combine(&path, &message, randomness, &shares)
+            .expect("Failed to combine shares");
+
+        assert!(verify_threshold_signature_bip340(
+            &sig,
+            &path,
+            &message,
+            randomness,
+            &protocol.presig.transcript,
+            &protocol.key.transcript,
+            ALG,
+        )
+        .is_ok());
+
+        // The signature must also be a plain BIP340 signature under the
+        // derived public key
+        let pk = derive_bip340_public_key(&protocol.key.constant_term().serialize(), &path)
+            .expect("Failed to derive public key");
+        let sig_bytes = sig.serialize()?;
+        assert_eq!(sig_bytes.len(), bip340::BIP340_SIGNATURE_BYTES);
+        assert!(bip340::verify_bip340_signature(&pk.public_key, &message, &sig_bytes).is_ok());
+
+        assert_eq!(
+            ThresholdBip340CombinedSignatureInternal::deserialize(&sig_bytes)?,
+            sig
+        );
+
+        // A signature is not valid under other randomness
+        let other_randomness = Randomness::from(rng.gen::<[u8; 32]>());
+        assert_matches!(
+            verify_threshold_signature_bip340(
+                &sig,
+                &path,
+                &message,
+                other_randomness,
+                &protocol.presig.transcript,
+                &protocol.key.transcript,
+                ALG,
+            ),
+            Err(ThresholdBip340VerifySignatureInternalError::InvalidSignature)
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn should_reject_insufficient_bip340_shares() -> Result<(), ThresholdEcdsaError> {
+    let mut rng = reproducible_rng();
+    let protocol = Bip340SignatureProtocol::new(4, 3)?;
+
+    let path = random_path();
+    let message = rng.gen::<[u8; 32]>();
+    let randomness = Randomness::from(rng.gen::<[u8; 32]>());
+
+    let mut shares = protocol.generate_shares(&path, &message, randomness);
+    shares.remove(&0);
+    shares.remove(&1);
+
+    assert_matches!(
+        protocol.combine(&path, &message, randomness, &shares),
+        Err(ThresholdBip340CombineSigSharesInternalError::InsufficientShares)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn should_reject_bip340_share_for_other_signer_or_message() -> Result<(), ThresholdEcdsaError> {
+    let mut rng = reproducible_rng();
+    let protocol = Bip340SignatureProtocol::new(4, 2)?;
+
+    let path = random_path();
+    let message = rng.gen::<[u8; 32]>();
+    let randomness = Randomness::from(rng.gen::<[u8; 32]>());
+
+    let shares = protocol.generate_shares(&path, &message, randomness);
+    let share = shares.get(&0).expect("Missing share");
+
+    assert_matches!(
+        verify_signature_share_bip340(
+            share,
+            &path,
+            &message,
+            randomness,
+            1,
+            &protocol.key.transcript,
+            &protocol.presig.transcript,
+            ALG,
+        ),
+        Err(ThresholdBip340VerifySigShareInternalError::InvalidSignatureShare)
+    );
+
+    let other_message = rng.gen::<[u8; 32]>();
+    assert_matches!(
+        verify_signature_share_bip340(
+            share,
+            &path,
+            &other_message,
+            randomness,
+            0,
+            &protocol.key.transcript,
+            &protocol.presig.transcript,
+            ALG,
+        ),
+        Err(ThresholdBip340VerifySigShareInternalError::InvalidSignatureShare)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn should_reject_bip340_signing_with_ecdsa_algorithm() -> Result<(), ThresholdEcdsaError> {
+    let mut rng = reproducible_rng();
+    let protocol = Bip340SignatureProtocol::new(1, 1)?;
+
+    let result = sign_share_bip340(
+        &random_path(),
+        &rng.gen::<[u8; 32]>(),
+        Randomness::from(rng.gen::<[u8; 32]>()),
+        &protocol.key.transcript,
+        &protocol.key.openings[0],
+        &protocol.presig.transcript,
+        &protocol.presig.openings[0],
+        AlgorithmId::ThresholdEcdsaSecp256k1,
+    );
+
+    assert_matches!(
+        result,
+        Err(ThresholdBip340GenerateSigShareInternalError::UnsupportedAlgorithm)
+    );
+
+    Ok(())
+}
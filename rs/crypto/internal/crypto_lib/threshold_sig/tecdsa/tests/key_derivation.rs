@@ -25,21 +25,25 @@ fn test_index_next_behavior() {
 }
 
 #[test]
-fn test_that_key_derivation_on_secp256r1_currently_fails() -> Result<(), ThresholdEcdsaError> {
+fn test_that_key_derivation_on_secp256r1_is_incremental() -> Result<(), ThresholdEcdsaError> {
     let mut rng = reproducible_rng();
-    let path = DerivationPath::new_bip32(&[1, 2, 3]);
     let master_key = EccPoint::hash_to_point(
         EccCurveType::P256,
         &rng.gen::<[u8; 32]>(),
         "public_key".as_bytes(),
     )?;
 
-    assert_eq!(
-        path.derive_tweak(&master_key),
-        Err(ThresholdEcdsaError::InvalidArguments(
-            "Currently key derivation not defined for secp256r1".to_string()
-        ))
-    );
+    let (tweak, chain_key) = DerivationPath::new_bip32(&[1, 2, 3]).derive_tweak(&master_key)?;
+    assert_eq!(tweak.curve_type(), EccCurveType::P256);
+
+    // Deriving [1, 2] and then [3] from the intermediate key must give the same result
+    let (tweak_12, chain_key_12) = DerivationPath::new_bip32(&[1, 2]).derive_tweak(&master_key)?;
+    let key_12 = master_key.add_points(&EccPoint::mul_by_g(&tweak_12)?)?;
+    let (tweak_3, chain_key_3) =
+        DerivationPath::new_bip32(&[3]).derive_tweak_with_chain_code(&key_12, &chain_key_12)?;
+
+    assert_eq!(tweak_12.add(&tweak_3)?, tweak);
+    assert_eq!(chain_key_3, chain_key);
 
     Ok(())
 }
@@ -115,6 +115,59 @@ fn mega_single_smoke_test() -> Result<(), ThresholdEcdsaError> {
     Ok(())
 }
 
+#[test]
+fn mega_can_encrypt_secp256r1_plaintexts_to_secp256k1_keys() -> Result<(), ThresholdEcdsaError> {
+    let key_curve = EccCurveType::K256;
+    let plaintext_curve = EccCurveType::P256;
+
+    let mut rng = reproducible_rng();
+
+    let a_sk = MEGaPrivateKey::generate(key_curve, &mut rng);
+    let b_sk = MEGaPrivateKey::generate(key_curve, &mut rng);
+
+    let a_pk = a_sk.public_key()?;
+    let b_pk = b_sk.public_key()?;
+
+    let associated_data = b"assoc_data_test";
+
+    let ptext_for_a = (
+        EccScalar::random(plaintext_curve, &mut rng),
+        EccScalar::random(plaintext_curve, &mut rng),
+    );
+    let ptext_for_b = (
+        EccScalar::random(plaintext_curve, &mut rng),
+        EccScalar::random(plaintext_curve, &mut rng),
+    );
+
+    let dealer_index = 0;
+
+    let ctext = MEGaCiphertextPair::encrypt(
+        Seed::from_rng(&mut rng),
+        &[ptext_for_a.clone(), ptext_for_b.clone()],
+        &[a_pk.clone(), b_pk.clone()],
+        dealer_index,
+        associated_data,
+    )?;
+
+    assert_eq!(ctext.ephemeral_key.curve_type(), key_curve);
+
+    let mega = MEGaCiphertext::from(ctext.clone());
+    assert!(mega
+        .verify_is(MEGaCiphertextType::Pairs, key_curve, plaintext_curve)
+        .is_ok());
+    assert!(mega
+        .verify_is(MEGaCiphertextType::Pairs, key_curve, key_curve)
+        .is_err());
+
+    let ptext_a = ctext.decrypt(associated_data, dealer_index, 0, &a_sk, &a_pk)?;
+    assert_eq!(ptext_a, ptext_for_a);
+
+    let ptext_b = ctext.decrypt(associated_data, dealer_index, 1, &b_sk, &b_pk)?;
+    assert_eq!(ptext_b, ptext_for_b);
+
+    Ok(())
+}
+
 #[test]
 fn mega_pair_smoke_test() -> Result<(), ThresholdEcdsaError> {
     let curve = EccCurveType::K256;
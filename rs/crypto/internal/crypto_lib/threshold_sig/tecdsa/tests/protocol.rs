@@ -219,60 +219,63 @@ fn should_basic_signing_protocol_work() -> Result<(), ThresholdEcdsaError> {
         Ok(())
     }
 
-    let nodes = 10;
-    let threshold = nodes / 3;
-    let number_of_dealings_corrupted = threshold;
-
     let mut rng = reproducible_rng();
-    let random_seed = Seed::from_rng(&mut rng);
-
-    let setup = SignatureProtocolSetup::new(
-        EccCurveType::K256,
-        nodes,
-        threshold,
-        number_of_dealings_corrupted,
-        random_seed,
-    )?;
-
-    let alg = setup.alg();
-
-    let signed_message = rng.gen::<[u8; 32]>().to_vec();
-    let random_beacon = Randomness::from(rng.gen::<[u8; 32]>());
-
-    let derivation_path = DerivationPath::new_bip32(&[1, 2, 3]);
-    let proto = SignatureProtocolExecution::new(
-        setup.clone(),
-        signed_message.clone(),
-        random_beacon,
-        derivation_path.clone(),
-    );
-
-    let shares = proto.generate_shares()?;
-
-    for i in 0..=nodes {
-        let shares = random_subset(&shares, i);
-
-        if shares.len() < threshold {
-            assert!(proto.generate_signature(&shares).is_err());
-        } else {
-            let sig = proto.generate_signature(&shares).unwrap();
-            test_sig_serialization(alg, &sig)?;
-            assert!(proto.verify_signature(&sig).is_ok());
+
+    for curve in EccCurveType::all() {
+        let nodes = 10;
+        let threshold = nodes / 3;
+        let number_of_dealings_corrupted = threshold;
+
+        let random_seed = Seed::from_rng(&mut rng);
+
+        let setup = SignatureProtocolSetup::new(
+            curve,
+            nodes,
+            threshold,
+            number_of_dealings_corrupted,
+            random_seed,
+        )?;
+
+        let alg = setup.alg();
+
+        let signed_message = rng.gen::<[u8; 32]>().to_vec();
+        let random_beacon = Randomness::from(rng.gen::<[u8; 32]>());
+
+        let derivation_path = DerivationPath::new_bip32(&[1, 2, 3]);
+        let proto = SignatureProtocolExecution::new(
+            setup.clone(),
+            signed_message.clone(),
+            random_beacon,
+            derivation_path.clone(),
+        );
+
+        let shares = proto.generate_shares()?;
+
+        for i in 0..=nodes {
+            let shares = random_subset(&shares, i);
+
+            if shares.len() < threshold {
+                assert!(proto.generate_signature(&shares).is_err());
+            } else {
+                let sig = proto.generate_signature(&shares).unwrap();
+                test_sig_serialization(alg, &sig)?;
+                assert!(proto.verify_signature(&sig).is_ok());
+            }
         }
-    }
 
-    // Test that another run of the protocol generates signatures
-    // which are not verifiable in the earlier one (due to different rho)
-    let random_beacon2 = Randomness::from(rng.gen::<[u8; 32]>());
-    let proto2 =
-        SignatureProtocolExecution::new(setup, signed_message, random_beacon2, derivation_path);
+        // Test that another run of the protocol generates signatures
+        // which are not verifiable in the earlier one (due to different rho)
+        let random_beacon2 = Randomness::from(rng.gen::<[u8; 32]>());
+        let proto2 =
+            SignatureProtocolExecution::new(setup, signed_message, random_beacon2, derivation_path);
 
-    let shares = proto2.generate_shares()?;
-    let sig = proto2.generate_signature(&shares).unwrap();
-    test_sig_serialization(alg, &sig)?;
+        let shares = proto2.generate_shares()?;
+        let sig = proto2.generate_signature(&shares).unwrap();
+        test_sig_serialization(alg, &sig)?;
 
-    assert!(proto.verify_signature(&sig).is_err());
-    assert!(proto2.verify_signature(&sig).is_ok());
+        assert!(proto.verify_signature(&sig).is_err());
+        assert!(proto2.verify_signature(&sig).is_ok());
+    }
 
     Ok(())
 }
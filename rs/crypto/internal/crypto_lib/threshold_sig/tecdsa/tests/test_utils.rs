@@ -28,11 +28,7 @@ impl ProtocolSetup {
     ) -> Result<Self, ThresholdEcdsaError> {
         let alg = match curve {
             EccCurveType::K256 => AlgorithmId::ThresholdEcdsaSecp256k1,
-            _ => {
-                return Err(ThresholdEcdsaError::InvalidArguments(
-                    "Unsupported curve".to_string(),
-                ))
-            }
+            EccCurveType::P256 => AlgorithmId::ThresholdEcdsaSecp256r1,
         };
 
         let mut rng = seed.into_rng();
@@ -42,7 +38,7 @@ impl ProtocolSetup {
         let mut pk = Vec::with_capacity(receivers);
 
         for _i in 0..receivers {
-            let k = MEGaPrivateKey::generate(curve, &mut rng);
+            let k = MEGaPrivateKey::generate(MEGA_KEY_CURVE, &mut rng);
             pk.push(k.public_key()?);
             sk.push(k);
         }
@@ -737,8 +733,12 @@ impl SignatureProtocolSetup {
     }
 
     pub fn public_key(&self, path: &DerivationPath) -> Result<EcdsaPublicKey, ThresholdEcdsaError> {
+        let algorithm_id = match self.setup.alg {
+            AlgorithmId::ThresholdEcdsaSecp256r1 => AlgorithmId::EcdsaP256,
+            _ => AlgorithmId::EcdsaSecp256k1,
+        };
         let master_public_key = MasterEcdsaPublicKey {
-            algorithm_id: AlgorithmId::EcdsaSecp256k1,
+            algorithm_id,
             public_key: self.key.transcript.constant_term().serialize(),
         };
         ic_crypto_internal_threshold_sig_ecdsa::sign::derive_public_key(&master_public_key, path)
@@ -850,15 +850,30 @@ impl SignatureProtocolExecution {
         // If verification succeeded, check with RustCrypto's ECDSA also
         let pk = self.setup.public_key(&self.derivation_path)?;
 
-        use k256::ecdsa::signature::Verifier;
+        match self.setup.alg() {
+            AlgorithmId::ThresholdEcdsaSecp256r1 => {
+                use p256::ecdsa::signature::Verifier;
+
+                let vk = p256::ecdsa::VerifyingKey::from_sec1_bytes(&pk.public_key)
+                    .expect("Failed to parse public key");
+
+                let sig = p256::ecdsa::Signature::try_from(sig.serialize().as_ref())
+                    .expect("Failed to parse signature");
 
-        let vk = k256::ecdsa::VerifyingKey::from_sec1_bytes(&pk.public_key)
-            .expect("Failed to parse public key");
+                assert!(vk.verify(&self.signed_message, &sig).is_ok());
+            }
+            _ => {
+                use k256::ecdsa::signature::Verifier;
+
+                let vk = k256::ecdsa::VerifyingKey::from_sec1_bytes(&pk.public_key)
+                    .expect("Failed to parse public key");
 
-        let sig = k256::ecdsa::Signature::try_from(sig.serialize().as_ref())
-            .expect("Failed to parse signature");
+                let sig = k256::ecdsa::Signature::try_from(sig.serialize().as_ref())
+                    .expect("Failed to parse signature");
 
-        assert!(vk.verify(&self.signed_message, &sig).is_ok());
+                assert!(vk.verify(&self.signed_message, &sig).is_ok());
+            }
+        }
 
         Ok(())
     }
@@ -5,6 +5,7 @@ mod keygen;
 mod sign;
 mod threshold;
 mod tls;
+mod vetkd;
 
 pub use canister_threshold::{
     CspCreateMEGaKeyError, CspIDkgProtocol, CspThresholdEcdsaSigVerifier, CspThresholdEcdsaSigner,
@@ -18,3 +19,4 @@ pub use threshold::{
     threshold_sign_error::CspThresholdSignError, NiDkgCspClient, ThresholdSignatureCspClient,
 };
 pub use tls::CspTlsHandshakeSignerProvider;
+pub use vetkd::CspVetKdProtocol;
@@ -0,0 +1,19 @@
+use crate::key_id::KeyId;
+use crate::vault::api::VetKdEncryptedKeyShareCreationVaultError;
+use ic_types::crypto::canister_threshold_sig::ExtendedDerivationPath;
+use ic_types::crypto::vetkd::VetKdEncryptedKeyShare;
+
+/// Crypto service provider (CSP) client for vetKD encrypted key share
+/// generation.
+pub trait CspVetKdProtocol {
+    /// Generate an encrypted key share using the threshold secret key share
+    /// identified by `key_id`.
+    fn create_encrypted_vetkd_key_share(
+        &self,
+        key_id: KeyId,
+        master_public_key: Vec<u8>,
+        encryption_public_key: Vec<u8>,
+        derivation_path: ExtendedDerivationPath,
+        derivation_id: Vec<u8>,
+    ) -> Result<VetKdEncryptedKeyShare, VetKdEncryptedKeyShareCreationVaultError>;
+}
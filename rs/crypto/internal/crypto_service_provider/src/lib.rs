@@ -17,6 +17,7 @@ pub mod threshold;
 pub mod tls;
 pub mod types;
 pub mod vault;
+pub mod vetkd;
 
 pub use crate::vault::api::TlsHandshakeCspVault;
 pub use crate::vault::local_csp_vault::LocalCspVault;
@@ -26,8 +27,8 @@ use crate::vault::remote_csp_vault::RemoteCspVault;
 use crate::api::{
     CspIDkgProtocol, CspKeyGenerator, CspPublicAndSecretKeyStoreChecker, CspPublicKeyStore,
     CspSecretKeyStoreChecker, CspSigVerifier, CspSigner, CspThresholdEcdsaSigVerifier,
-    CspThresholdEcdsaSigner, CspTlsHandshakeSignerProvider, NiDkgCspClient, NodePublicKeyDataError,
-    ThresholdSignatureCspClient,
+    CspThresholdEcdsaSigner, CspTlsHandshakeSignerProvider, CspVetKdProtocol, NiDkgCspClient,
+    NodePublicKeyDataError, ThresholdSignatureCspClient,
 };
 use crate::public_key_store::proto_pubkey_store::ProtoPublicKeyStore;
 use crate::secret_key_store::SecretKeyStore;
@@ -42,6 +43,7 @@ use ic_logger::{info, new_logger, replica_logger::no_op_logger, ReplicaLogger};
 use ic_types::crypto::CurrentNodePublicKeys;
 use key_id::KeyId;
 use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use secret_key_store::pkcs11_store::Pkcs11BackedSecretKeyStore;
 use secret_key_store::proto_store::ProtoSecretKeyStore;
 use std::path::Path;
 use std::sync::Arc;
@@ -65,6 +67,7 @@ pub trait CryptoServiceProvider:
     + CspIDkgProtocol
     + CspThresholdEcdsaSigner
     + CspThresholdEcdsaSigVerifier
+    + CspVetKdProtocol
     + CspPublicAndSecretKeyStoreChecker
     + CspSecretKeyStoreChecker
     + CspPublicAndSecretKeyStoreChecker
@@ -81,6 +84,7 @@ impl<T> CryptoServiceProvider for T where
         + CspIDkgProtocol
         + CspThresholdEcdsaSigner
         + CspThresholdEcdsaSigVerifier
+        + CspVetKdProtocol
         + NiDkgCspClient
         + CspPublicAndSecretKeyStoreChecker
         + CspSecretKeyStoreChecker
@@ -195,11 +199,6 @@ impl Csp {
             logger,
             "Proceeding with an in-replica csp_vault, CryptoConfig: {:?}", config
         );
-        let secret_key_store = ProtoSecretKeyStore::open(
-            &config.crypto_root,
-            SKS_DATA_FILENAME,
-            Some(new_logger!(&logger)),
-        );
         let canister_key_store = ProtoSecretKeyStore::open(
             &config.crypto_root,
             CANISTER_SKS_DATA_FILENAME,
@@ -210,13 +209,31 @@ impl Csp {
             PUBLIC_KEY_STORE_DATA_FILENAME,
             new_logger!(&logger),
         );
-        let csp_vault = Arc::new(LocalCspVault::new(
-            secret_key_store,
-            canister_key_store,
-            public_key_store,
-            metrics.clone(),
-            new_logger!(&logger),
-        ));
+        let csp_vault: Arc<dyn CspVault> = match &config.hsm {
+            None => Arc::new(LocalCspVault::new(
+                ProtoSecretKeyStore::open(
+                    &config.crypto_root,
+                    SKS_DATA_FILENAME,
+                    Some(new_logger!(&logger)),
+                ),
+                canister_key_store,
+                public_key_store,
+                metrics.clone(),
+                new_logger!(&logger),
+            )),
+            Some(hsm_config) => Arc::new(LocalCspVault::new_with_pkcs11_node_keys(
+                Pkcs11BackedSecretKeyStore::open(
+                    &config.crypto_root,
+                    SKS_DATA_FILENAME,
+                    hsm_config,
+                    new_logger!(&logger),
+                ),
+                canister_key_store,
+                public_key_store,
+                metrics.clone(),
+                new_logger!(&logger),
+            )),
+        };
         Csp {
             csp_vault,
             logger,
@@ -1,8 +1,10 @@
 use crate::key_id::KeyId;
 use crate::secret_key_store::{
-    Scope, SecretKeyStore, SecretKeyStoreError, SecretKeyStorePersistenceError,
+    ExternalSigningError, Scope, SecretKeyStore, SecretKeyStoreError,
+    SecretKeyStorePersistenceError,
 };
 use crate::types::CspSecretKey;
+use ic_crypto_internal_basic_sig_ed25519::types as ed25519_types;
 use mockall::predicate::*;
 use mockall::*;
 
@@ -16,5 +18,6 @@ mock! {
         fn remove(&mut self, id: &KeyId) -> Result<bool, SecretKeyStorePersistenceError>;
         fn retain<F>(&mut self, filter: F, scope: Scope) -> Result<(), SecretKeyStorePersistenceError>
             where F: Fn(&KeyId, &CspSecretKey) -> bool + 'static;
+        fn sign_ed25519_externally(&self, id: &KeyId, message: &[u8]) -> Option<Result<ed25519_types::SignatureBytes, ExternalSigningError>>;
     }
 }
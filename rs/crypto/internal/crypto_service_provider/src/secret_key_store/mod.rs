@@ -2,11 +2,13 @@
 
 use crate::key_id::KeyId;
 use crate::types::CspSecretKey;
+use ic_crypto_internal_basic_sig_ed25519::types as ed25519_types;
 pub use ic_crypto_internal_types::scope;
 pub use scope::Scope;
 use std::fmt;
 
 // Implementations
+pub mod pkcs11_store;
 pub mod proto_store;
 #[cfg(test)]
 pub mod temp_secret_key_store;
@@ -110,6 +112,21 @@ pub trait SecretKeyStore: Send + Sync {
     {
         unimplemented!()
     }
+
+    /// Signs `message` with the Ed25519 key with the given `id` if the store
+    /// holds that key in an external signer (e.g., a hardware security module)
+    /// that does not reveal the key material.
+    ///
+    /// Returns `None` if the key with the given `id` is not held by an external
+    /// signer, in which case the key, if any, must be retrieved with
+    /// [`Self::get`].
+    fn sign_ed25519_externally(
+        &self,
+        _id: &KeyId,
+        _message: &[u8],
+    ) -> Option<Result<ed25519_types::SignatureBytes, ExternalSigningError>> {
+        None
+    }
 }
 
 /// Errors that can occur while interacting with the secret key store
@@ -156,6 +173,24 @@ impl fmt::Display for SecretKeyStorePersistenceError {
     }
 }
 
+/// Errors that can occur while signing with a key held by an external signer
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExternalSigningError {
+    pub internal_error: String,
+}
+
+impl std::error::Error for ExternalSigningError {}
+
+impl fmt::Display for ExternalSigningError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Error signing with external signer: {}",
+            self.internal_error
+        )
+    }
+}
+
 /// Panic with a message containing the duplicated [crate::key_id::KeyId].
 ///
 /// It's acceptable to panic when the type of key generation leading to this duplicated key
@@ -0,0 +1,499 @@
+//! Secret key store keeping node signing and TLS keys in a PKCS#11 token
+//!
+//! Ed25519 node signing keys and TLS keys are imported into the token as
+//! sensitive, non-extractable private keys whose `CKA_ID` is the [`KeyId`],
+//! and signing with them is delegated to the token. All other keys, in
+//! particular threshold keys and dealing encryption keys, are kept in the
+//! wrapped local secret key store.
+use crate::key_id::KeyId;
+use crate::public_key_store::proto_pubkey_store::ProtoPublicKeyStore;
+use crate::public_key_store::PublicKeyStore;
+use crate::secret_key_store::proto_store::ProtoSecretKeyStore;
+use crate::secret_key_store::{
+    ExternalSigningError, Scope, SecretKeyStore, SecretKeyStoreError,
+    SecretKeyStorePersistenceError,
+};
+use crate::types::{CspPublicKey, CspSecretKey};
+use crate::{PUBLIC_KEY_STORE_DATA_FILENAME, SKS_DATA_FILENAME};
+use ic_config::crypto::HsmConfig;
+use ic_crypto_internal_basic_sig_ed25519::types as ed25519_types;
+use ic_crypto_internal_tls::keygen::TlsEd25519SecretKeyDerBytes;
+use ic_crypto_secrets_containers::{SecretArray, SecretVec};
+use ic_crypto_tls_interfaces::TlsPublicKeyCert;
+use ic_logger::{info, new_logger, warn, ReplicaLogger};
+use openssl::pkey::PKey;
+use parking_lot::Mutex;
+use pkcs11::types::{
+    CKA_CLASS, CKA_EC_PARAMS, CKA_EXTRACTABLE, CKA_ID, CKA_KEY_TYPE, CKA_PRIVATE, CKA_SENSITIVE,
+    CKA_SIGN, CKA_TOKEN, CKA_VALUE, CKF_RW_SESSION, CKF_SERIAL_SESSION, CKO_PRIVATE_KEY, CKU_USER,
+    CK_ATTRIBUTE, CK_BBOOL, CK_FALSE, CK_KEY_TYPE, CK_MECHANISM, CK_MECHANISM_TYPE,
+    CK_OBJECT_HANDLE, CK_SESSION_HANDLE, CK_TRUE,
+};
+use pkcs11::Ctx;
+use std::fmt;
+use std::path::Path;
+
+#[cfg(test)]
+mod tests;
+
+// EdDSA constants from PKCS#11 v3.0, which are not defined by the `pkcs11` crate.
+const CKK_EC_EDWARDS: CK_KEY_TYPE = 0x0000_0040;
+const CKM_EDDSA: CK_MECHANISM_TYPE = 0x0000_1057;
+
+/// DER encoding of the OID 1.3.101.112 of Ed25519, used as `CKA_EC_PARAMS`.
+const ED25519_EC_PARAMS: [u8; 5] = [0x06, 0x03, 0x2b, 0x65, 0x70];
+
+/// Errors that can occur while interacting with a PKCS#11 token
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Pkcs11TokenError {
+    /// The token could not be opened, e.g., because the module could not be
+    /// loaded or the login failed.
+    OpenError(String),
+    /// An operation on an open token failed.
+    OperationError(String),
+}
+
+impl std::error::Error for Pkcs11TokenError {}
+
+impl fmt::Display for Pkcs11TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pkcs11TokenError::OpenError(e) => write!(f, "Error opening PKCS#11 token: {}", e),
+            Pkcs11TokenError::OperationError(e) => {
+                write!(f, "Error performing PKCS#11 token operation: {}", e)
+            }
+        }
+    }
+}
+
+struct Pkcs11Session {
+    ctx: Ctx,
+    handle: CK_SESSION_HANDLE,
+}
+
+/// A logged-in session with a PKCS#11 token holding Ed25519 private keys.
+///
+/// PKCS#11 sessions must not be used concurrently, so all operations are
+/// serialized on the single session.
+pub struct Pkcs11Token {
+    session: Mutex<Pkcs11Session>,
+}
+
+impl Pkcs11Token {
+    /// Loads the PKCS#11 module and logs in to the token as configured in `config`.
+    pub fn open(config: &HsmConfig) -> Result<Self, Pkcs11TokenError> {
+        let pin = SecretVec::new_and_zeroize_argument(
+            &mut std::fs::read(&config.pin_file).map_err(|e| {
+                Pkcs11TokenError::OpenError(format!(
+                    "failed to read PIN file {}: {}",
+                    config.pin_file.display(),
+                    e
+                ))
+            })?,
+        );
+        let pin = std::str::from_utf8(pin.expose_secret())
+            .map_err(|_| Pkcs11TokenError::OpenError("PIN is not valid UTF-8".to_string()))?
+            .trim_end();
+        let ctx = Ctx::new_and_initialize(&config.pkcs11_module_path).map_err(|e| {
+            Pkcs11TokenError::OpenError(format!(
+                "failed to load PKCS#11 module {}: {}",
+                config.pkcs11_module_path.display(),
+                e
+            ))
+        })?;
+        let handle = ctx
+            .open_session(
+                config.slot_id,
+                CKF_SERIAL_SESSION | CKF_RW_SESSION,
+                None,
+                None,
+            )
+            .map_err(|e| {
+                Pkcs11TokenError::OpenError(format!(
+                    "failed to open session on slot {}: {}",
+                    config.slot_id, e
+                ))
+            })?;
+        ctx.login(handle, CKU_USER, Some(pin))
+            .map_err(|e| Pkcs11TokenError::OpenError(format!("failed to log in: {}", e)))?;
+        Ok(Pkcs11Token {
+            session: Mutex::new(Pkcs11Session { ctx, handle }),
+        })
+    }
+
+    /// Imports the Ed25519 `secret_key` into the token as a sensitive,
+    /// non-extractable private key identified by `id`.
+    pub fn import_ed25519_key(
+        &self,
+        id: &KeyId,
+        secret_key: &ed25519_types::SecretKeyBytes,
+    ) -> Result<(), Pkcs11TokenError> {
+        let class = CKO_PRIVATE_KEY;
+        let key_type = CKK_EC_EDWARDS;
+        let id_bytes = id.get();
+        let (yes, no): (CK_BBOOL, CK_BBOOL) = (CK_TRUE, CK_FALSE);
+        let template = vec![
+            CK_ATTRIBUTE::new(CKA_CLASS).with_ck_ulong(&class),
+            CK_ATTRIBUTE::new(CKA_KEY_TYPE).with_ck_ulong(&key_type),
+            CK_ATTRIBUTE::new(CKA_ID).with_bytes(&id_bytes),
+            CK_ATTRIBUTE::new(CKA_TOKEN).with_bool(&yes),
+            CK_ATTRIBUTE::new(CKA_PRIVATE).with_bool(&yes),
+            CK_ATTRIBUTE::new(CKA_SENSITIVE).with_bool(&yes),
+            CK_ATTRIBUTE::new(CKA_EXTRACTABLE).with_bool(&no),
+            CK_ATTRIBUTE::new(CKA_SIGN).with_bool(&yes),
+            CK_ATTRIBUTE::new(CKA_EC_PARAMS).with_bytes(&ED25519_EC_PARAMS),
+            CK_ATTRIBUTE::new(CKA_VALUE).with_bytes(secret_key.0.expose_secret()),
+        ];
+        let session = self.session.lock();
+        session
+            .ctx
+            .create_object(session.handle, &template)
+            .map(|_handle| ())
+            .map_err(|e| {
+                Pkcs11TokenError::OperationError(format!("failed to import key {}: {}", id, e))
+            })
+    }
+
+    /// Checks whether the token holds a private key identified by `id`.
+    pub fn contains(&self, id: &KeyId) -> Result<bool, Pkcs11TokenError> {
+        let session = self.session.lock();
+        Ok(find_private_key(&session, id)?.is_some())
+    }
+
+    /// Destroys the private key identified by `id`.
+    ///
+    /// Returns whether the token held such a key.
+    pub fn destroy_key(&self, id: &KeyId) -> Result<bool, Pkcs11TokenError> {
+        let session = self.session.lock();
+        match find_private_key(&session, id)? {
+            Some(object) => {
+                session
+                    .ctx
+                    .destroy_object(session.handle, object)
+                    .map_err(|e| {
+                        Pkcs11TokenError::OperationError(format!(
+                            "failed to destroy key {}: {}",
+                            id, e
+                        ))
+                    })?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Signs `message` with the Ed25519 private key identified by `id`.
+    ///
+    /// Returns `None` if the token does not hold such a key.
+    pub fn sign_ed25519(
+        &self,
+        id: &KeyId,
+        message: &[u8],
+    ) -> Result<Option<ed25519_types::SignatureBytes>, Pkcs11TokenError> {
+        let session = self.session.lock();
+        let object = match find_private_key(&session, id)? {
+            Some(object) => object,
+            None => return Ok(None),
+        };
+        let mechanism = CK_MECHANISM {
+            mechanism: CKM_EDDSA,
+            pParameter: std::ptr::null_mut(),
+            ulParameterLen: 0,
+        };
+        let signature = session
+            .ctx
+            .sign_init(session.handle, &mechanism, object)
+            .and_then(|()| session.ctx.sign(session.handle, message))
+            .map_err(|e| {
+                Pkcs11TokenError::OperationError(format!("failed to sign with key {}: {}", id, e))
+            })?;
+        let signature: [u8; ed25519_types::SignatureBytes::SIZE] =
+            signature.as_slice().try_into().map_err(|_| {
+                Pkcs11TokenError::OperationError(format!(
+                    "invalid Ed25519 signature length: expected {} bytes, but got {}",
+                    ed25519_types::SignatureBytes::SIZE,
+                    signature.len()
+                ))
+            })?;
+        Ok(Some(ed25519_types::SignatureBytes(signature)))
+    }
+}
+
+fn find_private_key(
+    session: &Pkcs11Session,
+    id: &KeyId,
+) -> Result<Option<CK_OBJECT_HANDLE>, Pkcs11TokenError> {
+    let class = CKO_PRIVATE_KEY;
+    let id_bytes = id.get();
+    let template = vec![
+        CK_ATTRIBUTE::new(CKA_CLASS).with_ck_ulong(&class),
+        CK_ATTRIBUTE::new(CKA_ID).with_bytes(&id_bytes),
+    ];
+    let to_error = |e: pkcs11::errors::Error| {
+        Pkcs11TokenError::OperationError(format!("failed to look up key {}: {}", id, e))
+    };
+    session
+        .ctx
+        .find_objects_init(session.handle, &template)
+        .map_err(to_error)?;
+    let objects = session.ctx.find_objects(session.handle, 1);
+    session
+        .ctx
+        .find_objects_final(session.handle)
+        .map_err(to_error)?;
+    Ok(objects.map_err(to_error)?.first().copied())
+}
+
+/// A secret key store that holds node signing and TLS keys in a PKCS#11
+/// token and all other keys in a local secret key store.
+///
+/// Keys held in the token never leave it: [`SecretKeyStore::get`] does not
+/// return them, and signatures are computed by the token via
+/// [`SecretKeyStore::sign_ed25519_externally`].
+pub struct Pkcs11BackedSecretKeyStore<S: SecretKeyStore> {
+    local_store: S,
+    token: Pkcs11Token,
+    logger: ReplicaLogger,
+}
+
+impl<S: SecretKeyStore> Pkcs11BackedSecretKeyStore<S> {
+    pub fn new(local_store: S, token: Pkcs11Token, logger: ReplicaLogger) -> Self {
+        Pkcs11BackedSecretKeyStore {
+            local_store,
+            token,
+            logger,
+        }
+    }
+
+    /// Returns the local store holding all keys not held in the token.
+    pub fn local_store(&self) -> &S {
+        &self.local_store
+    }
+}
+
+impl Pkcs11BackedSecretKeyStore<ProtoSecretKeyStore> {
+    /// Opens the token configured in `hsm_config` and the secret key store
+    /// with the given `file_name` in `dir`.
+    ///
+    /// # Panics
+    /// If the token cannot be opened or the secret key store cannot be opened
+    /// (see [`ProtoSecretKeyStore::open`]).
+    pub fn open(
+        dir: &Path,
+        file_name: &str,
+        hsm_config: &HsmConfig,
+        logger: ReplicaLogger,
+    ) -> Self {
+        let local_store = ProtoSecretKeyStore::open(dir, file_name, Some(new_logger!(&logger)));
+        let token = Pkcs11Token::open(hsm_config)
+            .unwrap_or_else(|e| panic!("failed to open PKCS#11 token: {}", e));
+        Self::new(local_store, token, logger)
+    }
+}
+
+impl<S: SecretKeyStore> SecretKeyStore for Pkcs11BackedSecretKeyStore<S> {
+    fn insert(
+        &mut self,
+        id: KeyId,
+        key: CspSecretKey,
+        scope: Option<Scope>,
+    ) -> Result<(), SecretKeyStoreError> {
+        if scope.is_some() {
+            return self.local_store.insert(id, key, scope);
+        }
+        let secret_key = match &key {
+            CspSecretKey::Ed25519(secret_key) => secret_key.clone(),
+            CspSecretKey::TlsEd25519(secret_key_der) => {
+                ed25519_secret_key_from_tls_der(secret_key_der).map_err(|e| {
+                    SecretKeyStoreError::PersistenceError(
+                        SecretKeyStorePersistenceError::SerializationError(e),
+                    )
+                })?
+            }
+            _ => return self.local_store.insert(id, key, scope),
+        };
+        let held_by_token = self.token.contains(&id).map_err(|e| {
+            SecretKeyStoreError::PersistenceError(SecretKeyStorePersistenceError::IoError(
+                e.to_string(),
+            ))
+        })?;
+        if held_by_token || self.local_store.contains(&id) {
+            return Err(SecretKeyStoreError::DuplicateKeyId(id));
+        }
+        self.token
+            .import_ed25519_key(&id, &secret_key)
+            .map_err(|e| {
+                SecretKeyStoreError::PersistenceError(SecretKeyStorePersistenceError::IoError(
+                    e.to_string(),
+                ))
+            })?;
+        info!(
+            self.logger,
+            "Stored {} with key ID {} in PKCS#11 token",
+            key.enum_variant(),
+            id
+        );
+        Ok(())
+    }
+
+    fn get(&self, id: &KeyId) -> Option<CspSecretKey> {
+        self.local_store.get(id)
+    }
+
+    /// A key that cannot be looked up in the token, e.g., because the token
+    /// is unavailable, is reported as not contained.
+    fn contains(&self, id: &KeyId) -> bool {
+        if self.local_store.contains(id) {
+            return true;
+        }
+        match self.token.contains(id) {
+            Ok(held_by_token) => held_by_token,
+            Err(e) => {
+                warn!(
+                    self.logger,
+                    "Failed to look up key with ID {} in PKCS#11 token: {}", id, e
+                );
+                false
+            }
+        }
+    }
+
+    fn remove(&mut self, id: &KeyId) -> Result<bool, SecretKeyStorePersistenceError> {
+        let removed_locally = self.local_store.remove(id)?;
+        let removed_from_token = self
+            .token
+            .destroy_key(id)
+            .map_err(|e| SecretKeyStorePersistenceError::IoError(e.to_string()))?;
+        Ok(removed_locally || removed_from_token)
+    }
+
+    fn retain<F>(&mut self, filter: F, scope: Scope) -> Result<(), SecretKeyStorePersistenceError>
+    where
+        F: Fn(&KeyId, &CspSecretKey) -> bool + 'static,
+    {
+        // Keys with a scope are never held in the token.
+        self.local_store.retain(filter, scope)
+    }
+
+    fn sign_ed25519_externally(
+        &self,
+        id: &KeyId,
+        message: &[u8],
+    ) -> Option<Result<ed25519_types::SignatureBytes, ExternalSigningError>> {
+        match self.token.sign_ed25519(id, message) {
+            Ok(Some(signature)) => Some(Ok(signature)),
+            Ok(None) => None,
+            Err(e) => Some(Err(ExternalSigningError {
+                internal_error: e.to_string(),
+            })),
+        }
+    }
+}
+
+fn ed25519_secret_key_from_tls_der(
+    secret_key_der: &TlsEd25519SecretKeyDerBytes,
+) -> Result<ed25519_types::SecretKeyBytes, String> {
+    let raw_private_key = SecretVec::new_and_zeroize_argument(
+        &mut PKey::private_key_from_der(secret_key_der.bytes.expose_secret())
+            .and_then(|pkey| pkey.raw_private_key())
+            .map_err(|_ignore_error_to_prevent_key_leakage| {
+                "failed to convert TLS secret key DER to raw Ed25519 secret key".to_string()
+            })?,
+    );
+    let mut sk_bytes: [u8; ed25519_types::SecretKeyBytes::SIZE] =
+        raw_private_key.expose_secret().try_into().map_err(|_| {
+            format!(
+                "invalid length of raw TLS secret key: expected {} bytes, but got {}",
+                ed25519_types::SecretKeyBytes::SIZE,
+                raw_private_key.expose_secret().len()
+            )
+        })?;
+    Ok(ed25519_types::SecretKeyBytes(
+        SecretArray::new_and_zeroize_argument(&mut sk_bytes),
+    ))
+}
+
+/// Moves the node signing and TLS secret keys of the node whose crypto state
+/// is in `crypto_root` from the secret key store on the file system into the
+/// PKCS#11 token configured in `hsm_config`.
+///
+/// The keys are identified via the node's public key store. Each key is first
+/// imported into the token and only then removed from the file system, so the
+/// migration can safely be re-run if it was interrupted. Returns the IDs of
+/// the migrated keys.
+///
+/// The CSP vault MUST NOT be running while the keys are migrated.
+pub fn migrate_node_keys_to_pkcs11_token(
+    crypto_root: &Path,
+    hsm_config: &HsmConfig,
+    logger: ReplicaLogger,
+) -> Result<Vec<KeyId>, Pkcs11TokenError> {
+    let public_key_store = ProtoPublicKeyStore::open(
+        crypto_root,
+        PUBLIC_KEY_STORE_DATA_FILENAME,
+        new_logger!(&logger),
+    );
+    let mut secret_key_store =
+        ProtoSecretKeyStore::open(crypto_root, SKS_DATA_FILENAME, Some(new_logger!(&logger)));
+    let token = Pkcs11Token::open(hsm_config)?;
+
+    let mut key_ids = vec![];
+    if let Some(node_signing_pk) = public_key_store.node_signing_pubkey() {
+        let key_id = CspPublicKey::try_from(&node_signing_pk)
+            .map_err(|e| format!("{:?}", e))
+            .and_then(|pk| KeyId::try_from(&pk).map_err(|e| format!("{:?}", e)))
+            .map_err(|e| {
+                Pkcs11TokenError::OperationError(format!(
+                    "malformed node signing public key: {}",
+                    e
+                ))
+            })?;
+        key_ids.push(key_id);
+    }
+    if let Some(tls_certificate) = public_key_store.tls_certificate() {
+        let key_id = TlsPublicKeyCert::new_from_der(tls_certificate.certificate_der)
+            .map_err(|e| format!("{:?}", e))
+            .and_then(|cert| KeyId::try_from(&cert).map_err(|e| format!("{:?}", e)))
+            .map_err(|e| {
+                Pkcs11TokenError::OperationError(format!("malformed TLS certificate: {}", e))
+            })?;
+        key_ids.push(key_id);
+    }
+
+    let mut migrated_key_ids = vec![];
+    for key_id in key_ids {
+        let secret_key = match secret_key_store.get(&key_id) {
+            Some(CspSecretKey::Ed25519(secret_key)) => secret_key,
+            Some(CspSecretKey::TlsEd25519(secret_key_der)) => {
+                ed25519_secret_key_from_tls_der(&secret_key_der)
+                    .map_err(Pkcs11TokenError::OperationError)?
+            }
+            Some(other) => {
+                return Err(Pkcs11TokenError::OperationError(format!(
+                    "unexpected secret key type {} for key ID {}",
+                    other.enum_variant(),
+                    key_id
+                )))
+            }
+            None => {
+                info!(
+                    logger,
+                    "Key with ID {} is not in the secret key store, skipping", key_id
+                );
+                continue;
+            }
+        };
+        if !token.contains(&key_id)? {
+            token.import_ed25519_key(&key_id, &secret_key)?;
+        }
+        secret_key_store.remove(&key_id).map_err(|e| {
+            Pkcs11TokenError::OperationError(format!(
+                "failed to remove key {} from the secret key store: {}",
+                key_id, e
+            ))
+        })?;
+        info!(logger, "Migrated key with ID {} to PKCS#11 token", key_id);
+        migrated_key_ids.push(key_id);
+    }
+    Ok(migrated_key_ids)
+}
@@ -0,0 +1,245 @@
+#![allow(clippy::unwrap_used)]
+use super::*;
+use crate::secret_key_store::temp_secret_key_store::TempSecretKeyStore;
+use crate::secret_key_store::test_utils::make_key_id;
+use assert_matches::assert_matches;
+use ic_crypto_internal_basic_sig_ed25519 as ed25519;
+use ic_crypto_internal_multi_sig_bls12381::types::SecretKeyBytes as MultiBlsSecretKeyBytes;
+use ic_logger::replica_logger::no_op_logger;
+use pkcs11::types::{CKF_TOKEN_INITIALIZED, CKU_SO};
+use rand::{thread_rng, Rng};
+use std::path::PathBuf;
+
+#[test]
+fn open_should_fail_if_pin_file_does_not_exist() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = HsmConfig {
+        pkcs11_module_path: PathBuf::from("/does/not/exist.so"),
+        slot_id: 0,
+        pin_file: dir.path().join("pin"),
+    };
+
+    let result = Pkcs11Token::open(&config);
+
+    assert_matches!(result, Err(Pkcs11TokenError::OpenError(e)) if e.contains("PIN file"));
+}
+
+#[test]
+fn open_should_fail_if_module_cannot_be_loaded() {
+    let dir = tempfile::tempdir().unwrap();
+    let pin_file = dir.path().join("pin");
+    std::fs::write(&pin_file, "1234\n").unwrap();
+    let config = HsmConfig {
+        pkcs11_module_path: dir.path().join("no_such_module.so"),
+        slot_id: 0,
+        pin_file,
+    };
+
+    let result = Pkcs11Token::open(&config);
+
+    assert_matches!(result, Err(Pkcs11TokenError::OpenError(e)) if e.contains("PKCS#11 module"));
+}
+
+#[test]
+fn should_convert_tls_secret_key_der_to_raw_ed25519_secret_key() {
+    let key_pair = PKey::generate_ed25519().unwrap();
+    let secret_key_der = TlsEd25519SecretKeyDerBytes::new(key_pair.private_key_to_der().unwrap());
+
+    let secret_key = ed25519_secret_key_from_tls_der(&secret_key_der).unwrap();
+
+    assert_eq!(
+        secret_key.0.expose_secret().to_vec(),
+        key_pair.raw_private_key().unwrap()
+    );
+}
+
+#[test]
+fn should_fail_to_convert_malformed_tls_secret_key_der() {
+    let secret_key_der = TlsEd25519SecretKeyDerBytes::new(b"invalid DER encoding".to_vec());
+
+    assert!(ed25519_secret_key_from_tls_der(&secret_key_der).is_err());
+}
+
+// The following tests run against a SoftHSM token that is initialized in a
+// temporary directory. The SoftHSM module is loaded from
+// `PKCS11_TEST_MODULE`, which defaults to the path of the module installed
+// by the Ubuntu `softhsm2` package.
+
+#[test]
+fn should_hold_node_signing_key_in_token_and_sign_verifiably() {
+    let token = TEST_TOKEN.lock();
+    let mut store = store_backed_by(&token);
+    let (secret_key, public_key) = ed25519::keypair_from_rng(&mut thread_rng());
+    let key_id = make_key_id(thread_rng().gen());
+
+    store
+        .insert(key_id, CspSecretKey::Ed25519(secret_key), None)
+        .unwrap();
+
+    assert!(store.contains(&key_id));
+    assert!(!store.local_store().contains(&key_id));
+    assert!(store.get(&key_id).is_none());
+    let signature = store
+        .sign_ed25519_externally(&key_id, b"message")
+        .expect("key not held by token")
+        .unwrap();
+    assert!(ed25519::verify(&signature, b"message", &public_key).is_ok());
+
+    assert!(store.remove(&key_id).unwrap());
+}
+
+#[test]
+fn should_hold_tls_key_in_token_and_sign_verifiably() {
+    let token = TEST_TOKEN.lock();
+    let mut store = store_backed_by(&token);
+    let key_pair = PKey::generate_ed25519().unwrap();
+    let secret_key_der = TlsEd25519SecretKeyDerBytes::new(key_pair.private_key_to_der().unwrap());
+    let public_key =
+        ed25519_types::PublicKeyBytes(key_pair.raw_public_key().unwrap().try_into().unwrap());
+    let key_id = make_key_id(thread_rng().gen());
+
+    store
+        .insert(key_id, CspSecretKey::TlsEd25519(secret_key_der), None)
+        .unwrap();
+
+    assert!(!store.local_store().contains(&key_id));
+    let signature = store
+        .sign_ed25519_externally(&key_id, b"message")
+        .expect("key not held by token")
+        .unwrap();
+    assert!(ed25519::verify(&signature, b"message", &public_key).is_ok());
+
+    assert!(store.remove(&key_id).unwrap());
+}
+
+#[test]
+fn should_keep_other_keys_in_local_store() {
+    let token = TEST_TOKEN.lock();
+    let mut store = store_backed_by(&token);
+    let key_id = make_key_id(thread_rng().gen());
+    let secret_key = CspSecretKey::MultiBls12_381(MultiBlsSecretKeyBytes::new(
+        SecretArray::new_and_dont_zeroize_argument(&thread_rng().gen()),
+    ));
+
+    store.insert(key_id, secret_key.clone(), None).unwrap();
+
+    assert_eq!(store.local_store().get(&key_id), Some(secret_key));
+    assert!(store.sign_ed25519_externally(&key_id, b"message").is_none());
+}
+
+#[test]
+fn should_return_duplicate_error_if_token_already_holds_key() {
+    let token = TEST_TOKEN.lock();
+    let mut store = store_backed_by(&token);
+    let (secret_key, _public_key) = ed25519::keypair_from_rng(&mut thread_rng());
+    let key_id = make_key_id(thread_rng().gen());
+    store
+        .insert(key_id, CspSecretKey::Ed25519(secret_key.clone()), None)
+        .unwrap();
+
+    let result = store.insert(key_id, CspSecretKey::Ed25519(secret_key), None);
+
+    assert_matches!(result, Err(SecretKeyStoreError::DuplicateKeyId(id)) if id == key_id);
+    assert!(store.remove(&key_id).unwrap());
+}
+
+#[test]
+fn should_report_key_as_not_contained_if_token_lookup_fails() {
+    let token = TEST_TOKEN.lock();
+    let mut store = store_backed_by(&token);
+    let (secret_key, _public_key) = ed25519::keypair_from_rng(&mut thread_rng());
+    let key_id = make_key_id(thread_rng().gen());
+    close_token_session(&store);
+
+    assert!(!store.contains(&key_id));
+    assert_matches!(
+        store.insert(key_id, CspSecretKey::Ed25519(secret_key), None),
+        Err(SecretKeyStoreError::PersistenceError(
+            SecretKeyStorePersistenceError::IoError(e)
+        )) if e.contains("failed to look up key")
+    );
+}
+
+/// Closes the session of the store's token, so that all further token
+/// operations fail.
+fn close_token_session(store: &Pkcs11BackedSecretKeyStore<TempSecretKeyStore>) {
+    let session = store.token.session.lock();
+    session.ctx.close_session(session.handle).unwrap();
+}
+
+const DEFAULT_SOFTHSM_MODULE: &str = "/usr/lib/softhsm/libsofthsm2.so";
+const TEST_TOKEN_LABEL: &str = "ic-crypto-test";
+const TEST_TOKEN_SO_PIN: &str = "5678";
+const TEST_TOKEN_USER_PIN: &str = "1234";
+
+/// A SoftHSM token initialized in a temporary directory.
+///
+/// A PKCS#11 module can only be initialized once per process, so tests using
+/// the token must hold the lock on [`TEST_TOKEN`] while they use it.
+struct TestToken {
+    _dir: tempfile::TempDir,
+    config: HsmConfig,
+}
+
+lazy_static::lazy_static! {
+    static ref TEST_TOKEN: Mutex<TestToken> = Mutex::new(TestToken::new());
+}
+
+impl TestToken {
+    fn new() -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let token_dir = dir.path().join("tokens");
+        std::fs::create_dir(&token_dir).unwrap();
+        let softhsm_config = dir.path().join("softhsm2.conf");
+        std::fs::write(
+            &softhsm_config,
+            format!(
+                "directories.tokendir = {}\nobjectstore.backend = file\nlog.level = ERROR\n",
+                token_dir.display()
+            ),
+        )
+        .unwrap();
+        std::env::set_var("SOFTHSM2_CONF", &softhsm_config);
+        let pin_file = dir.path().join("pin");
+        std::fs::write(&pin_file, format!("{}\n", TEST_TOKEN_USER_PIN)).unwrap();
+
+        let pkcs11_module_path = std::env::var("PKCS11_TEST_MODULE")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from(DEFAULT_SOFTHSM_MODULE));
+        let ctx = Ctx::new_and_initialize(&pkcs11_module_path).unwrap();
+        let free_slot = *ctx.get_slot_list(true).unwrap().first().unwrap();
+        ctx.init_token(free_slot, Some(TEST_TOKEN_SO_PIN), TEST_TOKEN_LABEL)
+            .unwrap();
+        // SoftHSM moves an initialized token to a new slot.
+        let slot_id = ctx
+            .get_slot_list(true)
+            .unwrap()
+            .into_iter()
+            .find(|slot| ctx.get_token_info(*slot).unwrap().flags & CKF_TOKEN_INITIALIZED != 0)
+            .unwrap();
+        let session = ctx
+            .open_session(slot_id, CKF_SERIAL_SESSION | CKF_RW_SESSION, None, None)
+            .unwrap();
+        ctx.login(session, CKU_SO, Some(TEST_TOKEN_SO_PIN)).unwrap();
+        ctx.init_pin(session, Some(TEST_TOKEN_USER_PIN)).unwrap();
+        ctx.logout(session).unwrap();
+        ctx.close_session(session).unwrap();
+
+        TestToken {
+            _dir: dir,
+            config: HsmConfig {
+                pkcs11_module_path,
+                slot_id,
+                pin_file,
+            },
+        }
+    }
+}
+
+fn store_backed_by(token: &TestToken) -> Pkcs11BackedSecretKeyStore<TempSecretKeyStore> {
+    Pkcs11BackedSecretKeyStore::new(
+        TempSecretKeyStore::new(),
+        Pkcs11Token::open(&token.config).unwrap(),
+        no_op_logger(),
+    )
+}
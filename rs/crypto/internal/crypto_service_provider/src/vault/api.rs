@@ -23,6 +23,7 @@ use ic_types::crypto::canister_threshold_sig::error::{
     IDkgVerifyDealingPrivateError, ThresholdEcdsaSignShareError,
 };
 use ic_types::crypto::canister_threshold_sig::ExtendedDerivationPath;
+use ic_types::crypto::vetkd::VetKdEncryptedKeyShare;
 use ic_types::crypto::{AlgorithmId, CryptoError, CurrentNodePublicKeys};
 use ic_types::{NodeId, NodeIndex, NumberOfNodes, Randomness};
 use serde::{Deserialize, Serialize};
@@ -373,6 +374,7 @@ pub trait CspVault:
     + NiDkgCspVault
     + IDkgProtocolCspVault
     + ThresholdEcdsaSignerCspVault
+    + VetKdCspVault
     + SecretKeyStoreCspVault
     + TlsHandshakeCspVault
     + PublicRandomSeedGenerator
@@ -390,6 +392,7 @@ impl<T> CspVault for T where
         + NiDkgCspVault
         + IDkgProtocolCspVault
         + ThresholdEcdsaSignerCspVault
+        + VetKdCspVault
         + SecretKeyStoreCspVault
         + TlsHandshakeCspVault
         + PublicRandomSeedGenerator
@@ -854,6 +857,40 @@ pub trait ThresholdEcdsaSignerCspVault {
     ) -> Result<ThresholdEcdsaSigShareInternal, ThresholdEcdsaSignShareError>;
 }
 
+/// Operations of `CspVault` related to vetKD (cf. `VetKdProtocol`).
+pub trait VetKdCspVault {
+    /// Creates an encrypted vetKD key share using the threshold secret key
+    /// share identified by `key_id`.
+    ///
+    /// # Arguments
+    /// * `key_id` identifies this node's share of the master secret key, which
+    ///   is the threshold key of an NI-DKG transcript.
+    /// * `master_public_key` is the (serialized) master public key, i.e., the
+    ///   combined public key of the transcript.
+    /// * `encryption_public_key` is the (serialized) transport public key the
+    ///   key share is encrypted under.
+    /// * `derivation_path` and `derivation_id` determine the derived key.
+    fn create_encrypted_vetkd_key_share(
+        &self,
+        key_id: KeyId,
+        master_public_key: Vec<u8>,
+        encryption_public_key: Vec<u8>,
+        derivation_path: ExtendedDerivationPath,
+        derivation_id: Vec<u8>,
+    ) -> Result<VetKdEncryptedKeyShare, VetKdEncryptedKeyShareCreationVaultError>;
+}
+
+/// An error returned by failing to create an encrypted vetKD key share.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum VetKdEncryptedKeyShareCreationVaultError {
+    /// The secret key share is missing or has the wrong type.
+    SecretKeyMissingOrWrongType(String),
+    /// An argument is malformed, e.g. the encryption public key.
+    InvalidArgument(String),
+    /// Transient internal error, e.g., an RPC error.
+    TransientInternalError(String),
+}
+
 /// An error returned by failing to generate a public seed from [`CspVault`].
 #[derive(Serialize, Deserialize, Debug)]
 pub enum PublicRandomSeedGeneratorError {
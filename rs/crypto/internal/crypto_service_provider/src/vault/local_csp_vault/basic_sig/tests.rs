@@ -6,7 +6,7 @@ use crate::public_key_store::PublicKeySetOnceError;
 use crate::secret_key_store::mock_secret_key_store::MockSecretKeyStore;
 use crate::secret_key_store::temp_secret_key_store::TempSecretKeyStore;
 use crate::secret_key_store::{
-    SecretKeyStore, SecretKeyStoreError, SecretKeyStorePersistenceError,
+    ExternalSigningError, SecretKeyStore, SecretKeyStoreError, SecretKeyStorePersistenceError,
 };
 use crate::types::{CspPublicKey, CspSignature};
 use crate::vault::api::PublicKeyStoreCspVault;
@@ -18,7 +18,7 @@ use crate::vault::local_csp_vault::LocalCspVault;
 use crate::KeyId;
 use assert_matches::assert_matches;
 use ic_crypto_internal_basic_sig_ed25519 as ed25519;
-use ic_crypto_internal_basic_sig_ed25519::types::PublicKeyBytes;
+use ic_crypto_internal_basic_sig_ed25519::types::{PublicKeyBytes, SignatureBytes};
 use ic_crypto_internal_test_vectors::ed25519::Ed25519TestVector::RFC8032_ED25519_SHA_ABC;
 use ic_crypto_test_utils_reproducible_rng::reproducible_rng;
 use ic_types::crypto::AlgorithmId;
@@ -284,6 +284,51 @@ fn should_fail_to_sign_if_secret_key_in_store_has_wrong_type() {
     );
 }
 
+#[test]
+fn should_sign_with_key_held_by_external_signer() {
+    let key_id = KeyId::from([42; 32]);
+    let signature_bytes = SignatureBytes([43; SignatureBytes::SIZE]);
+    let mut key_store = MockSecretKeyStore::new();
+    key_store
+        .expect_sign_ed25519_externally()
+        .withf(move |id, message| *id == key_id && message == b"message")
+        .times(1)
+        .return_once(move |_, _| Some(Ok(signature_bytes)));
+    key_store.expect_get().never();
+    let csp_vault = LocalCspVault::builder()
+        .with_node_secret_key_store(key_store)
+        .build();
+
+    let result = csp_vault.sign(AlgorithmId::Ed25519, b"message", key_id);
+
+    assert_eq!(result, Ok(CspSignature::Ed25519(signature_bytes)));
+}
+
+#[test]
+fn should_fail_with_internal_error_if_external_signer_fails() {
+    let key_id = KeyId::from([42; 32]);
+    let mut key_store = MockSecretKeyStore::new();
+    key_store
+        .expect_sign_ed25519_externally()
+        .times(1)
+        .return_once(|_, _| {
+            Some(Err(ExternalSigningError {
+                internal_error: "token removed".to_string(),
+            }))
+        });
+    let csp_vault = LocalCspVault::builder()
+        .with_node_secret_key_store(key_store)
+        .build();
+
+    let result = csp_vault.sign(AlgorithmId::Ed25519, b"message", key_id);
+
+    assert_matches!(
+        result,
+        Err(CspBasicSignatureError::InternalError { internal_error })
+        if internal_error.contains("token removed")
+    );
+}
+
 pub fn generate_key_pair_and_sign_and_verify_message(csp_vault: Arc<dyn CspVault>, message: &[u8]) {
     let (pk_bytes, sign_result) = generate_key_pair_and_sign_message(csp_vault, message);
     assert!(sign_result.is_ok());
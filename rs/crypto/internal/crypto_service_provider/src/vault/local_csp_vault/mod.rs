@@ -11,9 +11,11 @@ mod tecdsa;
 mod tests;
 mod threshold_sig;
 mod tls;
+mod vetkd;
 
 use crate::public_key_store::proto_pubkey_store::ProtoPublicKeyStore;
 use crate::public_key_store::PublicKeyStore;
+use crate::secret_key_store::pkcs11_store::Pkcs11BackedSecretKeyStore;
 use crate::secret_key_store::proto_store::ProtoSecretKeyStore;
 use crate::secret_key_store::SecretKeyStore;
 use crate::CspRwLock;
@@ -114,6 +116,44 @@ impl LocalCspVault<OsRng, ProtoSecretKeyStore, ProtoSecretKeyStore, ProtoPublicK
     }
 }
 
+impl
+    LocalCspVault<
+        OsRng,
+        Pkcs11BackedSecretKeyStore<ProtoSecretKeyStore>,
+        ProtoSecretKeyStore,
+        ProtoPublicKeyStore,
+    >
+{
+    /// Creates a production-grade local CSP vault whose node signing and TLS
+    /// secret keys are held in a PKCS#11 token.
+    ///
+    /// # Panics
+    /// If the key stores (`node_secret_key_store`,`canister_secret_key_store` or `public_key_store`)
+    /// do not use distinct files.
+    pub fn new_with_pkcs11_node_keys(
+        node_secret_key_store: Pkcs11BackedSecretKeyStore<ProtoSecretKeyStore>,
+        canister_secret_key_store: ProtoSecretKeyStore,
+        public_key_store: ProtoPublicKeyStore,
+        metrics: Arc<CryptoMetrics>,
+        logger: ReplicaLogger,
+    ) -> Self {
+        ensure_unique_paths(&[
+            node_secret_key_store.local_store().proto_file_path(),
+            canister_secret_key_store.proto_file_path(),
+            public_key_store.proto_file_path(),
+        ]);
+        LocalCspVault::new_internal(
+            OsRng,
+            node_secret_key_store,
+            canister_secret_key_store,
+            public_key_store,
+            Arc::new(CurrentSystemTimeSource::new(new_logger!(&logger))),
+            metrics,
+            logger,
+        )
+    }
+}
+
 impl<R: Rng + CryptoRng, S: SecretKeyStore, C: SecretKeyStore, P: PublicKeyStore>
     LocalCspVault<R, S, C, P>
 {
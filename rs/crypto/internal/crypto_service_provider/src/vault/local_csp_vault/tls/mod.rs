@@ -198,6 +198,16 @@ impl<R: Rng + CryptoRng, S: SecretKeyStore, C: SecretKeyStore, P: PublicKeyStore
         message: &[u8],
         key_id: &KeyId,
     ) -> Result<CspSignature, CspTlsSignError> {
+        if let Some(result) = self
+            .sks_read_lock()
+            .sign_ed25519_externally(key_id, message)
+        {
+            return result
+                .map(CspSignature::Ed25519)
+                .map_err(|e| CspTlsSignError::SigningFailed {
+                    error: e.to_string(),
+                });
+        }
         let maybe_secret_key = self.sks_read_lock().get(key_id);
         let secret_key: CspSecretKey =
             maybe_secret_key.ok_or(CspTlsSignError::SecretKeyNotFound { key_id: *key_id })?;
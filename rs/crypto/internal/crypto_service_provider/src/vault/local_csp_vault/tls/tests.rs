@@ -249,7 +249,12 @@ mod keygen {
 mod sign {
     use super::*;
     use crate::key_id::KeyId;
+    use crate::secret_key_store::mock_secret_key_store::MockSecretKeyStore;
+    use crate::secret_key_store::ExternalSigningError;
+    use crate::types::CspSignature;
+    use crate::vault::api::{CspTlsSignError, TlsHandshakeCspVault};
     use crate::vault::test_utils::sks::secret_key_store_containing_key_with_invalid_length;
+    use ic_crypto_internal_basic_sig_ed25519::types::SignatureBytes;
 
     #[test]
     fn should_sign_with_valid_key() {
@@ -288,6 +293,50 @@ mod sign {
         );
     }
 
+    #[test]
+    fn should_sign_with_key_held_by_external_signer() {
+        let key_id = KeyId::from([44; 32]);
+        let signature_bytes = SignatureBytes([45; SignatureBytes::SIZE]);
+        let mut key_store = MockSecretKeyStore::new();
+        key_store
+            .expect_sign_ed25519_externally()
+            .withf(move |id, message| *id == key_id && message == b"message")
+            .times(1)
+            .return_once(move |_, _| Some(Ok(signature_bytes)));
+        key_store.expect_get().never();
+        let csp_vault = LocalCspVault::builder()
+            .with_node_secret_key_store(key_store)
+            .build_into_arc();
+
+        let result = csp_vault.tls_sign(b"message", &key_id);
+
+        assert_matches!(result, Ok(CspSignature::Ed25519(bytes)) if bytes == signature_bytes);
+    }
+
+    #[test]
+    fn should_fail_to_sign_if_external_signer_fails() {
+        let key_id = KeyId::from([44; 32]);
+        let mut key_store = MockSecretKeyStore::new();
+        key_store
+            .expect_sign_ed25519_externally()
+            .times(1)
+            .return_once(|_, _| {
+                Some(Err(ExternalSigningError {
+                    internal_error: "token removed".to_string(),
+                }))
+            });
+        let csp_vault = LocalCspVault::builder()
+            .with_node_secret_key_store(key_store)
+            .build_into_arc();
+
+        let result = csp_vault.tls_sign(b"message", &key_id);
+
+        assert_matches!(
+            result,
+            Err(CspTlsSignError::SigningFailed { error }) if error.contains("token removed")
+        );
+    }
+
     #[test]
     fn should_fail_to_sign_if_secret_key_in_store_has_invalid_length() {
         let key_id = KeyId::from([43; 32]);
@@ -0,0 +1,110 @@
+use crate::key_id::KeyId;
+use crate::public_key_store::PublicKeyStore;
+use crate::secret_key_store::SecretKeyStore;
+use crate::types::CspSecretKey;
+use crate::vault::api::{VetKdCspVault, VetKdEncryptedKeyShareCreationVaultError};
+use crate::vault::local_csp_vault::LocalCspVault;
+use ic_crypto_internal_bls12_381_type::G2Affine;
+use ic_crypto_internal_logmon::metrics::{MetricsDomain, MetricsResult, MetricsScope};
+use ic_crypto_internal_threshold_sig_bls12381::vetkd::{
+    DerivationPath, EncryptedKeyShare, EncryptedKeyShareCreationError, TransportPublicKey,
+};
+use ic_types::crypto::canister_threshold_sig::ExtendedDerivationPath;
+use ic_types::crypto::vetkd::VetKdEncryptedKeyShare;
+use rand::{CryptoRng, Rng};
+
+#[cfg(test)]
+mod tests;
+
+impl<R: Rng + CryptoRng, S: SecretKeyStore, C: SecretKeyStore, P: PublicKeyStore> VetKdCspVault
+    for LocalCspVault<R, S, C, P>
+{
+    fn create_encrypted_vetkd_key_share(
+        &self,
+        key_id: KeyId,
+        master_public_key: Vec<u8>,
+        encryption_public_key: Vec<u8>,
+        derivation_path: ExtendedDerivationPath,
+        derivation_id: Vec<u8>,
+    ) -> Result<VetKdEncryptedKeyShare, VetKdEncryptedKeyShareCreationVaultError> {
+        let start_time = self.metrics.now();
+        let result = self.create_encrypted_vetkd_key_share_internal(
+            key_id,
+            master_public_key,
+            encryption_public_key,
+            derivation_path,
+            derivation_id,
+        );
+        self.metrics.observe_duration_seconds(
+            MetricsDomain::VetKd,
+            MetricsScope::Local,
+            "create_encrypted_vetkd_key_share",
+            MetricsResult::from(&result),
+            start_time,
+        );
+        result
+    }
+}
+
+impl<R: Rng + CryptoRng, S: SecretKeyStore, C: SecretKeyStore, P: PublicKeyStore>
+    LocalCspVault<R, S, C, P>
+{
+    fn create_encrypted_vetkd_key_share_internal(
+        &self,
+        key_id: KeyId,
+        master_public_key: Vec<u8>,
+        encryption_public_key: Vec<u8>,
+        derivation_path: ExtendedDerivationPath,
+        derivation_id: Vec<u8>,
+    ) -> Result<VetKdEncryptedKeyShare, VetKdEncryptedKeyShareCreationVaultError> {
+        let master_public_key = G2Affine::deserialize(&master_public_key).map_err(|_| {
+            VetKdEncryptedKeyShareCreationVaultError::InvalidArgument(format!(
+                "invalid master public key: 0x{}",
+                hex::encode(&master_public_key)
+            ))
+        })?;
+        let transport_public_key = TransportPublicKey::deserialize(&encryption_public_key)
+            .map_err(|e| {
+                VetKdEncryptedKeyShareCreationVaultError::InvalidArgument(format!(
+                    "invalid encryption public key: {:?}",
+                    e
+                ))
+            })?;
+
+        let secret_key_share = match self.sks_read_lock().get(&key_id) {
+            Some(CspSecretKey::ThresBls12_381(secret_key_share)) => secret_key_share,
+            _ => {
+                return Err(
+                    VetKdEncryptedKeyShareCreationVaultError::SecretKeyMissingOrWrongType(format!(
+                        "missing threshold BLS12-381 secret key with key id {}",
+                        key_id
+                    )),
+                )
+            }
+        };
+
+        let encrypted_key_share = EncryptedKeyShare::create(
+            &mut *self.rng_write_lock(),
+            &master_public_key,
+            &secret_key_share,
+            &transport_public_key,
+            &DerivationPath::new(
+                derivation_path.caller.as_slice(),
+                &derivation_path.derivation_path,
+            ),
+            &derivation_id,
+        )
+        .map_err(|e| match e {
+            EncryptedKeyShareCreationError::MalformedSecretKeyShare => {
+                VetKdEncryptedKeyShareCreationVaultError::SecretKeyMissingOrWrongType(format!(
+                    "malformed threshold BLS12-381 secret key with key id {}",
+                    key_id
+                ))
+            }
+        })?;
+
+        Ok(VetKdEncryptedKeyShare {
+            encrypted_key_share: encrypted_key_share.serialize().to_vec(),
+        })
+    }
+}
@@ -0,0 +1,157 @@
+#![allow(clippy::unwrap_used)]
+//! Tests for creating encrypted vetKD key shares
+
+use super::*;
+use crate::types::CspPublicCoefficients;
+use crate::vault::api::ThresholdSignatureCspVault;
+use ic_crypto_internal_seed::Seed;
+use ic_crypto_internal_threshold_sig_bls12381::api as bls12381_api;
+use ic_crypto_internal_threshold_sig_bls12381::vetkd::{EncryptedKey, TransportSecretKey};
+use ic_crypto_test_utils_reproducible_rng::reproducible_rng;
+use ic_types::crypto::AlgorithmId;
+use ic_types::{NodeIndex, NumberOfNodes, PrincipalId};
+use std::collections::BTreeMap;
+
+#[test]
+fn should_create_encrypted_key_shares_that_combine_and_decrypt() {
+    let rng = &mut reproducible_rng();
+    let vault = LocalCspVault::builder()
+        .with_rng(Seed::from_rng(rng).into_rng())
+        .build();
+    let (public_coefficients, key_ids) = vault
+        .threshold_keygen_for_test(
+            AlgorithmId::ThresBls12_381,
+            NumberOfNodes::from(2),
+            NumberOfNodes::from(3),
+        )
+        .unwrap();
+    let CspPublicCoefficients::Bls12_381(public_coefficients) = public_coefficients;
+    let master_public_key = bls12381_api::combined_public_key(&public_coefficients).unwrap();
+
+    let transport_secret_key = TransportSecretKey::generate(rng);
+    let derivation_path = ExtendedDerivationPath {
+        caller: PrincipalId::new_user_test_id(1),
+        derivation_path: vec![b"path".to_vec()],
+    };
+    let derivation_id = b"id".to_vec();
+
+    let mut shares = BTreeMap::new();
+    for (index, key_id) in key_ids.iter().enumerate() {
+        let share = vault
+            .create_encrypted_vetkd_key_share(
+                *key_id,
+                master_public_key.0.to_vec(),
+                transport_secret_key.public_key().serialize().to_vec(),
+                derivation_path.clone(),
+                derivation_id.clone(),
+            )
+            .unwrap();
+        let share = EncryptedKeyShare::deserialize(&share.encrypted_key_share).unwrap();
+
+        let public_key_share =
+            bls12381_api::individual_public_key(&public_coefficients, index as NodeIndex).unwrap();
+        assert!(share.is_valid(
+            &G2Affine::deserialize(&master_public_key.0).unwrap(),
+            &G2Affine::deserialize(&public_key_share.0).unwrap(),
+            &transport_secret_key.public_key(),
+            &clib_derivation_path(&derivation_path),
+            &derivation_id,
+        ));
+        shares.insert(index as NodeIndex, share);
+    }
+
+    let master_public_key = G2Affine::deserialize(&master_public_key.0).unwrap();
+    let encrypted_key = EncryptedKey::combine(
+        &shares,
+        2,
+        &master_public_key,
+        &transport_secret_key.public_key(),
+        &clib_derivation_path(&derivation_path),
+        &derivation_id,
+    )
+    .unwrap();
+    assert!(transport_secret_key
+        .decrypt(
+            &encrypted_key,
+            &master_public_key,
+            &clib_derivation_path(&derivation_path),
+            &derivation_id,
+        )
+        .is_some());
+}
+
+#[test]
+fn should_fail_to_create_encrypted_key_share_if_secret_key_is_missing() {
+    let rng = &mut reproducible_rng();
+    let vault = LocalCspVault::builder()
+        .with_rng(Seed::from_rng(rng).into_rng())
+        .build();
+    let (public_coefficients, _key_ids) = vault
+        .threshold_keygen_for_test(
+            AlgorithmId::ThresBls12_381,
+            NumberOfNodes::from(1),
+            NumberOfNodes::from(1),
+        )
+        .unwrap();
+    let CspPublicCoefficients::Bls12_381(public_coefficients) = public_coefficients;
+    let master_public_key = bls12381_api::combined_public_key(&public_coefficients).unwrap();
+
+    let result = vault.create_encrypted_vetkd_key_share(
+        KeyId::from([42; 32]),
+        master_public_key.0.to_vec(),
+        TransportSecretKey::generate(rng)
+            .public_key()
+            .serialize()
+            .to_vec(),
+        ExtendedDerivationPath {
+            caller: PrincipalId::new_user_test_id(1),
+            derivation_path: vec![],
+        },
+        b"id".to_vec(),
+    );
+
+    assert!(matches!(
+        result,
+        Err(VetKdEncryptedKeyShareCreationVaultError::SecretKeyMissingOrWrongType(_))
+    ));
+}
+
+#[test]
+fn should_fail_to_create_encrypted_key_share_with_invalid_encryption_public_key() {
+    let rng = &mut reproducible_rng();
+    let vault = LocalCspVault::builder()
+        .with_rng(Seed::from_rng(rng).into_rng())
+        .build();
+    let (public_coefficients, key_ids) = vault
+        .threshold_keygen_for_test(
+            AlgorithmId::ThresBls12_381,
+            NumberOfNodes::from(1),
+            NumberOfNodes::from(1),
+        )
+        .unwrap();
+    let CspPublicCoefficients::Bls12_381(public_coefficients) = public_coefficients;
+    let master_public_key = bls12381_api::combined_public_key(&public_coefficients).unwrap();
+
+    let result = vault.create_encrypted_vetkd_key_share(
+        key_ids[0],
+        master_public_key.0.to_vec(),
+        vec![0xff; TransportPublicKey::BYTES],
+        ExtendedDerivationPath {
+            caller: PrincipalId::new_user_test_id(1),
+            derivation_path: vec![],
+        },
+        b"id".to_vec(),
+    );
+
+    assert!(matches!(
+        result,
+        Err(VetKdEncryptedKeyShareCreationVaultError::InvalidArgument(_))
+    ));
+}
+
+fn clib_derivation_path(derivation_path: &ExtendedDerivationPath) -> DerivationPath {
+    DerivationPath::new(
+        derivation_path.caller.as_slice(),
+        &derivation_path.derivation_path,
+    )
+}
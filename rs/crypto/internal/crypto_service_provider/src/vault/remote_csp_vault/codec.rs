@@ -161,6 +161,7 @@ enum CspVaultMethod {
     IdkgGenDealingEncryptionKeyPair,
     IdkgOpenDealing,
     EcdsaSignShare,
+    CreateEncryptedVetKdKeyShare,
     NewPublicSeed,
 }
 
@@ -235,6 +236,9 @@ impl CspVaultMethod {
             ),
             CspVaultMethod::IdkgOpenDealing => (MetricsDomain::IdkgProtocol, "idkg_open_dealing"),
             CspVaultMethod::EcdsaSignShare => (MetricsDomain::ThresholdEcdsa, "ecdsa_sign_share"),
+            CspVaultMethod::CreateEncryptedVetKdKeyShare => {
+                (MetricsDomain::VetKd, "create_encrypted_vetkd_key_share")
+            }
             CspVaultMethod::NewPublicSeed => (MetricsDomain::PublicSeed, "new_public_seed"),
         }
     }
@@ -274,6 +278,7 @@ impl From<&TarpcCspVaultRequest> for CspVaultMethod {
             Req::IdkgGenDealingEncryptionKeyPair { .. } => Method::IdkgGenDealingEncryptionKeyPair,
             Req::IdkgOpenDealing { .. } => Method::IdkgOpenDealing,
             Req::EcdsaSignShare { .. } => Method::EcdsaSignShare,
+            Req::CreateEncryptedVetkdKeyShare { .. } => Method::CreateEncryptedVetKdKeyShare,
             Req::NewPublicSeed { .. } => Method::NewPublicSeed,
         }
     }
@@ -313,6 +318,7 @@ impl From<&TarpcCspVaultResponse> for CspVaultMethod {
             Resp::IdkgGenDealingEncryptionKeyPair { .. } => Method::IdkgGenDealingEncryptionKeyPair,
             Resp::IdkgOpenDealing { .. } => Method::IdkgOpenDealing,
             Resp::EcdsaSignShare { .. } => Method::EcdsaSignShare,
+            Resp::CreateEncryptedVetkdKeyShare { .. } => Method::CreateEncryptedVetKdKeyShare,
             Resp::NewPublicSeed { .. } => Method::NewPublicSeed,
         }
     }
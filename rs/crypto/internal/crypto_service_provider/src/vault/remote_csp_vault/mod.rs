@@ -4,8 +4,9 @@ use crate::vault::api::{
     CspBasicSignatureError, CspBasicSignatureKeygenError, CspMultiSignatureError,
     CspMultiSignatureKeygenError, CspPublicKeyStoreError, CspSecretKeyStoreContainsError,
     CspThresholdSignatureKeygenError, CspTlsKeygenError, CspTlsSignError, PksAndSksContainsErrors,
-    ValidatePksAndSksError,
+    ValidatePksAndSksError, VetKdEncryptedKeyShareCreationVaultError,
 };
+use ic_config::crypto::HsmConfig;
 use ic_crypto_internal_seed::Seed;
 use ic_crypto_internal_threshold_sig_bls12381::api::ni_dkg_errors;
 use ic_crypto_internal_threshold_sig_ecdsa::{
@@ -25,6 +26,7 @@ use ic_types::crypto::canister_threshold_sig::error::{
     IDkgVerifyDealingPrivateError, ThresholdEcdsaSignShareError,
 };
 use ic_types::crypto::canister_threshold_sig::ExtendedDerivationPath;
+use ic_types::crypto::vetkd::VetKdEncryptedKeyShare;
 use ic_types::crypto::{AlgorithmId, CurrentNodePublicKeys};
 use ic_types::{NodeId, NodeIndex, NumberOfNodes, Randomness};
 use std::collections::{BTreeMap, BTreeSet};
@@ -233,22 +235,52 @@ pub trait TarpcCspVault {
         algorithm_id: AlgorithmId,
     ) -> Result<ThresholdEcdsaSigShareInternal, ThresholdEcdsaSignShareError>;
 
+    // Corresponds to `VetKdCspVault.create_encrypted_vetkd_key_share`
+    async fn create_encrypted_vetkd_key_share(
+        key_id: KeyId,
+        master_public_key: Vec<u8>,
+        encryption_public_key: Vec<u8>,
+        derivation_path: ExtendedDerivationPath,
+        derivation_id: Vec<u8>,
+    ) -> Result<VetKdEncryptedKeyShare, VetKdEncryptedKeyShareCreationVaultError>;
+
     async fn new_public_seed() -> Result<Seed, PublicRandomSeedGeneratorError>;
 }
 
+/// Runs a CSP vault server with key stores in `sks_dir`.
+///
+/// If `hsm_config` is given, the node signing and TLS secret keys are held in
+/// the configured PKCS#11 token.
 pub async fn run_csp_vault_server(
     sks_dir: &Path,
+    hsm_config: Option<&HsmConfig>,
     listener: UnixListener,
     logger: ReplicaLogger,
     metrics: CryptoMetrics,
 ) {
-    let server = tarpc_csp_vault_server::TarpcCspVaultServerImpl::new(
-        sks_dir,
-        listener,
-        logger,
-        Arc::new(metrics),
-    );
-    server.run().await
+    match hsm_config {
+        None => {
+            tarpc_csp_vault_server::TarpcCspVaultServerImpl::new(
+                sks_dir,
+                listener,
+                logger,
+                Arc::new(metrics),
+            )
+            .run()
+            .await
+        }
+        Some(hsm_config) => {
+            tarpc_csp_vault_server::TarpcCspVaultServerImpl::new_with_pkcs11_node_keys(
+                sks_dir,
+                hsm_config,
+                listener,
+                logger,
+                Arc::new(metrics),
+            )
+            .run()
+            .await
+        }
+    }
 }
 
 pub fn remote_vault_codec_builder() -> Builder {
@@ -1,12 +1,14 @@
 use crate::api::{CspCreateMEGaKeyError, CspThresholdSignError};
 use crate::key_id::KeyId;
 use crate::public_key_store::proto_pubkey_store::ProtoPublicKeyStore;
+use crate::secret_key_store::pkcs11_store::Pkcs11BackedSecretKeyStore;
 use crate::secret_key_store::proto_store::ProtoSecretKeyStore;
 use crate::types::{CspPop, CspPublicCoefficients, CspPublicKey, CspSignature};
 use crate::vault::api::{
     CspBasicSignatureError, CspBasicSignatureKeygenError, CspMultiSignatureError,
     CspMultiSignatureKeygenError, CspSecretKeyStoreContainsError, CspThresholdSignatureKeygenError,
     CspTlsKeygenError, CspTlsSignError, PublicRandomSeedGeneratorError, ValidatePksAndSksError,
+    VetKdEncryptedKeyShareCreationVaultError,
 };
 use crate::vault::api::{CspPublicKeyStoreError, CspVault};
 use crate::vault::local_csp_vault::LocalCspVault;
@@ -14,6 +16,7 @@ use crate::vault::remote_csp_vault::PksAndSksContainsErrors;
 use crate::vault::remote_csp_vault::{remote_vault_codec_builder, TarpcCspVault};
 use crate::ExternalPublicKeys;
 use crate::{CANISTER_SKS_DATA_FILENAME, PUBLIC_KEY_STORE_DATA_FILENAME, SKS_DATA_FILENAME};
+use ic_config::crypto::HsmConfig;
 use ic_crypto_internal_logmon::metrics::CryptoMetrics;
 use ic_crypto_internal_seed::Seed;
 use ic_crypto_internal_threshold_sig_bls12381::api::ni_dkg_errors::{
@@ -40,6 +43,7 @@ use ic_types::crypto::canister_threshold_sig::error::{
     IDkgVerifyDealingPrivateError, ThresholdEcdsaSignShareError,
 };
 use ic_types::crypto::canister_threshold_sig::ExtendedDerivationPath;
+use ic_types::crypto::vetkd::VetKdEncryptedKeyShare;
 use ic_types::crypto::{AlgorithmId, CurrentNodePublicKeys};
 use ic_types::{NodeId, NumberOfNodes, Randomness};
 use rand::rngs::OsRng;
@@ -504,6 +508,29 @@ impl<C: CspVault + 'static> TarpcCspVault for TarpcCspVaultServerWorker<C> {
         execute_on_thread_pool(self.thread_pool_handle, job).await
     }
 
+    // `VetKdCspVault`-methods
+    async fn create_encrypted_vetkd_key_share(
+        self,
+        _: context::Context,
+        key_id: KeyId,
+        master_public_key: Vec<u8>,
+        encryption_public_key: Vec<u8>,
+        derivation_path: ExtendedDerivationPath,
+        derivation_id: Vec<u8>,
+    ) -> Result<VetKdEncryptedKeyShare, VetKdEncryptedKeyShareCreationVaultError> {
+        let vault = self.local_csp_vault;
+        let job = move || {
+            vault.create_encrypted_vetkd_key_share(
+                key_id,
+                master_public_key,
+                encryption_public_key,
+                derivation_path,
+                derivation_id,
+            )
+        };
+        execute_on_thread_pool(self.thread_pool_handle, job).await
+    }
+
     async fn new_public_seed(
         self,
         _: context::Context,
@@ -548,6 +575,52 @@ impl
     }
 }
 
+impl
+    TarpcCspVaultServerImpl<
+        LocalCspVault<
+            OsRng,
+            Pkcs11BackedSecretKeyStore<ProtoSecretKeyStore>,
+            ProtoSecretKeyStore,
+            ProtoPublicKeyStore,
+        >,
+    >
+{
+    /// Creates a remote CSP vault server whose node signing and TLS secret
+    /// keys are held in the PKCS#11 token configured in `hsm_config`.
+    pub fn new_with_pkcs11_node_keys(
+        key_store_dir: &Path,
+        hsm_config: &HsmConfig,
+        listener: UnixListener,
+        logger: ReplicaLogger,
+        metrics: Arc<CryptoMetrics>,
+    ) -> Self {
+        let node_secret_key_store = Pkcs11BackedSecretKeyStore::open(
+            key_store_dir,
+            SKS_DATA_FILENAME,
+            hsm_config,
+            new_logger!(&logger),
+        );
+        let canister_secret_key_store = ProtoSecretKeyStore::open(
+            key_store_dir,
+            CANISTER_SKS_DATA_FILENAME,
+            Some(new_logger!(&logger)),
+        );
+        let public_key_store = ProtoPublicKeyStore::open(
+            key_store_dir,
+            PUBLIC_KEY_STORE_DATA_FILENAME,
+            new_logger!(&logger),
+        );
+        let local_csp_server = Arc::new(LocalCspVault::new_with_pkcs11_node_keys(
+            node_secret_key_store,
+            canister_secret_key_store,
+            public_key_store,
+            metrics,
+            new_logger!(&logger),
+        ));
+        Self::new_with_local_csp_vault(local_csp_server, listener, logger)
+    }
+}
+
 impl<C: CspVault> TarpcCspVaultServerImpl<C> {
     /// Creates a remote CSP vault server for testing.
     ///
@@ -0,0 +1,36 @@
+//! Verifiably encrypted threshold key derivation (vetKD).
+//!
+//! The code in this file mediates between the external API and the CSP
+//! vault, which holds the threshold secret key shares.
+
+use crate::api::CspVetKdProtocol;
+use crate::key_id::KeyId;
+use crate::vault::api::VetKdEncryptedKeyShareCreationVaultError;
+use crate::Csp;
+use ic_logger::debug;
+use ic_types::crypto::canister_threshold_sig::ExtendedDerivationPath;
+use ic_types::crypto::vetkd::VetKdEncryptedKeyShare;
+
+/// vetKD encrypted key share generation client.
+///
+/// Please see the trait definition for full documentation.
+impl CspVetKdProtocol for Csp {
+    fn create_encrypted_vetkd_key_share(
+        &self,
+        key_id: KeyId,
+        master_public_key: Vec<u8>,
+        encryption_public_key: Vec<u8>,
+        derivation_path: ExtendedDerivationPath,
+        derivation_id: Vec<u8>,
+    ) -> Result<VetKdEncryptedKeyShare, VetKdEncryptedKeyShareCreationVaultError> {
+        debug!(self.logger; crypto.method_name => "create_encrypted_vetkd_key_share");
+
+        self.csp_vault.create_encrypted_vetkd_key_share(
+            key_id,
+            master_public_key,
+            encryption_public_key,
+            derivation_path,
+            derivation_id,
+        )
+    }
+}
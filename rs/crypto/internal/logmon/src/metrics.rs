@@ -285,6 +285,7 @@ pub enum MetricsDomain {
     TlsHandshake,
     IdkgProtocol,
     ThresholdEcdsa,
+    VetKd,
     PublicSeed,
     KeyManagement,
 }
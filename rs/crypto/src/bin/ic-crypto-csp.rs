@@ -48,8 +48,14 @@ async fn main() {
     // This way we can capture all the context if a critical error happens.
     abort_on_panic();
     let metrics = CryptoMetrics::new(Some(&MetricsRegistry::global()));
-    ic_crypto_internal_csp::run_csp_vault_server(sks_dir, systemd_socket_listener, logger, metrics)
-        .await;
+    ic_crypto_internal_csp::run_csp_vault_server(
+        sks_dir,
+        ic_config.crypto.hsm.as_ref(),
+        systemd_socket_listener,
+        logger,
+        metrics,
+    )
+    .await;
 }
 
 /// Aborts the whole program with a core dump if a single thread panics.
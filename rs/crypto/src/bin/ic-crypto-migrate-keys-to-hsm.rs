@@ -0,0 +1,62 @@
+use clap::Parser;
+use ic_config::{Config, ConfigSource};
+use ic_crypto_internal_csp::secret_key_store::pkcs11_store::migrate_node_keys_to_pkcs11_token;
+use ic_logger::{info, new_replica_logger_from_config};
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[clap(
+    name = "Node key migration to PKCS#11 token",
+    version = "0.1",
+    author = "Internet Computer Developers",
+    about = "Moves the node signing and TLS secret keys from the secret key store \
+               on disk into the PKCS#11 token configured in the replica configuration. \
+               NOTE: The CspVault server and the replica must be stopped while the keys \
+               are migrated."
+)]
+struct Opts {
+    /// Sets the replica configuration file
+    #[clap(long = "replica-config-file", parse(from_os_str))]
+    config: PathBuf,
+}
+
+fn main() {
+    let opts = Opts::parse();
+    let ic_config = get_ic_config(opts.config);
+    let hsm_config = ic_config
+        .crypto
+        .hsm
+        .as_ref()
+        .expect("no PKCS#11 token (`crypto.hsm`) configured in the replica configuration");
+    let crypto_root = ic_config.crypto.crypto_root.as_path();
+
+    // The `AsyncGuard` must be kept in scope for asynchronously logged messages to appear in the logs.
+    let (logger, _async_log_guard) = new_replica_logger_from_config(&ic_config.csp_vault_logger);
+
+    match migrate_node_keys_to_pkcs11_token(crypto_root, hsm_config, logger.clone()) {
+        Ok(key_ids) => {
+            info!(
+                logger,
+                "Migrated {} key(s) from '{}' to the PKCS#11 token in slot {}",
+                key_ids.len(),
+                crypto_root.display(),
+                hsm_config.slot_id
+            );
+        }
+        Err(e) => {
+            eprintln!("Failed to migrate node keys: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn get_ic_config(replica_config_file: PathBuf) -> Config {
+    let tmpdir = tempfile::Builder::new()
+        .prefix("ic_config")
+        .tempdir()
+        .expect("failed to create temporary directory for replica config")
+        .path()
+        .to_path_buf();
+
+    Config::load_with_tmpdir(ConfigSource::File(replica_config_file), tmpdir)
+}
@@ -12,7 +12,7 @@ use ic_crypto_internal_threshold_sig_bls12381::api::bls_signature_cache_statisti
 use ic_interfaces::crypto::{
     BasicSigVerifier, BasicSigVerifierByPublicKey, BasicSigner, CanisterSigVerifier,
     MultiSigVerifier, MultiSigner, ThresholdEcdsaSigVerifier, ThresholdEcdsaSigner,
-    ThresholdSigVerifier, ThresholdSigVerifierByPublicKey, ThresholdSigner,
+    ThresholdSigVerifier, ThresholdSigVerifierByPublicKey, ThresholdSigner, VetKdProtocol,
 };
 use ic_logger::{debug, new_logger};
 use ic_types::crypto::canister_threshold_sig::error::{
@@ -24,6 +24,10 @@ use ic_types::crypto::canister_threshold_sig::{
 };
 use ic_types::crypto::threshold_sig::errors::threshold_sign_error::ThresholdSignError;
 use ic_types::crypto::threshold_sig::ni_dkg::DkgId;
+use ic_types::crypto::vetkd::{
+    VetKdArgs, VetKdEncryptedKey, VetKdEncryptedKeyShare, VetKdKeyShareCombinationError,
+    VetKdKeyShareCreationError, VetKdKeyShareVerificationError, VetKdKeyVerificationError,
+};
 use ic_types::crypto::KeyPurpose::CommitteeSigning;
 use ic_types::crypto::{
     AlgorithmId, BasicSig, BasicSigOf, CanisterSigOf, CombinedMultiSig, CombinedMultiSigOf,
@@ -41,6 +45,7 @@ mod canister_sig;
 mod canister_threshold_sig;
 mod multi_sig;
 mod threshold_sig;
+mod vetkd;
 
 pub use canister_threshold_sig::{
     fetch_idkg_dealing_encryption_public_key_from_registry, get_mega_pubkey,
@@ -841,6 +846,157 @@ impl<C: CryptoServiceProvider> ThresholdEcdsaSigVerifier for CryptoComponentImpl
     }
 }
 
+impl<C: CryptoServiceProvider> VetKdProtocol for CryptoComponentImpl<C> {
+    fn create_encrypted_key_share(
+        &self,
+        args: VetKdArgs,
+    ) -> Result<VetKdEncryptedKeyShare, VetKdKeyShareCreationError> {
+        let log_id = get_log_id(&self.logger, module_path!());
+        let logger = new_logger!(&self.logger;
+            crypto.log_id => log_id,
+            crypto.trait_name => "VetKdProtocol",
+            crypto.method_name => "create_encrypted_key_share",
+        );
+        debug!(logger;
+            crypto.description => "start",
+            crypto.signature_inputs => format!("{:?}", args),
+        );
+        let start_time = self.metrics.now();
+        let result = vetkd::create_encrypted_key_share(
+            &self.lockable_threshold_sig_data_store,
+            &self.csp,
+            args,
+        );
+        self.metrics.observe_duration_seconds(
+            MetricsDomain::VetKd,
+            MetricsScope::Full,
+            "create_encrypted_key_share",
+            MetricsResult::from(&result),
+            start_time,
+        );
+        debug!(logger;
+            crypto.description => "end",
+            crypto.is_ok => result.is_ok(),
+            crypto.error => log_err(result.as_ref().err()),
+            crypto.signature_shares => log_ok_content(&result),
+        );
+        result
+    }
+
+    fn verify_encrypted_key_share(
+        &self,
+        creator: NodeId,
+        key_share: &VetKdEncryptedKeyShare,
+        args: &VetKdArgs,
+    ) -> Result<(), VetKdKeyShareVerificationError> {
+        let log_id = get_log_id(&self.logger, module_path!());
+        let logger = new_logger!(&self.logger;
+            crypto.log_id => log_id,
+            crypto.trait_name => "VetKdProtocol",
+            crypto.method_name => "verify_encrypted_key_share",
+        );
+        debug!(logger;
+            crypto.description => "start",
+            crypto.signature_shares => format!("{:?}", key_share),
+            crypto.signer => format!("{:?}", creator),
+            crypto.signature_inputs => format!("{:?}", args),
+        );
+        let start_time = self.metrics.now();
+        let result = vetkd::verify_encrypted_key_share(
+            &self.lockable_threshold_sig_data_store,
+            &self.csp,
+            creator,
+            key_share,
+            args,
+        );
+        self.metrics.observe_duration_seconds(
+            MetricsDomain::VetKd,
+            MetricsScope::Full,
+            "verify_encrypted_key_share",
+            MetricsResult::from(&result),
+            start_time,
+        );
+        debug!(logger;
+            crypto.description => "end",
+            crypto.is_ok => result.is_ok(),
+            crypto.error => log_err(result.as_ref().err()),
+        );
+        result
+    }
+
+    fn combine_encrypted_key_shares(
+        &self,
+        shares: &BTreeMap<NodeId, VetKdEncryptedKeyShare>,
+        args: &VetKdArgs,
+    ) -> Result<VetKdEncryptedKey, VetKdKeyShareCombinationError> {
+        let log_id = get_log_id(&self.logger, module_path!());
+        let logger = new_logger!(&self.logger;
+            crypto.log_id => log_id,
+            crypto.trait_name => "VetKdProtocol",
+            crypto.method_name => "combine_encrypted_key_shares",
+        );
+        debug!(logger;
+            crypto.description => "start",
+            crypto.signature_shares => format!("{:?}", shares),
+            crypto.signature_inputs => format!("{:?}", args),
+        );
+        let start_time = self.metrics.now();
+        let result = vetkd::combine_encrypted_key_shares(
+            &self.lockable_threshold_sig_data_store,
+            shares,
+            args,
+        );
+        self.metrics.observe_duration_seconds(
+            MetricsDomain::VetKd,
+            MetricsScope::Full,
+            "combine_encrypted_key_shares",
+            MetricsResult::from(&result),
+            start_time,
+        );
+        debug!(logger;
+            crypto.description => "end",
+            crypto.is_ok => result.is_ok(),
+            crypto.error => log_err(result.as_ref().err()),
+            crypto.signature => log_ok_content(&result),
+        );
+        result
+    }
+
+    fn verify_encrypted_key(
+        &self,
+        key: &VetKdEncryptedKey,
+        args: &VetKdArgs,
+    ) -> Result<(), VetKdKeyVerificationError> {
+        let log_id = get_log_id(&self.logger, module_path!());
+        let logger = new_logger!(&self.logger;
+            crypto.log_id => log_id,
+            crypto.trait_name => "VetKdProtocol",
+            crypto.method_name => "verify_encrypted_key",
+        );
+        debug!(logger;
+            crypto.description => "start",
+            crypto.signature => format!("{:?}", key),
+            crypto.signature_inputs => format!("{:?}", args),
+        );
+        let start_time = self.metrics.now();
+        let result =
+            vetkd::verify_encrypted_key(&self.lockable_threshold_sig_data_store, key, args);
+        self.metrics.observe_duration_seconds(
+            MetricsDomain::VetKd,
+            MetricsScope::Full,
+            "verify_encrypted_key",
+            MetricsResult::from(&result),
+            start_time,
+        );
+        debug!(logger;
+            crypto.description => "end",
+            crypto.is_ok => result.is_ok(),
+            crypto.error => log_err(result.as_ref().err()),
+        );
+        result
+    }
+}
+
 fn log_err<T: fmt::Display>(error_option: Option<&T>) -> String {
     if let Some(error) = error_option {
         return format!("{}", error);
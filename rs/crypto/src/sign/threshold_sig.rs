@@ -52,7 +52,7 @@ fn pub_coeffs_from_store(
     maybe_coeffs.ok_or(ThresholdSigDataNotFoundError::ThresholdSigDataNotFound { dkg_id })
 }
 
-fn transcript_data_from_store(
+pub(crate) fn transcript_data_from_store(
     dkg_id: DkgId,
     lockable_threshold_sig_data_store: &LockableThresholdSigDataStore,
 ) -> Result<TranscriptData, ThresholdSigDataNotFoundError> {
@@ -171,7 +171,7 @@ impl ThresholdSigVerifierInternal {
 /// Given that both cases indicate that the implementations of DKG and threshold
 /// signatures are not aligned and also a caller could not recover from this, we
 /// panic.
-fn lazily_calculated_public_key_from_store<C: ThresholdSignatureCspClient>(
+pub(crate) fn lazily_calculated_public_key_from_store<C: ThresholdSignatureCspClient>(
     lockable_threshold_sig_data_store: &LockableThresholdSigDataStore,
     threshold_sig_csp_client: &C,
     dkg_id: DkgId,
@@ -436,10 +436,16 @@ fn initial_ni_dkg_transcript_from_registry(
         .get_initial_dkg_transcripts(subnet_id, registry_version)
         .map_err(CryptoError::RegistryClient)?;
     match maybe_transcripts.value {
-        Some(transcripts) => Ok(match dkg_tag {
-            NiDkgTag::LowThreshold => transcripts.low_threshold,
-            NiDkgTag::HighThreshold => transcripts.high_threshold,
-        }),
+        Some(transcripts) => match dkg_tag {
+            NiDkgTag::LowThreshold => Ok(transcripts.low_threshold),
+            NiDkgTag::HighThreshold => Ok(transcripts.high_threshold),
+            // vetKD transcripts are created by the subnet itself and are
+            // never part of the registry.
+            NiDkgTag::VetKd => Err(CryptoError::DkgTranscriptNotFound {
+                subnet_id,
+                registry_version,
+            }),
+        },
         None => Err(CryptoError::DkgTranscriptNotFound {
             subnet_id,
             registry_version,
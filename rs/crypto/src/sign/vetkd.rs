@@ -0,0 +1,236 @@
+//! Verifiably encrypted threshold key derivation (vetKD)
+use super::threshold_sig::{lazily_calculated_public_key_from_store, transcript_data_from_store};
+use crate::LockableThresholdSigDataStore;
+use ic_crypto_internal_bls12_381_type::G2Affine;
+use ic_crypto_internal_csp::api::{CspVetKdProtocol, ThresholdSignatureCspClient};
+use ic_crypto_internal_csp::key_id::KeyId;
+use ic_crypto_internal_csp::types::CspPublicCoefficients;
+use ic_crypto_internal_csp::vault::api::VetKdEncryptedKeyShareCreationVaultError;
+use ic_crypto_internal_threshold_sig_bls12381::api::combined_public_key;
+use ic_crypto_internal_threshold_sig_bls12381::types::PublicKeyBytes;
+use ic_crypto_internal_threshold_sig_bls12381::vetkd::{
+    DerivationPath, EncryptedKey, EncryptedKeyCombinationError, EncryptedKeyShare,
+    TransportPublicKey,
+};
+use ic_types::crypto::canister_threshold_sig::ExtendedDerivationPath;
+use ic_types::crypto::threshold_sig::ni_dkg::DkgId;
+use ic_types::crypto::vetkd::{
+    VetKdArgs, VetKdEncryptedKey, VetKdEncryptedKeyShare, VetKdKeyShareCombinationError,
+    VetKdKeyShareCreationError, VetKdKeyShareVerificationError, VetKdKeyVerificationError,
+};
+use ic_types::crypto::CryptoError;
+use ic_types::{NodeId, NodeIndex};
+use std::collections::BTreeMap;
+
+pub fn create_encrypted_key_share<C: CspVetKdProtocol>(
+    lockable_threshold_sig_data_store: &LockableThresholdSigDataStore,
+    csp: &C,
+    args: VetKdArgs,
+) -> Result<VetKdEncryptedKeyShare, VetKdKeyShareCreationError> {
+    let dkg_id = DkgId::NiDkgId(args.ni_dkg_id);
+    let transcript_data = transcript_data_from_store(dkg_id, lockable_threshold_sig_data_store)
+        .map_err(VetKdKeyShareCreationError::ThresholdSigDataNotFound)?;
+    let public_coefficients = transcript_data.public_coefficients();
+    let master_public_key = master_public_key_bytes(public_coefficients)
+        .map_err(|e| VetKdKeyShareCreationError::InternalError(e.to_string()))?;
+    let key_id = KeyId::from(public_coefficients);
+
+    csp.create_encrypted_vetkd_key_share(
+        key_id,
+        master_public_key.0.to_vec(),
+        args.encryption_public_key,
+        args.derivation_path,
+        args.derivation_id,
+    )
+    .map_err(|e| match e {
+        VetKdEncryptedKeyShareCreationVaultError::SecretKeyMissingOrWrongType(_) => {
+            VetKdKeyShareCreationError::SecretKeyNotFound {
+                dkg_id: args.ni_dkg_id,
+                key_id: key_id.to_string(),
+            }
+        }
+        VetKdEncryptedKeyShareCreationVaultError::InvalidArgument(error) => {
+            VetKdKeyShareCreationError::InvalidArgument(error)
+        }
+        VetKdEncryptedKeyShareCreationVaultError::TransientInternalError(error) => {
+            VetKdKeyShareCreationError::TransientInternalError(error)
+        }
+    })
+}
+
+pub fn verify_encrypted_key_share<C: ThresholdSignatureCspClient>(
+    lockable_threshold_sig_data_store: &LockableThresholdSigDataStore,
+    csp: &C,
+    creator: NodeId,
+    key_share: &VetKdEncryptedKeyShare,
+    args: &VetKdArgs,
+) -> Result<(), VetKdKeyShareVerificationError> {
+    let dkg_id = DkgId::NiDkgId(args.ni_dkg_id);
+    let transcript_data = transcript_data_from_store(dkg_id, lockable_threshold_sig_data_store)
+        .map_err(VetKdKeyShareVerificationError::ThresholdSigDataNotFound)?;
+    if transcript_data.index(creator).is_none() {
+        return Err(VetKdKeyShareVerificationError::SignerNotInTranscript);
+    }
+    let master_public_key = master_public_key(transcript_data.public_coefficients())
+        .map_err(VetKdKeyShareVerificationError::InvalidArgument)?;
+    let master_public_key_share = lazily_calculated_public_key_from_store(
+        lockable_threshold_sig_data_store,
+        csp,
+        dkg_id,
+        creator,
+    )
+    .map_err(|e| VetKdKeyShareVerificationError::InvalidArgument(e.to_string()))
+    .and_then(|public_key| {
+        g2_from_public_key_bytes(&PublicKeyBytes::from(public_key))
+            .map_err(VetKdKeyShareVerificationError::InvalidArgument)
+    })?;
+    let transport_public_key = transport_public_key(&args.encryption_public_key)
+        .map_err(VetKdKeyShareVerificationError::InvalidArgument)?;
+    let encrypted_key_share = EncryptedKeyShare::deserialize(&key_share.encrypted_key_share)
+        .map_err(|e| {
+            VetKdKeyShareVerificationError::InvalidArgument(format!(
+                "invalid encrypted key share: {:?}",
+                e
+            ))
+        })?;
+
+    if encrypted_key_share.is_valid(
+        &master_public_key,
+        &master_public_key_share,
+        &transport_public_key,
+        &derivation_path(&args.derivation_path),
+        &args.derivation_id,
+    ) {
+        Ok(())
+    } else {
+        Err(VetKdKeyShareVerificationError::VerificationError)
+    }
+}
+
+pub fn combine_encrypted_key_shares(
+    lockable_threshold_sig_data_store: &LockableThresholdSigDataStore,
+    shares: &BTreeMap<NodeId, VetKdEncryptedKeyShare>,
+    args: &VetKdArgs,
+) -> Result<VetKdEncryptedKey, VetKdKeyShareCombinationError> {
+    let dkg_id = DkgId::NiDkgId(args.ni_dkg_id);
+    let transcript_data = transcript_data_from_store(dkg_id, lockable_threshold_sig_data_store)
+        .map_err(VetKdKeyShareCombinationError::ThresholdSigDataNotFound)?;
+    let CspPublicCoefficients::Bls12_381(public_coefficients) =
+        transcript_data.public_coefficients();
+    let threshold = public_coefficients.coefficients.len();
+    if shares.len() < threshold {
+        return Err(
+            VetKdKeyShareCombinationError::UnsatisfiedReconstructionThreshold {
+                threshold: threshold as u32,
+                share_count: shares.len(),
+            },
+        );
+    }
+
+    let master_public_key = master_public_key(transcript_data.public_coefficients())
+        .map_err(VetKdKeyShareCombinationError::InvalidArgument)?;
+    let transport_public_key = transport_public_key(&args.encryption_public_key)
+        .map_err(VetKdKeyShareCombinationError::InvalidArgument)?;
+    let shares_by_index = shares
+        .iter()
+        .map(|(node_id, share)| {
+            let index = transcript_data.index(*node_id).ok_or_else(|| {
+                VetKdKeyShareCombinationError::SignerNotInTranscript(format!(
+                    "node {} is not a receiver of the transcript with DKG ID {}",
+                    node_id, args.ni_dkg_id
+                ))
+            })?;
+            let share =
+                EncryptedKeyShare::deserialize(&share.encrypted_key_share).map_err(|e| {
+                    VetKdKeyShareCombinationError::InvalidArgument(format!(
+                        "invalid encrypted key share of node {}: {:?}",
+                        node_id, e
+                    ))
+                })?;
+            Ok((*index, share))
+        })
+        .collect::<Result<BTreeMap<NodeIndex, EncryptedKeyShare>, _>>()?;
+
+    EncryptedKey::combine(
+        &shares_by_index,
+        threshold,
+        &master_public_key,
+        &transport_public_key,
+        &derivation_path(&args.derivation_path),
+        &args.derivation_id,
+    )
+    .map(|encrypted_key| VetKdEncryptedKey {
+        encrypted_key: encrypted_key.serialize().to_vec(),
+    })
+    .map_err(|e| match e {
+        EncryptedKeyCombinationError::InsufficientShares {
+            num_shares,
+            threshold,
+        } => VetKdKeyShareCombinationError::UnsatisfiedReconstructionThreshold {
+            threshold: threshold as u32,
+            share_count: num_shares,
+        },
+        EncryptedKeyCombinationError::InterpolationFailed
+        | EncryptedKeyCombinationError::InvalidShares => {
+            VetKdKeyShareCombinationError::CombinationError(format!("{:?}", e))
+        }
+    })
+}
+
+pub fn verify_encrypted_key(
+    lockable_threshold_sig_data_store: &LockableThresholdSigDataStore,
+    key: &VetKdEncryptedKey,
+    args: &VetKdArgs,
+) -> Result<(), VetKdKeyVerificationError> {
+    let dkg_id = DkgId::NiDkgId(args.ni_dkg_id);
+    let transcript_data = transcript_data_from_store(dkg_id, lockable_threshold_sig_data_store)
+        .map_err(VetKdKeyVerificationError::ThresholdSigDataNotFound)?;
+    let master_public_key = master_public_key(transcript_data.public_coefficients())
+        .map_err(VetKdKeyVerificationError::InvalidArgument)?;
+    let transport_public_key = transport_public_key(&args.encryption_public_key)
+        .map_err(VetKdKeyVerificationError::InvalidArgument)?;
+    let encrypted_key = EncryptedKey::deserialize(&key.encrypted_key).map_err(|e| {
+        VetKdKeyVerificationError::InvalidArgument(format!("invalid encrypted key: {:?}", e))
+    })?;
+
+    if encrypted_key.is_valid(
+        &master_public_key,
+        &transport_public_key,
+        &derivation_path(&args.derivation_path),
+        &args.derivation_id,
+    ) {
+        Ok(())
+    } else {
+        Err(VetKdKeyVerificationError::VerificationError)
+    }
+}
+
+fn master_public_key_bytes(
+    public_coefficients: &CspPublicCoefficients,
+) -> Result<PublicKeyBytes, CryptoError> {
+    let CspPublicCoefficients::Bls12_381(public_coefficients) = public_coefficients;
+    combined_public_key(public_coefficients)
+}
+
+fn master_public_key(public_coefficients: &CspPublicCoefficients) -> Result<G2Affine, String> {
+    master_public_key_bytes(public_coefficients)
+        .map_err(|e| e.to_string())
+        .and_then(|public_key| g2_from_public_key_bytes(&public_key))
+}
+
+fn g2_from_public_key_bytes(public_key: &PublicKeyBytes) -> Result<G2Affine, String> {
+    G2Affine::deserialize(&public_key.0)
+        .map_err(|_| format!("invalid threshold public key: {:?}", public_key))
+}
+
+fn transport_public_key(encryption_public_key: &[u8]) -> Result<TransportPublicKey, String> {
+    TransportPublicKey::deserialize(encryption_public_key)
+        .map_err(|e| format!("invalid encryption public key: {:?}", e))
+}
+
+fn derivation_path(derivation_path: &ExtendedDerivationPath) -> DerivationPath {
+    DerivationPath::new(
+        derivation_path.caller.as_slice(),
+        &derivation_path.derivation_path,
+    )
+}
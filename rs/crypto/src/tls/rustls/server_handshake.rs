@@ -17,8 +17,8 @@ use tokio::net::TcpStream;
 use tokio_rustls::rustls::ciphersuite::{TLS13_AES_128_GCM_SHA256, TLS13_AES_256_GCM_SHA384};
 use tokio_rustls::rustls::sign::CertifiedKey;
 use tokio_rustls::rustls::{
-    ClientCertVerifier, NoClientAuth, ProtocolVersion, ResolvesServerCert, ServerConfig, Session,
-    SignatureScheme,
+    Certificate, ClientCertVerifier, NoClientAuth, ProtocolVersion, ResolvesServerCert,
+    ServerConfig, Session, SignatureScheme,
 };
 use tokio_rustls::TlsAcceptor;
 
@@ -30,6 +30,31 @@ pub async fn perform_tls_server_handshake<P: CspTlsHandshakeSignerProvider>(
     allowed_clients: AllowedClients,
     registry_version: RegistryVersion,
 ) -> Result<(Box<dyn TlsStream>, AuthenticatedPeer), TlsServerHandshakeError> {
+    let config = server_config(
+        signer_provider,
+        self_node_id,
+        registry_client,
+        allowed_clients,
+        registry_version,
+    )?;
+
+    let rustls_stream = accept_connection(tcp_stream, config).await?;
+
+    let authenticated_peer = authenticated_peer_from_client_certs(
+        rustls_stream.get_ref().1.get_peer_certificates().as_deref(),
+    )?;
+    let tls_stream = RustlsTlsStream::new(tokio_rustls::TlsStream::from(rustls_stream));
+
+    Ok((Box::new(tls_stream), authenticated_peer))
+}
+
+pub fn server_config<P: CspTlsHandshakeSignerProvider>(
+    signer_provider: &P,
+    self_node_id: NodeId,
+    registry_client: Arc<dyn RegistryClient>,
+    allowed_clients: AllowedClients,
+    registry_version: RegistryVersion,
+) -> Result<ServerConfig, TlsServerHandshakeError> {
     let self_tls_cert =
         tls_cert_from_registry(registry_client.as_ref(), self_node_id, registry_version)?;
     let self_tls_cert_key_id = KeyId::try_from(&self_tls_cert).map_err(|error| {
@@ -44,22 +69,21 @@ pub async fn perform_tls_server_handshake<P: CspTlsHandshakeSignerProvider>(
     );
     let ed25519_signing_key =
         CspServerEd25519SigningKey::new(self_tls_cert_key_id, signer_provider.handshake_signer());
-    let config = server_config_with_tls13_and_aes_ciphersuites_and_ed25519_signing_key(
-        Arc::new(client_cert_verifier),
-        self_tls_cert,
-        ed25519_signing_key,
-    );
-
-    let rustls_stream = accept_connection(tcp_stream, config).await?;
+    Ok(
+        server_config_with_tls13_and_aes_ciphersuites_and_ed25519_signing_key(
+            Arc::new(client_cert_verifier),
+            self_tls_cert,
+            ed25519_signing_key,
+        ),
+    )
+}
 
-    let client_cert_from_handshake = single_client_cert_from_handshake(&rustls_stream)?;
+pub fn authenticated_peer_from_client_certs(
+    peer_certs: Option<&[Certificate]>,
+) -> Result<AuthenticatedPeer, TlsServerHandshakeError> {
+    let client_cert_from_handshake = single_client_cert_from_handshake(peer_certs)?;
     let authenticated_peer = node_id_from_cert_subject_common_name(&client_cert_from_handshake)?;
-    let tls_stream = RustlsTlsStream::new(tokio_rustls::TlsStream::from(rustls_stream));
-
-    Ok((
-        Box::new(tls_stream),
-        AuthenticatedPeer::Node(authenticated_peer),
-    ))
+    Ok(AuthenticatedPeer::Node(authenticated_peer))
 }
 
 pub async fn perform_tls_server_handshake_without_client_auth<P: CspTlsHandshakeSignerProvider>(
@@ -126,13 +150,11 @@ fn static_cert_resolver(key: CertifiedKey, scheme: SignatureScheme) -> Arc<dyn R
 }
 
 fn single_client_cert_from_handshake(
-    tls_stream: &tokio_rustls::server::TlsStream<TcpStream>,
+    peer_certs: Option<&[Certificate]>,
 ) -> Result<TlsPublicKeyCert, TlsServerHandshakeError> {
-    let peer_certs = tls_stream.get_ref().1.get_peer_certificates().ok_or(
-        TlsServerHandshakeError::HandshakeError {
-            internal_error: "missing peer certificates in session".to_string(),
-        },
-    )?;
+    let peer_certs = peer_certs.ok_or(TlsServerHandshakeError::HandshakeError {
+        internal_error: "missing peer certificates in session".to_string(),
+    })?;
     if peer_certs.len() > 1 {
         return Err(TlsServerHandshakeError::HandshakeError {
             internal_error: "peer sent more than one certificate, but expected only a single one"
@@ -12,8 +12,8 @@ use ic_crypto_temp_crypto_vault::{
     RemoteVaultEnvironment, TempCspVaultServer, TokioRuntimeOrHandle,
 };
 use ic_crypto_tls_interfaces::{
-    AllowedClients, AuthenticatedPeer, TlsClientHandshakeError, TlsHandshake, TlsPublicKeyCert,
-    TlsServerHandshakeError, TlsStream,
+    rustls, AllowedClients, AuthenticatedPeer, TlsClientHandshakeError, TlsHandshake,
+    TlsPublicKeyCert, TlsServerHandshakeError, TlsStream,
 };
 use ic_crypto_utils_time::CurrentSystemTimeSource;
 use ic_interfaces::crypto::{
@@ -22,6 +22,7 @@ use ic_interfaces::crypto::{
     IdkgDealingEncPubKeysCountError, KeyManager, LoadTranscriptResult, MultiSigVerifier,
     MultiSigner, NiDkgAlgorithm, PublicKeyRegistrationStatus, ThresholdEcdsaSigVerifier,
     ThresholdEcdsaSigner, ThresholdSigVerifier, ThresholdSigVerifierByPublicKey, ThresholdSigner,
+    VetKdProtocol,
 };
 use ic_interfaces::time_source::TimeSource;
 use ic_interfaces_registry::RegistryClient;
@@ -57,6 +58,10 @@ use ic_types::crypto::threshold_sig::ni_dkg::errors::{
     verify_dealing_error::DkgVerifyDealingError,
 };
 use ic_types::crypto::threshold_sig::ni_dkg::{DkgId, NiDkgDealing, NiDkgTranscript};
+use ic_types::crypto::vetkd::{
+    VetKdArgs, VetKdEncryptedKey, VetKdEncryptedKeyShare, VetKdKeyShareCombinationError,
+    VetKdKeyShareCreationError, VetKdKeyShareVerificationError, VetKdKeyVerificationError,
+};
 use ic_types::crypto::{
     BasicSigOf, CanisterSigOf, CombinedMultiSigOf, CombinedThresholdSigOf, CryptoResult,
     CurrentNodePublicKeys, IndividualMultiSigOf, KeyPurpose, Signable, ThresholdSigShareOf,
@@ -459,6 +464,8 @@ impl EcdsaSubnetConfig {
                     idkg_key_rotation_period_ms: key_rotation_period
                         .map(|key_rotation_period| key_rotation_period.as_millis() as u64),
                 }),
+                adaptive_notarization_delays: false,
+                vetkd_config: None,
             },
         }
     }
@@ -755,6 +762,42 @@ impl<C: CryptoServiceProvider> ThresholdEcdsaSigVerifier for TempCryptoComponent
     }
 }
 
+impl<C: CryptoServiceProvider> VetKdProtocol for TempCryptoComponentGeneric<C> {
+    fn create_encrypted_key_share(
+        &self,
+        args: VetKdArgs,
+    ) -> Result<VetKdEncryptedKeyShare, VetKdKeyShareCreationError> {
+        self.crypto_component.create_encrypted_key_share(args)
+    }
+
+    fn verify_encrypted_key_share(
+        &self,
+        creator: NodeId,
+        key_share: &VetKdEncryptedKeyShare,
+        args: &VetKdArgs,
+    ) -> Result<(), VetKdKeyShareVerificationError> {
+        self.crypto_component
+            .verify_encrypted_key_share(creator, key_share, args)
+    }
+
+    fn combine_encrypted_key_shares(
+        &self,
+        shares: &BTreeMap<NodeId, VetKdEncryptedKeyShare>,
+        args: &VetKdArgs,
+    ) -> Result<VetKdEncryptedKey, VetKdKeyShareCombinationError> {
+        self.crypto_component
+            .combine_encrypted_key_shares(shares, args)
+    }
+
+    fn verify_encrypted_key(
+        &self,
+        key: &VetKdEncryptedKey,
+        args: &VetKdArgs,
+    ) -> Result<(), VetKdKeyVerificationError> {
+        self.crypto_component.verify_encrypted_key(key, args)
+    }
+}
+
 #[async_trait]
 impl<C: CryptoServiceProvider + Send + Sync> TlsHandshake for TempCryptoComponentGeneric<C> {
     async fn perform_tls_server_handshake(
@@ -788,6 +831,32 @@ impl<C: CryptoServiceProvider + Send + Sync> TlsHandshake for TempCryptoComponen
             .perform_tls_client_handshake(tcp_stream, server, registry_version)
             .await
     }
+
+    fn tls_server_config(
+        &self,
+        allowed_clients: AllowedClients,
+        registry_version: RegistryVersion,
+    ) -> Result<rustls::ServerConfig, TlsServerHandshakeError> {
+        self.crypto_component
+            .tls_server_config(allowed_clients, registry_version)
+    }
+
+    fn tls_client_config(
+        &self,
+        server: NodeId,
+        registry_version: RegistryVersion,
+    ) -> Result<rustls::ClientConfig, TlsClientHandshakeError> {
+        self.crypto_component
+            .tls_client_config(server, registry_version)
+    }
+
+    fn authenticated_peer_from_client_certs(
+        &self,
+        client_certs: &[rustls::Certificate],
+    ) -> Result<AuthenticatedPeer, TlsServerHandshakeError> {
+        self.crypto_component
+            .authenticated_peer_from_client_certs(client_certs)
+    }
 }
 
 impl<C: CryptoServiceProvider, T: Signable> BasicSigVerifier<T> for TempCryptoComponentGeneric<C> {
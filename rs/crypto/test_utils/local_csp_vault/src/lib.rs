@@ -25,6 +25,8 @@ use ic_crypto_internal_csp::vault::api::ThresholdEcdsaSignerCspVault;
 use ic_crypto_internal_csp::vault::api::ThresholdSignatureCspVault;
 use ic_crypto_internal_csp::vault::api::TlsHandshakeCspVault;
 use ic_crypto_internal_csp::vault::api::ValidatePksAndSksError;
+use ic_crypto_internal_csp::vault::api::VetKdCspVault;
+use ic_crypto_internal_csp::vault::api::VetKdEncryptedKeyShareCreationVaultError;
 use ic_crypto_internal_seed::Seed;
 use ic_crypto_internal_threshold_sig_bls12381::api::ni_dkg_errors;
 use ic_crypto_internal_threshold_sig_ecdsa::{
@@ -44,6 +46,7 @@ use ic_types::crypto::canister_threshold_sig::error::{
     IDkgVerifyDealingPrivateError, ThresholdEcdsaSignShareError,
 };
 use ic_types::crypto::canister_threshold_sig::ExtendedDerivationPath;
+use ic_types::crypto::vetkd::VetKdEncryptedKeyShare;
 use ic_types::crypto::{AlgorithmId, CurrentNodePublicKeys};
 use ic_types::{NodeId, NodeIndex, NumberOfNodes, Randomness};
 use mockall::mock;
@@ -203,6 +206,17 @@ mock! {
         ) -> Result<ThresholdEcdsaSigShareInternal, ThresholdEcdsaSignShareError>;
     }
 
+    pub trait VetKdCspVault {
+        fn create_encrypted_vetkd_key_share(
+            &self,
+            key_id: KeyId,
+            master_public_key: Vec<u8>,
+            encryption_public_key: Vec<u8>,
+            derivation_path: ExtendedDerivationPath,
+            derivation_id: Vec<u8>,
+        ) -> Result<VetKdEncryptedKeyShare, VetKdEncryptedKeyShareCreationVaultError>;
+    }
+
     pub trait SecretKeyStoreCspVault {
         fn sks_contains(&self, key_id: &KeyId) -> Result<bool, CspSecretKeyStoreContainsError>;
     }
@@ -228,6 +228,7 @@ fn registry_with_ni_dkg_transcript(
             cup_contents.initial_ni_dkg_transcript_low_threshold =
                 Some(InitialNiDkgTranscriptRecord::from(transcript()));
         }
+        NiDkgTag::VetKd => panic!("vetKD transcripts are not part of the CUP contents"),
     }
     let registry_data = ProtoRegistryDataProvider::new();
     registry_data
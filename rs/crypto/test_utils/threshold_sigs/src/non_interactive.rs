@@ -305,7 +305,7 @@ impl RandomNiDkgConfig {
 
         let dkg_tag = match self.0.dkg_id().dkg_tag {
             NiDkgTag::LowThreshold => NiDkgTag::HighThreshold,
-            NiDkgTag::HighThreshold => NiDkgTag::LowThreshold,
+            NiDkgTag::HighThreshold | NiDkgTag::VetKd => NiDkgTag::LowThreshold,
         };
 
         let subnet_size = self.0.receivers().get().len();
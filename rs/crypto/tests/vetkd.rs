@@ -0,0 +1,168 @@
+#![allow(clippy::unwrap_used)]
+use ic_crypto_internal_bls12_381_type::G2Affine;
+use ic_crypto_internal_threshold_sig_bls12381::vetkd::{
+    DerivationPath, EncryptedKey, TransportSecretKey,
+};
+use ic_crypto_temp_crypto::TempCryptoComponent;
+use ic_crypto_test_utils::crypto_for;
+use ic_crypto_test_utils_threshold_sigs::non_interactive::{
+    run_ni_dkg_and_create_single_transcript, NiDkgTestEnvironment, RandomNiDkgConfig,
+};
+use ic_interfaces::crypto::{NiDkgAlgorithm, VetKdProtocol};
+use ic_types::crypto::canister_threshold_sig::ExtendedDerivationPath;
+use ic_types::crypto::threshold_sig::ni_dkg::config::NiDkgConfig;
+use ic_types::crypto::threshold_sig::ni_dkg::NiDkgTranscript;
+use ic_types::crypto::threshold_sig::ThresholdSigPublicKey;
+use ic_types::crypto::vetkd::{
+    VetKdArgs, VetKdEncryptedKeyShare, VetKdKeyShareCombinationError,
+    VetKdKeyShareVerificationError,
+};
+use ic_types::{NodeId, PrincipalId};
+use rand::prelude::*;
+use std::collections::BTreeMap;
+
+#[test]
+fn should_derive_and_decrypt_key_with_shares_of_all_receivers() {
+    let rng = &mut thread_rng();
+    let subnet_size = rng.gen_range(1..7);
+    let (config, transcript, crypto_components) = setup_with_loaded_transcript(subnet_size);
+    let transport_secret_key = TransportSecretKey::generate(rng);
+    let args = vetkd_args(&config, &transport_secret_key);
+
+    let shares = create_key_shares(&config, &args, &crypto_components);
+    let verifier = random_node_in(&config, rng);
+    for (creator, share) in &shares {
+        assert_eq!(
+            crypto_for(verifier, &crypto_components)
+                .verify_encrypted_key_share(*creator, share, &args),
+            Ok(())
+        );
+    }
+
+    let combiner = random_node_in(&config, rng);
+    let encrypted_key = crypto_for(combiner, &crypto_components)
+        .combine_encrypted_key_shares(&shares, &args)
+        .unwrap();
+    assert_eq!(
+        crypto_for(verifier, &crypto_components).verify_encrypted_key(&encrypted_key, &args),
+        Ok(())
+    );
+
+    let master_public_key =
+        G2Affine::deserialize(&ThresholdSigPublicKey::from(&transcript).into_bytes()).unwrap();
+    let decrypted_key = transport_secret_key.decrypt(
+        &EncryptedKey::deserialize(&encrypted_key.encrypted_key).unwrap(),
+        &master_public_key,
+        &DerivationPath::new(
+            args.derivation_path.caller.as_slice(),
+            &args.derivation_path.derivation_path,
+        ),
+        &args.derivation_id,
+    );
+    assert!(decrypted_key.is_some());
+}
+
+#[test]
+fn should_reject_key_share_for_different_derivation_id() {
+    let rng = &mut thread_rng();
+    let (config, _transcript, crypto_components) = setup_with_loaded_transcript(4);
+    let transport_secret_key = TransportSecretKey::generate(rng);
+    let args = vetkd_args(&config, &transport_secret_key);
+    let other_args = VetKdArgs {
+        derivation_id: b"other-derivation-id".to_vec(),
+        ..args.clone()
+    };
+
+    let creator = random_node_in(&config, rng);
+    let share = crypto_for(creator, &crypto_components)
+        .create_encrypted_key_share(other_args)
+        .unwrap();
+
+    assert_eq!(
+        crypto_for(random_node_in(&config, rng), &crypto_components)
+            .verify_encrypted_key_share(creator, &share, &args),
+        Err(VetKdKeyShareVerificationError::VerificationError)
+    );
+}
+
+#[test]
+fn should_fail_to_combine_insufficient_shares() {
+    let rng = &mut thread_rng();
+    // Need >=4 nodes to have >=2 shares to combine in a low-threshold config
+    let subnet_size = rng.gen_range(4..7);
+    let (config, _transcript, crypto_components) = setup_with_loaded_transcript(subnet_size);
+    let transport_secret_key = TransportSecretKey::generate(rng);
+    let args = vetkd_args(&config, &transport_secret_key);
+    let threshold = config.threshold().get().get();
+
+    let shares: BTreeMap<_, _> = create_key_shares(&config, &args, &crypto_components)
+        .into_iter()
+        .take(threshold as usize - 1)
+        .collect();
+
+    assert_eq!(
+        crypto_for(random_node_in(&config, rng), &crypto_components)
+            .combine_encrypted_key_shares(&shares, &args),
+        Err(
+            VetKdKeyShareCombinationError::UnsatisfiedReconstructionThreshold {
+                threshold,
+                share_count: threshold as usize - 1
+            }
+        )
+    );
+}
+
+fn setup_with_loaded_transcript(
+    subnet_size: usize,
+) -> (
+    NiDkgConfig,
+    NiDkgTranscript,
+    BTreeMap<NodeId, TempCryptoComponent>,
+) {
+    let config = RandomNiDkgConfig::builder()
+        .subnet_size(subnet_size)
+        .build()
+        .into_config();
+    let crypto_components = NiDkgTestEnvironment::new_for_config(&config).crypto_components;
+    let transcript = run_ni_dkg_and_create_single_transcript(&config, &crypto_components);
+    for node_id in config.receivers().get() {
+        crypto_for(*node_id, &crypto_components)
+            .load_transcript(&transcript)
+            .unwrap();
+    }
+    (config, transcript, crypto_components)
+}
+
+fn vetkd_args(config: &NiDkgConfig, transport_secret_key: &TransportSecretKey) -> VetKdArgs {
+    VetKdArgs {
+        ni_dkg_id: config.dkg_id(),
+        derivation_path: ExtendedDerivationPath {
+            caller: PrincipalId::new_user_test_id(1),
+            derivation_path: vec![b"path".to_vec()],
+        },
+        derivation_id: b"derivation-id".to_vec(),
+        encryption_public_key: transport_secret_key.public_key().serialize().to_vec(),
+    }
+}
+
+fn create_key_shares(
+    config: &NiDkgConfig,
+    args: &VetKdArgs,
+    crypto_components: &BTreeMap<NodeId, TempCryptoComponent>,
+) -> BTreeMap<NodeId, VetKdEncryptedKeyShare> {
+    config
+        .receivers()
+        .get()
+        .iter()
+        .map(|node_id| {
+            let share = crypto_for(*node_id, crypto_components)
+                .create_encrypted_key_share(args.clone())
+                .unwrap();
+            (*node_id, share)
+        })
+        .collect()
+}
+
+fn random_node_in<R: Rng>(config: &NiDkgConfig, rng: &mut R) -> NodeId {
+    *config.receivers().get().iter().choose(rng).unwrap()
+}
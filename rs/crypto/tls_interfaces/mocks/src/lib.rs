@@ -1,7 +1,7 @@
 use async_trait::async_trait;
 use ic_base_types::{NodeId, RegistryVersion};
 use ic_crypto_tls_interfaces::{
-    AllowedClients, AuthenticatedPeer, TlsClientHandshakeError, TlsHandshake,
+    rustls, AllowedClients, AuthenticatedPeer, TlsClientHandshakeError, TlsHandshake,
     TlsServerHandshakeError, TlsStream,
 };
 use mockall::*;
@@ -31,5 +31,22 @@ mock! {
             server: NodeId,
             registry_version: RegistryVersion,
         ) -> Result<Box<dyn TlsStream>, TlsClientHandshakeError>;
+
+        fn tls_server_config(
+            &self,
+            allowed_clients: AllowedClients,
+            registry_version: RegistryVersion,
+        ) -> Result<rustls::ServerConfig, TlsServerHandshakeError>;
+
+        fn tls_client_config(
+            &self,
+            server: NodeId,
+            registry_version: RegistryVersion,
+        ) -> Result<rustls::ClientConfig, TlsClientHandshakeError>;
+
+        fn authenticated_peer_from_client_certs(
+            &self,
+            client_certs: &[rustls::Certificate],
+        ) -> Result<AuthenticatedPeer, TlsServerHandshakeError>;
     }
 }
@@ -17,6 +17,10 @@ use std::hash::{Hash, Hasher};
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::TcpStream;
 
+/// The rustls version that the configurations returned by `TlsHandshake` are
+/// built with.
+pub use tokio_rustls::rustls;
+
 #[cfg(test)]
 mod tests;
 
@@ -396,6 +400,65 @@ pub trait TlsHandshake {
         server: NodeId,
         registry_version: RegistryVersion,
     ) -> Result<Box<dyn TlsStream>, TlsClientHandshakeError>;
+
+    /// Returns the rustls server configuration that
+    /// `perform_tls_server_handshake` uses, for protocols that run the TLS
+    /// handshake themselves rather than on top of a TCP stream (e.g. QUIC).
+    ///
+    /// The configuration authenticates clients exactly as described for
+    /// `perform_tls_server_handshake`. The node's secret key stays in the
+    /// secret key store: the configuration only holds a handle that signs
+    /// handshake messages. Use `authenticated_peer_from_client_certs` to
+    /// determine the peer of a handshake that completed with this
+    /// configuration.
+    ///
+    /// # Errors
+    /// * TlsServerHandshakeError::RegistryError if the registry cannot be
+    ///   accessed.
+    /// * TlsServerHandshakeError::CertificateNotInRegistry if the node's own
+    ///   certificate is not found in the registry.
+    /// * TlsServerHandshakeError::MalformedSelfCertificate if the node's own
+    ///   server certificate is malformed.
+    fn tls_server_config(
+        &self,
+        allowed_clients: AllowedClients,
+        registry_version: RegistryVersion,
+    ) -> Result<rustls::ServerConfig, TlsServerHandshakeError>;
+
+    /// Returns the rustls client configuration that
+    /// `perform_tls_client_handshake` uses, for protocols that run the TLS
+    /// handshake themselves rather than on top of a TCP stream (e.g. QUIC).
+    ///
+    /// The configuration only accepts the `server` as peer, authenticated
+    /// exactly as described for `perform_tls_client_handshake`. Hostname
+    /// verification is disabled, so any server name can be used to connect.
+    ///
+    /// # Errors
+    /// * TlsClientHandshakeError::RegistryError if the registry cannot be
+    ///   accessed.
+    /// * TlsClientHandshakeError::CertificateNotInRegistry if the node's own
+    ///   certificate is not found in the registry.
+    /// * TlsClientHandshakeError::MalformedSelfCertificate if the node's own
+    ///   client certificate is malformed.
+    fn tls_client_config(
+        &self,
+        server: NodeId,
+        registry_version: RegistryVersion,
+    ) -> Result<rustls::ClientConfig, TlsClientHandshakeError>;
+
+    /// Determines the peer that authenticated with the given certificate
+    /// chain in a handshake performed with a configuration obtained from
+    /// `tls_server_config`.
+    ///
+    /// # Errors
+    /// * TlsServerHandshakeError::HandshakeError if the chain does not consist
+    ///   of exactly one well-formed certificate.
+    /// * TlsServerHandshakeError::MalformedClientCertificate if the subject
+    ///   CN of the certificate is not a node ID.
+    fn authenticated_peer_from_client_certs(
+        &self,
+        client_certs: &[rustls::Certificate],
+    ) -> Result<AuthenticatedPeer, TlsServerHandshakeError>;
 }
 
 #[derive(Clone, Debug)]
@@ -0,0 +1,34 @@
+use ic_crypto_internal_bls12_381_type::G2Affine;
+use ic_crypto_internal_threshold_sig_bls12381::vetkd::{
+    derive_public_key, DerivationPath, TransportPublicKey,
+};
+use ic_types::crypto::canister_threshold_sig::ExtendedDerivationPath;
+use ic_types::crypto::threshold_sig::ThresholdSigPublicKey;
+use ic_types::crypto::vetkd::VetKdDerivePublicKeyError;
+
+/// Derives the vetKD public key from the specified `master_public_key` for
+/// the given `extended_derivation_path`.
+pub fn derive_vetkd_public_key(
+    master_public_key: &ThresholdSigPublicKey,
+    extended_derivation_path: &ExtendedDerivationPath,
+) -> Result<Vec<u8>, VetKdDerivePublicKeyError> {
+    let master_public_key =
+        G2Affine::deserialize(&master_public_key.into_bytes()).map_err(|_| {
+            VetKdDerivePublicKeyError::InvalidArgument(
+                "master public key is not a valid G2 point".to_string(),
+            )
+        })?;
+    let derivation_path = DerivationPath::new(
+        extended_derivation_path.caller.as_slice(),
+        &extended_derivation_path.derivation_path,
+    );
+    Ok(derive_public_key(&master_public_key, &derivation_path)
+        .serialize()
+        .to_vec())
+}
+
+/// Returns true if `encryption_public_key` is a valid transport public key,
+/// i.e., one that encrypted key shares can be created for.
+pub fn is_valid_transport_public_key(encryption_public_key: &[u8]) -> bool {
+    TransportPublicKey::deserialize(encryption_public_key).is_ok()
+}
@@ -0,0 +1,10 @@
+fn main() {
+    let did_path = std::path::PathBuf::from("cketh_minter.did")
+        .canonicalize()
+        .unwrap();
+
+    println!(
+        "cargo:rustc-env=CKETH_MINTER_DID_PATH={}",
+        did_path.display()
+    );
+}
@@ -0,0 +1,150 @@
+//! Ethereum addresses and their EIP-55 textual representation.
+
+use crate::keccak256;
+use candid::types::{Serializer, Type};
+use candid::CandidType;
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use serde::{de, Deserialize, Deserializer, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// A 20-byte Ethereum account address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Address([u8; 20]);
+
+impl Address {
+    pub const ZERO: Self = Self([0u8; 20]);
+
+    pub const fn new(bytes: [u8; 20]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 20] {
+        &self.0
+    }
+
+    /// Derives the address controlled by the specified SEC1-encoded secp256k1
+    /// public key.  The address is the last 20 bytes of the Keccak-256 hash of
+    /// the uncompressed key without the 0x04 prefix.
+    pub fn from_pubkey(sec1_pubkey: &[u8]) -> Result<Self, String> {
+        let pubkey = k256::PublicKey::from_sec1_bytes(sec1_pubkey)
+            .map_err(|e| format!("failed to parse the public key: {}", e))?;
+        let uncompressed = pubkey.to_encoded_point(false);
+        let hash = keccak256(&uncompressed.as_bytes()[1..]);
+        let mut bytes = [0u8; 20];
+        bytes.copy_from_slice(&hash[12..]);
+        Ok(Self(bytes))
+    }
+}
+
+impl fmt::Display for Address {
+    /// Formats the address according to EIP-55 (mixed-case checksum).
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hex = hex::encode(self.0);
+        let hash = keccak256(hex.as_bytes());
+        write!(f, "0x")?;
+        for (i, c) in hex.chars().enumerate() {
+            let nibble = (hash[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0x0f;
+            if c.is_ascii_alphabetic() && nibble >= 8 {
+                write!(f, "{}", c.to_ascii_uppercase())?;
+            } else {
+                write!(f, "{}", c)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Address {
+    type Err = String;
+
+    /// Parses a 0x-prefixed hex address.  Mixed-case addresses must have a
+    /// valid EIP-55 checksum.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex_str = s
+            .strip_prefix("0x")
+            .ok_or_else(|| "address doesn't start with '0x'".to_string())?;
+        if hex_str.len() != 40 {
+            return Err(format!(
+                "address has length {}, expected 40 hex digits",
+                hex_str.len()
+            ));
+        }
+        let mut bytes = [0u8; 20];
+        hex::decode_to_slice(hex_str, &mut bytes)
+            .map_err(|e| format!("address is not valid hex: {}", e))?;
+        let address = Self(bytes);
+
+        let is_mixed_case = hex_str.chars().any(|c| c.is_ascii_lowercase())
+            && hex_str.chars().any(|c| c.is_ascii_uppercase());
+        if is_mixed_case && address.to_string() != s {
+            return Err("address has an invalid EIP-55 checksum".to_string());
+        }
+        Ok(address)
+    }
+}
+
+impl Serialize for Address {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Address {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Self::from_str(&s).map_err(de::Error::custom)
+    }
+}
+
+impl CandidType for Address {
+    fn _ty() -> Type {
+        Type::Text
+    }
+
+    fn idl_serialize<S: Serializer>(&self, serializer: S) -> Result<(), S::Error> {
+        serializer.serialize_text(&self.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Address;
+    use std::str::FromStr;
+
+    #[test]
+    fn should_display_eip55_checksum() {
+        // Test vectors from https://eips.ethereum.org/EIPS/eip-55
+        for address in [
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ] {
+            let parsed = Address::from_str(&address.to_lowercase()).unwrap();
+            assert_eq!(parsed.to_string(), address);
+            assert_eq!(Address::from_str(address), Ok(parsed));
+        }
+    }
+
+    #[test]
+    fn should_reject_invalid_addresses() {
+        assert!(Address::from_str("5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").is_err());
+        assert!(Address::from_str("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeA").is_err());
+        assert!(Address::from_str("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeZ").is_err());
+        // Wrong checksum: the last letter must be lowercase.
+        assert!(Address::from_str("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD").is_err());
+    }
+
+    #[test]
+    fn should_derive_address_from_public_key() {
+        // The public key of the private key 1 is the secp256k1 generator.
+        let generator =
+            hex::decode("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+                .unwrap();
+        assert_eq!(
+            Address::from_pubkey(&generator).unwrap().to_string(),
+            "0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf"
+        );
+    }
+}
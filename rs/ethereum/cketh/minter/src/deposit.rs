@@ -0,0 +1,548 @@
+//! Detection of ETH deposits and minting of ckETH.
+//!
+//! Users deposit ETH by calling the `deposit` function of the helper smart
+//! contract, which forwards the funds to the minter's address and emits
+//! `ReceivedEth(address indexed from, uint256 value, bytes32 indexed principal)`.
+//! The minter periodically fetches these logs and mints ckETH to the encoded
+//! principal.
+
+use crate::address::Address;
+use crate::eth_rpc::{BlockSpec, GetLogsParam, Hash, LogEntry, Quantity};
+use crate::eth_rpc_client::EthRpcClient;
+use crate::guard::{TaskType, TimerGuard};
+use crate::logs::{P0, P1};
+use crate::state::{audit, mutate_state, read_state};
+use crate::{keccak256, wei_to_cketh_units};
+use candid::{CandidType, Nat, Principal};
+use ic_canister_log::log;
+use ic_icrc1::{endpoints::TransferArg, Account};
+use ic_icrc1_client_cdk::{CdkRuntime, ICRC1Client};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The signature of the deposit event of the helper contract.
+const RECEIVED_ETH_EVENT_SIGNATURE: &str = "ReceivedEth(address,uint256,bytes32)";
+
+/// The maximum number of blocks that the minter scrapes in one eth_getLogs
+/// call.
+pub const MAX_BLOCK_SPREAD: u64 = 1024;
+
+/// Returns the first topic of the deposit logs: the Keccak-256 hash of the
+/// event signature.
+pub fn received_eth_event_topic() -> Hash {
+    Hash(keccak256(RECEIVED_ETH_EVENT_SIGNATURE.as_bytes()))
+}
+
+/// Uniquely identifies a log entry.
+#[derive(
+    CandidType, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub struct EventSource {
+    #[serde(rename = "transaction_hash")]
+    pub transaction_hash: Hash,
+    #[serde(rename = "log_index")]
+    pub log_index: u64,
+}
+
+impl fmt::Display for EventSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.transaction_hash, self.log_index)
+    }
+}
+
+/// A deposit of ETH to the helper contract.
+#[derive(CandidType, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReceivedEthEvent {
+    #[serde(rename = "transaction_hash")]
+    pub transaction_hash: Hash,
+    #[serde(rename = "block_number")]
+    pub block_number: u64,
+    #[serde(rename = "log_index")]
+    pub log_index: u64,
+    #[serde(rename = "from_address")]
+    pub from_address: Address,
+    /// The deposited amount in wei.
+    #[serde(rename = "value")]
+    pub value: u128,
+    /// The principal that receives ckETH.
+    #[serde(rename = "principal")]
+    pub principal: Principal,
+}
+
+impl ReceivedEthEvent {
+    pub fn source(&self) -> EventSource {
+        EventSource {
+            transaction_hash: self.transaction_hash,
+            log_index: self.log_index,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReceivedEthEventError {
+    /// The log entry is not mined yet or was removed by a reorganization, so
+    /// it does not identify a deposit.
+    PendingLogEntry,
+    /// The log entry is a deposit that the minter cannot mint ckETH for.
+    InvalidEventSource { source: EventSource, error: String },
+}
+
+impl TryFrom<LogEntry> for ReceivedEthEvent {
+    type Error = ReceivedEthEventError;
+
+    fn try_from(entry: LogEntry) -> Result<Self, Self::Error> {
+        let (transaction_hash, block_number, log_index) =
+            match (entry.transaction_hash, entry.block_number, entry.log_index) {
+                (Some(hash), Some(block_number), Some(log_index)) if !entry.removed => {
+                    (hash, block_number, log_index)
+                }
+                _ => return Err(ReceivedEthEventError::PendingLogEntry),
+            };
+        let invalid = |error: String| ReceivedEthEventError::InvalidEventSource {
+            source: EventSource {
+                transaction_hash,
+                log_index: log_index.as_u64().unwrap_or(u64::MAX),
+            },
+            error,
+        };
+        let block_number = block_number.as_u64().map_err(invalid)?;
+        let log_index = log_index.as_u64().map_err(invalid)?;
+
+        if entry.topics.len() != 3 {
+            return Err(invalid(format!(
+                "expected 3 topics, got {}",
+                entry.topics.len()
+            )));
+        }
+        if entry.topics[0] != received_eth_event_topic() {
+            return Err(invalid(format!(
+                "unexpected event topic {}",
+                entry.topics[0]
+            )));
+        }
+
+        let from_topic = &entry.topics[1].0;
+        if from_topic[..12].iter().any(|b| *b != 0) {
+            return Err(invalid(format!(
+                "the sender topic {} is not an address",
+                entry.topics[1]
+            )));
+        }
+        let mut from_address = [0u8; 20];
+        from_address.copy_from_slice(&from_topic[12..]);
+
+        let value = parse_value(&entry.data.0).map_err(invalid)?;
+        let principal = parse_principal_from_slice(&entry.topics[2].0).map_err(invalid)?;
+
+        Ok(Self {
+            transaction_hash,
+            block_number,
+            log_index,
+            from_address: Address::new(from_address),
+            value,
+            principal,
+        })
+    }
+}
+
+/// Parses the uint256 deposit value from the log data.
+fn parse_value(data: &[u8]) -> Result<u128, String> {
+    if data.len() != 32 {
+        return Err(format!("expected 32 bytes of data, got {}", data.len()));
+    }
+    if data[..16].iter().any(|b| *b != 0) {
+        return Err("the deposited value does not fit into u128".to_string());
+    }
+    let mut value = [0u8; 16];
+    value.copy_from_slice(&data[16..]);
+    Ok(u128::from_be_bytes(value))
+}
+
+/// Decodes a principal from the bytes32 representation that the helper
+/// contract expects: the principal length, followed by the principal bytes,
+/// padded with zeros.
+pub fn parse_principal_from_slice(bytes: &[u8; 32]) -> Result<Principal, String> {
+    const ANONYMOUS_PRINCIPAL_BYTES: [u8; 1] = [4];
+
+    let len = bytes[0] as usize;
+    if len == 0 || len > 29 {
+        return Err(format!("invalid principal length {}", len));
+    }
+    if bytes[1 + len..].iter().any(|b| *b != 0) {
+        return Err("the principal encoding has non-zero padding".to_string());
+    }
+    let principal_bytes = &bytes[1..1 + len];
+    if principal_bytes == ANONYMOUS_PRINCIPAL_BYTES {
+        return Err("the anonymous principal cannot receive ckETH".to_string());
+    }
+    Principal::try_from_slice(principal_bytes).map_err(|e| format!("invalid principal: {}", e))
+}
+
+/// Encodes a principal into the bytes32 representation that the helper
+/// contract expects.
+pub fn encode_principal(principal: &Principal) -> [u8; 32] {
+    let bytes = principal.as_slice();
+    let mut result = [0u8; 32];
+    result[0] = bytes.len() as u8;
+    result[1..1 + bytes.len()].copy_from_slice(bytes);
+    result
+}
+
+/// Classifies a deposit log entry and records the outcome in the state.
+fn register_deposit(entry: LogEntry) {
+    match ReceivedEthEvent::try_from(entry) {
+        Ok(event) => {
+            if read_state(|s| s.has_seen_event(&event.source())) {
+                return;
+            }
+            if wei_to_cketh_units(event.value) == 0 {
+                log!(
+                    P1,
+                    "[scrap_eth_logs]: the deposit {} of {} wei is below one ckETH unit, keeping it as unminted wei",
+                    event.source(),
+                    event.value
+                );
+                mutate_state(|s| audit::record_unminted_deposit(s, event));
+                return;
+            }
+            log!(
+                P1,
+                "[scrap_eth_logs]: accepted a deposit of {} wei from {} to {} ({})",
+                event.value,
+                event.from_address,
+                event.principal,
+                event.source()
+            );
+            mutate_state(|s| audit::accept_deposit(s, event));
+        }
+        Err(ReceivedEthEventError::PendingLogEntry) => {
+            log!(P0, "[scrap_eth_logs]: skipping a pending log entry");
+        }
+        Err(ReceivedEthEventError::InvalidEventSource { source, error }) => {
+            if read_state(|s| s.has_seen_event(&source)) {
+                return;
+            }
+            log!(
+                P1,
+                "[scrap_eth_logs]: invalid deposit {}: {}",
+                source,
+                error
+            );
+            mutate_state(|s| audit::record_invalid_deposit(s, source, error));
+        }
+    }
+}
+
+/// Checks that the log entries returned for the blocks `[from_block,
+/// to_block]` are final: none of them is pending or removed, and all of them
+/// are in the requested blocks. Otherwise, the blocks must be scraped again
+/// later, since skipping them would lose deposits.
+fn ensure_final_log_entries(
+    entries: &[LogEntry],
+    from_block: u64,
+    to_block: u64,
+) -> Result<(), String> {
+    for entry in entries {
+        let block_number = match (
+            entry.transaction_hash,
+            entry.block_number,
+            entry.log_index,
+            entry.removed,
+        ) {
+            (Some(_), Some(block_number), Some(_), false) => block_number,
+            _ => return Err(format!("the log entry {:?} is pending", entry)),
+        };
+        match block_number.as_u64() {
+            Ok(n) if (from_block..=to_block).contains(&n) => {}
+            _ => {
+                return Err(format!(
+                    "the log entry {:?} is not in blocks [{}, {}]",
+                    entry, from_block, to_block
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Fetches the deposit logs from the blocks after the last scraped block up
+/// to the configured block tag and records the deposits.
+async fn scrap_until_block_tag(client: &EthRpcClient) {
+    let (contract_address, block_tag) =
+        read_state(|s| (s.ethereum_contract_address, s.ethereum_block_height));
+    let contract_address = match contract_address {
+        Some(address) => address,
+        None => {
+            log!(
+                P1,
+                "[scrap_eth_logs]: the helper contract address is not configured"
+            );
+            return;
+        }
+    };
+
+    let last_block_number = match client
+        .eth_get_block_by_number(BlockSpec::Tag(block_tag))
+        .await
+        .map_err(|e| format!("{:?}", e))
+        .and_then(|block| block.number.as_u64())
+    {
+        Ok(number) => number,
+        Err(e) => {
+            log!(
+                P0,
+                "[scrap_eth_logs]: failed to get the {} block: {}",
+                block_tag,
+                e
+            );
+            return;
+        }
+    };
+
+    loop {
+        let from_block = read_state(|s| s.last_scraped_block_number) + 1;
+        if from_block > last_block_number {
+            return;
+        }
+        let to_block = last_block_number.min(from_block + MAX_BLOCK_SPREAD - 1);
+
+        let entries = match client
+            .eth_get_logs(GetLogsParam {
+                from_block: BlockSpec::Number(Quantity(from_block as u128)),
+                to_block: BlockSpec::Number(Quantity(to_block as u128)),
+                address: vec![contract_address],
+                topics: vec![received_eth_event_topic()],
+            })
+            .await
+        {
+            Ok(entries) => entries,
+            Err(e) => {
+                log!(
+                    P0,
+                    "[scrap_eth_logs]: failed to get logs in blocks [{}, {}]: {:?}",
+                    from_block,
+                    to_block,
+                    e
+                );
+                return;
+            }
+        };
+
+        if let Err(e) = ensure_final_log_entries(&entries, from_block, to_block) {
+            log!(
+                P0,
+                "[scrap_eth_logs]: will scrap blocks [{}, {}] again: {}",
+                from_block,
+                to_block,
+                e
+            );
+            return;
+        }
+        for entry in entries {
+            register_deposit(entry);
+        }
+        mutate_state(|s| audit::update_last_scraped_block_number(s, to_block));
+    }
+}
+
+/// Mints ckETH for all accepted deposits.
+async fn mint_cketh() {
+    let (ledger_id, events) = read_state(|s| {
+        (
+            s.ledger_id,
+            s.events_to_mint.values().cloned().collect::<Vec<_>>(),
+        )
+    });
+    let client = ICRC1Client {
+        runtime: CdkRuntime,
+        ledger_canister_id: ledger_id.get().into(),
+    };
+
+    for event in events {
+        let amount = wei_to_cketh_units(event.value);
+        let result = client
+            .transfer(TransferArg {
+                from_subaccount: None,
+                to: Account {
+                    owner: event.principal.into(),
+                    subaccount: None,
+                },
+                fee: None,
+                created_at_time: None,
+                memo: None,
+                amount: Nat::from(amount),
+            })
+            .await;
+        match result {
+            Ok(Ok(block_index)) => {
+                log!(
+                    P1,
+                    "[mint_cketh]: minted {} ckETH units to {} for the deposit {} (block index {})",
+                    amount,
+                    event.principal,
+                    event.source(),
+                    block_index
+                );
+                mutate_state(|s| audit::mint_cketh(s, event.source(), block_index));
+            }
+            Ok(Err(err)) => {
+                log!(
+                    P0,
+                    "[mint_cketh]: the ledger rejected the mint for the deposit {}: {:?}",
+                    event.source(),
+                    err
+                );
+            }
+            Err((code, msg)) => {
+                log!(
+                    P0,
+                    "[mint_cketh]: failed to call the ledger for the deposit {}: {} (reject_code = {})",
+                    event.source(),
+                    msg,
+                    code
+                );
+                return;
+            }
+        }
+    }
+}
+
+/// Scrapes the helper contract logs for new deposits and mints ckETH for
+/// them.
+pub async fn scrap_eth_logs() {
+    let _guard = match TimerGuard::new(TaskType::ScrapEthLogs) {
+        Some(guard) => guard,
+        None => return,
+    };
+    let client = read_state(|s| EthRpcClient::for_network(s.ethereum_network));
+    scrap_until_block_tag(&client).await;
+    mint_cketh().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eth_rpc::Data;
+    use std::str::FromStr;
+
+    fn deposit_log_entry() -> LogEntry {
+        let mut from = [0u8; 32];
+        from[12..].copy_from_slice(
+            Address::from_str("0xdd2851Cdd40aE6536831558DD46db62fAc7A844d")
+                .unwrap()
+                .as_bytes(),
+        );
+        let mut value = [0u8; 32];
+        value[16..].copy_from_slice(&10_000_000_000_000_000u128.to_be_bytes());
+        LogEntry {
+            address: Address::from_str("0x7574eB42cA208A4f6960ECCAfDF186D627dCC175").unwrap(),
+            topics: vec![
+                received_eth_event_topic(),
+                Hash(from),
+                Hash(encode_principal(
+                    &Principal::from_text("2chl6-4hpzw-vqaaa-aaaaa-c").unwrap(),
+                )),
+            ],
+            data: Data(value.to_vec()),
+            block_number: Some(Quantity(0x3ca487)),
+            transaction_hash: Some(Hash([0xab; 32])),
+            log_index: Some(Quantity(0x27)),
+            removed: false,
+        }
+    }
+
+    #[test]
+    fn should_parse_deposit_event() {
+        let event = ReceivedEthEvent::try_from(deposit_log_entry()).unwrap();
+        assert_eq!(
+            event,
+            ReceivedEthEvent {
+                transaction_hash: Hash([0xab; 32]),
+                block_number: 0x3ca487,
+                log_index: 0x27,
+                from_address: Address::from_str("0xdd2851Cdd40aE6536831558DD46db62fAc7A844d")
+                    .unwrap(),
+                value: 10_000_000_000_000_000,
+                principal: Principal::from_text("2chl6-4hpzw-vqaaa-aaaaa-c").unwrap(),
+            }
+        );
+    }
+
+    #[test]
+    fn should_reject_pending_log_entries() {
+        let mut entry = deposit_log_entry();
+        entry.block_number = None;
+        assert_eq!(
+            ReceivedEthEvent::try_from(entry),
+            Err(ReceivedEthEventError::PendingLogEntry)
+        );
+
+        let mut entry = deposit_log_entry();
+        entry.removed = true;
+        assert_eq!(
+            ReceivedEthEvent::try_from(entry),
+            Err(ReceivedEthEventError::PendingLogEntry)
+        );
+    }
+
+    #[test]
+    fn should_reject_malformed_deposits() {
+        let mut entry = deposit_log_entry();
+        entry.topics[2] = Hash([0xff; 32]);
+        assert!(matches!(
+            ReceivedEthEvent::try_from(entry),
+            Err(ReceivedEthEventError::InvalidEventSource { source, .. })
+                if source == EventSource { transaction_hash: Hash([0xab; 32]), log_index: 0x27 }
+        ));
+
+        let mut entry = deposit_log_entry();
+        entry.topics[0] = Hash([0u8; 32]);
+        assert!(matches!(
+            ReceivedEthEvent::try_from(entry),
+            Err(ReceivedEthEventError::InvalidEventSource { .. })
+        ));
+
+        let mut entry = deposit_log_entry();
+        entry.data = Data(vec![0xff; 32]);
+        assert!(matches!(
+            ReceivedEthEvent::try_from(entry),
+            Err(ReceivedEthEventError::InvalidEventSource { .. })
+        ));
+    }
+
+    #[test]
+    fn should_only_accept_final_log_entries() {
+        assert_eq!(
+            ensure_final_log_entries(&[deposit_log_entry()], 0x3ca400, 0x3ca4ff),
+            Ok(())
+        );
+        assert_eq!(ensure_final_log_entries(&[], 0x3ca400, 0x3ca4ff), Ok(()));
+
+        let mut pending = deposit_log_entry();
+        pending.block_number = None;
+        assert!(
+            ensure_final_log_entries(&[deposit_log_entry(), pending], 0x3ca400, 0x3ca4ff).is_err()
+        );
+
+        let mut removed = deposit_log_entry();
+        removed.removed = true;
+        assert!(ensure_final_log_entries(&[removed], 0x3ca400, 0x3ca4ff).is_err());
+
+        assert!(ensure_final_log_entries(&[deposit_log_entry()], 0x3ca488, 0x3ca4ff).is_err());
+    }
+
+    #[test]
+    fn should_roundtrip_principal_encoding() {
+        for principal in [
+            Principal::from_text("2chl6-4hpzw-vqaaa-aaaaa-c").unwrap(),
+            Principal::try_from_slice(&[0x42; 29]).unwrap(),
+        ] {
+            assert_eq!(
+                parse_principal_from_slice(&encode_principal(&principal)),
+                Ok(principal)
+            );
+        }
+        assert!(parse_principal_from_slice(&encode_principal(&Principal::anonymous())).is_err());
+        assert!(parse_principal_from_slice(&[0u8; 32]).is_err());
+    }
+}
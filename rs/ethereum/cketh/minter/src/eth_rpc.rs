@@ -0,0 +1,489 @@
+//! This module contains definitions for communicating with an Ethereum API
+//! using the JSON RPC interface, see https://ethereum.org/en/developers/docs/apis/json-rpc.
+
+use crate::address::Address;
+use crate::management::{self, CallError};
+use async_trait::async_trait;
+use candid::types::{Serializer, Type};
+use candid::CandidType;
+use ic_ic00_types::{
+    CanisterHttpRequestArgs, HttpHeader, HttpMethod, TransformContext, TransformFunc,
+};
+use serde::de::DeserializeOwned;
+use serde::{de, Deserialize, Deserializer, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// The maximum size of the HTTP response headers that providers return.
+pub const HEADER_SIZE_LIMIT: u64 = 2 * 1024;
+
+/// The name of the query method that strips non-deterministic parts (such as
+/// HTTP headers) from JSON-RPC responses.
+pub const TRANSFORM_METHOD_NAME: &str = "cleanup_response";
+
+/// A 32-byte value such as a transaction hash or a log topic, represented as
+/// a 0x-prefixed hex string in JSON.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Hash(pub [u8; 32]);
+
+impl fmt::Display for Hash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{}", hex::encode(self.0))
+    }
+}
+
+impl FromStr for Hash {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex_str = s
+            .strip_prefix("0x")
+            .ok_or_else(|| "hash doesn't start with '0x'".to_string())?;
+        let mut bytes = [0u8; 32];
+        hex::decode_to_slice(hex_str, &mut bytes)
+            .map_err(|e| format!("invalid 32-byte hash {}: {}", s, e))?;
+        Ok(Self(bytes))
+    }
+}
+
+impl Serialize for Hash {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Hash {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Self::from_str(&s).map_err(de::Error::custom)
+    }
+}
+
+impl CandidType for Hash {
+    fn _ty() -> Type {
+        Type::Text
+    }
+
+    fn idl_serialize<S: Serializer>(&self, serializer: S) -> Result<(), S::Error> {
+        serializer.serialize_text(&self.to_string())
+    }
+}
+
+/// An unsigned integer encoded as a 0x-prefixed hex string without leading
+/// zeros, see https://ethereum.org/en/developers/docs/apis/json-rpc/#quantities-encoding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Quantity(pub u128);
+
+impl Quantity {
+    pub fn as_u64(&self) -> Result<u64, String> {
+        u64::try_from(self.0).map_err(|_| format!("quantity {} does not fit into u64", self.0))
+    }
+}
+
+impl Serialize for Quantity {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{:#x}", self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for Quantity {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        let hex_str = s
+            .strip_prefix("0x")
+            .ok_or_else(|| de::Error::custom(format!("quantity {} doesn't start with '0x'", s)))?;
+        u128::from_str_radix(hex_str, 16)
+            .map(Self)
+            .map_err(|e| de::Error::custom(format!("invalid quantity {}: {}", s, e)))
+    }
+}
+
+impl CandidType for Quantity {
+    fn _ty() -> Type {
+        u128::ty()
+    }
+
+    fn idl_serialize<S: Serializer>(&self, serializer: S) -> Result<(), S::Error> {
+        self.0.idl_serialize(serializer)
+    }
+}
+
+/// Binary data encoded as a 0x-prefixed hex string.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub struct Data(pub Vec<u8>);
+
+impl Serialize for Data {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("0x{}", hex::encode(&self.0)))
+    }
+}
+
+impl<'de> Deserialize<'de> for Data {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        let hex_str = s
+            .strip_prefix("0x")
+            .ok_or_else(|| de::Error::custom(format!("data {} doesn't start with '0x'", s)))?;
+        hex::decode(hex_str)
+            .map(Self)
+            .map_err(|e| de::Error::custom(format!("invalid hex data: {}", e)))
+    }
+}
+
+/// Block tags, see https://ethereum.org/en/developers/docs/apis/json-rpc/#default-block.
+#[derive(CandidType, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BlockTag {
+    /// The latest mined block.
+    #[serde(rename = "latest")]
+    Latest,
+    /// The latest safe head block.
+    #[serde(rename = "safe")]
+    Safe,
+    /// The latest finalized block.
+    #[serde(rename = "finalized")]
+    Finalized,
+}
+
+impl fmt::Display for BlockTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Latest => write!(f, "latest"),
+            Self::Safe => write!(f, "safe"),
+            Self::Finalized => write!(f, "finalized"),
+        }
+    }
+}
+
+/// A block number or a block tag.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum BlockSpec {
+    Number(Quantity),
+    Tag(BlockTag),
+}
+
+/// Parameters of the eth_getLogs call.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetLogsParam {
+    pub from_block: BlockSpec,
+    pub to_block: BlockSpec,
+    /// Contract addresses that the logs should originate from.
+    pub address: Vec<Address>,
+    /// Topics that the logs must match, in order.
+    pub topics: Vec<Hash>,
+}
+
+/// An entry of the eth_getLogs response.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogEntry {
+    /// The address of the contract that emitted the log.
+    pub address: Address,
+    /// Indexed event arguments; the first topic is the event signature hash.
+    pub topics: Vec<Hash>,
+    /// Non-indexed event arguments.
+    pub data: Data,
+    /// None for pending logs.
+    pub block_number: Option<Quantity>,
+    /// None for pending logs.
+    pub transaction_hash: Option<Hash>,
+    /// None for pending logs.
+    pub log_index: Option<Quantity>,
+    /// True if the log was removed due to a chain reorganization.
+    #[serde(default)]
+    pub removed: bool,
+}
+
+/// The subset of block fields that the minter uses.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Block {
+    pub number: Quantity,
+    pub base_fee_per_gas: Quantity,
+}
+
+/// Parameters of the eth_getBlockByNumber call: the block and a flag
+/// indicating whether the response should contain full transactions.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct GetBlockByNumberParams(pub BlockSpec, pub bool);
+
+/// The receipt of a mined transaction.
+#[derive(CandidType, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionReceipt {
+    #[serde(rename = "transactionHash")]
+    pub transaction_hash: Hash,
+    #[serde(rename = "blockNumber")]
+    pub block_number: Quantity,
+    #[serde(rename = "effectiveGasPrice")]
+    pub effective_gas_price: Quantity,
+    #[serde(rename = "gasUsed")]
+    pub gas_used: Quantity,
+    /// 1 if the transaction succeeded, 0 if it reverted.
+    #[serde(rename = "status")]
+    pub status: Quantity,
+}
+
+impl TransactionReceipt {
+    /// Returns the amount of wei that the transaction burnt in fees.
+    pub fn transaction_fee(&self) -> u128 {
+        self.effective_gas_price.0.saturating_mul(self.gas_used.0)
+    }
+
+    pub fn succeeded(&self) -> bool {
+        self.status.0 == 1
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct JsonRpcRequest<T> {
+    pub jsonrpc: String,
+    pub method: String,
+    pub id: u64,
+    pub params: T,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct JsonRpcReply<T> {
+    pub id: u64,
+    pub jsonrpc: String,
+    #[serde(flatten)]
+    pub result: JsonRpcResult<T>,
+}
+
+/// An envelope for all JSON-RPC replies, see https://www.jsonrpc.org/specification.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JsonRpcResult<T> {
+    Result(T),
+    Error { code: i64, message: String },
+}
+
+/// The reason a JSON-RPC call to a single provider failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RpcError {
+    /// The HTTPS outcall failed.
+    HttpOutcallError(CallError),
+    /// The provider replied with a non-200 HTTP status.
+    HttpStatus(u128),
+    /// The provider's reply is not a valid JSON-RPC response.
+    InvalidResponse(String),
+    /// The provider returned a JSON-RPC error.
+    JsonRpcError { code: i64, message: String },
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::HttpOutcallError(e) => write!(f, "{}", e),
+            Self::HttpStatus(status) => write!(f, "unexpected HTTP status {}", status),
+            Self::InvalidResponse(msg) => write!(f, "invalid JSON-RPC response: {}", msg),
+            Self::JsonRpcError { code, message } => {
+                write!(f, "JSON-RPC error {}: {}", code, message)
+            }
+        }
+    }
+}
+
+/// Sends JSON-RPC requests over HTTP.  The canister implementation uses HTTPS
+/// outcalls; tests substitute a mock server.
+#[async_trait(?Send)]
+pub trait RpcTransport {
+    /// POSTs the JSON payload to the URL and returns the response body.
+    async fn post(
+        &self,
+        url: &str,
+        payload: Vec<u8>,
+        max_response_bytes: u64,
+    ) -> Result<Vec<u8>, RpcError>;
+}
+
+/// Calls the specified JSON-RPC method on a single provider.
+pub async fn call<T: RpcTransport + ?Sized, I: Serialize, O: DeserializeOwned>(
+    transport: &T,
+    url: &str,
+    method: impl Into<String>,
+    params: I,
+    max_response_bytes: u64,
+) -> Result<O, RpcError> {
+    let request = JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        method: method.into(),
+        id: 1,
+        params,
+    };
+    let payload =
+        serde_json::to_vec(&request).expect("BUG: failed to serialize a JSON-RPC request");
+    let body = transport.post(url, payload, max_response_bytes).await?;
+    let reply: JsonRpcReply<O> = serde_json::from_slice(&body).map_err(|e| {
+        RpcError::InvalidResponse(format!(
+            "{}: {}",
+            e,
+            String::from_utf8_lossy(&body[..body.len().min(256)])
+        ))
+    })?;
+    match reply.result {
+        JsonRpcResult::Result(result) => Ok(result),
+        JsonRpcResult::Error { code, message } => Err(RpcError::JsonRpcError { code, message }),
+    }
+}
+
+/// Sends JSON-RPC requests using the HTTPS outcalls feature of the management
+/// canister.
+pub struct HttpOutcallTransport;
+
+#[async_trait(?Send)]
+impl RpcTransport for HttpOutcallTransport {
+    async fn post(
+        &self,
+        url: &str,
+        payload: Vec<u8>,
+        max_response_bytes: u64,
+    ) -> Result<Vec<u8>, RpcError> {
+        let max_response_bytes = max_response_bytes + HEADER_SIZE_LIMIT;
+        let cycles = http_request_cost(payload.len() as u64, max_response_bytes);
+        let request = CanisterHttpRequestArgs {
+            url: url.to_string(),
+            max_response_bytes: Some(max_response_bytes),
+            headers: vec![HttpHeader {
+                name: "Content-Type".to_string(),
+                value: "application/json".to_string(),
+            }],
+            body: Some(payload),
+            method: HttpMethod::POST,
+            transform: Some(TransformContext {
+                function: TransformFunc(candid::Func {
+                    principal: ic_cdk::id(),
+                    method: TRANSFORM_METHOD_NAME.to_string(),
+                }),
+                context: vec![],
+            }),
+        };
+        let response = management::http_request(request, cycles)
+            .await
+            .map_err(RpcError::HttpOutcallError)?;
+        if response.status != 200 {
+            return Err(RpcError::HttpStatus(response.status));
+        }
+        Ok(response.body)
+    }
+}
+
+/// Returns the number of cycles that an HTTPS outcall costs on a 13-node
+/// subnet, see https://internetcomputer.org/docs/current/developer-docs/gas-cost.
+pub fn http_request_cost(payload_size_bytes: u64, max_response_bytes: u64) -> u64 {
+    const NODES_IN_SUBNET: u64 = 13;
+    const BASE_FEE: u64 = (3_000_000 + 60_000 * NODES_IN_SUBNET) * NODES_IN_SUBNET;
+    const REQUEST_FEE_PER_BYTE: u64 = 400 * NODES_IN_SUBNET;
+    const RESPONSE_FEE_PER_BYTE: u64 = 800 * NODES_IN_SUBNET;
+
+    BASE_FEE
+        + REQUEST_FEE_PER_BYTE * payload_size_bytes
+        + RESPONSE_FEE_PER_BYTE * max_response_bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_quantities() {
+        let q: Quantity = serde_json::from_str("\"0x0\"").unwrap();
+        assert_eq!(q, Quantity(0));
+        let q: Quantity = serde_json::from_str("\"0x1a2b\"").unwrap();
+        assert_eq!(q, Quantity(0x1a2b));
+        assert!(serde_json::from_str::<Quantity>("\"1a2b\"").is_err());
+        assert_eq!(serde_json::to_string(&Quantity(1024)).unwrap(), "\"0x400\"");
+    }
+
+    #[test]
+    fn should_serialize_get_logs_request() {
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "eth_getLogs".to_string(),
+            id: 1,
+            params: [GetLogsParam {
+                from_block: BlockSpec::Number(Quantity(0x3ca4ef)),
+                to_block: BlockSpec::Tag(BlockTag::Finalized),
+                address: vec![
+                    Address::from_str("0x7574eB42cA208A4f6960ECCAfDF186D627dCC175").unwrap(),
+                ],
+                topics: vec![Hash([0x11; 32])],
+            }],
+        };
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "eth_getLogs",
+                "id": 1,
+                "params": [{
+                    "fromBlock": "0x3ca4ef",
+                    "toBlock": "finalized",
+                    "address": ["0x7574eB42cA208A4f6960ECCAfDF186D627dCC175"],
+                    "topics": [format!("0x{}", "11".repeat(32))],
+                }]
+            })
+        );
+    }
+
+    #[test]
+    fn should_parse_json_rpc_replies() {
+        let reply: JsonRpcReply<Block> = serde_json::from_str(
+            r#"{"jsonrpc":"2.0","id":1,"result":{"number":"0x10","baseFeePerGas":"0x3b9aca00","hash":"0xabcd"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            reply.result,
+            JsonRpcResult::Result(Block {
+                number: Quantity(16),
+                base_fee_per_gas: Quantity(1_000_000_000),
+            })
+        );
+
+        let reply: JsonRpcReply<Block> = serde_json::from_str(
+            r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32000,"message":"header not found"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            reply.result,
+            JsonRpcResult::Error {
+                code: -32000,
+                message: "header not found".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn should_parse_log_entry() {
+        let entry: LogEntry = serde_json::from_str(
+            r#"{
+                "address": "0x7574eb42ca208a4f6960eccafdf186d627dcc175",
+                "topics": [
+                    "0x257e057bb61920d8d0ed2cb7b720ac7f9c513cd1110bc9fa543079154f45f435",
+                    "0x000000000000000000000000dd2851cdd40ae6536831558dd46db62fac7a844d",
+                    "0x09efcdab00000000000100000000000000000000000000000000000000000000"
+                ],
+                "data": "0x000000000000000000000000000000000000000000000000002386f26fc10000",
+                "blockNumber": "0x3ca487",
+                "transactionHash": "0x705f826861c802b407843e99af986cfde8749b669e5e0a5a150f4350bcaa9bc3",
+                "transactionIndex": "0x22",
+                "blockHash": "0x8436209a391f7bc076123616ecb229602124eb6c1007f5eae84df8e098885d3c",
+                "logIndex": "0x27",
+                "removed": false
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(entry.topics.len(), 3);
+        assert_eq!(entry.block_number, Some(Quantity(0x3ca487)));
+        assert_eq!(entry.log_index, Some(Quantity(0x27)));
+        assert_eq!(entry.data.0.len(), 32);
+        assert!(!entry.removed);
+    }
+
+    #[test]
+    fn should_compute_http_request_cost() {
+        assert_eq!(http_request_cost(0, 0), 49_140_000);
+        assert_eq!(http_request_cost(1, 1), 49_140_000 + 5_200 + 10_400);
+    }
+}
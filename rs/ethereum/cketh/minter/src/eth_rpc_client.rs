@@ -0,0 +1,278 @@
+//! A JSON-RPC client that queries several Ethereum API providers in parallel
+//! and only trusts results that the providers agree on.
+
+use crate::address::Address;
+use crate::eth_rpc::{
+    self, Block, BlockSpec, BlockTag, GetBlockByNumberParams, GetLogsParam, Hash,
+    HttpOutcallTransport, LogEntry, Quantity, RpcError, RpcTransport, TransactionReceipt,
+};
+use crate::state::EthereumNetwork;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+
+/// The maximum size of an eth_getLogs response.
+const ETH_GET_LOGS_MAX_RESPONSE_BYTES: u64 = 100_000;
+/// The maximum size of the responses of methods returning a single object.
+const SMALL_RESPONSE_MAX_BYTES: u64 = 8_000;
+
+/// Returns the JSON-RPC endpoints that the minter queries on the network.
+pub fn providers(network: EthereumNetwork) -> Vec<String> {
+    let urls: &[&str] = match network {
+        EthereumNetwork::Mainnet => &["https://cloudflare-eth.com", "https://rpc.ankr.com/eth"],
+        EthereumNetwork::Sepolia => &[
+            "https://rpc.sepolia.org",
+            "https://rpc.ankr.com/eth_sepolia",
+        ],
+    };
+    urls.iter().map(|url| url.to_string()).collect()
+}
+
+/// Results of a single JSON-RPC call, indexed by provider.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MultiCallResults<T> {
+    pub results: BTreeMap<String, Result<T, RpcError>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MultiCallError<T> {
+    /// All providers failed with the same error.
+    ConsistentError(RpcError),
+    /// The providers returned different results, or some of them failed.
+    InconsistentResults(MultiCallResults<T>),
+}
+
+impl<T: PartialEq> MultiCallResults<T> {
+    /// Returns the result if all providers agree on it.
+    pub fn reduce_with_equality(self) -> Result<T, MultiCallError<T>> {
+        let mut results = self.results.values();
+        let first = match results.next() {
+            Some(first) => first,
+            None => {
+                return Err(MultiCallError::ConsistentError(RpcError::InvalidResponse(
+                    "no providers".to_string(),
+                )))
+            }
+        };
+        if !results.all(|r| r == first) {
+            return Err(MultiCallError::InconsistentResults(self));
+        }
+        let first = self.results.into_values().next().unwrap();
+        first.map_err(MultiCallError::ConsistentError)
+    }
+
+    /// Returns the minimal result by the specified key if all providers
+    /// succeeded.  Providers are often a few blocks apart, so for values that
+    /// only grow (block numbers, nonces) the minimum is the value that all of
+    /// them have already observed.
+    pub fn reduce_with_min_by_key<K: Ord>(
+        self,
+        key: impl Fn(&T) -> K,
+    ) -> Result<T, MultiCallError<T>> {
+        if self.results.values().any(|r| r.is_err()) {
+            return self.reduce_with_equality();
+        }
+        Ok(self
+            .results
+            .into_values()
+            .map(|r| r.unwrap_or_else(|_| unreachable!()))
+            .min_by_key(|t| key(t))
+            .expect("BUG: no providers"))
+    }
+
+    /// Returns any successful result.
+    pub fn reduce_with_any_ok(self) -> Result<T, MultiCallError<T>> {
+        if self.results.values().any(|r| r.is_ok()) {
+            return Ok(self
+                .results
+                .into_values()
+                .find_map(|r| r.ok())
+                .unwrap_or_else(|| unreachable!()));
+        }
+        self.reduce_with_equality()
+    }
+}
+
+pub struct EthRpcClient<T: RpcTransport = HttpOutcallTransport> {
+    transport: T,
+    providers: Vec<String>,
+}
+
+impl EthRpcClient<HttpOutcallTransport> {
+    /// Creates a client that uses HTTPS outcalls to query the default
+    /// providers of the network.
+    pub fn for_network(network: EthereumNetwork) -> Self {
+        Self::new(HttpOutcallTransport, providers(network))
+    }
+}
+
+impl<T: RpcTransport> EthRpcClient<T> {
+    pub fn new(transport: T, providers: Vec<String>) -> Self {
+        assert!(!providers.is_empty(), "BUG: no JSON-RPC providers");
+        Self {
+            transport,
+            providers,
+        }
+    }
+
+    /// Calls the method on all providers in parallel.
+    async fn parallel_call<I, O>(
+        &self,
+        method: &str,
+        params: I,
+        max_response_bytes: u64,
+    ) -> MultiCallResults<O>
+    where
+        I: Serialize + Clone,
+        O: DeserializeOwned,
+    {
+        let results = futures::future::join_all(self.providers.iter().map(|url| {
+            eth_rpc::call(
+                &self.transport,
+                url,
+                method,
+                params.clone(),
+                max_response_bytes,
+            )
+        }))
+        .await;
+        MultiCallResults {
+            results: self.providers.iter().cloned().zip(results).collect(),
+        }
+    }
+
+    pub async fn eth_get_logs(
+        &self,
+        params: GetLogsParam,
+    ) -> Result<Vec<LogEntry>, MultiCallError<Vec<LogEntry>>> {
+        self.parallel_call("eth_getLogs", vec![params], ETH_GET_LOGS_MAX_RESPONSE_BYTES)
+            .await
+            .reduce_with_equality()
+    }
+
+    pub async fn eth_get_block_by_number(
+        &self,
+        block: BlockSpec,
+    ) -> Result<Block, MultiCallError<Block>> {
+        self.parallel_call(
+            "eth_getBlockByNumber",
+            GetBlockByNumberParams(block, false),
+            SMALL_RESPONSE_MAX_BYTES,
+        )
+        .await
+        .reduce_with_min_by_key(|block: &Block| block.number)
+    }
+
+    pub async fn eth_get_transaction_receipt(
+        &self,
+        hash: Hash,
+    ) -> Result<Option<TransactionReceipt>, MultiCallError<Option<TransactionReceipt>>> {
+        self.parallel_call(
+            "eth_getTransactionReceipt",
+            vec![hash],
+            SMALL_RESPONSE_MAX_BYTES,
+        )
+        .await
+        .reduce_with_equality()
+    }
+
+    /// Returns the number of transactions that the address sent as of the
+    /// specified block, i.e. the next nonce of the address.
+    pub async fn eth_get_transaction_count(
+        &self,
+        address: Address,
+        block: BlockTag,
+    ) -> Result<Quantity, MultiCallError<Quantity>> {
+        self.parallel_call(
+            "eth_getTransactionCount",
+            (address, block),
+            SMALL_RESPONSE_MAX_BYTES,
+        )
+        .await
+        .reduce_with_min_by_key(|count: &Quantity| *count)
+    }
+
+    /// Broadcasts the raw transaction and returns its hash.  One provider
+    /// accepting the transaction is enough for it to reach the network.
+    pub async fn eth_send_raw_transaction(
+        &self,
+        raw_transaction_hex: String,
+    ) -> Result<Hash, MultiCallError<Hash>> {
+        self.parallel_call(
+            "eth_sendRawTransaction",
+            vec![raw_transaction_hex],
+            SMALL_RESPONSE_MAX_BYTES,
+        )
+        .await
+        .reduce_with_any_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results(values: Vec<(&str, Result<u64, RpcError>)>) -> MultiCallResults<u64> {
+        MultiCallResults {
+            results: values
+                .into_iter()
+                .map(|(url, r)| (url.to_string(), r))
+                .collect(),
+        }
+    }
+
+    fn error() -> RpcError {
+        RpcError::JsonRpcError {
+            code: -32000,
+            message: "header not found".to_string(),
+        }
+    }
+
+    #[test]
+    fn should_require_agreement_for_equality_reduction() {
+        assert_eq!(
+            results(vec![("a", Ok(1)), ("b", Ok(1))]).reduce_with_equality(),
+            Ok(1)
+        );
+        assert_eq!(
+            results(vec![("a", Err(error())), ("b", Err(error()))]).reduce_with_equality(),
+            Err(MultiCallError::ConsistentError(error()))
+        );
+        let inconsistent = results(vec![("a", Ok(1)), ("b", Ok(2))]);
+        assert_eq!(
+            inconsistent.clone().reduce_with_equality(),
+            Err(MultiCallError::InconsistentResults(inconsistent))
+        );
+        let partial_failure = results(vec![("a", Ok(1)), ("b", Err(error()))]);
+        assert_eq!(
+            partial_failure.clone().reduce_with_equality(),
+            Err(MultiCallError::InconsistentResults(partial_failure))
+        );
+    }
+
+    #[test]
+    fn should_take_minimum_when_all_providers_succeed() {
+        assert_eq!(
+            results(vec![("a", Ok(10)), ("b", Ok(8)), ("c", Ok(9))]).reduce_with_min_by_key(|n| *n),
+            Ok(8)
+        );
+        let partial_failure = results(vec![("a", Ok(1)), ("b", Err(error()))]);
+        assert_eq!(
+            partial_failure.clone().reduce_with_min_by_key(|n| *n),
+            Err(MultiCallError::InconsistentResults(partial_failure))
+        );
+    }
+
+    #[test]
+    fn should_accept_any_success() {
+        assert_eq!(
+            results(vec![("a", Err(error())), ("b", Ok(3))]).reduce_with_any_ok(),
+            Ok(3)
+        );
+        assert_eq!(
+            results(vec![("a", Err(error())), ("b", Err(error()))]).reduce_with_any_ok(),
+            Err(MultiCallError::ConsistentError(error()))
+        );
+    }
+}
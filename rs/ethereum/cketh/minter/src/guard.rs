@@ -0,0 +1,126 @@
+use crate::state::mutate_state;
+use candid::Principal;
+
+const MAX_CONCURRENT: usize = 100;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum GuardError {
+    AlreadyProcessing,
+    TooManyConcurrentRequests,
+}
+
+/// Guards a block from executing twice when called by the same user and from being
+/// executed [MAX_CONCURRENT] or more times in parallel.
+#[must_use]
+pub struct RetrieveEthGuard {
+    principal: Principal,
+}
+
+impl RetrieveEthGuard {
+    /// Attempts to create a new guard for the current block. Fails if there is
+    /// already a pending request for the specified [principal] or if there
+    /// are at least [MAX_CONCURRENT] pending requests.
+    pub fn new(principal: Principal) -> Result<Self, GuardError> {
+        mutate_state(|s| {
+            if s.retrieve_eth_principals.contains(&principal) {
+                return Err(GuardError::AlreadyProcessing);
+            }
+            if s.retrieve_eth_principals.len() >= MAX_CONCURRENT {
+                return Err(GuardError::TooManyConcurrentRequests);
+            }
+            s.retrieve_eth_principals.insert(principal);
+            Ok(Self { principal })
+        })
+    }
+}
+
+impl Drop for RetrieveEthGuard {
+    fn drop(&mut self) {
+        mutate_state(|s| s.retrieve_eth_principals.remove(&self.principal));
+    }
+}
+
+pub fn retrieve_eth_guard(p: Principal) -> Result<RetrieveEthGuard, GuardError> {
+    RetrieveEthGuard::new(p)
+}
+
+/// Periodic tasks of the minter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TaskType {
+    ScrapEthLogs,
+    RetrieveEth,
+}
+
+/// Prevents a periodic task from running while the previous run of the same
+/// task is still awaiting responses.
+#[must_use]
+pub struct TimerGuard {
+    task: TaskType,
+}
+
+impl TimerGuard {
+    pub fn new(task: TaskType) -> Option<Self> {
+        mutate_state(|s| {
+            if !s.active_tasks.insert(task) {
+                return None;
+            }
+            Some(TimerGuard { task })
+        })
+    }
+}
+
+impl Drop for TimerGuard {
+    fn drop(&mut self) {
+        mutate_state(|s| {
+            s.active_tasks.remove(&self.task);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{retrieve_eth_guard, GuardError, TaskType, TimerGuard, MAX_CONCURRENT};
+    use crate::state::read_state;
+    use crate::tests::init_state;
+    use candid::Principal;
+
+    fn test_principal(id: u64) -> Principal {
+        Principal::try_from_slice(&id.to_le_bytes()).unwrap()
+    }
+
+    #[test]
+    fn should_reject_concurrent_requests_of_the_same_principal() {
+        init_state();
+        let guard = retrieve_eth_guard(test_principal(1)).unwrap();
+        assert_eq!(
+            retrieve_eth_guard(test_principal(1)).err(),
+            Some(GuardError::AlreadyProcessing)
+        );
+        drop(guard);
+        assert!(retrieve_eth_guard(test_principal(1)).is_ok());
+    }
+
+    #[test]
+    fn should_limit_concurrent_requests() {
+        init_state();
+        let guards: Vec<_> = (0..MAX_CONCURRENT as u64)
+            .map(|id| retrieve_eth_guard(test_principal(id)).unwrap())
+            .collect();
+        assert_eq!(
+            retrieve_eth_guard(test_principal(MAX_CONCURRENT as u64)).err(),
+            Some(GuardError::TooManyConcurrentRequests)
+        );
+        drop(guards);
+        assert!(read_state(|s| s.retrieve_eth_principals.is_empty()));
+    }
+
+    #[test]
+    fn should_prevent_concurrent_runs_of_the_same_task() {
+        init_state();
+        let guard = TimerGuard::new(TaskType::ScrapEthLogs).unwrap();
+        assert!(TimerGuard::new(TaskType::ScrapEthLogs).is_none());
+        assert!(TimerGuard::new(TaskType::RetrieveEth).is_some());
+        drop(guard);
+        assert!(TimerGuard::new(TaskType::ScrapEthLogs).is_some());
+    }
+}
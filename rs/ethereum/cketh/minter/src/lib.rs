@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+pub mod address;
+pub mod deposit;
+pub mod eth_rpc;
+pub mod eth_rpc_client;
+pub mod guard;
+pub mod lifecycle;
+pub mod logs;
+pub mod management;
+pub mod rlp;
+pub mod state;
+pub mod storage;
+pub mod tx;
+pub mod updates;
+pub mod withdraw;
+
+#[cfg(test)]
+mod tests;
+
+/// The ckETH ledger only supports 64-bit amounts, so one ckETH ledger unit
+/// corresponds to 10^10 wei (10^8 units per ETH).
+pub const WEI_PER_CKETH_UNIT: u128 = 10_000_000_000;
+
+/// The interval at which the minter fetches new deposit events.
+pub const SCRAPPING_ETH_LOGS_INTERVAL: Duration = Duration::from_secs(3 * 60);
+
+/// The interval at which the minter signs, sends and finalizes withdrawal
+/// transactions.
+pub const PROCESS_ETH_RETRIEVE_TRANSACTIONS_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Computes the Keccak-256 hash of the input.
+pub fn keccak256(bytes: &[u8]) -> [u8; 32] {
+    use sha3::{Digest, Keccak256};
+
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&Keccak256::digest(bytes));
+    hash
+}
+
+/// Converts an amount of wei to ckETH ledger units, rounding down.
+pub fn wei_to_cketh_units(wei: u128) -> u128 {
+    wei / WEI_PER_CKETH_UNIT
+}
+
+/// Converts an amount of ckETH ledger units to wei.
+pub fn cketh_units_to_wei(units: u64) -> u128 {
+    units as u128 * WEI_PER_CKETH_UNIT
+}
+
+/// Installs the periodic timers that drive the deposit and withdrawal logic.
+/// Timers do not survive upgrades, so the minter calls this function both on
+/// init and on post_upgrade.
+pub fn setup_timers() {
+    ic_cdk_timers::set_timer(Duration::from_secs(0), || {
+        ic_cdk::spawn(async {
+            let _ = updates::minter_address::init_ecdsa_public_key().await;
+        })
+    });
+    ic_cdk_timers::set_timer_interval(SCRAPPING_ETH_LOGS_INTERVAL, || {
+        ic_cdk::spawn(deposit::scrap_eth_logs())
+    });
+    ic_cdk_timers::set_timer_interval(PROCESS_ETH_RETRIEVE_TRANSACTIONS_INTERVAL, || {
+        ic_cdk::spawn(withdraw::process_retrieve_eth_requests())
+    });
+}
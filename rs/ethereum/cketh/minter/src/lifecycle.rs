@@ -0,0 +1,6 @@
+///! Module dealing with the lifecycle methods of the ckETH Minter.
+pub mod init;
+pub use init::init;
+
+pub mod upgrade;
+pub use upgrade::post_upgrade;
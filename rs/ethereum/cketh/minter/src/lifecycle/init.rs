@@ -0,0 +1,47 @@
+use crate::address::Address;
+use crate::eth_rpc::BlockTag;
+use crate::lifecycle::upgrade::UpgradeArgs;
+use crate::state::{replace_state, CkEthMinterState, EthereumNetwork};
+use candid::{CandidType, Deserialize};
+use ic_base_types::CanisterId;
+use serde::Serialize;
+
+#[derive(CandidType, serde::Deserialize)]
+pub enum MinterArg {
+    Init(InitArgs),
+    Upgrade(Option<UpgradeArgs>),
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct InitArgs {
+    /// The Ethereum network that the minter will connect to.
+    pub ethereum_network: EthereumNetwork,
+
+    /// The name of the [EcdsaKeyId]. Use "dfx_test_key" for local replica and "test_key_1" for
+    /// a testing key for testnet and mainnet
+    pub ecdsa_key_name: String,
+
+    /// The CanisterId of the ckETH Ledger.
+    pub ledger_id: CanisterId,
+
+    /// The address of the helper smart contract that emits deposit events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ethereum_contract_address: Option<Address>,
+
+    /// The block tag up to which the minter scrapes deposit events.
+    pub ethereum_block_height: BlockTag,
+
+    /// Minimum amount of ckETH ledger units that can be withdrawn.
+    pub minimum_withdrawal_amount: u64,
+
+    /// The nonce of the first transaction that the minter signs.
+    pub next_transaction_nonce: u64,
+
+    /// The block from which the minter starts scraping deposit events
+    /// (exclusive).  Usually the block that deployed the helper contract.
+    pub last_scraped_block_number: u64,
+}
+
+pub fn init(args: InitArgs) {
+    replace_state(CkEthMinterState::from(args));
+}
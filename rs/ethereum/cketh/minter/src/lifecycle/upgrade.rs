@@ -0,0 +1,59 @@
+use crate::address::Address;
+use crate::eth_rpc::BlockTag;
+use crate::logs::P0;
+use crate::state::eventlog::{replay, Event};
+use crate::state::replace_state;
+use crate::storage::{count_events, events, record_event};
+use candid::{CandidType, Deserialize};
+use ic_canister_log::log;
+use serde::Serialize;
+
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize, PartialEq, Eq, Default)]
+pub struct UpgradeArgs {
+    /// The address of the helper smart contract that emits deposit events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ethereum_contract_address: Option<Address>,
+
+    /// The block tag up to which the minter scrapes deposit events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ethereum_block_height: Option<BlockTag>,
+
+    /// Minimum amount of ckETH ledger units that can be withdrawn.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minimum_withdrawal_amount: Option<u64>,
+
+    /// Overrides the last scraped block, e.g. after changing the helper
+    /// contract address.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_scraped_block_number: Option<u64>,
+}
+
+pub fn post_upgrade(upgrade_args: Option<UpgradeArgs>) {
+    if let Some(upgrade_args) = upgrade_args {
+        log!(
+            P0,
+            "[upgrade]: updating configuration with {:?}",
+            upgrade_args
+        );
+        record_event(&Event::Upgrade(upgrade_args));
+    };
+
+    let start = ic_cdk::api::instruction_counter();
+
+    log!(P0, "[upgrade]: replaying {} events", count_events());
+
+    replace_state(replay(events()).unwrap_or_else(|e| {
+        ic_cdk::trap(&format!(
+            "[upgrade]: failed to replay the event log: {:?}",
+            e
+        ))
+    }));
+
+    let end = ic_cdk::api::instruction_counter();
+
+    log!(
+        P0,
+        "[upgrade]: replaying events consumed {} instructions",
+        end - start
+    );
+}
@@ -0,0 +1,7 @@
+use ic_canister_log::declare_log_buffer;
+
+// High-priority messages.
+declare_log_buffer!(name = P0, capacity = 1000);
+
+// Low-priority info messages.
+declare_log_buffer!(name = P1, capacity = 1000);
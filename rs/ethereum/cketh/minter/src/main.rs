@@ -0,0 +1,198 @@
+use candid::candid_method;
+use candid::Principal;
+use ic_canister_log::export as export_logs;
+use ic_canisters_http_types::{HttpRequest, HttpResponse, HttpResponseBuilder};
+use ic_cdk_macros::{init, post_upgrade, query, update};
+use ic_cketh_minter::lifecycle::{self, init::MinterArg, upgrade::UpgradeArgs};
+use ic_cketh_minter::state::{read_state, RetrieveEthStatus};
+use ic_cketh_minter::updates::{
+    self,
+    withdraw_eth::{RetrieveEthRequest, WithdrawalArg, WithdrawalError},
+};
+use ic_cketh_minter::{
+    state::eventlog::{Event, GetEventsArg},
+    storage,
+};
+use ic_ic00_types::{CanisterHttpResponsePayload, TransformArgs};
+use ic_icrc1::Account;
+
+#[init]
+fn init(args: MinterArg) {
+    match args {
+        MinterArg::Init(args) => {
+            storage::record_event(&Event::Init(args.clone()));
+            lifecycle::init::init(args);
+            ic_cketh_minter::setup_timers();
+        }
+        MinterArg::Upgrade(_) => {
+            panic!("expected InitArgs got UpgradeArgs");
+        }
+    }
+}
+
+fn check_anonymous_caller() {
+    if ic_cdk::caller() == Principal::anonymous() {
+        panic!("anonymous caller not allowed")
+    }
+}
+
+#[post_upgrade]
+fn post_upgrade(minter_arg: Option<MinterArg>) {
+    let mut upgrade_arg: Option<UpgradeArgs> = None;
+    if let Some(minter_arg) = minter_arg {
+        upgrade_arg = match minter_arg {
+            MinterArg::Upgrade(upgrade_args) => upgrade_args,
+            MinterArg::Init(_) => panic!("expected Option<UpgradeArgs> got InitArgs."),
+        };
+    }
+    lifecycle::upgrade::post_upgrade(upgrade_arg);
+    ic_cketh_minter::setup_timers();
+}
+
+#[candid_method(update)]
+#[update]
+async fn minter_address() -> String {
+    updates::minter_address::minter_address().await
+}
+
+#[candid_method(query)]
+#[query]
+fn smart_contract_address() -> String {
+    read_state(|s| s.ethereum_contract_address)
+        .map(|address| address.to_string())
+        .unwrap_or_else(|| "N/A".to_string())
+}
+
+#[candid_method(update)]
+#[update]
+fn get_withdrawal_account() -> Account {
+    check_anonymous_caller();
+    updates::get_withdrawal_account::get_withdrawal_account()
+}
+
+#[candid_method(update)]
+#[update]
+async fn withdraw_eth(args: WithdrawalArg) -> Result<RetrieveEthRequest, WithdrawalError> {
+    check_anonymous_caller();
+    updates::withdraw_eth::withdraw_eth(args).await
+}
+
+#[candid_method(query)]
+#[query]
+fn retrieve_eth_status(block_index: u64) -> RetrieveEthStatus {
+    read_state(|s| s.retrieve_eth_status(block_index))
+}
+
+/// Strips the HTTP headers from JSON-RPC responses so that all replicas
+/// agree on the response.
+#[query]
+fn cleanup_response(mut args: TransformArgs) -> CanisterHttpResponsePayload {
+    args.response.headers.clear();
+    args.response
+}
+
+#[candid_method(query)]
+#[query]
+fn http_request(req: HttpRequest) -> HttpResponse {
+    if req.path() == "/logs" {
+        use std::io::Write;
+        let mut buf = vec![];
+
+        writeln!(&mut buf, "P0 logs:").unwrap();
+        for entry in export_logs(&ic_cketh_minter::logs::P0) {
+            writeln!(
+                &mut buf,
+                "{} {}:{} {}",
+                entry.timestamp, entry.file, entry.line, entry.message
+            )
+            .unwrap();
+        }
+
+        writeln!(&mut buf, "P1 logs:").unwrap();
+        for entry in export_logs(&ic_cketh_minter::logs::P1) {
+            writeln!(
+                &mut buf,
+                "{} {}:{} {}",
+                entry.timestamp, entry.file, entry.line, entry.message
+            )
+            .unwrap();
+        }
+
+        HttpResponseBuilder::ok()
+            .header("Content-Type", "text/plain; charset=utf-8")
+            .with_body_and_content_length(buf)
+            .build()
+    } else {
+        HttpResponseBuilder::not_found().build()
+    }
+}
+
+#[candid_method(query)]
+#[query]
+fn get_events(args: GetEventsArg) -> Vec<Event> {
+    const MAX_EVENTS_PER_QUERY: usize = 2000;
+
+    storage::events()
+        .skip(args.start as usize)
+        .take(MAX_EVENTS_PER_QUERY.min(args.length as usize))
+        .collect()
+}
+
+#[query]
+fn __get_candid_interface_tmp_hack() -> &'static str {
+    include_str!(env!("CKETH_MINTER_DID_PATH"))
+}
+
+fn main() {}
+
+/// Checks the real candid interface against the one declared in the did file
+#[test]
+fn check_candid_interface_compatibility() {
+    fn source_to_str(source: &candid::utils::CandidSource) -> String {
+        match source {
+            candid::utils::CandidSource::File(f) => {
+                std::fs::read_to_string(f).unwrap_or_else(|_| "".to_string())
+            }
+            candid::utils::CandidSource::Text(t) => t.to_string(),
+        }
+    }
+
+    fn check_service_compatible(
+        new_name: &str,
+        new: candid::utils::CandidSource,
+        old_name: &str,
+        old: candid::utils::CandidSource,
+    ) {
+        let new_str = source_to_str(&new);
+        let old_str = source_to_str(&old);
+        match candid::utils::service_compatible(new, old) {
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!(
+                    "{} is not compatible with {}!\n\n\
+            {}:\n\
+            {}\n\n\
+            {}:\n\
+            {}\n",
+                    new_name, old_name, new_name, new_str, old_name, old_str
+                );
+                panic!("{:?}", e);
+            }
+        }
+    }
+
+    candid::export_service!();
+
+    let new_interface = __export_service();
+
+    // check the public interface against the actual one
+    let old_interface = std::path::PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap())
+        .join("cketh_minter.did");
+
+    check_service_compatible(
+        "actual ledger candid interface",
+        candid::utils::CandidSource::Text(&new_interface),
+        "declared candid interface in cketh_minter.did file",
+        candid::utils::CandidSource::File(old_interface.as_path()),
+    );
+}
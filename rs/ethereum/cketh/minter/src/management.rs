@@ -0,0 +1,172 @@
+//! This module contains async functions for interacting with the management canister.
+
+use crate::logs::P0;
+use candid::{CandidType, Principal};
+use ic_canister_log::log;
+use ic_cdk::api::call::RejectionCode;
+use ic_ic00_types::{
+    CanisterHttpRequestArgs, CanisterHttpResponsePayload, ECDSAPublicKeyArgs,
+    ECDSAPublicKeyResponse, EcdsaCurve, EcdsaKeyId, SignWithECDSAArgs, SignWithECDSAReply,
+};
+use serde::de::DeserializeOwned;
+use std::fmt;
+
+/// Represents an error from a management canister call, such as
+/// `sign_with_ecdsa` or `http_request`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallError {
+    method: String,
+    reason: Reason,
+}
+
+impl CallError {
+    /// Returns the name of the method that resulted in this error.
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    /// Returns the failure reason.
+    pub fn reason(&self) -> &Reason {
+        &self.reason
+    }
+}
+
+impl fmt::Display for CallError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            fmt,
+            "management call '{}' failed: {}",
+            self.method, self.reason
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The reason for the management call failure.
+pub enum Reason {
+    /// Failed to send a request because the local output queue is full.
+    QueueIsFull,
+    /// The canister does not have enough cycles to submit the request.
+    OutOfCycles,
+    /// The call failed with an error.
+    CanisterError(String),
+    /// The management canister rejected the request (not enough cycles, the
+    /// ECDSA subnet is overloaded, the HTTP server is unreachable, etc.).
+    Rejected(String),
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::QueueIsFull => write!(fmt, "the canister queue is full"),
+            Self::OutOfCycles => write!(fmt, "the canister is out of cycles"),
+            Self::CanisterError(msg) => write!(fmt, "canister error: {}", msg),
+            Self::Rejected(msg) => {
+                write!(fmt, "the management canister rejected the call: {}", msg)
+            }
+        }
+    }
+}
+
+impl Reason {
+    fn from_reject(reject_code: RejectionCode, reject_message: String) -> Self {
+        match reject_code {
+            RejectionCode::SysTransient => Self::QueueIsFull,
+            RejectionCode::CanisterError => Self::CanisterError(reject_message),
+            RejectionCode::CanisterReject => Self::Rejected(reject_message),
+            _ => Self::QueueIsFull,
+        }
+    }
+}
+
+async fn call<I, O>(method: &str, payment: u64, input: &I) -> Result<O, CallError>
+where
+    I: CandidType,
+    O: CandidType + DeserializeOwned,
+{
+    let balance = ic_cdk::api::canister_balance128();
+    if balance < payment as u128 {
+        log!(
+            P0,
+            "Failed to call {}: need {} cycles, the balance is only {}",
+            method,
+            payment,
+            balance
+        );
+
+        return Err(CallError {
+            method: method.to_string(),
+            reason: Reason::OutOfCycles,
+        });
+    }
+
+    let res: Result<(O,), _> = ic_cdk::api::call::call_with_payment(
+        Principal::management_canister(),
+        method,
+        (input,),
+        payment,
+    )
+    .await;
+
+    match res {
+        Ok((output,)) => Ok(output),
+        Err((code, msg)) => Err(CallError {
+            method: method.to_string(),
+            reason: Reason::from_reject(code, msg),
+        }),
+    }
+}
+
+/// Makes an HTTPS outcall with the specified cycles payment.
+pub async fn http_request(
+    request: CanisterHttpRequestArgs,
+    cycles: u64,
+) -> Result<CanisterHttpResponsePayload, CallError> {
+    call("http_request", cycles, &request).await
+}
+
+/// Fetches the SEC1-encoded public key of the minter at the given derivation
+/// path.
+pub async fn ecdsa_public_key(
+    key_name: String,
+    derivation_path: Vec<Vec<u8>>,
+) -> Result<Vec<u8>, CallError> {
+    let reply: ECDSAPublicKeyResponse = call(
+        "ecdsa_public_key",
+        0,
+        &ECDSAPublicKeyArgs {
+            canister_id: None,
+            derivation_path,
+            key_id: EcdsaKeyId {
+                curve: EcdsaCurve::Secp256k1,
+                name: key_name,
+            },
+        },
+    )
+    .await?;
+    Ok(reply.public_key)
+}
+
+/// Signs a message hash using the tECDSA API.
+pub async fn sign_with_ecdsa(
+    key_name: String,
+    derivation_path: Vec<Vec<u8>>,
+    message_hash: [u8; 32],
+) -> Result<Vec<u8>, CallError> {
+    const CYCLES_PER_SIGNATURE: u64 = 25_000_000_000;
+
+    let reply: SignWithECDSAReply = call(
+        "sign_with_ecdsa",
+        CYCLES_PER_SIGNATURE,
+        &SignWithECDSAArgs {
+            message_hash,
+            derivation_path,
+            key_id: EcdsaKeyId {
+                curve: EcdsaCurve::Secp256k1,
+                name: key_name,
+            },
+        },
+    )
+    .await?;
+    Ok(reply.signature)
+}
@@ -0,0 +1,152 @@
+//! A minimal encoder for the Recursive Length Prefix (RLP) serialization
+//! format used by Ethereum transactions.
+//!
+//! See https://ethereum.org/en/developers/docs/data-structures-and-encoding/rlp/.
+
+/// Accumulates the encoded items of an RLP list.
+#[derive(Clone, Debug, Default)]
+pub struct RlpList {
+    payload: Vec<u8>,
+}
+
+impl RlpList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a byte string.
+    pub fn append_bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        encode_bytes(bytes, &mut self.payload);
+        self
+    }
+
+    /// Appends an unsigned integer as a big-endian byte string without
+    /// leading zeros.
+    pub fn append_uint(&mut self, n: u128) -> &mut Self {
+        encode_uint(n, &mut self.payload);
+        self
+    }
+
+    /// Appends a big-endian 256-bit unsigned integer without leading zeros.
+    pub fn append_uint256(&mut self, n: &[u8; 32]) -> &mut Self {
+        encode_uint256(n, &mut self.payload);
+        self
+    }
+
+    /// Appends a nested list.
+    pub fn append_list(&mut self, list: &RlpList) -> &mut Self {
+        encode_list_payload(&list.payload, &mut self.payload);
+        self
+    }
+
+    /// Returns the encoding of the list.
+    pub fn finish(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.payload.len() + 9);
+        encode_list_payload(&self.payload, &mut out);
+        out
+    }
+}
+
+/// Encodes a byte string.
+pub fn encode_bytes(bytes: &[u8], out: &mut Vec<u8>) {
+    if bytes.len() == 1 && bytes[0] < 0x80 {
+        out.push(bytes[0]);
+    } else {
+        encode_length(bytes.len(), 0x80, out);
+        out.extend_from_slice(bytes);
+    }
+}
+
+/// Encodes an unsigned integer.  Zero is encoded as the empty string.
+pub fn encode_uint(n: u128, out: &mut Vec<u8>) {
+    let bytes = n.to_be_bytes();
+    let first_nonzero = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    encode_bytes(&bytes[first_nonzero..], out);
+}
+
+/// Strips leading zeros from a big-endian 256-bit integer and encodes it.
+pub fn encode_uint256(bytes: &[u8; 32], out: &mut Vec<u8>) {
+    let first_nonzero = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    encode_bytes(&bytes[first_nonzero..], out);
+}
+
+fn encode_list_payload(payload: &[u8], out: &mut Vec<u8>) {
+    encode_length(payload.len(), 0xc0, out);
+    out.extend_from_slice(payload);
+}
+
+fn encode_length(len: usize, offset: u8, out: &mut Vec<u8>) {
+    if len < 56 {
+        out.push(offset + len as u8);
+    } else {
+        let len_bytes = (len as u64).to_be_bytes();
+        let first_nonzero = len_bytes.iter().position(|b| *b != 0).unwrap_or(7);
+        let len_bytes = &len_bytes[first_nonzero..];
+        out.push(offset + 55 + len_bytes.len() as u8);
+        out.extend_from_slice(len_bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bytes(b: &[u8]) -> Vec<u8> {
+        let mut out = vec![];
+        encode_bytes(b, &mut out);
+        out
+    }
+
+    fn uint(n: u128) -> Vec<u8> {
+        let mut out = vec![];
+        encode_uint(n, &mut out);
+        out
+    }
+
+    #[test]
+    fn should_encode_strings() {
+        assert_eq!(bytes(b"dog"), hex::decode("83646f67").unwrap());
+        assert_eq!(bytes(b""), vec![0x80]);
+        assert_eq!(bytes(&[0x0f]), vec![0x0f]);
+        assert_eq!(bytes(&[0x80]), vec![0x81, 0x80]);
+
+        let lorem = b"Lorem ipsum dolor sit amet, consectetur adipisicing elit";
+        let mut expected = vec![0xb8, 0x38];
+        expected.extend_from_slice(lorem);
+        assert_eq!(bytes(lorem), expected);
+    }
+
+    #[test]
+    fn should_encode_integers() {
+        assert_eq!(uint(0), vec![0x80]);
+        assert_eq!(uint(15), vec![0x0f]);
+        assert_eq!(uint(1024), vec![0x82, 0x04, 0x00]);
+    }
+
+    #[test]
+    fn should_encode_lists() {
+        assert_eq!(RlpList::new().finish(), vec![0xc0]);
+        assert_eq!(
+            RlpList::new()
+                .append_bytes(b"cat")
+                .append_bytes(b"dog")
+                .finish(),
+            hex::decode("c88363617483646f67").unwrap()
+        );
+
+        // The set theoretical representation of three: [ [], [[]], [ [], [[]] ] ]
+        let empty = RlpList::new();
+        let mut one = RlpList::new();
+        one.append_list(&empty);
+        let mut two = RlpList::new();
+        two.append_list(&empty).append_list(&one);
+        assert_eq!(
+            RlpList::new()
+                .append_list(&empty)
+                .append_list(&one)
+                .append_list(&two)
+                .finish(),
+            hex::decode("c7c0c1c0c3c0c1c0").unwrap()
+        );
+    }
+}
@@ -0,0 +1,571 @@
+///! State management module.
+///!
+///! The state is stored in the global thread-level variable `__STATE`.
+///! This module provides utility functions to manage the state. Most
+///! code should use those functions instead of touching `__STATE` directly.
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet, VecDeque},
+};
+
+pub mod audit;
+pub mod eventlog;
+
+use crate::address::Address;
+use crate::deposit::{EventSource, ReceivedEthEvent};
+use crate::eth_rpc::{BlockTag, Hash, TransactionReceipt};
+use crate::guard::TaskType;
+use crate::lifecycle::init::InitArgs;
+use crate::lifecycle::upgrade::UpgradeArgs;
+use crate::tx::SignedEip1559TransactionRequest;
+use crate::withdraw::EthWithdrawalRequest;
+use crate::WEI_PER_CKETH_UNIT;
+use candid::{CandidType, Deserialize, Principal};
+use ic_base_types::CanisterId;
+use serde::Serialize;
+
+/// The maximum number of finalized withdrawal transactions that we keep in
+/// the history.
+const MAX_FINALIZED_TRANSACTIONS: usize = 1000;
+
+thread_local! {
+    static __STATE: RefCell<Option<CkEthMinterState>> = RefCell::default();
+}
+
+#[derive(CandidType, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EthereumNetwork {
+    Mainnet,
+    Sepolia,
+}
+
+impl EthereumNetwork {
+    /// Returns the EIP-155 chain identifier of the network.
+    pub fn chain_id(&self) -> u64 {
+        match self {
+            Self::Mainnet => 1,
+            Self::Sepolia => 11155111,
+        }
+    }
+}
+
+/// A deposit for which the minter minted ckETH.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MintedEvent {
+    pub deposit_event: ReceivedEthEvent,
+    pub mint_block_index: u64,
+}
+
+/// A signed withdrawal transaction that is not finalized yet.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SentTransaction {
+    pub request: EthWithdrawalRequest,
+    /// The latest transaction for the request.
+    pub transaction: SignedEip1559TransactionRequest,
+    /// Transactions with the same nonce that the latest transaction replaced,
+    /// oldest first.  Any of them can still end up in a block.
+    pub replaced_transactions: Vec<SignedEip1559TransactionRequest>,
+}
+
+impl SentTransaction {
+    /// Returns all transactions with the nonce of this request, the latest
+    /// first.
+    pub fn transactions(&self) -> impl Iterator<Item = &SignedEip1559TransactionRequest> {
+        std::iter::once(&self.transaction).chain(self.replaced_transactions.iter().rev())
+    }
+}
+
+/// A withdrawal transaction that was included in a finalized block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FinalizedTransaction {
+    pub request: EthWithdrawalRequest,
+    pub transaction: SignedEip1559TransactionRequest,
+    pub receipt: TransactionReceipt,
+}
+
+/// A withdrawal request for which the minter returned the burnt ckETH.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReimbursedWithdrawal {
+    pub request: EthWithdrawalRequest,
+    pub reimbursed_in_block: u64,
+}
+
+#[derive(CandidType, Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct EthTransaction {
+    pub transaction_hash: String,
+}
+
+impl From<Hash> for EthTransaction {
+    fn from(hash: Hash) -> Self {
+        Self {
+            transaction_hash: hash.to_string(),
+        }
+    }
+}
+
+#[derive(CandidType, Clone, Debug, PartialEq, Eq, Deserialize)]
+pub enum TxFinalizedStatus {
+    /// The transaction transferred the funds to the destination.
+    Success(EthTransaction),
+    /// The transaction was mined but reverted; the destination did not
+    /// receive the funds.
+    Reverted(EthTransaction),
+}
+
+/// The status of a withdrawal request.
+#[derive(CandidType, Clone, Debug, PartialEq, Eq, Deserialize)]
+pub enum RetrieveEthStatus {
+    /// The minter does not know about the request.
+    NotFound,
+    /// The minter burnt ckETH and will create a transaction for the request.
+    Pending,
+    /// The minter signed and sent the transaction, but the transaction is
+    /// not finalized yet.
+    TxSent(EthTransaction),
+    /// The transaction is in a finalized block.
+    TxFinalized(TxFinalizedStatus),
+    /// The withdrawal amount did not cover the transaction fee before the
+    /// request expired.  The minter will return the burnt ckETH.
+    Reimbursing,
+    /// The minter returned the burnt ckETH in the specified ledger block.
+    Reimbursed { reimbursed_in_block: u64 },
+}
+
+/// The state of the ckETH minter.
+///
+/// Every piece of state of the Minter should be stored as field of this struct.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CkEthMinterState {
+    /// The Ethereum network that the minter interacts with.
+    pub ethereum_network: EthereumNetwork,
+
+    /// The name of the ECDSA key to use (e.g., "key_1" on the mainnet).
+    pub ecdsa_key_name: String,
+
+    /// The SEC1-encoded public key of the minter's Ethereum account, fetched
+    /// lazily from the management canister.
+    pub ecdsa_public_key: Option<Vec<u8>>,
+
+    /// The CanisterId of the ckETH Ledger.
+    pub ledger_id: CanisterId,
+
+    /// The address of the helper smart contract that users send ETH to.
+    pub ethereum_contract_address: Option<Address>,
+
+    /// The minimum amount of ckETH ledger units that users can withdraw.
+    pub minimum_withdrawal_amount: u64,
+
+    /// The block tag that bounds the blocks the minter scrapes for deposits.
+    pub ethereum_block_height: BlockTag,
+
+    /// The last block that the minter scraped for deposit events.
+    pub last_scraped_block_number: u64,
+
+    /// Accepted deposits for which the minter did not mint ckETH yet.
+    pub events_to_mint: BTreeMap<EventSource, ReceivedEthEvent>,
+
+    /// Deposits for which the minter minted ckETH.
+    pub minted_events: BTreeMap<EventSource, MintedEvent>,
+
+    /// Deposit events that the minter could not accept, with the reason.
+    pub invalid_events: BTreeMap<EventSource, String>,
+
+    /// The amount of deposited wei that the minter did not mint ckETH for:
+    /// deposits below one ckETH unit and the sub-unit remainder of all other
+    /// deposits.
+    pub unminted_wei: u128,
+
+    /// Withdrawal requests that are waiting for a transaction, in the order
+    /// of acceptance.
+    pub pending_withdrawal_requests: VecDeque<EthWithdrawalRequest>,
+
+    /// Signed withdrawal transactions, indexed by the withdrawal id.
+    pub sent_transactions: BTreeMap<u64, SentTransaction>,
+
+    /// Finalized withdrawal transactions, indexed by the withdrawal id.
+    pub finalized_transactions: BTreeMap<u64, FinalizedTransaction>,
+
+    /// Expired withdrawal requests for which the minter did not return the
+    /// burnt ckETH yet, indexed by the withdrawal id.
+    pub withdrawals_to_reimburse: BTreeMap<u64, EthWithdrawalRequest>,
+
+    /// Withdrawal requests for which the minter returned the burnt ckETH,
+    /// indexed by the withdrawal id.
+    pub reimbursed_withdrawals: BTreeMap<u64, ReimbursedWithdrawal>,
+
+    /// The nonce of the next transaction that the minter signs.
+    pub next_transaction_nonce: u64,
+
+    /// Per-principal lock for withdraw_eth.
+    pub retrieve_eth_principals: BTreeSet<Principal>,
+
+    /// Periodic tasks that are currently running.
+    pub active_tasks: BTreeSet<TaskType>,
+}
+
+impl CkEthMinterState {
+    pub fn reinit(
+        &mut self,
+        InitArgs {
+            ethereum_network,
+            ecdsa_key_name,
+            ledger_id,
+            ethereum_contract_address,
+            ethereum_block_height,
+            minimum_withdrawal_amount,
+            next_transaction_nonce,
+            last_scraped_block_number,
+        }: InitArgs,
+    ) {
+        self.ethereum_network = ethereum_network;
+        self.ecdsa_key_name = ecdsa_key_name;
+        self.ledger_id = ledger_id;
+        self.ethereum_contract_address = ethereum_contract_address;
+        self.ethereum_block_height = ethereum_block_height;
+        self.minimum_withdrawal_amount = minimum_withdrawal_amount;
+        self.next_transaction_nonce = next_transaction_nonce;
+        self.last_scraped_block_number = last_scraped_block_number;
+    }
+
+    pub fn upgrade(
+        &mut self,
+        UpgradeArgs {
+            ethereum_contract_address,
+            ethereum_block_height,
+            minimum_withdrawal_amount,
+            last_scraped_block_number,
+        }: UpgradeArgs,
+    ) {
+        if let Some(address) = ethereum_contract_address {
+            self.ethereum_contract_address = Some(address);
+        }
+        if let Some(block_height) = ethereum_block_height {
+            self.ethereum_block_height = block_height;
+        }
+        if let Some(amount) = minimum_withdrawal_amount {
+            self.minimum_withdrawal_amount = amount;
+        }
+        if let Some(block_number) = last_scraped_block_number {
+            self.last_scraped_block_number = block_number;
+        }
+    }
+
+    /// Returns true if the minter has already processed the deposit event.
+    pub fn has_seen_event(&self, source: &EventSource) -> bool {
+        self.events_to_mint.contains_key(source)
+            || self.minted_events.contains_key(source)
+            || self.invalid_events.contains_key(source)
+    }
+
+    fn record_event_to_mint(&mut self, event: ReceivedEthEvent) {
+        let source = event.source();
+        assert!(
+            !self.has_seen_event(&source),
+            "BUG: attempted to accept the deposit {:?} twice",
+            source
+        );
+        self.unminted_wei += event.value % WEI_PER_CKETH_UNIT;
+        self.events_to_mint.insert(source, event);
+    }
+
+    fn record_unminted_deposit(&mut self, event: ReceivedEthEvent) {
+        let source = event.source();
+        assert!(
+            !self.has_seen_event(&source),
+            "BUG: attempted to record the deposit {:?} twice",
+            source
+        );
+        self.unminted_wei += event.value;
+        self.invalid_events.insert(
+            source,
+            format!(
+                "the deposited amount of {} wei is below one ckETH unit",
+                event.value
+            ),
+        );
+    }
+
+    fn record_invalid_deposit(&mut self, source: EventSource, reason: String) {
+        assert!(
+            !self.events_to_mint.contains_key(&source) && !self.minted_events.contains_key(&source),
+            "BUG: attempted to mark the accepted deposit {:?} as invalid",
+            source
+        );
+        self.invalid_events.insert(source, reason);
+    }
+
+    fn record_successful_mint(&mut self, source: &EventSource, mint_block_index: u64) {
+        let deposit_event = self.events_to_mint.remove(source).unwrap_or_else(|| {
+            panic!(
+                "BUG: attempted to mint ckETH for an unknown deposit {:?}",
+                source
+            )
+        });
+        self.minted_events.insert(
+            *source,
+            MintedEvent {
+                deposit_event,
+                mint_block_index,
+            },
+        );
+    }
+
+    fn record_synced_to_block(&mut self, block_number: u64) {
+        assert!(
+            block_number >= self.last_scraped_block_number,
+            "BUG: the last scraped block number decreased from {} to {}",
+            self.last_scraped_block_number,
+            block_number
+        );
+        self.last_scraped_block_number = block_number;
+    }
+
+    fn record_withdrawal_request(&mut self, request: EthWithdrawalRequest) {
+        assert_eq!(
+            self.retrieve_eth_status(request.ledger_burn_index),
+            RetrieveEthStatus::NotFound,
+            "BUG: duplicate withdrawal request"
+        );
+        self.pending_withdrawal_requests.push_back(request);
+    }
+
+    fn record_signed_transaction(
+        &mut self,
+        withdrawal_id: u64,
+        transaction: SignedEip1559TransactionRequest,
+    ) -> Result<(), String> {
+        if transaction.nonce() != self.next_transaction_nonce {
+            return Err(format!(
+                "transaction nonce {} does not match the next nonce {}",
+                transaction.nonce(),
+                self.next_transaction_nonce
+            ));
+        }
+        let position = self
+            .pending_withdrawal_requests
+            .iter()
+            .position(|r| r.ledger_burn_index == withdrawal_id)
+            .ok_or_else(|| format!("withdrawal request {} is not pending", withdrawal_id))?;
+        let request = self.pending_withdrawal_requests.remove(position).unwrap();
+        self.next_transaction_nonce += 1;
+        self.sent_transactions.insert(
+            withdrawal_id,
+            SentTransaction {
+                request,
+                transaction,
+                replaced_transactions: vec![],
+            },
+        );
+        Ok(())
+    }
+
+    fn record_replaced_transaction(
+        &mut self,
+        withdrawal_id: u64,
+        transaction: SignedEip1559TransactionRequest,
+    ) -> Result<(), String> {
+        let sent = self
+            .sent_transactions
+            .get_mut(&withdrawal_id)
+            .ok_or_else(|| format!("withdrawal {} has no sent transaction", withdrawal_id))?;
+        if transaction.nonce() != sent.transaction.nonce() {
+            return Err(format!(
+                "replacement nonce {} does not match the nonce {} of withdrawal {}",
+                transaction.nonce(),
+                sent.transaction.nonce(),
+                withdrawal_id
+            ));
+        }
+        let replaced = std::mem::replace(&mut sent.transaction, transaction);
+        sent.replaced_transactions.push(replaced);
+        Ok(())
+    }
+
+    fn record_finalized_transaction(
+        &mut self,
+        withdrawal_id: u64,
+        receipt: TransactionReceipt,
+    ) -> Result<(), String> {
+        let sent = self
+            .sent_transactions
+            .get(&withdrawal_id)
+            .ok_or_else(|| format!("withdrawal {} has no sent transaction", withdrawal_id))?;
+        let transaction = sent
+            .transactions()
+            .find(|tx| tx.hash() == receipt.transaction_hash)
+            .cloned()
+            .ok_or_else(|| {
+                format!(
+                    "receipt of transaction {} does not match any transaction of withdrawal {}",
+                    receipt.transaction_hash, withdrawal_id
+                )
+            })?;
+        let request = self
+            .sent_transactions
+            .remove(&withdrawal_id)
+            .expect("BUG: the sent transaction disappeared")
+            .request;
+        self.finalized_transactions.insert(
+            withdrawal_id,
+            FinalizedTransaction {
+                request,
+                transaction,
+                receipt,
+            },
+        );
+        while self.finalized_transactions.len() > MAX_FINALIZED_TRANSACTIONS {
+            self.finalized_transactions.pop_first();
+        }
+        Ok(())
+    }
+
+    fn record_expired_withdrawal_request(&mut self, withdrawal_id: u64) -> Result<(), String> {
+        let position = self
+            .pending_withdrawal_requests
+            .iter()
+            .position(|r| r.ledger_burn_index == withdrawal_id)
+            .ok_or_else(|| format!("withdrawal request {} is not pending", withdrawal_id))?;
+        let request = self.pending_withdrawal_requests.remove(position).unwrap();
+        self.withdrawals_to_reimburse.insert(withdrawal_id, request);
+        Ok(())
+    }
+
+    fn record_reimbursed_withdrawal(
+        &mut self,
+        withdrawal_id: u64,
+        reimbursed_in_block: u64,
+    ) -> Result<(), String> {
+        let request = self
+            .withdrawals_to_reimburse
+            .remove(&withdrawal_id)
+            .ok_or_else(|| format!("withdrawal {} is not waiting for a refund", withdrawal_id))?;
+        self.reimbursed_withdrawals.insert(
+            withdrawal_id,
+            ReimbursedWithdrawal {
+                request,
+                reimbursed_in_block,
+            },
+        );
+        while self.reimbursed_withdrawals.len() > MAX_FINALIZED_TRANSACTIONS {
+            self.reimbursed_withdrawals.pop_first();
+        }
+        Ok(())
+    }
+
+    /// Returns the status of the withdrawal with the specified ledger burn
+    /// index.
+    pub fn retrieve_eth_status(&self, ledger_burn_index: u64) -> RetrieveEthStatus {
+        if self
+            .pending_withdrawal_requests
+            .iter()
+            .any(|r| r.ledger_burn_index == ledger_burn_index)
+        {
+            return RetrieveEthStatus::Pending;
+        }
+        if let Some(sent) = self.sent_transactions.get(&ledger_burn_index) {
+            return RetrieveEthStatus::TxSent(sent.transaction.hash().into());
+        }
+        if let Some(finalized) = self.finalized_transactions.get(&ledger_burn_index) {
+            let tx = EthTransaction::from(finalized.transaction.hash());
+            return RetrieveEthStatus::TxFinalized(if finalized.receipt.succeeded() {
+                TxFinalizedStatus::Success(tx)
+            } else {
+                TxFinalizedStatus::Reverted(tx)
+            });
+        }
+        if self
+            .withdrawals_to_reimburse
+            .contains_key(&ledger_burn_index)
+        {
+            return RetrieveEthStatus::Reimbursing;
+        }
+        if let Some(reimbursed) = self.reimbursed_withdrawals.get(&ledger_burn_index) {
+            return RetrieveEthStatus::Reimbursed {
+                reimbursed_in_block: reimbursed.reimbursed_in_block,
+            };
+        }
+        RetrieveEthStatus::NotFound
+    }
+
+    /// Checks whether two states are equivalent, ignoring the fields that are
+    /// not persisted in the event log.
+    pub fn check_semantically_eq(&self, other: &Self) -> Result<(), String> {
+        let strip = |s: &Self| Self {
+            ecdsa_public_key: None,
+            retrieve_eth_principals: BTreeSet::new(),
+            active_tasks: BTreeSet::new(),
+            ..s.clone()
+        };
+        if strip(self) != strip(other) {
+            return Err(format!(
+                "states are not equivalent:\n{:?}\n!=\n{:?}",
+                self, other
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl From<InitArgs> for CkEthMinterState {
+    fn from(args: InitArgs) -> Self {
+        Self {
+            ethereum_network: args.ethereum_network,
+            ecdsa_key_name: args.ecdsa_key_name,
+            ecdsa_public_key: None,
+            ledger_id: args.ledger_id,
+            ethereum_contract_address: args.ethereum_contract_address,
+            minimum_withdrawal_amount: args.minimum_withdrawal_amount,
+            ethereum_block_height: args.ethereum_block_height,
+            last_scraped_block_number: args.last_scraped_block_number,
+            events_to_mint: Default::default(),
+            minted_events: Default::default(),
+            invalid_events: Default::default(),
+            unminted_wei: 0,
+            pending_withdrawal_requests: Default::default(),
+            sent_transactions: Default::default(),
+            finalized_transactions: Default::default(),
+            withdrawals_to_reimburse: Default::default(),
+            reimbursed_withdrawals: Default::default(),
+            next_transaction_nonce: args.next_transaction_nonce,
+            retrieve_eth_principals: Default::default(),
+            active_tasks: Default::default(),
+        }
+    }
+}
+
+/// Take the current state.
+///
+/// After calling this function the state won't be initialized anymore.
+/// Panics if there is no state.
+pub fn take_state<F, R>(f: F) -> R
+where
+    F: FnOnce(CkEthMinterState) -> R,
+{
+    __STATE.with(|s| f(s.take().expect("State not initialized!")))
+}
+
+/// Mutates (part of) the current state using `f`.
+///
+/// Panics if there is no state.
+pub fn mutate_state<F, R>(f: F) -> R
+where
+    F: FnOnce(&mut CkEthMinterState) -> R,
+{
+    __STATE.with(|s| f(s.borrow_mut().as_mut().expect("State not initialized!")))
+}
+
+/// Read (part of) the current state using `f`.
+///
+/// Panics if there is no state.
+pub fn read_state<F, R>(f: F) -> R
+where
+    F: FnOnce(&CkEthMinterState) -> R,
+{
+    __STATE.with(|s| f(s.borrow().as_ref().expect("State not initialized!")))
+}
+
+/// Replaces the current state.
+pub fn replace_state(state: CkEthMinterState) {
+    __STATE.with(|s| {
+        *s.borrow_mut() = Some(state);
+    });
+}
@@ -0,0 +1,107 @@
+//! State modifications that should end up in the event log.
+
+use super::{eventlog::Event, CkEthMinterState};
+use crate::deposit::{EventSource, ReceivedEthEvent};
+use crate::eth_rpc::TransactionReceipt;
+use crate::storage::record_event;
+use crate::tx::SignedEip1559TransactionRequest;
+use crate::withdraw::EthWithdrawalRequest;
+
+pub fn accept_deposit(state: &mut CkEthMinterState, deposit: ReceivedEthEvent) {
+    record_event(&Event::AcceptedDeposit(deposit.clone()));
+    state.record_event_to_mint(deposit);
+}
+
+pub fn record_unminted_deposit(state: &mut CkEthMinterState, deposit: ReceivedEthEvent) {
+    record_event(&Event::UnmintedDeposit(deposit.clone()));
+    state.record_unminted_deposit(deposit);
+}
+
+pub fn record_invalid_deposit(state: &mut CkEthMinterState, source: EventSource, reason: String) {
+    record_event(&Event::InvalidDeposit {
+        event_source: source,
+        reason: reason.clone(),
+    });
+    state.record_invalid_deposit(source, reason);
+}
+
+pub fn mint_cketh(state: &mut CkEthMinterState, source: EventSource, mint_block_index: u64) {
+    record_event(&Event::MintedCkEth {
+        event_source: source,
+        mint_block_index,
+    });
+    state.record_successful_mint(&source, mint_block_index);
+}
+
+pub fn update_last_scraped_block_number(state: &mut CkEthMinterState, block_number: u64) {
+    record_event(&Event::SyncedToBlock { block_number });
+    state.record_synced_to_block(block_number);
+}
+
+pub fn accept_withdrawal_request(state: &mut CkEthMinterState, request: EthWithdrawalRequest) {
+    record_event(&Event::AcceptedEthWithdrawalRequest(request.clone()));
+    state.record_withdrawal_request(request);
+}
+
+pub fn record_signed_transaction(
+    state: &mut CkEthMinterState,
+    withdrawal_id: u64,
+    transaction: SignedEip1559TransactionRequest,
+) {
+    record_event(&Event::SignedTransaction {
+        withdrawal_id,
+        transaction: transaction.clone(),
+    });
+    state
+        .record_signed_transaction(withdrawal_id, transaction)
+        .unwrap_or_else(|e| panic!("BUG: failed to record a signed transaction: {}", e));
+}
+
+pub fn replace_transaction(
+    state: &mut CkEthMinterState,
+    withdrawal_id: u64,
+    transaction: SignedEip1559TransactionRequest,
+) {
+    record_event(&Event::ReplacedTransaction {
+        withdrawal_id,
+        transaction: transaction.clone(),
+    });
+    state
+        .record_replaced_transaction(withdrawal_id, transaction)
+        .unwrap_or_else(|e| panic!("BUG: failed to record a replaced transaction: {}", e));
+}
+
+pub fn record_finalized_transaction(
+    state: &mut CkEthMinterState,
+    withdrawal_id: u64,
+    receipt: TransactionReceipt,
+) {
+    record_event(&Event::FinalizedTransaction {
+        withdrawal_id,
+        transaction_receipt: receipt.clone(),
+    });
+    state
+        .record_finalized_transaction(withdrawal_id, receipt)
+        .unwrap_or_else(|e| panic!("BUG: failed to record a finalized transaction: {}", e));
+}
+
+pub fn expire_withdrawal_request(state: &mut CkEthMinterState, withdrawal_id: u64) {
+    record_event(&Event::ExpiredWithdrawalRequest { withdrawal_id });
+    state
+        .record_expired_withdrawal_request(withdrawal_id)
+        .unwrap_or_else(|e| panic!("BUG: failed to expire a withdrawal request: {}", e));
+}
+
+pub fn reimburse_withdrawal(
+    state: &mut CkEthMinterState,
+    withdrawal_id: u64,
+    reimbursed_in_block: u64,
+) {
+    record_event(&Event::ReimbursedWithdrawal {
+        withdrawal_id,
+        reimbursed_in_block,
+    });
+    state
+        .record_reimbursed_withdrawal(withdrawal_id, reimbursed_in_block)
+        .unwrap_or_else(|e| panic!("BUG: failed to record a reimbursed withdrawal: {}", e));
+}
@@ -0,0 +1,237 @@
+use crate::deposit::{EventSource, ReceivedEthEvent};
+use crate::eth_rpc::TransactionReceipt;
+use crate::lifecycle::init::InitArgs;
+use crate::lifecycle::upgrade::UpgradeArgs;
+use crate::state::CkEthMinterState;
+use crate::tx::SignedEip1559TransactionRequest;
+use crate::withdraw::EthWithdrawalRequest;
+use serde::{Deserialize, Serialize};
+
+#[derive(candid::CandidType, Deserialize)]
+pub struct GetEventsArg {
+    pub start: u64,
+    pub length: u64,
+}
+
+#[derive(candid::CandidType, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Event {
+    /// Indicates the minter initialization with the specified arguments.  Must be
+    /// the first event in the event log.
+    #[serde(rename = "init")]
+    Init(InitArgs),
+
+    /// Indicates the minter upgrade with specified arguments.
+    #[serde(rename = "upgrade")]
+    Upgrade(UpgradeArgs),
+
+    /// Indicates that the minter found a valid deposit event in the helper
+    /// contract logs.  The minter mints ckETH for the deposit later.
+    #[serde(rename = "accepted_deposit")]
+    AcceptedDeposit(ReceivedEthEvent),
+
+    /// Indicates that the minter found a deposit event that it cannot mint
+    /// ckETH for (e.g., the principal is malformed).
+    #[serde(rename = "invalid_deposit")]
+    InvalidDeposit {
+        #[serde(rename = "event_source")]
+        event_source: EventSource,
+        #[serde(rename = "reason")]
+        reason: String,
+    },
+
+    /// Indicates that the minter found a valid deposit below one ckETH unit.
+    /// The minter does not mint ckETH for it and accounts the deposited wei
+    /// as unminted.
+    #[serde(rename = "unminted_deposit")]
+    UnmintedDeposit(ReceivedEthEvent),
+
+    /// Indicates that the minter minted ckETH for an accepted deposit.
+    #[serde(rename = "minted_cketh")]
+    MintedCkEth {
+        #[serde(rename = "event_source")]
+        event_source: EventSource,
+        /// The index of the ledger block containing the mint transaction.
+        #[serde(rename = "mint_block_index")]
+        mint_block_index: u64,
+    },
+
+    /// Indicates that the minter scraped all the logs up to the specified
+    /// block (inclusive).
+    #[serde(rename = "synced_to_block")]
+    SyncedToBlock {
+        #[serde(rename = "block_number")]
+        block_number: u64,
+    },
+
+    /// Indicates that the minter accepted a new withdrawal request.
+    /// The minter emits this event _after_ it burnt ckETH.
+    #[serde(rename = "accepted_eth_withdrawal_request")]
+    AcceptedEthWithdrawalRequest(EthWithdrawalRequest),
+
+    /// Indicates that the minter signed a transaction for a withdrawal
+    /// request.  The transaction consumes the next nonce of the minter.
+    #[serde(rename = "signed_transaction")]
+    SignedTransaction {
+        /// The ledger burn index of the withdrawal request.
+        #[serde(rename = "withdrawal_id")]
+        withdrawal_id: u64,
+        #[serde(rename = "transaction")]
+        transaction: SignedEip1559TransactionRequest,
+    },
+
+    /// Indicates that the minter signed a transaction with the same nonce and
+    /// a higher fee than the latest transaction of a withdrawal request.
+    #[serde(rename = "replaced_transaction")]
+    ReplacedTransaction {
+        #[serde(rename = "withdrawal_id")]
+        withdrawal_id: u64,
+        #[serde(rename = "transaction")]
+        transaction: SignedEip1559TransactionRequest,
+    },
+
+    /// Indicates that a withdrawal transaction was included in a finalized
+    /// block.
+    #[serde(rename = "finalized_transaction")]
+    FinalizedTransaction {
+        #[serde(rename = "withdrawal_id")]
+        withdrawal_id: u64,
+        #[serde(rename = "transaction_receipt")]
+        transaction_receipt: TransactionReceipt,
+    },
+
+    /// Indicates that the amount of a pending withdrawal request did not
+    /// cover the transaction fee until the request expired.  The minter
+    /// returns the burnt ckETH later.
+    #[serde(rename = "expired_withdrawal_request")]
+    ExpiredWithdrawalRequest {
+        #[serde(rename = "withdrawal_id")]
+        withdrawal_id: u64,
+    },
+
+    /// Indicates that the minter returned the burnt ckETH of an expired
+    /// withdrawal request.
+    #[serde(rename = "reimbursed_withdrawal")]
+    ReimbursedWithdrawal {
+        #[serde(rename = "withdrawal_id")]
+        withdrawal_id: u64,
+        /// The index of the ledger block containing the mint transaction.
+        #[serde(rename = "reimbursed_in_block")]
+        reimbursed_in_block: u64,
+    },
+}
+
+#[derive(Debug)]
+pub enum ReplayLogError {
+    /// There are no events in the event log.
+    EmptyLog,
+    /// The event log is inconsistent.
+    InconsistentLog(String),
+}
+
+/// Reconstructs the minter state from an event log.
+pub fn replay(mut events: impl Iterator<Item = Event>) -> Result<CkEthMinterState, ReplayLogError> {
+    let mut state = match events.next() {
+        Some(Event::Init(args)) => CkEthMinterState::from(args),
+        Some(evt) => {
+            return Err(ReplayLogError::InconsistentLog(format!(
+                "The first event is not Init: {:?}",
+                evt
+            )))
+        }
+        None => return Err(ReplayLogError::EmptyLog),
+    };
+
+    for event in events {
+        match event {
+            Event::Init(args) => {
+                state.reinit(args);
+            }
+            Event::Upgrade(args) => state.upgrade(args),
+            Event::AcceptedDeposit(deposit) => {
+                if state.has_seen_event(&deposit.source()) {
+                    return Err(ReplayLogError::InconsistentLog(format!(
+                        "Accepted the deposit {:?} twice",
+                        deposit.source()
+                    )));
+                }
+                state.record_event_to_mint(deposit);
+            }
+            Event::UnmintedDeposit(deposit) => {
+                if state.has_seen_event(&deposit.source()) {
+                    return Err(ReplayLogError::InconsistentLog(format!(
+                        "Recorded the deposit {:?} twice",
+                        deposit.source()
+                    )));
+                }
+                state.record_unminted_deposit(deposit);
+            }
+            Event::InvalidDeposit {
+                event_source,
+                reason,
+            } => {
+                if state.events_to_mint.contains_key(&event_source)
+                    || state.minted_events.contains_key(&event_source)
+                {
+                    return Err(ReplayLogError::InconsistentLog(format!(
+                        "Marked the accepted deposit {:?} as invalid",
+                        event_source
+                    )));
+                }
+                state.record_invalid_deposit(event_source, reason);
+            }
+            Event::MintedCkEth {
+                event_source,
+                mint_block_index,
+            } => {
+                if !state.events_to_mint.contains_key(&event_source) {
+                    return Err(ReplayLogError::InconsistentLog(format!(
+                        "Minted ckETH for an unknown deposit {:?}",
+                        event_source
+                    )));
+                }
+                state.record_successful_mint(&event_source, mint_block_index);
+            }
+            Event::SyncedToBlock { block_number } => {
+                if block_number < state.last_scraped_block_number {
+                    return Err(ReplayLogError::InconsistentLog(format!(
+                        "The last scraped block number decreased from {} to {}",
+                        state.last_scraped_block_number, block_number
+                    )));
+                }
+                state.record_synced_to_block(block_number);
+            }
+            Event::AcceptedEthWithdrawalRequest(request) => {
+                state.record_withdrawal_request(request);
+            }
+            Event::SignedTransaction {
+                withdrawal_id,
+                transaction,
+            } => state
+                .record_signed_transaction(withdrawal_id, transaction)
+                .map_err(ReplayLogError::InconsistentLog)?,
+            Event::ReplacedTransaction {
+                withdrawal_id,
+                transaction,
+            } => state
+                .record_replaced_transaction(withdrawal_id, transaction)
+                .map_err(ReplayLogError::InconsistentLog)?,
+            Event::FinalizedTransaction {
+                withdrawal_id,
+                transaction_receipt,
+            } => state
+                .record_finalized_transaction(withdrawal_id, transaction_receipt)
+                .map_err(ReplayLogError::InconsistentLog)?,
+            Event::ExpiredWithdrawalRequest { withdrawal_id } => state
+                .record_expired_withdrawal_request(withdrawal_id)
+                .map_err(ReplayLogError::InconsistentLog)?,
+            Event::ReimbursedWithdrawal {
+                withdrawal_id,
+                reimbursed_in_block,
+            } => state
+                .record_reimbursed_withdrawal(withdrawal_id, reimbursed_in_block)
+                .map_err(ReplayLogError::InconsistentLog)?,
+        }
+    }
+
+    Ok(state)
+}
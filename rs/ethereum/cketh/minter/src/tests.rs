@@ -0,0 +1,418 @@
+use crate::address::Address;
+use crate::deposit::{EventSource, ReceivedEthEvent};
+use crate::eth_rpc::{
+    BlockSpec, BlockTag, GetLogsParam, Hash, LogEntry, Quantity, RpcError, RpcTransport,
+    TransactionReceipt,
+};
+use crate::eth_rpc_client::{EthRpcClient, MultiCallError};
+use crate::lifecycle::init::InitArgs;
+use crate::state::eventlog::replay;
+use crate::state::{
+    audit, mutate_state, read_state, replace_state, CkEthMinterState, EthereumNetwork,
+    RetrieveEthStatus, TxFinalizedStatus,
+};
+use crate::storage;
+use crate::tx::{Eip1559Signature, Eip1559TransactionRequest, SignedEip1559TransactionRequest};
+use crate::withdraw::EthWithdrawalRequest;
+use async_trait::async_trait;
+use candid::Principal;
+use ic_base_types::CanisterId;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+pub(crate) fn init_args() -> InitArgs {
+    InitArgs {
+        ethereum_network: EthereumNetwork::Sepolia,
+        ecdsa_key_name: "test_key_1".to_string(),
+        ledger_id: CanisterId::from_u64(42),
+        ethereum_contract_address: Some(
+            Address::from_str("0x7574eB42cA208A4f6960ECCAfDF186D627dCC175").unwrap(),
+        ),
+        ethereum_block_height: BlockTag::Finalized,
+        minimum_withdrawal_amount: 1_000_000,
+        next_transaction_nonce: 0,
+        last_scraped_block_number: 3_956_206,
+    }
+}
+
+/// Initializes the minter state and records the Init event.
+pub(crate) fn init_state() {
+    let args = init_args();
+    storage::record_event(&crate::state::eventlog::Event::Init(args.clone()));
+    replace_state(CkEthMinterState::from(args));
+}
+
+fn principal(id: u64) -> Principal {
+    Principal::try_from_slice(&id.to_le_bytes()).unwrap()
+}
+
+fn deposit(log_index: u64) -> ReceivedEthEvent {
+    ReceivedEthEvent {
+        transaction_hash: Hash([0xab; 32]),
+        block_number: 3_956_207,
+        log_index,
+        from_address: Address::from_str("0xdd2851Cdd40aE6536831558DD46db62fAc7A844d").unwrap(),
+        value: 10_000_000_000_000_000,
+        principal: principal(1),
+    }
+}
+
+fn withdrawal_request(ledger_burn_index: u64) -> EthWithdrawalRequest {
+    EthWithdrawalRequest {
+        withdrawal_amount: 10_000_000_000_000_000,
+        destination: Address::from_str("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").unwrap(),
+        ledger_burn_index,
+        from: principal(2),
+        created_at: 0,
+    }
+}
+
+fn signed_transaction(nonce: u64) -> SignedEip1559TransactionRequest {
+    SignedEip1559TransactionRequest {
+        transaction: Eip1559TransactionRequest {
+            chain_id: EthereumNetwork::Sepolia.chain_id(),
+            nonce,
+            max_priority_fee_per_gas: 1_500_000_000,
+            max_fee_per_gas: 21_500_000_000,
+            gas_limit: 21_000,
+            destination: Address::from_str("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").unwrap(),
+            amount: 10_000_000_000_000_000 - 21_500_000_000 * 21_000,
+            data: vec![],
+        },
+        signature: Eip1559Signature {
+            signature_y_parity: false,
+            r: [1; 32],
+            s: [2; 32],
+        },
+    }
+}
+
+fn receipt(transaction: &SignedEip1559TransactionRequest, status: u128) -> TransactionReceipt {
+    TransactionReceipt {
+        transaction_hash: transaction.hash(),
+        block_number: Quantity(3_956_300),
+        effective_gas_price: Quantity(11_000_000_000),
+        gas_used: Quantity(21_000),
+        status: Quantity(status),
+    }
+}
+
+#[test]
+fn should_replay_event_log() {
+    init_state();
+
+    mutate_state(|s| {
+        audit::accept_deposit(s, deposit(1));
+        audit::accept_deposit(s, deposit(2));
+        audit::record_invalid_deposit(
+            s,
+            EventSource {
+                transaction_hash: Hash([0xcd; 32]),
+                log_index: 0,
+            },
+            "invalid principal".to_string(),
+        );
+        audit::mint_cketh(s, deposit(1).source(), 7);
+        audit::update_last_scraped_block_number(s, 3_957_000);
+
+        audit::accept_withdrawal_request(s, withdrawal_request(8));
+        audit::accept_withdrawal_request(s, withdrawal_request(9));
+        audit::record_signed_transaction(s, 8, signed_transaction(0));
+        audit::record_signed_transaction(s, 9, signed_transaction(1));
+        audit::record_finalized_transaction(s, 8, receipt(&signed_transaction(0), 1));
+    });
+
+    let replayed = replay(storage::events()).expect("failed to replay the event log");
+    read_state(|s| s.check_semantically_eq(&replayed)).unwrap();
+
+    assert_eq!(replayed.next_transaction_nonce, 2);
+    assert_eq!(replayed.last_scraped_block_number, 3_957_000);
+    assert_eq!(
+        replayed.events_to_mint.keys().collect::<Vec<_>>(),
+        vec![&deposit(2).source()]
+    );
+    assert_eq!(
+        replayed.retrieve_eth_status(8),
+        RetrieveEthStatus::TxFinalized(TxFinalizedStatus::Success(
+            signed_transaction(0).hash().into()
+        ))
+    );
+    assert_eq!(
+        replayed.retrieve_eth_status(9),
+        RetrieveEthStatus::TxSent(signed_transaction(1).hash().into())
+    );
+    assert_eq!(
+        replayed.retrieve_eth_status(10),
+        RetrieveEthStatus::NotFound
+    );
+}
+
+#[test]
+fn should_reject_signed_transaction_with_unexpected_nonce() {
+    init_state();
+    mutate_state(|s| audit::accept_withdrawal_request(s, withdrawal_request(8)));
+
+    let mut events: Vec<_> = storage::events().collect();
+    events.push(crate::state::eventlog::Event::SignedTransaction {
+        withdrawal_id: 8,
+        transaction: signed_transaction(1),
+    });
+    assert!(replay(events.into_iter()).is_err());
+}
+
+#[test]
+fn should_not_accept_the_same_deposit_twice() {
+    init_state();
+    mutate_state(|s| audit::accept_deposit(s, deposit(1)));
+    assert!(read_state(|s| s.has_seen_event(&deposit(1).source())));
+
+    let mut events: Vec<_> = storage::events().collect();
+    events.push(crate::state::eventlog::Event::AcceptedDeposit(deposit(1)));
+    assert!(replay(events.into_iter()).is_err());
+}
+
+#[test]
+fn should_report_reverted_transactions() {
+    init_state();
+    mutate_state(|s| {
+        audit::accept_withdrawal_request(s, withdrawal_request(8));
+        audit::record_signed_transaction(s, 8, signed_transaction(0));
+        audit::record_finalized_transaction(s, 8, receipt(&signed_transaction(0), 0));
+    });
+    assert_eq!(
+        read_state(|s| s.retrieve_eth_status(8)),
+        RetrieveEthStatus::TxFinalized(TxFinalizedStatus::Reverted(
+            signed_transaction(0).hash().into()
+        ))
+    );
+}
+
+#[test]
+fn should_replay_replaced_and_reimbursed_withdrawals() {
+    init_state();
+
+    let mut replacement = signed_transaction(0);
+    replacement.transaction.max_fee_per_gas = 30_000_000_000;
+    replacement.transaction.amount = 10_000_000_000_000_000 - 30_000_000_000 * 21_000;
+    let mut small_deposit = deposit(3);
+    small_deposit.value = 9_999_999_999;
+    let mut odd_deposit = deposit(4);
+    odd_deposit.value = 10_000_000_000_000_123;
+
+    mutate_state(|s| {
+        audit::record_unminted_deposit(s, small_deposit.clone());
+        audit::accept_deposit(s, odd_deposit.clone());
+
+        audit::accept_withdrawal_request(s, withdrawal_request(8));
+        audit::accept_withdrawal_request(s, withdrawal_request(9));
+        audit::accept_withdrawal_request(s, withdrawal_request(10));
+        audit::record_signed_transaction(s, 8, signed_transaction(0));
+        audit::replace_transaction(s, 8, replacement.clone());
+        audit::expire_withdrawal_request(s, 9);
+        audit::expire_withdrawal_request(s, 10);
+        audit::reimburse_withdrawal(s, 10, 12);
+    });
+    assert_eq!(
+        read_state(|s| s.retrieve_eth_status(8)),
+        RetrieveEthStatus::TxSent(replacement.hash().into())
+    );
+
+    // The replaced transaction can still end up in a block.
+    mutate_state(|s| audit::record_finalized_transaction(s, 8, receipt(&signed_transaction(0), 1)));
+
+    let replayed = replay(storage::events()).expect("failed to replay the event log");
+    read_state(|s| s.check_semantically_eq(&replayed)).unwrap();
+
+    assert_eq!(replayed.next_transaction_nonce, 1);
+    assert_eq!(replayed.unminted_wei, 9_999_999_999 + 123);
+    assert!(replayed.has_seen_event(&small_deposit.source()));
+    assert_eq!(
+        replayed.retrieve_eth_status(8),
+        RetrieveEthStatus::TxFinalized(TxFinalizedStatus::Success(
+            signed_transaction(0).hash().into()
+        ))
+    );
+    assert_eq!(
+        replayed.retrieve_eth_status(9),
+        RetrieveEthStatus::Reimbursing
+    );
+    assert_eq!(
+        replayed.retrieve_eth_status(10),
+        RetrieveEthStatus::Reimbursed {
+            reimbursed_in_block: 12
+        }
+    );
+}
+
+#[test]
+fn should_reject_replacement_with_another_nonce() {
+    init_state();
+    mutate_state(|s| {
+        audit::accept_withdrawal_request(s, withdrawal_request(8));
+        audit::record_signed_transaction(s, 8, signed_transaction(0));
+    });
+
+    let mut events: Vec<_> = storage::events().collect();
+    events.push(crate::state::eventlog::Event::ReplacedTransaction {
+        withdrawal_id: 8,
+        transaction: signed_transaction(1),
+    });
+    assert!(replay(events.into_iter()).is_err());
+}
+
+#[test]
+fn should_reject_receipt_of_unknown_transaction() {
+    init_state();
+    mutate_state(|s| {
+        audit::accept_withdrawal_request(s, withdrawal_request(8));
+        audit::record_signed_transaction(s, 8, signed_transaction(0));
+    });
+
+    let mut events: Vec<_> = storage::events().collect();
+    events.push(crate::state::eventlog::Event::FinalizedTransaction {
+        withdrawal_id: 8,
+        transaction_receipt: receipt(&signed_transaction(1), 1),
+    });
+    assert!(replay(events.into_iter()).is_err());
+}
+
+#[test]
+fn should_reject_reimbursement_of_pending_request() {
+    init_state();
+    mutate_state(|s| audit::accept_withdrawal_request(s, withdrawal_request(8)));
+
+    let mut events: Vec<_> = storage::events().collect();
+    events.push(crate::state::eventlog::Event::ReimbursedWithdrawal {
+        withdrawal_id: 8,
+        reimbursed_in_block: 12,
+    });
+    assert!(replay(events.into_iter()).is_err());
+}
+
+/// A JSON-RPC server mock that replies with canned responses per provider
+/// and method.
+#[derive(Default)]
+struct MockJsonRpcServer {
+    replies: BTreeMap<(String, String), String>,
+}
+
+impl MockJsonRpcServer {
+    fn reply(mut self, url: &str, method: &str, result: serde_json::Value) -> Self {
+        self.replies.insert(
+            (url.to_string(), method.to_string()),
+            serde_json::json!({"jsonrpc": "2.0", "id": 1, "result": result}).to_string(),
+        );
+        self
+    }
+}
+
+#[async_trait(?Send)]
+impl RpcTransport for MockJsonRpcServer {
+    async fn post(
+        &self,
+        url: &str,
+        payload: Vec<u8>,
+        _max_response_bytes: u64,
+    ) -> Result<Vec<u8>, RpcError> {
+        let request: serde_json::Value = serde_json::from_slice(&payload).unwrap();
+        let method = request["method"].as_str().unwrap().to_string();
+        self.replies
+            .get(&(url.to_string(), method))
+            .map(|reply| reply.as_bytes().to_vec())
+            .ok_or(RpcError::HttpStatus(404))
+    }
+}
+
+fn get_logs_param() -> GetLogsParam {
+    GetLogsParam {
+        from_block: BlockSpec::Number(Quantity(3_956_207)),
+        to_block: BlockSpec::Number(Quantity(3_957_000)),
+        address: vec![init_args().ethereum_contract_address.unwrap()],
+        topics: vec![crate::deposit::received_eth_event_topic()],
+    }
+}
+
+fn log_entry_json(principal_topic: &str) -> serde_json::Value {
+    serde_json::json!([{
+        "address": "0x7574eb42ca208a4f6960eccafdf186d627dcc175",
+        "topics": [
+            crate::deposit::received_eth_event_topic().to_string(),
+            "0x000000000000000000000000dd2851cdd40ae6536831558dd46db62fac7a844d",
+            principal_topic,
+        ],
+        "data": "0x000000000000000000000000000000000000000000000000002386f26fc10000",
+        "blockNumber": "0x3ca487",
+        "transactionHash": "0x705f826861c802b407843e99af986cfde8749b669e5e0a5a150f4350bcaa9bc3",
+        "logIndex": "0x27",
+        "removed": false
+    }])
+}
+
+#[test]
+fn should_fetch_logs_when_providers_agree() {
+    let principal_topic = Hash(crate::deposit::encode_principal(&principal(1))).to_string();
+    let server = MockJsonRpcServer::default()
+        .reply("https://a", "eth_getLogs", log_entry_json(&principal_topic))
+        .reply("https://b", "eth_getLogs", log_entry_json(&principal_topic));
+    let client = EthRpcClient::new(
+        server,
+        vec!["https://a".to_string(), "https://b".to_string()],
+    );
+
+    let entries: Vec<LogEntry> =
+        futures::executor::block_on(client.eth_get_logs(get_logs_param())).unwrap();
+    assert_eq!(entries.len(), 1);
+    let event = ReceivedEthEvent::try_from(entries[0].clone()).unwrap();
+    assert_eq!(event.principal, principal(1));
+    assert_eq!(event.value, 10_000_000_000_000_000);
+}
+
+#[test]
+fn should_fail_when_providers_disagree() {
+    let server = MockJsonRpcServer::default()
+        .reply(
+            "https://a",
+            "eth_getLogs",
+            log_entry_json(&Hash(crate::deposit::encode_principal(&principal(1))).to_string()),
+        )
+        .reply(
+            "https://b",
+            "eth_getLogs",
+            log_entry_json(&Hash(crate::deposit::encode_principal(&principal(2))).to_string()),
+        );
+    let client = EthRpcClient::new(
+        server,
+        vec!["https://a".to_string(), "https://b".to_string()],
+    );
+
+    assert!(matches!(
+        futures::executor::block_on(client.eth_get_logs(get_logs_param())),
+        Err(MultiCallError::InconsistentResults(_))
+    ));
+}
+
+#[test]
+fn should_take_lowest_block_from_providers() {
+    let server = MockJsonRpcServer::default()
+        .reply(
+            "https://a",
+            "eth_getBlockByNumber",
+            serde_json::json!({"number": "0x3ca4f0", "baseFeePerGas": "0x2540be400"}),
+        )
+        .reply(
+            "https://b",
+            "eth_getBlockByNumber",
+            serde_json::json!({"number": "0x3ca4ef", "baseFeePerGas": "0x2540be401"}),
+        );
+    let client = EthRpcClient::new(
+        server,
+        vec!["https://a".to_string(), "https://b".to_string()],
+    );
+
+    let block = futures::executor::block_on(
+        client.eth_get_block_by_number(BlockSpec::Tag(BlockTag::Finalized)),
+    )
+    .unwrap();
+    assert_eq!(block.number, Quantity(0x3ca4ef));
+    assert_eq!(block.base_fee_per_gas, Quantity(10_000_000_001));
+}
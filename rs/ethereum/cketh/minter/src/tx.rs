@@ -0,0 +1,247 @@
+//! EIP-1559 transactions, see https://eips.ethereum.org/EIPS/eip-1559.
+
+use crate::address::Address;
+use crate::eth_rpc::Hash;
+use crate::keccak256;
+use crate::rlp::RlpList;
+use candid::CandidType;
+use k256::ecdsa::{recoverable, Signature, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+/// The EIP-2718 type of EIP-1559 transactions.
+const EIP1559_TX_ID: u8 = 2;
+
+/// The gas limit of a plain ETH transfer to an externally owned account.
+pub const TRANSFER_GAS_LIMIT: u128 = 21_000;
+
+/// An unsigned EIP-1559 transaction.  The access list is always empty.
+#[derive(CandidType, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Eip1559TransactionRequest {
+    #[serde(rename = "chain_id")]
+    pub chain_id: u64,
+    #[serde(rename = "nonce")]
+    pub nonce: u64,
+    #[serde(rename = "max_priority_fee_per_gas")]
+    pub max_priority_fee_per_gas: u128,
+    #[serde(rename = "max_fee_per_gas")]
+    pub max_fee_per_gas: u128,
+    #[serde(rename = "gas_limit")]
+    pub gas_limit: u128,
+    #[serde(rename = "destination")]
+    pub destination: Address,
+    /// The transferred value in wei.
+    #[serde(rename = "amount")]
+    pub amount: u128,
+    #[serde(rename = "data")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub data: Vec<u8>,
+}
+
+impl Eip1559TransactionRequest {
+    fn rlp_fields(&self) -> RlpList {
+        let mut fields = RlpList::new();
+        fields
+            .append_uint(self.chain_id as u128)
+            .append_uint(self.nonce as u128)
+            .append_uint(self.max_priority_fee_per_gas)
+            .append_uint(self.max_fee_per_gas)
+            .append_uint(self.gas_limit)
+            .append_bytes(self.destination.as_bytes())
+            .append_uint(self.amount)
+            .append_bytes(&self.data)
+            .append_list(&RlpList::new());
+        fields
+    }
+
+    /// Returns the hash that the sender signs: keccak256(0x02 || rlp(fields)).
+    pub fn hash(&self) -> [u8; 32] {
+        let mut bytes = vec![EIP1559_TX_ID];
+        bytes.extend(self.rlp_fields().finish());
+        keccak256(&bytes)
+    }
+
+    /// Returns the maximum amount of wei that the transaction can burn in fees.
+    pub fn max_transaction_fee(&self) -> u128 {
+        self.max_fee_per_gas.saturating_mul(self.gas_limit)
+    }
+}
+
+/// The signature of an EIP-1559 transaction.
+#[derive(CandidType, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Eip1559Signature {
+    #[serde(rename = "y_parity")]
+    pub signature_y_parity: bool,
+    #[serde(rename = "r")]
+    pub r: [u8; 32],
+    #[serde(rename = "s")]
+    pub s: [u8; 32],
+}
+
+#[derive(CandidType, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedEip1559TransactionRequest {
+    #[serde(rename = "transaction")]
+    pub transaction: Eip1559TransactionRequest,
+    #[serde(rename = "signature")]
+    pub signature: Eip1559Signature,
+}
+
+impl SignedEip1559TransactionRequest {
+    /// Builds a signed transaction from a 64-byte `r || s` signature produced
+    /// by the threshold ECDSA API.  The recovery bit is determined by trying
+    /// both candidates against the signer's SEC1-encoded public key.
+    pub fn from_signature(
+        transaction: Eip1559TransactionRequest,
+        signature: &[u8],
+        sec1_pubkey: &[u8],
+    ) -> Result<Self, String> {
+        let signature = Signature::try_from(signature)
+            .map_err(|e| format!("failed to parse the signature: {}", e))?;
+        // Ethereum only accepts signatures with a low s value.
+        let signature = signature.normalize_s().unwrap_or(signature);
+        let pubkey = VerifyingKey::from_sec1_bytes(sec1_pubkey)
+            .map_err(|e| format!("failed to parse the public key: {}", e))?;
+        let digest = transaction.hash();
+
+        let y_parity = [0u8, 1]
+            .into_iter()
+            .find(|id| {
+                recoverable::Id::new(*id)
+                    .and_then(|id| recoverable::Signature::new(&signature, id))
+                    .and_then(|sig| {
+                        sig.recover_verifying_key_from_digest_bytes(k256::FieldBytes::from_slice(
+                            &digest,
+                        ))
+                    })
+                    .map(|recovered| recovered == pubkey)
+                    .unwrap_or(false)
+            })
+            .ok_or_else(|| "the signature does not match the public key".to_string())?;
+
+        let bytes = signature.to_bytes();
+        let mut r = [0u8; 32];
+        let mut s = [0u8; 32];
+        r.copy_from_slice(&bytes[..32]);
+        s.copy_from_slice(&bytes[32..]);
+
+        Ok(Self {
+            transaction,
+            signature: Eip1559Signature {
+                signature_y_parity: y_parity == 1,
+                r,
+                s,
+            },
+        })
+    }
+
+    /// Returns the serialized transaction: 0x02 || rlp(fields || y_parity, r, s).
+    pub fn raw_bytes(&self) -> Vec<u8> {
+        let mut fields = self.transaction.rlp_fields();
+        fields
+            .append_uint(self.signature.signature_y_parity as u128)
+            .append_uint256(&self.signature.r)
+            .append_uint256(&self.signature.s);
+
+        let mut bytes = vec![EIP1559_TX_ID];
+        bytes.extend(fields.finish());
+        bytes
+    }
+
+    /// Returns the serialized transaction as a 0x-prefixed hex string, the
+    /// format that eth_sendRawTransaction expects.
+    pub fn raw_transaction_hex(&self) -> String {
+        format!("0x{}", hex::encode(self.raw_bytes()))
+    }
+
+    /// Returns the transaction hash, i.e. keccak256 of the raw transaction.
+    pub fn hash(&self) -> Hash {
+        Hash(keccak256(&self.raw_bytes()))
+    }
+
+    pub fn nonce(&self) -> u64 {
+        self.transaction.nonce
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k256::ecdsa::signature::hazmat::PrehashSigner;
+    use k256::ecdsa::SigningKey;
+    use std::str::FromStr;
+
+    fn transaction() -> Eip1559TransactionRequest {
+        Eip1559TransactionRequest {
+            chain_id: 11155111,
+            nonce: 7,
+            max_priority_fee_per_gas: 1_500_000_000,
+            max_fee_per_gas: 40_000_000_000,
+            gas_limit: TRANSFER_GAS_LIMIT,
+            destination: Address::from_str("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").unwrap(),
+            amount: 1_000_000_000_000_000,
+            data: vec![],
+        }
+    }
+
+    #[test]
+    fn should_encode_unsigned_transaction_fields() {
+        let tx = transaction();
+        let mut expected = vec![0xf2];
+        // chain id 11155111 = 0xaa36a7
+        expected.extend([0x83, 0xaa, 0x36, 0xa7]);
+        expected.push(0x07);
+        expected.extend([0x84, 0x59, 0x68, 0x2f, 0x00]);
+        expected.extend([0x85, 0x09, 0x50, 0x2f, 0x90, 0x00]);
+        expected.extend([0x82, 0x52, 0x08]);
+        expected.push(0x94);
+        expected.extend(tx.destination.as_bytes());
+        expected.extend([0x87, 0x03, 0x8d, 0x7e, 0xa4, 0xc6, 0x80, 0x00]);
+        expected.extend([0x80, 0xc0]);
+        assert_eq!(tx.rlp_fields().finish(), expected);
+    }
+
+    #[test]
+    fn should_sign_transaction_with_recoverable_signature() {
+        let signing_key = SigningKey::from_bytes(&[0x2a; 32]).unwrap();
+        let pubkey = VerifyingKey::from(&signing_key).to_encoded_point(true);
+
+        let tx = transaction();
+        let signature: Signature = signing_key.sign_prehash(&tx.hash()).unwrap();
+        let signed = SignedEip1559TransactionRequest::from_signature(
+            tx.clone(),
+            &signature.to_bytes(),
+            pubkey.as_bytes(),
+        )
+        .unwrap();
+
+        let recovered = recoverable::Signature::new(
+            &Signature::try_from([signed.signature.r, signed.signature.s].concat().as_slice())
+                .unwrap(),
+            recoverable::Id::new(signed.signature.signature_y_parity as u8).unwrap(),
+        )
+        .unwrap()
+        .recover_verifying_key_from_digest_bytes(k256::FieldBytes::from_slice(&tx.hash()))
+        .unwrap();
+        assert_eq!(recovered, VerifyingKey::from(&signing_key));
+
+        let raw = signed.raw_bytes();
+        assert_eq!(raw[0], EIP1559_TX_ID);
+        assert_eq!(signed.hash(), Hash(keccak256(&raw)));
+        assert!(signed.raw_transaction_hex().starts_with("0x02"));
+    }
+
+    #[test]
+    fn should_reject_signature_from_another_key() {
+        let signing_key = SigningKey::from_bytes(&[0x2a; 32]).unwrap();
+        let other_key = SigningKey::from_bytes(&[0x2b; 32]).unwrap();
+        let other_pubkey = VerifyingKey::from(&other_key).to_encoded_point(true);
+
+        let tx = transaction();
+        let signature: Signature = signing_key.sign_prehash(&tx.hash()).unwrap();
+        assert!(SignedEip1559TransactionRequest::from_signature(
+            tx,
+            &signature.to_bytes(),
+            other_pubkey.as_bytes()
+        )
+        .is_err());
+    }
+}
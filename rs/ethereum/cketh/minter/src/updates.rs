@@ -0,0 +1,3 @@
+pub mod get_withdrawal_account;
+pub mod minter_address;
+pub mod withdraw_eth;
@@ -0,0 +1,34 @@
+use ic_base_types::PrincipalId;
+use ic_crypto_sha::Sha256;
+use ic_icrc1::{Account, Subaccount, DEFAULT_SUBACCOUNT};
+
+/// Deterministically computes a ckETH Ledger account ID based on the ckETH Minter’s principal ID and the caller’s principal ID.
+pub fn get_withdrawal_account() -> Account {
+    let caller = PrincipalId(ic_cdk::caller());
+    let cketh_principal = PrincipalId(ic_cdk::id());
+    let caller_subaccount: Subaccount = compute_subaccount(caller, 0);
+    // Check that the computed subaccount doesn't collide with minting account.
+    if &caller_subaccount == DEFAULT_SUBACCOUNT {
+        panic!(
+            "Subaccount collision with principal {}. Please contact DFINITY support.",
+            caller
+        );
+    }
+    Account {
+        owner: cketh_principal,
+        subaccount: Some(caller_subaccount),
+    }
+}
+
+/// Compute the subaccount of a principal based on a given nonce.
+pub fn compute_subaccount(controller: PrincipalId, nonce: u64) -> Subaccount {
+    const DOMAIN: &[u8] = b"cketh";
+    const DOMAIN_LENGTH: [u8; 1] = [0x05];
+
+    let mut hasher = Sha256::new();
+    hasher.write(&DOMAIN_LENGTH);
+    hasher.write(DOMAIN);
+    hasher.write(controller.as_slice());
+    hasher.write(&nonce.to_be_bytes());
+    hasher.finish()
+}
@@ -0,0 +1,29 @@
+use crate::address::Address;
+use crate::logs::P1;
+use crate::management;
+use crate::state::{mutate_state, read_state};
+use ic_canister_log::log;
+
+/// Initializes the minter ECDSA public key and returns the Ethereum address
+/// of the minter.  The minter uses the key at the empty derivation path.
+pub async fn init_ecdsa_public_key() -> Result<Address, String> {
+    if let Some(pubkey) = read_state(|s| s.ecdsa_public_key.clone()) {
+        return Address::from_pubkey(&pubkey);
+    }
+    let key_name = read_state(|s| s.ecdsa_key_name.clone());
+    log!(P1, "Fetching the ECDSA public key {}", &key_name);
+    let pubkey = management::ecdsa_public_key(key_name, vec![])
+        .await
+        .map_err(|e| e.to_string())?;
+    let address = Address::from_pubkey(&pubkey)?;
+    mutate_state(|s| s.ecdsa_public_key = Some(pubkey));
+    Ok(address)
+}
+
+/// Returns the Ethereum address controlled by the minter.
+pub async fn minter_address() -> String {
+    init_ecdsa_public_key()
+        .await
+        .unwrap_or_else(|e| ic_cdk::trap(&format!("failed to get the minter address: {}", e)))
+        .to_string()
+}
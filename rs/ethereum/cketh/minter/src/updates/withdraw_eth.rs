@@ -0,0 +1,188 @@
+use crate::address::Address;
+use crate::cketh_units_to_wei;
+use crate::guard::{retrieve_eth_guard, GuardError};
+use crate::logs::P1;
+use crate::state::{audit, mutate_state, read_state, RetrieveEthStatus};
+use crate::withdraw::EthWithdrawalRequest;
+use candid::{CandidType, Deserialize, Nat, Principal};
+use ic_base_types::PrincipalId;
+use ic_canister_log::log;
+use ic_icrc1::{
+    endpoints::{TransferArg, TransferError},
+    Account,
+};
+use ic_icrc1_client_cdk::{CdkRuntime, ICRC1Client};
+use num_traits::cast::ToPrimitive;
+use std::str::FromStr;
+
+use super::get_withdrawal_account::compute_subaccount;
+use super::minter_address::init_ecdsa_public_key;
+
+const MAX_CONCURRENT_PENDING_REQUESTS: usize = 1000;
+
+#[derive(CandidType, Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct WithdrawalArg {
+    // amount to withdraw in ckETH ledger units (10^-8 ETH)
+    pub amount: u64,
+
+    // Ethereum address where to send ETH
+    pub recipient: String,
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct RetrieveEthRequest {
+    // the index of the burn block on the ckETH ledger
+    pub block_index: u64,
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, PartialEq, Eq)]
+pub enum WithdrawalError {
+    /// There is another request for this principal.
+    AlreadyProcessing,
+
+    /// The withdrawal amount is too low.
+    AmountTooLow { min_withdrawal_amount: u64 },
+
+    /// The Ethereum address is not valid.
+    MalformedAddress(String),
+
+    /// The withdrawal account does not hold the requested ckETH amount.
+    InsufficientFunds { balance: u64 },
+
+    /// There are too many concurrent requests, retry later.
+    TemporarilyUnavailable(String),
+}
+
+impl From<GuardError> for WithdrawalError {
+    fn from(e: GuardError) -> Self {
+        match e {
+            GuardError::AlreadyProcessing => Self::AlreadyProcessing,
+            GuardError::TooManyConcurrentRequests => {
+                Self::TemporarilyUnavailable("too many concurrent requests".to_string())
+            }
+        }
+    }
+}
+
+pub async fn withdraw_eth(args: WithdrawalArg) -> Result<RetrieveEthRequest, WithdrawalError> {
+    let caller = ic_cdk::caller();
+
+    let destination =
+        Address::from_str(&args.recipient).map_err(WithdrawalError::MalformedAddress)?;
+    if destination == Address::ZERO {
+        return Err(WithdrawalError::MalformedAddress(
+            "the zero address cannot receive ETH".to_string(),
+        ));
+    }
+
+    let _guard = retrieve_eth_guard(caller)?;
+    let min_amount = read_state(|s| s.minimum_withdrawal_amount);
+    if args.amount < min_amount {
+        return Err(WithdrawalError::AmountTooLow {
+            min_withdrawal_amount: min_amount,
+        });
+    }
+
+    let minter_address = init_ecdsa_public_key()
+        .await
+        .map_err(WithdrawalError::TemporarilyUnavailable)?;
+    if destination == minter_address {
+        ic_cdk::trap("illegal withdraw_eth target");
+    }
+
+    if read_state(|s| s.pending_withdrawal_requests.len() >= MAX_CONCURRENT_PENDING_REQUESTS) {
+        return Err(WithdrawalError::TemporarilyUnavailable(
+            "too many pending withdrawal requests".to_string(),
+        ));
+    }
+
+    let block_index = burn_cketh(caller, args.amount).await?;
+    let request = EthWithdrawalRequest {
+        withdrawal_amount: cketh_units_to_wei(args.amount),
+        destination,
+        ledger_burn_index: block_index,
+        from: caller,
+        created_at: ic_cdk::api::time(),
+    };
+
+    log!(
+        P1,
+        "accepted a withdrawal request for {} wei to address {} (block_index = {})",
+        request.withdrawal_amount,
+        destination,
+        block_index
+    );
+
+    mutate_state(|s| audit::accept_withdrawal_request(s, request));
+
+    assert_eq!(
+        RetrieveEthStatus::Pending,
+        read_state(|s| s.retrieve_eth_status(block_index))
+    );
+
+    Ok(RetrieveEthRequest { block_index })
+}
+
+async fn burn_cketh(user: Principal, amount: u64) -> Result<u64, WithdrawalError> {
+    let client = ICRC1Client {
+        runtime: CdkRuntime,
+        ledger_canister_id: read_state(|s| s.ledger_id.get().into()),
+    };
+    let minter = PrincipalId(ic_cdk::id());
+    let from_subaccount = compute_subaccount(PrincipalId(user), 0);
+    let result = client
+        .transfer(TransferArg {
+            from_subaccount: Some(from_subaccount),
+            to: Account {
+                owner: minter,
+                subaccount: None,
+            },
+            fee: None,
+            created_at_time: None,
+            memo: None,
+            amount: Nat::from(amount),
+        })
+        .await
+        .map_err(|(code, msg)| {
+            WithdrawalError::TemporarilyUnavailable(format!(
+                "cannot enqueue a burn transaction: {} (reject_code = {})",
+                msg, code
+            ))
+        })?;
+
+    match result {
+        Ok(block_index) => Ok(block_index),
+        Err(TransferError::InsufficientFunds { balance }) => Err(WithdrawalError::InsufficientFunds {
+            balance: balance.0.to_u64().expect("unreachable: ledger balance does not fit into u64")
+        }),
+        Err(TransferError::TemporarilyUnavailable) => {
+            Err(WithdrawalError::TemporarilyUnavailable(
+                "cannot burn ckETH: the ledger is busy".to_string(),
+            ))
+        }
+        Err(TransferError::GenericError { error_code, message }) => {
+            Err(WithdrawalError::TemporarilyUnavailable(format!(
+                "cannot burn ckETH: the ledger fails with: {} (error code {})", message, error_code
+            )))
+        }
+        Err(TransferError::BadFee { expected_fee }) => ic_cdk::trap(&format!(
+            "unreachable: the ledger demands the fee of {} even though the fee field is unset",
+            expected_fee
+        )),
+        Err(TransferError::Duplicate{ duplicate_of }) => ic_cdk::trap(&format!(
+            "unreachable: the ledger reports duplicate ({}) even though the create_at_time field is unset",
+            duplicate_of
+        )),
+        Err(TransferError::CreatedInFuture{..}) => ic_cdk::trap(
+            "unreachable: the ledger reports CreatedInFuture even though the create_at_time field is unset"
+        ),
+        Err(TransferError::TooOld) => ic_cdk::trap(
+            "unreachable: the ledger reports TooOld even though the create_at_time field is unset"
+        ),
+        Err(TransferError::BadBurn { min_burn_amount }) => ic_cdk::trap(&format!(
+            "the minter is misconfigured: minimum_withdrawal_amount {} is less than ledger's min_burn_amount {}",
+            read_state(|s| s.minimum_withdrawal_amount),
+            min_burn_amount
+        )),
+    }
+}
@@ -0,0 +1,618 @@
+//! Processing of withdrawal requests: the minter builds an EIP-1559
+//! transaction for each request, signs it with threshold ECDSA, sends it to
+//! the network and waits until the transaction is finalized.  If the base fee
+//! rises above the fee of a sent transaction, the minter replaces it with a
+//! transaction with the same nonce and a higher fee.  If the amount of a
+//! request does not cover the fee until the request expires, the minter
+//! returns the burnt ckETH.
+
+use crate::address::Address;
+use crate::eth_rpc::{BlockSpec, BlockTag, Hash};
+use crate::eth_rpc_client::EthRpcClient;
+use crate::guard::{TaskType, TimerGuard};
+use crate::logs::{P0, P1};
+use crate::management;
+use crate::state::{audit, mutate_state, read_state};
+use crate::tx::{Eip1559TransactionRequest, SignedEip1559TransactionRequest, TRANSFER_GAS_LIMIT};
+use crate::updates::minter_address::init_ecdsa_public_key;
+use crate::wei_to_cketh_units;
+use candid::{CandidType, Nat, Principal};
+use ic_canister_log::log;
+use ic_icrc1::{endpoints::TransferArg, Account};
+use ic_icrc1_client_cdk::{CdkRuntime, ICRC1Client};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// The tip that the minter offers to block proposers, in wei per gas
+/// (1.5 gwei).
+pub const MAX_PRIORITY_FEE_PER_GAS: u128 = 1_500_000_000;
+
+/// The maximum number of transactions that the minter signs per run.
+const MAX_TRANSACTIONS_PER_RUN: usize = 5;
+
+/// The time after which the minter reimburses a withdrawal request whose
+/// amount does not cover the transaction fee.
+pub const WITHDRAWAL_REQUEST_EXPIRY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// A request to withdraw ETH, created after the minter burnt ckETH.
+#[derive(CandidType, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EthWithdrawalRequest {
+    /// The amount to withdraw in wei.  The transaction fee is deducted from
+    /// this amount.
+    #[serde(rename = "withdrawal_amount")]
+    pub withdrawal_amount: u128,
+    #[serde(rename = "destination")]
+    pub destination: Address,
+    /// The index of the burn transaction on the ckETH ledger.  Also serves as
+    /// the withdrawal identifier.
+    #[serde(rename = "ledger_burn_index")]
+    pub ledger_burn_index: u64,
+    #[serde(rename = "from")]
+    pub from: Principal,
+    /// The IC time at which the minter accepted the request.
+    #[serde(rename = "created_at")]
+    pub created_at: u64,
+}
+
+/// Fee parameters of the next transactions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TransactionPrice {
+    pub max_fee_per_gas: u128,
+    pub max_priority_fee_per_gas: u128,
+}
+
+impl TransactionPrice {
+    /// Estimates the transaction price from the base fee of the latest block.
+    /// The max fee per gas is twice the base fee plus the tip, which keeps
+    /// the transaction includable for six consecutive full blocks.
+    pub fn from_base_fee(base_fee_per_gas: u128) -> Self {
+        Self {
+            max_fee_per_gas: base_fee_per_gas
+                .saturating_mul(2)
+                .saturating_add(MAX_PRIORITY_FEE_PER_GAS),
+            max_priority_fee_per_gas: MAX_PRIORITY_FEE_PER_GAS,
+        }
+    }
+}
+
+/// Builds the transaction for a withdrawal request.  Returns None if the
+/// withdrawal amount does not cover the maximum transaction fee.
+pub fn create_transaction(
+    request: &EthWithdrawalRequest,
+    nonce: u64,
+    chain_id: u64,
+    price: TransactionPrice,
+) -> Option<Eip1559TransactionRequest> {
+    let max_transaction_fee = price.max_fee_per_gas.saturating_mul(TRANSFER_GAS_LIMIT);
+    let amount = request.withdrawal_amount.checked_sub(max_transaction_fee)?;
+    if amount == 0 {
+        return None;
+    }
+    Some(Eip1559TransactionRequest {
+        chain_id,
+        nonce,
+        max_priority_fee_per_gas: price.max_priority_fee_per_gas,
+        max_fee_per_gas: price.max_fee_per_gas,
+        gas_limit: TRANSFER_GAS_LIMIT,
+        destination: request.destination,
+        amount,
+        data: vec![],
+    })
+}
+
+/// Increases a fee by the minimum amount that Ethereum nodes accept for a
+/// transaction replacing another transaction with the same nonce (10%).
+fn bump_fee(fee: u128) -> u128 {
+    fee.saturating_add(fee.saturating_add(9) / 10)
+}
+
+/// Builds a transaction that replaces `previous`: it has the same nonce and
+/// offers the current price, but at least 10% more than `previous`.  Returns
+/// None if the withdrawal amount does not cover the new maximum transaction
+/// fee.
+pub fn create_replacement_transaction(
+    request: &EthWithdrawalRequest,
+    previous: &Eip1559TransactionRequest,
+    price: TransactionPrice,
+) -> Option<Eip1559TransactionRequest> {
+    let price = TransactionPrice {
+        max_fee_per_gas: price
+            .max_fee_per_gas
+            .max(bump_fee(previous.max_fee_per_gas)),
+        max_priority_fee_per_gas: price
+            .max_priority_fee_per_gas
+            .max(bump_fee(previous.max_priority_fee_per_gas)),
+    };
+    create_transaction(request, previous.nonce, previous.chain_id, price)
+}
+
+/// Returns true if the withdrawal request is older than
+/// [WITHDRAWAL_REQUEST_EXPIRY] at the specified IC time.
+pub fn is_expired(request: &EthWithdrawalRequest, now: u64) -> bool {
+    now.saturating_sub(request.created_at) >= WITHDRAWAL_REQUEST_EXPIRY.as_nanos() as u64
+}
+
+/// Estimates the price of the next transactions from the latest block.
+async fn estimate_transaction_price(client: &EthRpcClient) -> Option<TransactionPrice> {
+    match client
+        .eth_get_block_by_number(BlockSpec::Tag(BlockTag::Latest))
+        .await
+    {
+        Ok(block) => Some(TransactionPrice::from_base_fee(block.base_fee_per_gas.0)),
+        Err(e) => {
+            log!(
+                P0,
+                "[estimate_transaction_price]: failed to get the latest block: {:?}",
+                e
+            );
+            None
+        }
+    }
+}
+
+/// Signs a transaction with the minter's threshold ECDSA key.
+async fn sign_transaction(
+    transaction: Eip1559TransactionRequest,
+) -> Result<SignedEip1559TransactionRequest, String> {
+    let (key_name, pubkey) = read_state(|s| {
+        (
+            s.ecdsa_key_name.clone(),
+            s.ecdsa_public_key
+                .clone()
+                .expect("BUG: the ECDSA public key must be initialized"),
+        )
+    });
+    let signature = management::sign_with_ecdsa(key_name, vec![], transaction.hash())
+        .await
+        .map_err(|e| format!("failed to sign the transaction: {}", e))?;
+    SignedEip1559TransactionRequest::from_signature(transaction, &signature, &pubkey)
+        .map_err(|e| format!("invalid signature: {}", e))
+}
+
+/// Creates and signs transactions for pending withdrawal requests.  Requests
+/// whose amount does not cover the transaction fee for
+/// [WITHDRAWAL_REQUEST_EXPIRY] expire, and the minter returns the burnt
+/// ckETH later.
+async fn sign_pending_requests(price: TransactionPrice) {
+    let requests: Vec<_> = read_state(|s| {
+        s.pending_withdrawal_requests
+            .iter()
+            .take(MAX_TRANSACTIONS_PER_RUN)
+            .cloned()
+            .collect()
+    });
+    let chain_id = read_state(|s| s.ethereum_network.chain_id());
+
+    for request in requests {
+        let nonce = read_state(|s| s.next_transaction_nonce);
+        let transaction = match create_transaction(&request, nonce, chain_id, price) {
+            Some(tx) => tx,
+            None => {
+                if is_expired(&request, ic_cdk::api::time()) {
+                    log!(
+                        P1,
+                        "[sign_pending_requests]: withdrawal {} of {} wei did not cover the transaction fee before it expired, reimbursing",
+                        request.ledger_burn_index,
+                        request.withdrawal_amount
+                    );
+                    mutate_state(|s| {
+                        audit::expire_withdrawal_request(s, request.ledger_burn_index)
+                    });
+                } else {
+                    log!(
+                        P1,
+                        "[sign_pending_requests]: withdrawal {} of {} wei does not cover the maximum fee of {} wei per gas, retrying later",
+                        request.ledger_burn_index,
+                        request.withdrawal_amount,
+                        price.max_fee_per_gas
+                    );
+                }
+                continue;
+            }
+        };
+
+        match sign_transaction(transaction).await {
+            Ok(signed) => {
+                log!(
+                    P1,
+                    "[sign_pending_requests]: signed transaction {} with nonce {} for withdrawal {}",
+                    signed.hash(),
+                    nonce,
+                    request.ledger_burn_index
+                );
+                mutate_state(|s| {
+                    audit::record_signed_transaction(s, request.ledger_burn_index, signed)
+                });
+            }
+            Err(e) => {
+                log!(
+                    P0,
+                    "[sign_pending_requests]: failed to sign the transaction for withdrawal {}: {}",
+                    request.ledger_burn_index,
+                    e
+                );
+                return;
+            }
+        }
+    }
+}
+
+/// Replaces the sent transactions that are not mined yet and offer less
+/// than the current price with transactions with the same nonce and a higher
+/// fee.  Otherwise a base fee increase would stall the transaction and all
+/// transactions with a higher nonce.
+async fn resubmit_transactions(
+    client: &EthRpcClient,
+    minter_address: Address,
+    price: TransactionPrice,
+) {
+    if read_state(|s| s.sent_transactions.is_empty()) {
+        return;
+    }
+    let mined_tx_count = match client
+        .eth_get_transaction_count(minter_address, BlockTag::Latest)
+        .await
+        .map_err(|e| format!("{:?}", e))
+        .and_then(|count| count.as_u64())
+    {
+        Ok(count) => count,
+        Err(e) => {
+            log!(
+                P0,
+                "[resubmit_transactions]: failed to get the latest transaction count: {}",
+                e
+            );
+            return;
+        }
+    };
+
+    let stuck: Vec<_> = read_state(|s| {
+        s.sent_transactions
+            .iter()
+            .filter(|(_, sent)| {
+                sent.transaction.nonce() >= mined_tx_count
+                    && sent.transaction.transaction.max_fee_per_gas < price.max_fee_per_gas
+            })
+            .take(MAX_TRANSACTIONS_PER_RUN)
+            .map(|(id, sent)| (*id, sent.clone()))
+            .collect()
+    });
+    for (withdrawal_id, sent) in stuck {
+        let transaction = match create_replacement_transaction(
+            &sent.request,
+            &sent.transaction.transaction,
+            price,
+        ) {
+            Some(tx) => tx,
+            None => {
+                log!(
+                    P1,
+                    "[resubmit_transactions]: withdrawal {} of {} wei does not cover a higher fee than transaction {}, waiting for the base fee to drop",
+                    withdrawal_id,
+                    sent.request.withdrawal_amount,
+                    sent.transaction.hash()
+                );
+                continue;
+            }
+        };
+
+        match sign_transaction(transaction).await {
+            Ok(signed) => {
+                log!(
+                    P1,
+                    "[resubmit_transactions]: replaced transaction {} of withdrawal {} with transaction {}",
+                    sent.transaction.hash(),
+                    withdrawal_id,
+                    signed.hash()
+                );
+                mutate_state(|s| audit::replace_transaction(s, withdrawal_id, signed));
+            }
+            Err(e) => {
+                log!(
+                    P0,
+                    "[resubmit_transactions]: failed to sign the replacement for withdrawal {}: {}",
+                    withdrawal_id,
+                    e
+                );
+                return;
+            }
+        }
+    }
+}
+
+/// Broadcasts all signed transactions that are not finalized yet.  Sending a
+/// transaction that is already in the mempool or mined is harmless.
+async fn send_transactions(client: &EthRpcClient) {
+    let transactions: Vec<_> = read_state(|s| {
+        s.sent_transactions
+            .values()
+            .map(|sent| sent.transaction.clone())
+            .collect()
+    });
+    for transaction in transactions {
+        match client
+            .eth_send_raw_transaction(transaction.raw_transaction_hex())
+            .await
+        {
+            Ok(hash) => log!(P1, "[send_transactions]: sent transaction {}", hash),
+            Err(e) => log!(
+                P1,
+                "[send_transactions]: failed to send transaction {}: {:?}",
+                transaction.hash(),
+                e
+            ),
+        }
+    }
+}
+
+/// Records the receipts of sent transactions that are in finalized blocks.
+async fn finalize_transactions(client: &EthRpcClient, minter_address: Address) {
+    if read_state(|s| s.sent_transactions.is_empty()) {
+        return;
+    }
+    let finalized_tx_count = match client
+        .eth_get_transaction_count(minter_address, BlockTag::Finalized)
+        .await
+        .map_err(|e| format!("{:?}", e))
+        .and_then(|count| count.as_u64())
+    {
+        Ok(count) => count,
+        Err(e) => {
+            log!(
+                P0,
+                "[finalize_transactions]: failed to get the finalized transaction count: {}",
+                e
+            );
+            return;
+        }
+    };
+
+    let finalized: Vec<_> = read_state(|s| {
+        s.sent_transactions
+            .iter()
+            .filter(|(_, sent)| sent.transaction.nonce() < finalized_tx_count)
+            .map(|(id, sent)| {
+                (
+                    *id,
+                    sent.transactions().map(|tx| tx.hash()).collect::<Vec<_>>(),
+                )
+            })
+            .collect()
+    });
+    for (withdrawal_id, hashes) in finalized {
+        finalize_transaction(client, withdrawal_id, hashes).await;
+    }
+}
+
+/// Looks for the receipt of the transaction that consumed the finalized
+/// nonce of a withdrawal among all transactions with that nonce.
+async fn finalize_transaction(client: &EthRpcClient, withdrawal_id: u64, hashes: Vec<Hash>) {
+    for hash in hashes {
+        match client.eth_get_transaction_receipt(hash).await {
+            Ok(Some(receipt)) => {
+                log!(
+                    P1,
+                    "[finalize_transactions]: transaction {} for withdrawal {} is finalized with status {}",
+                    hash,
+                    withdrawal_id,
+                    receipt.status.0
+                );
+                mutate_state(|s| audit::record_finalized_transaction(s, withdrawal_id, receipt));
+                return;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                log!(
+                    P0,
+                    "[finalize_transactions]: failed to get the receipt of transaction {}: {:?}",
+                    hash,
+                    e
+                );
+                return;
+            }
+        }
+    }
+    log!(
+        P0,
+        "[finalize_transactions]: no receipt for the transactions of withdrawal {} with a finalized nonce",
+        withdrawal_id
+    );
+}
+
+/// Returns the burnt ckETH of expired withdrawal requests to the account of
+/// the user who made the request.
+async fn reimburse_withdrawals() {
+    let (ledger_id, requests) = read_state(|s| {
+        (
+            s.ledger_id,
+            s.withdrawals_to_reimburse
+                .values()
+                .cloned()
+                .collect::<Vec<_>>(),
+        )
+    });
+    if requests.is_empty() {
+        return;
+    }
+    let client = ICRC1Client {
+        runtime: CdkRuntime,
+        ledger_canister_id: ledger_id.get().into(),
+    };
+
+    for request in requests {
+        let amount = wei_to_cketh_units(request.withdrawal_amount);
+        let result = client
+            .transfer(TransferArg {
+                from_subaccount: None,
+                to: Account {
+                    owner: request.from.into(),
+                    subaccount: None,
+                },
+                fee: None,
+                created_at_time: None,
+                memo: None,
+                amount: Nat::from(amount),
+            })
+            .await;
+        match result {
+            Ok(Ok(block_index)) => {
+                log!(
+                    P1,
+                    "[reimburse_withdrawals]: returned {} ckETH units to {} for withdrawal {} (block index {})",
+                    amount,
+                    request.from,
+                    request.ledger_burn_index,
+                    block_index
+                );
+                mutate_state(|s| {
+                    audit::reimburse_withdrawal(s, request.ledger_burn_index, block_index)
+                });
+            }
+            Ok(Err(err)) => {
+                log!(
+                    P0,
+                    "[reimburse_withdrawals]: the ledger rejected the refund of withdrawal {}: {:?}",
+                    request.ledger_burn_index,
+                    err
+                );
+            }
+            Err((code, msg)) => {
+                log!(
+                    P0,
+                    "[reimburse_withdrawals]: failed to call the ledger for withdrawal {}: {} (reject_code = {})",
+                    request.ledger_burn_index,
+                    msg,
+                    code
+                );
+                return;
+            }
+        }
+    }
+}
+
+/// Signs, replaces, sends and finalizes withdrawal transactions, and
+/// reimburses expired withdrawal requests.
+pub async fn process_retrieve_eth_requests() {
+    let _guard = match TimerGuard::new(TaskType::RetrieveEth) {
+        Some(guard) => guard,
+        None => return,
+    };
+    reimburse_withdrawals().await;
+    if read_state(|s| s.pending_withdrawal_requests.is_empty() && s.sent_transactions.is_empty()) {
+        return;
+    }
+
+    let minter_address = match init_ecdsa_public_key().await {
+        Ok(address) => address,
+        Err(e) => {
+            log!(
+                P0,
+                "[process_retrieve_eth_requests]: failed to fetch the ECDSA public key: {}",
+                e
+            );
+            return;
+        }
+    };
+    let client = read_state(|s| EthRpcClient::for_network(s.ethereum_network));
+
+    if let Some(price) = estimate_transaction_price(&client).await {
+        sign_pending_requests(price).await;
+        resubmit_transactions(&client, minter_address, price).await;
+    }
+    send_transactions(&client).await;
+    finalize_transactions(&client, minter_address).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn request(withdrawal_amount: u128) -> EthWithdrawalRequest {
+        EthWithdrawalRequest {
+            withdrawal_amount,
+            destination: Address::from_str("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").unwrap(),
+            ledger_burn_index: 3,
+            from: Principal::from_text("2chl6-4hpzw-vqaaa-aaaaa-c").unwrap(),
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn should_deduct_max_fee_from_withdrawal_amount() {
+        let price = TransactionPrice::from_base_fee(10_000_000_000);
+        assert_eq!(price.max_fee_per_gas, 21_500_000_000);
+
+        let tx = create_transaction(&request(1_000_000_000_000_000), 7, 1, price).unwrap();
+        assert_eq!(tx.nonce, 7);
+        assert_eq!(tx.chain_id, 1);
+        assert_eq!(tx.gas_limit, TRANSFER_GAS_LIMIT);
+        assert_eq!(tx.amount, 1_000_000_000_000_000 - 21_500_000_000 * 21_000);
+        assert_eq!(tx.amount + tx.max_transaction_fee(), 1_000_000_000_000_000);
+    }
+
+    #[test]
+    fn should_not_create_transaction_if_fee_exceeds_amount() {
+        let price = TransactionPrice::from_base_fee(10_000_000_000);
+        let max_fee = price.max_fee_per_gas * TRANSFER_GAS_LIMIT;
+        assert_eq!(create_transaction(&request(max_fee), 0, 1, price), None);
+        assert_eq!(create_transaction(&request(max_fee - 1), 0, 1, price), None);
+        assert!(create_transaction(&request(max_fee + 1), 0, 1, price).is_some());
+    }
+
+    #[test]
+    fn should_replace_transaction_with_same_nonce_and_higher_fees() {
+        let previous = create_transaction(
+            &request(1_000_000_000_000_000),
+            7,
+            1,
+            TransactionPrice::from_base_fee(10_000_000_000),
+        )
+        .unwrap();
+
+        // The base fee barely rose: the replacement still offers 10% more.
+        let price = TransactionPrice::from_base_fee(10_000_000_001);
+        let replacement =
+            create_replacement_transaction(&request(1_000_000_000_000_000), &previous, price)
+                .unwrap();
+        assert_eq!(replacement.nonce, 7);
+        assert_eq!(replacement.chain_id, 1);
+        assert_eq!(replacement.max_fee_per_gas, 23_650_000_000);
+        assert_eq!(replacement.max_priority_fee_per_gas, 1_650_000_000);
+        assert_eq!(
+            replacement.amount + replacement.max_transaction_fee(),
+            1_000_000_000_000_000
+        );
+
+        // The base fee doubled: the replacement offers the current price.
+        let price = TransactionPrice::from_base_fee(20_000_000_000);
+        let replacement =
+            create_replacement_transaction(&request(1_000_000_000_000_000), &previous, price)
+                .unwrap();
+        assert_eq!(replacement.nonce, 7);
+        assert_eq!(replacement.max_fee_per_gas, price.max_fee_per_gas);
+        assert_eq!(replacement.max_priority_fee_per_gas, 1_650_000_000);
+    }
+
+    #[test]
+    fn should_not_replace_transaction_if_fee_exceeds_amount() {
+        let price = TransactionPrice::from_base_fee(10_000_000_000);
+        let amount = price.max_fee_per_gas * TRANSFER_GAS_LIMIT + 1;
+        let previous = create_transaction(&request(amount), 0, 1, price).unwrap();
+        assert_eq!(
+            create_replacement_transaction(&request(amount), &previous, price),
+            None
+        );
+    }
+
+    #[test]
+    fn should_expire_requests_after_expiry() {
+        let expiry = WITHDRAWAL_REQUEST_EXPIRY.as_nanos() as u64;
+        let mut request = request(1);
+        request.created_at = 1_000;
+        assert!(!is_expired(&request, 0));
+        assert!(!is_expired(&request, 1_000));
+        assert!(!is_expired(&request, 1_000 + expiry - 1));
+        assert!(is_expired(&request, 1_000 + expiry));
+    }
+}
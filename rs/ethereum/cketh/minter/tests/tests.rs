@@ -0,0 +1,177 @@
+use candid::{Decode, Encode, Principal};
+use ic_base_types::CanisterId;
+use ic_cketh_minter::eth_rpc::BlockTag;
+use ic_cketh_minter::lifecycle::init::{InitArgs, MinterArg};
+use ic_cketh_minter::lifecycle::upgrade::UpgradeArgs;
+use ic_cketh_minter::state::{EthereumNetwork, RetrieveEthStatus};
+use ic_cketh_minter::updates::withdraw_eth::{RetrieveEthRequest, WithdrawalArg, WithdrawalError};
+use ic_icrc1::Account;
+use ic_icrc1_ledger::{InitArgs as LedgerInitArgs, LedgerArgument};
+use ic_state_machine_tests::StateMachine;
+use ic_test_utilities_load_wasm::load_wasm;
+use icp_ledger::ArchiveOptions;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+const CONTRACT_ADDRESS: &str = "0x7574eB42cA208A4f6960ECCAfDF186D627dCC175";
+
+fn ledger_wasm() -> Vec<u8> {
+    let path = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap())
+        .parent()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("rosetta-api")
+        .join("icrc1")
+        .join("ledger");
+    load_wasm(path, "ic-icrc1-ledger", &[])
+}
+
+fn minter_wasm() -> Vec<u8> {
+    load_wasm(
+        std::env::var("CARGO_MANIFEST_DIR").unwrap(),
+        "ic-cketh-minter",
+        &[],
+    )
+}
+
+fn install_ledger(env: &StateMachine, minter_id: CanisterId) -> CanisterId {
+    let args = LedgerArgument::Init(LedgerInitArgs {
+        minting_account: Account {
+            owner: minter_id.into(),
+            subaccount: None,
+        },
+        initial_balances: vec![],
+        transfer_fee: 0,
+        token_name: "ckETH".to_string(),
+        token_symbol: "ckETH".to_string(),
+        metadata: vec![],
+        archive_options: ArchiveOptions {
+            trigger_threshold: 0,
+            num_blocks_to_archive: 0,
+            node_max_memory_size_bytes: None,
+            max_message_size_bytes: None,
+            controller_id: Default::default(),
+            cycles_for_archive_creation: None,
+            max_transactions_per_response: None,
+        },
+    });
+    env.install_canister(ledger_wasm(), Encode!(&args).unwrap(), None)
+        .unwrap()
+}
+
+fn minter_init_args(ledger_id: CanisterId) -> InitArgs {
+    InitArgs {
+        ethereum_network: EthereumNetwork::Sepolia,
+        ecdsa_key_name: "test_key_1".to_string(),
+        ledger_id,
+        ethereum_contract_address: Some(FromStr::from_str(CONTRACT_ADDRESS).unwrap()),
+        ethereum_block_height: BlockTag::Finalized,
+        minimum_withdrawal_amount: 1_000_000,
+        next_transaction_nonce: 0,
+        last_scraped_block_number: 3_956_206,
+    }
+}
+
+fn install_minter(env: &StateMachine, ledger_id: CanisterId) -> CanisterId {
+    let args = MinterArg::Init(minter_init_args(ledger_id));
+    env.install_canister(minter_wasm(), Encode!(&args).unwrap(), None)
+        .unwrap()
+}
+
+fn setup() -> (StateMachine, CanisterId, CanisterId) {
+    let env = StateMachine::new();
+    let minter_id = env.create_canister(None);
+    let ledger_id = install_ledger(&env, minter_id);
+    let args = MinterArg::Init(minter_init_args(ledger_id));
+    env.install_existing_canister(minter_id, minter_wasm(), Encode!(&args).unwrap())
+        .unwrap();
+    (env, minter_id, ledger_id)
+}
+
+fn smart_contract_address(env: &StateMachine, minter_id: CanisterId) -> String {
+    Decode!(
+        &env.query(minter_id, "smart_contract_address", Encode!().unwrap())
+            .expect("failed to query smart_contract_address")
+            .bytes(),
+        String
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_install_cketh_minter_canister() {
+    let env = StateMachine::new();
+    let ledger_id = install_ledger(&env, CanisterId::from_u64(100));
+    install_minter(&env, ledger_id);
+}
+
+#[test]
+fn test_upgrade_changes_contract_address() {
+    let (env, minter_id, _) = setup();
+    assert_eq!(smart_contract_address(&env, minter_id), CONTRACT_ADDRESS);
+
+    let new_address = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+    let upgrade_args = UpgradeArgs {
+        ethereum_contract_address: Some(FromStr::from_str(new_address).unwrap()),
+        ..Default::default()
+    };
+    env.upgrade_canister(
+        minter_id,
+        minter_wasm(),
+        Encode!(&MinterArg::Upgrade(Some(upgrade_args))).unwrap(),
+    )
+    .expect("Failed to upgrade the minter canister");
+    assert_eq!(smart_contract_address(&env, minter_id), new_address);
+
+    // An upgrade without arguments preserves the state.
+    env.upgrade_canister(minter_id, minter_wasm(), Encode!(&()).unwrap())
+        .expect("Failed to upgrade the minter canister");
+    assert_eq!(smart_contract_address(&env, minter_id), new_address);
+}
+
+#[test]
+fn test_withdraw_eth_validates_arguments() {
+    let (env, minter_id, _) = setup();
+    let caller =
+        Principal::from_str("k2t6j-2nvnp-4zjm3-25dtz-6xhaa-c7boj-5gayf-oj3xs-i43lp-teztq-6ae")
+            .unwrap();
+
+    let call_withdraw_eth = |amount: u64, recipient: &str| {
+        let res = env
+            .execute_ingress_as(
+                caller.into(),
+                minter_id,
+                "withdraw_eth",
+                Encode!(&WithdrawalArg {
+                    amount,
+                    recipient: recipient.to_string(),
+                })
+                .unwrap(),
+            )
+            .expect("failed to call withdraw_eth");
+        Decode!(&res.bytes(), Result<RetrieveEthRequest, WithdrawalError>).unwrap()
+    };
+
+    assert!(matches!(
+        call_withdraw_eth(1_000_000, "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD"),
+        Err(WithdrawalError::MalformedAddress(_))
+    ));
+    assert_eq!(
+        call_withdraw_eth(10, "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"),
+        Err(WithdrawalError::AmountTooLow {
+            min_withdrawal_amount: 1_000_000
+        })
+    );
+
+    let status = Decode!(
+        &env.query(minter_id, "retrieve_eth_status", Encode!(&0u64).unwrap())
+            .expect("failed to query retrieve_eth_status")
+            .bytes(),
+        RetrieveEthStatus
+    )
+    .unwrap();
+    assert_eq!(status, RetrieveEthStatus::NotFound);
+}
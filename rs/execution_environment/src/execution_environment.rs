@@ -22,6 +22,7 @@ use ic_config::execution_environment::Config as ExecutionConfig;
 use ic_config::flag_status::FlagStatus;
 use ic_constants::{LOG_CANISTER_OPERATION_CYCLES_THRESHOLD, SMALL_APP_SUBNET_MAX_SIZE};
 use ic_crypto_tecdsa::derive_tecdsa_public_key;
+use ic_crypto_vetkd::{derive_vetkd_public_key, is_valid_transport_public_key};
 use ic_cycles_account_manager::{CyclesAccountManager, IngressInductionCost};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::{
@@ -30,7 +31,8 @@ use ic_ic00_types::{
     ECDSAPublicKeyResponse, EcdsaKeyId, EmptyBlob, InstallCodeArgs, Method as Ic00Method,
     Payload as Ic00Payload, ProvisionalCreateCanisterWithCyclesArgs, ProvisionalTopUpCanisterArgs,
     SetControllerArgs, SetupInitialDKGArgs, SignWithECDSAArgs, UninstallCodeArgs,
-    UpdateSettingsArgs, IC_00,
+    UpdateSettingsArgs, VetKdEncryptedKeyArgs, VetKdKeyId, VetKdPublicKeyArgs,
+    VetKdPublicKeyResult, IC_00,
 };
 use ic_interfaces::{
     execution_environment::{
@@ -54,6 +56,7 @@ use ic_replicated_state::{
 use ic_replicated_state::{
     metadata_state::subnet_call_context_manager::{
         EcdsaDealingsContext, SetupInitialDkgContext, SignWithEcdsaContext,
+        VetKdEncryptedKeyContext,
     },
     CanisterState, NetworkTopology, ReplicatedState,
 };
@@ -61,7 +64,8 @@ use ic_system_api::{ExecutionParameters, InstructionLimits};
 use ic_types::{
     canister_http::CanisterHttpRequestContext,
     crypto::canister_threshold_sig::{ExtendedDerivationPath, MasterEcdsaPublicKey},
-    crypto::threshold_sig::ni_dkg::NiDkgTargetId,
+    crypto::threshold_sig::{ni_dkg::NiDkgTargetId, ThresholdSigPublicKey},
+    crypto::vetkd::vetkd_derivation_path,
     ingress::{IngressState, IngressStatus, WasmResult},
     messages::{
         extract_effective_canister_id, AnonymousQuery, Payload, RejectContext, Request, Response,
@@ -371,6 +375,7 @@ impl ExecutionEnvironment {
         instruction_limits: InstructionLimits,
         rng: &mut dyn RngCore,
         ecdsa_subnet_public_keys: &BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
+        vetkd_subnet_public_keys: &BTreeMap<VetKdKeyId, ThresholdSigPublicKey>,
         registry_settings: &RegistryExecutionSettings,
         round_limits: &mut RoundLimits,
     ) -> (ReplicatedState, Option<NumInstructions>) {
@@ -841,6 +846,67 @@ impl ExecutionEnvironment {
                 }
             }
 
+            Ok(Ic00Method::VetKdPublicKey) => {
+                let cycles = msg.take_cycles();
+                match &msg {
+                    CanisterCall::Request(request) => {
+                        let res = match VetKdPublicKeyArgs::decode(request.method_payload()) {
+                            Err(err) => Err(candid_error_to_user_error(err)),
+                            Ok(args) => match get_vetkd_subnet_public_key(
+                                vetkd_subnet_public_keys,
+                                self.own_subnet_id,
+                                &args.key_id,
+                            ) {
+                                Err(err) => Err(err),
+                                Ok(pubkey) => {
+                                    let canister_id = match args.canister_id {
+                                        Some(id) => id.into(),
+                                        None => *msg.sender(),
+                                    };
+                                    self.get_vetkd_public_key(
+                                        pubkey,
+                                        canister_id,
+                                        args.derivation_path,
+                                        &args.key_id,
+                                    )
+                                    .map(|res| res.encode())
+                                }
+                            },
+                        };
+                        Some((res, cycles))
+                    }
+                    CanisterCall::Ingress(_) => {
+                        self.reject_unexpected_ingress(Ic00Method::VetKdPublicKey)
+                    }
+                }
+            }
+
+            Ok(Ic00Method::VetKdEncryptedKey) => match &msg {
+                CanisterCall::Request(request) => match VetKdEncryptedKeyArgs::decode(payload) {
+                    Err(err) => Some((Err(candid_error_to_user_error(err)), msg.take_cycles())),
+                    Ok(args) => match get_vetkd_subnet_public_key(
+                        vetkd_subnet_public_keys,
+                        self.own_subnet_id,
+                        &args.key_id,
+                    ) {
+                        Err(err) => Some((Err(err), msg.take_cycles())),
+                        Ok(_) => self
+                            .vetkd_encrypted_key(
+                                (**request).clone(),
+                                args,
+                                registry_settings.max_vetkd_queue_size,
+                                &mut state,
+                                rng,
+                                registry_settings.subnet_size,
+                            )
+                            .map_or_else(|err| Some((Err(err), msg.take_cycles())), |()| None),
+                    },
+                },
+                CanisterCall::Ingress(_) => {
+                    self.reject_unexpected_ingress(Ic00Method::VetKdEncryptedKey)
+                }
+            },
+
             Ok(Ic00Method::ComputeInitialEcdsaDealings) => {
                 let cycles = msg.take_cycles();
                 match &msg {
@@ -1322,11 +1388,31 @@ impl ExecutionEnvironment {
         state: &mut ReplicatedState,
         subnet_size: usize,
     ) -> Result<Vec<u8>, UserError> {
+        let routing_table = Arc::clone(&state.metadata.network_topology.routing_table);
         let canister = get_canister_mut(canister_id, state)?;
 
+        // Byte size of output messages not yet routed, by remote destination subnet.
+        let mut outstanding_xnet_bytes = BTreeMap::<SubnetId, u64>::new();
+        for (receiver, size_bytes) in canister
+            .system_state
+            .queues()
+            .output_queues_size_bytes_by_receiver()
+        {
+            match routing_table.route(receiver.get()) {
+                Some(subnet_id) if subnet_id != self.own_subnet_id => {
+                    *outstanding_xnet_bytes.entry(subnet_id).or_default() += size_bytes as u64;
+                }
+                _ => {}
+            }
+        }
+
         self.canister_manager
             .get_canister_status(sender, canister, subnet_size)
-            .map(|status| status.encode())
+            .map(|status| {
+                status
+                    .with_outstanding_xnet_bytes(outstanding_xnet_bytes.into_iter().collect())
+                    .encode()
+            })
             .map_err(|err| err.into())
     }
 
@@ -1809,6 +1895,97 @@ impl ExecutionEnvironment {
         Ok(())
     }
 
+    fn get_vetkd_public_key(
+        &self,
+        subnet_public_key: &ThresholdSigPublicKey,
+        principal_id: PrincipalId,
+        derivation_path: Vec<Vec<u8>>,
+        key_id: &VetKdKeyId,
+    ) -> Result<VetKdPublicKeyResult, UserError> {
+        let _ = CanisterId::new(principal_id).map_err(|err| {
+            UserError::new(
+                ErrorCode::CanisterContractViolation,
+                format!("Not a canister id: {}", err),
+            )
+        })?;
+        let path = vetkd_derivation_path(principal_id, key_id, &derivation_path);
+        derive_vetkd_public_key(subnet_public_key, &path)
+            .map_err(|err| UserError::new(ErrorCode::CanisterRejectedMessage, format!("{}", err)))
+            .map(|public_key| VetKdPublicKeyResult { public_key })
+    }
+
+    fn vetkd_encrypted_key(
+        &self,
+        mut request: Request,
+        args: VetKdEncryptedKeyArgs,
+        max_queue_size: u32,
+        state: &mut ReplicatedState,
+        rng: &mut dyn RngCore,
+        subnet_size: usize,
+    ) -> Result<(), UserError> {
+        if !is_valid_transport_public_key(&args.encryption_public_key) {
+            return Err(UserError::new(
+                ErrorCode::CanisterRejectedMessage,
+                "vetkd_encrypted_key request sent with an invalid encryption_public_key."
+                    .to_string(),
+            ));
+        }
+
+        // As with sign_with_ecdsa, NNS callers are not charged.
+        let source_subnet = state
+            .metadata
+            .network_topology
+            .routing_table
+            .route(request.sender.get());
+        if source_subnet != Some(state.metadata.network_topology.nns_subnet_id) {
+            let fee = self.cycles_account_manager.vetkd_fee(subnet_size);
+            if request.payment < fee {
+                return Err(UserError::new(
+                    ErrorCode::CanisterRejectedMessage,
+                    format!(
+                        "vetkd_encrypted_key request sent with {} cycles, but {} cycles are required.",
+                        request.payment, fee
+                    ),
+                ));
+            } else {
+                request.payment -= fee;
+                state
+                    .metadata
+                    .subnet_metrics
+                    .observe_consumed_cycles_with_use_case(
+                        CyclesUseCase::VetKd,
+                        NominalCycles::from(fee),
+                    );
+            }
+        }
+
+        let mut pseudo_random_id = [0u8; 32];
+        rng.fill_bytes(&mut pseudo_random_id);
+
+        info!(
+            self.log,
+            "Assigned the pseudo_random_id {:?} to the new vetkd_encrypted_key request from {:?}",
+            pseudo_random_id,
+            request.sender()
+        );
+        state
+            .metadata
+            .subnet_call_context_manager
+            .push_vetkd_encrypted_key_request(
+                VetKdEncryptedKeyContext {
+                    request,
+                    key_id: args.key_id,
+                    derivation_path: args.derivation_path,
+                    derivation_id: args.derivation_id,
+                    encryption_public_key: args.encryption_public_key,
+                    pseudo_random_id,
+                    batch_time: state.metadata.batch_time,
+                },
+                max_queue_size,
+            )?;
+        Ok(())
+    }
+
     fn compute_initial_ecdsa_dealings(
         &self,
         state: &mut ReplicatedState,
@@ -2462,3 +2639,17 @@ fn get_master_ecdsa_public_key<'a>(
         Some(master_key) => Ok(master_key),
     }
 }
+
+fn get_vetkd_subnet_public_key<'a>(
+    vetkd_subnet_public_keys: &'a BTreeMap<VetKdKeyId, ThresholdSigPublicKey>,
+    subnet_id: SubnetId,
+    key_id: &VetKdKeyId,
+) -> Result<&'a ThresholdSigPublicKey, UserError> {
+    match vetkd_subnet_public_keys.get(key_id) {
+        None => Err(UserError::new(
+            ErrorCode::CanisterRejectedMessage,
+            format!("Subnet {} does not hold vetKD key {}.", subnet_id, key_id),
+        )),
+        Some(public_key) => Ok(public_key),
+    }
+}
@@ -9,7 +9,7 @@ use ic_ic00_types::{
     self as ic00, CanisterHttpRequestArgs, CanisterIdRecord, CanisterStatusResultV2,
     CanisterStatusType, EcdsaCurve, EcdsaKeyId, EmptyBlob, HttpMethod, Method,
     Payload as Ic00Payload, ProvisionalCreateCanisterWithCyclesArgs, ProvisionalTopUpCanisterArgs,
-    TransformContext, TransformFunc, IC_00,
+    TransformContext, TransformFunc, VetKdCurve, VetKdKeyId, IC_00,
 };
 use ic_registry_routing_table::canister_id_into_u64;
 use ic_registry_routing_table::CanisterIdRange;
@@ -1747,6 +1747,57 @@ fn ecdsa_signature_fee_charged() {
     );
 }
 
+#[test]
+fn ecdsa_signature_with_secp256r1_key_creates_context() {
+    let ecdsa_key = EcdsaKeyId {
+        curve: EcdsaCurve::Secp256r1,
+        name: "secp256r1".to_string(),
+    };
+    let mut test = ExecutionTestBuilder::new()
+        .with_subnet_type(SubnetType::System)
+        .with_own_subnet_id(subnet_test_id(1))
+        .with_nns_subnet_id(subnet_test_id(2))
+        .with_ecdsa_key(ecdsa_key.clone())
+        .build();
+
+    let canister_id = test.universal_canister().unwrap();
+    let esda_args = ic00::SignWithECDSAArgs {
+        message_hash: [1; 32],
+        derivation_path: vec![],
+        key_id: ecdsa_key.clone(),
+    };
+    let run = wasm()
+        .call_with_cycles(
+            ic00::IC_00,
+            Method::SignWithECDSA,
+            call_args()
+                .other_side(esda_args.encode())
+                .on_reject(wasm().reject_message().reject()),
+            (0, 1_000_000_000),
+        )
+        .build();
+
+    let (_, ingress_status) = test.ingress_raw(canister_id, "update", run);
+    assert_eq!(
+        ingress_status,
+        IngressStatus::Known {
+            receiver: canister_id.get(),
+            user_id: test.user_id(),
+            time: test.time(),
+            state: IngressState::Processing,
+        }
+    );
+    let (_, context) = test
+        .state()
+        .metadata
+        .subnet_call_context_manager
+        .sign_with_ecdsa_contexts
+        .iter()
+        .next()
+        .unwrap();
+    assert_eq!(context.key_id, ecdsa_key);
+}
+
 #[test]
 fn ecdsa_signature_rejected_without_fee() {
     let fee = 2_000_000;
@@ -1964,6 +2015,196 @@ fn ecdsa_signature_queue_fills_up() {
     );
 }
 
+fn make_vetkd_key(name: &str) -> VetKdKeyId {
+    VetKdKeyId {
+        curve: VetKdCurve::Bls12_381_G2,
+        name: name.to_string(),
+    }
+}
+
+/// The compressed encoding of the BLS12-381 G1 generator, a well-formed
+/// transport public key.
+fn valid_transport_public_key() -> Vec<u8> {
+    hex::decode("97f1d3a73197d7942695638c4fa9ac0fc3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb").unwrap()
+}
+
+fn vetkd_encrypted_key_call(
+    key_id: VetKdKeyId,
+    encryption_public_key: Vec<u8>,
+    cycles: u64,
+) -> Vec<u8> {
+    let args = ic00::VetKdEncryptedKeyArgs {
+        derivation_path: vec![b"path".to_vec()],
+        derivation_id: b"id".to_vec(),
+        key_id,
+        encryption_public_key,
+    };
+    wasm()
+        .call_with_cycles(
+            ic00::IC_00,
+            Method::VetKdEncryptedKey,
+            call_args()
+                .other_side(args.encode())
+                .on_reject(wasm().reject_message().reject()),
+            (0, cycles),
+        )
+        .build()
+}
+
+#[test]
+fn vetkd_encrypted_key_request_is_queued_and_charged() {
+    let fee = 1_000_000;
+    let payment = 2_000_000;
+    let vetkd_key = make_vetkd_key("test_key");
+    let mut test = ExecutionTestBuilder::new()
+        .with_subnet_type(SubnetType::System)
+        .with_own_subnet_id(subnet_test_id(1))
+        .with_nns_subnet_id(subnet_test_id(2))
+        .with_vetkd_fee(fee)
+        .with_vetkd_key(vetkd_key.clone())
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let run = vetkd_encrypted_key_call(
+        vetkd_key.clone(),
+        valid_transport_public_key(),
+        payment as u64,
+    );
+
+    let (_, ingress_status) = test.ingress_raw(canister_id, "update", run);
+    assert_eq!(
+        ingress_status,
+        IngressStatus::Known {
+            receiver: canister_id.get(),
+            user_id: test.user_id(),
+            time: test.time(),
+            state: IngressState::Processing,
+        }
+    );
+    let (_, context) = test
+        .state()
+        .metadata
+        .subnet_call_context_manager
+        .vetkd_encrypted_key_contexts
+        .iter()
+        .next()
+        .unwrap();
+    assert_eq!(context.key_id, vetkd_key);
+    assert_eq!(context.derivation_id, b"id".to_vec());
+    assert_eq!(context.encryption_public_key, valid_transport_public_key());
+    assert_eq!(context.request.payment, Cycles::new(payment - fee));
+    assert_eq!(
+        NominalCycles::from(fee),
+        *test
+            .state()
+            .metadata
+            .subnet_metrics
+            .get_consumed_cycles_by_use_case()
+            .get(&CyclesUseCase::VetKd)
+            .unwrap()
+    );
+    assert_eq!(
+        test.state()
+            .metadata
+            .subnet_metrics
+            .consumed_cycles_ecdsa_outcalls,
+        NominalCycles::from(0)
+    );
+}
+
+#[test]
+fn vetkd_encrypted_key_rejected_with_unknown_key() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_subnet_type(SubnetType::System)
+        .with_own_subnet_id(subnet_test_id(1))
+        .with_nns_subnet_id(subnet_test_id(2))
+        .with_ecdsa_key(make_key("secp256k1"))
+        .with_vetkd_key(make_vetkd_key("test_key"))
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let run = vetkd_encrypted_key_call(
+        make_vetkd_key("unknown_key"),
+        valid_transport_public_key(),
+        1_000_000_000,
+    );
+
+    let result = test.ingress(canister_id, "update", run).unwrap();
+    assert_eq!(
+        WasmResult::Reject(format!(
+            "Subnet {} does not hold vetKD key {}.",
+            subnet_test_id(1),
+            make_vetkd_key("unknown_key")
+        )),
+        result
+    );
+    assert!(test
+        .state()
+        .metadata
+        .subnet_call_context_manager
+        .vetkd_encrypted_key_contexts
+        .is_empty());
+}
+
+#[test]
+fn vetkd_public_key_rejected_with_unknown_key() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_subnet_type(SubnetType::System)
+        .with_own_subnet_id(subnet_test_id(1))
+        .with_nns_subnet_id(subnet_test_id(2))
+        .with_vetkd_key(make_vetkd_key("test_key"))
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let args = ic00::VetKdPublicKeyArgs {
+        canister_id: None,
+        derivation_path: vec![b"path".to_vec()],
+        key_id: make_vetkd_key("unknown_key"),
+    };
+    let run = wasm()
+        .call_simple(
+            ic00::IC_00,
+            Method::VetKdPublicKey,
+            call_args()
+                .other_side(args.encode())
+                .on_reject(wasm().reject_message().reject()),
+        )
+        .build();
+
+    let result = test.ingress(canister_id, "update", run).unwrap();
+    assert_eq!(
+        WasmResult::Reject(format!(
+            "Subnet {} does not hold vetKD key {}.",
+            subnet_test_id(1),
+            make_vetkd_key("unknown_key")
+        )),
+        result
+    );
+}
+
+#[test]
+fn vetkd_encrypted_key_rejected_with_invalid_transport_public_key() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_subnet_type(SubnetType::System)
+        .with_own_subnet_id(subnet_test_id(1))
+        .with_nns_subnet_id(subnet_test_id(2))
+        .with_vetkd_key(make_vetkd_key("test_key"))
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let run = vetkd_encrypted_key_call(make_vetkd_key("test_key"), vec![1; 48], 1_000_000_000);
+
+    let result = test.ingress(canister_id, "update", run).unwrap();
+    assert_eq!(
+        WasmResult::Reject(
+            "vetkd_encrypted_key request sent with an invalid encryption_public_key.".to_string()
+        ),
+        result
+    );
+    assert!(test
+        .state()
+        .metadata
+        .subnet_call_context_manager
+        .vetkd_encrypted_key_contexts
+        .is_empty());
+}
+
 #[test]
 fn canister_output_queue_does_not_overflow_when_calling_ic00() {
     let own_subnet = subnet_test_id(1);
@@ -12,7 +12,7 @@ use ic_config::subnet_config::SchedulerConfig;
 use ic_crypto_prng::{Csprng, RandomnessPurpose::ExecutionThread};
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_error_types::{ErrorCode, UserError};
-use ic_ic00_types::{CanisterStatusType, EcdsaKeyId, Method as Ic00Method};
+use ic_ic00_types::{CanisterStatusType, EcdsaKeyId, Method as Ic00Method, VetKdKeyId};
 use ic_interfaces::execution_environment::{
     ExecutionComplexity, ExecutionRoundType, RegistryExecutionSettings,
 };
@@ -29,7 +29,7 @@ use ic_replicated_state::{
 };
 use ic_system_api::InstructionLimits;
 use ic_types::{
-    crypto::canister_threshold_sig::MasterEcdsaPublicKey,
+    crypto::{canister_threshold_sig::MasterEcdsaPublicKey, threshold_sig::ThresholdSigPublicKey},
     ingress::{IngressState, IngressStatus},
     messages::{Ingress, MessageId},
     AccumulatedPriority, CanisterId, ComputeAllocation, Cycles, ExecutionRound, LongExecutionMode,
@@ -452,6 +452,7 @@ impl SchedulerImpl {
         long_running_canister_ids: BTreeSet<CanisterId>,
         registry_settings: &RegistryExecutionSettings,
         ecdsa_subnet_public_keys: &BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
+        vetkd_subnet_public_keys: &BTreeMap<VetKdKeyId, ThresholdSigPublicKey>,
     ) -> ReplicatedState {
         loop {
             let mut available_subnet_messages = false;
@@ -484,6 +485,7 @@ impl SchedulerImpl {
                     instruction_limits,
                     csprng,
                     ecdsa_subnet_public_keys,
+                    vetkd_subnet_public_keys,
                     registry_settings,
                     round_limits,
                 );
@@ -1292,6 +1294,7 @@ impl Scheduler for SchedulerImpl {
         mut state: ReplicatedState,
         randomness: Randomness,
         ecdsa_subnet_public_keys: BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
+        vetkd_subnet_public_keys: BTreeMap<VetKdKeyId, ThresholdSigPublicKey>,
         current_round: ExecutionRound,
         current_round_type: ExecutionRoundType,
         registry_settings: &RegistryExecutionSettings,
@@ -1406,6 +1409,7 @@ impl Scheduler for SchedulerImpl {
                     instruction_limits,
                     &mut csprng,
                     &ecdsa_subnet_public_keys,
+                    &vetkd_subnet_public_keys,
                     registry_settings,
                     &mut round_limits,
                 );
@@ -1450,6 +1454,7 @@ impl Scheduler for SchedulerImpl {
                 long_running_canister_ids,
                 registry_settings,
                 &ecdsa_subnet_public_keys,
+                &vetkd_subnet_public_keys,
             );
         }
 
@@ -2099,6 +2104,8 @@ fn get_instructions_limits_for_subnet_message(
             | HttpRequest
             | SetupInitialDKG
             | SignWithECDSA
+            | VetKdPublicKey
+            | VetKdEncryptedKey
             | ComputeInitialEcdsaDealings
             | StartCanister
             | StopCanister
@@ -468,6 +468,7 @@ impl SchedulerTest {
             state,
             Randomness::from([0; 32]),
             self.ecdsa_subnet_public_keys.clone(),
+            BTreeMap::new(),
             self.round,
             round_type,
             self.registry_settings(),
@@ -524,6 +525,7 @@ impl SchedulerTest {
             long_running_canister_ids,
             self.registry_settings(),
             &BTreeMap::new(),
+            &BTreeMap::new(),
         )
     }
 
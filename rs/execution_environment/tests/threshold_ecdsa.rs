@@ -0,0 +1,121 @@
+use candid::{Decode, Encode};
+use ic_crypto_tecdsa::derive_tecdsa_public_key;
+use ic_ic00_types::{self as ic00, ECDSAPublicKeyResponse, EcdsaCurve, EcdsaKeyId};
+use ic_state_machine_tests::{StateMachine, StateMachineBuilder};
+use ic_types::crypto::canister_threshold_sig::{ExtendedDerivationPath, MasterEcdsaPublicKey};
+use ic_types::crypto::AlgorithmId;
+use ic_types::ingress::WasmResult;
+use ic_types::{CanisterId, Cycles, PrincipalId, SubnetId};
+use ic_universal_canister::{call_args, wasm, UNIVERSAL_CANISTER_WASM};
+
+const B: u128 = 1_000_000_000;
+
+/// The master public key the state machine uses for secp256r1 keys, i.e. the
+/// SEC1 encoding of the secp256r1 generator.
+const SECP256R1_MASTER_PUBLIC_KEY: &str =
+    "036b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296";
+
+fn secp256r1_key_id() -> EcdsaKeyId {
+    EcdsaKeyId {
+        curve: EcdsaCurve::Secp256r1,
+        name: "key_id_secp256r1".to_string(),
+    }
+}
+
+fn setup(key_id: EcdsaKeyId) -> (StateMachine, CanisterId) {
+    let env = StateMachineBuilder::new()
+        .with_nns_subnet_id(SubnetId::from(PrincipalId::new_subnet_test_id(1)))
+        .with_subnet_id(SubnetId::from(PrincipalId::new_subnet_test_id(2)))
+        .with_ecdsa_key(key_id)
+        .build();
+    let canister_id = env
+        .install_canister_with_cycles(
+            UNIVERSAL_CANISTER_WASM.to_vec(),
+            vec![],
+            None,
+            Cycles::new(1_000 * B),
+        )
+        .unwrap();
+    (env, canister_id)
+}
+
+#[test]
+fn ecdsa_public_key_derives_secp256r1_key() {
+    let key_id = secp256r1_key_id();
+    let (env, canister_id) = setup(key_id.clone());
+    let derivation_path = vec![b"some".to_vec(), b"path".to_vec()];
+
+    let ecdsa_public_key = wasm()
+        .call_simple(
+            ic00::IC_00,
+            ic00::Method::ECDSAPublicKey,
+            call_args().other_side(
+                Encode!(&ic00::ECDSAPublicKeyArgs {
+                    canister_id: None,
+                    derivation_path: derivation_path.clone(),
+                    key_id,
+                })
+                .unwrap(),
+            ),
+        )
+        .build();
+    let response = match env
+        .execute_ingress(canister_id, "update", ecdsa_public_key)
+        .unwrap()
+    {
+        WasmResult::Reply(bytes) => Decode!(&bytes, ECDSAPublicKeyResponse).unwrap(),
+        WasmResult::Reject(err) => panic!("ecdsa_public_key was rejected: {}", err),
+    };
+
+    let expected = derive_tecdsa_public_key(
+        &MasterEcdsaPublicKey {
+            algorithm_id: AlgorithmId::EcdsaP256,
+            public_key: hex::decode(SECP256R1_MASTER_PUBLIC_KEY).unwrap(),
+        },
+        &ExtendedDerivationPath {
+            caller: canister_id.get(),
+            derivation_path,
+        },
+    )
+    .unwrap();
+    assert_eq!(response.public_key, expected.public_key);
+    assert_eq!(response.chain_code, expected.chain_key);
+}
+
+#[test]
+fn sign_with_ecdsa_on_secp256r1_creates_signing_context() {
+    let key_id = secp256r1_key_id();
+    let (env, canister_id) = setup(key_id.clone());
+
+    let sign_with_ecdsa = wasm()
+        .call_with_cycles(
+            ic00::IC_00,
+            ic00::Method::SignWithECDSA,
+            call_args().other_side(
+                Encode!(&ic00::SignWithECDSAArgs {
+                    message_hash: [1; 32],
+                    derivation_path: vec![b"some".to_vec(), b"path".to_vec()],
+                    key_id: key_id.clone(),
+                })
+                .unwrap(),
+            ),
+            Cycles::new(100 * B).into_parts(),
+        )
+        .build();
+    // The signature itself is produced by consensus, which is not simulated
+    // by the state machine, so only the resulting signing request is checked.
+    let _msg_id = env.send_ingress(
+        PrincipalId::new_anonymous(),
+        canister_id,
+        "update",
+        sign_with_ecdsa,
+    );
+    env.tick();
+
+    let contexts = env.sign_with_ecdsa_contexts();
+    assert_eq!(contexts.len(), 1);
+    let (_, context) = contexts.iter().next().unwrap();
+    assert_eq!(context.key_id, key_id);
+    assert_eq!(context.message_hash, [1; 32]);
+    assert_eq!(context.request.sender, canister_id);
+}
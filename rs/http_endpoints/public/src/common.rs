@@ -21,6 +21,7 @@ use std::task::Poll;
 use tower::{load_shed::error::Overloaded, timeout::error::Elapsed, BoxError};
 
 pub const CONTENT_TYPE_HTML: &str = "text/html";
+pub const CONTENT_TYPE_JSON: &str = "application/json";
 pub const CONTENT_TYPE_CBOR: &str = "application/cbor";
 pub const CONTENT_TYPE_PROTOBUF: &str = "application/x-protobuf";
 
@@ -1,17 +1,20 @@
 //! Module that serves the human-readable replica dashboard, which provide
-//! information about the state of the replica.
+//! information about the state of the replica, as well as its machine-readable
+//! JSON counterpart.
 
 use crate::{
-    common::{make_plaintext_response, CONTENT_TYPE_HTML},
+    common::{make_plaintext_response, CONTENT_TYPE_HTML, CONTENT_TYPE_JSON},
     state_reader_executor::StateReaderExecutor,
-    EndpointService,
+    EndpointService, HTTP_DASHBOARD_JSON_URL_PATH,
 };
 use askama::Template;
 use http::Request;
 use hyper::{Body, Response, StatusCode};
 use ic_config::http_handler::Config;
 use ic_registry_subnet_type::SubnetType;
-use ic_types::{Height, ReplicaVersion};
+use ic_replicated_state::{CanisterState, ReplicatedState};
+use ic_types::{Height, MemoryAllocation, ReplicaVersion};
+use serde::Serialize;
 use std::future::Future;
 use std::pin::Pin;
 use std::task::{Context, Poll};
@@ -25,6 +28,100 @@ include!(concat!(env!("OUT_DIR"), "/dashboard.rs"));
 
 const MAX_DASHBOARD_CONCURRENT_REQUESTS: usize = 100;
 
+/// The version of the schema of the JSON dashboard. It must be increased
+/// whenever a field is removed, renamed or changes its meaning; adding fields
+/// keeps the schema backwards compatible.
+const DASHBOARD_JSON_SCHEMA_VERSION: u32 = 1;
+
+/// The JSON dashboard, served at [`HTTP_DASHBOARD_JSON_URL_PATH`].
+#[derive(Serialize)]
+struct DashboardJson<'a> {
+    schema_version: u32,
+    replica_version: String,
+    subnet_type: SubnetType,
+    total_compute_allocation: u64,
+    http_config: &'a Config,
+    /// The height of the state the canister summary was taken at.
+    height: u64,
+    canisters: Vec<CanisterJson>,
+}
+
+#[derive(Serialize)]
+struct CanisterJson {
+    canister_id: String,
+    status: &'static str,
+    controllers: Vec<String>,
+    /// The reserved memory in bytes, `null` for a best-effort allocation.
+    memory_allocation: Option<u64>,
+    memory_usage: u64,
+    compute_allocation: u64,
+    freeze_threshold: u64,
+    cycles_balance: u128,
+    last_full_execution_round: u64,
+    accumulated_priority: i64,
+    certified_data_length: usize,
+    /// `null` if the canister has no execution state.
+    wasm_module_size: Option<usize>,
+    wasm_module_sha256: Option<String>,
+    heap_size_pages: Option<usize>,
+    stable_memory_size_pages: Option<usize>,
+}
+
+impl DashboardJson<'_> {
+    fn new<'a>(
+        subnet_type: SubnetType,
+        http_config: &'a Config,
+        height: Height,
+        state: &ReplicatedState,
+        replica_version: &ReplicaVersion,
+    ) -> DashboardJson<'a> {
+        DashboardJson {
+            schema_version: DASHBOARD_JSON_SCHEMA_VERSION,
+            replica_version: replica_version.to_string(),
+            subnet_type,
+            total_compute_allocation: state.total_compute_allocation(),
+            http_config,
+            height: height.get(),
+            canisters: state
+                .canisters_iter()
+                .map(|canister| CanisterJson::new(canister, subnet_type))
+                .collect(),
+        }
+    }
+}
+
+impl CanisterJson {
+    fn new(canister: &CanisterState, subnet_type: SubnetType) -> Self {
+        let system_state = &canister.system_state;
+        let execution_state = canister.execution_state.as_ref();
+        Self {
+            canister_id: canister.canister_id().to_string(),
+            status: system_state.status_string(),
+            controllers: system_state
+                .controllers
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            memory_allocation: match canister.memory_allocation() {
+                MemoryAllocation::Reserved(bytes) => Some(bytes.get()),
+                MemoryAllocation::BestEffort => None,
+            },
+            memory_usage: canister.memory_usage(subnet_type).get(),
+            compute_allocation: canister.scheduler_state.compute_allocation.as_percent(),
+            freeze_threshold: system_state.freeze_threshold.get(),
+            cycles_balance: system_state.balance().get(),
+            last_full_execution_round: canister.scheduler_state.last_full_execution_round.get(),
+            accumulated_priority: canister.scheduler_state.accumulated_priority.get(),
+            certified_data_length: system_state.certified_data.len(),
+            wasm_module_size: execution_state.map(|state| state.wasm_binary.binary.len()),
+            wasm_module_sha256: execution_state
+                .map(|state| hex::encode(state.wasm_binary.binary.module_hash())),
+            heap_size_pages: execution_state.map(|state| state.wasm_memory.size.get()),
+            stable_memory_size_pages: execution_state.map(|state| state.stable_memory.size.get()),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub(crate) struct DashboardService {
     config: Config,
@@ -63,8 +160,9 @@ impl Service<Request<Body>> for DashboardService {
         Poll::Ready(Ok(()))
     }
 
-    fn call(&mut self, _unused: Request<Body>) -> Self::Future {
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
         use hyper::header;
+        let json = request.uri().path() == HTTP_DASHBOARD_JSON_URL_PATH;
         let http_config = self.config.clone();
         let subnet_type = self.subnet_type;
         let state_reader_executor = self.state_reader_executor.clone();
@@ -74,6 +172,32 @@ impl Service<Request<Body>> for DashboardService {
                 Err(e) => return Ok(make_plaintext_response(e.status, e.message)),
             };
 
+            if json {
+                let dashboard = DashboardJson::new(
+                    subnet_type,
+                    &http_config,
+                    labeled_state.height(),
+                    labeled_state.get_ref(),
+                    &ReplicaVersion::default(),
+                );
+                let res = match serde_json::to_vec(&dashboard) {
+                    Ok(content) => {
+                        let mut response = Response::new(Body::from(content));
+                        *response.status_mut() = StatusCode::OK;
+                        response.headers_mut().insert(
+                            header::CONTENT_TYPE,
+                            header::HeaderValue::from_static(CONTENT_TYPE_JSON),
+                        );
+                        response
+                    }
+                    Err(e) => make_plaintext_response(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Internal error: {}", e),
+                    ),
+                };
+                return Ok(res);
+            }
+
             // See https://github.com/djc/askama/issues/333
             let canisters: Vec<&ic_replicated_state::CanisterState> =
                 labeled_state.get_ref().canisters_iter().collect();
@@ -109,3 +233,138 @@ impl Service<Request<Body>> for DashboardService {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_test_utilities::{
+        state::{CanisterStateBuilder, ReplicatedStateBuilder},
+        types::ids::{canister_test_id, user_test_id},
+    };
+    use ic_types::NumBytes;
+    use serde_json::{json, Value};
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn json_dashboard_has_the_expected_shape() {
+        let state = ReplicatedStateBuilder::new()
+            .with_canister(
+                CanisterStateBuilder::new()
+                    .with_canister_id(canister_test_id(1))
+                    .with_controller(user_test_id(1))
+                    .with_cycles(1_000u128)
+                    .with_memory_allocation(NumBytes::new(1 << 20))
+                    .with_wasm(vec![0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00])
+                    .build(),
+            )
+            .with_canister(
+                CanisterStateBuilder::new()
+                    .with_canister_id(canister_test_id(2))
+                    .build(),
+            )
+            .build();
+        let config = Config::default();
+        let dashboard = DashboardJson::new(
+            SubnetType::Application,
+            &config,
+            Height::new(7),
+            &state,
+            &ReplicaVersion::default(),
+        );
+        // Serialized the same way as in `DashboardService::call`.
+        let value: Value =
+            serde_json::from_slice(&serde_json::to_vec(&dashboard).unwrap()).unwrap();
+
+        assert_eq!(
+            value
+                .as_object()
+                .unwrap()
+                .keys()
+                .map(String::as_str)
+                .collect::<BTreeSet<_>>(),
+            BTreeSet::from([
+                "schema_version",
+                "replica_version",
+                "subnet_type",
+                "total_compute_allocation",
+                "http_config",
+                "height",
+                "canisters",
+            ])
+        );
+        assert_eq!(
+            value["schema_version"],
+            json!(DASHBOARD_JSON_SCHEMA_VERSION)
+        );
+        assert_eq!(
+            value["replica_version"],
+            json!(ReplicaVersion::default().to_string())
+        );
+        assert_eq!(value["subnet_type"], json!("application"));
+        assert_eq!(value["total_compute_allocation"], json!(0));
+        assert_eq!(value["http_config"], serde_json::to_value(&config).unwrap());
+        assert_eq!(value["height"], json!(7));
+
+        let canisters = value["canisters"].as_array().unwrap();
+        assert_eq!(canisters.len(), 2);
+        let canister_fields = BTreeSet::from([
+            "canister_id",
+            "status",
+            "controllers",
+            "memory_allocation",
+            "memory_usage",
+            "compute_allocation",
+            "freeze_threshold",
+            "cycles_balance",
+            "last_full_execution_round",
+            "accumulated_priority",
+            "certified_data_length",
+            "wasm_module_size",
+            "wasm_module_sha256",
+            "heap_size_pages",
+            "stable_memory_size_pages",
+        ]);
+        for canister in canisters {
+            assert_eq!(
+                canister
+                    .as_object()
+                    .unwrap()
+                    .keys()
+                    .map(String::as_str)
+                    .collect::<BTreeSet<_>>(),
+                canister_fields
+            );
+        }
+
+        let installed = &canisters[0];
+        assert_eq!(
+            installed["canister_id"],
+            json!(canister_test_id(1).to_string())
+        );
+        assert_eq!(installed["status"], json!("Running"));
+        assert_eq!(
+            installed["controllers"],
+            json!([user_test_id(1).get().to_string()])
+        );
+        assert_eq!(installed["memory_allocation"], json!(1 << 20));
+        assert_eq!(installed["cycles_balance"], json!(1_000));
+        assert_eq!(installed["wasm_module_size"], json!(8));
+        assert_eq!(installed["wasm_module_sha256"].as_str().unwrap().len(), 64);
+        assert!(installed["heap_size_pages"].is_u64());
+        assert!(installed["stable_memory_size_pages"].is_u64());
+
+        // A canister without a Wasm module has neither a memory allocation nor
+        // an execution state.
+        let empty = &canisters[1];
+        assert_eq!(empty["canister_id"], json!(canister_test_id(2).to_string()));
+        for field in [
+            "memory_allocation",
+            "wasm_module_size",
+            "wasm_module_sha256",
+            "heap_size_pages",
+            "stable_memory_size_pages",
+        ] {
+            assert_eq!(empty[field], Value::Null, "{}", field);
+        }
+    }
+}
@@ -140,6 +140,7 @@ pub(crate) const MAX_DELEGATION_CERTIFICATE_SIZE: Byte = Byte::from_bytes(1024 *
 pub(crate) const MAX_REQUEST_RECEIVE_DURATION: Duration = Duration::from_secs(300); // 5 min
 
 const HTTP_DASHBOARD_URL_PATH: &str = "/_/dashboard";
+const HTTP_DASHBOARD_JSON_URL_PATH: &str = "/_/dashboard.json";
 const CONTENT_TYPE_CBOR: &str = "application/cbor";
 
 // Placeholder used when we can't determine the approriate prometheus label.
@@ -688,7 +689,7 @@ async fn make_router(
                 timer.set_label(LABEL_REQUEST_TYPE, ApiReqType::RedirectToDashboard.into());
                 return (redirect_to_dasboard_response(), timer);
             }
-            HTTP_DASHBOARD_URL_PATH => {
+            HTTP_DASHBOARD_URL_PATH | HTTP_DASHBOARD_JSON_URL_PATH => {
                 timer.set_label(LABEL_REQUEST_TYPE, ApiReqType::Dashboard.into());
                 dashboard_service
             }
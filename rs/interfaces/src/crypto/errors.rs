@@ -8,6 +8,7 @@ use ic_types::crypto::threshold_sig::ni_dkg::errors::create_transcript_error::Dk
 use ic_types::crypto::threshold_sig::ni_dkg::errors::key_removal_error::DkgKeyRemovalError;
 use ic_types::crypto::threshold_sig::ni_dkg::errors::load_transcript_error::DkgLoadTranscriptError;
 use ic_types::crypto::threshold_sig::ni_dkg::errors::verify_dealing_error::DkgVerifyDealingError;
+use ic_types::crypto::vetkd::VetKdKeyShareVerificationError;
 use ic_types::crypto::CryptoError;
 use ic_types::registry::RegistryClientError;
 
@@ -333,6 +334,24 @@ impl ErrorReproducibility for ThresholdEcdsaVerifyCombinedSignatureError {
     }
 }
 
+impl ErrorReproducibility for VetKdKeyShareVerificationError {
+    fn is_reproducible(&self) -> bool {
+        // The match below is intentionally explicit on all possible values,
+        // to avoid defaults, which might be error-prone.
+        // Upon addition of any new error this match has to be updated.
+        match self {
+            // false, as the transcript may be loaded later
+            Self::ThresholdSigDataNotFound(_) => false,
+            // true, as this is a stable property of the transcript
+            Self::SignerNotInTranscript => true,
+            // true, as this is a stable property of the arguments
+            Self::InvalidArgument(_) => true,
+            // true, as the share does not become valid through retrying
+            Self::VerificationError => true,
+        }
+    }
+}
+
 impl ErrorReproducibility for IDkgVerifyOpeningError {
     fn is_reproducible(&self) -> bool {
         match self {
@@ -0,0 +1,93 @@
+//! Traits providing the crypto component interface for verifiably encrypted
+//! threshold key derivation (vetKD).
+//!
+//! A subnet uses the threshold key of its high-threshold NI-DKG transcript as
+//! vetKD master key. For a derivation path and a derivation id, every node
+//! computes a share of the derived key that is encrypted under a transport
+//! public key chosen by the recipient. A threshold number of valid encrypted
+//! key shares can be publicly combined into the encrypted derived key, which
+//! only the holder of the transport secret key can decrypt. Neither the nodes
+//! nor anybody observing the shares learn the derived key.
+
+use ic_base_types::NodeId;
+use ic_types::crypto::vetkd::{
+    VetKdArgs, VetKdEncryptedKey, VetKdEncryptedKeyShare, VetKdKeyShareCombinationError,
+    VetKdKeyShareCreationError, VetKdKeyShareVerificationError, VetKdKeyVerificationError,
+};
+use std::collections::BTreeMap;
+
+/// A Crypto Component interface to derive vetKD keys.
+pub trait VetKdProtocol {
+    /// Creates this node's share of the key derived for `args`, encrypted
+    /// under `args.encryption_public_key`.
+    ///
+    /// The transcript identified by `args.ni_dkg_id` must have been loaded.
+    ///
+    /// # Errors
+    /// * `VetKdKeyShareCreationError::ThresholdSigDataNotFound` if the
+    ///   transcript was not loaded.
+    /// * `VetKdKeyShareCreationError::SecretKeyNotFound` if this node does not
+    ///   hold a share of the transcript's threshold key.
+    /// * `VetKdKeyShareCreationError::InvalidArgument` if the encryption
+    ///   public key is malformed.
+    /// * `VetKdKeyShareCreationError::TransientInternalError` if there is a
+    ///   transient error, e.g. an RPC error when calling a remote CSP vault.
+    fn create_encrypted_key_share(
+        &self,
+        args: VetKdArgs,
+    ) -> Result<VetKdEncryptedKeyShare, VetKdKeyShareCreationError>;
+
+    /// Verifies an encrypted key share created by `creator` for `args`.
+    ///
+    /// # Errors
+    /// * `VetKdKeyShareVerificationError::ThresholdSigDataNotFound` if the
+    ///   transcript was not loaded.
+    /// * `VetKdKeyShareVerificationError::SignerNotInTranscript` if `creator`
+    ///   does not hold a share of the transcript's threshold key.
+    /// * `VetKdKeyShareVerificationError::InvalidArgument` if the share or
+    ///   the encryption public key are malformed.
+    /// * `VetKdKeyShareVerificationError::VerificationError` if the share is
+    ///   invalid.
+    fn verify_encrypted_key_share(
+        &self,
+        creator: NodeId,
+        key_share: &VetKdEncryptedKeyShare,
+        args: &VetKdArgs,
+    ) -> Result<(), VetKdKeyShareVerificationError>;
+
+    /// Combines encrypted key shares into an encrypted key.
+    ///
+    /// The shares should have been verified before. The combined key is
+    /// verified, so that an invalid share results in an error.
+    ///
+    /// # Errors
+    /// * `VetKdKeyShareCombinationError::ThresholdSigDataNotFound` if the
+    ///   transcript was not loaded.
+    /// * `VetKdKeyShareCombinationError::UnsatisfiedReconstructionThreshold`
+    ///   if there are fewer shares than the transcript's threshold.
+    /// * `VetKdKeyShareCombinationError::SignerNotInTranscript` if any of the
+    ///   creators does not hold a share of the transcript's threshold key.
+    /// * `VetKdKeyShareCombinationError::InvalidArgument` if any of the shares
+    ///   or the encryption public key are malformed.
+    /// * `VetKdKeyShareCombinationError::CombinationError` if the combined key
+    ///   is invalid.
+    fn combine_encrypted_key_shares(
+        &self,
+        shares: &BTreeMap<NodeId, VetKdEncryptedKeyShare>,
+        args: &VetKdArgs,
+    ) -> Result<VetKdEncryptedKey, VetKdKeyShareCombinationError>;
+
+    /// Verifies that `key` is an encryption of the key derived for `args`.
+    ///
+    /// # Errors
+    /// * `VetKdKeyVerificationError::ThresholdSigDataNotFound` if the
+    ///   transcript was not loaded.
+    /// * `VetKdKeyVerificationError::InvalidArgument` if the key or the
+    ///   encryption public key are malformed.
+    /// * `VetKdKeyVerificationError::VerificationError` if the key is invalid.
+    fn verify_encrypted_key(
+        &self,
+        key: &VetKdEncryptedKey,
+        args: &VetKdArgs,
+    ) -> Result<(), VetKdKeyVerificationError>;
+}
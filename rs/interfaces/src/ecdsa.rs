@@ -4,6 +4,7 @@ use crate::artifact_pool::UnvalidatedArtifact;
 use ic_types::artifact::EcdsaMessageId;
 use ic_types::consensus::ecdsa::{
     EcdsaComplaint, EcdsaMessage, EcdsaOpening, EcdsaPrefixOf, EcdsaSigShare, EcdsaStats,
+    VetKdKeyShare,
 };
 use ic_types::crypto::canister_threshold_sig::idkg::{IDkgDealingSupport, SignedIDkgDealing};
 
@@ -109,6 +110,17 @@ pub trait EcdsaPoolSection: Send + Sync {
     ) -> Box<dyn Iterator<Item = (EcdsaMessageId, EcdsaOpening)> + '_> {
         unimplemented!()
     }
+
+    /// Iterator for vetKD key share objects.
+    fn vetkd_key_shares(&self) -> Box<dyn Iterator<Item = (EcdsaMessageId, VetKdKeyShare)> + '_>;
+
+    /// Iterator for vetKD key share objects matching the prefix.
+    fn vetkd_key_shares_by_prefix(
+        &self,
+        _prefix: EcdsaPrefixOf<VetKdKeyShare>,
+    ) -> Box<dyn Iterator<Item = (EcdsaMessageId, VetKdKeyShare)> + '_> {
+        unimplemented!()
+    }
 }
 
 /// The mutable interface for validated/unvalidated parts of the artifact pool.
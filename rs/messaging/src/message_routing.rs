@@ -5,7 +5,7 @@ use crate::{
 use ic_config::execution_environment::{BitcoinConfig, Config as HypervisorConfig};
 use ic_constants::SMALL_APP_SUBNET_MAX_SIZE;
 use ic_cycles_account_manager::CyclesAccountManager;
-use ic_ic00_types::EcdsaKeyId;
+use ic_ic00_types::{EcdsaKeyId, VetKdKeyId};
 use ic_interfaces::{
     execution_environment::{IngressHistoryWriter, RegistryExecutionSettings, Scheduler},
     messaging::{MessageRouting, MessageRoutingError},
@@ -534,6 +534,7 @@ impl BatchProcessorImpl {
             let subnet_type = self.get_subnet_type(*subnet_id, registry_version);
             let subnet_features = self.get_subnet_features(*subnet_id, registry_version);
             let ecdsa_keys_held = self.get_ecdsa_keys_held(*subnet_id, registry_version);
+            let vetkd_keys_held = self.get_vetkd_keys_held(*subnet_id, registry_version);
             subnets.insert(
                 *subnet_id,
                 SubnetTopology {
@@ -542,6 +543,7 @@ impl BatchProcessorImpl {
                     subnet_type,
                     subnet_features,
                     ecdsa_keys_held,
+                    vetkd_keys_held,
                 },
             );
         }
@@ -647,6 +649,20 @@ impl BatchProcessorImpl {
             .unwrap_or_default()
     }
 
+    fn get_vetkd_keys_held(
+        &self,
+        subnet_id: SubnetId,
+        registry_version: RegistryVersion,
+    ) -> BTreeSet<VetKdKeyId> {
+        let record = self.get_subnet_record(subnet_id, registry_version);
+        record
+            .vetkd_config
+            .and_then(|vetkd_config| vetkd_config.key_id)
+            .map(|k| VetKdKeyId::try_from(k).expect("Could not read VetKdKeyId from protobuf"))
+            .into_iter()
+            .collect()
+    }
+
     fn get_max_number_of_canisters(
         &self,
         subnet_id: SubnetId,
@@ -664,6 +680,15 @@ impl BatchProcessorImpl {
         let record = self.get_subnet_record(subnet_id, registry_version);
         record.ecdsa_config.map(|c| c.max_queue_size).unwrap_or(0)
     }
+
+    fn get_max_vetkd_queue_size(
+        &self,
+        subnet_id: SubnetId,
+        registry_version: RegistryVersion,
+    ) -> u32 {
+        let record = self.get_subnet_record(subnet_id, registry_version);
+        record.vetkd_config.map(|c| c.max_queue_size).unwrap_or(0)
+    }
 }
 
 fn get_subnet_public_key(
@@ -734,6 +759,8 @@ impl BatchProcessor for BatchProcessorImpl {
             self.get_max_number_of_canisters(state.metadata.own_subnet_id, registry_version);
         let max_ecdsa_queue_size =
             self.get_max_ecdsa_queue_size(state.metadata.own_subnet_id, registry_version);
+        let max_vetkd_queue_size =
+            self.get_max_vetkd_queue_size(state.metadata.own_subnet_id, registry_version);
 
         let subnet_size = network_topology
             .get_subnet_size(&state.metadata.own_subnet_id)
@@ -756,6 +783,7 @@ impl BatchProcessor for BatchProcessorImpl {
                 max_number_of_canisters,
                 provisional_whitelist,
                 max_ecdsa_queue_size,
+                max_vetkd_queue_size,
                 subnet_size,
             },
         );
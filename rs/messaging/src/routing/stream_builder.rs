@@ -35,6 +35,11 @@ struct StreamBuilderMetrics {
     pub stream_bytes: IntGaugeVec,
     /// Stream begin, by destination subnet.
     pub stream_begin: IntGaugeVec,
+    /// Capacity advertised by the remote subnet, by destination subnet.
+    pub stream_remote_capacity_bytes: IntGaugeVec,
+    /// Output queues held back due to the remote subnet's advertised capacity
+    /// having been exhausted, by destination subnet.
+    pub throttled_queues: IntCounterVec,
     /// Routed XNet messages, by type and status.
     pub routed_messages: IntCounterVec,
     /// Successfully routed XNet messages' total payload size.
@@ -62,6 +67,8 @@ const MAX_STREAM_MESSAGES: usize = 50_000;
 const METRIC_STREAM_MESSAGES: &str = "mr_stream_messages";
 const METRIC_STREAM_BYTES: &str = "mr_stream_bytes";
 const METRIC_STREAM_BEGIN: &str = "mr_stream_begin";
+const METRIC_STREAM_REMOTE_CAPACITY_BYTES: &str = "mr_stream_remote_capacity_bytes";
+const METRIC_THROTTLED_QUEUES: &str = "mr_stream_throttled_queue_count";
 const METRIC_ROUTED_MESSAGES: &str = "mr_routed_message_count";
 const METRIC_ROUTED_PAYLOAD_SIZES: &str = "mr_routed_payload_size_bytes";
 
@@ -97,6 +104,16 @@ impl StreamBuilderMetrics {
             "Stream begin, by destination subnet",
             &[LABEL_REMOTE],
         );
+        let stream_remote_capacity_bytes = metrics_registry.int_gauge_vec(
+            METRIC_STREAM_REMOTE_CAPACITY_BYTES,
+            "Byte capacity advertised by the remote subnet, by destination subnet.",
+            &[LABEL_REMOTE],
+        );
+        let throttled_queues = metrics_registry.int_counter_vec(
+            METRIC_THROTTLED_QUEUES,
+            "Output queues held back because the remote subnet's advertised capacity was exhausted, by destination subnet.",
+            &[LABEL_REMOTE],
+        );
         let routed_messages = metrics_registry.int_counter_vec(
             METRIC_ROUTED_MESSAGES,
             "Routed XNet messages, by type and status.",
@@ -135,6 +152,8 @@ impl StreamBuilderMetrics {
             stream_messages,
             stream_bytes,
             stream_begin,
+            stream_remote_capacity_bytes,
+            throttled_queues,
             routed_messages,
             routed_payload_sizes,
             critical_error_infinite_loops,
@@ -288,6 +307,18 @@ impl StreamBuilderImpl {
                 && stream_messages_len >= 2 * SYSTEM_SUBNET_STREAM_MSG_LIMIT
         }
 
+        /// Tests whether a stream holds at least as many bytes as the capacity last
+        /// advertised by the remote subnet. Responses are never held back, as the
+        /// remote subnet has already reserved memory for them.
+        fn is_over_remote_capacity(stream: Option<&Stream>) -> bool {
+            match stream {
+                Some(stream) => stream
+                    .remote_capacity_bytes()
+                    .map_or(false, |capacity| stream.count_bytes() as u64 >= capacity),
+                None => false,
+            }
+        }
+
         let mut streams = state.take_streams();
         let routing_table = state.routing_table();
         let subnet_types: BTreeMap<_, _> = state
@@ -343,6 +374,19 @@ impl StreamBuilderImpl {
                         continue;
                     }
 
+                    if matches!(msg, RequestOrResponse::Request(_))
+                        && is_over_remote_capacity(streams.get(&dst_net_id))
+                    {
+                        // The remote subnet cannot accept any more requests, hold back
+                        // this queue (to preserve ordering) until it advertises capacity.
+                        self.metrics
+                            .throttled_queues
+                            .with_label_values(&[&dst_net_id.to_string()])
+                            .inc();
+                        output_iter.exclude_queue();
+                        continue;
+                    }
+
                     // We will route (or reject) the message, pop it.
                     let mut msg = validated_next(&mut output_iter, (queue_id, &msg));
 
@@ -482,9 +526,10 @@ impl StreamBuilderImpl {
                     stream.messages().len(),
                     stream.count_bytes(),
                     stream.messages_begin(),
+                    stream.remote_capacity_bytes(),
                 )
             })
-            .for_each(|(subnet, len, size_bytes, begin)| {
+            .for_each(|(subnet, len, size_bytes, begin, remote_capacity_bytes)| {
                 self.metrics
                     .stream_messages
                     .with_label_values(&[&subnet])
@@ -497,6 +542,12 @@ impl StreamBuilderImpl {
                     .stream_begin
                     .with_label_values(&[&subnet])
                     .set(begin.get() as i64);
+                if let Some(remote_capacity_bytes) = remote_capacity_bytes {
+                    self.metrics
+                        .stream_remote_capacity_bytes
+                        .with_label_values(&[&subnet])
+                        .set(remote_capacity_bytes as i64);
+                }
             });
 
         {
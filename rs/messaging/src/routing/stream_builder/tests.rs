@@ -552,6 +552,57 @@ fn build_streams_impl_respects_message_limit() {
     build_streams_impl_respects_limits(4, 1_000_000, 4);
 }
 
+/// Tests that requests are held back in output queues (rather than rejected)
+/// once the capacity advertised by the remote subnet has been exhausted.
+#[test]
+fn build_streams_throttles_requests_over_remote_capacity() {
+    with_test_replica_logger(|log| {
+        let (stream_builder, mut provided_state, metrics_registry) = new_fixture(&log);
+        provided_state.metadata.network_topology.routing_table = Arc::new(RoutingTable::try_from(
+            btreemap! {
+                CanisterIdRange{ start: CanisterId::from(0), end: CanisterId::from(0xfff) } => REMOTE_SUBNET,
+            },
+        ).unwrap());
+
+        // The remote subnet has advertised zero capacity, i.e. it has no room left
+        // for requests from this subnet.
+        let mut streams = provided_state.take_streams();
+        streams
+            .get_mut_or_insert(REMOTE_SUBNET)
+            .set_remote_capacity_bytes(Some(0));
+        provided_state.put_streams(streams);
+
+        // 4 output queues, with requests to `REMOTE_SUBNET`.
+        let msgs = generate_messages_for_test(/* senders = */ 2, /* receivers = */ 2);
+        let provided_canister_states = canister_states_with_outputs(msgs);
+        provided_state.put_canister_states(provided_canister_states);
+
+        let expected_state = provided_state.clone();
+
+        // Act.
+        let result_state =
+            stream_builder.build_streams_impl(provided_state, usize::MAX, usize::MAX);
+
+        // No messages were routed or rejected.
+        assert_eq!(result_state, expected_state);
+        assert_eq!(
+            btreemap! {},
+            nonzero_values(fetch_int_counter_vec(
+                &metrics_registry,
+                METRIC_ROUTED_MESSAGES
+            ))
+        );
+        assert_eq!(
+            metric_vec(&[(&[(LABEL_REMOTE, &REMOTE_SUBNET.to_string())], 4)]),
+            fetch_int_counter_vec(&metrics_registry, METRIC_THROTTLED_QUEUES)
+        );
+        assert_eq!(
+            metric_vec(&[(&[(LABEL_REMOTE, &REMOTE_SUBNET.to_string())], 0)]),
+            fetch_int_gauge_vec(&metrics_registry, METRIC_STREAM_REMOTE_CAPACITY_BYTES)
+        );
+    });
+}
+
 // Tests that messages addressed to canisters not mapped to a known subnet
 // result in reject Responses.
 #[test]
@@ -49,6 +49,8 @@ struct StreamHandlerMetrics {
     /// Backlog of XNet messages based on end in stream header and last message
     /// in slice, per subnet.
     pub xnet_message_backlog: IntGaugeVec,
+    /// Byte capacity advertised to each remote subnet.
+    pub advertised_capacity_bytes: IntGaugeVec,
     /// Critical error counter (see [`MetricsRegistry::error_counter`]) tracking the
     /// receival of reject signals for requests.
     pub critical_error_reject_signals_for_request: IntCounter,
@@ -71,6 +73,7 @@ const METRIC_GCED_XNET_MESSAGES: &str = "mr_gced_xnet_message_count";
 const METRIC_GCED_XNET_REJECT_SIGNALS: &str = "mr_gced_xnet_reject_signal_count";
 
 const METRIC_XNET_MESSAGE_BACKLOG: &str = "mr_xnet_message_backlog";
+const METRIC_ADVERTISED_CAPACITY_BYTES: &str = "mr_stream_advertised_capacity_bytes";
 
 const LABEL_STATUS: &str = "status";
 const LABEL_VALUE_SUCCESS: &str = "success";
@@ -116,6 +119,11 @@ impl StreamHandlerMetrics {
             "Backlog of XNet messages, by sending subnet.",
             &[LABEL_REMOTE],
         );
+        let advertised_capacity_bytes = metrics_registry.int_gauge_vec(
+            METRIC_ADVERTISED_CAPACITY_BYTES,
+            "Byte capacity for incoming messages advertised to remote subnets, by remote subnet.",
+            &[LABEL_REMOTE],
+        );
         let critical_error_reject_signals_for_request =
             metrics_registry.error_counter(CRITICAL_ERROR_REJECT_SIGNALS_FOR_REQUEST);
         let critical_error_induct_response_failed =
@@ -151,6 +159,7 @@ impl StreamHandlerMetrics {
             gced_xnet_messages,
             gced_xnet_reject_signals,
             xnet_message_backlog,
+            advertised_capacity_bytes,
             critical_error_reject_signals_for_request,
             critical_error_induct_response_failed,
             critical_error_sender_subnet_mismatch,
@@ -190,6 +199,10 @@ pub(crate) struct StreamHandlerImpl {
     /// Testing-only flag that forces generation of reject signals even if
     /// `CURRENT_CERTIFICATION_VERSION` is less than 9.
     testing_flag_generate_reject_signals: bool,
+
+    /// Testing-only flag that forces advertising capacity to remote subnets even
+    /// if `CURRENT_CERTIFICATION_VERSION` is less than 12.
+    testing_flag_advertise_capacity: bool,
 }
 
 impl StreamHandlerImpl {
@@ -212,6 +225,7 @@ impl StreamHandlerImpl {
             )),
             log,
             testing_flag_generate_reject_signals: false,
+            testing_flag_advertise_capacity: false,
         }
     }
 }
@@ -243,7 +257,10 @@ impl StreamHandler for StreamHandlerImpl {
         self.observe_backlog_durations(&stream_slices);
 
         // Induct the messages in `stream_slices`, updating signals as appropriate.
-        self.induct_stream_slices(state, stream_slices)
+        state = self.induct_stream_slices(state, stream_slices);
+
+        // Advertise the remaining capacity to remote subnets.
+        self.advertise_capacity(state)
     }
 }
 
@@ -350,6 +367,9 @@ impl StreamHandlerImpl {
                     );
                     self.garbage_collect_signals(&mut stream, *remote_subnet, stream_slice);
 
+                    // Record the capacity advertised by the remote subnet, if any.
+                    stream.set_remote_capacity_bytes(stream_slice.header().capacity_bytes);
+
                     // Reroute any rejected responses.
                     self.reroute_rejected_messages(
                         rejected_messages,
@@ -524,18 +544,7 @@ impl StreamHandlerImpl {
         mut state: ReplicatedState,
         stream_slices: BTreeMap<SubnetId, StreamSlice>,
     ) -> ReplicatedState {
-        let memory_taken = state.memory_taken();
-        let execution_memory_taken = memory_taken.execution();
-        let message_memory_taken = memory_taken.messages();
-        let wasm_custom_sections_memory_taken = memory_taken.wasm_custom_sections();
-        let subnet_available_memory = self.subnet_memory_capacity.get() as i64
-            - execution_memory_taken.get() as i64
-            - message_memory_taken.get() as i64
-            - wasm_custom_sections_memory_taken.get() as i64;
-        let subnet_available_message_memory =
-            self.subnet_message_memory_capacity.get() as i64 - message_memory_taken.get() as i64;
-        let mut subnet_available_memory =
-            subnet_available_memory.min(subnet_available_message_memory);
+        let mut subnet_available_memory = self.subnet_available_memory(&state);
         let mut streams = state.take_streams();
 
         for (remote_subnet_id, mut stream_slice) in stream_slices {
@@ -559,6 +568,62 @@ impl StreamHandlerImpl {
         state
     }
 
+    /// Computes the memory available for inducting messages into `state`, as the
+    /// minimum of available subnet memory and available subnet message memory.
+    /// May be negative.
+    fn subnet_available_memory(&self, state: &ReplicatedState) -> i64 {
+        let memory_taken = state.memory_taken();
+        let execution_memory_taken = memory_taken.execution();
+        let message_memory_taken = memory_taken.messages();
+        let wasm_custom_sections_memory_taken = memory_taken.wasm_custom_sections();
+        let subnet_available_memory = self.subnet_memory_capacity.get() as i64
+            - execution_memory_taken.get() as i64
+            - message_memory_taken.get() as i64
+            - wasm_custom_sections_memory_taken.get() as i64;
+        let subnet_available_message_memory =
+            self.subnet_message_memory_capacity.get() as i64 - message_memory_taken.get() as i64;
+        subnet_available_memory.min(subnet_available_message_memory)
+    }
+
+    /// Splits the memory available for inducting messages evenly across all
+    /// remote subnets that this subnet has streams with; and records each
+    /// share as the capacity to be advertised in the respective stream header.
+    ///
+    /// Remote subnets use the advertised capacity to hold back requests in
+    /// their output queues rather than have them rejected on induction.
+    /// Capacity is only advertised with certification versions 12 and up.
+    fn advertise_capacity(&self, mut state: ReplicatedState) -> ReplicatedState {
+        if state.metadata.certification_version < CertificationVersion::V12
+            && !self.testing_flag_advertise_capacity
+        {
+            return state;
+        }
+
+        let subnet_available_memory = self.subnet_available_memory(&state).max(0) as u64;
+        let mut streams = state.take_streams();
+        let remote_subnets: Vec<SubnetId> = streams
+            .keys()
+            .filter(|subnet_id| **subnet_id != self.subnet_id)
+            .cloned()
+            .collect();
+        if !remote_subnets.is_empty() {
+            let capacity_bytes = subnet_available_memory / remote_subnets.len() as u64;
+            for remote_subnet in remote_subnets {
+                // We know the stream exists, so it is safe to unwrap.
+                streams
+                    .get_mut(&remote_subnet)
+                    .unwrap()
+                    .set_capacity_bytes(Some(capacity_bytes));
+                self.metrics
+                    .advertised_capacity_bytes
+                    .with_label_values(&[&remote_subnet.to_string()])
+                    .set(capacity_bytes as i64);
+            }
+        }
+        state.put_streams(streams);
+        state
+    }
+
     /// Attempts to induct the given message at `stream_index` in the incoming
     /// stream from `remote_subnet_id` into `state`, producing a signal onto the
     /// provided reverse `stream`. The induction attempt will result in one of
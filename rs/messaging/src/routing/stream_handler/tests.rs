@@ -1143,6 +1143,48 @@ fn garbage_collect_local_state_success() {
     });
 }
 
+/// Tests that garbage collecting a provided `ReplicatedState` records the
+/// capacity advertised by the remote subnet in the stream header.
+#[test]
+fn garbage_collect_local_state_records_remote_capacity() {
+    with_test_replica_logger(|log| {
+        let (stream_handler, mut initial_state, _) = new_fixture(&log);
+        let mut expected_state = initial_state.clone();
+
+        let initial_stream = generate_outgoing_stream(StreamConfig {
+            messages_begin: 31,
+            message_count: 3,
+            signals_end: 43,
+            reject_signals: None,
+        });
+        initial_state.with_streams(btreemap![REMOTE_SUBNET => initial_stream]);
+
+        let mut stream_slice = generate_stream_slice(StreamSliceConfig {
+            header_begin: 42,
+            header_end: None,
+            messages_begin: 43,
+            message_count: 2,
+            signals_end: 33,
+            reject_signals: None,
+        });
+        stream_slice.header_mut().capacity_bytes = Some(1234);
+
+        let mut expected_stream = generate_outgoing_stream(StreamConfig {
+            messages_begin: 33,
+            message_count: 1,
+            signals_end: 43,
+            reject_signals: None,
+        });
+        expected_stream.set_remote_capacity_bytes(Some(1234));
+        expected_state.with_streams(btreemap![REMOTE_SUBNET => expected_stream]);
+
+        let pruned_state = stream_handler
+            .garbage_collect_local_state(initial_state, &btreemap![REMOTE_SUBNET => stream_slice]);
+
+        assert_eq!(pruned_state, expected_state);
+    });
+}
+
 /// Tests that garbage collecting a provided `ReplicatedState` results in all
 /// messages with matching signals being garbage collected  or rerouted, as
 /// appropriate.
@@ -2459,6 +2501,87 @@ fn induct_stream_slices_with_memory_limit_setup(
     (expected_state, expected_stream, stream_slice, request1)
 }
 
+/// Tests that `StreamHandlerImpl::advertise_capacity()` splits the available
+/// message memory evenly across remote streams, ignoring the loopback stream.
+#[test]
+fn advertise_capacity_splits_available_memory() {
+    with_test_replica_logger(|log| {
+        let (mut stream_handler, mut initial_state, metrics_registry) = new_fixture_with_config(
+            &log,
+            HypervisorConfig {
+                subnet_message_memory_capacity: NumBytes::new(10_000),
+                ..Default::default()
+            },
+        );
+        stream_handler.testing_flag_advertise_capacity = true;
+
+        let outgoing_stream = generate_outgoing_stream(StreamConfig {
+            messages_begin: 31,
+            message_count: 0,
+            signals_end: 43,
+            reject_signals: None,
+        });
+        let other_outgoing_stream = outgoing_stream.clone();
+        let loopback_stream = generate_loopback_stream(StreamConfig {
+            messages_begin: 21,
+            message_count: 0,
+            signals_end: 21,
+            reject_signals: None,
+        });
+        initial_state.with_streams(btreemap![
+            LOCAL_SUBNET => loopback_stream,
+            REMOTE_SUBNET => outgoing_stream,
+            CANISTER_MIGRATION_SUBNET => other_outgoing_stream,
+        ]);
+
+        let state = stream_handler.advertise_capacity(initial_state);
+
+        assert_eq!(
+            None,
+            state.get_stream(&LOCAL_SUBNET).unwrap().capacity_bytes()
+        );
+        for remote_subnet in [REMOTE_SUBNET, CANISTER_MIGRATION_SUBNET] {
+            assert_eq!(
+                Some(5_000),
+                state.get_stream(&remote_subnet).unwrap().capacity_bytes()
+            );
+        }
+        assert_eq!(
+            metric_vec(&[
+                (&[(LABEL_REMOTE, &REMOTE_SUBNET.to_string())], 5_000),
+                (
+                    &[(LABEL_REMOTE, &CANISTER_MIGRATION_SUBNET.to_string())],
+                    5_000
+                ),
+            ]),
+            fetch_int_gauge_vec(&metrics_registry, METRIC_ADVERTISED_CAPACITY_BYTES)
+        );
+    });
+}
+
+/// Tests that `StreamHandlerImpl::advertise_capacity()` is a no-op with
+/// certification versions below 12.
+#[test]
+fn advertise_capacity_disabled_before_v12() {
+    with_test_replica_logger(|log| {
+        let (stream_handler, mut initial_state, _) = new_fixture(&log);
+        initial_state.metadata.certification_version = CertificationVersion::V11;
+
+        let outgoing_stream = generate_outgoing_stream(StreamConfig {
+            messages_begin: 31,
+            message_count: 1,
+            signals_end: 43,
+            reject_signals: None,
+        });
+        initial_state.with_streams(btreemap![REMOTE_SUBNET => outgoing_stream]);
+        let expected_state = initial_state.clone();
+
+        let state = stream_handler.advertise_capacity(initial_state);
+
+        assert_eq!(expected_state, state);
+    });
+}
+
 /// Tests that messages in the loopback stream and incoming slices are inducted
 /// (with signals added appropriately); and messages present in the initial
 /// state are garbage collected or rerouted as appropriate.
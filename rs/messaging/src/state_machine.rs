@@ -120,6 +120,7 @@ impl StateMachine for StateMachineImpl {
             state_with_messages,
             batch.randomness,
             batch.ecdsa_subnet_public_keys,
+            batch.vetkd_subnet_public_keys,
             ExecutionRound::from(batch.batch_number.get()),
             execution_round_type,
             registry_settings,
@@ -1,4 +1,5 @@
 use candid::CandidType;
+use ic_ic00_types::CanisterSettingsArgs;
 use ic_nns_common::types::UpdateIcpXdrConversionRatePayload;
 use ic_types::{CanisterId, Cycles, PrincipalId, SubnetId};
 use icp_ledger::{
@@ -10,6 +11,15 @@ pub const DEFAULT_CYCLES_PER_XDR: u128 = 1_000_000_000_000u128; // 1T cycles = 1
 
 pub const CREATE_CANISTER_REFUND_FEE: Tokens = Tokens::from_e8s(DEFAULT_TRANSFER_FEE.get_e8s() * 4);
 pub const TOP_UP_CANISTER_REFUND_FEE: Tokens = Tokens::from_e8s(DEFAULT_TRANSFER_FEE.get_e8s() * 2);
+pub const MINT_CYCLES_REFUND_FEE: Tokens = Tokens::from_e8s(DEFAULT_TRANSFER_FEE.get_e8s() * 2);
+
+/// The minimum number of cycles that must be attached to a call to the
+/// create_canister endpoint.
+pub const CREATE_CANISTER_MIN_CYCLES: u128 = 100_000_000_000;
+
+/// The number of cycles kept by the cycles minting canister when a canister
+/// paid with attached cycles cannot be created.
+pub const BAD_REQUEST_CYCLES_PENALTY: u128 = 100_000_000;
 
 #[derive(Serialize, Deserialize, CandidType, Clone, Debug, PartialEq, Eq)]
 pub struct CyclesCanisterInitPayload {
@@ -17,6 +27,14 @@ pub struct CyclesCanisterInitPayload {
     pub governance_canister_id: CanisterId,
     pub minting_account_id: Option<AccountIdentifier>,
     pub last_purged_notification: Option<BlockIndex>,
+    pub cycles_ledger_canister_id: Option<CanisterId>,
+}
+
+/// Argument taken by the cycles minting canister on upgrade. Fields left unset
+/// keep their current value.
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug, PartialEq, Eq, Default)]
+pub struct CyclesCanisterUpgradePayload {
+    pub cycles_ledger_canister_id: Option<CanisterId>,
 }
 
 /// Argument taken by top up notification endpoint
@@ -26,12 +44,119 @@ pub struct NotifyTopUp {
     pub canister_id: CanisterId,
 }
 
+/// Argument taken by the mint cycles notification endpoint
+#[derive(Serialize, Deserialize, CandidType, Clone, Hash, Debug, PartialEq, Eq)]
+pub struct NotifyMintCycles {
+    pub block_index: BlockIndex,
+    /// The subaccount of the caller on the cycles ledger that receives the
+    /// cycles.
+    pub to_subaccount: Option<[u8; 32]>,
+    /// The memo of the deposit on the cycles ledger, at most 32 bytes long.
+    pub deposit_memo: Option<Vec<u8>>,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug, PartialEq, Eq)]
+pub struct NotifyMintCyclesSuccess {
+    /// The index of the deposit block on the cycles ledger.
+    pub block_index: candid::Nat,
+    /// The amount of cycles that were minted.
+    pub minted: candid::Nat,
+    /// The balance of the account on the cycles ledger after the deposit.
+    pub balance: candid::Nat,
+}
+
+/// An account on the cycles ledger, see the `deposit` endpoint of the cycles
+/// ledger.
+#[derive(Serialize, Deserialize, CandidType, Clone, Hash, Debug, PartialEq, Eq)]
+pub struct CyclesLedgerAccount {
+    pub owner: PrincipalId,
+    pub subaccount: Option<[u8; 32]>,
+}
+
+/// Argument of the `deposit` endpoint of the cycles ledger.
+#[derive(Serialize, Deserialize, CandidType, Clone, Hash, Debug, PartialEq, Eq)]
+pub struct CyclesLedgerDepositArgs {
+    pub to: CyclesLedgerAccount,
+    pub memo: Option<Vec<u8>>,
+}
+
+/// Result of the `deposit` endpoint of the cycles ledger.
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug, PartialEq, Eq)]
+pub struct CyclesLedgerDepositResult {
+    pub txid: candid::Nat,
+    pub balance: candid::Nat,
+}
+
+/// Determines on which subnet a new canister is created.
+#[derive(Serialize, Deserialize, CandidType, Clone, Hash, Debug, PartialEq, Eq)]
+pub enum SubnetSelection {
+    /// A random subnet of the given subnet type.
+    SubnetType(String),
+    /// The given subnet, which must be assigned to a subnet type or be one of
+    /// the subnets the controller is authorized to create canisters on.
+    Subnet(SubnetId),
+}
+
 /// Argument taken by create canister notification endpoint
 #[derive(Serialize, Deserialize, CandidType, Clone, Hash, Debug, PartialEq, Eq)]
 pub struct NotifyCreateCanister {
     pub block_index: BlockIndex,
     pub controller: PrincipalId,
+    /// Deprecated, use `subnet_selection` instead.
+    pub subnet_type: Option<String>,
+    pub subnet_selection: Option<SubnetSelection>,
+}
+
+/// Argument taken by the create_canister endpoint, the canister is paid for
+/// with the cycles attached to the call.
+#[derive(Deserialize, CandidType, Clone, Debug, Default)]
+pub struct CreateCanister {
+    /// The settings of the new canister. The caller is the only controller
+    /// if no controllers are set.
+    pub settings: Option<CanisterSettingsArgs>,
+    /// Deprecated, use `subnet_selection` instead.
     pub subnet_type: Option<String>,
+    pub subnet_selection: Option<SubnetSelection>,
+}
+
+/// Error for the create_canister endpoint
+#[derive(Serialize, Deserialize, CandidType, Clone, Hash, Debug, PartialEq, Eq)]
+pub enum CreateCanisterError {
+    /// The canister was not created and `refund_amount` cycles were returned
+    /// to the caller.
+    Refunded {
+        refund_amount: u128,
+        create_error: String,
+    },
+    /// The canister was not created and returning the cycles to the caller
+    /// failed as well.
+    RefundFailed {
+        create_error: String,
+        refund_error: String,
+    },
+}
+
+impl std::fmt::Display for CreateCanisterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Refunded {
+                refund_amount,
+                create_error,
+            } => write!(
+                f,
+                "Creating the canister failed, {} cycles were refunded: {}",
+                refund_amount, create_error
+            ),
+            Self::RefundFailed {
+                create_error,
+                refund_error,
+            } => write!(
+                f,
+                "Creating the canister failed: {}. Refunding the cycles failed: {}",
+                create_error, refund_error
+            ),
+        }
+    }
 }
 
 /// Error for notify endpoints
@@ -58,6 +183,8 @@ pub enum NotifyErrorCode {
     FailedToFetchBlock = 2,
     /// The cycles minting canister failed to execute the refund transaction.
     RefundFailed = 3,
+    /// The subnet selection of a create canister request is invalid.
+    BadSubnetSelection = 4,
 }
 
 impl NotifyError {
@@ -101,6 +228,7 @@ impl std::fmt::Display for NotifyError {
 
 pub const MEMO_CREATE_CANISTER: Memo = Memo(0x41455243); // == 'CREA'
 pub const MEMO_TOP_UP_CANISTER: Memo = Memo(0x50555054); // == 'TPUP'
+pub const MEMO_MINT_CYCLES: Memo = Memo(0x544e494d); // == 'MINT'
 
 pub fn create_canister_txn(
     amount: Tokens,
@@ -120,6 +248,24 @@ pub fn create_canister_txn(
     (send_args, sub_account)
 }
 
+pub fn mint_cycles_txn(
+    amount: Tokens,
+    from_subaccount: Option<Subaccount>,
+    cycles_canister_id: &CanisterId,
+    receiver_principal_id: &PrincipalId,
+) -> (SendArgs, Subaccount) {
+    let sub_account = receiver_principal_id.into();
+    let send_args = SendArgs {
+        memo: MEMO_MINT_CYCLES,
+        amount,
+        fee: DEFAULT_TRANSFER_FEE,
+        from_subaccount,
+        to: AccountIdentifier::new(*cycles_canister_id.get_ref(), Some(sub_account)),
+        created_at_time: None,
+    };
+    (send_args, sub_account)
+}
+
 pub fn top_up_canister_txn(
     amount: Tokens,
     from_subaccount: Option<Subaccount>,
@@ -289,6 +435,21 @@ pub struct SubnetTypesToSubnetsResponse {
     pub data: Vec<(String, Vec<SubnetId>)>,
 }
 
+/// A subnet type along with the subnets it is assigned to. The number of
+/// subnets is the capacity of the type, canisters created with the type are
+/// spread evenly over them.
+#[derive(Serialize, Deserialize, CandidType, Clone, PartialEq, Eq, Debug, Default)]
+pub struct SubnetTypeCapacity {
+    pub subnet_type: String,
+    pub subnets: Vec<SubnetId>,
+    pub num_subnets: u64,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Clone, PartialEq, Eq, Debug, Default)]
+pub struct AvailableSubnetTypesResponse {
+    pub data: Vec<SubnetTypeCapacity>,
+}
+
 #[derive(Serialize, Deserialize, CandidType, Clone, PartialEq, Eq, Debug, Default)]
 pub struct IcpXdrConversionRate {
     /// The time for which the market data was queried, expressed in UNIX epoch
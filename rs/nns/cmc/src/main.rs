@@ -4,7 +4,7 @@ use std::collections::{btree_map::Entry, BTreeMap, BTreeSet};
 use std::convert::TryInto;
 use std::time::{Duration, UNIX_EPOCH};
 
-use candid::{candid_method, CandidType, Encode};
+use candid::{candid_method, CandidType, Decode, Encode};
 use cycles_minting_canister::*;
 use dfn_candid::{candid_one, CandidOne};
 use dfn_core::{
@@ -43,6 +43,8 @@ pub const LABEL_AVERAGE_ICP_XDR_CONVERSION_RATE: &[u8] = b"AVERAGE_ICP_XDR_CONVE
 const MAX_NOTIFY_HISTORY: usize = 1_000_000;
 /// The maximum number of old notification statuses we purge in one go.
 const MAX_NOTIFY_PURGE: usize = 100_000;
+/// The maximum length of the memo of a deposit to the cycles ledger.
+const MAX_DEPOSIT_MEMO_LENGTH: usize = 32;
 
 /// The maturity modulation range in basis points.
 const MIN_MATURITY_MODULATION_PERMYRIAD: i32 = -500;
@@ -73,6 +75,8 @@ enum NotificationStatus {
     NotifiedTopUp(Result<Cycles, NotifyError>),
     /// The cached result of a completed canister creation.
     NotifiedCreateCanister(Result<CanisterId, NotifyError>),
+    /// The cached result of a completed deposit to the cycles ledger.
+    NotifiedMint(Result<NotifyMintCyclesSuccess, NotifyError>),
 }
 
 #[derive(Serialize, Deserialize, Clone, CandidType, Eq, PartialEq, Debug)]
@@ -134,6 +138,10 @@ struct State {
     /// Each subnet can be assigned to at most one type and cannot be a default
     /// or an authorized subnet.
     subnet_types_to_subnets: Option<BTreeMap<String, BTreeSet<SubnetId>>>,
+
+    /// The cycles ledger that receives the cycles minted by
+    /// `notify_mint_cycles`.
+    cycles_ledger_canister_id: Option<CanisterId>,
 }
 
 impl State {
@@ -201,6 +209,7 @@ impl Default for State {
             last_purged_notification: Some(0),
             maturity_modulation_permyriad: Some(0),
             subnet_types_to_subnets: Some(BTreeMap::new()),
+            cycles_ledger_canister_id: None,
         }
     }
 }
@@ -234,6 +243,7 @@ fn init(args: CyclesCanisterInitPayload) {
         state.governance_canister_id = args.governance_canister_id;
         state.minting_account_id = args.minting_account_id;
         state.last_purged_notification = args.last_purged_notification;
+        state.cycles_ledger_canister_id = args.cycles_ledger_canister_id;
     });
 }
 
@@ -543,6 +553,32 @@ fn get_subnet_types_to_subnets_() {
     over(candid_one, |_: ()| get_subnet_types_to_subnets())
 }
 
+#[candid_method(query, rename = "get_available_subnet_types")]
+fn get_available_subnet_types() -> AvailableSubnetTypesResponse {
+    with_state(|state| {
+        let data = state
+            .subnet_types_to_subnets
+            .as_ref()
+            .expect("subnet types to subnets mapping is not `None`")
+            .iter()
+            .map(|(subnet_type, subnets)| SubnetTypeCapacity {
+                subnet_type: subnet_type.clone(),
+                subnets: subnets.iter().copied().collect(),
+                num_subnets: subnets.len() as u64,
+            })
+            .collect();
+
+        AvailableSubnetTypesResponse { data }
+    })
+}
+
+/// Retrieves the subnet types that can be selected when creating a canister,
+/// along with the subnets each of them spans.
+#[export_name = "canister_query get_available_subnet_types"]
+fn get_available_subnet_types_() {
+    over(candid_one, |_: ()| get_available_subnet_types())
+}
+
 /// Constructs a hash tree that can be used to certify requests for the
 /// conversion rate (both the current and the average, if they are set).
 ///
@@ -907,6 +943,16 @@ fn notify_top_up_() {
     over_async(candid_one, notify_top_up)
 }
 
+#[export_name = "canister_update notify_mint_cycles"]
+fn notify_mint_cycles_() {
+    over_async(candid_one, notify_mint_cycles)
+}
+
+#[export_name = "canister_update create_canister"]
+fn create_canister_() {
+    over_async(candid_one, create_canister)
+}
+
 #[export_name = "canister_update notify_create_canister"]
 fn notify_create_canister_() {
     over_async(candid_one, notify_create_canister)
@@ -957,6 +1003,9 @@ async fn notify_top_up(
                         "The same payment is already processed as create canister request".into(),
                     )))
                 }
+                NotificationStatus::NotifiedMint(_) => Some(Err(NotifyError::InvalidTransaction(
+                    "The same payment is already processed as mint cycles request".into(),
+                ))),
             },
             Entry::Vacant(entry) => {
                 entry.insert(NotificationStatus::Processing);
@@ -985,6 +1034,94 @@ async fn notify_top_up(
     }
 }
 
+/// Notify about a payment for cycles that are deposited to the cycles ledger
+/// account of the caller. The payment must be sent to the subaccount of the
+/// caller.
+///
+/// # Arguments
+///
+/// * `block_index` -  The index of the block you would like to send a
+///   notification about.
+/// * `to_subaccount` - The subaccount of the caller on the cycles ledger.
+/// * `deposit_memo` - The memo of the deposit on the cycles ledger.
+#[candid_method(update, rename = "notify_mint_cycles")]
+async fn notify_mint_cycles(
+    NotifyMintCycles {
+        block_index,
+        to_subaccount,
+        deposit_memo,
+    }: NotifyMintCycles,
+) -> Result<NotifyMintCyclesSuccess, NotifyError> {
+    if let Some(memo) = &deposit_memo {
+        if memo.len() > MAX_DEPOSIT_MEMO_LENGTH {
+            return Err(NotifyError::InvalidTransaction(format!(
+                "The deposit memo is {} bytes long, at most {} bytes are allowed",
+                memo.len(),
+                MAX_DEPOSIT_MEMO_LENGTH
+            )));
+        }
+    }
+
+    let caller = caller();
+    let cmc_id = dfn_core::api::id();
+    let sub = Subaccount::from(&caller);
+    let expected_to = AccountIdentifier::new(cmc_id.get(), Some(sub));
+
+    let (amount, from) = fetch_transaction(block_index, expected_to, MEMO_MINT_CYCLES).await?;
+
+    let maybe_early_result = with_state_mut(|state| {
+        state.purge_old_notifications(MAX_NOTIFY_HISTORY);
+
+        if block_index <= state.last_purged_notification.unwrap() {
+            return Some(Err(NotifyError::TransactionTooOld(
+                state.last_purged_notification.unwrap() + 1,
+            )));
+        }
+
+        match state.blocks_notified.as_mut().unwrap().entry(block_index) {
+            Entry::Occupied(entry) => match entry.get() {
+                NotificationStatus::Processing => Some(Err(NotifyError::Processing)),
+                NotificationStatus::NotifiedMint(resp) => Some(resp.clone()),
+                NotificationStatus::NotifiedTopUp(_) => Some(Err(NotifyError::InvalidTransaction(
+                    "The same payment is already processed as a top up request.".into(),
+                ))),
+                NotificationStatus::NotifiedCreateCanister(_) => {
+                    Some(Err(NotifyError::InvalidTransaction(
+                        "The same payment is already processed as create canister request".into(),
+                    )))
+                }
+            },
+            Entry::Vacant(entry) => {
+                entry.insert(NotificationStatus::Processing);
+                None
+            }
+        }
+    });
+
+    match maybe_early_result {
+        Some(result) => result,
+        None => {
+            let to = CyclesLedgerAccount {
+                owner: caller,
+                subaccount: to_subaccount,
+            };
+            let result = process_mint_cycles(to, deposit_memo, sub, from, amount).await;
+
+            with_state_mut(|state| {
+                state.blocks_notified.as_mut().unwrap().insert(
+                    block_index,
+                    NotificationStatus::NotifiedMint(result.clone()),
+                );
+                if is_transient_error(&result) {
+                    state.blocks_notified.as_mut().unwrap().remove(&block_index);
+                }
+            });
+
+            result
+        }
+    }
+}
+
 /// Notify about create canister transaction
 ///
 /// # Arguments
@@ -998,8 +1135,17 @@ async fn notify_create_canister(
         block_index,
         controller,
         subnet_type,
+        subnet_selection,
     }: NotifyCreateCanister,
 ) -> Result<CanisterId, NotifyError> {
+    let subnet_selection =
+        merge_subnet_selection(subnet_type, subnet_selection).map_err(|error_message| {
+            NotifyError::Other {
+                error_code: NotifyErrorCode::BadSubnetSelection as u64,
+                error_message,
+            }
+        })?;
+
     let cmc_id = dfn_core::api::id();
     let sub = Subaccount::from(&controller);
     let expected_to = AccountIdentifier::new(cmc_id.get(), Some(sub));
@@ -1022,6 +1168,9 @@ async fn notify_create_canister(
                 NotificationStatus::NotifiedTopUp(_) => Some(Err(NotifyError::InvalidTransaction(
                     "The same payment is already processed as a top up request.".into(),
                 ))),
+                NotificationStatus::NotifiedMint(_) => Some(Err(NotifyError::InvalidTransaction(
+                    "The same payment is already processed as a mint cycles request.".into(),
+                ))),
             },
             Entry::Vacant(entry) => {
                 entry.insert(NotificationStatus::Processing);
@@ -1033,7 +1182,7 @@ async fn notify_create_canister(
     match maybe_early_result {
         Some(result) => result,
         None => {
-            let result = process_create_canister(controller, from, amount, subnet_type).await;
+            let result = process_create_canister(controller, from, amount, subnet_selection).await;
 
             with_state_mut(|state| {
                 state.blocks_notified.as_mut().unwrap().insert(
@@ -1100,6 +1249,7 @@ fn memo_to_intent_str(memo: Memo) -> String {
     match memo {
         MEMO_CREATE_CANISTER => "CreateCanister".into(),
         MEMO_TOP_UP_CANISTER => "TopUp".into(),
+        MEMO_MINT_CYCLES => "MintCycles".into(),
         _ => "unrecognized".into(),
     }
 }
@@ -1180,6 +1330,9 @@ async fn transaction_notification(tn: TransactionNotification) -> Result<CyclesR
                 NotificationStatus::NotifiedCreateCanister(resp) => {
                     Err(format!("Already notified: {:?}", resp))
                 }
+                NotificationStatus::NotifiedMint(resp) => {
+                    Err(format!("Already notified: {:?}", resp))
+                }
             },
             Entry::Vacant(entry) => {
                 entry.insert(NotificationStatus::Processing);
@@ -1298,7 +1451,7 @@ async fn process_create_canister(
     controller: PrincipalId,
     from: AccountIdentifier,
     amount: Tokens,
-    subnet_type: Option<String>,
+    subnet_selection: Option<SubnetSelection>,
 ) -> Result<CanisterId, NotifyError> {
     let cycles = tokens_to_cycles(amount)?;
 
@@ -1312,7 +1465,8 @@ async fn process_create_canister(
     // Create the canister. If this fails, refund. Either way,
     // return a result so that the notification cannot be retried.
     // If refund fails, we allow to retry.
-    match create_canister(controller, cycles, subnet_type).await {
+    let settings = CanisterSettingsArgs::new(Some(vec![controller]), None, None, None);
+    match do_create_canister(controller, settings, cycles, subnet_selection, true).await {
         Ok(canister_id) => {
             burn_and_log(sub, amount).await;
             Ok(canister_id)
@@ -1341,7 +1495,7 @@ async fn process_top_up(
         canister_id, cycles
     ));
 
-    match deposit_cycles(canister_id, cycles).await {
+    match deposit_cycles(canister_id, cycles, true).await {
         Ok(()) => {
             burn_and_log(sub, amount).await;
             Ok(cycles)
@@ -1356,6 +1510,77 @@ async fn process_top_up(
     }
 }
 
+async fn process_mint_cycles(
+    to: CyclesLedgerAccount,
+    deposit_memo: Option<Vec<u8>>,
+    sub: Subaccount,
+    from: AccountIdentifier,
+    amount: Tokens,
+) -> Result<NotifyMintCyclesSuccess, NotifyError> {
+    let cycles = tokens_to_cycles(amount)?;
+
+    print(format!(
+        "Depositing {} cycles to {:?} on the cycles ledger.",
+        cycles, to
+    ));
+
+    let deposit_result = match with_state(|state| state.cycles_ledger_canister_id) {
+        Some(cycles_ledger_canister_id) => {
+            deposit_to_cycles_ledger(cycles_ledger_canister_id, to, deposit_memo, cycles).await
+        }
+        None => Err("The cycles ledger canister id is not set.".to_string()),
+    };
+
+    match deposit_result {
+        Ok(CyclesLedgerDepositResult { txid, balance }) => {
+            burn_and_log(sub, amount).await;
+            Ok(NotifyMintCyclesSuccess {
+                block_index: txid,
+                minted: candid::Nat::from(cycles.get()),
+                balance,
+            })
+        }
+        Err(err) => {
+            let refund_block = refund(sub, from, amount, MINT_CYCLES_REFUND_FEE).await?;
+            Err(NotifyError::Refunded {
+                reason: err,
+                block_index: refund_block,
+            })
+        }
+    }
+}
+
+async fn deposit_to_cycles_ledger(
+    cycles_ledger_canister_id: CanisterId,
+    to: CyclesLedgerAccount,
+    memo: Option<Vec<u8>>,
+    cycles: Cycles,
+) -> Result<CyclesLedgerDepositResult, String> {
+    let attached_cycles: u64 = u128::from(cycles).try_into().map_err(|_| {
+        format!(
+            "{} cycles cannot be attached to a single call to the cycles ledger.",
+            cycles
+        )
+    })?;
+    ensure_balance(cycles)?;
+    let result: Result<CyclesLedgerDepositResult, (Option<i32>, String)> =
+        dfn_core::api::call_with_funds_and_cleanup(
+            cycles_ledger_canister_id,
+            "deposit",
+            dfn_candid::candid_one,
+            CyclesLedgerDepositArgs { to, memo },
+            dfn_core::api::Funds::new(attached_cycles),
+        )
+        .await;
+    result.map_err(|(code, msg)| {
+        format!(
+            "Depositing cycles to the cycles ledger failed with code {}: {:?}",
+            code.unwrap_or_default(),
+            msg
+        )
+    })
+}
+
 /// Attempt to burn the funds.
 /// Burning doesn't return errors - we don't want to reject the transaction
 /// notification because then it could be retried.
@@ -1450,8 +1675,14 @@ async fn refund(
     Ok(refund_block_index)
 }
 
-async fn deposit_cycles(canister_id: CanisterId, cycles: Cycles) -> Result<(), String> {
-    ensure_balance(cycles)?;
+async fn deposit_cycles(
+    canister_id: CanisterId,
+    cycles: Cycles,
+    mint_cycles: bool,
+) -> Result<(), String> {
+    if mint_cycles {
+        ensure_balance(cycles)?;
+    }
 
     let res: Result<(), (Option<i32>, String)> = dfn_core::api::call_with_funds_and_cleanup(
         IC_00,
@@ -1473,10 +1704,112 @@ async fn deposit_cycles(canister_id: CanisterId, cycles: Cycles) -> Result<(), S
     Ok(())
 }
 
+/// Creates a canister paid for with the cycles attached to the call. All
+/// attached cycles are sent to the new canister. If the creation fails, the cycles are returned to the caller
+/// minus `BAD_REQUEST_CYCLES_PENALTY`.
+#[candid_method(update, rename = "create_canister")]
 async fn create_canister(
+    CreateCanister {
+        settings,
+        subnet_type,
+        subnet_selection,
+    }: CreateCanister,
+) -> Result<CanisterId, CreateCanisterError> {
+    let caller = caller();
+    let cycles = dfn_core::api::msg_cycles_available128();
+    if cycles < CREATE_CANISTER_MIN_CYCLES {
+        return Err(CreateCanisterError::Refunded {
+            refund_amount: cycles,
+            create_error: format!(
+                "Insufficient cycles attached: {} cycles, at least {} cycles are required.",
+                cycles, CREATE_CANISTER_MIN_CYCLES
+            ),
+        });
+    }
+    if u64::try_from(cycles).is_err() {
+        return Err(CreateCanisterError::Refunded {
+            refund_amount: cycles,
+            create_error: format!("Too many cycles attached: {} cycles.", cycles),
+        });
+    }
+    let subnet_selection = match merge_subnet_selection(subnet_type, subnet_selection) {
+        Ok(subnet_selection) => subnet_selection,
+        Err(create_error) => {
+            return Err(CreateCanisterError::Refunded {
+                refund_amount: cycles,
+                create_error,
+            })
+        }
+    };
+
+    let mut settings = settings.unwrap_or_default();
+    if settings.controllers.is_none() {
+        settings.controllers = Some(vec![caller]);
+    }
+
+    // The attached cycles become part of the balance of this canister, from
+    // which they are sent along with the call to the management canister.
+    let accepted = dfn_core::api::msg_cycles_accept128((cycles >> 64) as u64, cycles as u64);
+    assert_eq!(accepted, cycles);
+
+    print(format!(
+        "Creating canister for {} with {} cycles.",
+        caller, cycles,
+    ));
+
+    match do_create_canister(
+        caller,
+        settings,
+        Cycles::new(cycles),
+        subnet_selection,
+        false,
+    )
+    .await
+    {
+        Ok(canister_id) => Ok(canister_id),
+        Err(create_error) => {
+            let refund_amount = cycles.saturating_sub(BAD_REQUEST_CYCLES_PENALTY);
+            // Only canisters can attach cycles to a call.
+            let caller_canister = CanisterId::new(caller).expect("caller is not a canister");
+            match deposit_cycles(caller_canister, Cycles::new(refund_amount), false).await {
+                Ok(()) => Err(CreateCanisterError::Refunded {
+                    refund_amount,
+                    create_error,
+                }),
+                Err(refund_error) => Err(CreateCanisterError::RefundFailed {
+                    create_error,
+                    refund_error,
+                }),
+            }
+        }
+    }
+}
+
+/// Combines the deprecated `subnet_type` argument with `subnet_selection`,
+/// at most one of them may be set.
+fn merge_subnet_selection(
+    subnet_type: Option<String>,
+    subnet_selection: Option<SubnetSelection>,
+) -> Result<Option<SubnetSelection>, String> {
+    match (subnet_type, subnet_selection) {
+        (Some(_), Some(_)) => {
+            Err("Only one of subnet_type and subnet_selection can be set.".to_string())
+        }
+        (Some(subnet_type), None) => Ok(Some(SubnetSelection::SubnetType(subnet_type))),
+        (None, subnet_selection) => Ok(subnet_selection),
+    }
+}
+
+/// Creates a canister with the given settings on one of the subnets eligible
+/// for `subnet_selection`, see `get_subnets_for_selection`. If `mint_cycles`
+/// is set, the cycles of the new canister are minted, otherwise they must
+/// already be part of the balance of this canister.
+async fn do_create_canister(
     controller_id: PrincipalId,
+    settings: CanisterSettingsArgs,
     cycles: Cycles,
-    subnet_type: Option<String>,
+    subnet_selection: Option<SubnetSelection>,
+    mint_cycles: bool,
 ) -> Result<CanisterId, String> {
     // Retrieve randomness from the system to use later to get a random
     // permutation of subnets. Performing the asynchronous call before
@@ -1484,25 +1817,7 @@ async fn create_canister(
     // subnets change in the meantime.
     let mut rng = get_rng().await?;
 
-    // If subnet_type is `Some`, then use it to determine the eligible list
-    // of subnets. Otherwise, fall back to the list of subnets for the
-    // provided controller id.
-    let mut subnets: Vec<SubnetId> = match subnet_type {
-        Some(subnet_type) => with_state(|state| {
-            let subnet_types_to_subnets = state
-                .subnet_types_to_subnets
-                .as_ref()
-                .expect("subnet types to subnets mapping is not `None`");
-            match subnet_types_to_subnets.get(&subnet_type) {
-                Some(s) => Ok(s.iter().copied().collect()),
-                None => Err(format!(
-                    "Provided subnet type {} does not exist",
-                    subnet_type
-                )),
-            }
-        }),
-        None => Ok(get_subnets_for(&controller_id)),
-    }?;
+    let mut subnets = get_subnets_for_selection(&controller_id, subnet_selection)?;
 
     // Perform a random permutation of the eligible list of subnets to ensure
     // that we load balance canister creations among them.
@@ -1510,7 +1825,7 @@ async fn create_canister(
 
     let mut last_err = None;
 
-    if !subnets.is_empty() {
+    if mint_cycles && !subnets.is_empty() {
         // TODO(NNS1-503): If CreateCanister fails, then we still have minted
         // these cycles.
         ensure_balance(cycles)?;
@@ -1522,12 +1837,7 @@ async fn create_canister(
             &Method::CreateCanister.to_string(),
             dfn_candid::candid_one,
             CreateCanisterArgs {
-                settings: Some(CanisterSettingsArgs::new(
-                    Some(vec![controller_id]),
-                    None,
-                    None,
-                    None,
-                )),
+                settings: Some(settings.clone()),
                 sender_canister_version: Some(dfn_core::api::canister_version()),
             },
             dfn_core::api::Funds::new(cycles.get().try_into().unwrap()),
@@ -1597,6 +1907,51 @@ fn total_supply_() {
     })
 }
 
+/// Return the list of subnets in which a canister for this controller may be
+/// created given the `subnet_selection`. Without a selection, these are the
+/// subnets the controller is authorized to use. A subnet type selects all
+/// subnets of that type. An explicit subnet must be of some type or be one
+/// of the subnets the controller is authorized to use.
+fn get_subnets_for_selection(
+    controller_id: &PrincipalId,
+    subnet_selection: Option<SubnetSelection>,
+) -> Result<Vec<SubnetId>, String> {
+    match subnet_selection {
+        Some(SubnetSelection::SubnetType(subnet_type)) => with_state(|state| {
+            let subnet_types_to_subnets = state
+                .subnet_types_to_subnets
+                .as_ref()
+                .expect("subnet types to subnets mapping is not `None`");
+            match subnet_types_to_subnets.get(&subnet_type) {
+                Some(s) => Ok(s.iter().copied().collect()),
+                None => Err(format!(
+                    "Provided subnet type {} does not exist",
+                    subnet_type
+                )),
+            }
+        }),
+        Some(SubnetSelection::Subnet(subnet_id)) => {
+            let is_typed = with_state(|state| {
+                state
+                    .subnet_types_to_subnets
+                    .as_ref()
+                    .expect("subnet types to subnets mapping is not `None`")
+                    .values()
+                    .any(|subnets| subnets.contains(&subnet_id))
+            });
+            if is_typed || get_subnets_for(controller_id).contains(&subnet_id) {
+                Ok(vec![subnet_id])
+            } else {
+                Err(format!(
+                    "Controller {} is not authorized to create canisters on subnet {}",
+                    controller_id, subnet_id
+                ))
+            }
+        }
+        None => Ok(get_subnets_for(controller_id)),
+    }
+}
+
 /// Return the list of subnets in which this controller is allowed to create
 /// canisters
 fn get_subnets_for(controller_id: &PrincipalId) -> Vec<SubnetId> {
@@ -1641,7 +1996,15 @@ fn pre_upgrade() {
 
 #[export_name = "canister_post_upgrade"]
 fn post_upgrade() {
-    over_init(|_: BytesS| {
+    over_init(|BytesS(arg)| {
+        // Upgrades without an argument keep the state as it is.
+        let args = if arg.is_empty() {
+            None
+        } else {
+            Decode!(&arg, Option<CyclesCanisterUpgradePayload>)
+                .expect("Failed to decode the upgrade argument")
+        };
+
         let bytes = stable::get();
         print(format!(
             "[cycles] deserializing state after upgrade ({} bytes)",
@@ -1652,6 +2015,15 @@ fn post_upgrade() {
         if new_state.subnet_types_to_subnets.is_none() {
             new_state.subnet_types_to_subnets = Some(BTreeMap::new());
         }
+        if let Some(args) = args {
+            print(format!(
+                "[cycles] post_upgrade() with cycles ledger canister {:?}",
+                args.cycles_ledger_canister_id
+            ));
+            if let Some(cycles_ledger_canister_id) = args.cycles_ledger_canister_id {
+                new_state.cycles_ledger_canister_id = Some(cycles_ledger_canister_id);
+            }
+        }
 
         STATE.with(|state| state.replace(Some(new_state)));
     })
@@ -1710,6 +2082,7 @@ mod tests {
             governance_canister_id: CanisterId::ic_00(),
             minting_account_id: None,
             last_purged_notification: Some(0),
+            cycles_ledger_canister_id: None,
         })
     }
 
@@ -1743,7 +2116,16 @@ mod tests {
             )
             .unwrap())),
         );
+        blocks_notified.insert(
+            61,
+            NotificationStatus::NotifiedMint(Ok(NotifyMintCyclesSuccess {
+                block_index: candid::Nat::from(7u64),
+                minted: candid::Nat::from(1_000_000u64),
+                balance: candid::Nat::from(2_000_000u64),
+            })),
+        );
         state.blocks_notified = Some(blocks_notified);
+        state.cycles_ledger_canister_id = Some(CanisterId::from_u64(5));
 
         let bytes = state.encode();
 
@@ -2219,6 +2601,102 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_merge_subnet_selection() {
+        let subnet_type = "Type1".to_string();
+        let subnet = subnet_test_id(0);
+
+        assert_eq!(merge_subnet_selection(None, None), Ok(None));
+        assert_eq!(
+            merge_subnet_selection(Some(subnet_type.clone()), None),
+            Ok(Some(SubnetSelection::SubnetType(subnet_type.clone())))
+        );
+        assert_eq!(
+            merge_subnet_selection(None, Some(SubnetSelection::Subnet(subnet))),
+            Ok(Some(SubnetSelection::Subnet(subnet)))
+        );
+        assert!(
+            merge_subnet_selection(Some(subnet_type), Some(SubnetSelection::Subnet(subnet)))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_get_subnets_for_selection() {
+        let type1 = "Type1".to_string();
+
+        let subnet1 = subnet_test_id(0);
+        let subnet2 = subnet_test_id(1);
+        let subnet3 = subnet_test_id(2);
+        let subnet4 = subnet_test_id(3);
+        let subnet5 = subnet_test_id(4);
+
+        let authorized_user = user_test_id(0).get();
+        let other_user = user_test_id(1).get();
+
+        let mut authorized_subnets = BTreeMap::new();
+        authorized_subnets.insert(authorized_user, vec![subnet1, subnet2]);
+        STATE.with(|state| {
+            state.replace(Some(State {
+                authorized_subnets,
+                default_subnets: vec![subnet3],
+                ..Default::default()
+            }))
+        });
+        add_subnet_type(type1.clone()).unwrap();
+        add_subnets_to_type(vec![subnet4], type1.clone()).unwrap();
+
+        // Without a selection, the authorized or default subnets are used.
+        assert_eq!(
+            get_subnets_for_selection(&authorized_user, None),
+            Ok(vec![subnet1, subnet2])
+        );
+        assert_eq!(
+            get_subnets_for_selection(&other_user, None),
+            Ok(vec![subnet3])
+        );
+
+        // A subnet type selects all subnets of that type.
+        assert_eq!(
+            get_subnets_for_selection(
+                &other_user,
+                Some(SubnetSelection::SubnetType(type1.clone()))
+            ),
+            Ok(vec![subnet4])
+        );
+        assert!(get_subnets_for_selection(
+            &other_user,
+            Some(SubnetSelection::SubnetType("Type2".to_string()))
+        )
+        .is_err());
+
+        // An explicit subnet must be authorized for the controller or be
+        // assigned to a subnet type.
+        assert_eq!(
+            get_subnets_for_selection(&authorized_user, Some(SubnetSelection::Subnet(subnet2))),
+            Ok(vec![subnet2])
+        );
+        assert!(
+            get_subnets_for_selection(&other_user, Some(SubnetSelection::Subnet(subnet2))).is_err()
+        );
+        assert!(get_subnets_for_selection(
+            &authorized_user,
+            Some(SubnetSelection::Subnet(subnet3))
+        )
+        .is_err());
+        assert_eq!(
+            get_subnets_for_selection(&other_user, Some(SubnetSelection::Subnet(subnet3))),
+            Ok(vec![subnet3])
+        );
+        assert_eq!(
+            get_subnets_for_selection(&authorized_user, Some(SubnetSelection::Subnet(subnet4))),
+            Ok(vec![subnet4])
+        );
+        assert!(
+            get_subnets_for_selection(&other_user, Some(SubnetSelection::Subnet(subnet5))).is_err()
+        );
+    }
+
     #[test]
     fn test_candid_interface_compatibility() {
         use candid::utils::{service_compatible, CandidSource};
@@ -1,24 +1,35 @@
-use canister_test::Canister;
+use candid::{Decode, Encode, Nat};
+use canister_test::{Canister, Project};
 use cycles_minting_canister::{
-    ChangeSubnetTypeAssignmentArgs, IcpXdrConversionRateCertifiedResponse, SubnetListWithType,
-    SubnetTypesToSubnetsResponse, UpdateSubnetTypeArgs, MEMO_TOP_UP_CANISTER,
+    mint_cycles_txn, ChangeSubnetTypeAssignmentArgs, CyclesCanisterUpgradePayload,
+    CyclesLedgerAccount, IcpXdrConversionRateCertifiedResponse, NotifyError, NotifyMintCycles,
+    NotifyMintCyclesSuccess, SubnetListWithType, SubnetTypesToSubnetsResponse,
+    UpdateSubnetTypeArgs, MEMO_TOP_UP_CANISTER, MINT_CYCLES_REFUND_FEE,
 };
 use dfn_candid::candid_one;
 use dfn_protobuf::protobuf;
+use ic_base_types::{CanisterId, PrincipalId};
 use ic_canister_client_sender::Sender;
+use ic_cycles_ledger::{InitArgs as CyclesLedgerInitArgs, LedgerArgument as CyclesLedgerArgument};
+use ic_ledger_canister_core::archive::ArchiveOptions;
 use ic_nervous_system_common_test_keys::{
     TEST_NEURON_1_OWNER_KEYPAIR, TEST_USER1_KEYPAIR, TEST_USER1_PRINCIPAL,
 };
 use ic_nns_common::types::{NeuronId, ProposalId, UpdateIcpXdrConversionRatePayload};
-use ic_nns_constants::{CYCLES_MINTING_CANISTER_ID, GOVERNANCE_CANISTER_ID};
+use ic_nns_constants::{
+    CYCLES_MINTING_CANISTER_ID, GOVERNANCE_CANISTER_ID, LEDGER_CANISTER_ID,
+    SNS_WASM_CANISTER_INDEX_IN_NNS_SUBNET,
+};
 use ic_nns_governance::pb::v1::{NnsFunction, ProposalStatus};
 use ic_nns_test_utils::{
-    common::NnsInitPayloadsBuilder,
+    common::{build_cmc_wasm, NnsInitPayloadsBuilder},
     governance::submit_external_update_proposal,
     governance::wait_for_final_state,
     ids::TEST_NEURON_1_ID,
     itest_helpers::{local_test_on_nns_subnet, NnsCanisters},
+    state_test_helpers::setup_nns_canisters,
 };
+use ic_state_machine_tests::{StateMachine, WasmResult};
 use ic_types_test_utils::ids::subnet_test_id;
 use icp_ledger::{
     tokens_from_proto, AccountBalanceArgs, AccountIdentifier, BlockIndex, CyclesResponse, Memo,
@@ -389,3 +400,254 @@ fn test_change_subnet_type_assignment() {
         Ok(())
     });
 }
+
+/// The canister id of the cycles ledger installed after the NNS canisters.
+const CYCLES_LEDGER_CANISTER_ID: CanisterId =
+    CanisterId::from_u64(SNS_WASM_CANISTER_INDEX_IN_NNS_SUBNET + 1);
+
+/// Sets up the NNS, with 100 ICP on the account of `TEST_USER1_PRINCIPAL`,
+/// and a cycles ledger that receives the cycles minted by
+/// `notify_mint_cycles` if the CMC is initialized with its canister id.
+fn setup_nns_and_cycles_ledger(state_machine: &StateMachine, init_cmc_with_cycles_ledger: bool) {
+    let account = AccountIdentifier::new(*TEST_USER1_PRINCIPAL, None);
+    let mut nns_init_payload = NnsInitPayloadsBuilder::new()
+        .with_ledger_account(account, Tokens::new(100, 0).unwrap())
+        .build();
+    if init_cmc_with_cycles_ledger {
+        nns_init_payload.cycles_minting.cycles_ledger_canister_id = Some(CYCLES_LEDGER_CANISTER_ID);
+    }
+    setup_nns_canisters(state_machine, nns_init_payload);
+
+    let args = CyclesLedgerArgument::Init(CyclesLedgerInitArgs {
+        transfer_fee: 100_000_000,
+        archive_options: ArchiveOptions {
+            trigger_threshold: 1000,
+            num_blocks_to_archive: 100,
+            node_max_memory_size_bytes: None,
+            max_message_size_bytes: None,
+            controller_id: GOVERNANCE_CANISTER_ID.get(),
+            cycles_for_archive_creation: None,
+            max_transactions_per_response: None,
+        },
+        cycles_minting_canister_id: Some(CYCLES_MINTING_CANISTER_ID),
+    });
+    let cycles_ledger_id = state_machine
+        .install_canister(
+            Project::cargo_bin_maybe_from_env("ic-cycles-ledger", &[]).bytes(),
+            Encode!(&args).unwrap(),
+            None,
+        )
+        .unwrap();
+    assert_eq!(cycles_ledger_id, CYCLES_LEDGER_CANISTER_ID);
+}
+
+/// Sends `amount` ICP from the account of `TEST_USER1_PRINCIPAL` to its
+/// subaccount of the CMC with the memo of `notify_mint_cycles`.
+fn send_icp_to_mint_cycles(state_machine: &StateMachine, amount: Tokens) -> BlockIndex {
+    let (send_args, _) = mint_cycles_txn(
+        amount,
+        None,
+        &CYCLES_MINTING_CANISTER_ID,
+        &TEST_USER1_PRINCIPAL,
+    );
+    let result = state_machine
+        .execute_ingress_as(
+            *TEST_USER1_PRINCIPAL,
+            LEDGER_CANISTER_ID,
+            "send_dfx",
+            Encode!(&send_args).unwrap(),
+        )
+        .unwrap();
+    Decode!(&result.bytes(), BlockIndex).unwrap()
+}
+
+fn notify_mint_cycles(
+    state_machine: &StateMachine,
+    args: &NotifyMintCycles,
+) -> Result<NotifyMintCyclesSuccess, NotifyError> {
+    let result = state_machine
+        .execute_ingress_as(
+            *TEST_USER1_PRINCIPAL,
+            CYCLES_MINTING_CANISTER_ID,
+            "notify_mint_cycles",
+            Encode!(args).unwrap(),
+        )
+        .unwrap();
+    match result {
+        WasmResult::Reply(bytes) => {
+            Decode!(&bytes, Result<NotifyMintCyclesSuccess, NotifyError>).unwrap()
+        }
+        WasmResult::Reject(reason) => panic!("notify_mint_cycles rejected: {}", reason),
+    }
+}
+
+fn icp_balance(state_machine: &StateMachine, account: AccountIdentifier) -> Tokens {
+    let result = state_machine
+        .query(
+            LEDGER_CANISTER_ID,
+            "account_balance_dfx",
+            Encode!(&AccountBalanceArgs { account }).unwrap(),
+        )
+        .unwrap();
+    Decode!(&result.bytes(), Tokens).unwrap()
+}
+
+fn cycles_ledger_balance(state_machine: &StateMachine, owner: PrincipalId) -> Nat {
+    let account = CyclesLedgerAccount {
+        owner,
+        subaccount: None,
+    };
+    let result = state_machine
+        .query(
+            CYCLES_LEDGER_CANISTER_ID,
+            "icrc1_balance_of",
+            Encode!(&account).unwrap(),
+        )
+        .unwrap();
+    Decode!(&result.bytes(), Nat).unwrap()
+}
+
+#[test]
+fn test_cmc_notify_mint_cycles_deposits_to_the_cycles_ledger() {
+    let state_machine = StateMachine::new();
+    setup_nns_and_cycles_ledger(&state_machine, true);
+    let cmc_subaccount = AccountIdentifier::new(
+        CYCLES_MINTING_CANISTER_ID.get(),
+        Some(Subaccount::from(&*TEST_USER1_PRINCIPAL)),
+    );
+
+    let block_index = send_icp_to_mint_cycles(&state_machine, Tokens::new(1, 0).unwrap());
+    assert_eq!(
+        icp_balance(&state_machine, cmc_subaccount),
+        Tokens::new(1, 0).unwrap()
+    );
+
+    let args = NotifyMintCycles {
+        block_index,
+        to_subaccount: None,
+        deposit_memo: Some(b"deposit".to_vec()),
+    };
+    let result = notify_mint_cycles(&state_machine, &args).unwrap();
+    // The default rate of the CMC is 100 XDR per ICP, 1 XDR buys 1T cycles.
+    assert_eq!(result.minted, Nat::from(100_000_000_000_000u64));
+    assert_eq!(result.balance, result.minted);
+    assert_eq!(result.block_index, Nat::from(0));
+    assert_eq!(
+        cycles_ledger_balance(&state_machine, *TEST_USER1_PRINCIPAL),
+        result.minted
+    );
+    // The ICP paid for the cycles was burned.
+    assert_eq!(icp_balance(&state_machine, cmc_subaccount), Tokens::ZERO);
+
+    // Notifying again returns the cached result without minting again.
+    assert_eq!(
+        notify_mint_cycles(&state_machine, &args),
+        Ok(result.clone())
+    );
+    assert_eq!(
+        cycles_ledger_balance(&state_machine, *TEST_USER1_PRINCIPAL),
+        result.minted
+    );
+
+    let args = NotifyMintCycles {
+        block_index,
+        to_subaccount: None,
+        deposit_memo: Some(vec![0; 33]),
+    };
+    assert!(matches!(
+        notify_mint_cycles(&state_machine, &args),
+        Err(NotifyError::InvalidTransaction(_))
+    ));
+}
+
+#[test]
+fn test_cmc_notify_mint_cycles_refunds_when_the_deposit_fails() {
+    let state_machine = StateMachine::new();
+    setup_nns_and_cycles_ledger(&state_machine, true);
+    let account = AccountIdentifier::new(*TEST_USER1_PRINCIPAL, None);
+
+    state_machine
+        .stop_canister(CYCLES_LEDGER_CANISTER_ID)
+        .unwrap();
+
+    let block_index = send_icp_to_mint_cycles(&state_machine, Tokens::new(1, 0).unwrap());
+    let balance_after_send = icp_balance(&state_machine, account);
+    let args = NotifyMintCycles {
+        block_index,
+        to_subaccount: None,
+        deposit_memo: None,
+    };
+    match notify_mint_cycles(&state_machine, &args) {
+        Err(NotifyError::Refunded {
+            block_index: Some(_),
+            ..
+        }) => (),
+        result => panic!("unexpected notify_mint_cycles result {:?}", result),
+    }
+
+    // The ICP minus the fees is refunded, the refund is not repeated.
+    let expected_balance = (((balance_after_send + Tokens::new(1, 0).unwrap()).unwrap()
+        - DEFAULT_TRANSFER_FEE)
+        .unwrap()
+        - MINT_CYCLES_REFUND_FEE)
+        .unwrap();
+    assert_eq!(icp_balance(&state_machine, account), expected_balance);
+    assert!(matches!(
+        notify_mint_cycles(&state_machine, &args),
+        Err(NotifyError::Refunded { .. })
+    ));
+    assert_eq!(icp_balance(&state_machine, account), expected_balance);
+}
+
+#[test]
+fn test_cmc_upgrade_sets_the_cycles_ledger_canister_id() {
+    let state_machine = StateMachine::new();
+    setup_nns_and_cycles_ledger(&state_machine, false);
+
+    // Without the cycles ledger canister id, the ICP is refunded.
+    let block_index = send_icp_to_mint_cycles(&state_machine, Tokens::new(1, 0).unwrap());
+    let args = NotifyMintCycles {
+        block_index,
+        to_subaccount: None,
+        deposit_memo: None,
+    };
+    assert!(matches!(
+        notify_mint_cycles(&state_machine, &args),
+        Err(NotifyError::Refunded { .. })
+    ));
+
+    state_machine
+        .upgrade_canister(
+            CYCLES_MINTING_CANISTER_ID,
+            build_cmc_wasm().bytes(),
+            Encode!(&Some(CyclesCanisterUpgradePayload {
+                cycles_ledger_canister_id: Some(CYCLES_LEDGER_CANISTER_ID),
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+    let block_index = send_icp_to_mint_cycles(&state_machine, Tokens::new(1, 0).unwrap());
+    let args = NotifyMintCycles {
+        block_index,
+        to_subaccount: None,
+        deposit_memo: None,
+    };
+    let result = notify_mint_cycles(&state_machine, &args).unwrap();
+    assert_eq!(
+        cycles_ledger_balance(&state_machine, *TEST_USER1_PRINCIPAL),
+        result.minted
+    );
+
+    // Upgrading without an argument keeps the cycles ledger canister id.
+    state_machine
+        .upgrade_canister(CYCLES_MINTING_CANISTER_ID, build_cmc_wasm().bytes(), vec![])
+        .unwrap();
+    let block_index = send_icp_to_mint_cycles(&state_machine, Tokens::new(1, 0).unwrap());
+    let args = NotifyMintCycles {
+        block_index,
+        to_subaccount: None,
+        deposit_memo: None,
+    };
+    assert!(notify_mint_cycles(&state_machine, &args).is_ok());
+}
@@ -67,6 +67,8 @@ fn test_submit_and_accept_update_subnet_proposal() {
                 ssh_readonly_access: vec![],
                 ssh_backup_access: vec![],
                 ecdsa_config: None,
+                adaptive_notarization_delays: false,
+                vetkd_config: None,
             };
 
             let key = make_subnet_record_key(subnet_id);
@@ -97,6 +99,7 @@ fn test_submit_and_accept_update_subnet_proposal() {
                 initial_notary_delay_millis: None,
                 dkg_interval_length: Some(10),
                 dkg_dealings_per_block: Some(1),
+                adaptive_notarization_delays: None,
                 max_artifact_streams_per_peer: Some(MAX_ARTIFACT_STREAMS_PER_PEER),
                 max_chunk_wait_ms: Some(MAX_CHUNK_WAIT_MS),
                 max_duplicity: Some(MAX_DUPLICITY),
@@ -116,6 +119,8 @@ fn test_submit_and_accept_update_subnet_proposal() {
                 ecdsa_config: None,
                 ecdsa_key_signing_enable: None,
                 ecdsa_key_signing_disable: None,
+                ecdsa_key_reshare_requests: None,
+                vetkd_config: None,
                 max_number_of_canisters: Some(200),
                 ssh_readonly_access: Some(vec!["pub_key_0".to_string()]),
                 ssh_backup_access: Some(vec!["pub_key_1".to_string()]),
@@ -188,6 +193,8 @@ fn test_submit_and_accept_update_subnet_proposal() {
                     ssh_readonly_access: vec!["pub_key_0".to_string()],
                     ssh_backup_access: vec!["pub_key_1".to_string()],
                     ecdsa_config: None,
+                    adaptive_notarization_delays: false,
+                    vetkd_config: None,
                 }
             );
             Ok(())
@@ -44,7 +44,9 @@ pub trait Dashboard {
     }
 
     /// Checks the request. If it is a GET request on `/`, responds with the
-    /// contents built by `build_response`. Otherwise respondes with 404.
+    /// contents built by `build_response`. If it is a GET request on `/json`
+    /// and the implementation provides a JSON dashboard, responds with the
+    /// contents built by `build_json_response`. Otherwise respondes with 404.
     async fn handle_connection(&self, mut stream: TcpStream) {
         let mut buffer = [0; 512];
         if let Err(e) = stream.read(&mut buffer).await {
@@ -53,13 +55,22 @@ pub trait Dashboard {
         }
 
         let get = b"GET / ";
-        let response = match buffer.starts_with(get) {
-            true => {
+        let get_json = b"GET /json ";
+        let json_contents = match buffer.starts_with(get_json) {
+            true => self.build_json_response().await,
+            false => None,
+        };
+        let response = match (buffer.starts_with(get), json_contents) {
+            (true, _) => {
                 let headers = "HTTP/1.1 200 OK\r\n\r\n";
                 let contents = self.build_response().await;
                 format!("{}{}", headers, contents)
             }
-            false => {
+            (false, Some(contents)) => {
+                let headers = "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n";
+                format!("{}{}", headers, contents)
+            }
+            (false, None) => {
                 let request = match buffer.lines().next() {
                     Some(Ok(s)) => s,
                     _ => "parse error".to_string(),
@@ -91,6 +102,88 @@ pub trait Dashboard {
         "Default response must be overridden".to_string()
     }
 
+    /// Builds the contents of the dashboard as a JSON document. Returns `None`
+    /// by default, in which case requests on `/json` are answered with 404.
+    async fn build_json_response(&self) -> Option<String> {
+        None
+    }
+
     /// Adds an INFO level log using the implementation's logger.
     fn log_info(&self, log_line: &str);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TextDashboard;
+
+    #[async_trait]
+    impl Dashboard for TextDashboard {
+        fn port() -> u16 {
+            0
+        }
+
+        async fn build_response(&self) -> String {
+            "text".to_string()
+        }
+
+        fn log_info(&self, _log_line: &str) {}
+    }
+
+    struct JsonDashboard;
+
+    #[async_trait]
+    impl Dashboard for JsonDashboard {
+        fn port() -> u16 {
+            0
+        }
+
+        async fn build_response(&self) -> String {
+            "text".to_string()
+        }
+
+        async fn build_json_response(&self) -> Option<String> {
+            Some(r#"{"schema_version":1}"#.to_string())
+        }
+
+        fn log_info(&self, _log_line: &str) {}
+    }
+
+    /// Sends `request` to `dashboard` and returns the raw response.
+    async fn get<D: Dashboard + Sync>(dashboard: &D, request: &str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        client.write_all(request.as_bytes()).await.unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+        dashboard.handle_connection(server).await;
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn json_is_served_with_a_json_content_type() {
+        assert_eq!(
+            get(&JsonDashboard, "GET /json HTTP/1.1\r\n\r\n").await,
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n{\"schema_version\":1}"
+        );
+        assert_eq!(
+            get(&JsonDashboard, "GET / HTTP/1.1\r\n\r\n").await,
+            "HTTP/1.1 200 OK\r\n\r\ntext"
+        );
+    }
+
+    #[tokio::test]
+    async fn json_is_not_found_without_a_json_dashboard() {
+        assert!(get(&TextDashboard, "GET /json HTTP/1.1\r\n\r\n")
+            .await
+            .starts_with("HTTP/1.1 404 NOT FOUND\r\n\r\n"));
+        assert_eq!(
+            get(&TextDashboard, "GET / HTTP/1.1\r\n\r\n").await,
+            "HTTP/1.1 200 OK\r\n\r\ntext"
+        );
+    }
+}
@@ -24,6 +24,7 @@ const REBOOT_TIME_FILENAME: &str = "reboot_time.txt";
 /// and a return value `R` stemming from a peridoically called `check_for_upgrade` function.
 /// The lifecycle of an image can be described by:
 /// 1. Confirming the boot of the current image using the `manageboot.sh` script. Cf. `confirm_boot()`
+///    Alternatively, an unconfirmed image can be reverted, Cf. `is_boot_confirmed()` and `rollback_boot()`
 /// 2. Optionally collecting metrics of the reboot time from disk.
 /// 3. Checking for new versions and executing upgrades in a loop, Cf. `upgrade_loop()`
 ///
@@ -126,6 +127,57 @@ pub trait ImageUpgrader<V: Clone + Debug + PartialEq + Eq + Send + Sync, R: Send
         }
     }
 
+    /// Returns `false` if the current image was booted for the first time after
+    /// an upgrade and this boot has not been confirmed yet, i.e. if the image
+    /// would be reverted on the next restart.
+    async fn is_boot_confirmed(&self) -> UpgradeResult<bool> {
+        let current = self.manageboot_output("current").await?;
+        let next = self.manageboot_output("next").await?;
+        Ok(current == next)
+    }
+
+    /// Reverts an unconfirmed upgrade by rebooting into the previous image.
+    /// Only returns if the rollback could not be triggered.
+    async fn rollback_boot(&self) -> UpgradeError {
+        info!(self.log(), "Attempting to roll back to the previous image");
+        let mut script = self.binary_dir().clone();
+        script.push("manageboot.sh");
+        let mut c = Command::new(script.into_os_string());
+        let out = match c.arg("rollback").output().await {
+            Ok(out) => out,
+            Err(e) => return UpgradeError::file_command_error(e, &c),
+        };
+
+        if !out.status.success() {
+            warn!(self.log(), "rollback has failed");
+            UpgradeError::GenericError(format!(
+                "rollback failed: {}",
+                String::from_utf8_lossy(&out.stderr).trim()
+            ))
+        } else {
+            info!(self.log(), "Rebooting {:?}", out);
+            exit(42);
+        }
+    }
+
+    /// Runs `manageboot.sh` with the given informational action and returns its
+    /// trimmed output.
+    async fn manageboot_output(&self, action: &str) -> UpgradeResult<String> {
+        let mut c = Command::new(self.binary_dir().join("manageboot.sh").into_os_string());
+        let out = c
+            .arg(action)
+            .output()
+            .await
+            .map_err(|e| UpgradeError::file_command_error(e, &c))?;
+        if !out.status.success() {
+            return Err(UpgradeError::GenericError(format!(
+                "manageboot.sh {} failed",
+                action
+            )));
+        }
+        Ok(String::from_utf8_lossy(&out.stdout).trim().to_string())
+    }
+
     /// Return a value that would differentiate the nodes (but not necessarily unique) in order
     /// to allow them to download the new release package from different URLs.
     fn get_load_balance_number(&self) -> usize;
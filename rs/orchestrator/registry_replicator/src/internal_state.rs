@@ -27,12 +27,42 @@ use std::convert::TryFrom;
 use std::fmt::Debug;
 use std::net::IpAddr;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use url::Url;
 
 const MAX_CONSECUTIVE_FAILURES: i64 = 3;
 
+/// The local store is compacted once at least this many versions are stored
+/// outside of its compacted snapshot, see [`InternalState::compact_local_store`].
+const COMPACTION_INTERVAL: u64 = 1000;
+
+/// The number of latest versions that are never compacted, so that tools
+/// operating on the local store can still amend them, and readers of the local
+/// store that lag behind by fewer versions can still read the changes since
+/// their version.
+const UNCOMPACTED_TAIL_LENGTH: u64 = 100;
+
+/// Returns the version up to which the local store should be compacted, if
+/// any. Versions in the [`UNCOMPACTED_TAIL_LENGTH`] latest versions and
+/// versions from `oldest_version_in_use` onwards are never compacted, since the
+/// registry client cannot serve reads at versions below the compacted version.
+fn compaction_target(
+    latest_version: RegistryVersion,
+    compacted_version: RegistryVersion,
+    oldest_version_in_use: RegistryVersion,
+) -> Option<RegistryVersion> {
+    let target_version = latest_version
+        .get()
+        .saturating_sub(UNCOMPACTED_TAIL_LENGTH)
+        .min(oldest_version_in_use.get());
+    if target_version < compacted_version.get() + COMPACTION_INTERVAL {
+        return None;
+    }
+    Some(RegistryVersion::from(target_version))
+}
+
 /// The `InternalState` encompasses a locally persisted registry changelog which
 /// is kept up to date by repeated calls to [`Self::poll()`]. If this node is
 /// part of a subnet that is starting up as the NNS after a switch-over, the
@@ -58,6 +88,7 @@ pub(crate) struct InternalState {
     registry_canister_fallback: Option<Arc<RegistryCanister>>,
     poll_delay: Duration,
     failed_poll_count: i64,
+    oldest_version_in_use: Arc<AtomicU64>,
 }
 
 impl InternalState {
@@ -68,6 +99,7 @@ impl InternalState {
         local_store: Arc<dyn LocalStore>,
         config_urls: Vec<Url>,
         poll_delay: Duration,
+        oldest_version_in_use: Arc<AtomicU64>,
     ) -> Self {
         let last_certified_time = local_store.read_certified_time();
         let registry_canister_fallback = if !config_urls.is_empty() {
@@ -91,6 +123,7 @@ impl InternalState {
             registry_canister_fallback,
             poll_delay,
             failed_poll_count: 0,
+            oldest_version_in_use,
         }
     }
 
@@ -193,11 +226,51 @@ impl InternalState {
                     latest_version + RegistryVersion::from(entries as u64)
                 );
             }
+
+            self.compact_local_store(latest_version + RegistryVersion::from(entries as u64));
         }
 
         Ok(())
     }
 
+    /// Compacts all but the [`UNCOMPACTED_TAIL_LENGTH`] latest versions of the
+    /// local store into its snapshot, if at least [`COMPACTION_INTERVAL`]
+    /// versions were added since the last compaction. This keeps the number of
+    /// files the registry client has to read on startup small. Versions from the
+    /// oldest registry version still in use on this node onwards are never
+    /// compacted, see [`crate::RegistryReplicator::set_oldest_registry_version_in_use`].
+    ///
+    /// Compaction is an optimization, so failures are logged but not fatal.
+    fn compact_local_store(&self, latest_version: RegistryVersion) {
+        let compacted_version = match self.local_store.get_compacted_version() {
+            Ok(v) => v,
+            Err(e) => {
+                warn!(
+                    self.logger,
+                    "Could not read compacted version of local store: {:?}", e
+                );
+                return;
+            }
+        };
+        let oldest_version_in_use =
+            RegistryVersion::from(self.oldest_version_in_use.load(Ordering::Relaxed));
+        let target_version =
+            match compaction_target(latest_version, compacted_version, oldest_version_in_use) {
+                Some(v) => v,
+                None => return,
+            };
+        match self.local_store.compact(target_version) {
+            Ok(()) => info!(
+                self.logger,
+                "Compacted registry local store up to version: {}", target_version
+            ),
+            Err(e) => warn!(
+                self.logger,
+                "Could not compact local store up to version {}: {:?}", target_version, e
+            ),
+        }
+    }
+
     /// Iff at version `latest_version` the node id of this node appears on a
     /// subnet record that has the `start_as_nns`-flag set, this function will
     /// adjust the registry such that the aforementioned subnet will become the
@@ -257,6 +330,10 @@ impl InternalState {
             .get_changelog_since_version(RegistryVersion::from(0))
             .expect("Could not read changelog from disk.");
         changelog.truncate(k.get() as usize);
+        let compacted_version = self
+            .local_store
+            .get_compacted_version()
+            .expect("Could not read compacted version from disk.");
 
         self.apply_switch_over_to_last_changelog_entry(
             changelog.as_mut_slice(),
@@ -268,9 +345,21 @@ impl InternalState {
             .expect("Could not clear registry local store");
 
         for (v, cle) in changelog.into_iter().enumerate() {
-            self.local_store
-                .store(RegistryVersion::from((v + 1) as u64), cle)
-                .expect("Could not store change log entry");
+            let v = RegistryVersion::from((v + 1) as u64);
+            // Compacted versions are stored as part of the compacted snapshot,
+            // which is held by the entry of the compacted version.
+            if v < compacted_version {
+                continue;
+            }
+            if v == compacted_version {
+                self.local_store
+                    .store_compacted_snapshot(v, cle)
+                    .expect("Could not store compacted snapshot");
+            } else {
+                self.local_store
+                    .store(v, cle)
+                    .expect("Could not store change log entry");
+            }
         }
 
         warn!(
@@ -514,3 +603,26 @@ impl InternalState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(v: u64) -> RegistryVersion {
+        RegistryVersion::from(v)
+    }
+
+    #[test]
+    fn compaction_keeps_the_uncompacted_tail() {
+        assert_eq!(compaction_target(v(1099), v(0), v(u64::MAX)), None);
+        assert_eq!(compaction_target(v(1100), v(0), v(u64::MAX)), Some(v(1000)));
+        assert_eq!(compaction_target(v(2000), v(1000), v(u64::MAX)), None);
+    }
+
+    #[test]
+    fn compaction_never_passes_the_oldest_version_in_use() {
+        assert_eq!(compaction_target(v(5000), v(0), v(1500)), Some(v(1500)));
+        assert_eq!(compaction_target(v(5000), v(1001), v(1500)), None);
+        assert_eq!(compaction_target(v(5000), v(0), v(0)), None);
+    }
+}
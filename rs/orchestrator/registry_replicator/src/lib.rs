@@ -44,7 +44,7 @@ use ic_types::{NodeId, RegistryVersion};
 use metrics::RegistryreplicatorMetrics;
 use std::io::{Error, ErrorKind};
 use std::net::SocketAddr;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::task::JoinHandle;
@@ -63,6 +63,7 @@ pub struct RegistryReplicator {
     cancelled: Arc<AtomicBool>,
     poll_delay: Duration,
     metrics: Arc<RegistryreplicatorMetrics>,
+    oldest_version_in_use: Arc<AtomicU64>,
 }
 
 impl RegistryReplicator {
@@ -83,6 +84,7 @@ impl RegistryReplicator {
             cancelled: Arc::new(AtomicBool::new(false)),
             poll_delay,
             metrics,
+            oldest_version_in_use: Arc::new(AtomicU64::new(ZERO_REGISTRY_VERSION.get())),
         }
     }
 
@@ -120,6 +122,7 @@ impl RegistryReplicator {
             cancelled: Arc::new(AtomicBool::new(false)),
             poll_delay,
             metrics,
+            oldest_version_in_use: Arc::new(AtomicU64::new(ZERO_REGISTRY_VERSION.get())),
         }
     }
 
@@ -334,6 +337,7 @@ impl RegistryReplicator {
             self.local_store.clone(),
             nns_urls,
             self.poll_delay,
+            Arc::clone(&self.oldest_version_in_use),
         );
 
         let logger = self.logger.clone();
@@ -374,15 +378,30 @@ impl RegistryReplicator {
         let changelog = source_registry
             .get_changelog_since_version(RegistryVersion::from(0))
             .expect("Could not read changelog from source registry.");
+        let compacted_version = source_registry
+            .get_compacted_version()
+            .expect("Could not read compacted version of source registry.");
 
         // Reset the local store and fill it with the read registry data.
         self.local_store
             .clear()
             .expect("Could not clear registry local store");
         for (v, cle) in changelog.into_iter().enumerate() {
-            self.local_store
-                .store(RegistryVersion::from((v + 1) as u64), cle)
-                .expect("Could not store change log entry");
+            let v = RegistryVersion::from((v + 1) as u64);
+            // Compacted versions are stored as part of the compacted snapshot,
+            // which is held by the entry of the compacted version.
+            if v < compacted_version {
+                continue;
+            }
+            if v == compacted_version {
+                self.local_store
+                    .store_compacted_snapshot(v, cle)
+                    .expect("Could not store compacted snapshot");
+            } else {
+                self.local_store
+                    .store(v, cle)
+                    .expect("Could not store change log entry");
+            }
         }
     }
 
@@ -391,6 +410,15 @@ impl RegistryReplicator {
         self.set_local_registry_data(source_registry);
     }
 
+    /// Sets the oldest registry version that is still read on this node, e.g.
+    /// the oldest version referenced by the local CUP. The local store is never
+    /// compacted past this version. Until it is set, the local store is not
+    /// compacted at all.
+    pub fn set_oldest_registry_version_in_use(&self, version: RegistryVersion) {
+        self.oldest_version_in_use
+            .store(version.get(), Ordering::Relaxed);
+    }
+
     pub fn stop_polling(&self) {
         self.cancelled.fetch_or(true, Ordering::Relaxed);
     }
@@ -60,6 +60,14 @@ pub struct OrchestratorArgs {
     /// If not provided, the relevant data are not persisted to the disk.
     #[clap(long, parse(from_os_str))]
     pub(crate) orchestrator_data_directory: Option<PathBuf>,
+
+    /// If set, the boot of a newly installed replica version is only confirmed
+    /// after the replica stayed healthy for the given number of seconds, or
+    /// for at least two DKG intervals of the subnet of the node, whichever is
+    /// longer. Otherwise, the node reboots into the previous version. Requires
+    /// `--orchestrator-data-directory`.
+    #[clap(long)]
+    pub(crate) upgrade_health_check_window_secs: Option<u64>,
 }
 
 impl OrchestratorArgs {
@@ -1,27 +1,116 @@
 use crate::{
     catch_up_package_provider::CatchUpPackageProvider, registry_helper::RegistryHelper,
     replica_process::ReplicaProcess, ssh_access_manager::SshAccessParameters,
+    upgrade_health::UpgradeHealthReport,
 };
 use async_trait::async_trait;
 pub use ic_dashboard::Dashboard;
 use ic_logger::{info, warn, ReplicaLogger};
+use ic_protobuf::registry::firewall::v1::{FirewallAction, FirewallRule, FirewallRuleDirection};
 use ic_types::{consensus::HasHeight, NodeId, RegistryVersion, ReplicaVersion, SubnetId};
+use serde::Serialize;
 use std::process::Command;
 use std::sync::{Arc, Mutex};
 use tokio::sync::RwLock;
 
 const ORCHESTRATOR_DASHBOARD_PORT: u16 = 7070;
 
+/// The version of the schema of the JSON dashboard. It must be increased
+/// whenever a field is removed, renamed or changes its meaning; adding fields
+/// keeps the schema backwards compatible.
+const DASHBOARD_JSON_SCHEMA_VERSION: u32 = 1;
+
+/// The JSON dashboard, served on `/json`. It contains the same data as the
+/// text dashboard.
+#[derive(Serialize)]
+struct OrchestratorDashboardJson {
+    schema_version: u32,
+    node_id: String,
+    dc_id: Option<String>,
+    registry_version: u64,
+    subnet_id: Option<String>,
+    replica_process_id: Option<i32>,
+    replica_crash_count: u64,
+    replica_version: String,
+    /// The version the node will upgrade to, if an upgrade is scheduled.
+    scheduled_upgrade: Option<String>,
+    cup: Option<CupJson>,
+    upgrade_health: UpgradeHealthJson,
+    firewall: FirewallJson,
+    ssh: SshJson,
+    /// Describes the data that could not be determined.
+    errors: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct CupJson {
+    height: u64,
+    signed: bool,
+}
+
+#[derive(Serialize)]
+struct UpgradeHealthJson {
+    status: &'static str,
+    details: String,
+}
+
+#[derive(Serialize)]
+struct FirewallJson {
+    registry_version: u64,
+    rules: Vec<FirewallRuleJson>,
+}
+
+#[derive(Serialize)]
+struct FirewallRuleJson {
+    ipv4_prefixes: Vec<String>,
+    ipv6_prefixes: Vec<String>,
+    ports: Vec<u32>,
+    action: String,
+    direction: String,
+    user: Option<String>,
+    comment: String,
+}
+
+impl From<&FirewallRule> for FirewallRuleJson {
+    fn from(rule: &FirewallRule) -> Self {
+        let action = FirewallAction::from_i32(rule.action).unwrap_or(FirewallAction::Unspecified);
+        let direction = rule
+            .direction
+            .and_then(FirewallRuleDirection::from_i32)
+            .unwrap_or(FirewallRuleDirection::Unspecified);
+        Self {
+            ipv4_prefixes: rule.ipv4_prefixes.clone(),
+            ipv6_prefixes: rule.ipv6_prefixes.clone(),
+            ports: rule.ports.clone(),
+            action: format!("{:?}", action).to_lowercase(),
+            direction: format!("{:?}", direction).to_lowercase(),
+            user: rule.user.clone(),
+            comment: rule.comment.clone(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct SshJson {
+    registry_version: u64,
+    subnet_id: Option<String>,
+    readonly_keys: Vec<String>,
+    backup_keys: Vec<String>,
+    admin_keys: Vec<String>,
+}
+
 /// Listens to ORCHESTRATOR_DASHBOARD_PORT and responds with orchestrator state.
 pub(crate) struct OrchestratorDashboard {
     registry: Arc<RegistryHelper>,
     node_id: NodeId,
     last_applied_ssh_parameters: Arc<RwLock<SshAccessParameters>>,
     last_applied_firewall_version: Arc<RwLock<RegistryVersion>>,
+    last_applied_firewall_rules: Arc<RwLock<Vec<FirewallRule>>>,
     replica_process: Arc<Mutex<ReplicaProcess>>,
     subnet_id: Arc<RwLock<Option<SubnetId>>>,
     replica_version: ReplicaVersion,
     cup_provider: Arc<CatchUpPackageProvider>,
+    upgrade_health_report: Arc<RwLock<UpgradeHealthReport>>,
     logger: ReplicaLogger,
 }
 
@@ -40,6 +129,7 @@ impl Dashboard for OrchestratorDashboard {
              replica process id: {}\n\
              replica version: {}\n\
              scheduled upgrade: {}\n\
+             upgrade health check: {}\n\
              {}\n\
              firewall config registry version: {}\n\
              {}\n\
@@ -52,7 +142,8 @@ impl Dashboard for OrchestratorDashboard {
             self.get_subnet_id().await,
             self.get_pid(),
             self.replica_version,
-            self.get_scheduled_upgrade().await,
+            self.display_scheduled_upgrade().await,
+            *self.upgrade_health_report.read().await,
             self.get_local_cup_info(),
             *self.last_applied_firewall_version.read().await,
             self.display_last_applied_ssh_parameters().await,
@@ -62,6 +153,78 @@ impl Dashboard for OrchestratorDashboard {
         )
     }
 
+    async fn build_json_response(&self) -> Option<String> {
+        let mut errors = Vec::new();
+        let scheduled_upgrade = self.get_scheduled_upgrade().await.unwrap_or_else(|e| {
+            errors.push(format!("scheduled upgrade: {}", e));
+            None
+        });
+        let mut authorized_keys = |account: &'static str| {
+            try_to_get_authorized_keys(account).unwrap_or_else(|e| {
+                errors.push(format!("{} keys: {}", account, e));
+                vec![]
+            })
+        };
+        let (readonly_keys, backup_keys, admin_keys) = (
+            authorized_keys("readonly"),
+            authorized_keys("backup"),
+            authorized_keys("admin"),
+        );
+        let ssh_parameters = self.last_applied_ssh_parameters.read().await;
+        let upgrade_health = self.upgrade_health_report.read().await;
+        let (replica_process_id, replica_crash_count) = {
+            let replica_process = self.replica_process.lock().unwrap();
+            (
+                replica_process.get_pid().map(|pid| pid.as_raw()),
+                replica_process.crash_count(),
+            )
+        };
+        let dashboard = OrchestratorDashboardJson {
+            schema_version: DASHBOARD_JSON_SCHEMA_VERSION,
+            node_id: self.node_id.to_string(),
+            dc_id: self.registry.dc_id(),
+            registry_version: self.registry.get_latest_version().get(),
+            subnet_id: self.subnet_id.read().await.map(|id| id.to_string()),
+            replica_process_id,
+            replica_crash_count,
+            replica_version: self.replica_version.to_string(),
+            scheduled_upgrade: scheduled_upgrade.map(|v| v.to_string()),
+            cup: self.cup_provider.get_local_cup().map(|cup| CupJson {
+                height: cup.cup.content.height().get(),
+                signed: !cup.cup.signature.signature.get().0.is_empty(),
+            }),
+            upgrade_health: UpgradeHealthJson {
+                status: upgrade_health.status.into(),
+                details: upgrade_health.details.clone(),
+            },
+            firewall: FirewallJson {
+                registry_version: self.last_applied_firewall_version.read().await.get(),
+                rules: self
+                    .last_applied_firewall_rules
+                    .read()
+                    .await
+                    .iter()
+                    .map(FirewallRuleJson::from)
+                    .collect(),
+            },
+            ssh: SshJson {
+                registry_version: ssh_parameters.registry_version.get(),
+                subnet_id: ssh_parameters.subnet_id.map(|id| id.to_string()),
+                readonly_keys,
+                backup_keys,
+                admin_keys,
+            },
+            errors,
+        };
+        match serde_json::to_string(&dashboard) {
+            Ok(json) => Some(json),
+            Err(e) => {
+                warn!(self.logger, "Failed to serialize the JSON dashboard: {}", e);
+                None
+            }
+        }
+    }
+
     fn log_info(&self, log_line: &str) {
         info!(self.logger, "{}", log_line);
     }
@@ -73,10 +236,12 @@ impl OrchestratorDashboard {
         node_id: NodeId,
         last_applied_ssh_parameters: Arc<RwLock<SshAccessParameters>>,
         last_applied_firewall_version: Arc<RwLock<RegistryVersion>>,
+        last_applied_firewall_rules: Arc<RwLock<Vec<FirewallRule>>>,
         replica_process: Arc<Mutex<ReplicaProcess>>,
         subnet_id: Arc<RwLock<Option<SubnetId>>>,
         replica_version: ReplicaVersion,
         cup_provider: Arc<CatchUpPackageProvider>,
+        upgrade_health_report: Arc<RwLock<UpgradeHealthReport>>,
         logger: ReplicaLogger,
     ) -> Self {
         Self {
@@ -84,20 +249,24 @@ impl OrchestratorDashboard {
             node_id,
             last_applied_ssh_parameters,
             last_applied_firewall_version,
+            last_applied_firewall_rules,
             replica_process,
             subnet_id,
             replica_version,
             cup_provider,
+            upgrade_health_report,
             logger,
         }
     }
 
     fn get_authorized_keys(&self, account: &str) -> String {
-        try_to_get_authorized_keys(account).unwrap_or_else(|e| {
-            let error = format!("Failed to read the keys of the accout {}: {}", account, e);
-            warn!(self.logger, "{}", error);
-            error
-        })
+        try_to_get_authorized_keys(account)
+            .map(|keys| keys.join(", "))
+            .unwrap_or_else(|e| {
+                let error = format!("Failed to read the keys of the accout {}: {}", account, e);
+                warn!(self.logger, "{}", error);
+                error
+            })
     }
 
     async fn display_last_applied_ssh_parameters(&self) -> String {
@@ -127,22 +296,31 @@ impl OrchestratorDashboard {
         }
     }
 
-    async fn get_scheduled_upgrade(&self) -> String {
+    async fn display_scheduled_upgrade(&self) -> String {
+        match self.get_scheduled_upgrade().await {
+            Ok(Some(version)) => format!("{} -> {}", self.replica_version, version),
+            Ok(None) => "None".to_string(),
+            Err(e) => e,
+        }
+    }
+
+    /// Returns the replica version the node will upgrade to, if any.
+    async fn get_scheduled_upgrade(&self) -> Result<Option<ReplicaVersion>, String> {
         let subnet_id = match *self.subnet_id.read().await {
             Some(id) => id,
-            None => return "None".to_string(),
+            None => return Ok(None),
         };
 
-        let expected_replica_version = match self.registry.get_expected_replica_version(subnet_id) {
-            Ok((v, _)) => v,
-            Err(e) => return e.to_string(),
-        };
+        let (expected_replica_version, _) = self
+            .registry
+            .get_expected_replica_version(subnet_id)
+            .map_err(|e| e.to_string())?;
 
         if expected_replica_version == self.replica_version {
-            return "None".to_string();
+            return Ok(None);
         }
 
-        format!("{} -> {}", self.replica_version, expected_replica_version)
+        Ok(Some(expected_replica_version))
     }
 
     fn get_local_cup_info(&self) -> String {
@@ -158,10 +336,10 @@ impl OrchestratorDashboard {
     }
 }
 
-fn try_to_get_authorized_keys(account: &str) -> Result<String, String> {
-    let stringify = |res| {
+fn try_to_get_authorized_keys(account: &str) -> Result<Vec<String>, String> {
+    let lines = |res| {
         std::str::from_utf8(res)
-            .map(|s| s.lines().collect::<Vec<_>>().join(", "))
+            .map(|s| s.lines().map(String::from).collect::<Vec<_>>())
             .map_err(|e| e.to_string())
     };
 
@@ -170,7 +348,96 @@ fn try_to_get_authorized_keys(account: &str) -> Result<String, String> {
         .output()
         .map_err(|e| format!("Failed to execute \"read-ssh-keys.sh\" : {}", e))?;
     match output.status.success() {
-        true => Ok(stringify(&output.stdout)?),
-        false => Err(stringify(&output.stderr)?),
+        true => Ok(lines(&output.stdout)?),
+        false => Err(lines(&output.stderr)?.join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn json_dashboard_has_the_expected_shape() {
+        let dashboard = OrchestratorDashboardJson {
+            schema_version: DASHBOARD_JSON_SCHEMA_VERSION,
+            node_id: "node".to_string(),
+            dc_id: None,
+            registry_version: 10,
+            subnet_id: Some("subnet".to_string()),
+            replica_process_id: Some(42),
+            replica_crash_count: 1,
+            replica_version: "0.8.0".to_string(),
+            scheduled_upgrade: None,
+            cup: Some(CupJson {
+                height: 100,
+                signed: true,
+            }),
+            upgrade_health: UpgradeHealthJson {
+                status: "healthy",
+                details: String::new(),
+            },
+            firewall: FirewallJson {
+                registry_version: 9,
+                rules: vec![FirewallRuleJson::from(&FirewallRule {
+                    ipv4_prefixes: vec!["10.0.0.0/8".to_string()],
+                    ipv6_prefixes: vec![],
+                    ports: vec![22],
+                    action: FirewallAction::Allow as i32,
+                    comment: "ssh".to_string(),
+                    user: None,
+                    direction: Some(FirewallRuleDirection::Inbound as i32),
+                })],
+            },
+            ssh: SshJson {
+                registry_version: 8,
+                subnet_id: None,
+                readonly_keys: vec!["key1".to_string(), "key2".to_string()],
+                backup_keys: vec![],
+                admin_keys: vec!["key3".to_string()],
+            },
+            errors: vec!["scheduled upgrade: unavailable".to_string()],
+        };
+
+        let value: Value =
+            serde_json::from_str(&serde_json::to_string(&dashboard).unwrap()).unwrap();
+
+        assert_eq!(
+            value,
+            json!({
+                "schema_version": DASHBOARD_JSON_SCHEMA_VERSION,
+                "node_id": "node",
+                "dc_id": null,
+                "registry_version": 10,
+                "subnet_id": "subnet",
+                "replica_process_id": 42,
+                "replica_crash_count": 1,
+                "replica_version": "0.8.0",
+                "scheduled_upgrade": null,
+                "cup": { "height": 100, "signed": true },
+                "upgrade_health": { "status": "healthy", "details": "" },
+                "firewall": {
+                    "registry_version": 9,
+                    "rules": [{
+                        "ipv4_prefixes": ["10.0.0.0/8"],
+                        "ipv6_prefixes": [],
+                        "ports": [22],
+                        "action": "allow",
+                        "direction": "inbound",
+                        "user": null,
+                        "comment": "ssh",
+                    }],
+                },
+                "ssh": {
+                    "registry_version": 8,
+                    "subnet_id": null,
+                    "readonly_keys": ["key1", "key2"],
+                    "backup_keys": [],
+                    "admin_keys": ["key3"],
+                },
+                "errors": ["scheduled upgrade: unavailable"],
+            })
+        );
     }
 }
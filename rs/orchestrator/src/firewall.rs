@@ -33,6 +33,7 @@ pub(crate) struct Firewall {
     source: DataSource,
     compiled_config: String,
     last_applied_version: Arc<RwLock<RegistryVersion>>,
+    last_applied_rules: Arc<RwLock<Vec<FirewallRule>>>,
     // If true, write the file content even if no change was detected in registry, i.e. first time
     must_write: bool,
     // If false, do not update the firewall rules (test mode)
@@ -70,6 +71,7 @@ impl Firewall {
             logger,
             compiled_config: Default::default(),
             last_applied_version: Default::default(),
+            last_applied_rules: Default::default(),
             must_write: true,
             enabled,
             node_id,
@@ -206,7 +208,7 @@ impl Firewall {
         rules.insert(0, ic_http_adapter_rule);
 
         // Generate the firewall file content
-        let content = Self::generate_firewall_file_content_full(&self.configuration, rules);
+        let content = Self::generate_firewall_file_content_full(&self.configuration, rules.clone());
 
         let changed = content.ne(&self.compiled_config);
         if changed {
@@ -229,6 +231,7 @@ impl Firewall {
                 self.write_firewall_file(&content)?;
 
                 self.compiled_config = content;
+                *self.last_applied_rules.write().await = rules;
                 update_version_metric = true;
             }
             self.must_write = false;
@@ -400,6 +403,10 @@ impl Firewall {
     pub fn get_last_applied_version(&self) -> Arc<RwLock<RegistryVersion>> {
         Arc::clone(&self.last_applied_version)
     }
+
+    pub fn get_last_applied_rules(&self) -> Arc<RwLock<Vec<FirewallRule>>> {
+        Arc::clone(&self.last_applied_rules)
+    }
 }
 
 #[test]
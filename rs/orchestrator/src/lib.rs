@@ -26,6 +26,15 @@
 //! 5. If the version is different from what we are currently running, apply
 //! upgrade and restart replica with that CUP.
 //!
+//! If an upgrade health check window is configured, the orchestrator does not
+//! confirm the boot of a newly installed version right away. Instead, it
+//! monitors the replica for the duration of the window and reboots into the
+//! previous version if the replica keeps crashing or the subnet does not make
+//! progress. On assigned nodes, the window spans at least two DKG intervals of
+//! the subnet, and a halted subnet is not expected to make progress. While the
+//! upgrade to a version is refused, e.g. after rolling back from it, the
+//! replica of the current version keeps running.
+//!
 //! # Registry
 //!
 //! The orchestrator also fetches configuration updates from the
@@ -45,3 +54,4 @@ mod replica_process;
 mod signer;
 mod ssh_access_manager;
 mod upgrade;
+mod upgrade_health;
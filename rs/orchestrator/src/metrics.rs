@@ -15,6 +15,7 @@ pub struct OrchestratorMetrics {
     pub reboot_duration: IntGauge,
     pub orchestrator_info: IntGaugeVec,
     pub key_rotation_status: IntGaugeVec,
+    pub upgrade_health_status: IntGaugeVec,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, Eq, IntoStaticStr, PartialOrd, Ord, PartialEq)]
@@ -27,6 +28,21 @@ pub enum KeyRotationStatus {
     Error,
 }
 
+#[derive(Copy, Clone, Debug, EnumIter, Eq, IntoStaticStr, PartialOrd, Ord, PartialEq)]
+pub enum UpgradeHealthStatus {
+    /// The boot was confirmed without a health check.
+    NotRequired,
+    /// The replica is being monitored after the first boot into a new version.
+    Monitoring,
+    /// The new version passed the health check and its boot was confirmed.
+    Healthy,
+    /// The node rolled back to this version after a new version failed its
+    /// health check.
+    RolledBack,
+    /// The new version failed its health check but could not be rolled back.
+    RollbackFailed,
+}
+
 impl KeyRotationStatus {
     fn is_transient(self) -> bool {
         matches!(
@@ -77,9 +93,23 @@ impl OrchestratorMetrics {
                 "The current key rotation status.",
                 &["status"],
             ),
+            upgrade_health_status: metrics_registry.int_gauge_vec(
+                "orchestrator_upgrade_health_status",
+                "The status of the health check of the last replica version upgrade.",
+                &["status"],
+            ),
         }
     }
 
+    /// Set the current upgrade health status to the given status and clear all other states.
+    pub fn observe_upgrade_health_status(&self, status: UpgradeHealthStatus) {
+        UpgradeHealthStatus::iter().for_each(|s| {
+            self.upgrade_health_status
+                .with_label_values(&[s.into()])
+                .set((s == status) as i64);
+        });
+    }
+
     /// Set the current key rotation status to the given status and clear all other states.
     /// If the given status is a transient state, do not clear the error status.
     pub fn observe_key_rotation_status(&self, status: KeyRotationStatus) {
@@ -188,23 +188,25 @@ impl Orchestrator {
             registration.register_node().await;
         }
 
-        let upgrade = Some(
-            Upgrade::new(
-                Arc::clone(&registry),
-                Arc::clone(&metrics),
-                Arc::clone(&replica_process),
-                Arc::clone(&cup_provider),
-                replica_version.clone(),
-                args.replica_config_file.clone(),
-                node_id,
-                ic_binary_directory,
-                registry_replicator,
-                args.replica_binary_dir.clone(),
-                logger.clone(),
-                args.orchestrator_data_directory.clone(),
-            )
-            .await,
-        );
+        let upgrade = Upgrade::new(
+            Arc::clone(&registry),
+            Arc::clone(&metrics),
+            Arc::clone(&replica_process),
+            Arc::clone(&cup_provider),
+            replica_version.clone(),
+            args.replica_config_file.clone(),
+            node_id,
+            ic_binary_directory,
+            registry_replicator,
+            args.replica_binary_dir.clone(),
+            logger.clone(),
+            args.orchestrator_data_directory.clone(),
+            args.upgrade_health_check_window_secs
+                .map(Duration::from_secs),
+        )
+        .await;
+        let upgrade_health_report = upgrade.get_health_report();
+        let upgrade = Some(upgrade);
 
         let firewall = Firewall::new(
             node_id,
@@ -224,10 +226,12 @@ impl Orchestrator {
             node_id,
             ssh_access_manager.get_last_applied_parameters(),
             firewall.get_last_applied_version(),
+            firewall.get_last_applied_rules(),
             replica_process,
             Arc::clone(&subnet_id),
             replica_version,
             cup_provider,
+            upgrade_health_report,
             logger.clone(),
         ));
 
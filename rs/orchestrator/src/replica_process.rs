@@ -4,6 +4,7 @@ use nix::{
     unistd::Pid,
 };
 use slog::{debug, info, warn};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Mutex;
 use std::{io::Result, sync::Arc};
 
@@ -21,6 +22,12 @@ pub(crate) struct ReplicaProcess {
     pub(crate) pid_cell: PIDCell,
     pub(crate) log: slog::Logger,
     pub(crate) join_handle: Option<std::thread::JoinHandle<()>>,
+    /// Set while the orchestrator is stopping the current replica process, so
+    /// that its exit is not counted as a crash.
+    stop_requested: Arc<AtomicBool>,
+    /// Number of times a replica process exited without being stopped by the
+    /// orchestrator.
+    crash_count: Arc<AtomicU64>,
 }
 
 impl ReplicaProcess {
@@ -30,9 +37,17 @@ impl ReplicaProcess {
             pid_cell: Default::default(),
             log: logger.clone(),
             join_handle: None,
+            stop_requested: Default::default(),
+            crash_count: Default::default(),
         }
     }
 
+    /// Returns the number of times a replica process exited on its own since
+    /// the orchestrator started.
+    pub fn crash_count(&self) -> u64 {
+        self.crash_count.load(Ordering::SeqCst)
+    }
+
     /// Returns true only if the replica process is running.
     pub fn is_running(&self) -> bool {
         self.get_pid().is_some()
@@ -79,6 +94,7 @@ impl ReplicaProcess {
     pub fn kill(&mut self) -> Result<()> {
         let pid = self.pid_cell.lock().unwrap();
         if let Some(pid) = *pid {
+            self.stop_requested.store(true, Ordering::SeqCst);
             let mut gpid = pid;
             // We want to signal the whole process group.
             if gpid > Pid::from_raw(0) {
@@ -138,24 +154,29 @@ impl ReplicaProcess {
                 .args(&args)
                 .spawn()?;
             debug!(self.log, "Process started. Pid: {}", child.id());
+            self.stop_requested.store(false, Ordering::SeqCst);
             self.set_pid(Pid::from_raw(child.id() as i32));
 
             self.join_handle = Some(std::thread::spawn(wait_on_exit(
                 self.log.clone(),
                 child,
                 self.pid_cell.clone(),
+                self.stop_requested.clone(),
+                self.crash_count.clone(),
             )));
         }
         Ok(())
     }
 }
 
-/// Wait for the child process to return, log the exit status and send
-/// `ReplicaExited`-message to `exit_recipient`.
+/// Wait for the child process to return, log the exit status and count the
+/// exit as a crash unless it was requested by the orchestrator.
 fn wait_on_exit(
     log: slog::Logger,
     mut process: std::process::Child,
     pid_cell: PIDCell,
+    stop_requested: Arc<AtomicBool>,
+    crash_count: Arc<AtomicU64>,
 ) -> impl FnOnce() {
     move || {
         let exit_status = process.wait();
@@ -164,6 +185,13 @@ fn wait_on_exit(
         } else {
             info!(log, "Replica exited. Exit Status: {:?}", exit_status);
         }
+        if !stop_requested.load(Ordering::SeqCst) {
+            let crashes = crash_count.fetch_add(1, Ordering::SeqCst) + 1;
+            warn!(
+                log,
+                "Replica exited unexpectedly ({} time(s) so far)", crashes
+            );
+        }
         let _pid = pid_cell.lock().unwrap().take();
     }
 }
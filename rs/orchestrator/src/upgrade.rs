@@ -1,22 +1,32 @@
 use crate::catch_up_package_provider::CatchUpPackageProvider;
 use crate::error::{OrchestratorError, OrchestratorResult};
-use crate::metrics::OrchestratorMetrics;
+use crate::metrics::{OrchestratorMetrics, UpgradeHealthStatus};
 use crate::registry_helper::RegistryHelper;
 use crate::replica_process::ReplicaProcess;
+use crate::upgrade_health::{
+    min_window_for_subnet, HealthVerdict, UpgradeHealthCheck, UpgradeHealthReport,
+};
 use async_trait::async_trait;
 use ic_http_utils::file_downloader::FileDownloader;
 use ic_image_upgrader::error::{UpgradeError, UpgradeResult};
 use ic_image_upgrader::ImageUpgrader;
 use ic_interfaces_registry::RegistryClient;
-use ic_logger::{info, warn, ReplicaLogger};
+use ic_logger::{error, info, warn, ReplicaLogger};
 use ic_registry_client_helpers::node::NodeRegistry;
 use ic_registry_client_helpers::subnet::SubnetRegistry;
 use ic_registry_local_store::LocalStoreImpl;
 use ic_registry_replicator::RegistryReplicator;
 use ic_types::consensus::{CatchUpPackage, HasHeight};
 use ic_types::{Height, NodeId, RegistryVersion, ReplicaVersion, SubnetId};
+use std::convert::TryFrom;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// The file in the orchestrator data directory recording the replica version
+/// that was rolled back after failing its health check.
+const ROLLED_BACK_VERSION_FILENAME: &str = "rolled_back_version.txt";
 
 /// Provides function to continuously check the Registry to determine if this
 /// node should upgrade to a new release package, and if so, downloads and
@@ -36,6 +46,12 @@ pub(crate) struct Upgrade {
     /// The replica version that is prepared by 'prepare_upgrade' to upgrade to.
     pub prepared_upgrade_version: Option<ReplicaVersion>,
     pub orchestrator_data_directory: Option<PathBuf>,
+    metrics: Arc<OrchestratorMetrics>,
+    /// Set while the boot of a newly installed version awaits confirmation.
+    health_check: Option<UpgradeHealthCheck>,
+    health_report: Arc<RwLock<UpgradeHealthReport>>,
+    /// A version we rolled back from, which we must not upgrade to again.
+    rolled_back_version: Option<ReplicaVersion>,
 }
 
 impl Upgrade {
@@ -53,8 +69,9 @@ impl Upgrade {
         release_content_dir: PathBuf,
         logger: ReplicaLogger,
         orchestrator_data_directory: Option<PathBuf>,
+        upgrade_health_check_window: Option<Duration>,
     ) -> Self {
-        let value = Self {
+        let mut value = Self {
             registry,
             replica_process,
             cup_provider,
@@ -67,14 +84,271 @@ impl Upgrade {
             logger: logger.clone(),
             prepared_upgrade_version: None,
             orchestrator_data_directory,
+            metrics: Arc::clone(&metrics),
+            health_check: None,
+            health_report: Default::default(),
+            rolled_back_version: None,
         };
         if let Err(e) = value.report_reboot_time(metrics) {
             warn!(logger, "Cannot report the reboot time: {}", e);
         }
-        value.confirm_boot().await;
+        value.rolled_back_version = value.read_rolled_back_version();
+        let report = match &value.rolled_back_version {
+            Some(version) => UpgradeHealthReport::new(
+                UpgradeHealthStatus::RolledBack,
+                format!("rolled back from version {}", version),
+            ),
+            None => UpgradeHealthReport::default(),
+        };
+        value.set_health_report(report).await;
+        value
+            .confirm_boot_or_start_health_check(upgrade_health_check_window)
+            .await;
         value
     }
 
+    /// Returns the outcome of the health check of the last upgrade.
+    pub(crate) fn get_health_report(&self) -> Arc<RwLock<UpgradeHealthReport>> {
+        Arc::clone(&self.health_report)
+    }
+
+    async fn set_health_report(&self, report: UpgradeHealthReport) {
+        self.metrics.observe_upgrade_health_status(report.status);
+        *self.health_report.write().await = report;
+    }
+
+    // Confirms the boot right away, unless a health check window is configured
+    // and this is the first boot into a newly installed version. In that case,
+    // the boot is confirmed or rolled back by `check_upgrade_health()`.
+    async fn confirm_boot_or_start_health_check(&mut self, window: Option<Duration>) {
+        let window = match (window, &self.orchestrator_data_directory) {
+            (Some(window), Some(_)) => window,
+            (Some(_), None) => {
+                warn!(
+                    self.logger,
+                    "Upgrade health checks require `orchestrator_data_directory`, confirming the boot"
+                );
+                self.confirm_boot().await;
+                return;
+            }
+            (None, _) => {
+                self.confirm_boot().await;
+                return;
+            }
+        };
+        match self.is_boot_confirmed().await {
+            Ok(false) => {
+                info!(
+                    self.logger,
+                    "First boot into version {}: monitoring the replica for {:?} before confirming the boot",
+                    self.replica_version,
+                    window
+                );
+                let cup_height = self
+                    .cup_provider
+                    .get_local_cup()
+                    .map(|cup| cup.cup.content.height());
+                let crash_count = self.replica_process.lock().unwrap().crash_count();
+                self.health_check = Some(UpgradeHealthCheck::new(
+                    window,
+                    Instant::now(),
+                    cup_height,
+                    crash_count,
+                ));
+                self.set_health_report(UpgradeHealthReport::new(
+                    UpgradeHealthStatus::Monitoring,
+                    format!(
+                        "confirming version {} after {:?}",
+                        self.replica_version, window
+                    ),
+                ))
+                .await;
+            }
+            Ok(true) => self.confirm_boot().await,
+            Err(e) => {
+                warn!(
+                    self.logger,
+                    "Cannot determine whether the boot was confirmed, confirming it: {}", e
+                );
+                self.confirm_boot().await;
+            }
+        }
+    }
+
+    // Confirms the boot of the current version once it passed its health
+    // check, or reboots into the previous version if it failed.
+    async fn check_upgrade_health(&mut self) {
+        if self.health_check.is_none() {
+            return;
+        }
+        let local_cup = self.cup_provider.get_local_cup();
+        let cup_height = local_cup.as_ref().map(|cup| cup.cup.content.height());
+        let subnet_halted = match local_cup
+            .as_ref()
+            .and_then(|cup| get_subnet_id(&*self.registry.registry_client, &cup.cup).ok())
+        {
+            Some(subnet_id) => {
+                self.extend_health_check_window(subnet_id);
+                self.is_subnet_halted(subnet_id)
+            }
+            None => false,
+        };
+        let health_check = match &self.health_check {
+            Some(health_check) => health_check,
+            None => return,
+        };
+        let crash_count = self.replica_process.lock().unwrap().crash_count();
+        let now = Instant::now();
+        match health_check.evaluate(now, cup_height, subnet_halted, crash_count) {
+            HealthVerdict::Pending => {
+                info!(
+                    self.logger,
+                    "Upgrade health check of version {} ends in {:?}",
+                    self.replica_version,
+                    health_check.remaining(now)
+                );
+            }
+            HealthVerdict::Healthy => {
+                info!(
+                    self.logger,
+                    "Version {} passed its health check, confirming the boot", self.replica_version
+                );
+                self.health_check = None;
+                self.confirm_boot().await;
+                if let Err(e) = self.clear_rolled_back_version() {
+                    warn!(self.logger, "{}", e);
+                }
+                self.rolled_back_version = None;
+                self.set_health_report(UpgradeHealthReport::new(
+                    UpgradeHealthStatus::Healthy,
+                    format!("version {} confirmed", self.replica_version),
+                ))
+                .await;
+            }
+            HealthVerdict::Unhealthy(reason) => {
+                warn!(
+                    self.logger,
+                    "Version {} failed its health check: {}", self.replica_version, reason
+                );
+                self.health_check = None;
+                if let Err(e) = self.persist_rolled_back_version() {
+                    warn!(self.logger, "{}", e);
+                }
+                if let Err(e) = self.stop_replica() {
+                    warn!(self.logger, "{}", e);
+                }
+                // Only returns if the rollback failed.
+                let e = self.rollback_boot().await;
+                error!(
+                    self.logger,
+                    "Cannot roll back version {}: {}", self.replica_version, e
+                );
+                self.set_health_report(UpgradeHealthReport::new(
+                    UpgradeHealthStatus::RollbackFailed,
+                    format!("{}; rollback failed: {}", reason, e),
+                ))
+                .await;
+            }
+        }
+    }
+
+    // Extends the health check window to span several DKG intervals of the
+    // given subnet. Registry errors are logged and leave the window as is.
+    fn extend_health_check_window(&mut self, subnet_id: SubnetId) {
+        let registry_client = &*self.registry.registry_client;
+        let version = registry_client.get_latest_version();
+        match (
+            registry_client.get_dkg_interval_length(subnet_id, version),
+            registry_client.get_notarization_delay_settings(subnet_id, version),
+        ) {
+            (Ok(Some(dkg_interval_length)), Ok(Some(delays))) => {
+                if let Some(health_check) = self.health_check.as_mut() {
+                    health_check.extend_window(min_window_for_subnet(
+                        dkg_interval_length,
+                        delays.unit_delay,
+                        delays.initial_notary_delay,
+                    ));
+                }
+            }
+            other => warn!(
+                self.logger,
+                "Cannot read the DKG interval of subnet {} at registry version {}: {:?}",
+                subnet_id,
+                version,
+                other
+            ),
+        }
+    }
+
+    // Returns whether the given subnet is halted at the latest registry version.
+    // Registry errors are logged and treated as not halted.
+    fn is_subnet_halted(&self, subnet_id: SubnetId) -> bool {
+        let version = self.registry.get_latest_version();
+        match self
+            .registry
+            .registry_client
+            .get_is_halted(subnet_id, version)
+        {
+            Ok(Some(halted)) => halted,
+            other => {
+                warn!(
+                    self.logger,
+                    "Cannot read whether subnet {} is halted at registry version {}: {:?}",
+                    subnet_id,
+                    version,
+                    other
+                );
+                false
+            }
+        }
+    }
+
+    // Refuses to upgrade while the current version awaits its health check or
+    // if the given version was rolled back before.
+    fn ensure_upgrade_allowed(&self, version: &ReplicaVersion) -> OrchestratorResult<()> {
+        ensure_upgrade_allowed(
+            version,
+            &self.replica_version,
+            self.health_check.is_some(),
+            self.rolled_back_version.as_ref(),
+        )
+    }
+
+    fn get_rolled_back_version_file_path(&self) -> Option<PathBuf> {
+        self.orchestrator_data_directory
+            .as_ref()
+            .map(|dir| dir.join(ROLLED_BACK_VERSION_FILENAME))
+    }
+
+    // Returns the version recorded by a rollback, unless it is the version we
+    // are running, which means that the rollback did not happen.
+    fn read_rolled_back_version(&self) -> Option<ReplicaVersion> {
+        let path = self.get_rolled_back_version_file_path()?;
+        let contents = std::fs::read_to_string(path).ok()?;
+        ReplicaVersion::try_from(contents.trim())
+            .ok()
+            .filter(|version| version != &self.replica_version)
+    }
+
+    fn persist_rolled_back_version(&self) -> OrchestratorResult<()> {
+        let path = self.get_rolled_back_version_file_path().ok_or_else(|| {
+            OrchestratorError::UpgradeError(
+                "`orchestrator_data_directory` is not provided".to_string(),
+            )
+        })?;
+        std::fs::write(&path, self.replica_version.as_ref())
+            .map_err(|e| OrchestratorError::file_write_error(&path, e))
+    }
+
+    fn clear_rolled_back_version(&self) -> OrchestratorResult<()> {
+        match self.get_rolled_back_version_file_path() {
+            Some(path) if path.exists() => std::fs::remove_file(&path).map_err(|e| {
+                OrchestratorError::IoError(format!("Failed to remove file: {:?}", path), e)
+            }),
+            _ => Ok(()),
+        }
+    }
+
     fn report_reboot_time(&self, metrics: Arc<OrchestratorMetrics>) -> OrchestratorResult<()> {
         let elapsed_time = self.get_time_since_last_reboot_trigger()?;
         metrics.reboot_duration.set(elapsed_time.as_secs() as i64);
@@ -84,6 +358,8 @@ impl Upgrade {
     /// Checks for a new release package, and if found, upgrades to this release
     /// package
     pub(crate) async fn check(&mut self) -> OrchestratorResult<Option<SubnetId>> {
+        self.check_upgrade_health().await;
+
         let latest_registry_version = self.registry.get_latest_version();
         // Determine the subnet_id using the local CUP.
         let (subnet_id, local_cup) = if let Some(cup) = self.cup_provider.get_local_cup() {
@@ -112,6 +388,9 @@ impl Upgrade {
 
         // When we arrived here, we are an assigned node.
         let old_cup_height = local_cup.as_ref().map(|cup| cup.cup.content.height());
+        let old_oldest_version_in_use = local_cup
+            .as_ref()
+            .map(|cup| get_oldest_registry_version_in_use(&cup.cup));
 
         // Get the latest available CUP from the disk, peers or registry and
         // persist it if necessary.
@@ -156,8 +435,19 @@ impl Upgrade {
         }
 
         // If we arrived here, we have the newest CUP and we're still assigned.
+        // The replica reads the registry at the versions referenced by this CUP
+        // and, until it picked the new CUP up, by the previous local one, so the
+        // local store must not be compacted past them.
+        let oldest_version_in_use = get_oldest_registry_version_in_use(&cup.cup);
+        self.registry_replicator.set_oldest_registry_version_in_use(
+            old_oldest_version_in_use
+                .map_or(oldest_version_in_use, |v| v.min(oldest_version_in_use)),
+        );
+
         // Now we check if this CUP requires a new replica version.
         let cup_registry_version = cup.cup.content.registry_version();
+
+        // Now we check if this CUP requires a new replica version.
         let new_replica_version = self
             .registry
             .get_replica_version(subnet_id, cup_registry_version)?;
@@ -169,13 +459,20 @@ impl Upgrade {
                 self.replica_version,
                 new_replica_version
             );
-            // Only downloads the new image if it doesn't already exists locally, i.e. it
-            // was previously downloaded by `prepare_upgrade_if_scheduled()`, see
-            // below.
-            return self
-                .execute_upgrade(&new_replica_version)
-                .await
-                .map_err(OrchestratorError::from);
+            match self.ensure_upgrade_allowed(&new_replica_version) {
+                // Only downloads the new image if it doesn't already exists locally, i.e. it
+                // was previously downloaded by `prepare_upgrade_if_scheduled()`, see
+                // below.
+                Ok(()) => {
+                    return self
+                        .execute_upgrade(&new_replica_version)
+                        .await
+                        .map_err(OrchestratorError::from)
+                }
+                // A refused upgrade must not stop the node from participating in its
+                // subnet, e.g. after a rollback the current replica keeps running.
+                Err(e) => warn!(self.logger, "{}", e),
+            }
         }
 
         // If we arrive here, we are on the newest replica version.
@@ -260,6 +557,10 @@ impl Upgrade {
                 self.replica_version,
                 expected_replica_version
             );
+            if let Err(e) = self.ensure_upgrade_allowed(&expected_replica_version) {
+                warn!(self.logger, "{}", e);
+                return Ok(());
+            }
             self.prepare_upgrade(&expected_replica_version).await?
         }
         Ok(())
@@ -279,6 +580,7 @@ impl Upgrade {
             self.replica_version,
             replica_version
         );
+        self.ensure_upgrade_allowed(&replica_version)?;
         self.execute_upgrade(&replica_version)
             .await
             .map_err(OrchestratorError::from)
@@ -452,6 +754,42 @@ fn get_subnet_id(registry: &dyn RegistryClient, cup: &CatchUpPackage) -> Result<
     }
 }
 
+// Refuses to upgrade from `current_version` to `version` while the current
+// version awaits its health check or if `version` was rolled back before.
+fn ensure_upgrade_allowed(
+    version: &ReplicaVersion,
+    current_version: &ReplicaVersion,
+    health_check_pending: bool,
+    rolled_back_version: Option<&ReplicaVersion>,
+) -> OrchestratorResult<()> {
+    if health_check_pending {
+        return Err(OrchestratorError::UpgradeError(format!(
+            "Upgrade to version {} deferred until version {} passed its health check",
+            version, current_version
+        )));
+    }
+    if rolled_back_version == Some(version) {
+        return Err(OrchestratorError::UpgradeError(format!(
+            "Version {} was rolled back after failing its health check, waiting for a different version",
+            version
+        )));
+    }
+    Ok(())
+}
+
+// Returns the oldest registry version at which the replica reads the registry
+// when running from the given CUP.
+fn get_oldest_registry_version_in_use(cup: &CatchUpPackage) -> RegistryVersion {
+    cup.content
+        .block
+        .get_value()
+        .payload
+        .as_ref()
+        .as_summary()
+        .get_oldest_registry_version_in_use()
+        .min(cup.content.registry_version())
+}
+
 // Checks if the node still belongs to the subnet it was assigned the last time.
 // We decide this by checking the subnet membership starting from the oldest
 // relevant version of the local CUP and ending with the latest registry
@@ -526,3 +864,30 @@ fn reexec_current_process(logger: &ReplicaLogger) -> OrchestratorError {
     let error = exec::Command::new(&args[0]).args(&args[1..]).exec();
     OrchestratorError::ExecError(PathBuf::new(), error)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(v: &str) -> ReplicaVersion {
+        ReplicaVersion::try_from(v).unwrap()
+    }
+
+    #[test]
+    fn upgrade_is_deferred_while_the_health_check_is_pending() {
+        assert!(ensure_upgrade_allowed(&version("B"), &version("A"), true, None).is_err());
+        assert!(ensure_upgrade_allowed(&version("B"), &version("A"), false, None).is_ok());
+    }
+
+    #[test]
+    fn upgrade_to_a_rolled_back_version_is_refused() {
+        let rolled_back = version("B");
+        assert!(
+            ensure_upgrade_allowed(&version("B"), &version("A"), false, Some(&rolled_back))
+                .is_err()
+        );
+        assert!(
+            ensure_upgrade_allowed(&version("C"), &version("A"), false, Some(&rolled_back)).is_ok()
+        );
+    }
+}
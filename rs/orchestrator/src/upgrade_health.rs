@@ -0,0 +1,247 @@
+use crate::metrics::UpgradeHealthStatus;
+use ic_types::Height;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// The number of unexpected replica exits after which a newly installed
+/// version is considered broken, even before the health check window elapsed.
+const MAX_REPLICA_CRASHES: u64 = 3;
+
+/// The number of DKG intervals the health check window of an assigned node
+/// lasts at least, so that the subnet has the chance to create a new CUP on
+/// the new version even if the upgrade happened right after a CUP.
+const MIN_DKG_INTERVALS_PER_WINDOW: u32 = 2;
+
+/// Returns the minimal health check window for a node on a subnet with the
+/// given DKG interval length and notarization delays, see
+/// [`UpgradeHealthCheck::extend_window`].
+pub(crate) fn min_window_for_subnet(
+    dkg_interval_length: Height,
+    unit_delay: Duration,
+    initial_notary_delay: Duration,
+) -> Duration {
+    let blocks_per_interval = u32::try_from(dkg_interval_length.get() + 1).unwrap_or(u32::MAX);
+    (unit_delay + initial_notary_delay)
+        .saturating_mul(blocks_per_interval)
+        .saturating_mul(MIN_DKG_INTERVALS_PER_WINDOW)
+}
+
+/// The outcome of the health check of a newly installed version, as shown on
+/// the orchestrator dashboard.
+#[derive(Clone, Debug)]
+pub(crate) struct UpgradeHealthReport {
+    pub(crate) status: UpgradeHealthStatus,
+    pub(crate) details: String,
+}
+
+impl UpgradeHealthReport {
+    pub(crate) fn new(status: UpgradeHealthStatus, details: impl ToString) -> Self {
+        Self {
+            status,
+            details: details.to_string(),
+        }
+    }
+}
+
+impl Default for UpgradeHealthReport {
+    fn default() -> Self {
+        Self::new(UpgradeHealthStatus::NotRequired, "boot confirmed")
+    }
+}
+
+impl fmt::Display for UpgradeHealthReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} ({})", self.status, self.details)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum HealthVerdict {
+    /// The health check window did not elapse yet.
+    Pending,
+    /// The new version can be confirmed.
+    Healthy,
+    /// The new version should be rolled back for the given reason.
+    Unhealthy(String),
+}
+
+/// Monitors the replica after the first boot into a new version.
+///
+/// A version is considered healthy if, over the whole window, the replica did
+/// not crash more than [`MAX_REPLICA_CRASHES`] times and, if the node is
+/// assigned to a subnet that is not halted, the local CUP advanced, i.e. the
+/// subnet finalized at least one DKG interval on the new version. For assigned
+/// nodes, the window is hence extended to span several DKG intervals of the
+/// subnet, see [`Self::extend_window`].
+pub(crate) struct UpgradeHealthCheck {
+    start: Instant,
+    deadline: Instant,
+    initial_cup_height: Option<Height>,
+    initial_crash_count: u64,
+}
+
+impl UpgradeHealthCheck {
+    pub(crate) fn new(
+        window: Duration,
+        now: Instant,
+        cup_height: Option<Height>,
+        crash_count: u64,
+    ) -> Self {
+        Self {
+            start: now,
+            deadline: now + window,
+            initial_cup_height: cup_height,
+            initial_crash_count: crash_count,
+        }
+    }
+
+    /// Returns the time left until the health check window elapses.
+    pub(crate) fn remaining(&self, now: Instant) -> Duration {
+        self.deadline.saturating_duration_since(now)
+    }
+
+    /// Extends the window to last at least `min_window` since the start of the
+    /// health check, e.g. the duration returned by [`min_window_for_subnet`].
+    pub(crate) fn extend_window(&mut self, min_window: Duration) {
+        self.deadline = self.deadline.max(self.start + min_window);
+    }
+
+    /// Evaluates the health of the replica given the current height of the
+    /// local CUP (`None` if the node is unassigned), whether the subnet of the
+    /// node is halted and the number of replica crashes since the orchestrator
+    /// started. A halted subnet does not create CUPs, so only crashes count.
+    pub(crate) fn evaluate(
+        &self,
+        now: Instant,
+        cup_height: Option<Height>,
+        subnet_halted: bool,
+        crash_count: u64,
+    ) -> HealthVerdict {
+        let crashes = crash_count.saturating_sub(self.initial_crash_count);
+        if crashes > MAX_REPLICA_CRASHES {
+            return HealthVerdict::Unhealthy(format!(
+                "the replica crashed {} times since the upgrade",
+                crashes
+            ));
+        }
+        if now < self.deadline {
+            return HealthVerdict::Pending;
+        }
+        match cup_height {
+            Some(height) if !subnet_halted && Some(height) <= self.initial_cup_height => {
+                HealthVerdict::Unhealthy(format!(
+                    "no finalization progress since the upgrade: the local CUP is still at height {}",
+                    height
+                ))
+            }
+            _ => HealthVerdict::Healthy,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: Duration = Duration::from_secs(600);
+
+    #[test]
+    fn pending_until_window_elapses() {
+        let start = Instant::now();
+        let check = UpgradeHealthCheck::new(WINDOW, start, Some(Height::from(100)), 0);
+        assert_eq!(
+            check.evaluate(start + WINDOW / 2, Some(Height::from(100)), false, 1),
+            HealthVerdict::Pending
+        );
+        assert_eq!(check.remaining(start + WINDOW / 2), WINDOW / 2);
+        assert_eq!(
+            check.evaluate(start + WINDOW, Some(Height::from(200)), false, 1),
+            HealthVerdict::Healthy
+        );
+    }
+
+    #[test]
+    fn crash_loop_is_unhealthy_before_window_elapses() {
+        let start = Instant::now();
+        let check = UpgradeHealthCheck::new(WINDOW, start, None, 2);
+        assert_eq!(
+            check.evaluate(start, None, false, 2 + MAX_REPLICA_CRASHES),
+            HealthVerdict::Pending
+        );
+        assert!(matches!(
+            check.evaluate(start, None, false, 3 + MAX_REPLICA_CRASHES),
+            HealthVerdict::Unhealthy(_)
+        ));
+    }
+
+    #[test]
+    fn missing_finalization_progress_is_unhealthy() {
+        let start = Instant::now();
+        let check = UpgradeHealthCheck::new(WINDOW, start, Some(Height::from(100)), 0);
+        assert!(matches!(
+            check.evaluate(start + WINDOW, Some(Height::from(100)), false, 0),
+            HealthVerdict::Unhealthy(_)
+        ));
+    }
+
+    #[test]
+    fn unassigned_node_only_needs_to_stay_up() {
+        let start = Instant::now();
+        let check = UpgradeHealthCheck::new(WINDOW, start, None, 0);
+        assert_eq!(
+            check.evaluate(start + WINDOW, None, false, 0),
+            HealthVerdict::Healthy
+        );
+        // A node that got assigned during the window made progress.
+        assert_eq!(
+            check.evaluate(start + WINDOW, Some(Height::from(0)), false, 0),
+            HealthVerdict::Healthy
+        );
+    }
+
+    #[test]
+    fn halted_subnet_only_needs_to_stay_up() {
+        let start = Instant::now();
+        let check = UpgradeHealthCheck::new(WINDOW, start, Some(Height::from(100)), 0);
+        assert_eq!(
+            check.evaluate(start + WINDOW, Some(Height::from(100)), true, 0),
+            HealthVerdict::Healthy
+        );
+        assert!(matches!(
+            check.evaluate(
+                start,
+                Some(Height::from(100)),
+                true,
+                1 + MAX_REPLICA_CRASHES
+            ),
+            HealthVerdict::Unhealthy(_)
+        ));
+    }
+
+    #[test]
+    fn window_spans_several_dkg_intervals() {
+        let min_window = min_window_for_subnet(
+            Height::from(499),
+            Duration::from_millis(1000),
+            Duration::from_millis(600),
+        );
+        assert_eq!(min_window, Duration::from_secs(1600));
+
+        let start = Instant::now();
+        let mut check = UpgradeHealthCheck::new(WINDOW, start, Some(Height::from(100)), 0);
+        check.extend_window(min_window);
+        assert_eq!(check.remaining(start), min_window);
+        assert_eq!(
+            check.evaluate(start + WINDOW, Some(Height::from(100)), false, 0),
+            HealthVerdict::Pending
+        );
+        assert!(matches!(
+            check.evaluate(start + min_window, Some(Height::from(100)), false, 0),
+            HealthVerdict::Unhealthy(_)
+        ));
+
+        // A window that is long enough already is not shortened.
+        check.extend_window(WINDOW);
+        assert_eq!(check.remaining(start), min_window);
+    }
+}
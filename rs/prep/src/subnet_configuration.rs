@@ -102,6 +102,10 @@ pub struct SubnetConfig {
     /// The list of public keys whose owners have "backup" SSH access to nodes
     /// on the NNS subnet.
     pub ssh_backup_access: Vec<String>,
+
+    /// Whether consensus adapts the notarization delays to the observed block
+    /// rate, using `unit_delay` and `initial_notary_delay` as upper bounds.
+    pub adaptive_notarization_delays: bool,
 }
 
 #[derive(Error, Debug)]
@@ -252,6 +256,7 @@ impl SubnetConfig {
             max_number_of_canisters: max_number_of_canisters.unwrap_or(0),
             ssh_readonly_access,
             ssh_backup_access,
+            adaptive_notarization_delays: false,
         }
     }
 
@@ -305,6 +310,8 @@ impl SubnetConfig {
             ssh_readonly_access: self.ssh_readonly_access,
             ssh_backup_access: self.ssh_backup_access,
             ecdsa_config: self.ecdsa_config,
+            adaptive_notarization_delays: self.adaptive_notarization_delays,
+            vetkd_config: None,
         };
 
         let dkg_dealing_encryption_pubkeys: BTreeMap<_, _> = initialized_nodes
@@ -76,6 +76,16 @@ pub struct SubnetRecord {
     /// If a removed key is not held by another subnet, it will be lost.
     #[prost(message, optional, tag = "27")]
     pub ecdsa_config: ::core::option::Option<EcdsaConfig>,
+    /// If true, consensus scales the notarization delays down based on statistics
+    /// of the finalized chain. `unit_delay_millis` and `initial_notary_delay_millis`
+    /// remain the upper bounds.
+    #[prost(bool, tag = "28")]
+    pub adaptive_notarization_delays: bool,
+    /// vetKD Config. The subnet derives the vetKD key from its own NI-DKG
+    /// transcript with the `VetKd` tag. This field cannot be set back to `None`
+    /// once it has been set to `Some`, and the key id cannot be changed.
+    #[prost(message, optional, tag = "29")]
+    pub vetkd_config: ::core::option::Option<VetKdConfig>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -354,6 +364,20 @@ pub struct EcdsaConfig {
     #[prost(uint64, optional, tag = "6")]
     pub idkg_key_rotation_period_ms: ::core::option::Option<u64>,
 }
+/// Per subnet vetKD configuration
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct VetKdConfig {
+    /// Identifier of the vetKD key held by the subnet.
+    #[prost(message, optional, tag = "1")]
+    pub key_id: ::core::option::Option<super::super::crypto::v1::VetKdKeyId>,
+    /// The maximum number of encrypted key requests that can be enqueued at once.
+    #[prost(uint32, tag = "2")]
+    pub max_queue_size: u32,
+    /// Encrypted key requests will timeout after the given number of nano seconds.
+    #[prost(uint64, optional, tag = "3")]
+    pub request_timeout_ns: ::core::option::Option<u64>,
+}
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
@@ -489,6 +489,7 @@ pub enum CyclesUseCase {
     HttpOutcalls = 9,
     DeletedCanisters = 10,
     NonConsumed = 11,
+    VetKd = 12,
 }
 impl CyclesUseCase {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -511,6 +512,7 @@ impl CyclesUseCase {
             CyclesUseCase::HttpOutcalls => "CYCLES_USE_CASE_HTTP_OUTCALLS",
             CyclesUseCase::DeletedCanisters => "CYCLES_USE_CASE_DELETED_CANISTERS",
             CyclesUseCase::NonConsumed => "CYCLES_USE_CASE_NON_CONSUMED",
+            CyclesUseCase::VetKd => "CYCLES_USE_CASE_VET_KD",
         }
     }
 }
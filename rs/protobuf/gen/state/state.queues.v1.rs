@@ -25,6 +25,10 @@ pub struct Stream {
     pub signals_end: u64,
     #[prost(uint64, repeated, tag = "6")]
     pub reject_signals: ::prost::alloc::vec::Vec<u64>,
+    #[prost(uint64, optional, tag = "7")]
+    pub capacity_bytes: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "8")]
+    pub remote_capacity_bytes: ::core::option::Option<u64>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
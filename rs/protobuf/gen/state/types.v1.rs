@@ -57,6 +57,7 @@ pub enum NiDkgTag {
     Unspecified = 0,
     LowThreshold = 1,
     HighThreshold = 2,
+    VetKd = 3,
 }
 impl NiDkgTag {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -68,6 +69,7 @@ impl NiDkgTag {
             NiDkgTag::Unspecified => "NI_DKG_TAG_UNSPECIFIED",
             NiDkgTag::LowThreshold => "NI_DKG_TAG_LOW_THRESHOLD",
             NiDkgTag::HighThreshold => "NI_DKG_TAG_HIGH_THRESHOLD",
+            NiDkgTag::VetKd => "NI_DKG_TAG_VET_KD",
         }
     }
 }
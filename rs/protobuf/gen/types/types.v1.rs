@@ -65,6 +65,7 @@ pub enum NiDkgTag {
     Unspecified = 0,
     LowThreshold = 1,
     HighThreshold = 2,
+    VetKd = 3,
 }
 impl NiDkgTag {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -76,6 +77,7 @@ impl NiDkgTag {
             NiDkgTag::Unspecified => "NI_DKG_TAG_UNSPECIFIED",
             NiDkgTag::LowThreshold => "NI_DKG_TAG_LOW_THRESHOLD",
             NiDkgTag::HighThreshold => "NI_DKG_TAG_HIGH_THRESHOLD",
+            NiDkgTag::VetKd => "NI_DKG_TAG_VET_KD",
         }
     }
 }
@@ -262,6 +264,10 @@ pub struct EcdsaPayload {
     pub next_key_in_creation: ::core::option::Option<KeyTranscriptCreation>,
     #[prost(message, optional, tag = "12")]
     pub key_id: ::core::option::Option<super::super::registry::crypto::v1::EcdsaKeyId>,
+    #[prost(message, repeated, tag = "13")]
+    pub ongoing_vetkd_requests: ::prost::alloc::vec::Vec<OngoingVetKdRequest>,
+    #[prost(message, repeated, tag = "14")]
+    pub vetkd_agreements: ::prost::alloc::vec::Vec<CompletedSignature>,
 }
 #[derive(serde::Serialize, serde::Deserialize)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -275,6 +281,22 @@ pub struct OngoingSignature {
 #[derive(serde::Serialize, serde::Deserialize)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct OngoingVetKdRequest {
+    #[prost(bytes = "vec", tag = "1")]
+    pub pseudo_random_id: ::prost::alloc::vec::Vec<u8>,
+    #[prost(message, optional, tag = "2")]
+    pub ni_dkg_id: ::core::option::Option<NiDkgId>,
+    #[prost(message, optional, tag = "3")]
+    pub derivation_path:
+        ::core::option::Option<super::super::registry::subnet::v1::ExtendedDerivationPath>,
+    #[prost(bytes = "vec", tag = "4")]
+    pub derivation_id: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "5")]
+    pub encryption_public_key: ::prost::alloc::vec::Vec<u8>,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct AvailableQuadruple {
     #[prost(uint64, tag = "1")]
     pub quadruple_id: u64,
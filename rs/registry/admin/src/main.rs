@@ -26,7 +26,7 @@ use ic_sns_swap::pb::v1::params::NeuronBasketConstructionParameters;
 use ic_types::p2p;
 #[macro_use]
 extern crate ic_admin_derive;
-use ic_ic00_types::{CanisterIdRecord, CanisterInstallMode, EcdsaKeyId};
+use ic_ic00_types::{CanisterIdRecord, CanisterInstallMode, EcdsaKeyId, VetKdKeyId};
 use ic_interfaces_registry::RegistryClient;
 use ic_nervous_system_common_test_keys::{
     TEST_NEURON_1_OWNER_KEYPAIR, TEST_USER1_KEYPAIR, TEST_USER1_PRINCIPAL, TEST_USER2_KEYPAIR,
@@ -38,7 +38,9 @@ use ic_nervous_system_root::{
     StopOrStartCanisterProposal,
 };
 use ic_nns_common::types::{NeuronId, ProposalId, UpdateIcpXdrConversionRatePayload};
-use ic_nns_constants::{memory_allocation_of, GOVERNANCE_CANISTER_ID, ROOT_CANISTER_ID};
+use ic_nns_constants::{
+    memory_allocation_of, GOVERNANCE_CANISTER_ID, REGISTRY_CANISTER_ID, ROOT_CANISTER_ID,
+};
 use ic_nns_governance::pb::v1::{
     add_or_remove_node_provider::Change, manage_neuron::Command, proposal::Action,
     AddOrRemoveNodeProvider, GovernanceError, ManageNeuron, NodeProvider, OpenSnsTokenSwap,
@@ -94,9 +96,16 @@ use ic_registry_routing_table::{
     CanisterIdRange, CanisterMigrations as OtherCanisterMigrations,
     RoutingTable as OtherRoutingTable,
 };
-use ic_registry_subnet_features::{EcdsaConfig, SubnetFeatures, DEFAULT_ECDSA_MAX_QUEUE_SIZE};
+use ic_registry_subnet_features::{
+    EcdsaConfig, SubnetFeatures, VetKdConfig, DEFAULT_ECDSA_MAX_QUEUE_SIZE,
+    DEFAULT_VETKD_MAX_QUEUE_SIZE,
+};
 use ic_registry_subnet_type::SubnetType;
-use ic_registry_transport::Error;
+use ic_registry_transport::{
+    delete,
+    pb::v1::{RegistryAtomicMutateRequest, RegistryDryRunAtomicMutateResponse, RegistryMutation},
+    upsert, Error,
+};
 use ic_sns_wasm::pb::v1::{
     AddWasmRequest, InsertUpgradePathEntriesRequest, PrettySnsVersion, SnsCanisterType, SnsUpgrade,
     SnsVersion, SnsWasm, UpdateAllowedPrincipalsRequest, UpdateSnsSubnetListRequest,
@@ -362,6 +371,9 @@ enum SubCommand {
     ProposeToAddOrRemoveNodeProvider(ProposeToAddOrRemoveNodeProviderCmd),
     // Get latest registry version number
     GetRegistryVersion,
+    /// Check registry mutations against the registry invariants, without
+    /// applying them.
+    DryRunRegistryMutations(DryRunRegistryMutationsCmd),
     // Submit a root proposal to the root canister to upgrade the governance canister.
     SubmitRootProposalToUpgradeGovernanceCanister(SubmitRootProposalToUpgradeGovernanceCanisterCmd),
     // Get the pending proposals to upgrade the governance canister.
@@ -1240,34 +1252,37 @@ fn parse_initial_ecdsa_config_options(
         quadruples_to_create_in_advance,
         keys: ecdsa_keys_to_request
             .as_ref()
-            .map_or_else(std::vec::Vec::new, |json| {
-                let raw: Vec<BTreeMap<String, String>> = serde_json::from_str(json).unwrap();
-
-                raw.iter()
-                    .map(|btree| {
-                        let key_id = btree
-                            .get("key_id")
-                            .map(|key| {
-                                key.parse::<EcdsaKeyId>()
-                                    .unwrap_or_else(|_| panic!("Could not parse key_id: '{}'", key))
-                            })
-                            .unwrap();
-
-                        let subnet_id = btree
-                            .get("subnet_id")
-                            .map(|x| Some(PrincipalId::from_str(x).unwrap()))
-                            .expect("subnet_id is required in EcdsaKeyRequest.");
-
-                        EcdsaKeyRequest { key_id, subnet_id }
-                    })
-                    .collect()
-            }),
+            .map_or_else(std::vec::Vec::new, |json| parse_ecdsa_key_requests(json)),
         max_queue_size: Some(max_ecdsa_queue_size.unwrap_or(DEFAULT_ECDSA_MAX_QUEUE_SIZE)),
         signature_request_timeout_ns: *signature_request_timeout_ns,
         idkg_key_rotation_period_ms: *idkg_key_rotation_period_ms,
     })
 }
 
+/// Parse a JSON encoded list of EcdsaKeyRequests, as documented for `ecdsa_keys_to_request`.
+fn parse_ecdsa_key_requests(json: &str) -> Vec<EcdsaKeyRequest> {
+    let raw: Vec<BTreeMap<String, String>> = serde_json::from_str(json).unwrap();
+
+    raw.iter()
+        .map(|btree| {
+            let key_id = btree
+                .get("key_id")
+                .map(|key| {
+                    key.parse::<EcdsaKeyId>()
+                        .unwrap_or_else(|_| panic!("Could not parse key_id: '{}'", key))
+                })
+                .unwrap();
+
+            let subnet_id = btree
+                .get("subnet_id")
+                .map(|x| Some(PrincipalId::from_str(x).unwrap()))
+                .expect("subnet_id is required in EcdsaKeyRequest.");
+
+            EcdsaKeyRequest { key_id, subnet_id }
+        })
+        .collect()
+}
+
 #[async_trait]
 impl ProposalTitleAndPayload<CreateSubnetPayload> for ProposeToCreateSubnetCmd {
     fn title(&self) -> String {
@@ -1573,6 +1588,12 @@ struct ProposeToUpdateSubnetCmd {
     /// of this field.
     pub dkg_dealings_per_block: Option<u64>,
 
+    #[clap(long)]
+    /// If set, enables (true) or disables (false) the adaptation of the
+    /// notarization delays to the block rate observed on the subnet. The unit
+    /// delay and the initial notary delay remain the upper bounds.
+    pub adaptive_notarization_delays: Option<bool>,
+
     #[clap(long)]
     /// If set, the created proposal will contain a desired override of that
     /// field to the value set. See `ProposeToCreateSubnetCmd` for the semantic
@@ -1694,6 +1715,24 @@ struct ProposeToUpdateSubnetCmd {
     #[clap(long)]
     pub ecdsa_keys_to_remove: Option<Vec<String>>,
 
+    /// Configuration for ECDSA:
+    /// The keys to reshare to the current members of this subnet from the subnets holding them,
+    /// without recovering this subnet. The keys are added to this subnet, and its nodes receive
+    /// shares of the existing keys, so the public keys are unchanged.
+    ///
+    /// The keys are given as a JSON encoded list of key requests, in the same format as the
+    /// `ecdsa_keys_to_request` option of `propose-to-create-subnet`.
+    ///
+    /// Example:
+    /// '[
+    ///     {
+    ///         "key_id": "Secp256k1:key_id_1",
+    ///         "subnet_id": "gxevo-lhkam-aaaaa-aaaap-yai"
+    ///     }
+    /// ]'
+    #[clap(long)]
+    pub ecdsa_keys_to_reshare: Option<String>,
+
     /// Configuration for ECDSA:
     /// The maximum number of signature requests that can be enqueued at once.
     /// If the queue fills up, signature requests will be rejected until there
@@ -1713,6 +1752,25 @@ struct ProposeToUpdateSubnetCmd {
     #[clap(long)]
     pub idkg_key_rotation_period_ms: Option<u64>,
 
+    /// Configuration for vetKD:
+    /// The key the subnet derives from its vetKD transcript. The key must not already exist on
+    /// the IC, and it cannot be changed once the subnet holds a key.
+    ///
+    /// The key must be given in CurveID:KeyName format, like `Bls12_381_G2:some_key_name`.
+    #[clap(long)]
+    pub vetkd_key_id: Option<String>,
+
+    /// Configuration for vetKD:
+    /// The maximum number of vetKD requests that can be enqueued at once.
+    #[clap(long)]
+    pub max_vetkd_queue_size: Option<u32>,
+
+    /// Configuration for vetKD:
+    /// The number of nanoseconds after which a vetKD request will time out.
+    /// If none is specified, no request will time out.
+    #[clap(long)]
+    pub vetkd_request_timeout_ns: Option<u64>,
+
     /// The features that are enabled and disabled on the subnet.
     #[clap(long)]
     pub features: Option<SubnetFeatures>,
@@ -1766,9 +1824,15 @@ impl ProposalTitleAndPayload<UpdateSubnetPayload> for ProposeToUpdateSubnetCmd {
         let registry_canister = RegistryCanister::new(vec![nns_url.clone()]);
         let subnet_id = self.subnet.get_id(&registry_canister).await;
 
+        let ecdsa_key_reshare_requests = self
+            .ecdsa_keys_to_reshare
+            .as_ref()
+            .map(|json| parse_ecdsa_key_requests(json));
+
         let ecdsa_config = if self.ecdsa_quadruples_to_create_in_advance.is_none()
             && self.ecdsa_keys_to_generate.is_none()
             && self.ecdsa_keys_to_remove.is_none()
+            && ecdsa_key_reshare_requests.is_none()
             && self.idkg_key_rotation_period_ms.is_none()
         {
             // No update
@@ -1800,6 +1864,12 @@ impl ProposalTitleAndPayload<UpdateSubnetPayload> for ProposeToUpdateSubnetCmd {
 
             current_keys.retain(|current| !keys_to_remove.contains(current));
             current_keys.append(&mut keys_to_add);
+            current_keys.extend(
+                ecdsa_key_reshare_requests
+                    .iter()
+                    .flatten()
+                    .map(|key_request| key_request.key_id.clone()),
+            );
 
             Some(EcdsaConfig {
                 // Default to current value if present, then 1
@@ -1819,6 +1889,43 @@ impl ProposalTitleAndPayload<UpdateSubnetPayload> for ProposeToUpdateSubnetCmd {
             })
         };
 
+        let vetkd_config = if self.vetkd_key_id.is_none()
+            && self.max_vetkd_queue_size.is_none()
+            && self.vetkd_request_timeout_ns.is_none()
+        {
+            // No update
+            None
+        } else {
+            let subnet = get_subnet_record(&registry_canister, subnet_id).await;
+            let current_vetkd_config = subnet
+                .vetkd_config
+                .map(|c| VetKdConfig::try_from(c).unwrap());
+            let key_id = match &self.vetkd_key_id {
+                Some(key) => key
+                    .parse::<VetKdKeyId>()
+                    .unwrap_or_else(|_| panic!("Could not parse key_id: '{}'", key)),
+                None => current_vetkd_config
+                    .as_ref()
+                    .map(|c| c.key_id.clone())
+                    .expect("The subnet does not hold a vetKD key, --vetkd-key-id is required."),
+            };
+
+            Some(VetKdConfig {
+                key_id,
+                max_queue_size: Some(self.max_vetkd_queue_size.unwrap_or_else(|| {
+                    current_vetkd_config
+                        .as_ref()
+                        .and_then(|c| c.max_queue_size)
+                        .unwrap_or(DEFAULT_VETKD_MAX_QUEUE_SIZE)
+                })),
+                request_timeout_ns: self.vetkd_request_timeout_ns.or_else(|| {
+                    current_vetkd_config
+                        .as_ref()
+                        .and_then(|c| c.request_timeout_ns)
+                }),
+            })
+        };
+
         let ecdsa_key_signing_enable = self
             .ecdsa_key_signing_enable
             .as_ref()
@@ -1851,6 +1958,7 @@ impl ProposalTitleAndPayload<UpdateSubnetPayload> for ProposeToUpdateSubnetCmd {
             initial_notary_delay_millis: self.initial_notary_delay_millis,
             dkg_interval_length: self.dkg_interval_length,
             dkg_dealings_per_block: self.dkg_dealings_per_block,
+            adaptive_notarization_delays: self.adaptive_notarization_delays,
             max_artifact_streams_per_peer: self.gossip_max_artifact_streams_per_peer,
             max_chunk_wait_ms: self.gossip_max_chunk_wait_ms,
             max_duplicity: self.gossip_max_duplicity,
@@ -1873,6 +1981,8 @@ impl ProposalTitleAndPayload<UpdateSubnetPayload> for ProposeToUpdateSubnetCmd {
             ecdsa_config,
             ecdsa_key_signing_enable,
             ecdsa_key_signing_disable,
+            ecdsa_key_reshare_requests,
+            vetkd_config,
             ssh_readonly_access: self.ssh_readonly_access.clone(),
             ssh_backup_access: self.ssh_backup_access.clone(),
             max_number_of_canisters: self.max_number_of_canisters,
@@ -3253,6 +3363,52 @@ struct GetFirewallRulesetHashCmd {
     pub rules_file: PathBuf,
 }
 
+/// Sub-command to check registry mutations against the registry invariants.
+///
+/// The mutations are applied to a copy of the latest registry version in the
+/// registry canister, and the outcome of every invariant check is printed.
+/// The command exits with a non-zero status if any check fails.
+#[derive(Parser)]
+struct DryRunRegistryMutationsCmd {
+    /// File with a protobuf-encoded `RegistryAtomicMutateRequest`. Its
+    /// mutations are checked before the ones given by --upsert and --delete.
+    #[clap(long)]
+    pub mutations_file: Option<PathBuf>,
+
+    /// Upserts a key with the protobuf-encoded value read from a file, in the
+    /// form KEY=VALUE_FILE. Can be given multiple times.
+    #[clap(long, multiple_occurrences(true))]
+    pub upsert: Vec<String>,
+
+    /// Deletes a key. Can be given multiple times.
+    #[clap(long, multiple_occurrences(true))]
+    pub delete: Vec<String>,
+}
+
+impl DryRunRegistryMutationsCmd {
+    fn mutations(&self) -> Vec<RegistryMutation> {
+        let mut mutations = self
+            .mutations_file
+            .as_ref()
+            .map(|path| {
+                RegistryAtomicMutateRequest::decode(&read_file_fully(path)[..])
+                    .expect("Failed to decode RegistryAtomicMutateRequest")
+                    .mutations
+            })
+            .unwrap_or_default();
+        for upsert_arg in &self.upsert {
+            let (key, value_file) = upsert_arg
+                .split_once('=')
+                .unwrap_or_else(|| panic!("Expected KEY=VALUE_FILE, got: {}", upsert_arg));
+            mutations.push(upsert(key, read_file_fully(Path::new(value_file))));
+        }
+        for key in &self.delete {
+            mutations.push(delete(key));
+        }
+        mutations
+    }
+}
+
 /// Sub-command to submit a proposal to remove nodes.
 #[derive_common_proposal_fields]
 #[derive(ProposalMetadata, Parser)]
@@ -4358,6 +4514,11 @@ async fn main() {
             let latest_version = registry_canister.get_latest_version().await.unwrap();
             println!("{}", latest_version)
         }
+        SubCommand::DryRunRegistryMutations(cmd) => {
+            if !dry_run_registry_mutations(&registry_canister, cmd.mutations()).await {
+                std::process::exit(1);
+            }
+        }
         SubCommand::SubmitRootProposalToUpgradeGovernanceCanister(cmd) => {
             let sender = get_test_sender_if_set(sender, cmd.test_user_proposer);
             submit_root_proposal_to_upgrade_governance_canister(
@@ -4783,18 +4944,20 @@ async fn propose_external_proposal_from_command<
     proposer: NeuronId,
 ) {
     let payload = cmd.payload(agent.url.clone()).await;
-    let canister_client = GovernanceCanisterClient(NnsCanisterClient::new(
-        agent,
-        GOVERNANCE_CANISTER_ID,
-        Some(proposer),
-    ));
 
     print_payload(&payload, &cmd);
 
     if cmd.is_dry_run() {
+        dry_run_proposal(nns_function, &payload, agent.url).await;
         return;
     }
 
+    let canister_client = GovernanceCanisterClient(NnsCanisterClient::new(
+        agent,
+        GOVERNANCE_CANISTER_ID,
+        Some(proposer),
+    ));
+
     let response = canister_client
         .submit_external_proposal_candid(
             payload,
@@ -4826,6 +4989,74 @@ struct FirewallCommandResult {
     hash: String,
 }
 
+/// Checks the given mutations against the registry invariants in the
+/// registry canister, without applying them, and prints the outcome of every
+/// check. Returns whether all checks passed.
+async fn dry_run_registry_mutations(
+    registry_canister: &RegistryCanister,
+    mutations: Vec<RegistryMutation>,
+) -> bool {
+    let response = registry_canister
+        .dry_run_atomic_mutate(mutations)
+        .await
+        .unwrap_or_else(|e| panic!("Dry run of registry mutations failed: {}", e));
+    print_dry_run_response(&response)
+}
+
+/// Dry runs a proposal executed by the registry canister: prints the
+/// mutations executing `payload` would make, and the outcome of every
+/// registry invariant check against them. Proposals executed by other
+/// canisters are not dry run.
+async fn dry_run_proposal<C: CandidType>(nns_function: NnsFunction, payload: &C, nns_url: Url) {
+    let method_name = match registry_proposal_method(nns_function) {
+        Some(method_name) => method_name,
+        None => return,
+    };
+    let payload = Encode!(payload).expect("Couldn't candid-encode the proposal payload");
+    match RegistryCanister::new(vec![nns_url])
+        .dry_run_proposal(&method_name, payload)
+        .await
+    {
+        Ok(response) => {
+            if !print_dry_run_response(&response) {
+                eprintln!("WARNING: the proposal would fail the registry invariant checks.");
+            }
+        }
+        Err(e) => eprintln!("WARNING: the proposal could not be dry run: {}", e),
+    }
+}
+
+/// Returns the registry canister method that executes proposals of
+/// `nns_function`, or `None` if they are executed by another canister.
+fn registry_proposal_method(nns_function: NnsFunction) -> Option<String> {
+    match nns_function.canister_and_function() {
+        Ok((canister_id, method_name)) if canister_id == REGISTRY_CANISTER_ID => {
+            Some(method_name.to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Prints the mutations checked by a dry run and the outcome of every check.
+/// Returns whether all checks passed.
+fn print_dry_run_response(response: &RegistryDryRunAtomicMutateResponse) -> bool {
+    for mutation in &response.mutations {
+        println!("Mutation: {}", mutation);
+    }
+    println!("Checked against registry version {}", response.version);
+    for error in &response.errors {
+        println!("[FAIL] mutation: {}", Error::from(error.clone()));
+    }
+    for check in &response.invariant_checks {
+        if check.passed {
+            println!("[PASS] {}", check.name);
+        } else {
+            println!("[FAIL] {}: {}", check.name, check.error);
+        }
+    }
+    response.errors.is_empty() && response.invariant_checks.iter().all(|check| check.passed)
+}
+
 async fn test_add_firewall_rules(
     cmd: ProposeToAddFirewallRulesCmd,
     registry_canister: &RegistryCanister,
@@ -5894,3 +6125,50 @@ fn print_payload<T: Serialize + Debug, C: ProposalMetadata>(payload: &T, cmd: &C
         println!("submit_proposal payload: \n{:#?}", payload);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proposals_executed_by_the_registry_are_dry_run_through_their_registry_method() {
+        for (nns_function, method_name) in [
+            (NnsFunction::CreateSubnet, "create_subnet"),
+            (NnsFunction::UpdateConfigOfSubnet, "update_subnet"),
+            (
+                NnsFunction::UpdateSubnetReplicaVersion,
+                "update_subnet_replica_version",
+            ),
+            (NnsFunction::AddFirewallRules, "add_firewall_rules"),
+            (NnsFunction::RemoveNodes, "remove_nodes"),
+            (
+                NnsFunction::RerouteCanisterRanges,
+                "reroute_canister_ranges",
+            ),
+        ] {
+            assert_eq!(
+                registry_proposal_method(nns_function),
+                Some(method_name.to_string()),
+                "{:?}",
+                nns_function
+            );
+        }
+    }
+
+    #[test]
+    fn proposals_executed_by_other_canisters_are_not_dry_run() {
+        for nns_function in [
+            NnsFunction::NnsCanisterUpgrade,
+            NnsFunction::IcpXdrConversionRate,
+            NnsFunction::AddSnsWasm,
+            NnsFunction::Unspecified,
+        ] {
+            assert_eq!(
+                registry_proposal_method(nns_function),
+                None,
+                "{:?}",
+                nns_function
+            );
+        }
+    }
+}
@@ -5,7 +5,7 @@ use ic_protobuf::registry::{
     subnet::v1::{GossipConfig as GossipConfigProto, SubnetRecord as SubnetRecordProto},
 };
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
-use ic_registry_subnet_features::{EcdsaConfig, SubnetFeatures};
+use ic_registry_subnet_features::{EcdsaConfig, SubnetFeatures, VetKdConfig};
 use ic_registry_subnet_type::SubnetType;
 use ic_types::{NodeId, PrincipalId};
 use serde::Serialize;
@@ -68,6 +68,8 @@ pub(crate) struct SubnetRecord {
     pub ssh_readonly_access: Vec<String>,
     pub ssh_backup_access: Vec<String>,
     pub ecdsa_config: Option<EcdsaConfig>,
+    pub adaptive_notarization_delays: bool,
+    pub vetkd_config: Option<VetKdConfig>,
 }
 
 impl From<&SubnetRecordProto> for SubnetRecord {
@@ -108,6 +110,11 @@ impl From<&SubnetRecordProto> for SubnetRecord {
                 .ecdsa_config
                 .as_ref()
                 .map(|c| c.clone().try_into().unwrap()),
+            adaptive_notarization_delays: value.adaptive_notarization_delays,
+            vetkd_config: value
+                .vetkd_config
+                .as_ref()
+                .map(|c| c.clone().try_into().unwrap()),
         }
     }
 }
@@ -1,9 +1,10 @@
-use candid::{candid_method, Decode};
+use candid::{candid_method, CandidType, Decode};
 use dfn_candid::{candid, candid_one};
 use dfn_core::{
     api::{arg_data, data_certificate, reply},
     over, over_async, over_may_reject, stable,
 };
+use futures::FutureExt;
 use ic_base_types::NodeId;
 use ic_certified_map::{AsHashTree, HashTree};
 use ic_nervous_system_common::MethodAuthzChange;
@@ -15,20 +16,21 @@ use ic_protobuf::registry::{
     node_rewards::v2::UpdateNodeRewardsTableProposalPayload,
 };
 use ic_registry_transport::{
-    deserialize_atomic_mutate_request, deserialize_get_changes_since_request,
-    deserialize_get_value_request,
+    deserialize_atomic_mutate_request, deserialize_dry_run_proposal_request,
+    deserialize_get_changes_since_request, deserialize_get_value_request,
     pb::v1::{
         registry_error::Code, CertifiedResponse, RegistryAtomicMutateResponse, RegistryDelta,
-        RegistryError, RegistryGetChangesSinceRequest, RegistryGetChangesSinceResponse,
-        RegistryGetLatestVersionResponse, RegistryGetValueResponse,
+        RegistryDryRunAtomicMutateResponse, RegistryError, RegistryGetChangesSinceRequest,
+        RegistryGetChangesSinceResponse, RegistryGetLatestVersionResponse,
+        RegistryGetValueHistoryRequest, RegistryGetValueResponse,
     },
-    serialize_atomic_mutate_response, serialize_get_changes_since_response,
-    serialize_get_value_response,
+    serialize_atomic_mutate_response, serialize_dry_run_atomic_mutate_response,
+    serialize_get_changes_since_response, serialize_get_value_response,
 };
 use ic_types::PrincipalId;
 use prost::Message;
 use registry_canister::{
-    certification::{current_version_tree, hash_tree_to_proto},
+    certification::{current_version_tree, hash_tree_to_proto, merge_witnesses},
     common::LOG_PREFIX,
     init::RegistryCanisterInitPayload,
     mutations::{
@@ -65,6 +67,8 @@ use registry_canister::{
     registry::{EncodedVersion, Registry, MAX_REGISTRY_DELTAS_SIZE},
     registry_lifecycle,
 };
+use serde::de::DeserializeOwned;
+use std::future::Future;
 
 #[cfg(target_arch = "wasm32")]
 use dfn_core::println;
@@ -307,6 +311,62 @@ fn get_value() {
     reply(&bytes);
 }
 
+/// Returns a certified response revealing the changelog entries, in the
+/// requested range of versions, that mutate the requested key.
+///
+/// Only the authenticity of the revealed entries is certified: as all other
+/// entries are pruned, the response does not prove that no entry was left out.
+/// Clients that need this guarantee should use get_certified_changes_since.
+#[export_name = "canister_query get_value_history"]
+fn get_value_history() {
+    over(
+        protobuf,
+        |req: RegistryGetValueHistoryRequest| -> CertifiedResponse {
+            use ic_certified_map::{fork, labeled, labeled_hash};
+            let registry = registry();
+            let latest_version = registry.latest_version();
+            let to_version = if req.to_version == 0 {
+                latest_version
+            } else {
+                req.to_version.min(latest_version)
+            };
+
+            let mut size = 0;
+            let delta_tree = registry
+                .get_value_history(
+                    &req.key,
+                    req.from_version,
+                    to_version,
+                    Some(MAX_VERSIONS_PER_QUERY),
+                )
+                .into_iter()
+                .map(|value| EncodedVersion::from(value.version))
+                .take_while(|version| {
+                    size += version.as_ref().len()
+                        + registry
+                            .changelog()
+                            .get(version.as_ref())
+                            .map_or(0, |entry| entry.len());
+                    size <= MAX_REGISTRY_DELTAS_SIZE
+                })
+                .map(|version| registry.changelog().witness(version.as_ref()))
+                .reduce(merge_witnesses);
+
+            let hash_tree = fork(
+                current_version_tree(latest_version),
+                match delta_tree {
+                    Some(delta_tree) => labeled(b"delta", delta_tree),
+                    None => {
+                        HashTree::Pruned(labeled_hash(b"delta", &registry.changelog().root_hash()))
+                    }
+                },
+            );
+
+            certified_response(hash_tree)
+        },
+    )
+}
+
 #[export_name = "canister_query get_latest_version"]
 fn get_latest_version() {
     over(protobuf, |_: Vec<u8>| RegistryGetLatestVersionResponse {
@@ -372,6 +432,163 @@ fn atomic_mutate() {
     reply(&bytes)
 }
 
+/// Checks a batch of mutations against the registry invariants, as
+/// `atomic_mutate` would, but without applying them. This lets proposal
+/// authors find out about invariant violations before a proposal is voted on.
+#[export_name = "canister_query dry_run_atomic_mutate"]
+fn dry_run_atomic_mutate() {
+    let response_pb = match deserialize_atomic_mutate_request(arg_data()) {
+        Ok(request_pb) => registry().dry_run_mutations(&request_pb.mutations),
+        Err(error) => dry_run_error_response(error.to_string()),
+    };
+
+    let bytes =
+        serialize_dry_run_atomic_mutate_response(response_pb).expect("Error serializing response");
+    reply(&bytes)
+}
+
+/// Runs the mutation logic of the registry canister method that an adopted
+/// proposal would call, and checks the resulting mutations against the
+/// registry invariants without applying them.
+///
+/// The argument is a protobuf-encoded `RegistryDryRunProposalRequest`, whose
+/// payload is the candid-encoded proposal payload. Proposal payloads that the
+/// method would reject make the call trap, or are reported as an error.
+#[export_name = "canister_query dry_run_proposal"]
+fn dry_run_proposal() {
+    let response_pb = match deserialize_dry_run_proposal_request(arg_data()) {
+        Ok(request_pb) => dry_run_proposal_(&request_pb.method_name, &request_pb.payload)
+            .unwrap_or_else(dry_run_error_response),
+        Err(error) => dry_run_error_response(error.to_string()),
+    };
+
+    let bytes =
+        serialize_dry_run_atomic_mutate_response(response_pb).expect("Error serializing response");
+    reply(&bytes)
+}
+
+fn dry_run_error_response(reason: String) -> RegistryDryRunAtomicMutateResponse {
+    RegistryDryRunAtomicMutateResponse {
+        errors: vec![RegistryError {
+            code: Code::MalformedMessage as i32,
+            reason,
+            ..Default::default()
+        }],
+        version: registry().latest_version(),
+        invariant_checks: vec![],
+        mutations: vec![],
+    }
+}
+
+fn dry_run_proposal_(
+    method_name: &str,
+    payload: &[u8],
+) -> Result<RegistryDryRunAtomicMutateResponse, String> {
+    match method_name {
+        "add_node_operator" => dry_run(payload, Registry::do_add_node_operator),
+        // Creating a subnet always calls `setup_initial_dkg` on IC00.
+        "create_subnet" => dry_run_may_reject(payload, |_, _: CreateSubnetPayload| {
+            Err(cannot_dry_run_calls("create_subnet", "setup_initial_dkg"))
+        }),
+        "add_nodes_to_subnet" => dry_run(payload, Registry::do_add_nodes_to_subnet),
+        "remove_nodes_from_subnet" => dry_run(payload, Registry::do_remove_nodes_from_subnet),
+        "change_subnet_membership" => dry_run(payload, Registry::do_change_subnet_membership),
+        // Only recovering a subnet from a registry store URI skips the new DKG.
+        "recover_subnet" => {
+            dry_run_may_reject(payload, |registry, payload: RecoverSubnetPayload| {
+                if payload.registry_store_uri.is_none() {
+                    return Err(cannot_dry_run_calls("recover_subnet", "setup_initial_dkg"));
+                }
+                run_without_calls(registry.do_recover_subnet(payload))
+            })
+        }
+        "bless_replica_version" => dry_run(payload, Registry::do_bless_replica_version),
+        "retire_replica_version" => dry_run(payload, Registry::do_retire_replica_version),
+        "update_elected_replica_versions" => {
+            dry_run(payload, Registry::do_update_elected_replica_versions)
+        }
+        "update_node_operator_config" => dry_run(payload, Registry::do_update_node_operator_config),
+        "update_subnet_replica_version" => {
+            dry_run(payload, Registry::do_update_subnet_replica_version)
+        }
+        "update_subnet" => dry_run_may_reject(payload, |registry, payload: UpdateSubnetPayload| {
+            if payload
+                .ecdsa_key_reshare_requests
+                .as_ref()
+                .map_or(false, |key_requests| !key_requests.is_empty())
+            {
+                return Err(cannot_dry_run_calls(
+                    "update_subnet",
+                    "compute_initial_ecdsa_dealings",
+                ));
+            }
+            run_without_calls(registry.do_update_subnet(payload))
+        }),
+        "clear_provisional_whitelist" => registry().dry_run_proposal(|registry| {
+            registry.do_clear_provisional_whitelist();
+            Ok(())
+        }),
+        "set_firewall_config" => dry_run(payload, Registry::do_set_firewall_config),
+        "add_firewall_rules" => dry_run(payload, Registry::do_add_firewall_rules),
+        "remove_firewall_rules" => dry_run(payload, Registry::do_remove_firewall_rules),
+        "update_firewall_rules" => dry_run(payload, Registry::do_update_firewall_rules),
+        "remove_nodes" => dry_run(payload, Registry::do_remove_nodes),
+        "update_node_rewards_table" => dry_run(payload, Registry::do_update_node_rewards_table),
+        "add_or_remove_data_centers" => dry_run(payload, Registry::do_add_or_remove_data_centers),
+        "update_unassigned_nodes_config" => {
+            dry_run(payload, Registry::do_update_unassigned_nodes_config)
+        }
+        "remove_node_operators" => dry_run(payload, Registry::do_remove_node_operators),
+        "reroute_canister_ranges" => dry_run_may_reject(payload, Registry::reroute_canister_ranges),
+        "prepare_canister_migration" => {
+            dry_run_may_reject(payload, Registry::prepare_canister_migration)
+        }
+        "complete_canister_migration" => {
+            dry_run_may_reject(payload, Registry::complete_canister_migration)
+        }
+        _ => Err(format!(
+            "Method {} of the registry canister cannot be dry run.",
+            method_name
+        )),
+    }
+}
+
+fn dry_run<T: CandidType + DeserializeOwned>(
+    payload: &[u8],
+    execute: impl FnOnce(&mut Registry, T),
+) -> Result<RegistryDryRunAtomicMutateResponse, String> {
+    dry_run_may_reject(payload, |registry, payload| {
+        execute(registry, payload);
+        Ok(())
+    })
+}
+
+fn dry_run_may_reject<T: CandidType + DeserializeOwned>(
+    payload: &[u8],
+    execute: impl FnOnce(&mut Registry, T) -> Result<(), String>,
+) -> Result<RegistryDryRunAtomicMutateResponse, String> {
+    let payload = Decode!(payload, T)
+        .map_err(|err| format!("Failed to decode the proposal payload: {}", err))?;
+    registry().dry_run_proposal(|registry| execute(registry, payload))
+}
+
+/// Queries cannot call other canisters, so proposals whose mutation logic
+/// needs to, e.g. to request initial ECDSA dealings, cannot be dry run. Such
+/// payloads must be rejected before the call is made, as making it traps.
+fn run_without_calls(future: impl Future<Output = ()>) -> Result<(), String> {
+    future.now_or_never().ok_or_else(|| {
+        "The proposal calls other canisters when it is executed, so it cannot be dry run."
+            .to_string()
+    })
+}
+
+fn cannot_dry_run_calls(method_name: &str, ic00_method_name: &str) -> String {
+    format!(
+        "Method {} of the registry canister cannot be dry run with this payload, as it calls {} on the management canister.",
+        method_name, ic00_method_name
+    )
+}
+
 #[export_name = "canister_update bless_replica_version"]
 fn bless_replica_version() {
     check_caller_is_governance_and_log("bless_replica_version");
@@ -594,14 +811,14 @@ fn remove_node_operators_(payload: RemoveNodeOperatorsPayload) {
 #[export_name = "canister_update update_subnet"]
 fn update_subnet() {
     check_caller_is_governance_and_log("update_subnet");
-    over(candid_one, |payload: UpdateSubnetPayload| {
-        update_subnet_(payload)
+    over_async(candid_one, |payload: UpdateSubnetPayload| async move {
+        update_subnet_(payload).await
     });
 }
 
 #[candid_method(update, rename = "update_subnet")]
-fn update_subnet_(payload: UpdateSubnetPayload) {
-    registry_mut().do_update_subnet(payload);
+async fn update_subnet_(payload: UpdateSubnetPayload) {
+    registry_mut().do_update_subnet(payload).await;
     recertify_registry();
 }
 
@@ -37,6 +37,30 @@ pub fn current_version_tree(v: Version) -> HashTree<'static> {
     )
 }
 
+/// Merges two witnesses of the same tree into a single witness that reveals
+/// everything revealed by either of them.
+///
+/// Panics if the witnesses disagree on the structure of the tree, which can
+/// only happen if they were not produced from the same tree.
+pub fn merge_witnesses<'a>(lhs: HashTree<'a>, rhs: HashTree<'a>) -> HashTree<'a> {
+    use HashTree::*;
+
+    match (lhs, rhs) {
+        (Pruned(_), tree) | (tree, Pruned(_)) => tree,
+        (Empty, Empty) => Empty,
+        (Fork(l), Fork(r)) => {
+            let (ll, lr) = *l;
+            let (rl, rr) = *r;
+            Fork(Box::new((merge_witnesses(ll, rl), merge_witnesses(lr, rr))))
+        }
+        (Labeled(l_label, l_subtree), Labeled(r_label, r_subtree)) if l_label == r_label => {
+            Labeled(l_label, Box::new(merge_witnesses(*l_subtree, *r_subtree)))
+        }
+        (Leaf(l), Leaf(r)) if l == r => Leaf(l),
+        _ => panic!("cannot merge witnesses of different trees"),
+    }
+}
+
 /// Encodes a hash tree into the protobuf representation expected by
 /// the registry client.
 pub fn hash_tree_to_proto(tree: HashTree<'_>) -> pb::MixedHashTree {
@@ -1,7 +1,7 @@
 use crate::{
     common::LOG_PREFIX,
     invariants::{
-        common::RegistrySnapshot,
+        common::{InvariantCheckError, RegistrySnapshot},
         crypto::check_node_crypto_keys_invariants,
         endpoint::check_endpoint_invariants,
         firewall::check_firewall_invariants,
@@ -14,7 +14,38 @@ use crate::{
     registry::Registry,
 };
 
-use ic_registry_transport::pb::v1::{registry_mutation::Type, RegistryMutation};
+use ic_registry_transport::pb::v1::{
+    registry_mutation::Type, RegistryDryRunAtomicMutateResponse, RegistryError,
+    RegistryInvariantCheckResult, RegistryMutation,
+};
+
+type InvariantCheck = fn(&RegistrySnapshot) -> Result<(), InvariantCheckError>;
+
+/// The global state invariant checks, with the names under which they are
+/// reported, in the order in which they are run.
+///
+/// Node invariants are not checked for now.
+// TODO(NNS1-202): re-enable node checks when cd hourly test issues are sorted
+// out. Note that for now, once a node record has been added, it MUST not be
+// modified, as P2P and Transport rely on this data to stay the same.
+const GLOBAL_STATE_INVARIANT_CHECKS: &[(&str, InvariantCheck)] = &[
+    ("node_operator", |snapshot| {
+        check_node_operator_invariants(snapshot, false)
+    }),
+    ("node_crypto_keys", check_node_crypto_keys_invariants),
+    ("routing_table", check_routing_table_invariants),
+    ("canister_migrations", check_canister_migrations_invariants),
+    ("subnet", check_subnet_invariants),
+    ("replica_version", check_replica_version_invariants),
+    ("endpoint", |snapshot| {
+        check_endpoint_invariants(snapshot, false)
+    }),
+    ("firewall", check_firewall_invariants),
+    (
+        "unassigned_nodes_config",
+        check_unassigned_nodes_config_invariants,
+    ),
+];
 
 impl Registry {
     pub fn check_changelog_version_invariants(&self) {
@@ -56,38 +87,11 @@ impl Registry {
 
         let snapshot = self.take_latest_snapshot_with_mutations(mutations);
 
-        // Node invariants
-        // TODO(NNS1-202): re-enable this check when cd hourly test issues are sorted
-        // out.
-        // Note that for now, once a node record has been added, it MUST not be
-        // modified, as P2P and Transport rely on this data to stay the same
-
-        // Node Operator invariants
-        let mut result = check_node_operator_invariants(&snapshot, false);
-
-        // Crypto invariants
-        result = result.and(check_node_crypto_keys_invariants(&snapshot));
-
-        // Routing Table invariants
-        result = result.and(check_routing_table_invariants(&snapshot));
-
-        // Canister migrations invariants
-        result = result.and(check_canister_migrations_invariants(&snapshot));
-
-        // Subnet invariants
-        result = result.and(check_subnet_invariants(&snapshot));
-
-        // Replica version invariants
-        result = result.and(check_replica_version_invariants(&snapshot));
-
-        // Endpoint invariants
-        result = result.and(check_endpoint_invariants(&snapshot, false));
-
-        // Firewall invariants
-        result = result.and(check_firewall_invariants(&snapshot));
-
-        // Unassigned node invariants
-        result = result.and(check_unassigned_nodes_config_invariants(&snapshot));
+        // All checks are run, but only the first failure is reported.
+        let result = GLOBAL_STATE_INVARIANT_CHECKS
+            .iter()
+            .map(|(_, check)| check(&snapshot))
+            .fold(Ok(()), Result::and);
 
         if let Err(e) = result {
             panic!(
@@ -97,6 +101,80 @@ impl Registry {
         }
     }
 
+    /// Checks the given mutations against the latest version of the
+    /// registry without applying them, and reports the outcome of the
+    /// mutation type verification and of every global state invariant check.
+    ///
+    /// Note that some invariant checks panic instead of returning an error
+    /// when the snapshot is malformed, in which case the call traps with the
+    /// panic message.
+    pub fn dry_run_mutations(
+        &self,
+        mutations: &[RegistryMutation],
+    ) -> RegistryDryRunAtomicMutateResponse {
+        println!(
+            "{}dry_run_mutations: {} mutations",
+            LOG_PREFIX,
+            mutations.len()
+        );
+
+        let errors = self
+            .verify_mutation_type(mutations)
+            .into_iter()
+            .map(RegistryError::from)
+            .collect();
+
+        // Mutations of unknown type are reported above and left out of the
+        // snapshot.
+        let known_mutations: Vec<_> = mutations
+            .iter()
+            .filter(|mutation| Type::from_i32(mutation.mutation_type).is_some())
+            .cloned()
+            .collect();
+        let snapshot = self.take_latest_snapshot_with_mutations(&known_mutations);
+        let invariant_checks = GLOBAL_STATE_INVARIANT_CHECKS
+            .iter()
+            .map(|(name, check)| match check(&snapshot) {
+                Ok(()) => RegistryInvariantCheckResult {
+                    name: name.to_string(),
+                    passed: true,
+                    error: String::new(),
+                },
+                Err(e) => RegistryInvariantCheckResult {
+                    name: name.to_string(),
+                    passed: false,
+                    error: e.to_string(),
+                },
+            })
+            .collect();
+
+        RegistryDryRunAtomicMutateResponse {
+            errors,
+            version: self.latest_version(),
+            invariant_checks,
+            mutations: mutations.to_vec(),
+        }
+    }
+
+    /// Dry runs a proposal: `execute` runs the proposal's mutation logic
+    /// against a scratch copy of the latest version of the registry, and the
+    /// mutations it would apply are checked with `dry_run_mutations` instead
+    /// of being applied. The recorded mutations are applied to the scratch
+    /// copy, so that the mutation logic reads back what it already mutated.
+    ///
+    /// Validation errors of the proposal payload are returned as an `Err`.
+    /// Mutation logic that panics on invalid payloads traps the call instead.
+    pub fn dry_run_proposal(
+        &self,
+        execute: impl FnOnce(&mut Registry) -> Result<(), String>,
+    ) -> Result<RegistryDryRunAtomicMutateResponse, String> {
+        let mut scratch = self.clone();
+        scratch.recorded_mutations = Some(vec![]);
+        let result = execute(&mut scratch);
+        let mutations = scratch.recorded_mutations.take().unwrap_or_default();
+        result.map(|()| self.dry_run_mutations(&mutations))
+    }
+
     fn take_latest_snapshot_with_mutations(
         &self,
         mutations: &[RegistryMutation],
@@ -134,25 +212,37 @@ mod tests {
     use crate::registry::EncodedVersion;
 
     use super::*;
+    use crate::{
+        common::test_helpers::invariant_compliant_registry,
+        mutations::{
+            do_update_subnet_replica::UpdateSubnetReplicaVersionPayload,
+            firewall::{compute_firewall_ruleset_hash, AddFirewallRulesPayload},
+        },
+    };
     use ic_base_types::CanisterId;
     use ic_nervous_system_common_test_keys::TEST_USER1_PRINCIPAL;
     use ic_nns_common::registry::encode_or_panic;
+    use ic_nns_test_utils::registry::TEST_ID;
     use ic_protobuf::registry::{
+        firewall::v1::{FirewallAction, FirewallRule, FirewallRuleDirection, FirewallRuleSet},
         node_operator::v1::NodeOperatorRecord,
         routing_table::v1::{
             CanisterMigrations as PbCanisterMigrations, RoutingTable as PbRoutingTable,
         },
     };
     use ic_registry_keys::{
-        make_canister_migrations_record_key, make_node_operator_record_key,
-        make_routing_table_record_key,
+        make_canister_migrations_record_key, make_firewall_rules_record_key,
+        make_node_operator_record_key, make_routing_table_record_key, make_subnet_record_key,
+        FirewallRulesScope,
     };
     use ic_registry_routing_table::{CanisterIdRange, CanisterMigrations, RoutingTable};
     use ic_registry_transport::{
         delete, insert,
         pb::v1::{RegistryAtomicMutateRequest, RegistryMutation},
+        update, upsert,
     };
     use ic_test_utilities::types::ids::subnet_test_id;
+    use ic_types::ReplicaVersion;
     use maplit::btreemap;
     use std::collections::BTreeMap;
     use std::convert::TryFrom;
@@ -272,6 +362,205 @@ mod tests {
         registry.check_global_state_invariants(&mutations);
     }
 
+    #[test]
+    fn dry_run_reports_all_checks_without_applying_mutations() {
+        let routing_table = RoutingTable::try_from(btreemap! {
+            CanisterIdRange{ start: CanisterId::from(0x0), end: CanisterId::from(0xff) } => subnet_test_id(1),
+        })
+        .unwrap();
+        let canister_migrations = CanisterMigrations::try_from(btreemap! {
+            CanisterIdRange{ start: CanisterId::from(0x200), end: CanisterId::from(0x2ff) } => vec![subnet_test_id(1), subnet_test_id(2)],
+        })
+        .unwrap();
+        let mutations = vec![
+            insert(
+                make_routing_table_record_key().as_bytes(),
+                encode_or_panic(&PbRoutingTable::from(routing_table)),
+            ),
+            insert(
+                make_canister_migrations_record_key().as_bytes(),
+                encode_or_panic(&PbCanisterMigrations::from(canister_migrations)),
+            ),
+            delete("missing_key"),
+        ];
+
+        let registry = Registry::new();
+        let response = registry.dry_run_mutations(&mutations);
+
+        assert_eq!(response.version, 0);
+        assert_eq!(
+            response.errors,
+            vec![RegistryError::from(
+                ic_registry_transport::Error::KeyNotPresent(b"missing_key".to_vec())
+            )]
+        );
+        assert_eq!(
+            response.invariant_checks.len(),
+            GLOBAL_STATE_INVARIANT_CHECKS.len()
+        );
+        let check = |name: &str| {
+            response
+                .invariant_checks
+                .iter()
+                .find(|check| check.name == name)
+                .unwrap()
+        };
+        assert!(check("routing_table").passed);
+        let canister_migrations_check = check("canister_migrations");
+        assert!(!canister_migrations_check.passed);
+        assert!(canister_migrations_check
+            .error
+            .contains("not hosted by any subnet"));
+
+        // Nothing was applied.
+        assert_eq!(registry.latest_version(), 0);
+        assert!(registry
+            .get(make_routing_table_record_key().as_bytes(), 1)
+            .is_none());
+    }
+
+    #[test]
+    fn dry_run_proposal_checks_the_mutations_of_the_proposal_without_applying_them() {
+        let mut registry = invariant_compliant_registry();
+        let version = registry.latest_version();
+        let subnet_key = make_subnet_record_key(subnet_test_id(TEST_ID));
+
+        let response = registry
+            .dry_run_proposal(|registry| {
+                registry.do_update_subnet_replica_version(UpdateSubnetReplicaVersionPayload {
+                    subnet_id: subnet_test_id(TEST_ID).get(),
+                    replica_version_id: ReplicaVersion::default().to_string(),
+                });
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(response.version, version);
+        assert!(response.errors.is_empty());
+        assert!(response.invariant_checks.iter().all(|check| check.passed));
+        assert_eq!(response.mutations.len(), 1);
+        assert_eq!(response.mutations[0].key, subnet_key.as_bytes());
+
+        // Nothing was applied, and mutations are applied again afterwards.
+        assert_eq!(registry.latest_version(), version);
+        assert_eq!(registry.recorded_mutations, None);
+        registry.do_update_subnet_replica_version(UpdateSubnetReplicaVersionPayload {
+            subnet_id: subnet_test_id(TEST_ID).get(),
+            replica_version_id: ReplicaVersion::default().to_string(),
+        });
+        assert_eq!(registry.latest_version(), version + 1);
+    }
+
+    #[test]
+    fn dry_run_proposal_returns_the_mutations_of_a_firewall_proposal() {
+        let registry = invariant_compliant_registry();
+        let version = registry.latest_version();
+        let rules = vec![FirewallRule {
+            ipv4_prefixes: vec!["10.0.0.0/8".to_string()],
+            ipv6_prefixes: vec![],
+            ports: vec![80, 8080],
+            action: FirewallAction::Allow as i32,
+            comment: "test comment".to_string(),
+            user: None,
+            direction: Some(FirewallRuleDirection::Inbound as i32),
+        }];
+
+        let response = registry
+            .dry_run_proposal(|registry| {
+                registry.do_add_firewall_rules(AddFirewallRulesPayload {
+                    scope: FirewallRulesScope::ReplicaNodes,
+                    rules: rules.clone(),
+                    positions: vec![0],
+                    expected_hash: compute_firewall_ruleset_hash(&rules),
+                });
+                Ok(())
+            })
+            .unwrap();
+
+        assert!(response.errors.is_empty());
+        assert!(response.invariant_checks.iter().all(|check| check.passed));
+        assert_eq!(
+            response.mutations,
+            vec![upsert(
+                make_firewall_rules_record_key(&FirewallRulesScope::ReplicaNodes),
+                encode_or_panic(&FirewallRuleSet { entries: rules }),
+            )]
+        );
+        assert_eq!(registry.latest_version(), version);
+    }
+
+    #[test]
+    fn dry_run_proposal_reports_invariant_violations_and_rejections() {
+        let registry = invariant_compliant_registry();
+        let canister_migrations = CanisterMigrations::try_from(btreemap! {
+            CanisterIdRange{ start: CanisterId::from(0x200), end: CanisterId::from(0x2ff) } => vec![subnet_test_id(1), subnet_test_id(2)],
+        })
+        .unwrap();
+
+        // A mutation that would make `maybe_apply_mutation_internal` panic is
+        // reported instead.
+        let response = registry
+            .dry_run_proposal(|registry| {
+                registry.maybe_apply_mutation_internal(vec![insert(
+                    make_canister_migrations_record_key().as_bytes(),
+                    encode_or_panic(&PbCanisterMigrations::from(canister_migrations)),
+                )]);
+                Ok(())
+            })
+            .unwrap();
+        let canister_migrations_check = response
+            .invariant_checks
+            .iter()
+            .find(|check| check.name == "canister_migrations")
+            .unwrap();
+        assert!(!canister_migrations_check.passed);
+
+        // A rejected proposal is reported as an error.
+        assert_eq!(
+            registry.dry_run_proposal(|_| Err("rejected".to_string())),
+            Err("rejected".to_string())
+        );
+        assert_eq!(registry.recorded_mutations, None);
+    }
+
+    #[test]
+    fn dry_run_proposal_reads_back_its_own_mutations() {
+        let registry = invariant_compliant_registry();
+        let version = registry.latest_version();
+        let key = make_node_operator_record_key(*TEST_USER1_PRINCIPAL);
+        let record = NodeOperatorRecord {
+            node_operator_principal_id: (*TEST_USER1_PRINCIPAL).to_vec(),
+            node_allowance: 1,
+            ..Default::default()
+        };
+
+        let response = registry
+            .dry_run_proposal(|registry| {
+                registry.maybe_apply_mutation_internal(vec![insert(
+                    key.as_bytes(),
+                    encode_or_panic(&record),
+                )]);
+                // The second step of the proposal sees the first one.
+                let inserted = registry.get(key.as_bytes(), registry.latest_version());
+                assert_eq!(inserted.unwrap().value, encode_or_panic(&record));
+                registry.maybe_apply_mutation_internal(vec![update(
+                    key.as_bytes(),
+                    encode_or_panic(&NodeOperatorRecord {
+                        node_allowance: 2,
+                        ..record.clone()
+                    }),
+                )]);
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(response.mutations.len(), 2);
+        assert_eq!(registry.latest_version(), version);
+        assert!(registry
+            .get(key.as_bytes(), registry.latest_version())
+            .is_none());
+    }
+
     #[test]
     fn snapshot_reflects_latest_registry_state() {
         let key1 = make_routing_table_record_key();
@@ -225,7 +225,7 @@ pub struct RecoverSubnetPayload {
     pub ecdsa_config: Option<EcdsaInitialConfig>,
 }
 
-fn panic_if_record_changed_across_versions(
+pub(crate) fn panic_if_record_changed_across_versions(
     registry: &Registry,
     key: &str,
     initial_registry_version: Version,
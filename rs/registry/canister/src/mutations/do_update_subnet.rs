@@ -1,15 +1,20 @@
+use crate::mutations::do_create_subnet::EcdsaKeyRequest;
+use crate::mutations::do_recover_subnet::panic_if_record_changed_across_versions;
 use crate::{common::LOG_PREFIX, mutations::common::encode_or_panic, registry::Registry};
 use std::collections::HashSet;
+use std::convert::TryFrom;
 
 use candid::{CandidType, Deserialize};
 use dfn_core::println;
 use serde::Serialize;
 
-use ic_base_types::{subnet_id_into_protobuf, SubnetId};
-use ic_ic00_types::EcdsaKeyId;
+use ic_base_types::{subnet_id_into_protobuf, NodeId, PrincipalId, SubnetId};
+use ic_ic00_types::{EcdsaKeyId, VetKdKeyId};
 use ic_protobuf::registry::subnet::v1::SubnetRecord;
-use ic_registry_keys::{make_ecdsa_signing_subnet_list_key, make_subnet_record_key};
-use ic_registry_subnet_features::{EcdsaConfig, SubnetFeatures};
+use ic_registry_keys::{
+    make_catch_up_package_contents_key, make_ecdsa_signing_subnet_list_key, make_subnet_record_key,
+};
+use ic_registry_subnet_features::{EcdsaConfig, SubnetFeatures, VetKdConfig};
 use ic_registry_subnet_type::SubnetType;
 use ic_registry_transport::pb::v1::RegistryMutation;
 use ic_registry_transport::upsert;
@@ -20,22 +25,36 @@ use ic_types::p2p::build_default_gossip_config;
 /// This method is called by the proposals canister, after a proposal
 /// for updating a new subnet has been accepted.
 impl Registry {
-    pub fn do_update_subnet(&mut self, payload: UpdateSubnetPayload) {
+    pub async fn do_update_subnet(&mut self, payload: UpdateSubnetPayload) {
         println!("{}do_update_subnet: {:?}", LOG_PREFIX, payload);
 
         self.validate_update_payload_ecdsa_config(&payload);
+        self.validate_update_payload_vetkd_config(&payload);
 
         let subnet_id = payload.subnet_id;
 
+        let mut mutations = vec![];
+
+        // If keys held by other subnets are to be reshared to this subnet, request the initial
+        // dealings for the subnet's current members. They are placed in the subnet's CUP contents,
+        // from which consensus bootstraps the key transcript once the key appears in the subnet's
+        // `ecdsa_config`.
+        if let Some(ref key_requests) = payload.ecdsa_key_reshare_requests {
+            if !key_requests.is_empty() {
+                mutations.push(
+                    self.mutation_to_reshare_ecdsa_keys(subnet_id, key_requests)
+                        .await,
+                );
+            }
+        }
+
         let new_subnet_record =
             merge_subnet_record(self.get_subnet_or_panic(subnet_id), payload.clone());
 
-        let subnet_record_mutation = upsert(
+        mutations.push(upsert(
             make_subnet_record_key(subnet_id).into_bytes(),
             encode_or_panic(&new_subnet_record),
-        );
-
-        let mut mutations = vec![subnet_record_mutation];
+        ));
 
         if let Some(ecdsa_key_signing_enable) = payload.ecdsa_key_signing_enable {
             mutations.append(
@@ -54,23 +73,117 @@ impl Registry {
         self.maybe_apply_mutation_internal(mutations);
     }
 
-    /// Validates that EcdsaKeyId's are globally unique across all subnets
+    /// Requests the initial dealings of the given keys from the subnets holding them, with the
+    /// current members of `subnet_id` as receivers, and returns the mutation that records them in
+    /// the subnet's CUP contents.
+    ///
+    /// Panics if the subnet record or its CUP contents changed during the call to IC00, as the
+    /// dealings would then not match the subnet's membership.
+    async fn mutation_to_reshare_ecdsa_keys(
+        &self,
+        subnet_id: SubnetId,
+        key_requests: &[EcdsaKeyRequest],
+    ) -> RegistryMutation {
+        let pre_call_registry_version = self.latest_version();
+
+        let receiver_nodes = self
+            .get_subnet_or_panic(subnet_id)
+            .membership
+            .iter()
+            .map(|bytes| NodeId::from(PrincipalId::try_from(bytes).unwrap()))
+            .collect();
+
+        let ecdsa_initializations = self
+            .get_initial_ecdsa_dealings_for_key_requests(key_requests, receiver_nodes)
+            .await;
+
+        let post_call_registry_version = self.latest_version();
+
+        panic_if_record_changed_across_versions(
+            self,
+            &make_subnet_record_key(subnet_id),
+            pre_call_registry_version,
+            post_call_registry_version,
+            format!(
+                "Subnet with ID {} was updated during the `compute_initial_ecdsa_dealings` call",
+                subnet_id
+            ),
+        );
+
+        panic_if_record_changed_across_versions(
+            self,
+            &make_catch_up_package_contents_key(subnet_id),
+            pre_call_registry_version,
+            post_call_registry_version,
+            format!(
+                "CUP for Subnet {} was updated during the `compute_initial_ecdsa_dealings` call",
+                subnet_id
+            ),
+        );
+
+        let mut cup_contents = self
+            .get_subnet_catch_up_package(subnet_id, Some(post_call_registry_version))
+            .unwrap();
+        cup_contents.ecdsa_initializations = ecdsa_initializations;
+
+        upsert(
+            make_catch_up_package_contents_key(subnet_id).into_bytes(),
+            encode_or_panic(&cup_contents),
+        )
+    }
+
+    /// Validates that EcdsaKeyId's are globally unique across all subnets, unless they are
+    /// reshared from the subnet that holds them.
     /// Panics if they are not
     fn validate_update_payload_ecdsa_config(&self, payload: &UpdateSubnetPayload) {
+        let subnet_id = payload.subnet_id;
+        let reshare_requests = payload
+            .ecdsa_key_reshare_requests
+            .clone()
+            .unwrap_or_default();
+
         if payload.ecdsa_config.is_none() {
+            if !reshare_requests.is_empty() {
+                panic!(
+                    "{}Proposal attempts to reshare ECDSA keys to Subnet '{}' without \
+                    specifying an ecdsa_config that holds them.",
+                    LOG_PREFIX, subnet_id
+                );
+            }
             return;
         }
-        let subnet_id = payload.subnet_id;
         let ecdsa_config = payload.ecdsa_config.as_ref().unwrap();
 
-        // Validate that any new keys do not exist in another subnet, as that would trigger
-        // creating another key with the same EcdsaKeyId, which would break ECDSA signing.
+        // Keys can only be reshared to this subnet if they are held elsewhere, and
+        // are added to this subnet by the same proposal.
+        if let Err(message) = self.validate_ecdsa_key_requests(&reshare_requests, None) {
+            panic!(
+                "{}Cannot update subnet '{}': {}",
+                LOG_PREFIX, subnet_id, message
+            );
+        }
         let new_keys =
             self.get_keys_that_will_be_added_to_subnet(subnet_id, ecdsa_config.key_ids.clone());
-
+        for key_request in &reshare_requests {
+            if !new_keys.contains(&key_request.key_id) {
+                panic!(
+                    "{}Proposal attempts to reshare ECDSA key '{}' to Subnet '{}', but the key \
+                    is either already held by the subnet or missing from its new ecdsa_config.",
+                    LOG_PREFIX, key_request.key_id, subnet_id
+                );
+            }
+        }
+        let reshared_keys = reshare_requests
+            .iter()
+            .map(|key_request| &key_request.key_id)
+            .collect::<HashSet<_>>();
+
+        // Validate that any new keys that are not reshared do not exist in another subnet, as
+        // that would trigger creating another key with the same EcdsaKeyId, which would break
+        // ECDSA signing.
         let ecdsa_subnet_map = self.get_ecdsa_keys_to_subnets_map();
         new_keys.iter().for_each(|key_id| {
-            if ecdsa_subnet_map.contains_key(key_id) {
+            if ecdsa_subnet_map.contains_key(key_id) && !reshared_keys.contains(key_id) {
                 panic!(
                     "{}ECDSA key with id '{}' already exists.  ID must be globally unique.",
                     LOG_PREFIX, key_id
@@ -110,6 +223,42 @@ impl Registry {
         }
     }
 
+    /// Validates that the vetKD key of a subnet is not changed once set, and that a newly added
+    /// VetKdKeyId is not held by any other subnet.
+    /// Panics if either is the case.
+    fn validate_update_payload_vetkd_config(&self, payload: &UpdateSubnetPayload) {
+        let subnet_id = payload.subnet_id;
+        let vetkd_config = match payload.vetkd_config {
+            Some(ref vetkd_config) => vetkd_config,
+            None => return,
+        };
+
+        let current_key_id = self
+            .get_subnet_or_panic(subnet_id)
+            .vetkd_config
+            .and_then(|vetkd_config| vetkd_config.key_id)
+            .map(|key_id| VetKdKeyId::try_from(key_id).unwrap());
+        match current_key_id {
+            Some(current_key_id) if current_key_id != vetkd_config.key_id => panic!(
+                "{}Proposal attempts to change the vetKD key of Subnet '{}' from '{}' to '{}', \
+                but the key of a subnet cannot be changed once set.",
+                LOG_PREFIX, subnet_id, current_key_id, vetkd_config.key_id
+            ),
+            Some(_) => (),
+            None => {
+                if self
+                    .get_vetkd_keys_to_subnets_map()
+                    .contains_key(&vetkd_config.key_id)
+                {
+                    panic!(
+                        "{}vetKD key with id '{}' already exists.  ID must be globally unique.",
+                        LOG_PREFIX, vetkd_config.key_id
+                    );
+                }
+            }
+        }
+    }
+
     fn mutations_to_enable_subnet_signing(
         &self,
         subnet_id: SubnetId,
@@ -166,6 +315,7 @@ pub struct UpdateSubnetPayload {
     pub initial_notary_delay_millis: Option<u64>,
     pub dkg_interval_length: Option<u64>,
     pub dkg_dealings_per_block: Option<u64>,
+    pub adaptive_notarization_delays: Option<bool>,
 
     pub max_artifact_streams_per_peer: Option<u32>,
     pub max_chunk_wait_ms: Option<u32>,
@@ -195,6 +345,12 @@ pub struct UpdateSubnetPayload {
     pub ecdsa_key_signing_enable: Option<Vec<EcdsaKeyId>>,
     /// This disables signing for keys the subnet holds, which is not held in the SubnetRecord
     pub ecdsa_key_signing_disable: Option<Vec<EcdsaKeyId>>,
+    /// This reshares keys held by other subnets to the current members of this subnet, without
+    /// recovering it. Each reshared key must also be added to the subnet by `ecdsa_config`.
+    pub ecdsa_key_reshare_requests: Option<Vec<EcdsaKeyRequest>>,
+
+    /// This defines the vetKD key held by the subnet. It cannot be changed or removed once set.
+    pub vetkd_config: Option<VetKdConfig>,
 
     pub max_number_of_canisters: Option<u64>,
 
@@ -264,6 +420,7 @@ fn merge_subnet_record(
         initial_notary_delay_millis,
         dkg_interval_length,
         dkg_dealings_per_block,
+        adaptive_notarization_delays,
         max_artifact_streams_per_peer,
         max_chunk_wait_ms,
         max_duplicity,
@@ -283,6 +440,8 @@ fn merge_subnet_record(
         ecdsa_config,
         ecdsa_key_signing_enable: _,
         ecdsa_key_signing_disable: _,
+        ecdsa_key_reshare_requests: _,
+        vetkd_config,
         max_number_of_canisters,
         ssh_readonly_access,
         ssh_backup_access,
@@ -295,6 +454,7 @@ fn merge_subnet_record(
     maybe_set!(subnet_record, initial_notary_delay_millis);
     maybe_set!(subnet_record, dkg_interval_length);
     maybe_set!(subnet_record, dkg_dealings_per_block);
+    maybe_set!(subnet_record, adaptive_notarization_delays);
 
     // Set a default gossip config if it was requested...
     if set_gossip_config_to_default {
@@ -328,6 +488,7 @@ fn merge_subnet_record(
 
     maybe_set_option!(subnet_record, features);
     maybe_set_option!(subnet_record, ecdsa_config);
+    maybe_set_option!(subnet_record, vetkd_config);
 
     maybe_set!(subnet_record, max_number_of_canisters);
 
@@ -344,10 +505,10 @@ mod tests {
         add_fake_subnet, get_invariant_compliant_subnet_record, invariant_compliant_registry,
         prepare_registry_with_nodes,
     };
-    use ic_ic00_types::{EcdsaCurve, EcdsaKeyId};
+    use ic_ic00_types::{EcdsaCurve, EcdsaKeyId, VetKdCurve, VetKdKeyId};
     use ic_nervous_system_common_test_keys::{TEST_USER1_PRINCIPAL, TEST_USER2_PRINCIPAL};
     use ic_protobuf::registry::subnet::v1::{GossipConfig, SubnetRecord};
-    use ic_registry_subnet_features::DEFAULT_ECDSA_MAX_QUEUE_SIZE;
+    use ic_registry_subnet_features::{DEFAULT_ECDSA_MAX_QUEUE_SIZE, DEFAULT_VETKD_MAX_QUEUE_SIZE};
     use ic_registry_subnet_type::SubnetType;
     use ic_test_utilities::types::ids::subnet_test_id;
     use ic_types::p2p::{
@@ -379,6 +540,7 @@ mod tests {
             initial_notary_delay_millis: Some(200),
             dkg_interval_length: Some(8),
             dkg_dealings_per_block: Some(1),
+            adaptive_notarization_delays: None,
             max_artifact_streams_per_peer: Some(0),
             max_chunk_wait_ms: Some(200),
             max_duplicity: Some(5),
@@ -409,6 +571,8 @@ mod tests {
             }),
             ecdsa_key_signing_enable: Some(vec![make_ecdsa_key("key_id_2")]),
             ecdsa_key_signing_disable: None,
+            ecdsa_key_reshare_requests: None,
+            vetkd_config: None,
             max_number_of_canisters: Some(10),
             ssh_readonly_access: Some(vec!["pub_key_0".to_string()]),
             ssh_backup_access: Some(vec!["pub_key_1".to_string()]),
@@ -425,6 +589,7 @@ mod tests {
             initial_notary_delay_millis: None,
             dkg_interval_length: None,
             dkg_dealings_per_block: None,
+            adaptive_notarization_delays: None,
             max_artifact_streams_per_peer: None,
             max_chunk_wait_ms: None,
             max_duplicity: None,
@@ -444,6 +609,8 @@ mod tests {
             ecdsa_config: None,
             ecdsa_key_signing_enable: None,
             ecdsa_key_signing_disable: None,
+            ecdsa_key_reshare_requests: None,
+            vetkd_config: None,
             max_number_of_canisters: None,
             ssh_readonly_access: None,
             ssh_backup_access: None,
@@ -483,6 +650,8 @@ mod tests {
             ssh_readonly_access: vec![],
             ssh_backup_access: vec![],
             ecdsa_config: None,
+            adaptive_notarization_delays: false,
+            vetkd_config: None,
         };
 
         let payload = UpdateSubnetPayload {
@@ -499,6 +668,7 @@ mod tests {
             initial_notary_delay_millis: Some(200),
             dkg_interval_length: Some(8),
             dkg_dealings_per_block: Some(1),
+            adaptive_notarization_delays: Some(true),
             max_artifact_streams_per_peer: Some(0),
             max_chunk_wait_ms: Some(10),
             max_duplicity: Some(5),
@@ -529,6 +699,8 @@ mod tests {
             }),
             ecdsa_key_signing_enable: Some(vec![make_ecdsa_key("key_id_2")]),
             ecdsa_key_signing_disable: None,
+            ecdsa_key_reshare_requests: None,
+            vetkd_config: None,
             max_number_of_canisters: Some(10),
             ssh_readonly_access: Some(vec!["pub_key_0".to_string()]),
             ssh_backup_access: Some(vec!["pub_key_1".to_string()]),
@@ -581,6 +753,8 @@ mod tests {
                     }
                     .into()
                 ),
+                adaptive_notarization_delays: true,
+                vetkd_config: None,
                 max_number_of_canisters: 10,
                 ssh_readonly_access: vec!["pub_key_0".to_string()],
                 ssh_backup_access: vec!["pub_key_1".to_string()],
@@ -621,6 +795,8 @@ mod tests {
             ssh_readonly_access: vec![],
             ssh_backup_access: vec![],
             ecdsa_config: None,
+            adaptive_notarization_delays: false,
+            vetkd_config: None,
         };
 
         let payload = UpdateSubnetPayload {
@@ -637,6 +813,7 @@ mod tests {
             initial_notary_delay_millis: None,
             dkg_interval_length: Some(2),
             dkg_dealings_per_block: Some(1),
+            adaptive_notarization_delays: None,
             max_artifact_streams_per_peer: Some(0),
             max_chunk_wait_ms: Some(10),
             max_duplicity: None,
@@ -656,6 +833,8 @@ mod tests {
             ecdsa_config: None,
             ecdsa_key_signing_enable: None,
             ecdsa_key_signing_disable: None,
+            ecdsa_key_reshare_requests: None,
+            vetkd_config: None,
             max_number_of_canisters: Some(50),
             ssh_readonly_access: None,
             ssh_backup_access: None,
@@ -694,6 +873,8 @@ mod tests {
                 ssh_readonly_access: vec![],
                 ssh_backup_access: vec![],
                 ecdsa_config: None,
+                adaptive_notarization_delays: false,
+                vetkd_config: None,
             }
         );
     }
@@ -746,6 +927,8 @@ mod tests {
             ssh_readonly_access: vec![],
             ssh_backup_access: vec![],
             ecdsa_config: None,
+            adaptive_notarization_delays: false,
+            vetkd_config: None,
         };
 
         let payload = UpdateSubnetPayload {
@@ -762,6 +945,7 @@ mod tests {
             initial_notary_delay_millis: None,
             dkg_interval_length: Some(2),
             dkg_dealings_per_block: Some(1),
+            adaptive_notarization_delays: None,
             max_artifact_streams_per_peer: Some(0),
             max_chunk_wait_ms: Some(10),
             max_duplicity: None,
@@ -781,6 +965,8 @@ mod tests {
             ecdsa_config: None,
             ecdsa_key_signing_enable: None,
             ecdsa_key_signing_disable: None,
+            ecdsa_key_reshare_requests: None,
+            vetkd_config: None,
             max_number_of_canisters: None,
             ssh_readonly_access: None,
             ssh_backup_access: None,
@@ -813,6 +999,8 @@ mod tests {
             ssh_readonly_access: vec![],
             ssh_backup_access: vec![],
             ecdsa_config: None,
+            adaptive_notarization_delays: false,
+            vetkd_config: None,
         };
 
         let payload = UpdateSubnetPayload {
@@ -829,6 +1017,7 @@ mod tests {
             initial_notary_delay_millis: None,
             dkg_interval_length: None,
             dkg_dealings_per_block: None,
+            adaptive_notarization_delays: None,
             max_artifact_streams_per_peer: Some(MAX_ARTIFACT_STREAMS_PER_PEER),
             max_chunk_wait_ms: Some(MAX_CHUNK_WAIT_MS),
             max_duplicity: Some(MAX_DUPLICITY),
@@ -848,6 +1037,8 @@ mod tests {
             ecdsa_config: None,
             ecdsa_key_signing_enable: None,
             ecdsa_key_signing_disable: None,
+            ecdsa_key_reshare_requests: None,
+            vetkd_config: None,
             max_number_of_canisters: None,
             ssh_readonly_access: None,
             ssh_backup_access: None,
@@ -886,6 +1077,8 @@ mod tests {
                 ssh_readonly_access: vec![],
                 ssh_backup_access: vec![],
                 ecdsa_config: None,
+                adaptive_notarization_delays: false,
+                vetkd_config: None,
             }
         );
     }
@@ -923,6 +1116,8 @@ mod tests {
             ssh_readonly_access: vec![],
             ssh_backup_access: vec![],
             ecdsa_config: None,
+            adaptive_notarization_delays: false,
+            vetkd_config: None,
         };
 
         let payload = UpdateSubnetPayload {
@@ -939,6 +1134,7 @@ mod tests {
             initial_notary_delay_millis: None,
             dkg_interval_length: Some(2),
             dkg_dealings_per_block: Some(1),
+            adaptive_notarization_delays: None,
             max_artifact_streams_per_peer: Some(0),
             max_chunk_wait_ms: Some(10),
             max_duplicity: None,
@@ -958,6 +1154,8 @@ mod tests {
             ecdsa_config: None,
             ecdsa_key_signing_enable: None,
             ecdsa_key_signing_disable: None,
+            ecdsa_key_reshare_requests: None,
+            vetkd_config: None,
             max_number_of_canisters: None,
             ssh_readonly_access: None,
             ssh_backup_access: None,
@@ -996,6 +1194,8 @@ mod tests {
                 ssh_readonly_access: vec![],
                 ssh_backup_access: vec![],
                 ecdsa_config: None,
+                adaptive_notarization_delays: false,
+                vetkd_config: None,
             }
         );
     }
@@ -1040,7 +1240,7 @@ mod tests {
         payload.ecdsa_key_signing_enable = Some(vec![key]);
 
         // Should panic because we are trying to enable a key that hasn't previously held it
-        registry.do_update_subnet(payload);
+        futures::executor::block_on(registry.do_update_subnet(payload));
     }
 
     #[test]
@@ -1110,7 +1310,110 @@ mod tests {
             idkg_key_rotation_period_ms: None,
         });
 
-        registry.do_update_subnet(payload);
+        futures::executor::block_on(registry.do_update_subnet(payload));
+    }
+
+    /// Adds a subnet holding `key_id` and a subnet without any keys to the registry, and
+    /// returns their IDs, in that order.
+    fn setup_subnet_holding_key_and_subnet_to_update(
+        registry: &mut Registry,
+        key_id: &EcdsaKeyId,
+    ) -> (SubnetId, SubnetId) {
+        let subnet_holding_key_id = SubnetId::from(*TEST_USER1_PRINCIPAL);
+        let subnet_to_update_id = SubnetId::from(*TEST_USER2_PRINCIPAL);
+
+        let (mutate_request, mut node_ids) = prepare_registry_with_nodes(2);
+        registry.maybe_apply_mutation_internal(mutate_request.mutations);
+
+        let mut subnet_list_record = registry.get_subnet_list_record();
+
+        let mut subnet_holding_key_record =
+            get_invariant_compliant_subnet_record(vec![node_ids.pop().unwrap()]);
+        subnet_holding_key_record.ecdsa_config = Some(
+            EcdsaConfig {
+                quadruples_to_create_in_advance: 1,
+                key_ids: vec![key_id.clone()],
+                max_queue_size: Some(DEFAULT_ECDSA_MAX_QUEUE_SIZE),
+                signature_request_timeout_ns: None,
+                idkg_key_rotation_period_ms: None,
+            }
+            .into(),
+        );
+        registry.maybe_apply_mutation_internal(add_fake_subnet(
+            subnet_holding_key_id,
+            &mut subnet_list_record,
+            subnet_holding_key_record,
+        ));
+
+        let subnet_to_update = get_invariant_compliant_subnet_record(vec![node_ids.pop().unwrap()]);
+        registry.maybe_apply_mutation_internal(add_fake_subnet(
+            subnet_to_update_id,
+            &mut subnet_list_record,
+            subnet_to_update,
+        ));
+
+        (subnet_holding_key_id, subnet_to_update_id)
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "is either already held by the subnet or missing from its new \
+                    ecdsa_config."
+    )]
+    fn test_reshared_ecdsa_keys_must_be_added_to_ecdsa_config() {
+        let key_id = EcdsaKeyId {
+            curve: EcdsaCurve::Secp256k1,
+            name: "existing_key_id".to_string(),
+        };
+        let mut registry = invariant_compliant_registry();
+        let (subnet_holding_key_id, subnet_to_update_id) =
+            setup_subnet_holding_key_and_subnet_to_update(&mut registry, &key_id);
+
+        let mut payload = make_empty_update_payload(subnet_to_update_id);
+        payload.ecdsa_config = Some(EcdsaConfig {
+            quadruples_to_create_in_advance: 1,
+            key_ids: vec![],
+            max_queue_size: Some(DEFAULT_ECDSA_MAX_QUEUE_SIZE),
+            signature_request_timeout_ns: None,
+            idkg_key_rotation_period_ms: None,
+        });
+        payload.ecdsa_key_reshare_requests = Some(vec![EcdsaKeyRequest {
+            key_id,
+            subnet_id: Some(subnet_holding_key_id.get()),
+        }]);
+
+        futures::executor::block_on(registry.do_update_subnet(payload));
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "The requested ECDSA key 'Secp256k1:existing_key_id' is not available in \
+                    targeted subnet"
+    )]
+    fn test_reshared_ecdsa_keys_must_be_held_by_the_requested_subnet() {
+        let key_id = EcdsaKeyId {
+            curve: EcdsaCurve::Secp256k1,
+            name: "existing_key_id".to_string(),
+        };
+        let mut registry = invariant_compliant_registry();
+        let (_, subnet_to_update_id) =
+            setup_subnet_holding_key_and_subnet_to_update(&mut registry, &key_id);
+
+        let mut payload = make_empty_update_payload(subnet_to_update_id);
+        payload.ecdsa_config = Some(EcdsaConfig {
+            quadruples_to_create_in_advance: 1,
+            key_ids: vec![key_id.clone()],
+            max_queue_size: Some(DEFAULT_ECDSA_MAX_QUEUE_SIZE),
+            signature_request_timeout_ns: None,
+            idkg_key_rotation_period_ms: None,
+        });
+        // Request the key from the subnet being updated, which does not hold it.
+        payload.ecdsa_key_reshare_requests = Some(vec![EcdsaKeyRequest {
+            key_id,
+            subnet_id: Some(subnet_to_update_id.get()),
+        }]);
+
+        futures::executor::block_on(registry.do_update_subnet(payload));
     }
 
     #[test]
@@ -1149,7 +1452,7 @@ mod tests {
             idkg_key_rotation_period_ms: None,
         });
 
-        registry.do_update_subnet(payload);
+        futures::executor::block_on(registry.do_update_subnet(payload));
 
         // Make sure config contains the key.
         assert!(registry
@@ -1169,7 +1472,7 @@ mod tests {
             idkg_key_rotation_period_ms: None,
         });
 
-        registry.do_update_subnet(payload);
+        futures::executor::block_on(registry.do_update_subnet(payload));
 
         // Make sure config contains both keys.
         assert!(vec![first_key, second_key].iter().all(|k| registry
@@ -1226,7 +1529,7 @@ mod tests {
         });
         payload.ecdsa_key_signing_enable = Some(vec![key.clone()]);
 
-        registry.do_update_subnet(payload);
+        futures::executor::block_on(registry.do_update_subnet(payload));
 
         // Make sure it's actually in the signing list.
         assert!(registry
@@ -1253,7 +1556,7 @@ mod tests {
             idkg_key_rotation_period_ms: None,
         });
         payload.ecdsa_key_signing_disable = Some(vec![key.clone()]);
-        registry.do_update_subnet(payload);
+        futures::executor::block_on(registry.do_update_subnet(payload));
 
         // Ensure it's now removed from signing list.
         assert!(!registry
@@ -1316,7 +1619,7 @@ mod tests {
         });
         payload.ecdsa_key_signing_enable = Some(vec![key_held_by_subnet.clone()]);
 
-        registry.do_update_subnet(payload);
+        futures::executor::block_on(registry.do_update_subnet(payload));
 
         // Make sure it's actually in the signing list.
         assert!(registry
@@ -1336,7 +1639,7 @@ mod tests {
         // The next payload to disable signing with the key.
         let mut payload = make_empty_update_payload(subnet_id);
         payload.ecdsa_key_signing_disable = Some(vec![key_held_by_subnet.clone()]);
-        registry.do_update_subnet(payload);
+        futures::executor::block_on(registry.do_update_subnet(payload));
 
         // Ensure it's now removed from signing list.
         assert!(!registry
@@ -1405,6 +1708,118 @@ mod tests {
         payload.ecdsa_key_signing_disable = Some(vec![key]);
 
         // Should panic because we are trying to enable/disable same key
-        registry.do_update_subnet(payload);
+        futures::executor::block_on(registry.do_update_subnet(payload));
+    }
+
+    fn make_vetkd_config(name: &str) -> VetKdConfig {
+        VetKdConfig {
+            key_id: VetKdKeyId {
+                curve: VetKdCurve::Bls12_381_G2,
+                name: name.to_string(),
+            },
+            max_queue_size: Some(DEFAULT_VETKD_MAX_QUEUE_SIZE),
+            request_timeout_ns: None,
+        }
+    }
+
+    /// Adds a subnet holding the vetKD key of `vetkd_config`, if any, and a subnet without a
+    /// vetKD key to the registry, and returns their IDs, in that order.
+    fn setup_subnets_with_vetkd_config(
+        registry: &mut Registry,
+        vetkd_config: Option<VetKdConfig>,
+    ) -> (SubnetId, SubnetId) {
+        let subnet_holding_key_id = SubnetId::from(*TEST_USER1_PRINCIPAL);
+        let subnet_to_update_id = SubnetId::from(*TEST_USER2_PRINCIPAL);
+
+        let (mutate_request, mut node_ids) = prepare_registry_with_nodes(2);
+        registry.maybe_apply_mutation_internal(mutate_request.mutations);
+
+        let mut subnet_list_record = registry.get_subnet_list_record();
+
+        let mut subnet_holding_key_record =
+            get_invariant_compliant_subnet_record(vec![node_ids.pop().unwrap()]);
+        subnet_holding_key_record.vetkd_config = vetkd_config.map(Into::into);
+        registry.maybe_apply_mutation_internal(add_fake_subnet(
+            subnet_holding_key_id,
+            &mut subnet_list_record,
+            subnet_holding_key_record,
+        ));
+
+        let subnet_to_update = get_invariant_compliant_subnet_record(vec![node_ids.pop().unwrap()]);
+        registry.maybe_apply_mutation_internal(add_fake_subnet(
+            subnet_to_update_id,
+            &mut subnet_list_record,
+            subnet_to_update,
+        ));
+
+        (subnet_holding_key_id, subnet_to_update_id)
+    }
+
+    #[test]
+    fn test_vetkd_config_can_be_set_and_updated() {
+        let mut registry = invariant_compliant_registry();
+        let (_, subnet_id) = setup_subnets_with_vetkd_config(&mut registry, None);
+
+        let mut payload = make_empty_update_payload(subnet_id);
+        payload.vetkd_config = Some(make_vetkd_config("key_1"));
+        futures::executor::block_on(registry.do_update_subnet(payload));
+        assert_eq!(
+            registry.get_subnet_or_panic(subnet_id).vetkd_config,
+            Some(make_vetkd_config("key_1").into())
+        );
+
+        // The other settings of the key can still be changed.
+        let mut vetkd_config = make_vetkd_config("key_1");
+        vetkd_config.max_queue_size = Some(5);
+        vetkd_config.request_timeout_ns = Some(1_000_000_000);
+        let mut payload = make_empty_update_payload(subnet_id);
+        payload.vetkd_config = Some(vetkd_config.clone());
+        futures::executor::block_on(registry.do_update_subnet(payload));
+        assert_eq!(
+            registry.get_subnet_or_panic(subnet_id).vetkd_config,
+            Some(vetkd_config.into())
+        );
+
+        // Leaving the config unset in the payload keeps the key.
+        futures::executor::block_on(
+            registry.do_update_subnet(make_empty_update_payload(subnet_id)),
+        );
+        assert!(registry
+            .get_subnet_or_panic(subnet_id)
+            .vetkd_config
+            .is_some());
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "vetKD key with id 'Bls12_381_G2:existing_key_id' already exists.  \
+                    ID must be globally unique."
+    )]
+    fn test_vetkd_key_ids_must_be_globally_unique() {
+        let mut registry = invariant_compliant_registry();
+        let (_, subnet_to_update_id) = setup_subnets_with_vetkd_config(
+            &mut registry,
+            Some(make_vetkd_config("existing_key_id")),
+        );
+
+        let mut payload = make_empty_update_payload(subnet_to_update_id);
+        payload.vetkd_config = Some(make_vetkd_config("existing_key_id"));
+
+        futures::executor::block_on(registry.do_update_subnet(payload));
+    }
+
+    #[test]
+    #[should_panic(expected = "but the key of a subnet cannot be changed once set.")]
+    fn test_vetkd_key_id_cannot_be_changed() {
+        let mut registry = invariant_compliant_registry();
+        let (subnet_holding_key_id, _) = setup_subnets_with_vetkd_config(
+            &mut registry,
+            Some(make_vetkd_config("existing_key_id")),
+        );
+
+        let mut payload = make_empty_update_payload(subnet_holding_key_id);
+        payload.vetkd_config = Some(make_vetkd_config("other_key_id"));
+
+        futures::executor::block_on(registry.do_update_subnet(payload));
     }
 }
@@ -1,5 +1,5 @@
 use crate::mutations::common::get_subnet_ids_from_subnet_list;
-use crate::mutations::do_create_subnet::EcdsaInitialConfig;
+use crate::mutations::do_create_subnet::{EcdsaInitialConfig, EcdsaKeyRequest};
 use crate::registry::Version;
 use crate::{
     common::LOG_PREFIX,
@@ -12,7 +12,7 @@ use ic_base_types::{
     subnet_id_into_protobuf, CanisterId, NodeId, PrincipalId, RegistryVersion, SubnetId,
 };
 use ic_ic00_types::{
-    ComputeInitialEcdsaDealingsArgs, ComputeInitialEcdsaDealingsResponse, EcdsaKeyId,
+    ComputeInitialEcdsaDealingsArgs, ComputeInitialEcdsaDealingsResponse, EcdsaKeyId, VetKdKeyId,
 };
 use ic_protobuf::registry::crypto::v1::EcdsaSigningSubnetList;
 use ic_protobuf::registry::subnet::v1::EcdsaInitialization;
@@ -166,6 +166,28 @@ impl Registry {
         key_map
     }
 
+    /// Get a map representing VetKdKeyId => Subnets that hold the key
+    pub fn get_vetkd_keys_to_subnets_map(&self) -> HashMap<VetKdKeyId, Vec<SubnetId>> {
+        let mut key_map: HashMap<VetKdKeyId, Vec<SubnetId>> = HashMap::new();
+
+        get_subnet_ids_from_subnet_list(self.get_subnet_list_record())
+            .iter()
+            .for_each(|subnet_id| {
+                let subnet_record = self.get_subnet_or_panic(*subnet_id);
+                if let Some(key_id) = subnet_record
+                    .vetkd_config
+                    .and_then(|vetkd_conf| vetkd_conf.key_id)
+                {
+                    key_map
+                        .entry(key_id.try_into().unwrap())
+                        .or_default()
+                        .push(*subnet_id);
+                }
+            });
+
+        key_map
+    }
+
     /// Get the initial ECDSA dealings via a call to IC00 for a given EcdsaInitialConfig and a set of
     /// nodes to receive them.
     pub async fn get_all_initial_ecdsa_dealings_from_ic00(
@@ -173,29 +195,40 @@ impl Registry {
         ecdsa_initial_config: &Option<EcdsaInitialConfig>,
         receiver_nodes: Vec<NodeId>,
     ) -> Vec<EcdsaInitialization> {
-        let initial_ecdsa_dealings_futures = ecdsa_initial_config
-            .as_ref()
-            .map(|config| {
-                self.get_compute_ecdsa_args_from_initial_config(config, receiver_nodes)
-                    .into_iter()
-                    .map(|request| self.get_ecdsa_initializations_from_ic00(request))
-                    .collect::<Vec<_>>()
-            })
-            .unwrap_or_default();
+        match ecdsa_initial_config {
+            Some(config) => {
+                self.get_initial_ecdsa_dealings_for_key_requests(&config.keys, receiver_nodes)
+                    .await
+            }
+            None => vec![],
+        }
+    }
+
+    /// Get the initial ECDSA dealings via a call to IC00 for each of the given key requests,
+    /// reshared from the requested subnet to the given set of receiver nodes.
+    pub async fn get_initial_ecdsa_dealings_for_key_requests(
+        &self,
+        key_requests: &[EcdsaKeyRequest],
+        receiver_nodes: Vec<NodeId>,
+    ) -> Vec<EcdsaInitialization> {
+        let initial_ecdsa_dealings_futures = self
+            .get_compute_ecdsa_args_from_key_requests(key_requests, receiver_nodes)
+            .into_iter()
+            .map(|request| self.get_ecdsa_initializations_from_ic00(request))
+            .collect::<Vec<_>>();
 
         futures::future::join_all(initial_ecdsa_dealings_futures).await
     }
 
     /// Helper function to build the request objects to send to IC00 for
     /// `compute_initial_ecdsa_dealings`
-    fn get_compute_ecdsa_args_from_initial_config(
+    fn get_compute_ecdsa_args_from_key_requests(
         &self,
-        ecdsa_initial_config: &EcdsaInitialConfig,
+        key_requests: &[EcdsaKeyRequest],
         receiver_nodes: Vec<NodeId>,
     ) -> Vec<ComputeInitialEcdsaDealingsArgs> {
         let latest_version = self.latest_version();
-        ecdsa_initial_config
-            .keys
+        key_requests
             .iter()
             .map(|key_request| {
                 // create requests outside of async move context to avoid ownership problems
@@ -334,10 +367,20 @@ impl Registry {
         &self,
         ecdsa_initial_config: &EcdsaInitialConfig,
         own_subnet_id: Option<PrincipalId>,
+    ) -> Result<(), String> {
+        self.validate_ecdsa_key_requests(&ecdsa_initial_config.keys, own_subnet_id)
+    }
+
+    /// Validates that each of the given EcdsaKeyRequests targets a subnet that holds the
+    /// requested key, and that the targeted subnet is not `own_subnet_id`.
+    pub fn validate_ecdsa_key_requests(
+        &self,
+        key_requests: &[EcdsaKeyRequest],
+        own_subnet_id: Option<PrincipalId>,
     ) -> Result<(), String> {
         let ecdsa_subnet_map = self.get_ecdsa_keys_to_subnets_map();
 
-        for key_request in &ecdsa_initial_config.keys {
+        for key_request in key_requests {
             // Requested key must be a known key.
             if !ecdsa_subnet_map.contains_key(&key_request.key_id) {
                 return Err(format!(
@@ -92,6 +92,11 @@ pub struct Registry {
     /// RegistryAtomicMutateRequest.  We keep the serialized version around to
     /// make sure that hash trees stay the same even if protobuf schema evolves.
     pub(crate) changelog: RbTree<EncodedVersion, Vec<u8>>,
+
+    /// While a proposal is being dry run against a scratch copy of the
+    /// registry, the mutations it would apply are recorded here, and applied
+    /// to the copy without being checked. See `Registry::dry_run_proposal`.
+    pub(crate) recorded_mutations: Option<Vec<RegistryMutation>>,
 }
 
 impl Registry {
@@ -147,6 +152,28 @@ impl Registry {
         Some(value)
     }
 
+    /// Returns the values (including deletion markers) that `key` took in
+    /// versions `[from_version, to_version]`, oldest first; optionally
+    /// limited to the first `max_versions` of them.
+    pub fn get_value_history(
+        &self,
+        key: &[u8],
+        from_version: Version,
+        to_version: Version,
+        max_versions: Option<usize>,
+    ) -> Vec<&RegistryValue> {
+        let values = match self.store.get(key) {
+            Some(values) => values,
+            None => return vec![],
+        };
+        values
+            .iter()
+            .skip_while(|value| value.version < from_version)
+            .take_while(|value| value.version <= to_version)
+            .take(max_versions.unwrap_or(usize::MAX))
+            .collect()
+    }
+
     /// Computes the number of deltas with version greater than `since_version`
     /// that fit into the specified byte limit.
     ///
@@ -253,7 +280,7 @@ impl Registry {
 
     /// Verifies the implicit precondition corresponding to the mutation_type
     /// field.
-    fn verify_mutation_type(&self, mutations: &[RegistryMutation]) -> Vec<Error> {
+    pub(crate) fn verify_mutation_type(&self, mutations: &[RegistryMutation]) -> Vec<Error> {
         mutations
             .iter()
             .map(|m| {
@@ -288,6 +315,17 @@ impl Registry {
             mutations.len()
         );
 
+        if let Some(recorded_mutations) = self.recorded_mutations.as_mut() {
+            recorded_mutations.extend(mutations.iter().cloned());
+            // The registry is a scratch copy while a proposal is dry run, so
+            // the mutations are applied for the rest of the proposal to see
+            // them. They are checked by the dry run itself.
+            if self.verify_mutation_type(&mutations).is_empty() {
+                self.apply_mutations(mutations);
+            }
+            return;
+        }
+
         self.verify_mutations_internal(&mutations);
         self.apply_mutations(mutations);
     }
@@ -664,6 +702,66 @@ mod tests {
         serialize_then_deserialize(registry);
     }
 
+    #[test]
+    fn test_get_value_history() {
+        let mut registry = Registry::new();
+        let key1 = vec![1, 2, 3, 4];
+        let key2 = vec![5, 6, 7, 8];
+        let value1 = vec![5, 6, 7, 8];
+        let value2 = vec![9, 10, 11, 12];
+        assert_empty!(apply_mutations_skip_invariant_checks(
+            &mut registry,
+            vec![insert(&key1, &value1)]
+        ));
+        assert_empty!(apply_mutations_skip_invariant_checks(
+            &mut registry,
+            vec![insert(&key2, &value1)]
+        ));
+        assert_empty!(apply_mutations_skip_invariant_checks(
+            &mut registry,
+            vec![update(&key1, &value2)]
+        ));
+        assert_empty!(apply_mutations_skip_invariant_checks(
+            &mut registry,
+            vec![delete(&key1)]
+        ));
+
+        let versions = |history: Vec<&RegistryValue>| {
+            history
+                .into_iter()
+                .map(|value| value.version)
+                .collect::<Vec<_>>()
+        };
+
+        let history = registry.get_value_history(&key1, 0, u64::MAX, None);
+        assert_eq!(versions(history.clone()), vec![1, 3, 4]);
+        assert_eq!(history[0].value, value1);
+        assert_eq!(history[1].value, value2);
+        assert!(history[2].deletion_marker);
+
+        // Both ends of the range are inclusive.
+        assert_eq!(
+            versions(registry.get_value_history(&key1, 1, 3, None)),
+            vec![1, 3]
+        );
+        assert_eq!(
+            versions(registry.get_value_history(&key1, 2, 2, None)),
+            Vec::<u64>::new()
+        );
+        assert_eq!(
+            versions(registry.get_value_history(&key1, 2, 4, Some(1))),
+            vec![3]
+        );
+        assert_eq!(
+            versions(registry.get_value_history(&key2, 0, u64::MAX, None)),
+            vec![2]
+        );
+        assert_eq!(
+            versions(registry.get_value_history(&[0], 0, u64::MAX, None)),
+            Vec::<u64>::new()
+        );
+    }
+
     #[test]
     fn test_insert() {
         let mut registry = Registry::new();
@@ -151,6 +151,7 @@ fn test_subnet_is_only_deleted_when_appropriate() {
                 governance_canister_id: GOVERNANCE_CANISTER_ID,
                 minting_account_id: Some(GOVERNANCE_CANISTER_ID.get().into()),
                 last_purged_notification: Some(1),
+                cycles_ledger_canister_id: None,
             },
         )
         .await;
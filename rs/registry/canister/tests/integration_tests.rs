@@ -15,13 +15,16 @@ use ic_nns_test_utils::{
     registry::invariant_compliant_mutation_as_atomic_req,
 };
 use ic_nns_test_utils_macros::parameterized_upgrades;
-use ic_registry_nns_data_provider::certification::decode_hash_tree;
+use ic_registry_nns_data_provider::certification::{
+    decode_hash_tree, decode_value_history_hash_tree,
+};
 use ic_registry_transport::{
     insert,
     pb::v1::{
         registry_error::Code, CertifiedResponse, RegistryAtomicMutateRequest,
         RegistryAtomicMutateResponse, RegistryError, RegistryGetChangesSinceRequest,
-        RegistryGetLatestVersionResponse, RegistryGetValueRequest, RegistryGetValueResponse,
+        RegistryGetLatestVersionResponse, RegistryGetValueHistoryRequest, RegistryGetValueRequest,
+        RegistryGetValueResponse, RegistryMutation,
     },
     precondition, update, upsert,
 };
@@ -389,6 +392,82 @@ fn test_does_not_return_more_than_1000_certified_deltas() {
     });
 }
 
+#[test]
+fn test_get_value_history() {
+    fn mutate_request(mutations: Vec<RegistryMutation>) -> RegistryAtomicMutateRequest {
+        RegistryAtomicMutateRequest {
+            mutations,
+            preconditions: vec![],
+        }
+    }
+
+    local_test_on_nns_subnet(|runtime| async move {
+        // Version 1 is the invariant compliant mutation.
+        let canister = install_registry_canister(
+            &runtime,
+            RegistryCanisterInitPayloadBuilder::new()
+                .push_init_mutate_request(invariant_compliant_mutation_as_atomic_req())
+                .push_init_mutate_request(mutate_request(vec![insert("key1", "value1")]))
+                .push_init_mutate_request(mutate_request(vec![insert("key2", "value2")]))
+                .push_init_mutate_request(mutate_request(vec![update("key1", "value11")]))
+                .build(),
+        )
+        .await;
+
+        let latest_version_response: CertifiedResponse = canister
+            .query_("get_certified_latest_version", protobuf, vec![])
+            .await
+            .unwrap();
+        let latest_version_tree: MixedHashTree = latest_version_response
+            .hash_tree
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        for (from_version, to_version, expected_versions) in [
+            (0, 0, vec![2, 4]),
+            (3, 0, vec![4]),
+            (0, 3, vec![2]),
+            (3, 3, vec![]),
+        ] {
+            let certified_response: CertifiedResponse = canister
+                .query_(
+                    "get_value_history",
+                    protobuf,
+                    RegistryGetValueHistoryRequest {
+                        key: b"key1".to_vec(),
+                        from_version,
+                        to_version,
+                    },
+                )
+                .await
+                .unwrap();
+            let tree: MixedHashTree = certified_response.hash_tree.unwrap().try_into().unwrap();
+
+            // The merged witnesses must reconstruct the certified root hash.
+            assert_eq!(tree.digest(), latest_version_tree.digest());
+
+            let (history, current_version) = decode_value_history_hash_tree(
+                b"key1",
+                from_version,
+                Some(to_version).filter(|v| *v != 0),
+                tree,
+            )
+            .unwrap();
+            assert_eq!(current_version, RegistryVersion::from(4));
+            assert_eq!(
+                history
+                    .iter()
+                    .map(|record| record.version.get())
+                    .collect::<Vec<_>>(),
+                expected_versions
+            );
+        }
+
+        Ok(())
+    });
+}
+
 #[test]
 fn test_canister_installation_traps_on_bad_init_payload() {
     local_test_on_nns_subnet(|runtime| async move {
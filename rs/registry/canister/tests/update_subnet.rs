@@ -1,8 +1,11 @@
 use assert_matches::assert_matches;
 use candid::Encode;
+use canister_test::Runtime;
 use dfn_candid::candid;
 use ic_base_types::{subnet_id_try_from_protobuf, PrincipalId, SubnetId};
+use ic_config::Config;
 use ic_ic00_types::{EcdsaCurve, EcdsaKeyId};
+use ic_interfaces_registry::RegistryClient;
 use ic_nns_common::registry::encode_or_panic;
 use ic_nns_test_utils::{
     itest_helpers::{
@@ -12,13 +15,18 @@ use ic_nns_test_utils::{
     registry::{get_value_or_panic, invariant_compliant_mutation_as_atomic_req},
 };
 use ic_protobuf::registry::{
-    crypto::v1::EcdsaSigningSubnetList,
-    subnet::v1::{GossipConfig, SubnetRecord},
+    crypto::v1::{EcdsaCurve as pbEcdsaCurve, EcdsaKeyId as pbEcdsaKeyId, EcdsaSigningSubnetList},
+    subnet::v1::{GossipConfig, SubnetListRecord, SubnetRecord},
+};
+use ic_registry_keys::{
+    make_catch_up_package_contents_key, make_crypto_threshold_signing_pubkey_key,
+    make_ecdsa_signing_subnet_list_key, make_subnet_list_record_key, make_subnet_record_key,
 };
-use ic_registry_keys::{make_ecdsa_signing_subnet_list_key, make_subnet_record_key};
 use ic_registry_subnet_features::{EcdsaConfig, DEFAULT_ECDSA_MAX_QUEUE_SIZE};
 use ic_registry_subnet_type::SubnetType;
-use ic_registry_transport::{insert, pb::v1::RegistryAtomicMutateRequest};
+use ic_registry_transport::{insert, pb::v1::RegistryAtomicMutateRequest, upsert};
+use ic_replica_tests::{canister_test_with_config_async, get_ic_config};
+use ic_test_utilities::types::ids::subnet_test_id;
 use ic_types::{
     p2p::{
         build_default_gossip_config, MAX_ARTIFACT_STREAMS_PER_PEER, MAX_CHUNK_SIZE,
@@ -28,12 +36,21 @@ use ic_types::{
     ReplicaVersion,
 };
 use registry_canister::{
-    init::RegistryCanisterInitPayloadBuilder, mutations::do_update_subnet::UpdateSubnetPayload,
+    init::RegistryCanisterInitPayloadBuilder,
+    mutations::{
+        common::decode_registry_value,
+        do_create_subnet::{CreateSubnetPayload, EcdsaKeyRequest},
+        do_update_subnet::UpdateSubnetPayload,
+    },
 };
-use std::str::FromStr;
+use std::{convert::TryFrom, str::FromStr};
 
 mod common;
-use common::test_helpers::get_subnet_record;
+use common::test_helpers::{
+    dummy_cup_for_subnet, get_cup_contents, get_subnet_record, prepare_registry_with_nodes,
+    set_up_universal_canister_as_governance, setup_registry_synced_with_fake_client,
+    wait_for_ecdsa_setup,
+};
 
 fn make_ecdsa_key(name: &str) -> EcdsaKeyId {
     EcdsaKeyId {
@@ -73,6 +90,7 @@ fn test_the_anonymous_user_cannot_update_a_subnets_configuration() {
             initial_notary_delay_millis: None,
             dkg_interval_length: None,
             dkg_dealings_per_block: None,
+            adaptive_notarization_delays: None,
             max_artifact_streams_per_peer: Some(MAX_ARTIFACT_STREAMS_PER_PEER),
             max_chunk_wait_ms: Some(MAX_CHUNK_WAIT_MS),
             max_duplicity: Some(MAX_DUPLICITY),
@@ -92,6 +110,8 @@ fn test_the_anonymous_user_cannot_update_a_subnets_configuration() {
             ecdsa_config: None,
             ecdsa_key_signing_enable: None,
             ecdsa_key_signing_disable: None,
+            ecdsa_key_reshare_requests: None,
+            vetkd_config: None,
             max_number_of_canisters: Some(10),
             ssh_readonly_access: Some(vec!["pub_key_0".to_string()]),
             ssh_backup_access: Some(vec!["pub_key_1".to_string()]),
@@ -164,6 +184,8 @@ fn test_a_canister_other_than_the_governance_canister_cannot_update_a_subnets_co
             ssh_readonly_access: vec![],
             ssh_backup_access: vec![],
             ecdsa_config: None,
+            adaptive_notarization_delays: false,
+            vetkd_config: None,
         };
 
         // An attacker got a canister that is trying to pass for the governance
@@ -200,6 +222,7 @@ fn test_a_canister_other_than_the_governance_canister_cannot_update_a_subnets_co
             initial_notary_delay_millis: None,
             dkg_interval_length: None,
             dkg_dealings_per_block: None,
+            adaptive_notarization_delays: None,
             max_artifact_streams_per_peer: Some(MAX_ARTIFACT_STREAMS_PER_PEER),
             max_chunk_wait_ms: Some(MAX_CHUNK_WAIT_MS),
             max_duplicity: Some(MAX_DUPLICITY),
@@ -219,6 +242,8 @@ fn test_a_canister_other_than_the_governance_canister_cannot_update_a_subnets_co
             ecdsa_config: None,
             ecdsa_key_signing_enable: None,
             ecdsa_key_signing_disable: None,
+            ecdsa_key_reshare_requests: None,
+            vetkd_config: None,
             max_number_of_canisters: Some(100),
             ssh_readonly_access: None,
             ssh_backup_access: None,
@@ -290,6 +315,8 @@ fn test_the_governance_canister_can_update_a_subnets_configuration() {
                             ssh_readonly_access: vec![],
                             ssh_backup_access: vec![],
                             ecdsa_config: None,
+                            adaptive_notarization_delays: false,
+                            vetkd_config: None,
                         }),
                     )],
                     preconditions: vec![],
@@ -317,6 +344,7 @@ fn test_the_governance_canister_can_update_a_subnets_configuration() {
             initial_notary_delay_millis: None,
             dkg_interval_length: Some(2),
             dkg_dealings_per_block: Some(1),
+            adaptive_notarization_delays: None,
             max_artifact_streams_per_peer: Some(MAX_ARTIFACT_STREAMS_PER_PEER),
             max_chunk_wait_ms: Some(MAX_CHUNK_WAIT_MS),
             max_duplicity: Some(MAX_DUPLICITY),
@@ -336,6 +364,8 @@ fn test_the_governance_canister_can_update_a_subnets_configuration() {
             ecdsa_config: None,
             ecdsa_key_signing_enable: None,
             ecdsa_key_signing_disable: None,
+            ecdsa_key_reshare_requests: None,
+            vetkd_config: None,
             max_number_of_canisters: Some(42),
             ssh_readonly_access: Some(vec!["pub_key_0".to_string()]),
             ssh_backup_access: Some(vec!["pub_key_1".to_string()]),
@@ -392,6 +422,8 @@ fn test_the_governance_canister_can_update_a_subnets_configuration() {
                 ssh_readonly_access: vec!["pub_key_0".to_string()],
                 ssh_backup_access: vec!["pub_key_1".to_string()],
                 ecdsa_config: None,
+                adaptive_notarization_delays: false,
+                vetkd_config: None,
             }
         );
 
@@ -445,6 +477,8 @@ fn test_subnets_configuration_ecdsa_fields_are_updated_correctly() {
             ssh_readonly_access: vec![],
             ssh_backup_access: vec![],
             ecdsa_config: None,
+            adaptive_notarization_delays: false,
+            vetkd_config: None,
         };
 
         // Just create the registry canister and wait until the subnet_handler ID is
@@ -624,6 +658,183 @@ fn test_subnets_configuration_ecdsa_fields_are_updated_correctly() {
     });
 }
 
+#[test]
+fn test_update_subnet_reshares_ecdsa_keys_from_another_subnet() {
+    let ic_config = get_ic_config();
+    let (config, _tmpdir) = Config::temp_config();
+    let subnet_config = ic_config::subnet_config::SubnetConfig::default_system_subnet();
+    canister_test_with_config_async(
+        config,
+        subnet_config,
+        ic_config,
+        |local_runtime| async move {
+            let data_provider = local_runtime.registry_data_provider.clone();
+            let fake_client = local_runtime.registry_client.clone();
+
+            let runtime = Runtime::Local(local_runtime);
+            // get some nodes for our tests
+            let (init_mutate, mut node_ids) = prepare_registry_with_nodes(5);
+
+            let key_1 = make_ecdsa_key("foo-bar");
+
+            let subnet_to_update_nodes = vec![node_ids.pop().unwrap()];
+            let subnet_to_update: SubnetRecord = CreateSubnetPayload {
+                node_ids: subnet_to_update_nodes.clone(),
+                unit_delay_millis: 10,
+                gossip_retransmission_request_ms: 10_000,
+                gossip_registry_poll_period_ms: 2000,
+                gossip_pfn_evaluation_period_ms: 50,
+                gossip_receive_check_cache_size: 1,
+                gossip_max_duplicity: 1,
+                gossip_max_chunk_wait_ms: 200,
+                gossip_max_artifact_streams_per_peer: 1,
+                replica_version_id: ReplicaVersion::default().into(),
+                ..CreateSubnetPayload::default()
+            }
+            .into();
+
+            // Here we discover the IC's subnet ID (from our test harness)
+            // and then modify it to hold the key and sign for it.
+            let mut subnet_list_record = decode_registry_value::<SubnetListRecord>(
+                fake_client
+                    .get_value(
+                        &make_subnet_list_record_key(),
+                        fake_client.get_latest_version(),
+                    )
+                    .unwrap()
+                    .unwrap(),
+            );
+
+            let subnet_to_update_subnet_id = subnet_test_id(1003);
+
+            subnet_list_record
+                .subnets
+                .push(subnet_to_update_subnet_id.get().into_vec());
+
+            let system_subnet_id = SubnetId::new(
+                PrincipalId::try_from(subnet_list_record.subnets.get(0).unwrap()).unwrap(),
+            );
+            let mut subnet_record = decode_registry_value::<SubnetRecord>(
+                fake_client
+                    .get_value(
+                        &make_subnet_record_key(system_subnet_id),
+                        fake_client.get_latest_version(),
+                    )
+                    .unwrap()
+                    .unwrap(),
+            );
+            subnet_record.ecdsa_config = Some(
+                EcdsaConfig {
+                    quadruples_to_create_in_advance: 1,
+                    key_ids: vec![key_1.clone()],
+                    max_queue_size: Some(DEFAULT_ECDSA_MAX_QUEUE_SIZE),
+                    signature_request_timeout_ns: None,
+                    idkg_key_rotation_period_ms: None,
+                }
+                .into(),
+            );
+
+            let modify_base_subnet_mutate = RegistryAtomicMutateRequest {
+                mutations: vec![upsert(
+                    make_subnet_record_key(system_subnet_id),
+                    encode_or_panic(&subnet_record),
+                )],
+                preconditions: vec![],
+            };
+
+            // Add the subnet we are updating, which does not hold any keys yet.
+            // Note, because these mutations are also synced with underlying IC registry, they
+            // need a CUP
+            let add_subnets_mutate = RegistryAtomicMutateRequest {
+                preconditions: vec![],
+                mutations: vec![
+                    upsert(
+                        make_subnet_record_key(subnet_to_update_subnet_id).into_bytes(),
+                        encode_or_panic(&subnet_to_update),
+                    ),
+                    upsert(
+                        make_subnet_list_record_key().into_bytes(),
+                        encode_or_panic(&subnet_list_record),
+                    ),
+                    insert(
+                        make_crypto_threshold_signing_pubkey_key(subnet_to_update_subnet_id)
+                            .as_bytes(),
+                        encode_or_panic(&vec![]),
+                    ),
+                    insert(
+                        make_catch_up_package_contents_key(subnet_to_update_subnet_id).as_bytes(),
+                        encode_or_panic(&dummy_cup_for_subnet(subnet_to_update_nodes)),
+                    ),
+                ],
+            };
+
+            let registry = setup_registry_synced_with_fake_client(
+                &runtime,
+                fake_client,
+                data_provider,
+                vec![init_mutate, add_subnets_mutate, modify_base_subnet_mutate],
+            )
+            .await;
+
+            let before_update_cup_contents =
+                get_cup_contents(&registry, subnet_to_update_subnet_id).await;
+            assert_eq!(before_update_cup_contents.ecdsa_initializations.len(), 0);
+
+            // Install the universal canister in place of the governance canister
+            let fake_governance_canister = set_up_universal_canister_as_governance(&runtime).await;
+            wait_for_ecdsa_setup(&runtime, &fake_governance_canister, &key_1).await;
+
+            let payload = UpdateSubnetPayload {
+                ecdsa_config: Some(EcdsaConfig {
+                    quadruples_to_create_in_advance: 1,
+                    key_ids: vec![key_1.clone()],
+                    max_queue_size: Some(DEFAULT_ECDSA_MAX_QUEUE_SIZE),
+                    signature_request_timeout_ns: None,
+                    idkg_key_rotation_period_ms: None,
+                }),
+                ecdsa_key_reshare_requests: Some(vec![EcdsaKeyRequest {
+                    key_id: key_1.clone(),
+                    subnet_id: Some(system_subnet_id.get()),
+                }]),
+                ..empty_update_subnet_payload(subnet_to_update_subnet_id)
+            };
+
+            // When we reshare the key held by the system subnet to our subnet
+            try_call_via_universal_canister(
+                &fake_governance_canister,
+                &registry,
+                "update_subnet",
+                Encode!(&payload).unwrap(),
+            )
+            .await
+            .unwrap();
+
+            // The initial dealings for the key are placed in the subnet's CUP contents
+            let cup_contents = get_cup_contents(&registry, subnet_to_update_subnet_id).await;
+            let dealings = &cup_contents.ecdsa_initializations;
+            assert_eq!(dealings.len(), 1);
+            assert_eq!(
+                dealings[0_usize].key_id,
+                Some(pbEcdsaKeyId {
+                    curve: pbEcdsaCurve::Secp256k1.into(),
+                    name: "foo-bar".to_string(),
+                })
+            );
+
+            // And the key is added to the subnet's EcdsaConfig
+            let subnet_record = get_subnet_record(&registry, subnet_to_update_subnet_id).await;
+            let ecdsa_config = subnet_record.ecdsa_config.unwrap();
+            assert_eq!(
+                ecdsa_config.key_ids,
+                vec![pbEcdsaKeyId {
+                    curve: pbEcdsaCurve::Secp256k1.into(),
+                    name: "foo-bar".to_string(),
+                }]
+            );
+        },
+    );
+}
+
 /// Returns an update to the given subnet that doesn't change any fields.
 fn empty_update_subnet_payload(subnet_id: SubnetId) -> UpdateSubnetPayload {
     UpdateSubnetPayload {
@@ -635,6 +846,7 @@ fn empty_update_subnet_payload(subnet_id: SubnetId) -> UpdateSubnetPayload {
         initial_notary_delay_millis: None,
         dkg_interval_length: None,
         dkg_dealings_per_block: None,
+        adaptive_notarization_delays: None,
         max_artifact_streams_per_peer: None,
         max_chunk_wait_ms: None,
         max_duplicity: None,
@@ -657,5 +869,7 @@ fn empty_update_subnet_payload(subnet_id: SubnetId) -> UpdateSubnetPayload {
         ecdsa_config: None,
         ecdsa_key_signing_enable: None,
         ecdsa_key_signing_disable: None,
+        ecdsa_key_reshare_requests: None,
+        vetkd_config: None,
     }
 }
@@ -12,7 +12,7 @@ use ic_registry_keys::{
     make_catch_up_package_contents_key, make_node_record_key, make_replica_version_key,
     make_subnet_list_record_key, make_subnet_record_key, ROOT_SUBNET_ID_KEY,
 };
-use ic_registry_subnet_features::{EcdsaConfig, SubnetFeatures};
+use ic_registry_subnet_features::{EcdsaConfig, SubnetFeatures, VetKdConfig};
 use ic_types::{Height, NodeId, PrincipalId, RegistryVersion, ReplicaVersion, SubnetId};
 use std::convert::{TryFrom, TryInto};
 use std::time::Duration;
@@ -21,6 +21,9 @@ use std::time::Duration;
 pub struct NotarizationDelaySettings {
     pub unit_delay: Duration,
     pub initial_notary_delay: Duration,
+    /// Whether consensus may scale the delays above down, based on statistics
+    /// of the finalized chain.
+    pub adaptive: bool,
 }
 
 pub struct IngressMessageSettings {
@@ -83,6 +86,13 @@ pub trait SubnetRegistry {
         version: RegistryVersion,
     ) -> RegistryClientResult<EcdsaConfig>;
 
+    /// Returns vetkd config
+    fn get_vetkd_config(
+        &self,
+        subnet_id: SubnetId,
+        version: RegistryVersion,
+    ) -> RegistryClientResult<VetKdConfig>;
+
     /// Returns notarization delay settings:
     /// - the unit delay for blockmaker;
     /// - the initial delay for notary, to give time to rank-0 block
@@ -262,6 +272,16 @@ impl<T: RegistryClient + ?Sized> SubnetRegistry for T {
         Ok(subnet.and_then(|subnet| subnet.ecdsa_config.map(|config| config.try_into().unwrap())))
     }
 
+    fn get_vetkd_config(
+        &self,
+        subnet_id: SubnetId,
+        version: RegistryVersion,
+    ) -> RegistryClientResult<VetKdConfig> {
+        let bytes = self.get_value(&make_subnet_record_key(subnet_id), version);
+        let subnet = deserialize_registry_value::<SubnetRecord>(bytes)?;
+        Ok(subnet.and_then(|subnet| subnet.vetkd_config.map(|config| config.try_into().unwrap())))
+    }
+
     fn get_notarization_delay_settings(
         &self,
         subnet_id: SubnetId,
@@ -273,6 +293,7 @@ impl<T: RegistryClient + ?Sized> SubnetRegistry for T {
                 NotarizationDelaySettings {
                     unit_delay: Duration::from_millis(subnet.unit_delay_millis),
                     initial_notary_delay: Duration::from_millis(subnet.initial_notary_delay_millis),
+                    adaptive: subnet.adaptive_notarization_delays,
                 }
             }),
         )
@@ -0,0 +1,96 @@
+//! An index over the changelog of a local store that answers, for a single
+//! key, which values it took in a range of versions, and reconstructs the
+//! content of the registry at any version.
+
+use crate::LocalStoreReader;
+use ic_types::RegistryVersion;
+use std::{collections::BTreeMap, io, ops::RangeInclusive};
+
+/// A value a key took in the history of the registry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HistoryEntry {
+    /// The version at which the key was mutated.
+    pub version: RegistryVersion,
+
+    /// The value of the key at `version`. `None` means that the key was deleted
+    /// at `version`.
+    pub value: Option<Vec<u8>>,
+}
+
+/// Maps every key of the registry to the list of its mutations, ordered by
+/// version.
+///
+/// The index is built from the changelog of a local store and can be brought
+/// up to date incrementally by calling [`KeyHistoryIndex::update`].
+#[derive(Clone, Debug, Default)]
+pub struct KeyHistoryIndex {
+    latest_version: RegistryVersion,
+    index: BTreeMap<String, Vec<(RegistryVersion, Option<Vec<u8>>)>>,
+}
+
+impl KeyHistoryIndex {
+    /// Builds the index of all versions available in `store`.
+    pub fn build(store: &dyn LocalStoreReader) -> io::Result<Self> {
+        let mut index = Self::default();
+        index.update(store)?;
+        Ok(index)
+    }
+
+    /// Adds the versions that were added to `store` since the index was last
+    /// updated.
+    pub fn update(&mut self, store: &dyn LocalStoreReader) -> io::Result<()> {
+        for changelog_entry in store.get_changelog_since_version(self.latest_version)? {
+            self.latest_version = RegistryVersion::from(self.latest_version.get() + 1);
+            for key_mutation in changelog_entry {
+                self.index
+                    .entry(key_mutation.key)
+                    .or_default()
+                    .push((self.latest_version, key_mutation.value));
+            }
+        }
+        Ok(())
+    }
+
+    /// The latest version covered by the index.
+    pub fn latest_version(&self) -> RegistryVersion {
+        self.latest_version
+    }
+
+    /// Returns the values `key` took in `versions`, oldest first.
+    pub fn key_history(
+        &self,
+        key: &str,
+        versions: RangeInclusive<RegistryVersion>,
+    ) -> Vec<HistoryEntry> {
+        let mutations = match self.index.get(key) {
+            Some(mutations) => mutations,
+            None => return vec![],
+        };
+        let start = mutations.partition_point(|(v, _)| v < versions.start());
+        mutations[start..]
+            .iter()
+            .take_while(|(v, _)| v <= versions.end())
+            .map(|(version, value)| HistoryEntry {
+                version: *version,
+                value: value.clone(),
+            })
+            .collect()
+    }
+
+    /// Returns the value of `key` at `version`, or `None` if the key was not
+    /// present at that version.
+    pub fn get_value(&self, key: &str, version: RegistryVersion) -> Option<&[u8]> {
+        let mutations = self.index.get(key)?;
+        let end = mutations.partition_point(|(v, _)| *v <= version);
+        let (_, value) = mutations[..end].last()?;
+        value.as_deref()
+    }
+
+    /// Returns the content of the registry at `version`.
+    pub fn snapshot(&self, version: RegistryVersion) -> BTreeMap<&str, &[u8]> {
+        self.index
+            .keys()
+            .filter_map(|key| Some((key.as_str(), self.get_value(key, version)?)))
+            .collect()
+    }
+}
@@ -10,11 +10,17 @@ use ic_types::RegistryVersion;
 use ic_utils::fs::write_protobuf_using_tmp_file;
 use prost::Message;
 use std::{
+    collections::BTreeMap,
     io::{self},
     path::{Path, PathBuf},
     sync::{Arc, Mutex},
     time::{Duration, Instant},
 };
+
+mod history;
+
+pub use history::{HistoryEntry, KeyHistoryIndex};
+
 pub trait LocalStore: LocalStoreWriter + LocalStoreReader + LocalStoreCertifiedTimeReader {}
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -41,7 +47,17 @@ pub trait LocalStoreReader: Send + Sync {
     /// `cl` where the subsequence `cl[0..i]`, `0 <= i <= len(ds)`, applied
     /// to a registry at latest version `v` represents the registry at
     /// version `v+i+1`.
+    ///
+    /// If the store was compacted at version `c`, the changes of the
+    /// individual versions up to `c` are no longer available: for `version`
+    /// 0, the entries of the versions before `c` are empty and the entry of
+    /// version `c` holds the whole content of the registry at `c`, and for
+    /// `0 < version < c` an error is returned.
     fn get_changelog_since_version(&self, version: RegistryVersion) -> io::Result<Changelog>;
+
+    /// Returns the latest version contained in the compacted snapshot of the
+    /// store, or version 0 if the store was never compacted.
+    fn get_compacted_version(&self) -> io::Result<RegistryVersion>;
 }
 
 pub trait LocalStoreWriter: Send + Sync {
@@ -55,17 +71,58 @@ pub trait LocalStoreWriter: Send + Sync {
 
     /// Clears the Local Store.
     ///
-    /// Note: This clears registry versions, stored in directories, but not the
-    /// certified timestamp file in the root of the local store.
+    /// Note: This clears registry versions, stored in directories or in the
+    /// compacted snapshot, but not the certified timestamp file in the root of
+    /// the local store.
     fn clear(&self) -> io::Result<()>;
 
+    /// Replaces all versions up to and including `version` by a single
+    /// snapshot file holding the content of the registry at `version`. The
+    /// files of the individual versions and any previous snapshot are
+    /// removed, while the versions after `version` are kept as they are.
+    ///
+    /// Versions that are part of the compacted snapshot can no longer be
+    /// overwritten using [LocalStoreWriter::store], and the changes they made
+    /// are no longer available, see
+    /// [LocalStoreReader::get_changelog_since_version].
+    ///
+    /// Preconditions: `version` must exist in the store.
+    fn compact(&self, version: RegistryVersion) -> io::Result<()>;
+
+    /// Stores `snapshot`, the content of the registry at `version`, as the
+    /// compacted snapshot of an empty store. Keys without a value are not part
+    /// of the snapshot.
+    ///
+    /// This allows to store a changelog read from a compacted store, in which
+    /// the entry at the compacted version holds the compacted snapshot, see
+    /// [LocalStoreReader::get_changelog_since_version].
+    ///
+    /// Preconditions: the store is empty and `version` > 0.
+    fn store_compacted_snapshot(
+        &self,
+        version: RegistryVersion,
+        snapshot: ChangelogEntry,
+    ) -> io::Result<()>;
+
     /// Update the locally stored certified time to `unix_epoch_nanos`.
     fn update_certified_time(&self, unix_epoch_nanos: u64) -> io::Result<()>;
 }
 
+/// Name prefix of the file holding the compacted snapshot. The file name
+/// contains the version of the snapshot as a 16 digit hex number, followed by
+/// [COMPACTED_SNAPSHOT_SUFFIX].
+///
+/// The file holds a `Delta` whose `registry_version` is the version of the
+/// snapshot and whose changelog consists of a single entry that sets every key
+/// present in the registry at that version, or of no entry if the registry was
+/// empty.
+const COMPACTED_SNAPSHOT_PREFIX: &str = "compacted.";
+const COMPACTED_SNAPSHOT_SUFFIX: &str = ".local_store.v1.Delta.pb";
+
 #[derive(Clone, Debug)]
 pub struct LocalStoreImpl {
-    /// Directory with one .pb file per registry version.
+    /// Directory with one .pb file per registry version that is not part of the
+    /// compacted snapshot, and a single file holding the compacted snapshot.
     path: PathBuf,
 
     /// Cached certified local store time, indicating instant at which the cache
@@ -102,6 +159,107 @@ impl LocalStoreImpl {
         self.path.join(v_path.as_path())
     }
 
+    fn compacted_snapshot_path(&self, version: u64) -> PathBuf {
+        self.path.join(format!(
+            "{}{:016x}{}",
+            COMPACTED_SNAPSHOT_PREFIX, version, COMPACTED_SNAPSHOT_SUFFIX
+        ))
+    }
+
+    /// Returns the versions of all compacted snapshots present in the store, in
+    /// ascending order. Usually there is at most one, but a crash during
+    /// compaction may leave behind a superseded snapshot.
+    fn compacted_snapshot_versions(&self) -> io::Result<Vec<u64>> {
+        if !self.path.exists() {
+            return Ok(vec![]);
+        }
+        let mut versions =
+            std::fs::read_dir(self.path.as_path())?.try_fold(vec![], |mut versions, de| {
+                let file_name = de?.file_name();
+                if let Some(version) = file_name
+                    .to_str()
+                    .and_then(|n| n.strip_prefix(COMPACTED_SNAPSHOT_PREFIX))
+                    .and_then(|n| n.strip_suffix(COMPACTED_SNAPSHOT_SUFFIX))
+                    .and_then(|v| u64::from_str_radix(v, 16).ok())
+                {
+                    versions.push(version);
+                }
+                Ok::<_, io::Error>(versions)
+            })?;
+        versions.sort_unstable();
+        Ok(versions)
+    }
+
+    /// Reads the compacted snapshot at `version`, i.e. the content of the
+    /// registry at `version`.
+    fn read_compacted_snapshot(&self, version: u64) -> io::Result<BTreeMap<String, Vec<u8>>> {
+        let bytes = std::fs::read(self.compacted_snapshot_path(version))?;
+        let (snapshot_version, changelog) = compact_delta_to_changelog(bytes.as_slice())?;
+        if snapshot_version.get() != version || changelog.len() > 1 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Compacted snapshot at version {} holds {} entries at version {}.",
+                    version,
+                    changelog.len(),
+                    snapshot_version
+                ),
+            ));
+        }
+        changelog
+            .into_iter()
+            .flatten()
+            .map(|km| match km.value {
+                Some(value) => Ok((km.key, value)),
+                None => Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Compacted snapshot at version {} deletes a key.", version),
+                )),
+            })
+            .collect()
+    }
+
+    /// Writes `snapshot` as the compacted snapshot at `version`.
+    fn write_compacted_snapshot(
+        &self,
+        version: u64,
+        snapshot: BTreeMap<String, Vec<u8>>,
+    ) -> io::Result<()> {
+        let snapshot: ChangelogEntry = snapshot
+            .into_iter()
+            .map(|(key, value)| KeyMutation {
+                key,
+                value: Some(value),
+            })
+            .collect();
+        let delta = PbDelta {
+            registry_version: version,
+            changelog: if snapshot.is_empty() {
+                vec![]
+            } else {
+                vec![changelog_entry_to_protobuf(snapshot)]
+            },
+        };
+        std::fs::create_dir_all(self.path.as_path())?;
+        write_protobuf_using_tmp_file(self.compacted_snapshot_path(version), &delta)
+    }
+
+    /// Removes the file of `version` as well as the directories containing it
+    /// that are left empty.
+    fn remove_version_file(&self, version: u64) -> io::Result<()> {
+        let path = self.get_path(version);
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            res => res?,
+        }
+        for dir in path.ancestors().skip(1) {
+            if dir == self.path || std::fs::remove_dir(dir).is_err() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
     fn read_changelog_entry<P: AsRef<Path>>(p: P) -> io::Result<PbChangelogEntry> {
         let bytes = std::fs::read(p)?;
         PbChangelogEntry::decode(bytes.as_slice())
@@ -116,7 +274,17 @@ impl LocalStoreImpl {
         if version == 0 {
             panic!("Version must be > 0.")
         }
-        if version > 1 && !self.get_path(version - 1).exists() {
+        let compacted_version = self.get_compacted_version()?.get();
+        if version <= compacted_version {
+            return Err(io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Version {} is part of the compacted snapshot at version {}.",
+                    version, compacted_version
+                ),
+            ));
+        }
+        if version - 1 > compacted_version && !self.get_path(version - 1).exists() {
             return Err(io::Error::new(
                 std::io::ErrorKind::NotFound,
                 format!("Version {} does not exist.", version - 1),
@@ -157,17 +325,47 @@ impl LocalStore for LocalStoreImpl {}
 
 impl LocalStoreReader for LocalStoreImpl {
     fn get_changelog_since_version(&self, version: RegistryVersion) -> io::Result<Changelog> {
-        let start = version.get() + 1;
+        let compacted_version = self.get_compacted_version()?.get();
+        // The compacted snapshot is only read if it contains requested versions.
+        let compacted = if version.get() >= compacted_version {
+            vec![]
+        } else if version.get() == 0 {
+            let snapshot = self
+                .read_compacted_snapshot(compacted_version)?
+                .into_iter()
+                .map(|(key, value)| KeyMutation {
+                    key,
+                    value: Some(value),
+                })
+                .collect();
+            let mut changelog = vec![vec![]; compacted_version as usize - 1];
+            changelog.push(snapshot);
+            changelog
+        } else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "Changes since version {} were compacted into the snapshot at version {}.",
+                    version, compacted_version
+                ),
+            ));
+        };
+        let start = version.get().max(compacted_version) + 1;
         (start..)
             .map(|i| self.get_path(i))
             .take_while(|p| p.exists())
-            .try_fold(vec![], |mut res, p| {
+            .try_fold(compacted, |mut res, p| {
                 res.push(changelog_entry_try_from_proto(Self::read_changelog_entry(
                     p,
                 )?)?);
                 Ok(res)
             })
     }
+
+    fn get_compacted_version(&self) -> io::Result<RegistryVersion> {
+        let versions = self.compacted_snapshot_versions()?;
+        Ok(RegistryVersion::from(versions.last().copied().unwrap_or(0)))
+    }
 }
 
 impl LocalStoreWriter for LocalStoreImpl {
@@ -185,7 +383,76 @@ impl LocalStoreWriter for LocalStoreImpl {
             } else {
                 Ok(())
             }
-        })
+        })?;
+        self.compacted_snapshot_versions()?
+            .into_iter()
+            .try_for_each(|v| std::fs::remove_file(self.compacted_snapshot_path(v)))
+    }
+
+    fn compact(&self, version: RegistryVersion) -> io::Result<()> {
+        let compacted_versions = self.compacted_snapshot_versions()?;
+        let compacted_version = compacted_versions.last().copied().unwrap_or(0);
+        if version.get() <= compacted_version {
+            return Ok(());
+        }
+
+        let changelog =
+            self.get_changelog_since_version(RegistryVersion::from(compacted_version))?;
+        if (changelog.len() as u64) < version.get() - compacted_version {
+            return Err(io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Version {} does not exist.", version),
+            ));
+        }
+        let mut snapshot = if compacted_version > 0 {
+            self.read_compacted_snapshot(compacted_version)?
+        } else {
+            BTreeMap::new()
+        };
+        for km in changelog
+            .into_iter()
+            .take((version.get() - compacted_version) as usize)
+            .flatten()
+        {
+            match km.value {
+                Some(value) => snapshot.insert(km.key, value),
+                None => snapshot.remove(&km.key),
+            };
+        }
+        self.write_compacted_snapshot(version.get(), snapshot)?;
+
+        // Readers always use the latest snapshot, so whatever the new snapshot
+        // supersedes can be removed without affecting them. Superseded
+        // snapshots are removed last, so that files left behind by an
+        // interrupted compaction are removed by the next one.
+        let first_compacted_version = compacted_versions.first().copied().unwrap_or(0);
+        for v in first_compacted_version + 1..=version.get() {
+            self.remove_version_file(v)?;
+        }
+        compacted_versions
+            .into_iter()
+            .try_for_each(|v| std::fs::remove_file(self.compacted_snapshot_path(v)))
+    }
+
+    fn store_compacted_snapshot(
+        &self,
+        version: RegistryVersion,
+        snapshot: ChangelogEntry,
+    ) -> io::Result<()> {
+        if version.get() == 0 {
+            panic!("Version must be > 0.")
+        }
+        if self.get_compacted_version()?.get() > 0 || self.get_path(1).exists() {
+            return Err(io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "A compacted snapshot can only be stored in an empty store.",
+            ));
+        }
+        let snapshot = snapshot
+            .into_iter()
+            .filter_map(|km| Some((km.key, km.value?)))
+            .collect();
+        self.write_compacted_snapshot(version.get(), snapshot)
     }
 
     // Store the certified time
@@ -374,6 +641,7 @@ fn changelog_entry_to_protobuf(ce: ChangelogEntry) -> PbChangelogEntry {
 mod tests {
     use super::*;
     use rand::prelude::*;
+    use std::collections::BTreeMap;
     use tempfile::TempDir;
 
     #[test]
@@ -456,6 +724,182 @@ mod tests {
         }
     }
 
+    #[test]
+    fn compaction_replaces_compacted_versions_by_a_snapshot() {
+        let tempdir = TempDir::new().unwrap();
+        let store = LocalStoreImpl::new(tempdir.path());
+        let mut rng = rand::thread_rng();
+
+        let mut changelog = get_random_changelog(200, &mut rng);
+        changelog.iter().enumerate().for_each(|(i, c)| {
+            store
+                .store(RegistryVersion::from((i + 1) as u64), c.clone())
+                .unwrap()
+        });
+        assert_eq!(
+            store.get_compacted_version().unwrap(),
+            RegistryVersion::from(0)
+        );
+        let index = KeyHistoryIndex::build(&store).unwrap();
+
+        store.compact(RegistryVersion::from(150)).unwrap();
+        assert_eq!(
+            store.get_compacted_version().unwrap(),
+            RegistryVersion::from(150)
+        );
+        assert!(!store.get_path(150).exists());
+        assert!(store.get_path(151).exists());
+
+        // The snapshot holds the content of the registry at the compacted
+        // version, and the later versions are unchanged.
+        let cl = store
+            .get_changelog_since_version(RegistryVersion::from(0))
+            .unwrap();
+        assert_eq!(cl.len(), changelog.len());
+        assert!(cl[..149].iter().all(|ce| ce.is_empty()));
+        assert_eq!(&cl[150..], &changelog[150..]);
+        let compacted_index = KeyHistoryIndex::build(&store).unwrap();
+        for v in 150..=200 {
+            let v = RegistryVersion::from(v);
+            assert_eq!(compacted_index.snapshot(v), index.snapshot(v));
+        }
+        for i in (150..changelog.len()).step_by(10) {
+            let cl = store
+                .get_changelog_since_version(RegistryVersion::from(i as u64))
+                .unwrap();
+            assert_eq!(&changelog[i..], cl.as_slice());
+        }
+        assert!(store
+            .get_changelog_since_version(RegistryVersion::from(100))
+            .is_err());
+
+        // Compacted versions can't be overwritten, but the store can be
+        // extended.
+        assert!(store
+            .store(RegistryVersion::from(150), changelog[0].clone())
+            .is_err());
+        let mut new_changelog = get_random_changelog(100, &mut rng);
+        new_changelog.iter().enumerate().for_each(|(i, c)| {
+            store
+                .store(RegistryVersion::from((i + 201) as u64), c.clone())
+                .unwrap()
+        });
+        changelog.append(&mut new_changelog);
+        let index = KeyHistoryIndex::build(&store).unwrap();
+
+        store.compact(RegistryVersion::from(300)).unwrap();
+        assert_eq!(store.compacted_snapshot_versions().unwrap(), vec![300]);
+        assert_eq!(
+            KeyHistoryIndex::build(&store)
+                .unwrap()
+                .snapshot(RegistryVersion::from(300)),
+            index.snapshot(RegistryVersion::from(300))
+        );
+        assert!(store
+            .get_changelog_since_version(RegistryVersion::from(300))
+            .unwrap()
+            .is_empty());
+        // Only the snapshot and no version directories are left.
+        assert_eq!(std::fs::read_dir(tempdir.path()).unwrap().count(), 1);
+
+        assert!(store.compact(RegistryVersion::from(301)).is_err());
+        store
+            .store(RegistryVersion::from(301), changelog[0].clone())
+            .unwrap();
+    }
+
+    #[test]
+    fn compacted_snapshot_does_not_grow_with_the_number_of_versions() {
+        let tempdir = TempDir::new().unwrap();
+        let store = LocalStoreImpl::new(tempdir.path());
+        let mut rng = rand::thread_rng();
+
+        // All versions mutate the same few keys, so the snapshot holds at
+        // most one value per key.
+        let changelog = get_random_changelog(500, &mut rng);
+        changelog.iter().enumerate().for_each(|(i, c)| {
+            store
+                .store(RegistryVersion::from((i + 1) as u64), c.clone())
+                .unwrap()
+        });
+        store.compact(RegistryVersion::from(500)).unwrap();
+
+        let bytes = std::fs::read(store.compacted_snapshot_path(500)).unwrap();
+        let (version, snapshot) = compact_delta_to_changelog(bytes.as_slice()).unwrap();
+        assert_eq!(version, RegistryVersion::from(500));
+        assert!(snapshot.len() <= 1);
+        let keys: Vec<_> = snapshot.iter().flatten().map(|km| &km.key).collect();
+        assert!(keys.windows(2).all(|w| w[0] < w[1]));
+        assert!(snapshot.iter().flatten().all(|km| km.value.is_some()));
+    }
+
+    #[test]
+    fn changelog_of_compacted_store_can_be_stored_in_another_store() {
+        let tempdir = TempDir::new().unwrap();
+        let store = LocalStoreImpl::new(tempdir.path());
+        let mut rng = rand::thread_rng();
+        let changelog = get_random_changelog(20, &mut rng);
+        changelog.iter().enumerate().for_each(|(i, c)| {
+            store
+                .store(RegistryVersion::from((i + 1) as u64), c.clone())
+                .unwrap()
+        });
+        store.compact(RegistryVersion::from(10)).unwrap();
+        let compacted_changelog = store
+            .get_changelog_since_version(RegistryVersion::from(0))
+            .unwrap();
+
+        let other_tempdir = TempDir::new().unwrap();
+        let other_store = LocalStoreImpl::new(other_tempdir.path());
+        other_store
+            .store_compacted_snapshot(RegistryVersion::from(10), compacted_changelog[9].clone())
+            .unwrap();
+        for (i, c) in compacted_changelog.iter().enumerate().skip(10) {
+            other_store
+                .store(RegistryVersion::from((i + 1) as u64), c.clone())
+                .unwrap();
+        }
+
+        assert_eq!(
+            other_store
+                .get_changelog_since_version(RegistryVersion::from(0))
+                .unwrap(),
+            compacted_changelog
+        );
+        // Only an empty store can receive a compacted snapshot.
+        assert!(other_store
+            .store_compacted_snapshot(RegistryVersion::from(20), vec![])
+            .is_err());
+    }
+
+    #[test]
+    fn empty_changelog_after_clearing_compacted_store() {
+        let tempdir = TempDir::new().unwrap();
+        let store = LocalStoreImpl::new(tempdir.path());
+        let mut rng = rand::thread_rng();
+        let changelog = get_random_changelog(10, &mut rng);
+        changelog.iter().enumerate().for_each(|(i, c)| {
+            store
+                .store(RegistryVersion::from((i + 1) as u64), c.clone())
+                .unwrap()
+        });
+        store.compact(RegistryVersion::from(5)).unwrap();
+
+        store.clear().unwrap();
+
+        assert!(store
+            .get_changelog_since_version(RegistryVersion::from(0))
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            store.get_compacted_version().unwrap(),
+            RegistryVersion::from(0)
+        );
+        store
+            .store(RegistryVersion::from(1), changelog[0].clone())
+            .unwrap();
+    }
+
     #[test]
     fn can_store_and_read_certified_time() {
         use std::time::{SystemTime, UNIX_EPOCH};
@@ -472,6 +916,62 @@ mod tests {
         assert_eq!(expected_time, actual_time);
     }
 
+    #[test]
+    fn key_history_index_matches_changelog() {
+        let tempdir = TempDir::new().unwrap();
+        let store = LocalStoreImpl::new(tempdir.path());
+        let mut rng = rand::thread_rng();
+        let changelog = get_random_changelog(10, &mut rng);
+
+        for (i, ce) in changelog.iter().take(5).enumerate() {
+            store
+                .store(RegistryVersion::from((i + 1) as u64), ce.clone())
+                .unwrap();
+        }
+        let mut index = KeyHistoryIndex::build(&store).unwrap();
+        assert_eq!(index.latest_version(), RegistryVersion::from(5));
+
+        for (i, ce) in changelog.iter().enumerate().skip(5) {
+            store
+                .store(RegistryVersion::from((i + 1) as u64), ce.clone())
+                .unwrap();
+        }
+        index.update(&store).unwrap();
+        assert_eq!(index.latest_version(), RegistryVersion::from(10));
+
+        let from = RegistryVersion::from(3);
+        let to = RegistryVersion::from(7);
+        let expected_history: Vec<_> = changelog
+            .iter()
+            .enumerate()
+            .map(|(i, ce)| (RegistryVersion::from((i + 1) as u64), ce))
+            .filter(|(v, _)| from <= *v && *v <= to)
+            .filter_map(|(version, ce)| {
+                let km = ce.iter().find(|km| km.key == "2")?;
+                Some(HistoryEntry {
+                    version,
+                    value: km.value.clone(),
+                })
+            })
+            .collect();
+        assert_eq!(index.key_history("2", from..=to), expected_history);
+
+        let mut expected_snapshot = BTreeMap::new();
+        for (i, ce) in changelog.iter().enumerate() {
+            for km in ce {
+                match &km.value {
+                    Some(value) => expected_snapshot.insert(km.key.as_str(), value.as_slice()),
+                    None => expected_snapshot.remove(km.key.as_str()),
+                };
+            }
+            assert_eq!(
+                index.snapshot(RegistryVersion::from((i + 1) as u64)),
+                expected_snapshot
+            );
+        }
+        assert!(index.snapshot(RegistryVersion::from(0)).is_empty());
+    }
+
     fn get_random_changelog(n: usize, rng: &mut ThreadRng) -> Changelog {
         // some pseudo random entries
         (0..n)
@@ -15,7 +15,7 @@ use tree_deserializer::{types::Leb128EncodedU64, LabeledTreeDeserializer};
 mod tests;
 
 /// Describes an error occurred during parsing and validation of the result of a
-/// "get_certified_changes_since" method call.
+/// "get_certified_changes_since" or "get_value_history" method call.
 #[derive(Debug)]
 pub enum CertificationError {
     /// Failed to deserialize some part of the response.
@@ -104,12 +104,10 @@ fn validate_version_range(
     Ok(p.current_version.0)
 }
 
-/// Decodes registry deltas from their hash tree representation.
-pub fn decode_hash_tree(
-    since_version: u64,
+/// Extracts the structured certified payload from its tree representation.
+fn decode_certified_payload(
     hash_tree: MixedHashTree,
-) -> Result<(Vec<RegistryTransportRecord>, RegistryVersion), CertificationError> {
-    // Extract structured deltas from their tree representation.
+) -> Result<CertifiedPayload, CertificationError> {
     let labeled_tree = LabeledTree::<Vec<u8>>::try_from(hash_tree).map_err(|err| {
         CertificationError::MalformedHashTree(format!(
             "failed to convert hash tree to labeled tree: {:?}",
@@ -127,44 +125,92 @@ pub fn decode_hash_tree(
         ))
     })?;
 
+    Ok(certified_payload)
+}
+
+/// Converts the deltas of a certified payload to the format that
+/// RegistryClient wants.
+fn deltas_to_transport_records(
+    delta: BTreeMap<u64, Protobuf<RegistryAtomicMutateRequest>>,
+) -> impl Iterator<Item = RegistryTransportRecord> {
+    delta.into_iter().flat_map(|(v, mutate_req)| {
+        mutate_req.0.mutations.into_iter().map(move |m| {
+            let value = if m.mutation_type == Type::Delete as i32 {
+                None
+            } else {
+                Some(m.value)
+            };
+            RegistryTransportRecord {
+                key: String::from_utf8_lossy(&m.key[..]).to_string(),
+                value,
+                version: RegistryVersion::from(v),
+            }
+        })
+    })
+}
+
+/// Decodes registry deltas from their hash tree representation.
+pub fn decode_hash_tree(
+    since_version: u64,
+    hash_tree: MixedHashTree,
+) -> Result<(Vec<RegistryTransportRecord>, RegistryVersion), CertificationError> {
+    let certified_payload = decode_certified_payload(hash_tree)?;
+
     // Validate that the deltas form a proper range and convert them to the
     // format that RegistryClient wants.
     let current_version = validate_version_range(since_version, &certified_payload)?;
 
-    let changes = certified_payload
-        .delta
-        .into_iter()
-        .flat_map(|(v, mutate_req)| {
-            mutate_req.0.mutations.into_iter().map(move |m| {
-                let value = if m.mutation_type == Type::Delete as i32 {
-                    None
-                } else {
-                    Some(m.value)
-                };
-                RegistryTransportRecord {
-                    key: String::from_utf8_lossy(&m.key[..]).to_string(),
-                    value,
-                    version: RegistryVersion::from(v),
-                }
-            })
-        })
-        .collect();
+    let changes = deltas_to_transport_records(certified_payload.delta).collect();
 
     Ok((changes, RegistryVersion::from(current_version)))
 }
 
-/// Parses a response of the "get_certified_changes_since" registry method,
-/// validates data integrity and authenticity and returns
-///   * The list of changes to apply.
-///   * The latest version available (might be greater than the version of the
-///     last received delta if there were too many deltas to send in one go).
-///   * The time when the received data was last certified by the subnet.
-pub fn decode_certified_deltas(
-    since_version: u64,
+/// Decodes the history of `key` from the hash tree returned by the
+/// "get_value_history" registry method.
+///
+/// Validates that every revealed delta lies in `[from_version, to_version]`
+/// (where `None` stands for the current version) and mutates `key`. Note
+/// that the hash tree cannot prove that no delta mutating `key` was omitted.
+pub fn decode_value_history_hash_tree(
+    key: &[u8],
+    from_version: u64,
+    to_version: Option<u64>,
+    hash_tree: MixedHashTree,
+) -> Result<(Vec<RegistryTransportRecord>, RegistryVersion), CertificationError> {
+    let certified_payload = decode_certified_payload(hash_tree)?;
+    let current_version = certified_payload.current_version.0;
+    let to_version = to_version.map_or(current_version, |v| v.min(current_version));
+
+    for (version, mutate_req) in certified_payload.delta.iter() {
+        if *version < from_version || *version > to_version {
+            return Err(CertificationError::InvalidDeltas(format!(
+                "delta at version {} is outside of the requested range [{}, {}]",
+                version, from_version, to_version
+            )));
+        }
+        if !mutate_req.0.mutations.iter().any(|m| m.key == key) {
+            return Err(CertificationError::InvalidDeltas(format!(
+                "delta at version {} does not mutate the requested key",
+                version
+            )));
+        }
+    }
+
+    let history = deltas_to_transport_records(certified_payload.delta)
+        .filter(|record| record.key.as_bytes() == key)
+        .collect();
+
+    Ok((history, RegistryVersion::from(current_version)))
+}
+
+/// Decodes a certified response of the registry canister and verifies the
+/// authenticity of its hash tree, returning the tree together with the time
+/// when it was certified by the subnet.
+fn verify_certified_response(
     canister_id: &CanisterId,
     nns_pk: &ThresholdSigPublicKey,
     payload: &[u8],
-) -> Result<(Vec<RegistryTransportRecord>, RegistryVersion, Time), CertificationError> {
+) -> Result<(MixedHashTree, Time), CertificationError> {
     let certified_response = CertifiedResponse::decode(payload).map_err(|err| {
         CertificationError::DeserError(format!(
             "failed to decode certified response from {}: {:?}",
@@ -195,11 +241,51 @@ pub fn decode_certified_deltas(
     )
     .map_err(embed_certificate_error)?;
 
+    Ok((mixed_hash_tree, time))
+}
+
+/// Parses a response of the "get_certified_changes_since" registry method,
+/// validates data integrity and authenticity and returns
+///   * The list of changes to apply.
+///   * The latest version available (might be greater than the version of the
+///     last received delta if there were too many deltas to send in one go).
+///   * The time when the received data was last certified by the subnet.
+pub fn decode_certified_deltas(
+    since_version: u64,
+    canister_id: &CanisterId,
+    nns_pk: &ThresholdSigPublicKey,
+    payload: &[u8],
+) -> Result<(Vec<RegistryTransportRecord>, RegistryVersion, Time), CertificationError> {
+    let (mixed_hash_tree, time) = verify_certified_response(canister_id, nns_pk, payload)?;
+
     let (changes, current_version) = decode_hash_tree(since_version, mixed_hash_tree)?;
 
     Ok((changes, current_version, time))
 }
 
+/// Parses a response of the "get_value_history" registry method, validates
+/// data integrity and authenticity and returns
+///   * The values `key` took in the requested range, oldest first. The list
+///     might be a prefix of the full history if it was too long to send in one
+///     go.
+///   * The latest version available.
+///   * The time when the received data was last certified by the subnet.
+pub fn decode_certified_value_history(
+    key: &[u8],
+    from_version: u64,
+    to_version: Option<u64>,
+    canister_id: &CanisterId,
+    nns_pk: &ThresholdSigPublicKey,
+    payload: &[u8],
+) -> Result<(Vec<RegistryTransportRecord>, RegistryVersion, Time), CertificationError> {
+    let (mixed_hash_tree, time) = verify_certified_response(canister_id, nns_pk, payload)?;
+
+    let (history, current_version) =
+        decode_value_history_hash_tree(key, from_version, to_version, mixed_hash_tree)?;
+
+    Ok((history, current_version, time))
+}
+
 /// An auxiliary type that instructs serde to deserialize blob as a protobuf
 /// message.
 struct Protobuf<T>(T);
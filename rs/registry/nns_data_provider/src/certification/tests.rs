@@ -1,4 +1,4 @@
-use super::{decode_certified_deltas, CertificationError};
+use super::{decode_certified_deltas, decode_certified_value_history, CertificationError};
 use ic_certification_test_utils::{CertificateBuilder, CertificateData};
 use ic_crypto_tree_hash::{
     flatmap, Digest, FlatMap, HashTreeBuilder, HashTreeBuilderImpl, Label, LabeledTree,
@@ -39,6 +39,14 @@ fn make_certified_delta(
     deltas: Vec<RegistryAtomicMutateRequest>,
     selection: impl std::ops::RangeBounds<u64>,
     garble_response: GarbleResponse,
+) -> (CanisterId, ThresholdSigPublicKey, EncodedResponse) {
+    make_certified_sparse_delta(deltas, |v| selection.contains(&v), garble_response)
+}
+
+fn make_certified_sparse_delta(
+    deltas: Vec<RegistryAtomicMutateRequest>,
+    is_selected: impl Fn(u64) -> bool,
+    garble_response: GarbleResponse,
 ) -> (CanisterId, ThresholdSigPublicKey, EncodedResponse) {
     let cid = CanisterId::from_u64(1);
 
@@ -72,7 +80,7 @@ fn make_certified_delta(
             b.write_leaf(&buf[..]);
             b.finish_leaf();
 
-            if selection.contains(&version) && !garble_response.should_drop_version(version) {
+            if is_selected(version) && !garble_response.should_drop_version(version) {
                 map.try_append(label, LabeledTree::Leaf(buf)).unwrap();
             }
         }
@@ -310,3 +318,73 @@ fn test_decode_empty_prefix() {
         other => panic!("Expected InvalidDeltas error, got {:?}", other),
     }
 }
+
+#[test]
+fn test_decode_value_history() {
+    let (cid, pk, payload) = make_certified_sparse_delta(
+        vec![
+            make_change(vec![upsert("key1", "value1"), upsert("key2", "value2")]),
+            make_change(vec![upsert("key2", "value22")]),
+            make_change(vec![delete("key1")]),
+        ],
+        |v| v == 1 || v == 3,
+        GarbleResponse::LeaveAsIs,
+    );
+    assert_eq!(
+        decode_certified_value_history(b"key1", 1, None, &cid, &pk, &payload[..]).unwrap(),
+        (
+            vec![set_key(1, "key1", "value1"), rem_key(3, "key1")],
+            RegistryVersion::from(3u64),
+            Time::from_nanos_since_unix_epoch(REPLICA_TIME),
+        ),
+    )
+}
+
+#[test]
+fn test_decode_value_history_unrelated_delta() {
+    let (cid, pk, payload) = make_certified_sparse_delta(
+        vec![
+            make_change(vec![upsert("key1", "value1")]),
+            make_change(vec![upsert("key2", "value2")]),
+        ],
+        |_| true,
+        GarbleResponse::LeaveAsIs,
+    );
+    match decode_certified_value_history(b"key1", 1, None, &cid, &pk, &payload[..]) {
+        Err(CertificationError::InvalidDeltas(_)) => (),
+        other => panic!("Expected InvalidDeltas error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_decode_value_history_out_of_range() {
+    let (cid, pk, payload) = make_certified_sparse_delta(
+        vec![
+            make_change(vec![upsert("key1", "value1")]),
+            make_change(vec![upsert("key1", "value2")]),
+        ],
+        |_| true,
+        GarbleResponse::LeaveAsIs,
+    );
+    match decode_certified_value_history(b"key1", 2, None, &cid, &pk, &payload[..]) {
+        Err(CertificationError::InvalidDeltas(_)) => (),
+        other => panic!("Expected InvalidDeltas error, got {:?}", other),
+    }
+    match decode_certified_value_history(b"key1", 1, Some(1), &cid, &pk, &payload[..]) {
+        Err(CertificationError::InvalidDeltas(_)) => (),
+        other => panic!("Expected InvalidDeltas error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_decode_value_history_bad_sig() {
+    let (cid, pk, payload) = make_certified_sparse_delta(
+        vec![make_change(vec![upsert("key1", "value1")])],
+        |_| true,
+        GarbleResponse::OverrideSignature(CombinedThresholdSig(vec![0u8; 32])),
+    );
+    match decode_certified_value_history(b"key1", 1, None, &cid, &pk, &payload[..]) {
+        Err(CertificationError::InvalidSignature(_)) => (),
+        other => panic!("Expected InvalidSignature error, got {:?}", other),
+    }
+}
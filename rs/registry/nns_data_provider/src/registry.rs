@@ -7,12 +7,14 @@ use url::Url;
 use ic_canister_client::{Agent, Sender};
 use ic_interfaces_registry::RegistryTransportRecord;
 use ic_registry_transport::{
-    deserialize_atomic_mutate_response, deserialize_get_changes_since_response,
-    deserialize_get_value_response, serialize_atomic_mutate_request,
-    serialize_get_changes_since_request, serialize_get_value_request,
+    deserialize_atomic_mutate_response, deserialize_dry_run_atomic_mutate_response,
+    deserialize_get_changes_since_response, deserialize_get_value_response,
+    serialize_atomic_mutate_request, serialize_dry_run_proposal_request,
+    serialize_get_changes_since_request, serialize_get_value_history_request,
+    serialize_get_value_request,
 };
 use ic_registry_transport::{
-    pb::v1::{Precondition, RegistryDelta, RegistryMutation},
+    pb::v1::{Precondition, RegistryDelta, RegistryDryRunAtomicMutateResponse, RegistryMutation},
     Error,
 };
 use ic_types::{crypto::threshold_sig::ThresholdSigPublicKey, CanisterId, RegistryVersion, Time};
@@ -140,6 +142,51 @@ impl RegistryCanister {
         .map_err(|err| Error::UnknownError(format!("{:?}", err)))
     }
 
+    /// Queries the registry for the values `key` took in versions
+    /// `[from_version, to_version]` using a certified endpoint. A `to_version`
+    /// of `None` stands for the latest version.
+    ///
+    /// The records returned by this function are sorted by version. If the
+    /// history is too long to be returned in one go, only a prefix of it is
+    /// returned; the remainder can be fetched starting from the version
+    /// following the last returned record.
+    pub async fn get_certified_value_history(
+        &self,
+        key: Vec<u8>,
+        from_version: u64,
+        to_version: Option<u64>,
+        nns_public_key: &ThresholdSigPublicKey,
+    ) -> Result<(Vec<RegistryTransportRecord>, RegistryVersion, Time), Error> {
+        let payload =
+            serialize_get_value_history_request(key.clone(), from_version, to_version).unwrap();
+        let response = self
+            .choose_random_agent()
+            .execute_query(&self.canister_id, "get_value_history", payload)
+            .await
+            .map_err(|err| {
+                Error::UnknownError(format!(
+                    "Failed to query get_value_history on canister {}: {}",
+                    self.canister_id, err,
+                ))
+            })?
+            .ok_or_else(|| {
+                Error::UnknownError(format!(
+                    "No response was received when queried get_value_history on {}",
+                    self.canister_id,
+                ))
+            })?;
+
+        crate::certification::decode_certified_value_history(
+            &key,
+            from_version,
+            to_version,
+            &self.canister_id,
+            nns_public_key,
+            &response[..],
+        )
+        .map_err(|err| Error::UnknownError(format!("{:?}", err)))
+    }
+
     pub async fn get_latest_version(&self) -> Result<u64, Error> {
         let agent = self.choose_random_agent();
         match agent
@@ -225,6 +272,58 @@ impl RegistryCanister {
             ))]),
         }
     }
+
+    /// Checks 'mutations' against the registry invariants, without applying
+    /// them.
+    pub async fn dry_run_atomic_mutate(
+        &self,
+        mutations: Vec<RegistryMutation>,
+    ) -> Result<RegistryDryRunAtomicMutateResponse, Error> {
+        let payload = serialize_atomic_mutate_request(mutations, vec![]);
+        let agent = self.choose_random_agent();
+        match agent
+            .execute_query(&self.canister_id, "dry_run_atomic_mutate", payload)
+            .await
+        {
+            Ok(result) => match result {
+                Some(response) => deserialize_dry_run_atomic_mutate_response(response),
+                None => Err(ic_registry_transport::Error::UnknownError(
+                    "No response was received from registry_dry_run_atomic_mutate.".to_string(),
+                )),
+            },
+            Err(error_string) => Err(ic_registry_transport::Error::UnknownError(format!(
+                "Error on registry_dry_run_atomic_mutate: {} using agent {:?}",
+                error_string, &agent
+            ))),
+        }
+    }
+
+    /// Computes the mutations that executing the proposal method
+    /// 'method_name' with the candid-encoded 'payload' would make, and checks
+    /// them against the registry invariants, without applying them.
+    pub async fn dry_run_proposal(
+        &self,
+        method_name: &str,
+        payload: Vec<u8>,
+    ) -> Result<RegistryDryRunAtomicMutateResponse, Error> {
+        let payload = serialize_dry_run_proposal_request(method_name, payload);
+        let agent = self.choose_random_agent();
+        match agent
+            .execute_query(&self.canister_id, "dry_run_proposal", payload)
+            .await
+        {
+            Ok(result) => match result {
+                Some(response) => deserialize_dry_run_atomic_mutate_response(response),
+                None => Err(ic_registry_transport::Error::UnknownError(
+                    "No response was received from registry_dry_run_proposal.".to_string(),
+                )),
+            },
+            Err(error_string) => Err(ic_registry_transport::Error::UnknownError(format!(
+                "Error on registry_dry_run_proposal: {} using agent {:?}",
+                error_string, &agent
+            ))),
+        }
+    }
 }
 
 /// Convert `Vec<RegistryDelta>` to `Vec<RegistryTransportRecord>`.
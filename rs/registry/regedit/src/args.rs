@@ -67,6 +67,23 @@ pub enum CommandArg {
         #[clap(parse(from_os_str))]
         snapshot_file: PathBuf,
     },
+    History {
+        /// The key whose history should be shown.
+        key: String,
+
+        /// The first registry version to include. (default: 1)
+        #[clap(long)]
+        from_version: Option<u64>,
+
+        /// The last registry version to include. (default: latest available
+        /// version.)
+        #[clap(long)]
+        to_version: Option<u64>,
+
+        /// Path to the local store.
+        #[clap(parse(from_os_str))]
+        local_store_path: PathBuf,
+    },
     CanisterSnapshot {
         /// Url to a node hosting the registry canister (may not be specified
         /// together with --local-store).
@@ -160,6 +177,17 @@ impl CliArgs {
                     amend,
                 }
             }
+            CommandArg::History {
+                key,
+                from_version,
+                to_version,
+                local_store_path,
+            } => Command::History {
+                local_store_path: Self::is_dir(local_store_path)?,
+                key,
+                from_version: RegistryVersion::from(from_version.unwrap_or(1)),
+                to_version: to_version.map(RegistryVersion::from),
+            },
             CommandArg::CanisterSnapshot {
                 url,
                 nns_public_key,
@@ -281,6 +309,12 @@ pub enum Command {
         snapshot: Value,
         amend: bool,
     },
+    History {
+        local_store_path: PathBuf,
+        key: String,
+        from_version: RegistryVersion,
+        to_version: Option<RegistryVersion>,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
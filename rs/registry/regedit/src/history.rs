@@ -0,0 +1,28 @@
+use crate::{diff::DELETED_MARKER, json, protobuf::raw_data_to_value};
+use ic_registry_local_store::HistoryEntry;
+use serde_json::{Map, Value};
+
+pub const VERSION_FIELD: &str = "version";
+pub const VALUE_FIELD: &str = "value";
+
+/// Renders the history of `key` as a list of objects, oldest first. Deletions
+/// are rendered as the deleted marker used in diffs.
+pub fn history_to_value(key: &str, history: Vec<HistoryEntry>) -> Value {
+    let entries = history
+        .into_iter()
+        .map(|entry| {
+            let mut obj = Map::new();
+            obj.insert(
+                VERSION_FIELD.to_string(),
+                json::assert_to_value(entry.version.get()),
+            );
+            let value = match entry.value {
+                Some(value) => raw_data_to_value(key, &value),
+                None => json::assert_to_value(DELETED_MARKER),
+            };
+            obj.insert(VALUE_FIELD.to_string(), value);
+            Value::Object(obj)
+        })
+        .collect();
+    Value::Array(entries)
+}
@@ -1,5 +1,6 @@
 pub mod args;
 mod diff;
+mod history;
 mod json;
 mod normalization;
 mod projection;
@@ -11,7 +12,7 @@ mod tests;
 use anyhow::Result;
 use args::{universal_projection, Command, RegistrySpec, SourceSpec, VersionSpec};
 use ic_base_types::RegistryVersion;
-use ic_registry_local_store::{LocalStoreImpl, LocalStoreWriter};
+use ic_registry_local_store::{KeyHistoryIndex, LocalStoreImpl, LocalStoreWriter};
 use normalization::NormalizedSnapshot;
 use serde_json::Value;
 use snapshot::Snapshot;
@@ -67,6 +68,19 @@ pub fn execute_command(cmd: Command) -> Result<Value> {
             local_store.store(v, changelog_entry)?;
             diff.0
         }
+        Command::History {
+            local_store_path,
+            key,
+            from_version,
+            to_version,
+        } => {
+            let index = KeyHistoryIndex::build(&LocalStoreImpl::new(local_store_path))?;
+            let to_version = to_version.unwrap_or_else(|| index.latest_version());
+            let history = index.key_history(&key, from_version..=to_version);
+            let (normalized_history, _) =
+                normalization::normalize(history::history_to_value(&key, history));
+            normalized_history.0
+        }
     };
     Ok(res)
 }
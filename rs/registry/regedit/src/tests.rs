@@ -5,6 +5,7 @@ use crate::{
     execute_command, normalization,
     snapshot::SPECIAL_FIELD_PREFIX,
 };
+use ic_base_types::RegistryVersion;
 use ic_prep_lib::{
     internet_computer::{IcConfig, TopologyConfig},
     node::{NodeConfiguration, NodeIndex},
@@ -108,6 +109,53 @@ fn adding_deleting_values_shows_up_in_diff() {
     assert_eq!(expected_snapshot.0, final_snapshot);
 }
 
+#[test]
+fn deleting_value_shows_up_in_history() {
+    let (_guard, ic_prep_dir) = run_ic_prep();
+    let local_store_path = ic_prep_dir.registry_local_store_path();
+    let mut snapshot = execute_command(Command::Snapshot {
+        registry_spec: local_store_latest_snapshot(local_store_path.clone()),
+        projection: universal_projection(),
+    })
+    .unwrap();
+
+    let obj = snapshot.as_object_mut().unwrap();
+    let removed_key = obj.keys().rev().next().unwrap().clone();
+    assert!(obj.remove(&removed_key).is_some());
+
+    execute_command(Command::ApplyUpdate {
+        local_store_path: local_store_path.clone(),
+        snapshot,
+        amend: false,
+    })
+    .unwrap();
+
+    // Returns the (version, is_deleted) pairs of the history of `removed_key`.
+    let history = |from_version: u64, to_version: Option<u64>| {
+        let out = execute_command(Command::History {
+            local_store_path: local_store_path.clone(),
+            key: removed_key.clone(),
+            from_version: RegistryVersion::from(from_version),
+            to_version: to_version.map(RegistryVersion::from),
+        })
+        .unwrap();
+        out.as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| {
+                (
+                    entry["version"].as_u64().unwrap(),
+                    entry["value"] == DELETED_MARKER,
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(history(1, None), vec![(1, false), (2, true)]);
+    assert_eq!(history(1, Some(1)), vec![(1, false)]);
+    assert_eq!(history(2, None), vec![(2, true)]);
+}
+
 pub fn local_store_latest_snapshot(path: PathBuf) -> RegistrySpec {
     let source = SourceSpec::LocalStore(path);
     let version = VersionSpec::RelativeToLatest(0);
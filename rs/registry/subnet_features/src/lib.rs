@@ -1,10 +1,14 @@
 use candid::CandidType;
-use ic_ic00_types::{BitcoinNetwork, EcdsaKeyId};
-use ic_protobuf::{proxy::ProxyDecodeError, registry::subnet::v1 as pb};
+use ic_ic00_types::{BitcoinNetwork, EcdsaKeyId, VetKdKeyId};
+use ic_protobuf::{
+    proxy::{try_from_option_field, ProxyDecodeError},
+    registry::subnet::v1 as pb,
+};
 use serde::{Deserialize, Serialize};
 use std::{convert::TryFrom, str::FromStr};
 
 pub const DEFAULT_ECDSA_MAX_QUEUE_SIZE: u32 = 20;
+pub const DEFAULT_VETKD_MAX_QUEUE_SIZE: u32 = 20;
 
 /// List of features that can be enabled or disabled on the given subnet.
 #[derive(CandidType, Clone, Copy, Default, Deserialize, Debug, Eq, PartialEq, Serialize)]
@@ -269,6 +273,37 @@ impl TryFrom<pb::EcdsaConfig> for EcdsaConfig {
     }
 }
 
+/// The vetKD key held by a subnet. The key is derived from the subnet's
+/// NI-DKG transcript with the `VetKd` tag.
+#[derive(CandidType, Clone, Deserialize, Debug, Eq, PartialEq, Serialize)]
+pub struct VetKdConfig {
+    pub key_id: VetKdKeyId,
+    pub max_queue_size: Option<u32>,
+    pub request_timeout_ns: Option<u64>,
+}
+
+impl From<VetKdConfig> for pb::VetKdConfig {
+    fn from(item: VetKdConfig) -> Self {
+        pb::VetKdConfig {
+            key_id: Some((&item.key_id).into()),
+            max_queue_size: item.max_queue_size.unwrap_or(DEFAULT_VETKD_MAX_QUEUE_SIZE),
+            request_timeout_ns: item.request_timeout_ns,
+        }
+    }
+}
+
+impl TryFrom<pb::VetKdConfig> for VetKdConfig {
+    type Error = ProxyDecodeError;
+
+    fn try_from(value: pb::VetKdConfig) -> Result<Self, Self::Error> {
+        Ok(VetKdConfig {
+            key_id: try_from_option_field(value.key_id, "VetKdConfig::key_id")?,
+            max_queue_size: Some(value.max_queue_size),
+            request_timeout_ns: value.request_timeout_ns,
+        })
+    }
+}
+
 #[derive(CandidType, Clone, Copy, Deserialize, Debug, Eq, PartialEq, Serialize)]
 pub enum SevFeatureStatus {
     Disabled,
@@ -390,6 +425,27 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_vetkd_config_round_trip() {
+        let config = VetKdConfig {
+            key_id: VetKdKeyId {
+                curve: ic_ic00_types::VetKdCurve::Bls12_381_G2,
+                name: "some_key".to_string(),
+            },
+            max_queue_size: Some(10),
+            request_timeout_ns: Some(1_000),
+        };
+        assert_eq!(
+            VetKdConfig::try_from(pb::VetKdConfig::from(config.clone())).unwrap(),
+            config
+        );
+    }
+
+    #[test]
+    fn test_vetkd_config_without_key_id_is_rejected() {
+        assert!(VetKdConfig::try_from(pb::VetKdConfig::default()).is_err());
+    }
 }
 
 #[test]
@@ -134,6 +134,27 @@ pub struct RegistryGetValueResponse {
     #[prost(bytes = "vec", tag = "3")]
     pub value: ::prost::alloc::vec::Vec<u8>,
 }
+/// Message to retrieve all the values a registry key took in a range of
+/// versions from the registry canister.
+///
+/// The response to a get_value_history() request is a CertifiedResponse whose
+/// hash tree reveals exactly those changelog entries in the range that mutate
+/// the key.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RegistryGetValueHistoryRequest {
+    /// The key whose history to retrieve.
+    /// Required.
+    #[prost(bytes = "vec", tag = "1")]
+    pub key: ::prost::alloc::vec::Vec<u8>,
+    /// The first version of the range (inclusive).
+    #[prost(uint64, tag = "2")]
+    pub from_version: u64,
+    /// The last version of the range (inclusive).
+    /// Optional: If not set (or set to the default value, 0), the range extends
+    /// to the latest version.
+    #[prost(uint64, tag = "3")]
+    pub to_version: u64,
+}
 /// Message corresponding to the response from the canister
 /// to a get_latest_version() request.
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -230,6 +251,47 @@ pub struct RegistryAtomicMutateResponse {
     #[prost(uint64, tag = "2")]
     pub version: u64,
 }
+/// The outcome of a single registry invariant check.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RegistryInvariantCheckResult {
+    /// The name of the check, e.g. "routing_table".
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    /// Whether the invariant holds after applying the mutations.
+    #[prost(bool, tag = "2")]
+    pub passed: bool,
+    /// Why the invariant does not hold. Empty if the check passed.
+    #[prost(string, tag = "3")]
+    pub error: ::prost::alloc::string::String,
+}
+/// Message corresponding to a dry_run_proposal request: the registry canister
+/// method that an adopted proposal would call, and its candid-encoded payload.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RegistryDryRunProposalRequest {
+    #[prost(string, tag = "1")]
+    pub method_name: ::prost::alloc::string::String,
+    #[prost(bytes = "vec", tag = "2")]
+    pub payload: ::prost::alloc::vec::Vec<u8>,
+}
+/// Message corresponding to the response of a dry_run_atomic_mutate request.
+/// The mutations are checked against the latest version of the registry, but
+/// never applied.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RegistryDryRunAtomicMutateResponse {
+    /// Errors for mutations whose type does not match the current content of the
+    /// registry, e.g. an insert of a key that is already present.
+    #[prost(message, repeated, tag = "1")]
+    pub errors: ::prost::alloc::vec::Vec<RegistryError>,
+    /// The registry version that the mutations were checked against.
+    #[prost(uint64, tag = "2")]
+    pub version: u64,
+    /// The outcome of every invariant check, in the order they were run.
+    #[prost(message, repeated, tag = "3")]
+    pub invariant_checks: ::prost::alloc::vec::Vec<RegistryInvariantCheckResult>,
+    /// The mutations that were checked.
+    #[prost(message, repeated, tag = "4")]
+    pub mutations: ::prost::alloc::vec::Vec<RegistryMutation>,
+}
 /// Message encoding a response to any *_certified method call.
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CertifiedResponse {
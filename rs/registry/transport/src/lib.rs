@@ -245,6 +245,26 @@ pub fn deserialize_get_changes_since_response(
     }
 }
 
+/// Serializes the arguments for a request to the get_value_history() function
+/// in the registry canister, into protobuf. A `to_version` of `None` stands
+/// for the latest version.
+pub fn serialize_get_value_history_request(
+    key: Vec<u8>,
+    from_version: u64,
+    to_version: Option<u64>,
+) -> Result<Vec<u8>, Error> {
+    let request = pb::v1::RegistryGetValueHistoryRequest {
+        key,
+        from_version,
+        to_version: to_version.unwrap_or(0),
+    };
+    let mut buf = Vec::new();
+    match request.encode(&mut buf) {
+        Ok(_) => Ok(buf),
+        Err(error) => Err(Error::MalformedMessage(error.to_string())),
+    }
+}
+
 /// Serializes the arguments for a request to the insert() function in the
 /// registry canister, into protobuf.
 pub fn serialize_atomic_mutate_request(
@@ -312,6 +332,44 @@ pub fn deserialize_atomic_mutate_response(response: Vec<u8>) -> Result<u64, Vec<
     Ok(response.version)
 }
 
+/// Serializes a dry_run_proposal() request to the registry canister.
+pub fn serialize_dry_run_proposal_request(method_name: &str, payload: Vec<u8>) -> Vec<u8> {
+    pb::v1::RegistryDryRunProposalRequest {
+        method_name: method_name.to_string(),
+        payload,
+    }
+    .encode_to_vec()
+}
+
+/// Deserializes a dry_run_proposal() request to the registry canister.
+pub fn deserialize_dry_run_proposal_request(
+    request: Vec<u8>,
+) -> Result<pb::v1::RegistryDryRunProposalRequest, Error> {
+    pb::v1::RegistryDryRunProposalRequest::decode(&request[..])
+        .map_err(|error| Error::MalformedMessage(error.to_string()))
+}
+
+/// Serializes a response for a dry_run_atomic_mutate() or dry_run_proposal()
+/// request to the registry canister.
+pub fn serialize_dry_run_atomic_mutate_response(
+    response: pb::v1::RegistryDryRunAtomicMutateResponse,
+) -> Result<Vec<u8>, Error> {
+    let mut buf = Vec::new();
+    match response.encode(&mut buf) {
+        Ok(_) => Ok(buf),
+        Err(error) => Err(Error::MalformedMessage(error.to_string())),
+    }
+}
+
+/// Deserializes the response obtained from the registry canister for a
+/// dry_run_atomic_mutate() or dry_run_proposal() call from protobuf.
+pub fn deserialize_dry_run_atomic_mutate_response(
+    response: Vec<u8>,
+) -> Result<pb::v1::RegistryDryRunAtomicMutateResponse, Error> {
+    pb::v1::RegistryDryRunAtomicMutateResponse::decode(&response[..])
+        .map_err(|error| Error::MalformedMessage(error.to_string()))
+}
+
 fn mutation(
     mutation_type: Type,
     key: impl AsRef<[u8]>,
@@ -6,7 +6,7 @@ use ic_consensus::{
     certification::CertificationCrypto,
     consensus::{
         dkg_key_manager::DkgKeyManager, pool_reader::PoolReader, utils, validator::Validator,
-        ConsensusCrypto, Membership, ValidatorMetrics,
+        AdaptiveDelay, ConsensusCrypto, Membership, ValidatorMetrics,
     },
 };
 use ic_interfaces::{
@@ -129,6 +129,7 @@ impl ReplayValidator {
             log.clone(),
             ValidatorMetrics::new(metrics_registry.clone()),
             time_source.clone(),
+            Arc::new(AdaptiveDelay::new(metrics_registry.clone())),
         );
 
         Self {
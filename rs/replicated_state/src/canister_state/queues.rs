@@ -652,6 +652,23 @@ impl CanisterQueues {
             .collect()
     }
 
+    /// Returns the byte size of the messages enqueued in each non-empty output
+    /// queue, by receiver.
+    ///
+    /// Time complexity: O(num_messages).
+    pub fn output_queues_size_bytes_by_receiver(&self) -> BTreeMap<CanisterId, usize> {
+        self.canister_queues
+            .iter()
+            .filter(|(_, (_, output_queue))| output_queue.num_messages() > 0)
+            .map(|(canister, (_, output_queue))| {
+                (
+                    *canister,
+                    output_queue.calculate_stat_sum(|msg| msg.count_bytes()),
+                )
+            })
+            .collect()
+    }
+
     /// Pushes a `Response` type message into the relevant output queue. The
     /// protocol should have already reserved a slot, so this cannot fail.
     ///
@@ -199,6 +199,35 @@ fn test_available_output_request_slots_dont_counts() {
     );
 }
 
+/// Check `output_queues_size_bytes_by_receiver` sums up the byte sizes of
+/// output messages and skips empty queues.
+#[test]
+fn test_output_queues_size_bytes_by_receiver() {
+    let mut queues = CanisterQueuesFixture::new();
+    // A reservation in the output queue, but no messages.
+    queues.push_input_request().unwrap();
+    assert_eq!(
+        btreemap! {},
+        queues.queues.output_queues_size_bytes_by_receiver()
+    );
+
+    queues.pop_input().unwrap();
+    queues.push_output_response();
+    queues.push_output_request().unwrap();
+    let expected_size_bytes = queues
+        .queues
+        .output_queue_iter_for_testing(&queues.other)
+        .unwrap()
+        .flatten()
+        .map(|msg| msg.count_bytes())
+        .sum::<usize>();
+    assert!(expected_size_bytes > 0);
+    assert_eq!(
+        btreemap! {queues.other => expected_size_bytes},
+        queues.queues.output_queues_size_bytes_by_receiver()
+    );
+}
+
 /// Check `available_output_request_slots` counts output requests and input
 /// reservations and responses.
 #[test]
@@ -49,6 +49,7 @@ pub enum CyclesUseCase {
     HTTPOutcalls,
     DeletedCanisters,
     NonConsumed,
+    VetKd,
 }
 
 impl CyclesUseCase {
@@ -67,6 +68,7 @@ impl CyclesUseCase {
             Self::HTTPOutcalls => "HTTPOutcalls",
             Self::DeletedCanisters => "DeletedCanisters",
             Self::NonConsumed => "NonConsumed",
+            Self::VetKd => "VetKd",
         }
     }
 }
@@ -85,6 +87,7 @@ impl From<CyclesUseCase> for i32 {
             CyclesUseCase::HTTPOutcalls => 9,
             CyclesUseCase::DeletedCanisters => 10,
             CyclesUseCase::NonConsumed => 11,
+            CyclesUseCase::VetKd => 12,
         }
     }
 }
@@ -103,6 +106,7 @@ impl From<i32> for CyclesUseCase {
             9 => Self::HTTPOutcalls,
             10 => Self::DeletedCanisters,
             11 => Self::NonConsumed,
+            12 => Self::VetKd,
             _ => panic!("Unsupported value"),
         }
     }
@@ -1102,11 +1106,12 @@ impl SystemState {
         use_case: CyclesUseCase,
         consuming_cycles: ConsumingCycles,
     ) {
-        // The three CyclesUseCase below are not valid on the canister
+        // The four CyclesUseCase below are not valid on the canister
         // level, they should only appear on the subnet level.
         debug_assert_ne!(use_case, CyclesUseCase::ECDSAOutcalls);
         debug_assert_ne!(use_case, CyclesUseCase::HTTPOutcalls);
         debug_assert_ne!(use_case, CyclesUseCase::DeletedCanisters);
+        debug_assert_ne!(use_case, CyclesUseCase::VetKd);
 
         if use_case == CyclesUseCase::NonConsumed || amount == Cycles::from(0u128) {
             return;
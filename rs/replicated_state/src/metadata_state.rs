@@ -11,7 +11,7 @@ use ic_btc_types::Network as BitcoinNetwork;
 use ic_btc_types_internal::BlockBlob;
 use ic_certification_version::{CertificationVersion, CURRENT_CERTIFICATION_VERSION};
 use ic_constants::MAX_INGRESS_TTL;
-use ic_ic00_types::EcdsaKeyId;
+use ic_ic00_types::{EcdsaKeyId, VetKdKeyId};
 use ic_protobuf::{
     proxy::{try_from_option_field, ProxyDecodeError},
     registry::subnet::v1 as pb_subnet,
@@ -336,6 +336,8 @@ pub struct SubnetTopology {
     /// a backup. An additional NNS proposal will be needed to allow the subnet
     /// holding the key as backup to actually produce signatures.
     pub ecdsa_keys_held: BTreeSet<EcdsaKeyId>,
+    /// vetKD keys held by this subnet.
+    pub vetkd_keys_held: BTreeSet<VetKdKeyId>,
 }
 
 impl From<&SubnetTopology> for pb_metadata::SubnetTopology {
@@ -353,6 +355,7 @@ impl From<&SubnetTopology> for pb_metadata::SubnetTopology {
             subnet_type: i32::from(item.subnet_type),
             subnet_features: Some(pb_subnet::SubnetFeatures::from(item.subnet_features)),
             ecdsa_keys_held: item.ecdsa_keys_held.iter().map(|k| k.into()).collect(),
+            vetkd_keys_held: item.vetkd_keys_held.iter().map(|k| k.into()).collect(),
         }
     }
 }
@@ -376,6 +379,11 @@ impl TryFrom<pb_metadata::SubnetTopology> for SubnetTopology {
             ecdsa_keys_held.insert(EcdsaKeyId::try_from(key)?);
         }
 
+        let mut vetkd_keys_held = BTreeSet::new();
+        for key in item.vetkd_keys_held {
+            vetkd_keys_held.insert(VetKdKeyId::try_from(key)?);
+        }
+
         Ok(Self {
             public_key: item.public_key,
             nodes,
@@ -388,6 +396,7 @@ impl TryFrom<pb_metadata::SubnetTopology> for SubnetTopology {
                 .map(SubnetFeatures::from)
                 .unwrap_or_default(),
             ecdsa_keys_held,
+            vetkd_keys_held,
         })
     }
 }
@@ -821,6 +830,14 @@ pub struct Stream {
     /// Stream indices of rejected messages, in ascending order.
     reject_signals: VecDeque<StreamIndex>,
 
+    /// Byte size of messages that this subnet is willing to accept from the
+    /// remote subnet beyond `signals_end`, advertised in the stream header.
+    capacity_bytes: Option<u64>,
+
+    /// Capacity most recently advertised by the remote subnet for messages
+    /// from this subnet, beyond the last message it inducted.
+    remote_capacity_bytes: Option<u64>,
+
     /// Estimated byte size of `self.messages`.
     messages_size_bytes: usize,
 }
@@ -835,6 +852,8 @@ impl Default for Stream {
             messages,
             signals_end,
             reject_signals,
+            capacity_bytes: None,
+            remote_capacity_bytes: None,
             messages_size_bytes,
         }
     }
@@ -852,6 +871,8 @@ impl From<&Stream> for pb_queues::Stream {
                 .collect(),
             signals_end: item.signals_end.get(),
             reject_signals,
+            capacity_bytes: item.capacity_bytes,
+            remote_capacity_bytes: item.remote_capacity_bytes,
         }
     }
 }
@@ -876,6 +897,8 @@ impl TryFrom<pb_queues::Stream> for Stream {
             messages,
             signals_end: item.signals_end.into(),
             reject_signals,
+            capacity_bytes: item.capacity_bytes,
+            remote_capacity_bytes: item.remote_capacity_bytes,
             messages_size_bytes,
         })
     }
@@ -889,6 +912,8 @@ impl Stream {
             messages,
             signals_end,
             reject_signals: VecDeque::new(),
+            capacity_bytes: None,
+            remote_capacity_bytes: None,
             messages_size_bytes,
         }
     }
@@ -904,6 +929,8 @@ impl Stream {
             messages,
             signals_end,
             reject_signals,
+            capacity_bytes: None,
+            remote_capacity_bytes: None,
             messages_size_bytes,
         }
     }
@@ -922,6 +949,7 @@ impl Stream {
             end: self.messages.end(),
             signals_end: self.signals_end,
             reject_signals: self.reject_signals.clone(),
+            capacity_bytes: self.capacity_bytes,
         }
     }
 
@@ -1036,6 +1064,27 @@ impl Stream {
         self.reject_signals.push_back(index)
     }
 
+    /// Returns the capacity advertised to the remote subnet, if any.
+    pub fn capacity_bytes(&self) -> Option<u64> {
+        self.capacity_bytes
+    }
+
+    /// Sets the capacity to be advertised to the remote subnet.
+    pub fn set_capacity_bytes(&mut self, capacity_bytes: Option<u64>) {
+        self.capacity_bytes = capacity_bytes;
+    }
+
+    /// Returns the capacity most recently advertised by the remote subnet, if
+    /// any.
+    pub fn remote_capacity_bytes(&self) -> Option<u64> {
+        self.remote_capacity_bytes
+    }
+
+    /// Records the capacity advertised by the remote subnet.
+    pub fn set_remote_capacity_bytes(&mut self, remote_capacity_bytes: Option<u64>) {
+        self.remote_capacity_bytes = remote_capacity_bytes;
+    }
+
     /// Calculates the estimated byte size of the given messages.
     fn size_bytes(messages: &StreamIndexedQueue<RequestOrResponse>) -> usize {
         messages.iter().map(|(_, m)| m.count_bytes()).sum()
@@ -1057,6 +1106,7 @@ impl From<Stream> for StreamSlice {
                 end: val.messages.end(),
                 signals_end: val.signals_end,
                 reject_signals: val.reject_signals,
+                capacity_bytes: val.capacity_bytes,
             },
             val.messages,
         )
@@ -1268,6 +1318,22 @@ impl<'a> StreamHandle<'a> {
     pub fn discard_signals_before(&mut self, new_signals_begin: StreamIndex) {
         self.stream.discard_signals_before(new_signals_begin);
     }
+
+    /// Returns the capacity most recently advertised by the remote subnet, if
+    /// any.
+    pub fn remote_capacity_bytes(&self) -> Option<u64> {
+        self.stream.remote_capacity_bytes()
+    }
+
+    /// Sets the capacity to be advertised to the remote subnet.
+    pub fn set_capacity_bytes(&mut self, capacity_bytes: Option<u64>) {
+        self.stream.set_capacity_bytes(capacity_bytes);
+    }
+
+    /// Records the capacity advertised by the remote subnet.
+    pub fn set_remote_capacity_bytes(&mut self, remote_capacity_bytes: Option<u64>) {
+        self.stream.set_remote_capacity_bytes(remote_capacity_bytes);
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
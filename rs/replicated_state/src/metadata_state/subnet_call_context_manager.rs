@@ -1,6 +1,6 @@
 use ic_btc_types_internal::{CanisterGetSuccessorsRequestInitial, CanisterSendTransactionRequest};
 use ic_error_types::{ErrorCode, UserError};
-use ic_ic00_types::EcdsaKeyId;
+use ic_ic00_types::{EcdsaKeyId, VetKdKeyId};
 use ic_logger::{info, ReplicaLogger};
 use ic_protobuf::{
     proxy::{try_from_option_field, ProxyDecodeError},
@@ -24,6 +24,7 @@ pub enum SubnetCallContext {
     EcdsaDealings(EcdsaDealingsContext),
     BitcoinGetSuccessors(BitcoinGetSuccessorsContext),
     BitcoinSendTransactionInternal(BitcoinSendTransactionInternalContext),
+    VetKdEncryptedKey(VetKdEncryptedKeyContext),
 }
 
 impl SubnetCallContext {
@@ -35,6 +36,7 @@ impl SubnetCallContext {
             SubnetCallContext::EcdsaDealings(context) => &context.request,
             SubnetCallContext::BitcoinGetSuccessors(context) => &context.request,
             SubnetCallContext::BitcoinSendTransactionInternal(context) => &context.request,
+            SubnetCallContext::VetKdEncryptedKey(context) => &context.request,
         }
     }
 
@@ -46,6 +48,7 @@ impl SubnetCallContext {
             SubnetCallContext::EcdsaDealings(context) => context.time,
             SubnetCallContext::BitcoinGetSuccessors(context) => context.time,
             SubnetCallContext::BitcoinSendTransactionInternal(context) => context.time,
+            SubnetCallContext::VetKdEncryptedKey(context) => context.batch_time,
         }
     }
 }
@@ -60,6 +63,7 @@ pub struct SubnetCallContextManager {
     pub bitcoin_get_successors_contexts: BTreeMap<CallbackId, BitcoinGetSuccessorsContext>,
     pub bitcoin_send_transaction_internal_contexts:
         BTreeMap<CallbackId, BitcoinSendTransactionInternalContext>,
+    pub vetkd_encrypted_key_contexts: BTreeMap<CallbackId, VetKdEncryptedKeyContext>,
 }
 
 impl SubnetCallContextManager {
@@ -89,6 +93,26 @@ impl SubnetCallContextManager {
         }
     }
 
+    pub fn push_vetkd_encrypted_key_request(
+        &mut self,
+        context: VetKdEncryptedKeyContext,
+        max_queue_size: u32,
+    ) -> Result<(), UserError> {
+        if self.vetkd_encrypted_key_contexts.len() >= max_queue_size as usize {
+            Err(UserError::new(
+                ErrorCode::CanisterRejectedMessage,
+                "vetkd_encrypted_key request could not be handled, the vetKD request queue is full."
+                    .to_string(),
+            ))
+        } else {
+            let callback_id = CallbackId::new(self.next_callback_id);
+            self.next_callback_id += 1;
+            self.vetkd_encrypted_key_contexts
+                .insert(callback_id, context);
+            Ok(())
+        }
+    }
+
     pub fn push_http_request(&mut self, context: CanisterHttpRequestContext) {
         let callback_id = CallbackId::new(self.next_callback_id);
         self.next_callback_id += 1;
@@ -208,6 +232,19 @@ impl SubnetCallContextManager {
                         SubnetCallContext::BitcoinSendTransactionInternal(context)
                     })
             })
+            .or_else(|| {
+                self.vetkd_encrypted_key_contexts
+                    .remove(&callback_id)
+                    .map(|context| {
+                        info!(
+                            logger,
+                            "Received the response for VetKdEncryptedKey request with id {:?} from {:?}",
+                            context.pseudo_random_id,
+                            context.request.sender
+                        );
+                        SubnetCallContext::VetKdEncryptedKey(context)
+                    })
+            })
     }
 }
 
@@ -275,6 +312,16 @@ impl From<&SubnetCallContextManager> for pb_metadata::SubnetCallContextManager {
                     }
                 })
                 .collect(),
+            vetkd_encrypted_key_contexts: item
+                .vetkd_encrypted_key_contexts
+                .iter()
+                .map(
+                    |(callback_id, context)| pb_metadata::VetKdEncryptedKeyContextTree {
+                        callback_id: callback_id.get(),
+                        context: Some(context.into()),
+                    },
+                )
+                .collect(),
         }
     }
 }
@@ -338,6 +385,14 @@ impl TryFrom<(Time, pb_metadata::SubnetCallContextManager)> for SubnetCallContex
                 .insert(CallbackId::new(entry.callback_id), context);
         }
 
+        let mut vetkd_encrypted_key_contexts =
+            BTreeMap::<CallbackId, VetKdEncryptedKeyContext>::new();
+        for entry in item.vetkd_encrypted_key_contexts {
+            let context: VetKdEncryptedKeyContext =
+                try_from_option_field(entry.context, "SystemMetadata::VetKdEncryptedKeyContext")?;
+            vetkd_encrypted_key_contexts.insert(CallbackId::new(entry.callback_id), context);
+        }
+
         Ok(Self {
             next_callback_id: item.next_callback_id,
             setup_initial_dkg_contexts,
@@ -346,6 +401,7 @@ impl TryFrom<(Time, pb_metadata::SubnetCallContextManager)> for SubnetCallContex
             ecdsa_dealings_contexts,
             bitcoin_get_successors_contexts,
             bitcoin_send_transaction_internal_contexts,
+            vetkd_encrypted_key_contexts,
         })
     }
 }
@@ -459,6 +515,51 @@ impl TryFrom<pb_metadata::SignWithEcdsaContext> for SignWithEcdsaContext {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VetKdEncryptedKeyContext {
+    pub request: Request,
+    pub key_id: VetKdKeyId,
+    pub derivation_path: Vec<Vec<u8>>,
+    pub derivation_id: Vec<u8>,
+    pub encryption_public_key: Vec<u8>,
+    pub pseudo_random_id: [u8; 32],
+    pub batch_time: Time,
+}
+
+impl From<&VetKdEncryptedKeyContext> for pb_metadata::VetKdEncryptedKeyContext {
+    fn from(context: &VetKdEncryptedKeyContext) -> Self {
+        pb_metadata::VetKdEncryptedKeyContext {
+            request: Some((&context.request).into()),
+            key_id: Some((&context.key_id).into()),
+            derivation_path: context.derivation_path.clone(),
+            derivation_id: context.derivation_id.clone(),
+            encryption_public_key: context.encryption_public_key.clone(),
+            pseudo_random_id: context.pseudo_random_id.to_vec(),
+            batch_time: context.batch_time.as_nanos_since_unix_epoch(),
+        }
+    }
+}
+
+impl TryFrom<pb_metadata::VetKdEncryptedKeyContext> for VetKdEncryptedKeyContext {
+    type Error = ProxyDecodeError;
+    fn try_from(context: pb_metadata::VetKdEncryptedKeyContext) -> Result<Self, Self::Error> {
+        let request: Request =
+            try_from_option_field(context.request, "VetKdEncryptedKeyContext::request")?;
+        let key_id = try_from_option_field(context.key_id, "VetKdEncryptedKeyContext::key_id")?;
+        let pseudo_random_id = <[u8; 32]>::try_from(context.pseudo_random_id.as_slice())
+            .map_err(|_| Self::Error::Other("pseudo_random_id is not 32 bytes.".to_string()))?;
+        Ok(VetKdEncryptedKeyContext {
+            request,
+            key_id,
+            derivation_path: context.derivation_path,
+            derivation_id: context.derivation_id,
+            encryption_public_key: context.encryption_public_key,
+            pseudo_random_id,
+            batch_time: Time::from_nanos_since_unix_epoch(context.batch_time),
+        })
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct EcdsaDealingsContext {
     pub request: Request,
@@ -1,8 +1,10 @@
 use super::*;
-use crate::metadata_state::subnet_call_context_manager::SubnetCallContextManager;
+use crate::metadata_state::subnet_call_context_manager::{
+    SubnetCallContextManager, VetKdEncryptedKeyContext,
+};
 use ic_constants::MAX_INGRESS_TTL;
 use ic_error_types::{ErrorCode, UserError};
-use ic_ic00_types::EcdsaCurve;
+use ic_ic00_types::{EcdsaCurve, VetKdCurve, VetKdKeyId};
 use ic_registry_routing_table::CanisterIdRange;
 use ic_test_utilities::{
     mock_time,
@@ -498,6 +500,47 @@ fn subnet_call_contexts_deserialization() {
     assert_eq!(deserialized_http_request_context.transform, Some(transform));
 }
 
+#[test]
+fn vetkd_encrypted_key_contexts_roundtrip_and_are_bounded() {
+    let mut subnet_call_context_manager = SubnetCallContextManager::default();
+    let context = VetKdEncryptedKeyContext {
+        request: RequestBuilder::default()
+            .sender(canister_test_id(1))
+            .receiver(CanisterId::ic_00())
+            .build(),
+        key_id: VetKdKeyId {
+            curve: VetKdCurve::Bls12_381_G2,
+            name: "key".to_string(),
+        },
+        derivation_path: vec![vec![1, 2, 3]],
+        derivation_id: vec![4, 5, 6],
+        encryption_public_key: vec![7; 48],
+        pseudo_random_id: [8; 32],
+        batch_time: mock_time(),
+    };
+    subnet_call_context_manager
+        .push_vetkd_encrypted_key_request(context.clone(), 1)
+        .unwrap();
+    assert_eq!(
+        subnet_call_context_manager
+            .push_vetkd_encrypted_key_request(context.clone(), 1)
+            .unwrap_err()
+            .code(),
+        ErrorCode::CanisterRejectedMessage
+    );
+
+    let proto: ic_protobuf::state::system_metadata::v1::SubnetCallContextManager =
+        (&subnet_call_context_manager).into();
+    let deserialized = SubnetCallContextManager::try_from((mock_time(), proto)).unwrap();
+    assert_eq!(deserialized, subnet_call_context_manager);
+    assert_eq!(
+        deserialized
+            .vetkd_encrypted_key_contexts
+            .get(&CallbackId::from(0)),
+        Some(&context)
+    );
+}
+
 #[test]
 fn empty_network_topology() {
     let network_topology = NetworkTopology {
@@ -526,6 +569,7 @@ fn network_topology_bitcoin_testnet_subnets() {
                 subnet_type: SubnetType::Application,
                 subnet_features: SubnetFeatures::from_str("bitcoin_testnet").unwrap(),
                 ecdsa_keys_held: BTreeSet::new(),
+                vetkd_keys_held: BTreeSet::new(),
             },
 
             // A subnet with the bitcoin testnet feature paused.
@@ -535,6 +579,7 @@ fn network_topology_bitcoin_testnet_subnets() {
                 subnet_type: SubnetType::Application,
                 subnet_features: SubnetFeatures::from_str("bitcoin_testnet_paused").unwrap(),
                 ecdsa_keys_held: BTreeSet::new(),
+                vetkd_keys_held: BTreeSet::new(),
             },
 
             // A subnet without the bitcoin feature enabled.
@@ -544,6 +589,7 @@ fn network_topology_bitcoin_testnet_subnets() {
                 subnet_type: SubnetType::Application,
                 subnet_features: SubnetFeatures::default(),
                 ecdsa_keys_held: BTreeSet::new(),
+                vetkd_keys_held: BTreeSet::new(),
             }
         ],
         routing_table: Arc::new(RoutingTable::default()),
@@ -1,8 +1,10 @@
 use candid::candid_method;
 use dfn_candid::candid_one;
-use dfn_core::api::{print, stable_memory_size_in_pages};
+use dfn_core::api::{print, stable_memory_size_in_pages, trap_with};
 use dfn_core::{over_init, stable, BytesS};
 use dfn_protobuf::protobuf;
+use ic_icrc1::blocks::BlockValue;
+use ic_icrc1::endpoints::{BlockRange as GenericBlockRange, GetBlocksArgs as GetGenericBlocksArgs};
 use ic_ledger_canister_core::range_utils;
 use ic_ledger_core::block::{BlockIndex, BlockType, EncodedBlock};
 use ic_metrics_encoder::MetricsEncoder;
@@ -228,6 +230,52 @@ fn get_blocks_candid_() {
     dfn_core::over(candid_one, get_blocks);
 }
 
+/// Get multiple Blocks by BlockIndex and length in the generic block encoding
+/// shared with ICRC-1 archives. Requesting blocks below the range stored in
+/// the Node traps; the result is truncated if it exceeds the range.
+#[candid_method(query, rename = "get_generic_blocks")]
+fn get_generic_blocks(req: GetGenericBlocksArgs) -> GenericBlockRange {
+    let (start, length) = req.as_start_and_length().unwrap_or_else(|msg| {
+        trap_with(&msg);
+        unreachable!()
+    });
+
+    let archive_state = ARCHIVE_STATE.read().unwrap();
+    let blocks = &archive_state.blocks;
+
+    let block_range = range_utils::make_range(archive_state.block_height_offset, blocks.len());
+
+    if start < block_range.start {
+        trap_with(&format!(
+            "requested index {} is less than the minimal index {} this archive serves",
+            start, block_range.start
+        ));
+    }
+
+    let length = length.min(MAX_BLOCKS_PER_REQUEST as u64) as usize;
+    let requested_range = range_utils::make_range(start, length);
+    let effective_range = match range_utils::intersect(&block_range, &requested_range) {
+        Ok(range) => range,
+        Err(range_utils::NoIntersection) => return GenericBlockRange { blocks: vec![] },
+    };
+
+    let blocks = effective_range
+        .map(|i| {
+            let encoded_block = &blocks[(i - block_range.start) as usize];
+            BlockValue::from(
+                Block::decode(encoded_block.clone()).expect("failed to decode a block"),
+            )
+        })
+        .collect();
+
+    GenericBlockRange { blocks }
+}
+
+#[export_name = "canister_query get_generic_blocks"]
+fn get_generic_blocks_() {
+    dfn_core::over(candid_one, get_generic_blocks);
+}
+
 #[export_name = "canister_post_upgrade"]
 fn post_upgrade() {
     over_init(|_: BytesS| {
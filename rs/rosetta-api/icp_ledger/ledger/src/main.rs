@@ -8,7 +8,12 @@ use dfn_protobuf::protobuf;
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_canister_log::{LogEntry, Sink};
 use ic_icrc1::{
-    endpoints::{StandardRecord, TransferArg, Value},
+    blocks::BlockValue,
+    endpoints::{
+        ArchivedRange, GetBlocksArgs as GetGenericBlocksArgs,
+        GetBlocksResponse as GetGenericBlocksResponse, QueryBlockArchiveFn, StandardRecord,
+        TransferArg, Value,
+    },
     Account,
 };
 use ic_ledger_canister_core::{
@@ -1040,6 +1045,56 @@ fn query_blocks_() {
     over(candid_one, query_blocks)
 }
 
+/// Returns blocks in the generic value encoding shared with ICRC-1 ledgers.
+/// Archived blocks are served by the `get_generic_blocks` endpoint of the
+/// archive canisters.
+#[candid_method(query, rename = "get_blocks")]
+fn get_generic_blocks(req: GetGenericBlocksArgs) -> GetGenericBlocksResponse {
+    let (start, length) = req.as_start_and_length().unwrap_or_else(|msg| {
+        trap_with(&msg);
+        unreachable!()
+    });
+
+    let ledger = LEDGER.read().unwrap();
+    let locations = block_locations(&*ledger, start, length as usize);
+
+    let local_blocks = range_utils::take(&locations.local_blocks, MAX_BLOCKS_PER_REQUEST);
+
+    let blocks: Vec<BlockValue> = ledger
+        .blockchain
+        .block_slice(local_blocks.clone())
+        .iter()
+        .map(|enc_block| {
+            Block::decode(enc_block.clone())
+                .expect("bug: failed to decode encoded block")
+                .into()
+        })
+        .collect();
+
+    let archived_blocks = locations
+        .archived_blocks
+        .into_iter()
+        .map(|(canister_id, slice)| ArchivedRange {
+            start: Nat::from(slice.start),
+            length: Nat::from(range_utils::range_len(&slice)),
+            callback: QueryBlockArchiveFn::new(canister_id, "get_generic_blocks"),
+        })
+        .collect();
+
+    GetGenericBlocksResponse {
+        first_index: Nat::from(local_blocks.start),
+        chain_length: ledger.blockchain.chain_length(),
+        certificate: data_certificate().map(serde_bytes::ByteBuf::from),
+        blocks,
+        archived_blocks,
+    }
+}
+
+#[export_name = "canister_query get_blocks"]
+fn get_generic_blocks_() {
+    over(candid_one, get_generic_blocks)
+}
+
 #[export_name = "canister_query icrc1_minting_account"]
 fn icrc1_minting_account_candid() {
     over(candid_one, |()| icrc1_minting_account())
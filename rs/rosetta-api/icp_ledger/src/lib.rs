@@ -2,7 +2,10 @@ use candid::CandidType;
 use dfn_protobuf::ProtoBuf;
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_crypto_sha::Sha256;
-use ic_icrc1::Account;
+use ic_icrc1::{
+    blocks::{BlockValue, BlockValueMap},
+    Account,
+};
 pub use ic_ledger_canister_core::archive::ArchiveOptions;
 use ic_ledger_canister_core::ledger::{LedgerContext, LedgerTransaction, TxApplyError};
 use ic_ledger_core::{
@@ -791,6 +794,260 @@ impl From<Block> for CandidBlock {
     }
 }
 
+/// Converts a block to the generic block value served by the `get_blocks`
+/// endpoint. Fields that ICRC-1 blocks also have use the same names and,
+/// except for accounts, the same value types:
+///
+/// * Integers are encoded as `Nat`.
+/// * "from", "to" and "spender" are blobs holding 32-byte account identifiers,
+///   as ICP blocks do not store the principals and subaccounts of accounts.
+///   ICRC-1 blocks store compact accounts (arrays of blobs) in "from" and
+///   "to", so a decoder cannot mistake one for the other.
+/// * "memo" is the ICRC-1 memo (if any), the ICP memo is in "icp_memo".
+/// * Approvals that increase the allowance have the "approve" operation,
+///   approvals that decrease it have the "decrease_allowance" operation.
+///   Both store the change in "amt".
+///
+/// Note that "phash" is the hash of the parent block in the ledger's native
+/// (protobuf) encoding, not the hash of the parent's generic value as in
+/// ICRC-1 blocks, see [generic_block_hash].
+impl From<Block> for BlockValue {
+    fn from(
+        Block {
+            parent_hash,
+            transaction,
+            timestamp,
+        }: Block,
+    ) -> Self {
+        fn nat(n: u64) -> BlockValue {
+            BlockValue::Nat(candid::Nat::from(n))
+        }
+        fn account(account: AccountIdentifier) -> BlockValue {
+            BlockValue::Blob(ByteBuf::from(account.to_vec()))
+        }
+        fn tokens(tokens: Tokens) -> BlockValue {
+            nat(tokens.get_e8s())
+        }
+        fn op(name: &str) -> BlockValue {
+            BlockValue::Text(name.to_string())
+        }
+
+        let mut tx = BlockValueMap::new();
+        match transaction.operation {
+            Operation::Burn { from, amount } => {
+                tx.insert("op".to_string(), op("burn"));
+                tx.insert("from".to_string(), account(from));
+                tx.insert("amt".to_string(), tokens(amount));
+            }
+            Operation::Mint { to, amount } => {
+                tx.insert("op".to_string(), op("mint"));
+                tx.insert("to".to_string(), account(to));
+                tx.insert("amt".to_string(), tokens(amount));
+            }
+            Operation::Transfer {
+                from,
+                to,
+                amount,
+                fee,
+            } => {
+                tx.insert("op".to_string(), op("xfer"));
+                tx.insert("from".to_string(), account(from));
+                tx.insert("to".to_string(), account(to));
+                tx.insert("amt".to_string(), tokens(amount));
+                tx.insert("fee".to_string(), tokens(fee));
+            }
+            Operation::Approve {
+                from,
+                spender,
+                allowance,
+                expires_at,
+                fee,
+            } => {
+                let (name, amount) = match allowance {
+                    SignedTokens::Plus(amount) => ("approve", amount),
+                    SignedTokens::Minus(amount) => ("decrease_allowance", amount),
+                };
+                tx.insert("op".to_string(), op(name));
+                tx.insert("from".to_string(), account(from));
+                tx.insert("spender".to_string(), account(spender));
+                tx.insert("amt".to_string(), tokens(amount));
+                if let Some(expires_at) = expires_at {
+                    tx.insert(
+                        "expires_at".to_string(),
+                        nat(expires_at.as_nanos_since_unix_epoch()),
+                    );
+                }
+                tx.insert("fee".to_string(), tokens(fee));
+            }
+            Operation::TransferFrom {
+                from,
+                to,
+                spender,
+                amount,
+                fee,
+            } => {
+                tx.insert("op".to_string(), op("xfer"));
+                tx.insert("from".to_string(), account(from));
+                tx.insert("to".to_string(), account(to));
+                tx.insert("spender".to_string(), account(spender));
+                tx.insert("amt".to_string(), tokens(amount));
+                tx.insert("fee".to_string(), tokens(fee));
+            }
+        }
+        tx.insert("icp_memo".to_string(), nat(transaction.memo.0));
+        if let Some(icrc1_memo) = transaction.icrc1_memo {
+            tx.insert("memo".to_string(), BlockValue::Blob(icrc1_memo));
+        }
+        if let Some(created_at_time) = transaction.created_at_time {
+            tx.insert(
+                "ts".to_string(),
+                nat(created_at_time.as_nanos_since_unix_epoch()),
+            );
+        }
+
+        let mut block = BlockValueMap::new();
+        if let Some(parent_hash) = parent_hash {
+            block.insert(
+                "phash".to_string(),
+                BlockValue::Blob(ByteBuf::from(parent_hash.into_bytes().to_vec())),
+            );
+        }
+        block.insert("tx".to_string(), BlockValue::Map(tx));
+        block.insert("ts".to_string(), nat(timestamp.as_nanos_since_unix_epoch()));
+        BlockValue::Map(block)
+    }
+}
+
+/// Converts a generic block value produced by the ICP ledger back to the
+/// native block. This is the inverse of `BlockValue::from(Block)`.
+impl TryFrom<BlockValue> for Block {
+    type Error = String;
+
+    fn try_from(value: BlockValue) -> Result<Self, String> {
+        fn get(map: &BlockValueMap, key: &str) -> Result<BlockValue, String> {
+            map.get(key)
+                .cloned()
+                .ok_or_else(|| format!("missing field {}", key))
+        }
+        fn as_map(value: BlockValue) -> Result<BlockValueMap, String> {
+            match value {
+                BlockValue::Map(map) => Ok(map),
+                value => Err(format!("expected a Map, got {:?}", value)),
+            }
+        }
+        fn as_nat(value: BlockValue) -> Result<u64, String> {
+            match value {
+                BlockValue::Nat(n) => {
+                    u64::try_from(&n.0).map_err(|_| format!("{} does not fit into u64", n))
+                }
+                value => Err(format!("expected a Nat, got {:?}", value)),
+            }
+        }
+        fn as_blob(value: BlockValue) -> Result<ByteBuf, String> {
+            match value {
+                BlockValue::Blob(blob) => Ok(blob),
+                value => Err(format!("expected a Blob, got {:?}", value)),
+            }
+        }
+        fn as_account(value: BlockValue) -> Result<AccountIdentifier, String> {
+            AccountIdentifier::from_slice(&as_blob(value)?)
+                .map_err(|e| format!("invalid account identifier: {}", e))
+        }
+
+        let block = as_map(value)?;
+        let tx = as_map(get(&block, "tx")?)?;
+        let account = |key| get(&tx, key).and_then(as_account);
+        let tokens = |key| get(&tx, key).and_then(as_nat).map(Tokens::from_e8s);
+        let spender = || get(&tx, "spender").and_then(as_account);
+
+        let op = match get(&tx, "op")? {
+            BlockValue::Text(op) => op,
+            value => return Err(format!("expected a Text op, got {:?}", value)),
+        };
+        let operation = match op.as_str() {
+            "burn" => Operation::Burn {
+                from: account("from")?,
+                amount: tokens("amt")?,
+            },
+            "mint" => Operation::Mint {
+                to: account("to")?,
+                amount: tokens("amt")?,
+            },
+            "xfer" if tx.contains_key("spender") => Operation::TransferFrom {
+                from: account("from")?,
+                to: account("to")?,
+                spender: spender()?,
+                amount: tokens("amt")?,
+                fee: tokens("fee")?,
+            },
+            "xfer" => Operation::Transfer {
+                from: account("from")?,
+                to: account("to")?,
+                amount: tokens("amt")?,
+                fee: tokens("fee")?,
+            },
+            "approve" | "decrease_allowance" => Operation::Approve {
+                from: account("from")?,
+                spender: spender()?,
+                allowance: if op == "approve" {
+                    SignedTokens::Plus(tokens("amt")?)
+                } else {
+                    SignedTokens::Minus(tokens("amt")?)
+                },
+                expires_at: tx
+                    .get("expires_at")
+                    .cloned()
+                    .map(as_nat)
+                    .transpose()?
+                    .map(TimeStamp::from_nanos_since_unix_epoch),
+                fee: tokens("fee")?,
+            },
+            op => return Err(format!("unknown operation {}", op)),
+        };
+
+        let parent_hash = match block.get("phash").cloned().map(as_blob).transpose()? {
+            Some(bytes) => Some(HashOf::new(
+                <[u8; HASH_LENGTH]>::try_from(&bytes[..]).map_err(|_| {
+                    format!(
+                        "expected a {}-byte phash, got {} bytes",
+                        HASH_LENGTH,
+                        bytes.len()
+                    )
+                })?,
+            )),
+            None => None,
+        };
+
+        Ok(Block {
+            parent_hash,
+            transaction: Transaction {
+                operation,
+                memo: Memo(get(&tx, "icp_memo").and_then(as_nat)?),
+                created_at_time: tx
+                    .get("ts")
+                    .cloned()
+                    .map(as_nat)
+                    .transpose()?
+                    .map(TimeStamp::from_nanos_since_unix_epoch),
+                icrc1_memo: tx.get("memo").cloned().map(as_blob).transpose()?,
+            },
+            timestamp: TimeStamp::from_nanos_since_unix_epoch(get(&block, "ts").and_then(as_nat)?),
+        })
+    }
+}
+
+/// Returns the hash of a generic block in the ledger's native encoding.
+///
+/// The ICP ledger chains and certifies blocks by their native hashes, so
+/// clients verify generic blocks with this function: the hash of each block
+/// must be equal to the "phash" field of the next block, and the hash of the
+/// last block must be equal to the certified tip hash. The hash of the generic
+/// value itself, which ICRC-1 ledgers chain and certify, matches neither.
+pub fn generic_block_hash(value: &BlockValue) -> Result<HashOf<EncodedBlock>, String> {
+    let block = Block::try_from(value.clone())?;
+    Ok(Block::block_hash(&block.encode()))
+}
+
 /// Argument taken by the transfer fee endpoint
 ///
 /// The reason it is a struct is so that it can be extended -- e.g., to be able
@@ -986,3 +1243,252 @@ pub struct QueryBlocksResponse {
     pub first_block_index: BlockIndex,
     pub archived_blocks: Vec<ArchivedBlocksRange>,
 }
+
+#[test]
+fn generic_block_encoding() {
+    let from = AccountIdentifier::new(PrincipalId::new_user_test_id(1), None);
+    let to = AccountIdentifier::new(PrincipalId::new_user_test_id(2), None);
+    let parent_hash = HashOf::new([7; HASH_LENGTH]);
+    let block = Block::from_transaction(
+        Some(parent_hash),
+        Transaction {
+            operation: Operation::Transfer {
+                from,
+                to,
+                amount: Tokens::from_e8s(1_000_000),
+                fee: DEFAULT_TRANSFER_FEE,
+            },
+            memo: Memo(42),
+            created_at_time: Some(TimeStamp::from_nanos_since_unix_epoch(1_000)),
+            icrc1_memo: Some(ByteBuf::from(vec![1, 2, 3])),
+        },
+        TimeStamp::from_nanos_since_unix_epoch(2_000),
+        DEFAULT_TRANSFER_FEE,
+    );
+
+    let blob = |bytes: &[u8]| BlockValue::Blob(ByteBuf::from(bytes.to_vec()));
+    let nat = |n: u64| BlockValue::Nat(candid::Nat::from(n));
+    let tx = BlockValueMap::from([
+        ("op".to_string(), BlockValue::Text("xfer".to_string())),
+        ("from".to_string(), blob(&from.to_address())),
+        ("to".to_string(), blob(&to.to_address())),
+        ("amt".to_string(), nat(1_000_000)),
+        ("fee".to_string(), nat(10_000)),
+        ("icp_memo".to_string(), nat(42)),
+        ("memo".to_string(), blob(&[1, 2, 3])),
+        ("ts".to_string(), nat(1_000)),
+    ]);
+    let expected = BlockValue::Map(BlockValueMap::from([
+        ("phash".to_string(), blob(&[7; HASH_LENGTH])),
+        ("tx".to_string(), BlockValue::Map(tx)),
+        ("ts".to_string(), nat(2_000)),
+    ]));
+
+    assert_eq!(BlockValue::from(block), expected);
+}
+
+#[test]
+fn generic_block_roundtrip() {
+    let from = AccountIdentifier::new(PrincipalId::new_user_test_id(1), None);
+    let to = AccountIdentifier::new(PrincipalId::new_user_test_id(2), None);
+    let spender = AccountIdentifier::new(PrincipalId::new_user_test_id(3), None);
+    let operations = vec![
+        Operation::Mint {
+            to,
+            amount: Tokens::from_e8s(1),
+        },
+        Operation::Burn {
+            from,
+            amount: Tokens::from_e8s(2),
+        },
+        Operation::Transfer {
+            from,
+            to,
+            amount: Tokens::from_e8s(3),
+            fee: DEFAULT_TRANSFER_FEE,
+        },
+        Operation::Approve {
+            from,
+            spender,
+            allowance: SignedTokens::Plus(Tokens::from_e8s(4)),
+            expires_at: Some(TimeStamp::from_nanos_since_unix_epoch(5)),
+            fee: DEFAULT_TRANSFER_FEE,
+        },
+        Operation::Approve {
+            from,
+            spender,
+            allowance: SignedTokens::Minus(Tokens::from_e8s(6)),
+            expires_at: None,
+            fee: DEFAULT_TRANSFER_FEE,
+        },
+        Operation::TransferFrom {
+            from,
+            to,
+            spender,
+            amount: Tokens::from_e8s(7),
+            fee: DEFAULT_TRANSFER_FEE,
+        },
+    ];
+
+    let mut parent_hash = None;
+    for (i, operation) in operations.into_iter().enumerate() {
+        let block = Block::from_transaction(
+            parent_hash,
+            Transaction {
+                operation,
+                memo: Memo(i as u64),
+                created_at_time: (i % 2 == 0)
+                    .then_some(TimeStamp::from_nanos_since_unix_epoch(1_000 + i as u64)),
+                icrc1_memo: (i % 3 == 0).then_some(ByteBuf::from(vec![i as u8; 32])),
+            },
+            TimeStamp::from_nanos_since_unix_epoch(2_000 + i as u64),
+            DEFAULT_TRANSFER_FEE,
+        );
+        let encoded = block.clone().encode();
+        let value = BlockValue::from(block.clone());
+
+        assert_eq!(Block::try_from(value.clone()), Ok(block));
+        assert_eq!(generic_block_hash(&value), Ok(Block::block_hash(&encoded)));
+        parent_hash = Some(Block::block_hash(&encoded));
+    }
+}
+
+/// Decodes the fields that ICRC-1 and ICP blocks share from a generic block,
+/// failing if any of them has an unexpected type. Accounts are either compact
+/// ICRC-1 accounts (arrays of blobs) or ICP account identifiers (blobs).
+#[cfg(test)]
+fn decode_common_fields(
+    value: &BlockValue,
+) -> std::collections::BTreeMap<&'static str, BlockValue> {
+    fn is_account(v: &BlockValue) -> bool {
+        match v {
+            BlockValue::Array(a) => a.iter().all(|c| matches!(c, BlockValue::Blob(_))),
+            BlockValue::Blob(_) => true,
+            _ => false,
+        }
+    }
+    let field_types: [(&'static str, fn(&BlockValue) -> bool); 9] = [
+        ("op", |v| matches!(v, BlockValue::Text(_))),
+        ("from", is_account),
+        ("to", is_account),
+        ("spender", |v| matches!(v, BlockValue::Blob(_))),
+        ("amt", |v| matches!(v, BlockValue::Nat(_))),
+        ("fee", |v| matches!(v, BlockValue::Nat(_))),
+        ("expires_at", |v| matches!(v, BlockValue::Nat(_))),
+        ("memo", |v| matches!(v, BlockValue::Blob(_))),
+        ("ts", |v| matches!(v, BlockValue::Nat(_))),
+    ];
+
+    let block = match value {
+        BlockValue::Map(block) => block,
+        _ => panic!("expected a Map block, got {:?}", value),
+    };
+    assert!(matches!(block.get("ts"), Some(BlockValue::Nat(_))));
+    assert!(matches!(
+        block.get("phash"),
+        None | Some(BlockValue::Blob(_))
+    ));
+    let tx = match block.get("tx") {
+        Some(BlockValue::Map(tx)) => tx,
+        tx => panic!("expected a Map transaction, got {:?}", tx),
+    };
+
+    let mut fields = std::collections::BTreeMap::new();
+    for (name, has_expected_type) in field_types {
+        if let Some(v) = tx.get(name) {
+            assert!(has_expected_type(v), "unexpected type of {}: {:?}", name, v);
+            fields.insert(name, v.clone());
+        }
+    }
+    fields
+}
+
+#[test]
+fn decode_icp_and_icrc1_blocks_with_the_same_decoder() {
+    use ic_icrc1::blocks::Icrc1Block;
+
+    let nat = |n: u64| BlockValue::Nat(candid::Nat::from(n));
+    let text = |s: &str| BlockValue::Text(s.to_string());
+    let icrc1_account = Account {
+        owner: PrincipalId::new_user_test_id(1),
+        subaccount: Some([1; 32]),
+    };
+    let icrc1_spender = PrincipalId::new_user_test_id(2);
+    let icp_from = AccountIdentifier::new(PrincipalId::new_user_test_id(1), None);
+    let icp_spender = AccountIdentifier::new(PrincipalId::new_user_test_id(2), None);
+
+    let icrc1_approve = ic_icrc1::Block::from_transaction(
+        None,
+        ic_icrc1::Transaction::approve(
+            icrc1_account,
+            icrc1_spender,
+            Tokens::from_e8s(100),
+            Some(TimeStamp::from_nanos_since_unix_epoch(3_000)),
+            Some(Tokens::from_e8s(10)),
+            Some(TimeStamp::from_nanos_since_unix_epoch(1_000)),
+            Some(ic_icrc1::Memo::from(42)),
+        ),
+        TimeStamp::from_nanos_since_unix_epoch(2_000),
+        Tokens::from_e8s(10),
+    );
+    let icp_approve = Block::from_transaction(
+        None,
+        Transaction {
+            operation: Operation::Approve {
+                from: icp_from,
+                spender: icp_spender,
+                allowance: SignedTokens::Plus(Tokens::from_e8s(100)),
+                expires_at: Some(TimeStamp::from_nanos_since_unix_epoch(3_000)),
+                fee: Tokens::from_e8s(10),
+            },
+            memo: Memo(0),
+            created_at_time: Some(TimeStamp::from_nanos_since_unix_epoch(1_000)),
+            icrc1_memo: Some(ByteBuf::from(42u64.to_be_bytes().to_vec())),
+        },
+        TimeStamp::from_nanos_since_unix_epoch(2_000),
+        Tokens::from_e8s(10),
+    );
+
+    let icrc1 = decode_common_fields(&Icrc1Block::from(&icrc1_approve.encode()));
+    let icp = decode_common_fields(&BlockValue::from(icp_approve));
+
+    // Both ledgers encode the same fields, except that ICP blocks hold
+    // account identifiers where ICRC-1 blocks hold principals and accounts.
+    assert_eq!(
+        icrc1.keys().collect::<Vec<_>>(),
+        icp.keys().collect::<Vec<_>>()
+    );
+    for (name, expected) in [
+        ("op", text("approve")),
+        ("amt", nat(100)),
+        ("fee", nat(10)),
+        ("expires_at", nat(3_000)),
+        ("ts", nat(1_000)),
+        (
+            "memo",
+            BlockValue::Blob(ByteBuf::from(42u64.to_be_bytes().to_vec())),
+        ),
+    ] {
+        assert_eq!(icrc1[name], expected, "ICRC-1 field {}", name);
+        assert_eq!(icp[name], expected, "ICP field {}", name);
+    }
+    assert_eq!(
+        icrc1["spender"],
+        BlockValue::Blob(ByteBuf::from(icrc1_spender.to_vec()))
+    );
+    assert_eq!(
+        icp["spender"],
+        BlockValue::Blob(ByteBuf::from(icp_spender.to_vec()))
+    );
+    assert_eq!(
+        icrc1["from"],
+        BlockValue::Array(vec![
+            BlockValue::Blob(ByteBuf::from(icrc1_account.owner.to_vec())),
+            BlockValue::Blob(ByteBuf::from(vec![1; 32])),
+        ])
+    );
+    assert_eq!(
+        icp["from"],
+        BlockValue::Blob(ByteBuf::from(icp_from.to_vec()))
+    );
+}
@@ -1,22 +1,27 @@
-use candid::{utils::service_compatible, utils::CandidSource, CandidType};
+use candid::{utils::service_compatible, utils::CandidSource, CandidType, Nat};
 use canister_test::*;
 use dfn_candid::{candid, candid_one, CandidOne};
 use dfn_protobuf::protobuf;
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_canister_client_sender::Sender;
 use ic_canisters_http_types::{HttpRequest, HttpResponse};
+use ic_icrc1::blocks::BlockValue;
+use ic_icrc1::endpoints::{
+    BlockRange as GenericBlockRange, GetBlocksArgs as GetGenericBlocksArgs,
+    GetBlocksResponse as GetGenericBlocksResponse,
+};
 use ic_ledger_canister_core::archive::ArchiveOptions;
 use ic_ledger_core::{
     block::{BlockIndex, BlockType, EncodedBlock},
     timestamp::TimeStamp,
 };
 use icp_ledger::{
-    tokens_from_proto, AccountBalanceArgs, AccountIdentifier, Archives, BinaryAccountBalanceArgs,
-    Block, BlockArg, BlockRange, BlockRes, CandidBlock, GetBlocksArgs, GetBlocksError,
-    GetBlocksRes, GetBlocksResult, IterBlocksArgs, IterBlocksRes, LedgerCanisterInitPayload, Memo,
-    NotifyCanisterArgs, Operation, QueryBlocksResponse, SendArgs, Subaccount, Tokens,
-    TotalSupplyArgs, Transaction, TransferArgs, TransferError, TransferFee, TransferFeeArgs,
-    DEFAULT_TRANSFER_FEE,
+    generic_block_hash, tokens_from_proto, AccountBalanceArgs, AccountIdentifier, Archives,
+    BinaryAccountBalanceArgs, Block, BlockArg, BlockRange, BlockRes, CandidBlock, GetBlocksArgs,
+    GetBlocksError, GetBlocksRes, GetBlocksResult, IterBlocksArgs, IterBlocksRes,
+    LedgerCanisterInitPayload, Memo, NotifyCanisterArgs, Operation, QueryBlocksResponse, SendArgs,
+    Subaccount, Tokens, TotalSupplyArgs, Transaction, TransferArgs, TransferError, TransferFee,
+    TransferFeeArgs, DEFAULT_TRANSFER_FEE,
 };
 use on_wire::IntoWire;
 use serde::Deserialize;
@@ -618,6 +623,123 @@ fn archived_blocks_ranges() {
     });
 }
 
+#[test]
+fn get_generic_blocks_test() {
+    local_test_e(|r| async move {
+        let proj = Project::new();
+
+        // 12 blocks
+        let accounts = make_accounts(4, 3);
+
+        let archive_options = ArchiveOptions {
+            trigger_threshold: 12,
+            num_blocks_to_archive: 10,
+            node_max_memory_size_bytes: None,
+            max_message_size_bytes: None,
+            controller_id: CanisterId::from_u64(876).into(),
+            cycles_for_archive_creation: Some(0),
+            max_transactions_per_response: None,
+        };
+
+        let minting_account = create_sender(0);
+
+        let ledger: canister_test::Canister = {
+            let payload = LedgerCanisterInitPayload::builder()
+                .minting_account(
+                    CanisterId::try_from(minting_account.get_principal_id())
+                        .unwrap()
+                        .into(),
+                )
+                .initial_values(accounts)
+                .archive_options(archive_options)
+                .build()
+                .unwrap();
+            let mut install = proj.cargo_bin("ledger-canister", &[]).install(&r);
+            install.memory_allocation = Some(128 * 1024 * 1024);
+            install.bytes(CandidOne(payload).into_bytes()?).await?
+        };
+
+        let IterBlocksRes(mut native_blocks) = ledger
+            .query_(
+                "iter_blocks_pb",
+                protobuf,
+                IterBlocksArgs::new(0usize, 128usize),
+            )
+            .await?;
+        assert_eq!(native_blocks.len(), 12);
+
+        // make a transfer to trigger archiving
+        simple_send(&ledger, &create_sender(12345), &minting_account, 100, 0).await?;
+        let GetBlocksRes(tip) = get_blocks_pb(&ledger, 12..13).await?;
+        native_blocks.extend(tip.unwrap());
+
+        let response: GetGenericBlocksResponse = ledger
+            .query_(
+                "get_blocks",
+                candid_one,
+                GetGenericBlocksArgs {
+                    start: Nat::from(0u64),
+                    length: Nat::from(100u64),
+                },
+            )
+            .await?;
+        assert!(response.certificate.is_some());
+        assert_eq!(response.chain_length, 13);
+        assert_eq!(response.first_index, Nat::from(10u64));
+        assert_eq!(response.blocks.len(), 3);
+        assert_eq!(response.archived_blocks.len(), 1);
+
+        let archived = &response.archived_blocks[0];
+        assert_eq!(archived.start, Nat::from(0u64));
+        assert_eq!(archived.length, Nat::from(10u64));
+        assert_eq!(archived.callback.method, "get_generic_blocks");
+
+        let archive = Canister::new(&r, archived.callback.canister_id);
+        let GenericBlockRange {
+            blocks: archived_blocks,
+        } = archive
+            .query_(
+                &archived.callback.method,
+                candid_one,
+                GetGenericBlocksArgs {
+                    start: archived.start.clone(),
+                    length: archived.length.clone(),
+                },
+            )
+            .await?;
+
+        let generic_blocks: Vec<BlockValue> = archived_blocks
+            .into_iter()
+            .chain(response.blocks.into_iter())
+            .collect();
+        let expected_blocks: Vec<BlockValue> = native_blocks
+            .iter()
+            .map(|eb| Block::decode(eb.clone()).unwrap().into())
+            .collect();
+        assert_eq!(generic_blocks, expected_blocks);
+
+        // The generic blocks form the same hash chain as the native blocks.
+        for (i, pair) in generic_blocks.windows(2).enumerate() {
+            let parent_hash = generic_block_hash(&pair[0]).unwrap();
+            assert_eq!(parent_hash, Block::block_hash(&native_blocks[i]));
+            match &pair[1] {
+                BlockValue::Map(block) => assert_eq!(
+                    block.get("phash"),
+                    Some(&BlockValue::Blob(ByteBuf::from(
+                        parent_hash.into_bytes().to_vec()
+                    )))
+                ),
+                block => panic!("expected a Map block, got {:?}", block),
+            }
+        }
+        assert_eq!(
+            generic_block_hash(generic_blocks.last().unwrap()).unwrap(),
+            Block::block_hash(native_blocks.last().unwrap())
+        );
+        Ok(())
+    })
+}
+
 #[test]
 fn notify_timeout_test() {
     local_test_e(|r| async move {
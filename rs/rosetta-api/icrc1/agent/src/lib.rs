@@ -1,7 +1,10 @@
 use candid::{Decode, Encode, Nat, Principal};
 use ic_agent::Agent;
 pub use ic_icrc1::{
-    endpoints::{TransferArg, TransferError, Value},
+    endpoints::{
+        BlockRange, GetBlocksArgs, GetBlocksResponse, QueryBlockArchiveFn, TransferArg,
+        TransferError, Value,
+    },
     Account,
 };
 pub use ic_ledger_core::block::BlockIndex;
@@ -171,4 +174,34 @@ impl Icrc1Agent {
             Decode!(&self.update("icrc1_transfer", &Encode!(&args)?).await?, Result<Nat, TransferError>)?,
         )
     }
+
+    /// Returns the blocks in the range [args.start, args.start + args.length)
+    /// together with the certificate of the tip of the chain.
+    /// Blocks that have been archived are not returned, instead the response
+    /// contains the archive callbacks to fetch them.
+    pub async fn get_blocks(
+        &self,
+        args: GetBlocksArgs,
+    ) -> Result<GetBlocksResponse, Icrc1AgentError> {
+        Ok(Decode!(
+            &self.query("get_blocks", &Encode!(&args)?).await?,
+            GetBlocksResponse
+        )?)
+    }
+
+    /// Fetches the blocks in the range [args.start, args.start + args.length)
+    /// from the archive referenced by the callback.
+    pub async fn get_blocks_from_archive(
+        &self,
+        callback: &QueryBlockArchiveFn,
+        args: GetBlocksArgs,
+    ) -> Result<BlockRange, Icrc1AgentError> {
+        let bytes = self
+            .agent
+            .query(&callback.canister_id.get().0, &callback.method)
+            .with_arg(Encode!(&args)?)
+            .call()
+            .await?;
+        Ok(Decode!(&bytes, BlockRange)?)
+    }
 }
@@ -0,0 +1,195 @@
+use candid::{CandidType, Nat};
+use cycles_minting_canister::CreateCanister as CmcCreateCanisterArgs;
+use ic_base_types::{CanisterId, PrincipalId};
+use ic_icrc1::{Account, Memo, Subaccount};
+use ic_icrc1_ledger::Ledger;
+use ic_ledger_canister_core::{
+    archive::ArchiveOptions, ledger::TransferError as CoreTransferError,
+};
+use serde::{Deserialize, Serialize};
+
+/// The number of decimals of the cycles token, 1 token is 1T cycles.
+pub const DECIMALS: u8 = 12;
+
+#[derive(Deserialize, CandidType, Clone, Debug, PartialEq, Eq)]
+pub struct InitArgs {
+    /// The fee in cycles charged for transfers, withdrawals and canister
+    /// creations.
+    pub transfer_fee: u64,
+    pub archive_options: ArchiveOptions,
+    /// The cycles minting canister used to create canisters, defaults to
+    /// the one of the NNS.
+    pub cycles_minting_canister_id: Option<CanisterId>,
+}
+
+#[derive(Deserialize, CandidType, Clone, Debug, PartialEq, Eq)]
+pub struct UpgradeArgs {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transfer_fee: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cycles_minting_canister_id: Option<CanisterId>,
+}
+
+#[derive(Deserialize, CandidType, Clone, Debug, PartialEq, Eq)]
+pub enum LedgerArgument {
+    Init(InitArgs),
+    Upgrade(Option<UpgradeArgs>),
+}
+
+/// The state of the cycles ledger. The balances and the blocks are kept by an
+/// ICRC-1 ledger whose minting account is the cycles ledger itself: cycles
+/// deposited to the ledger are minted and cycles leaving the ledger are
+/// burned.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct State {
+    pub ledger: Ledger,
+    pub cycles_minting_canister_id: CanisterId,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DepositArgs {
+    pub to: Account,
+    pub memo: Option<Memo>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DepositResult {
+    /// The index of the mint block.
+    pub txid: Nat,
+    /// The balance of the account after the deposit.
+    pub balance: Nat,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct WithdrawArgs {
+    pub from_subaccount: Option<Subaccount>,
+    /// The canister that receives the cycles.
+    pub to: PrincipalId,
+    pub created_at_time: Option<u64>,
+    pub amount: Nat,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum WithdrawError {
+    InsufficientFunds {
+        balance: Nat,
+    },
+    TooOld,
+    CreatedInFuture {
+        ledger_time: u64,
+    },
+    TemporarilyUnavailable,
+    Duplicate {
+        duplicate_of: Nat,
+    },
+    /// The cycles could not be sent to the canister. The withdrawn amount was
+    /// minted back to the account in `refund_block`, the fee is not refunded.
+    FailedToWithdraw {
+        burn_block: Nat,
+        refund_block: Option<Nat>,
+        rejection_code: i32,
+        rejection_reason: String,
+    },
+    GenericError {
+        error_code: Nat,
+        message: String,
+    },
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct CreateCanisterArgs {
+    pub from_subaccount: Option<Subaccount>,
+    pub created_at_time: Option<u64>,
+    /// The cycles sent to the new canister, the fee is charged on top.
+    pub amount: Nat,
+    /// The arguments passed to the cycles minting canister. The caller is the
+    /// only controller of the new canister if no controllers are set.
+    pub creation_args: Option<CmcCreateCanisterArgs>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CreateCanisterSuccess {
+    pub block_id: Nat,
+    pub canister_id: PrincipalId,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum CreateCanisterError {
+    InsufficientFunds {
+        balance: Nat,
+    },
+    TooOld,
+    CreatedInFuture {
+        ledger_time: u64,
+    },
+    TemporarilyUnavailable,
+    Duplicate {
+        duplicate_of: Nat,
+    },
+    /// The canister could not be created. The cycles returned by the cycles
+    /// minting canister were minted back to the account in `refund_block`.
+    FailedToCreate {
+        burn_block: Nat,
+        refund_block: Option<Nat>,
+        error: String,
+    },
+    GenericError {
+        error_code: Nat,
+        message: String,
+    },
+}
+
+impl From<CoreTransferError> for WithdrawError {
+    fn from(err: CoreTransferError) -> Self {
+        use ic_ledger_canister_core::ledger::TransferError as LTE;
+
+        match err {
+            LTE::InsufficientFunds { balance } => Self::InsufficientFunds {
+                balance: Nat::from(balance.get_e8s()),
+            },
+            LTE::TxTooOld { .. } => Self::TooOld,
+            LTE::TxCreatedInFuture { ledger_time } => Self::CreatedInFuture {
+                ledger_time: ledger_time.as_nanos_since_unix_epoch(),
+            },
+            LTE::TxThrottled => Self::TemporarilyUnavailable,
+            LTE::TxDuplicate { duplicate_of } => Self::Duplicate {
+                duplicate_of: Nat::from(duplicate_of),
+            },
+            LTE::BadFee { .. } => unreachable!("withdrawals do not take a fee argument"),
+            LTE::InsufficientAllowance { .. } | LTE::ExpiredApproval { .. } => {
+                unreachable!("withdrawals do not use allowances")
+            }
+        }
+    }
+}
+
+impl From<CoreTransferError> for CreateCanisterError {
+    fn from(err: CoreTransferError) -> Self {
+        use ic_ledger_canister_core::ledger::TransferError as LTE;
+
+        match err {
+            LTE::InsufficientFunds { balance } => Self::InsufficientFunds {
+                balance: Nat::from(balance.get_e8s()),
+            },
+            LTE::TxTooOld { .. } => Self::TooOld,
+            LTE::TxCreatedInFuture { ledger_time } => Self::CreatedInFuture {
+                ledger_time: ledger_time.as_nanos_since_unix_epoch(),
+            },
+            LTE::TxThrottled => Self::TemporarilyUnavailable,
+            LTE::TxDuplicate { duplicate_of } => Self::Duplicate {
+                duplicate_of: Nat::from(duplicate_of),
+            },
+            LTE::BadFee { .. } => unreachable!("canister creations do not take a fee argument"),
+            LTE::InsufficientAllowance { .. } | LTE::ExpiredApproval { .. } => {
+                unreachable!("canister creations do not use allowances")
+            }
+        }
+    }
+}
+
+/// Returns the memo of the block that burns the cycles withdrawn to
+/// `canister_id`.
+pub fn withdraw_memo(canister_id: &PrincipalId) -> Memo {
+    Memo::try_from(canister_id.as_slice().to_vec())
+        .expect("bug: principals are shorter than the maximum memo length")
+}
@@ -0,0 +1,624 @@
+use candid::{candid_method, types::number::Nat, Principal};
+use cycles_minting_canister::CreateCanisterError as CmcCreateCanisterError;
+use ic_base_types::{CanisterId, PrincipalId};
+use ic_canister_log::{declare_log_buffer, export, log};
+use ic_canisters_http_types::{HttpRequest, HttpResponse, HttpResponseBuilder};
+use ic_cdk::api::call::{call_with_payment128, CallResult};
+use ic_cdk::api::stable::{StableReader, StableWriter};
+use ic_cdk_macros::{init, post_upgrade, pre_upgrade, query, update};
+use ic_cycles_ledger::{
+    withdraw_memo, CreateCanisterArgs, CreateCanisterError, CreateCanisterSuccess, DepositArgs,
+    DepositResult, InitArgs, LedgerArgument, State, WithdrawArgs, WithdrawError, DECIMALS,
+};
+use ic_ic00_types::CanisterIdRecord;
+use ic_icrc1::{
+    endpoints::{
+        GetBlocksArgs, GetBlocksResponse, GetTransactionsRequest, GetTransactionsResponse,
+        StandardRecord, TransferArg, TransferError, Value,
+    },
+    Account, Memo, Operation, Transaction,
+};
+use ic_icrc1_ledger::{InitArgs as Icrc1InitArgs, Ledger, UpgradeArgs as Icrc1UpgradeArgs};
+use ic_ledger_canister_core::ledger::{
+    apply_transaction, archive_blocks, LedgerAccess, LedgerContext, LedgerData,
+    TransferError as CoreTransferError,
+};
+use ic_ledger_core::{block::BlockIndex, timestamp::TimeStamp, tokens::Tokens};
+use ic_nns_constants::CYCLES_MINTING_CANISTER_ID;
+use num_traits::ToPrimitive;
+use std::cell::RefCell;
+
+const MAX_MESSAGE_SIZE: u64 = 1024 * 1024;
+const TOKEN_NAME: &str = "Trillion Cycles";
+const TOKEN_SYMBOL: &str = "TCYCLES";
+
+thread_local! {
+    static STATE: RefCell<Option<State>> = RefCell::new(None);
+}
+
+declare_log_buffer!(name = LOG, capacity = 1000);
+
+struct Access;
+impl LedgerAccess for Access {
+    type Ledger = Ledger;
+
+    fn with_ledger<R>(f: impl FnOnce(&Ledger) -> R) -> R {
+        STATE.with(|cell| {
+            f(&cell
+                .borrow()
+                .as_ref()
+                .expect("ledger state not initialized")
+                .ledger)
+        })
+    }
+
+    fn with_ledger_mut<R>(f: impl FnOnce(&mut Ledger) -> R) -> R {
+        STATE.with(|cell| {
+            f(&mut cell
+                .borrow_mut()
+                .as_mut()
+                .expect("ledger state not initialized")
+                .ledger)
+        })
+    }
+}
+
+fn cycles_minting_canister_id() -> CanisterId {
+    STATE.with(|cell| {
+        cell.borrow()
+            .as_ref()
+            .expect("ledger state not initialized")
+            .cycles_minting_canister_id
+    })
+}
+
+fn now() -> TimeStamp {
+    TimeStamp::from_nanos_since_unix_epoch(ic_cdk::api::time())
+}
+
+/// Updates the certified data after a modification of the ledger. This must
+/// happen before the first async call to make sure that the blockchain state
+/// agrees with the certificate while archiving is in progress.
+fn certify() {
+    ic_cdk::api::set_certified_data(&Access::with_ledger(Ledger::root_hash));
+}
+
+/// Burns `amount` plus the fee from the `from` account. The fee stays in the
+/// balance of the ledger to pay for its operation.
+fn burn(
+    from: Account,
+    amount: Tokens,
+    created_at_time: Option<u64>,
+    memo: Option<Memo>,
+) -> Result<BlockIndex, CoreTransferError> {
+    Access::with_ledger_mut(|ledger| {
+        let tx = Transaction {
+            operation: Operation::Burn {
+                from,
+                amount: amount.saturating_add(ledger.transfer_fee()).get_e8s(),
+            },
+            created_at_time,
+            memo,
+        };
+        let (block_idx, _) = apply_transaction(ledger, tx, now(), Tokens::ZERO)?;
+        Ok(block_idx)
+    })
+}
+
+/// Mints back `amount` to `to` after an operation that burned the cycles
+/// failed.
+fn refund(to: Account, amount: Tokens, memo: Option<Memo>) -> Option<BlockIndex> {
+    if amount == Tokens::ZERO {
+        return None;
+    }
+    let result = Access::with_ledger_mut(|ledger| {
+        apply_transaction(
+            ledger,
+            Transaction::mint(to, amount, None, memo),
+            now(),
+            Tokens::ZERO,
+        )
+    });
+    match result {
+        Ok((block_idx, _)) => Some(block_idx),
+        Err(err) => {
+            log!(
+                LOG,
+                "failed to refund {} cycles to {}: {:?}",
+                amount.get_e8s(),
+                to,
+                err
+            );
+            None
+        }
+    }
+}
+
+#[init]
+fn init(args: LedgerArgument) {
+    match args {
+        LedgerArgument::Init(InitArgs {
+            transfer_fee,
+            archive_options,
+            cycles_minting_canister_id,
+        }) => {
+            let minting_account = Account::from(PrincipalId::from(ic_cdk::api::id()));
+            let ledger = Ledger::from_init_args(
+                Icrc1InitArgs {
+                    minting_account,
+                    initial_balances: vec![],
+                    transfer_fee,
+                    token_name: TOKEN_NAME.to_string(),
+                    token_symbol: TOKEN_SYMBOL.to_string(),
+                    metadata: vec![],
+                    archive_options,
+                },
+                now(),
+            );
+            STATE.with(|cell| {
+                *cell.borrow_mut() = Some(State {
+                    ledger,
+                    cycles_minting_canister_id: cycles_minting_canister_id
+                        .unwrap_or(CYCLES_MINTING_CANISTER_ID),
+                })
+            })
+        }
+        LedgerArgument::Upgrade(_) => {
+            panic!("Cannot initialize the canister with an Upgrade argument. Please provide an Init argument.");
+        }
+    }
+}
+
+#[pre_upgrade]
+fn pre_upgrade() {
+    STATE
+        .with(|cell| ciborium::ser::into_writer(&*cell.borrow(), StableWriter::default()))
+        .expect("failed to encode ledger state");
+}
+
+#[post_upgrade]
+fn post_upgrade(args: Option<LedgerArgument>) {
+    STATE.with(|cell| {
+        *cell.borrow_mut() = ciborium::de::from_reader(StableReader::default())
+            .expect("failed to decode ledger state");
+    });
+
+    if let Some(args) = args {
+        match args {
+            LedgerArgument::Init(_) => panic!("Cannot upgrade the canister with an Init argument. Please provide an Upgrade argument."),
+            LedgerArgument::Upgrade(Some(upgrade_args)) => {
+                Access::with_ledger_mut(|ledger| {
+                    ledger.upgrade_metadata(Icrc1UpgradeArgs {
+                        metadata: None,
+                        token_name: None,
+                        token_symbol: None,
+                        transfer_fee: upgrade_args.transfer_fee,
+                    })
+                });
+                if let Some(cycles_minting_canister_id) = upgrade_args.cycles_minting_canister_id {
+                    STATE.with(|cell| {
+                        cell.borrow_mut()
+                            .as_mut()
+                            .expect("ledger state not initialized")
+                            .cycles_minting_canister_id = cycles_minting_canister_id
+                    });
+                }
+            }
+            LedgerArgument::Upgrade(None) => {}
+        }
+    }
+}
+
+fn encode_metrics(w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> std::io::Result<()> {
+    w.encode_gauge(
+        "cycles_ledger_stable_memory_bytes",
+        (ic_cdk::api::stable::stable64_size() * 64 * 1024) as f64,
+        "Size of the stable memory allocated by this canister.",
+    )?;
+    w.encode_gauge(
+        "cycles_ledger_cycle_balance",
+        ic_cdk::api::canister_balance128() as f64,
+        "Cycle balance on the cycles ledger canister, including the cycles held for accounts.",
+    )?;
+    Access::with_ledger(|ledger| {
+        w.encode_gauge(
+            "cycles_ledger_total_supply",
+            ledger.balances().total_supply().get_e8s() as f64,
+            "Total number of cycles held for accounts.",
+        )?;
+        w.encode_gauge(
+            "cycles_ledger_balance_store_entries",
+            ledger.balances().store.len() as f64,
+            "Total number of accounts in the balance store.",
+        )?;
+        w.encode_gauge(
+            "cycles_ledger_transactions",
+            ledger.blockchain().blocks.len() as f64,
+            "Total number of transactions stored in the main memory.",
+        )?;
+        w.encode_gauge(
+            "cycles_ledger_archived_transactions",
+            ledger.blockchain().num_archived_blocks as f64,
+            "Total number of transactions sent to the archive.",
+        )?;
+        Ok(())
+    })
+}
+
+#[candid_method(query)]
+#[query]
+fn http_request(req: HttpRequest) -> HttpResponse {
+    if req.path() == "/metrics" {
+        let mut writer =
+            ic_metrics_encoder::MetricsEncoder::new(vec![], ic_cdk::api::time() as i64 / 1_000_000);
+
+        match encode_metrics(&mut writer) {
+            Ok(()) => HttpResponseBuilder::ok()
+                .header("Content-Type", "text/plain; version=0.0.4")
+                .with_body_and_content_length(writer.into_inner())
+                .build(),
+            Err(err) => {
+                HttpResponseBuilder::server_error(format!("Failed to encode metrics: {}", err))
+                    .build()
+            }
+        }
+    } else if req.path() == "/logs" {
+        use std::io::Write;
+        let mut buf = vec![];
+        for entry in export(&LOG) {
+            writeln!(
+                &mut buf,
+                "{} {}:{} {}",
+                entry.timestamp, entry.file, entry.line, entry.message
+            )
+            .unwrap();
+        }
+        HttpResponseBuilder::ok()
+            .header("Content-Type", "text/plain; charset=utf-8")
+            .with_body_and_content_length(buf)
+            .build()
+    } else {
+        HttpResponseBuilder::not_found().build()
+    }
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc1_name() -> String {
+    Access::with_ledger(|ledger| ledger.token_name().to_string())
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc1_symbol() -> String {
+    Access::with_ledger(|ledger| ledger.token_symbol().to_string())
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc1_decimals() -> u8 {
+    DECIMALS
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc1_fee() -> Nat {
+    Nat::from(Access::with_ledger(|ledger| ledger.transfer_fee()).get_e8s())
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc1_metadata() -> Vec<(String, Value)> {
+    // The underlying ICRC-1 ledger reports the decimals of ICP.
+    let mut metadata: Vec<(String, Value)> = Access::with_ledger(|ledger| ledger.metadata())
+        .into_iter()
+        .filter(|(key, _)| key != "icrc1:decimals")
+        .collect();
+    metadata.push(Value::entry("icrc1:decimals", DECIMALS as u64));
+    metadata
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc1_minting_account() -> Option<Account> {
+    Access::with_ledger(|ledger| Some(*ledger.minting_account()))
+}
+
+#[query(name = "icrc1_balance_of")]
+#[candid_method(query, rename = "icrc1_balance_of")]
+fn icrc1_balance_of(account: Account) -> Nat {
+    Access::with_ledger(|ledger| Nat::from(ledger.balances().account_balance(&account).get_e8s()))
+}
+
+#[query(name = "icrc1_total_supply")]
+#[candid_method(query, rename = "icrc1_total_supply")]
+fn icrc1_total_supply() -> Nat {
+    Access::with_ledger(|ledger| Nat::from(ledger.balances().total_supply().get_e8s()))
+}
+
+#[update]
+#[candid_method(update)]
+async fn icrc1_transfer(arg: TransferArg) -> Result<Nat, TransferError> {
+    let block_idx = Access::with_ledger_mut(|ledger| {
+        let from_account = Account {
+            owner: PrincipalId::from(ic_cdk::api::caller()),
+            subaccount: arg.from_subaccount,
+        };
+
+        if &arg.to == ledger.minting_account() {
+            return Err(TransferError::GenericError {
+                error_code: Nat::from(0u64),
+                message: "cycles can only leave the ledger through withdraw or create_canister"
+                    .to_string(),
+            });
+        }
+
+        let amount = match arg.amount.0.to_u64() {
+            Some(n) => Tokens::from_e8s(n),
+            None => {
+                // No one can have so many cycles
+                let balance = Nat::from(ledger.balances().account_balance(&from_account).get_e8s());
+                assert!(balance < arg.amount);
+                return Err(TransferError::InsufficientFunds { balance });
+            }
+        };
+
+        let expected_fee_tokens = ledger.transfer_fee();
+        let expected_fee = Nat::from(expected_fee_tokens.get_e8s());
+        if arg.fee.is_some() && arg.fee.as_ref() != Some(&expected_fee) {
+            return Err(TransferError::BadFee { expected_fee });
+        }
+
+        let tx = Transaction::transfer(
+            from_account,
+            arg.to,
+            amount,
+            arg.fee.map(|_| expected_fee_tokens),
+            arg.created_at_time
+                .map(TimeStamp::from_nanos_since_unix_epoch),
+            arg.memo,
+        );
+        let (block_idx, _) = apply_transaction(ledger, tx, now(), expected_fee_tokens)?;
+        Ok(block_idx)
+    })?;
+
+    certify();
+    archive_blocks::<Access>(&LOG, MAX_MESSAGE_SIZE).await;
+    Ok(Nat::from(block_idx))
+}
+
+/// Mints the cycles attached to the call to the `to` account. The call is
+/// rejected if the ledger cannot hold all the attached cycles.
+#[update]
+#[candid_method(update)]
+async fn deposit(arg: DepositArgs) -> DepositResult {
+    let amount = ic_cdk::api::call::msg_cycles_available128();
+    if amount == 0 {
+        ic_cdk::trap("No cycles available for deposit.");
+    }
+    let (block_idx, balance) = Access::with_ledger_mut(|ledger| {
+        // The total supply of the ledger cannot exceed u64::MAX.
+        let max_deposit = ledger.balances().token_pool.get_e8s();
+        if amount > max_deposit as u128 {
+            ic_cdk::trap(&format!(
+                "Cannot deposit {} cycles, the ledger can hold at most {} more cycles.",
+                amount, max_deposit
+            ));
+        }
+        let tx = Transaction::mint(arg.to, Tokens::from_e8s(amount as u64), None, arg.memo);
+        let (block_idx, _) = apply_transaction(ledger, tx, now(), Tokens::ZERO)
+            .unwrap_or_else(|err| ic_cdk::trap(&format!("Failed to deposit cycles: {:?}", err)));
+        (block_idx, ledger.balances().account_balance(&arg.to))
+    });
+    let accepted = ic_cdk::api::call::msg_cycles_accept128(amount);
+    assert_eq!(accepted, amount);
+
+    certify();
+    archive_blocks::<Access>(&LOG, MAX_MESSAGE_SIZE).await;
+    DepositResult {
+        txid: Nat::from(block_idx),
+        balance: Nat::from(balance.get_e8s()),
+    }
+}
+
+/// Sends `amount` cycles from the account of the caller to the canister `to`.
+#[update]
+#[candid_method(update)]
+async fn withdraw(arg: WithdrawArgs) -> Result<Nat, WithdrawError> {
+    let from = Account {
+        owner: PrincipalId::from(ic_cdk::api::caller()),
+        subaccount: arg.from_subaccount,
+    };
+    let canister_id = CanisterId::new(arg.to).map_err(|err| WithdrawError::GenericError {
+        error_code: Nat::from(0u64),
+        message: format!("Invalid canister id {}: {}", arg.to, err),
+    })?;
+    let amount = match arg.amount.0.to_u64() {
+        Some(n) => Tokens::from_e8s(n),
+        None => {
+            let balance = Access::with_ledger(|ledger| ledger.balances().account_balance(&from));
+            return Err(WithdrawError::InsufficientFunds {
+                balance: Nat::from(balance.get_e8s()),
+            });
+        }
+    };
+    let memo = withdraw_memo(&arg.to);
+
+    let burn_block = burn(from, amount, arg.created_at_time, Some(memo.clone()))?;
+    certify();
+
+    let result: CallResult<()> = call_with_payment128(
+        Principal::management_canister(),
+        "deposit_cycles",
+        (CanisterIdRecord::from(canister_id),),
+        amount.get_e8s() as u128,
+    )
+    .await;
+
+    if let Err((rejection_code, rejection_reason)) = result {
+        log!(
+            LOG,
+            "failed to withdraw {} cycles from {} to {}: {}",
+            amount.get_e8s(),
+            from,
+            arg.to,
+            rejection_reason
+        );
+        let refund_block = refund(from, amount, Some(memo));
+        certify();
+        archive_blocks::<Access>(&LOG, MAX_MESSAGE_SIZE).await;
+        return Err(WithdrawError::FailedToWithdraw {
+            burn_block: Nat::from(burn_block),
+            refund_block: refund_block.map(Nat::from),
+            rejection_code: rejection_code as i32,
+            rejection_reason,
+        });
+    }
+
+    archive_blocks::<Access>(&LOG, MAX_MESSAGE_SIZE).await;
+    Ok(Nat::from(burn_block))
+}
+
+/// Creates a canister through the cycles minting canister, paid for with
+/// `amount` cycles from the account of the caller.
+#[update]
+#[candid_method(update)]
+async fn create_canister(
+    arg: CreateCanisterArgs,
+) -> Result<CreateCanisterSuccess, CreateCanisterError> {
+    let caller = PrincipalId::from(ic_cdk::api::caller());
+    let from = Account {
+        owner: caller,
+        subaccount: arg.from_subaccount,
+    };
+    let amount = match arg.amount.0.to_u64() {
+        Some(n) => Tokens::from_e8s(n),
+        None => {
+            let balance = Access::with_ledger(|ledger| ledger.balances().account_balance(&from));
+            return Err(CreateCanisterError::InsufficientFunds {
+                balance: Nat::from(balance.get_e8s()),
+            });
+        }
+    };
+
+    // The cycles minting canister makes the calling canister the controller
+    // of the new canister unless told otherwise.
+    let mut creation_args = arg.creation_args.unwrap_or_default();
+    let mut settings = creation_args.settings.take().unwrap_or_default();
+    if settings.controllers.is_none() {
+        settings.controllers = Some(vec![caller]);
+    }
+    creation_args.settings = Some(settings);
+
+    let burn_block = burn(from, amount, arg.created_at_time, None)?;
+    certify();
+
+    let result: CallResult<(Result<CanisterId, CmcCreateCanisterError>,)> = call_with_payment128(
+        cycles_minting_canister_id().get().into(),
+        "create_canister",
+        (creation_args,),
+        amount.get_e8s() as u128,
+    )
+    .await;
+
+    let (refund_amount, error) = match result {
+        Ok((Ok(canister_id),)) => {
+            archive_blocks::<Access>(&LOG, MAX_MESSAGE_SIZE).await;
+            return Ok(CreateCanisterSuccess {
+                block_id: Nat::from(burn_block),
+                canister_id: canister_id.get(),
+            });
+        }
+        Ok((Err(err),)) => {
+            let refund_amount = match &err {
+                CmcCreateCanisterError::Refunded { refund_amount, .. } => *refund_amount,
+                CmcCreateCanisterError::RefundFailed { .. } => 0,
+            };
+            (refund_amount, err.to_string())
+        }
+        // The attached cycles are returned along with the rejection.
+        Err((code, msg)) => (
+            amount.get_e8s() as u128,
+            format!(
+                "The cycles minting canister rejected the call with code {:?}: {}",
+                code, msg
+            ),
+        ),
+    };
+    log!(
+        LOG,
+        "failed to create a canister for {} with {} cycles: {}",
+        from,
+        amount.get_e8s(),
+        error
+    );
+
+    let refund_amount = Tokens::from_e8s(refund_amount.min(amount.get_e8s() as u128) as u64);
+    let refund_block = refund(from, refund_amount, None);
+    certify();
+    archive_blocks::<Access>(&LOG, MAX_MESSAGE_SIZE).await;
+    Err(CreateCanisterError::FailedToCreate {
+        burn_block: Nat::from(burn_block),
+        refund_block: refund_block.map(Nat::from),
+        error,
+    })
+}
+
+#[query(name = "icrc1_supported_standards")]
+#[candid_method(query, rename = "icrc1_supported_standards")]
+fn supported_standards() -> Vec<StandardRecord> {
+    vec![StandardRecord {
+        name: "ICRC-1".to_string(),
+        url: "https://github.com/dfinity/ICRC-1".to_string(),
+    }]
+}
+
+#[query]
+#[candid_method(query)]
+fn get_transactions(req: GetTransactionsRequest) -> GetTransactionsResponse {
+    let (start, length) = req
+        .as_start_and_length()
+        .unwrap_or_else(|msg| ic_cdk::api::trap(&msg));
+    Access::with_ledger(|ledger| ledger.get_transactions(start, length as usize))
+}
+
+#[query]
+#[candid_method(query)]
+fn get_blocks(req: GetBlocksArgs) -> GetBlocksResponse {
+    let (start, length) = req
+        .as_start_and_length()
+        .unwrap_or_else(|msg| ic_cdk::api::trap(&msg));
+    Access::with_ledger(|ledger| ledger.get_blocks(start, length as usize))
+}
+
+candid::export_service!();
+
+#[query]
+fn __get_candid_interface_tmp_hack() -> String {
+    __export_service()
+}
+
+fn main() {}
+
+#[test]
+fn check_candid_interface() {
+    use candid::utils::{service_compatible, CandidSource};
+    use std::path::PathBuf;
+
+    let new_interface = __export_service();
+    let manifest_dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
+    for candid_file in ["cycles_ledger.did", "../ledger/txlog.did"].iter() {
+        let old_interface = manifest_dir.join(candid_file);
+        service_compatible(
+            CandidSource::Text(&new_interface),
+            CandidSource::File(old_interface.as_path()),
+        )
+        .unwrap_or_else(|e| {
+            panic!(
+                "the cycles ledger interface is not compatible with {}: {:?}",
+                old_interface.display(),
+                e
+            )
+        });
+    }
+}
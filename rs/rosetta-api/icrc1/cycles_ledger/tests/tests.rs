@@ -0,0 +1,306 @@
+use candid::{Decode, Encode, Nat};
+use ic_base_types::{CanisterId, PrincipalId};
+use ic_cycles_ledger::{
+    DepositArgs, DepositResult, InitArgs, LedgerArgument, WithdrawArgs, WithdrawError,
+};
+use ic_icrc1::{
+    endpoints::{TransferArg, TransferError},
+    Account,
+};
+use ic_ledger_canister_core::archive::ArchiveOptions;
+use ic_state_machine_tests::{Cycles, StateMachine, WasmResult};
+use ic_universal_canister::{call_args, wasm, UNIVERSAL_CANISTER_WASM};
+use num_traits::ToPrimitive;
+
+const FEE: u64 = 100_000_000;
+const DEPOSITOR_CYCLES: u128 = 100_000_000_000_000;
+
+fn cycles_ledger_wasm() -> Vec<u8> {
+    ic_test_utilities_load_wasm::load_wasm(
+        std::env::var("CARGO_MANIFEST_DIR").unwrap(),
+        "ic-cycles-ledger",
+        &[],
+    )
+}
+
+fn install_cycles_ledger(env: &StateMachine) -> CanisterId {
+    let args = LedgerArgument::Init(InitArgs {
+        transfer_fee: FEE,
+        archive_options: ArchiveOptions {
+            trigger_threshold: 1000,
+            num_blocks_to_archive: 100,
+            node_max_memory_size_bytes: None,
+            max_message_size_bytes: None,
+            controller_id: PrincipalId::new_user_test_id(100),
+            cycles_for_archive_creation: None,
+            max_transactions_per_response: None,
+        },
+        cycles_minting_canister_id: None,
+    });
+    env.install_canister(cycles_ledger_wasm(), Encode!(&args).unwrap(), None)
+        .unwrap()
+}
+
+// Installs a universal canister that attaches cycles to its calls.
+fn install_depositor(env: &StateMachine, cycles: u128) -> CanisterId {
+    env.install_canister_with_cycles(
+        UNIVERSAL_CANISTER_WASM.to_vec(),
+        vec![],
+        None,
+        Cycles::new(cycles),
+    )
+    .unwrap()
+}
+
+fn deposit(
+    env: &StateMachine,
+    ledger: CanisterId,
+    depositor: CanisterId,
+    to: Account,
+    cycles: u128,
+) -> Result<DepositResult, String> {
+    let arg = Encode!(&DepositArgs { to, memo: None }).unwrap();
+    let result = env
+        .execute_ingress(
+            depositor,
+            "update",
+            wasm()
+                .call_with_cycles(
+                    ledger,
+                    "deposit",
+                    call_args()
+                        .other_side(arg)
+                        .on_reject(wasm().reject_message().reject()),
+                    ((cycles >> 64) as u64, cycles as u64),
+                )
+                .build(),
+        )
+        .expect("failed to call the depositor");
+    match result {
+        WasmResult::Reply(bytes) => {
+            Ok(Decode!(&bytes, DepositResult).expect("failed to decode deposit response"))
+        }
+        WasmResult::Reject(reason) => Err(reason),
+    }
+}
+
+fn balance_of(env: &StateMachine, ledger: CanisterId, account: Account) -> u64 {
+    Decode!(
+        &env.query(ledger, "icrc1_balance_of", Encode!(&account).unwrap())
+            .expect("failed to query balance")
+            .bytes(),
+        Nat
+    )
+    .expect("failed to decode balance_of response")
+    .0
+    .to_u64()
+    .unwrap()
+}
+
+fn total_supply(env: &StateMachine, ledger: CanisterId) -> u64 {
+    Decode!(
+        &env.query(ledger, "icrc1_total_supply", Encode!().unwrap())
+            .expect("failed to query total supply")
+            .bytes(),
+        Nat
+    )
+    .expect("failed to decode total_supply response")
+    .0
+    .to_u64()
+    .unwrap()
+}
+
+fn transfer(
+    env: &StateMachine,
+    ledger: CanisterId,
+    from: Account,
+    to: Account,
+    amount: u64,
+) -> Result<Nat, TransferError> {
+    let arg = TransferArg {
+        from_subaccount: from.subaccount,
+        to,
+        fee: None,
+        created_at_time: None,
+        memo: None,
+        amount: Nat::from(amount),
+    };
+    Decode!(
+        &env.execute_ingress_as(from.owner, ledger, "icrc1_transfer", Encode!(&arg).unwrap())
+            .expect("failed to transfer funds")
+            .bytes(),
+        Result<Nat, TransferError>
+    )
+    .expect("failed to decode transfer response")
+}
+
+fn withdraw(
+    env: &StateMachine,
+    ledger: CanisterId,
+    from: Account,
+    to: CanisterId,
+    amount: u64,
+) -> Result<Nat, WithdrawError> {
+    let arg = WithdrawArgs {
+        from_subaccount: from.subaccount,
+        to: to.get(),
+        created_at_time: None,
+        amount: Nat::from(amount),
+    };
+    Decode!(
+        &env.execute_ingress_as(from.owner, ledger, "withdraw", Encode!(&arg).unwrap())
+            .expect("failed to withdraw cycles")
+            .bytes(),
+        Result<Nat, WithdrawError>
+    )
+    .expect("failed to decode withdraw response")
+}
+
+fn account(n: u64) -> Account {
+    Account {
+        owner: PrincipalId::new_user_test_id(n),
+        subaccount: None,
+    }
+}
+
+#[test]
+fn test_deposit() {
+    let env = StateMachine::new();
+    let ledger_id = install_cycles_ledger(&env);
+    let depositor_id = install_depositor(&env, DEPOSITOR_CYCLES);
+    let ledger_cycles = env.cycle_balance(ledger_id);
+
+    let result = deposit(&env, ledger_id, depositor_id, account(1), 1_000_000_000).unwrap();
+    assert_eq!(result.txid, Nat::from(0));
+    assert_eq!(result.balance, Nat::from(1_000_000_000));
+
+    let result = deposit(&env, ledger_id, depositor_id, account(1), 500_000_000).unwrap();
+    assert_eq!(result.txid, Nat::from(1));
+    assert_eq!(result.balance, Nat::from(1_500_000_000));
+
+    assert_eq!(balance_of(&env, ledger_id, account(1)), 1_500_000_000);
+    assert_eq!(total_supply(&env, ledger_id), 1_500_000_000);
+    assert!(env.cycle_balance(ledger_id) >= ledger_cycles + 1_500_000_000);
+}
+
+#[test]
+fn test_deposit_rejects_cycles_exceeding_the_max_supply() {
+    let env = StateMachine::new();
+    let ledger_id = install_cycles_ledger(&env);
+    let depositor_id = install_depositor(&env, u64::MAX as u128 + DEPOSITOR_CYCLES);
+
+    let depositor_cycles = env.cycle_balance(depositor_id);
+    let err = deposit(
+        &env,
+        ledger_id,
+        depositor_id,
+        account(1),
+        u64::MAX as u128 + 1,
+    )
+    .unwrap_err();
+    assert!(err.contains("the ledger can hold at most"), "{}", err);
+    assert_eq!(balance_of(&env, ledger_id, account(1)), 0);
+    assert_eq!(total_supply(&env, ledger_id), 0);
+    // The attached cycles were returned, only the cost of the call was charged.
+    assert!(env.cycle_balance(depositor_id) > depositor_cycles - DEPOSITOR_CYCLES / 100);
+
+    let err = deposit(&env, ledger_id, depositor_id, account(1), 0).unwrap_err();
+    assert!(err.contains("No cycles available for deposit"), "{}", err);
+}
+
+#[test]
+fn test_transfer() {
+    let env = StateMachine::new();
+    let ledger_id = install_cycles_ledger(&env);
+    let depositor_id = install_depositor(&env, DEPOSITOR_CYCLES);
+    deposit(&env, ledger_id, depositor_id, account(1), 1_000_000_000).unwrap();
+
+    assert_eq!(
+        transfer(&env, ledger_id, account(1), account(2), 300_000_000),
+        Ok(Nat::from(1))
+    );
+    assert_eq!(balance_of(&env, ledger_id, account(1)), 600_000_000);
+    assert_eq!(balance_of(&env, ledger_id, account(2)), 300_000_000);
+    assert_eq!(total_supply(&env, ledger_id), 900_000_000);
+
+    assert_eq!(
+        transfer(&env, ledger_id, account(2), account(1), 300_000_000),
+        Err(TransferError::InsufficientFunds {
+            balance: Nat::from(300_000_000)
+        })
+    );
+
+    // Cycles can only leave the ledger through withdraw or create_canister.
+    let minting_account = Account::from(ledger_id.get());
+    assert!(matches!(
+        transfer(&env, ledger_id, account(1), minting_account, 100_000_000),
+        Err(TransferError::GenericError { .. })
+    ));
+}
+
+#[test]
+fn test_withdraw() {
+    let env = StateMachine::new();
+    let ledger_id = install_cycles_ledger(&env);
+    let depositor_id = install_depositor(&env, DEPOSITOR_CYCLES);
+    deposit(&env, ledger_id, depositor_id, account(1), 1_000_000_000_000).unwrap();
+
+    let depositor_cycles = env.cycle_balance(depositor_id);
+    assert_eq!(
+        withdraw(&env, ledger_id, account(1), depositor_id, 400_000_000_000),
+        Ok(Nat::from(1))
+    );
+    assert_eq!(
+        env.cycle_balance(depositor_id),
+        depositor_cycles + 400_000_000_000
+    );
+    assert_eq!(
+        balance_of(&env, ledger_id, account(1)),
+        1_000_000_000_000 - 400_000_000_000 - FEE
+    );
+    assert_eq!(
+        total_supply(&env, ledger_id),
+        1_000_000_000_000 - 400_000_000_000 - FEE
+    );
+
+    assert_eq!(
+        withdraw(&env, ledger_id, account(2), depositor_id, 1),
+        Err(WithdrawError::InsufficientFunds {
+            balance: Nat::from(0)
+        })
+    );
+}
+
+#[test]
+fn test_withdraw_to_a_missing_canister_refunds_the_cycles() {
+    let env = StateMachine::new();
+    let ledger_id = install_cycles_ledger(&env);
+    let depositor_id = install_depositor(&env, DEPOSITOR_CYCLES);
+    deposit(&env, ledger_id, depositor_id, account(1), 1_000_000_000_000).unwrap();
+
+    let missing_canister_id = CanisterId::from_u64(1_000_000);
+    assert!(!env.canister_exists(missing_canister_id));
+    match withdraw(
+        &env,
+        ledger_id,
+        account(1),
+        missing_canister_id,
+        400_000_000_000,
+    ) {
+        Err(WithdrawError::FailedToWithdraw {
+            burn_block,
+            refund_block,
+            ..
+        }) => {
+            assert_eq!(burn_block, Nat::from(1));
+            assert_eq!(refund_block, Some(Nat::from(2)));
+        }
+        result => panic!("unexpected withdraw result {:?}", result),
+    }
+    // Only the fee is charged.
+    assert_eq!(
+        balance_of(&env, ledger_id, account(1)),
+        1_000_000_000_000 - FEE
+    );
+    assert_eq!(total_supply(&env, ledger_id), 1_000_000_000_000 - FEE);
+}
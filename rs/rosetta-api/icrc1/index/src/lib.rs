@@ -1,17 +1,23 @@
 use candid::{CandidType, Nat};
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_canister_profiler::{measure_span, SpanStats};
-use ic_cdk::api::stable::{StableReader, StableWriter};
+use ic_cdk::api::stable::StableReader;
 use ic_icrc1::endpoints::{ArchivedRange, QueryTxArchiveFn, TransactionRange};
 use ic_icrc1::{
-    endpoints::{GetTransactionsRequest, GetTransactionsResponse, Transaction, Transfer},
+    endpoints::{Approve, GetTransactionsRequest, GetTransactionsResponse, Transaction, Transfer},
     Account, Subaccount,
 };
+use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemory};
+use ic_stable_structures::{
+    cell::Cell as StableCell, BoundedStorable, DefaultMemoryImpl, StableBTreeMap, Storable,
+};
 use num_traits::cast::ToPrimitive;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::cell::RefCell;
-use std::collections::{btree_map, BTreeMap};
+use std::collections::BTreeMap;
 use std::ops::Bound::{Included, Unbounded};
+use std::time::Duration;
 
 // Maximum number of subaccounts that can be returned
 // by [list_subaccounts]
@@ -21,86 +27,248 @@ const MAX_SUBACCOUNTS_PER_RESPONSE: usize = 1000;
 // by [get_account_transactions]
 const MAX_TRANSACTIONS_PER_RESPONSE: usize = 1000;
 
+// Maximum number of blocks to fetch from the Ledger
+// in a single [build_index] batch
+const MAX_BLOCKS_PER_BATCH: usize = 1000;
+
 // One second in nanosecond
 const SEC_NANOS: f64 = 1_000_000_000_f64;
 const DEFAULT_MAX_WAIT_TIME_NANOS: f64 = 60_f64 * SEC_NANOS;
 
 const LOG_PREFIX: &str = "[ic-icrc1-index] ";
 
+// The magic bytes the [MemoryManager] writes at the beginning of the
+// stable memory. Versions of the Index that predate the stable structures
+// serialized their whole state to the stable memory on upgrade.
+const MEMORY_MANAGER_MAGIC: &[u8; 3] = b"MGR";
+
+const STATE_MEMORY_ID: MemoryId = MemoryId::new(0);
+const ACCOUNT_TXS_MEMORY_ID: MemoryId = MemoryId::new(1);
+const ACCOUNT_DATA_MEMORY_ID: MemoryId = MemoryId::new(2);
+
+const MAX_PRINCIPAL_LEN: usize = PrincipalId::MAX_LENGTH_IN_BYTES;
+const ACCOUNT_KEY_SIZE: usize = 1 + MAX_PRINCIPAL_LEN + 32;
+const ACCOUNT_TX_KEY_SIZE: usize = ACCOUNT_KEY_SIZE + 8;
+
 type TxId = Nat;
+type Memory = VirtualMemory<DefaultMemoryImpl>;
+type StateCell = StableCell<State, Memory>;
 
-#[derive(Serialize, Deserialize, Debug)]
-struct Index {
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct State {
     // The id of the Ledger canister to index
     pub ledger_id: CanisterId,
 
     // The next txid to query from the Ledger
     pub next_txid: u64,
+}
 
-    // Whether there is a [heartbeat] running right now
-    pub is_heartbeat_running: bool,
+// NOTE: the default state is dysfunctional, but it's convenient to have
+// a Default impl for the initialization of the [STATE] variable below.
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            ledger_id: CanisterId::ic_00(),
+            next_txid: 0,
+        }
+    }
+}
 
-    // Next time to call [build_index]
-    #[serde(default)]
-    pub next_build_index_time: u64,
+impl Storable for State {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let mut buf = vec![];
+        ciborium::ser::into_writer(self, &mut buf).expect("failed to encode index state");
+        Cow::Owned(buf)
+    }
 
-    // The index of transactions per account
-    pub account_index: BTreeMap<PrincipalId, BTreeMap<Subaccount, Vec<u64>>>,
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        ciborium::de::from_reader(&bytes[..]).expect("failed to decode index state")
+    }
+}
 
-    // The number of unique (principal, subaccount) pairs in the index.
-    pub accounts_num: u64,
+// The part of the state of the pre-stable-structures Index that
+// survives the migration. All the other fields are ignored and the
+// Index rebuilds them from the Ledger.
+#[derive(Deserialize)]
+struct LegacyIndex {
+    pub ledger_id: CanisterId,
 }
 
-impl Index {
-    fn from(init_args: InitArgs) -> Self {
-        Self {
-            ledger_id: init_args.ledger_id,
-            next_txid: 0,
-            is_heartbeat_running: false,
-            next_build_index_time: 0,
-            account_index: BTreeMap::new(),
-            accounts_num: 0,
-        }
+/// A fixed-size encoding of an account that preserves the grouping of
+/// subaccounts by principal:
+/// ```text
+/// [principal length][principal bytes padded to 29 bytes][effective subaccount]
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+struct AccountKey([u8; ACCOUNT_KEY_SIZE]);
+
+impl AccountKey {
+    fn new(owner: &PrincipalId, subaccount: &Subaccount) -> Self {
+        let principal = owner.as_slice();
+        let mut key = [0u8; ACCOUNT_KEY_SIZE];
+        key[0] = principal.len() as u8;
+        key[1..1 + principal.len()].copy_from_slice(principal);
+        key[1 + MAX_PRINCIPAL_LEN..].copy_from_slice(subaccount);
+        Self(key)
+    }
+
+    fn owner(&self) -> PrincipalId {
+        let len = self.0[0] as usize;
+        PrincipalId::try_from(&self.0[1..1 + len]).expect("bug: invalid principal in account key")
+    }
+
+    fn subaccount(&self) -> Subaccount {
+        let mut subaccount = [0u8; 32];
+        subaccount.copy_from_slice(&self.0[1 + MAX_PRINCIPAL_LEN..]);
+        subaccount
     }
 }
 
-thread_local! {
-    static INDEX: RefCell<Option<Index>> = RefCell::new(None);
-    static PROFILING_DATA: RefCell<SpanStats> = RefCell::new(SpanStats::default());
+impl From<&Account> for AccountKey {
+    fn from(account: &Account) -> Self {
+        Self::new(&account.owner, account.effective_subaccount())
+    }
 }
 
-fn with_index<R>(f: impl FnOnce(&Index) -> R) -> R {
-    INDEX.with(|idx| f(idx.borrow().as_ref().expect("Index state is not set!")))
+impl Storable for AccountKey {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Borrowed(&self.0)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        let mut key = [0u8; ACCOUNT_KEY_SIZE];
+        key.copy_from_slice(&bytes);
+        Self(key)
+    }
 }
 
-fn with_index_mut<R>(f: impl FnOnce(&mut Index) -> R) -> R {
-    INDEX.with(|idx| f(idx.borrow_mut().as_mut().expect("Index state is not set!")))
+impl BoundedStorable for AccountKey {
+    const MAX_SIZE: u32 = ACCOUNT_KEY_SIZE as u32;
+    const IS_FIXED_SIZE: bool = true;
 }
 
-pub fn ledger_id() -> CanisterId {
-    with_index(|idx| idx.ledger_id)
+/// The key of the account to transaction index.
+///
+/// The txid is stored as `u64::MAX - txid` in big-endian so that the
+/// transactions of an account are sorted from the most recent to the
+/// least recent.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+struct AccountTxKey([u8; ACCOUNT_TX_KEY_SIZE]);
+
+impl AccountTxKey {
+    fn new(account: &AccountKey, txid: u64) -> Self {
+        let mut key = [0u8; ACCOUNT_TX_KEY_SIZE];
+        key[..ACCOUNT_KEY_SIZE].copy_from_slice(&account.0);
+        key[ACCOUNT_KEY_SIZE..].copy_from_slice(&(u64::MAX - txid).to_be_bytes());
+        Self(key)
+    }
+
+    fn account(&self) -> AccountKey {
+        let mut key = [0u8; ACCOUNT_KEY_SIZE];
+        key.copy_from_slice(&self.0[..ACCOUNT_KEY_SIZE]);
+        AccountKey(key)
+    }
+
+    fn txid(&self) -> u64 {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&self.0[ACCOUNT_KEY_SIZE..]);
+        u64::MAX - u64::from_be_bytes(bytes)
+    }
 }
 
-struct HeartbeatGuard;
+impl Storable for AccountTxKey {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Borrowed(&self.0)
+    }
 
-impl HeartbeatGuard {
-    fn new() -> Option<HeartbeatGuard> {
-        with_index_mut(|idx| {
-            if idx.is_heartbeat_running {
-                return None;
-            }
-            idx.is_heartbeat_running = true;
-            Some(HeartbeatGuard {})
-        })
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        let mut key = [0u8; ACCOUNT_TX_KEY_SIZE];
+        key.copy_from_slice(&bytes);
+        Self(key)
     }
 }
 
-impl Drop for HeartbeatGuard {
-    fn drop(&mut self) {
-        with_index_mut(|idx| idx.is_heartbeat_running = false)
+impl BoundedStorable for AccountTxKey {
+    const MAX_SIZE: u32 = ACCOUNT_TX_KEY_SIZE as u32;
+    const IS_FIXED_SIZE: bool = true;
+}
+
+/// Per-account data derived from the transactions indexed so far.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct AccountData {
+    balance: u64,
+    oldest_txid: u64,
+}
+
+impl Storable for AccountData {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let mut bytes = Vec::with_capacity(16);
+        bytes.extend_from_slice(&self.balance.to_be_bytes());
+        bytes.extend_from_slice(&self.oldest_txid.to_be_bytes());
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        let mut balance = [0u8; 8];
+        let mut oldest_txid = [0u8; 8];
+        balance.copy_from_slice(&bytes[..8]);
+        oldest_txid.copy_from_slice(&bytes[8..16]);
+        Self {
+            balance: u64::from_be_bytes(balance),
+            oldest_txid: u64::from_be_bytes(oldest_txid),
+        }
     }
 }
 
+impl BoundedStorable for AccountData {
+    const MAX_SIZE: u32 = 16;
+    const IS_FIXED_SIZE: bool = true;
+}
+
+thread_local! {
+    static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> = RefCell::new(
+        MemoryManager::init(DefaultMemoryImpl::default())
+    );
+
+    /// The configuration and the sync progress of the Index.
+    static STATE: RefCell<StateCell> = MEMORY_MANAGER.with(|memory_manager| {
+        RefCell::new(StateCell::init(memory_manager.borrow().get(STATE_MEMORY_ID), State::default())
+            .expect("failed to initialize stable cell"))
+    });
+
+    /// The index of transactions per account.
+    static ACCOUNT_TXS: RefCell<StableBTreeMap<AccountTxKey, (), Memory>> = MEMORY_MANAGER.with(|memory_manager| {
+        RefCell::new(StableBTreeMap::init(memory_manager.borrow().get(ACCOUNT_TXS_MEMORY_ID)))
+    });
+
+    /// The balance and the oldest transaction of every account that
+    /// appeared in at least one transaction.
+    static ACCOUNT_DATA: RefCell<StableBTreeMap<AccountKey, AccountData, Memory>> = MEMORY_MANAGER.with(|memory_manager| {
+        RefCell::new(StableBTreeMap::init(memory_manager.borrow().get(ACCOUNT_DATA_MEMORY_ID)))
+    });
+
+    static PROFILING_DATA: RefCell<SpanStats> = RefCell::new(SpanStats::default());
+}
+
+fn with_state<R>(f: impl FnOnce(&State) -> R) -> R {
+    STATE.with(|cell| f(cell.borrow().get()))
+}
+
+fn with_state_mut<R>(f: impl FnOnce(&mut State) -> R) -> R {
+    STATE.with(|cell| {
+        let mut state = cell.borrow().get().clone();
+        let result = f(&mut state);
+        cell.borrow_mut()
+            .set(state)
+            .expect("failed to update index state");
+        result
+    })
+}
+
+pub fn ledger_id() -> CanisterId {
+    with_state(|state| state.ledger_id)
+}
+
 #[derive(CandidType, Clone, Debug, candid::Deserialize)]
 pub struct InitArgs {
     // The Ledger canister id of the Ledger to index.
@@ -108,7 +276,13 @@ pub struct InitArgs {
 }
 
 pub fn init(init_args: InitArgs) {
-    INDEX.with(|idx| *idx.borrow_mut() = Some(Index::from(init_args)));
+    with_state_mut(|state| {
+        *state = State {
+            ledger_id: init_args.ledger_id,
+            next_txid: 0,
+        }
+    });
+    schedule_build_index(Duration::ZERO);
 }
 
 #[derive(CandidType, Debug, candid::Deserialize, PartialEq, Eq)]
@@ -152,38 +326,62 @@ pub struct ListSubaccountsArgs {
     pub start: Option<Subaccount>,
 }
 
+#[derive(CandidType, Debug, candid::Deserialize, PartialEq, Eq)]
+pub struct Status {
+    // The number of Ledger blocks the Index has processed.
+    pub num_blocks_synced: Nat,
+}
+
 pub fn list_subaccounts(list_subaccounts_args: ListSubaccountsArgs) -> Vec<Subaccount> {
-    with_index(
-        |idx| match idx.account_index.get(&list_subaccounts_args.owner) {
-            None => vec![],
-            Some(subaccounts) => subaccounts
-                .range((
-                    list_subaccounts_args
-                        .start
-                        .map(Included)
-                        .unwrap_or(Unbounded),
-                    Unbounded,
-                ))
-                .take(MAX_SUBACCOUNTS_PER_RESPONSE)
-                .map(|(k, _)| *k)
-                .collect(),
-        },
-    )
+    let owner = list_subaccounts_args.owner;
+    let start = AccountKey::new(
+        &owner,
+        list_subaccounts_args
+            .start
+            .as_ref()
+            .unwrap_or(ic_icrc1::DEFAULT_SUBACCOUNT),
+    );
+    ACCOUNT_DATA.with(|data| {
+        data.borrow()
+            .range((Included(start), Unbounded))
+            .take_while(|(key, _)| key.owner() == owner)
+            .take(MAX_SUBACCOUNTS_PER_RESPONSE)
+            .map(|(key, _)| key.subaccount())
+            .collect()
+    })
 }
 
-pub async fn heartbeat() {
-    let _guard = match HeartbeatGuard::new() {
-        Some(guard) => guard,
-        None => return,
-    };
+/// Returns the balance of the account computed from the transactions
+/// indexed so far.
+pub fn icrc1_balance_of(account: Account) -> Nat {
+    let balance = ACCOUNT_DATA.with(|data| {
+        data.borrow()
+            .get(&AccountKey::from(&account))
+            .map(|data| data.balance)
+            .unwrap_or_default()
+    });
+    Nat::from(balance)
+}
 
-    if ic_cdk::api::time() < with_index(|idx| idx.next_build_index_time) {
-        return;
+pub fn status() -> Status {
+    Status {
+        num_blocks_synced: Nat::from(with_state(|state| state.next_txid)),
     }
+}
 
-    if let Err(err) = build_index().await {
-        ic_cdk::eprintln!("{}Failed to fetch blocks: {}", LOG_PREFIX, err);
-    }
+fn schedule_build_index(delay: Duration) {
+    ic_cdk_timers::set_timer(delay, || {
+        ic_cdk::spawn(async {
+            let wait_time = match build_index().await {
+                Ok(tx_indexed_count) => compute_wait_time(tx_indexed_count),
+                Err(err) => {
+                    ic_cdk::eprintln!("{}Failed to fetch blocks: {}", LOG_PREFIX, err);
+                    DEFAULT_MAX_WAIT_TIME_NANOS as u64
+                }
+            };
+            schedule_build_index(Duration::from_nanos(wait_time));
+        })
+    });
 }
 
 async fn get_transactions_from_ledger(
@@ -219,9 +417,9 @@ async fn get_transactions_from_archive(
     Ok(res)
 }
 
-async fn build_index() -> Result<(), String> {
-    let next_txid = with_index(|idx| idx.next_txid);
-    let res = get_transactions_from_ledger(next_txid, MAX_TRANSACTIONS_PER_RESPONSE).await?;
+async fn build_index() -> Result<usize, String> {
+    let next_txid = with_state(|state| state.next_txid);
+    let res = get_transactions_from_ledger(next_txid, MAX_BLOCKS_PER_BATCH).await?;
     let mut tx_indexed_cout: usize = 0;
     for archived in res.archived_transactions {
         // The archive node limits the number of transactions returned by a
@@ -258,77 +456,157 @@ async fn build_index() -> Result<(), String> {
         idx += 1;
         tx_indexed_cout += 1;
     }
-    let wait_time: u64 = compute_wait_time(tx_indexed_cout);
     ic_cdk::eprintln!(
         "{}Indexed: {} waiting : {}",
         LOG_PREFIX,
         tx_indexed_cout,
-        wait_time
+        compute_wait_time(tx_indexed_cout)
     );
-    with_index_mut(|idx| idx.next_build_index_time = ic_cdk::api::time() + wait_time);
-    Ok(())
+    Ok(tx_indexed_cout)
 }
 
 /// Compute the waiting time before next indexing
 pub fn compute_wait_time(indexed_tx_count: usize) -> u64 {
-    if indexed_tx_count >= MAX_TRANSACTIONS_PER_RESPONSE {
-        // If we indexed more than MAX_SPEED_THRESHOLD,
-        // we index again on the next heartbeat.
+    if indexed_tx_count >= MAX_BLOCKS_PER_BATCH {
+        // If we indexed a full batch, there are likely more
+        // transactions to index so we index again right away.
         return 0;
     }
-    ((1_f64 - indexed_tx_count as f64 / MAX_TRANSACTIONS_PER_RESPONSE as f64)
-        * DEFAULT_MAX_WAIT_TIME_NANOS) as u64
+    ((1_f64 - indexed_tx_count as f64 / MAX_BLOCKS_PER_BATCH as f64) * DEFAULT_MAX_WAIT_TIME_NANOS)
+        as u64
+}
+
+fn nat_to_u64(n: &Nat) -> Result<u64, String> {
+    n.0.to_u64().ok_or_else(|| {
+        format!(
+            "The Ledger returned an amount that is not a valid u64: {}",
+            n
+        )
+    })
 }
 
 fn index_transaction(txid: u64, transaction: Transaction) -> Result<(), String> {
-    match transaction.kind.as_str() {
-        "mint" => {
-            let mint = transaction
-                .mint
-                .ok_or("Got a transaction with kind 'mint' but the mint field was None")?
-                .to;
-            add_tx(txid, mint);
-            Ok(())
-        }
-        "burn" => {
-            let burn = transaction
-                .burn
-                .ok_or("Got a transaction with kind 'burn' but the burn field was None")?
-                .from;
-            add_tx(txid, burn);
-            Ok(())
-        }
-        "transfer" => {
-            let Transfer { from, to, .. } = transaction
-                .transfer
-                .ok_or("Got a transaction with kind 'transfer' but the transfer field was None")?;
-            add_tx(txid, from);
-            add_tx(txid, to);
-            Ok(())
+    // The accounts involved in the transaction and the balance changes it
+    // causes. A transaction can debit and credit the same account.
+    let (accounts, balance_changes): (Vec<Account>, Vec<(Account, i128)>) =
+        match transaction.kind.as_str() {
+            "mint" => {
+                let mint = transaction
+                    .mint
+                    .ok_or("Got a transaction with kind 'mint' but the mint field was None")?;
+                let amount = nat_to_u64(&mint.amount)?;
+                (vec![mint.to], vec![(mint.to, amount as i128)])
+            }
+            "burn" => {
+                let burn = transaction
+                    .burn
+                    .ok_or("Got a transaction with kind 'burn' but the burn field was None")?;
+                let amount = nat_to_u64(&burn.amount)?;
+                (vec![burn.from], vec![(burn.from, -(amount as i128))])
+            }
+            "transfer" => {
+                let Transfer {
+                    from,
+                    to,
+                    amount,
+                    fee,
+                    ..
+                } = transaction.transfer.ok_or(
+                    "Got a transaction with kind 'transfer' but the transfer field was None",
+                )?;
+                let amount = nat_to_u64(&amount)?;
+                let fee = fee.as_ref().map(nat_to_u64).transpose()?.unwrap_or(0);
+                (
+                    vec![from, to],
+                    vec![
+                        (from, -(amount as i128) - fee as i128),
+                        (to, amount as i128),
+                    ],
+                )
+            }
+            "approve" => {
+                let Approve { from, fee, .. } = transaction.approve.ok_or(
+                    "Got a transaction with kind 'approve' but the approve field was None",
+                )?;
+                let fee = fee.as_ref().map(nat_to_u64).transpose()?.unwrap_or(0);
+                (vec![from], vec![(from, -(fee as i128))])
+            }
+            kind => return Err(format!("Found transaction of unknown kind {}", kind)),
+        };
+
+    let new_balances = compute_new_balances(txid, balance_changes)?;
+    for account in accounts {
+        add_tx(txid, account);
+    }
+    ACCOUNT_DATA.with(|data| {
+        let mut data = data.borrow_mut();
+        for (key, balance) in new_balances {
+            let mut account_data = data
+                .get(&key)
+                .expect("bug: the account must have been indexed");
+            account_data.balance = balance;
+            data.insert(key, account_data);
         }
-        kind => Err(format!("Found transaction of unknown kind {}", kind)),
+    });
+    with_state_mut(|state| state.next_txid = txid + 1);
+    Ok(())
+}
+
+/// Applies the balance changes of the transaction with the given txid to the
+/// current balances without modifying the index. Returns an error if any of
+/// the resulting balances is negative or doesn't fit into a u64, which means
+/// that the index disagrees with the Ledger.
+fn compute_new_balances(
+    txid: u64,
+    balance_changes: Vec<(Account, i128)>,
+) -> Result<BTreeMap<AccountKey, u64>, String> {
+    let mut deltas: BTreeMap<AccountKey, (Account, i128)> = BTreeMap::new();
+    for (account, delta) in balance_changes {
+        deltas
+            .entry(AccountKey::from(&account))
+            .or_insert((account, 0))
+            .1 += delta;
+    }
+    let mut new_balances = BTreeMap::new();
+    for (key, (account, delta)) in deltas {
+        let balance = ACCOUNT_DATA
+            .with(|data| data.borrow().get(&key))
+            .map(|data| data.balance)
+            .unwrap_or_default();
+        let new_balance = u64::try_from(balance as i128 + delta).map_err(|_| {
+            format!(
+                "Transaction {} changes the balance {} of account {} by {}",
+                txid, balance, account, delta
+            )
+        })?;
+        new_balances.insert(key, new_balance);
     }
+    Ok(new_balances)
 }
 
 fn add_tx(txid: u64, account: Account) {
     measure_span(&PROFILING_DATA, "add_tx", move || {
-        with_index_mut(|idx| {
-            let account_index = match idx.account_index.entry(account.owner) {
-                btree_map::Entry::Vacant(v) => v.insert(BTreeMap::new()),
-                btree_map::Entry::Occupied(o) => o.into_mut(),
-            };
-            match account_index.entry(*account.effective_subaccount()) {
-                btree_map::Entry::Vacant(v) => {
-                    idx.accounts_num += 1;
-                    let _ = v.insert(vec![txid]);
-                }
-                btree_map::Entry::Occupied(o) => o.into_mut().push(txid),
-            };
-            idx.next_txid = txid + 1;
+        let key = AccountKey::from(&account);
+        ACCOUNT_TXS.with(|txs| txs.borrow_mut().insert(AccountTxKey::new(&key, txid), ()));
+        ACCOUNT_DATA.with(|data| {
+            let mut data = data.borrow_mut();
+            if data.get(&key).is_none() {
+                data.insert(
+                    key,
+                    AccountData {
+                        balance: 0,
+                        oldest_txid: txid,
+                    },
+                );
+            }
         });
     })
 }
 
+fn accounts_num() -> u64 {
+    ACCOUNT_DATA.with(|data| data.borrow().len())
+}
+
 /// Returns args.max_results transactions ids of the account args.account
 /// since args.start.
 /// The transactions will be sorted from the most recent to the least recent.
@@ -352,32 +630,17 @@ fn get_account_transactions_ids(args: GetAccountTransactionsArgs) -> Vec<u64> {
         .0
         .to_usize()
         .unwrap();
-    with_index(|idx| {
-        let account_index = match idx.account_index.get(&args.account.owner) {
-            Some(account_index) => account_index,
-            None => return vec![],
-        };
-        let txids = match account_index.get(args.account.effective_subaccount()) {
-            Some(txids) => txids,
-            None => return vec![],
-        };
-        let start_pos = match &args.start {
-            None => txids.len() - 1,
-            // binary_search doc:
-            // If the value is found then Result::Ok is returned, containing the index
-            // of the matching element. ... If the value is not found then Result::Err
-            // is returned, containing the index where a matching element could be
-            // inserted while maintaining sorted order.
-            Some(start) => match txids.binary_search(&start.0.to_u64().unwrap()) {
-                Ok(i) => i,
-                Err(i) if i > 0 => i - 1,
-                _ => return vec![],
-            },
-        };
-        let end_pos = (start_pos as i64 - max_results as i64 + 1).max(0) as usize;
-        (end_pos..=start_pos)
-            .rev()
-            .map(|pos| *txids.get(pos).unwrap())
+    let account = AccountKey::from(&args.account);
+    let start = args
+        .start
+        .map(|start| start.0.to_u64().unwrap())
+        .unwrap_or(u64::MAX);
+    ACCOUNT_TXS.with(|txs| {
+        txs.borrow()
+            .range((Included(AccountTxKey::new(&account, start)), Unbounded))
+            .take_while(|(key, _)| key.account() == account)
+            .take(max_results)
+            .map(|(key, _)| key.txid())
             .collect()
     })
 }
@@ -430,12 +693,10 @@ pub async fn get_account_transactions(args: GetAccountTransactionsArgs) -> GetTr
 }
 
 fn get_oldest_txid(account: &Account) -> Option<Nat> {
-    with_index(|idx| {
-        idx.account_index
-            .get(&account.owner)?
-            .get(account.effective_subaccount())?
-            .first()
-            .map(|txid| Nat::from(*txid))
+    ACCOUNT_DATA.with(|data| {
+        data.borrow()
+            .get(&AccountKey::from(account))
+            .map(|data| Nat::from(data.oldest_txid))
     })
 }
 
@@ -457,12 +718,12 @@ pub fn encode_metrics(w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> st
     )?;
     w.encode_gauge(
         "index_number_of_transactions",
-        with_index(|idx| idx.next_txid) as f64,
-        "Total number of transaction stored in the main memory.",
+        with_state(|state| state.next_txid) as f64,
+        "Total number of transactions indexed.",
     )?;
     w.encode_gauge(
         "index_number_of_accounts",
-        with_index(|idx| idx.accounts_num) as f64,
+        accounts_num() as f64,
         "Total number of accounts indexed.",
     )?;
     PROFILING_DATA.with(|cell| -> std::io::Result<()> {
@@ -475,35 +736,52 @@ pub fn encode_metrics(w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> st
     Ok(())
 }
 
-pub fn pre_upgrade() {
-    ic_cdk::println!("Running pre-upgrade on index canister...");
-    with_index(|idx| ciborium::ser::into_writer(idx, StableWriter::default()))
-        .expect("failed to encode index state");
+/// Returns true if the stable memory contains the state of an Index
+/// that predates the stable structures.
+fn has_legacy_state() -> bool {
+    if ic_cdk::api::stable::stable64_size() == 0 {
+        return false;
+    }
+    let mut magic = [0u8; 3];
+    ic_cdk::api::stable::stable64_read(0, &mut magic);
+    &magic != MEMORY_MANAGER_MAGIC
 }
 
 pub fn post_upgrade() {
     ic_cdk::println!("Running post-upgrade on index canister...");
-    INDEX.with(|idx| {
-        *idx.borrow_mut() = Some(
-            ciborium::de::from_reader(StableReader::default())
-                .expect("failed to decode index state"),
-        )
-    });
+    // NB. The legacy state must be read before the first access to the
+    // stable structures because initializing the memory manager
+    // overwrites the beginning of the stable memory.
+    if has_legacy_state() {
+        let legacy: LegacyIndex = ciborium::de::from_reader(StableReader::default())
+            .expect("failed to decode legacy index state");
+        ic_cdk::println!(
+            "{}Migrating the index to stable structures, re-indexing from block 0",
+            LOG_PREFIX
+        );
+        with_state_mut(|state| {
+            *state = State {
+                ledger_id: legacy.ledger_id,
+                next_txid: 0,
+            }
+        });
+    }
+    schedule_build_index(Duration::ZERO);
 }
 
 #[cfg(test)]
 mod tests {
-    use std::collections::{btree_map, BTreeMap};
-
     use candid::Nat;
-    use ic_base_types::{CanisterId, PrincipalId};
+    use ic_base_types::PrincipalId;
     use ic_icrc1::Account;
+    use ic_stable_structures::StableBTreeMap;
 
-    use proptest::{option, proptest};
+    use proptest::{option, prelude::any, proptest};
 
     use crate::{
-        add_tx, get_account_transactions_ids, with_index, GetAccountTransactionsArgs,
-        HeartbeatGuard, Index, INDEX,
+        accounts_num, add_tx, compute_new_balances, get_account_transactions_ids, AccountKey,
+        AccountTxKey, GetAccountTransactionsArgs, ACCOUNT_DATA, ACCOUNT_DATA_MEMORY_ID,
+        ACCOUNT_TXS, ACCOUNT_TXS_MEMORY_ID, MEMORY_MANAGER,
     };
 
     fn account(n: u64) -> Account {
@@ -514,28 +792,20 @@ mod tests {
     }
 
     fn init_state(txids: Vec<(Account, Vec<u64>)>) {
-        INDEX.with(|idx| {
-            let mut account_index = BTreeMap::new();
-            for (account, new_txids) in txids {
-                let account_index = match account_index.entry(account.owner) {
-                    btree_map::Entry::Vacant(v) => v.insert(BTreeMap::new()),
-                    btree_map::Entry::Occupied(o) => o.into_mut(),
-                };
-                let txids = match account_index.entry(*account.effective_subaccount()) {
-                    btree_map::Entry::Vacant(v) => v.insert(vec![]),
-                    btree_map::Entry::Occupied(o) => o.into_mut(),
-                };
-                txids.extend(new_txids);
-            }
-            *idx.borrow_mut() = Some(Index {
-                ledger_id: CanisterId::from_u64(42),
-                next_txid: 0,
-                is_heartbeat_running: false,
-                next_build_index_time: 0,
-                account_index,
-                accounts_num: 0,
+        MEMORY_MANAGER.with(|memory_manager| {
+            let memory_manager = memory_manager.borrow();
+            ACCOUNT_TXS.with(|txs| {
+                *txs.borrow_mut() = StableBTreeMap::new(memory_manager.get(ACCOUNT_TXS_MEMORY_ID))
+            });
+            ACCOUNT_DATA.with(|data| {
+                *data.borrow_mut() = StableBTreeMap::new(memory_manager.get(ACCOUNT_DATA_MEMORY_ID))
             });
         });
+        for (account, txids) in txids {
+            for txid in txids {
+                add_tx(txid, account);
+            }
+        }
     }
 
     fn check_get_account_transactions_ids(
@@ -679,11 +949,11 @@ mod tests {
                 subaccount: Some(subaccount),
             };
             add_tx(next_txid.next().unwrap(), account);
-            with_index(|idx| idx.accounts_num)
+            accounts_num()
         };
 
         // no accounts at the beginning
-        assert_eq!(0, with_index(|idx| idx.accounts_num));
+        assert_eq!(0, accounts_num());
 
         // new tx for new principal => add one account
         assert_eq!(1, add_tx_for(0, 0));
@@ -712,20 +982,40 @@ mod tests {
         assert_eq!(8, add_tx_for(2, 10));
     }
 
-    #[test]
-    fn heartbeat_guard_test() {
-        init_state(vec![]);
+    proptest! {
+        #[test]
+        fn account_tx_key_roundtrip(principal in 0u64.., subaccount in any::<[u8; 32]>(), txid in any::<u64>()) {
+            let owner = PrincipalId::new_user_test_id(principal);
+            let account = AccountKey::new(&owner, &subaccount);
+            assert_eq!(account.owner(), owner);
+            assert_eq!(account.subaccount(), subaccount);
+
+            let key = AccountTxKey::new(&account, txid);
+            assert_eq!(key.account(), account);
+            assert_eq!(key.txid(), txid);
+        }
+
+        #[test]
+        fn account_tx_keys_are_ordered_newest_first(txid in 0u64..u64::MAX) {
+            let account = AccountKey::from(&account(1));
+            assert!(AccountTxKey::new(&account, txid + 1) < AccountTxKey::new(&account, txid));
+        }
+    }
 
-        let guard = HeartbeatGuard::new().unwrap();
+    #[test]
+    fn new_balances_reject_overdrafts() {
+        init_state(vec![(account(1), vec![0])]);
 
-        // should not allow to create another guard
-        // while the previous one is still open
-        assert!(HeartbeatGuard::new().is_none());
+        // changes to the same account are combined before being checked
+        let new_balances =
+            compute_new_balances(1, vec![(account(1), 15), (account(1), -5)]).unwrap();
+        assert_eq!(new_balances.get(&AccountKey::from(&account(1))), Some(&10));
 
-        drop(guard);
+        // the balance of the account is still 0 so it cannot be debited
+        assert!(compute_new_balances(1, vec![(account(1), -1)]).is_err());
 
-        // should allow to create a new guard after the
-        // previous ones have been closed
-        assert!(HeartbeatGuard::new().is_some());
+        // an account that is not indexed yet has no balance
+        assert!(compute_new_balances(1, vec![(account(2), -1)]).is_err());
+        assert!(compute_new_balances(1, vec![(account(2), u64::MAX as i128 + 1)]).is_err());
     }
 }
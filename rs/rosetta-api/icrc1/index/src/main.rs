@@ -1,11 +1,11 @@
-use candid::candid_method;
+use candid::{candid_method, Nat};
 use ic_base_types::CanisterId;
 use ic_canisters_http_types::{HttpRequest, HttpResponse, HttpResponseBuilder};
-use ic_cdk_macros::{heartbeat, init, post_upgrade, pre_upgrade, query, update};
-use ic_icrc1::Subaccount;
+use ic_cdk_macros::{init, post_upgrade, query, update};
+use ic_icrc1::{Account, Subaccount};
 use ic_icrc1_index::{
     encode_metrics, GetAccountTransactionsArgs, GetTransactionsResult, InitArgs,
-    ListSubaccountsArgs,
+    ListSubaccountsArgs, Status,
 };
 
 fn main() {}
@@ -15,11 +15,6 @@ fn init(args: InitArgs) {
     ic_icrc1_index::init(args);
 }
 
-#[heartbeat]
-async fn heartbeat() {
-    ic_icrc1_index::heartbeat().await;
-}
-
 #[update]
 #[candid_method(update)]
 async fn get_account_transactions(args: GetAccountTransactionsArgs) -> GetTransactionsResult {
@@ -32,6 +27,18 @@ fn list_subaccounts(args: ListSubaccountsArgs) -> Vec<Subaccount> {
     ic_icrc1_index::list_subaccounts(args)
 }
 
+#[query]
+#[candid_method(query)]
+fn icrc1_balance_of(account: Account) -> Nat {
+    ic_icrc1_index::icrc1_balance_of(account)
+}
+
+#[query]
+#[candid_method(query)]
+fn status() -> Status {
+    ic_icrc1_index::status()
+}
+
 #[query]
 #[candid_method(query)]
 fn ledger_id() -> CanisterId {
@@ -60,11 +67,6 @@ fn http_request(req: HttpRequest) -> HttpResponse {
     }
 }
 
-#[pre_upgrade]
-fn pre_upgrade() {
-    ic_icrc1_index::pre_upgrade()
-}
-
 #[post_upgrade]
 fn post_upgrade() {
     ic_icrc1_index::post_upgrade()
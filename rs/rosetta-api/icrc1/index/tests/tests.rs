@@ -1,12 +1,15 @@
 use candid::{Decode, Encode, Nat};
 use ic_base_types::PrincipalId;
 use ic_icrc1::{
-    endpoints::{ArchiveInfo, TransferArg, TransferError, Value},
+    endpoints::{
+        ApproveArgs, ApproveError, ArchiveInfo, TransferArg, TransferError, TransferFromArgs,
+        TransferFromError, Value,
+    },
     Account, Block, Memo, Operation, Subaccount, Transaction,
 };
 use ic_icrc1_index::{
     GetAccountTransactionsArgs, GetTransactions, GetTransactionsResult, InitArgs as IndexInitArgs,
-    ListSubaccountsArgs, TransactionWithId,
+    ListSubaccountsArgs, Status, TransactionWithId,
 };
 use ic_icrc1_ledger::{InitArgs as LedgerInitArgs, LedgerArgument};
 use ic_ledger_canister_core::archive::ArchiveOptions;
@@ -17,6 +20,8 @@ use ic_ledger_core::{
 };
 use ic_state_machine_tests::{CanisterId, StateMachine};
 use num_traits::cast::ToPrimitive;
+use serde::Serialize;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -211,6 +216,70 @@ fn mint(env: &StateMachine, ledger: CanisterId, to: Account, amount: u64) -> Blo
     transfer(env, ledger, MINTER, to, amount)
 }
 
+fn approve(
+    env: &StateMachine,
+    ledger: CanisterId,
+    from: Account,
+    spender: PrincipalId,
+    amount: u64,
+) -> BlockIndex {
+    Decode!(
+        &env.execute_ingress_as(
+            from.owner,
+            ledger,
+            "icrc2_approve",
+            Encode!(&ApproveArgs {
+                from_subaccount: from.subaccount,
+                spender,
+                amount: Nat::from(amount),
+                expires_at: None,
+                fee: None,
+                memo: None,
+                created_at_time: None,
+            })
+            .unwrap()
+        )
+        .expect("failed to approve")
+        .bytes(),
+        Result<Nat, ApproveError>
+    )
+    .expect("failed to decode approve response")
+    .map(|n| n.0.to_u64().unwrap())
+    .unwrap()
+}
+
+fn transfer_from(
+    env: &StateMachine,
+    ledger: CanisterId,
+    spender: PrincipalId,
+    from: Account,
+    to: Account,
+    amount: u64,
+) -> BlockIndex {
+    Decode!(
+        &env.execute_ingress_as(
+            spender,
+            ledger,
+            "icrc2_transfer_from",
+            Encode!(&TransferFromArgs {
+                from,
+                to,
+                amount: Nat::from(amount),
+                fee: None,
+                memo: None,
+                created_at_time: None,
+            })
+            .unwrap()
+        )
+        .expect("failed to transfer funds")
+        .bytes(),
+        Result<Nat, TransferFromError>
+    )
+    .expect("failed to decode transfer_from response")
+    .map(|n| n.0.to_u64().unwrap())
+    .unwrap()
+}
+
 fn archives(env: &StateMachine, ledger: CanisterId) -> Vec<ArchiveInfo> {
     Decode!(
         &env.query(ledger, "archives", Encode!().unwrap())
@@ -282,6 +351,46 @@ fn index_ledger_id(env: &StateMachine, index: CanisterId) -> CanisterId {
     .expect("failed to decode ledger_id response")
 }
 
+fn icrc1_balance_of(env: &StateMachine, canister_id: CanisterId, account: Account) -> u64 {
+    Decode!(
+        &env.query(canister_id, "icrc1_balance_of", Encode!(&account).unwrap())
+            .expect("failed to query balance")
+            .bytes(),
+        Nat
+    )
+    .expect("failed to decode icrc1_balance_of response")
+    .0
+    .to_u64()
+    .unwrap()
+}
+
+fn status(env: &StateMachine, index: CanisterId) -> Status {
+    Decode!(
+        &env.query(index, "status", Encode!().unwrap())
+            .expect("failed to query status")
+            .bytes(),
+        Status
+    )
+    .expect("failed to decode status response")
+}
+
+// Checks that the Index agrees with the Ledger on the balances of the accounts.
+fn assert_balances_match(
+    env: &StateMachine,
+    ledger: CanisterId,
+    index: CanisterId,
+    accounts: &[Account],
+) {
+    for account in accounts {
+        assert_eq!(
+            icrc1_balance_of(env, ledger, *account),
+            icrc1_balance_of(env, index, *account),
+            "balance mismatch for account {}",
+            account
+        );
+    }
+}
+
 fn account(n: u64) -> Account {
     Account {
         owner: PrincipalId::new_user_test_id(n),
@@ -326,7 +435,7 @@ fn test() {
     burn(&env, ledger_id, account(1), 10000); // block=5
 
     env.advance_time(Duration::from_secs(60));
-    env.tick(); // trigger index heartbeat
+    env.tick(); // trigger index timer
 
     let txs = get_account_transactions(&env, index_id, account(1), None, u64::MAX);
     assert_eq!(Some(Nat::from(offset)), txs.oldest_tx_id);
@@ -352,7 +461,7 @@ fn test() {
     transfer(&env, ledger_id, account(1), account(2), 7); // block=7
 
     env.advance_time(Duration::from_secs(60));
-    env.tick(); // trigger index heartbeat
+    env.tick(); // trigger index timer
 
     // fetch the more recent transfers
     let txs = get_account_transactions(&env, index_id, account(1), Some(offset + 8), 2);
@@ -400,10 +509,10 @@ fn test_wait_time() {
     let txs = get_account_transactions(&env, index_id, account(1), None, u64::MAX);
     assert!(!txs.transactions.is_empty());
 
-    // Next heartbeat should happen 60 seconds later, as we only indexed
+    // Next indexing should happen 60 seconds later, as we only indexed
     // 5 transactions.
     mint(&env, ledger_id, account(3), 100000); // block=5
-    env.tick(); // trigger index heartbeat that shouldn't index new transactions.
+    env.tick(); // trigger index timer that shouldn't index new transactions.
 
     let txs = get_account_transactions(&env, index_id, account(3), None, u64::MAX);
     assert!(txs.transactions.is_empty());
@@ -515,3 +624,231 @@ fn test_index_archived_txs_paging() {
     let expected_txids: Vec<u64> = (0..ARCHIVE_TRIGGER_THRESHOLD).rev().collect();
     assert_eq!(expected_txids, actual_txids);
 }
+
+#[test]
+fn test_index_approvals() {
+    let env = StateMachine::new();
+    let ledger_id = install_ledger(
+        &env,
+        vec![(account(1), 1_000_000)],
+        default_archive_options(),
+    );
+    let index_id = install_index(&env, ledger_id);
+
+    let spender = account(2).owner;
+    approve(&env, ledger_id, account(1), spender, 100_000); // block=1
+    transfer_from(&env, ledger_id, spender, account(1), account(3), 50_000); // block=2
+
+    env.advance_time(Duration::from_secs(60));
+    env.tick(); // trigger index timer
+
+    let txs = get_account_transactions(&env, index_id, account(1), None, u64::MAX).transactions;
+    assert_eq!(3, txs.len());
+    check_transfer(2, account(1), account(3), 50_000, txs.get(0).unwrap());
+    assert_eq!("approve".to_string(), txs[1].transaction.kind);
+    let approve = txs[1].transaction.approve.as_ref().unwrap();
+    assert_eq!(
+        (&txs[1].id, &approve.from, &approve.spender, &approve.amount),
+        (&Nat::from(1), &account(1), &spender, &Nat::from(100_000))
+    );
+    check_mint(0, account(1), 1_000_000, txs.get(2).unwrap());
+
+    let txs = get_account_transactions(&env, index_id, account(3), None, u64::MAX).transactions;
+    assert_eq!(1, txs.len());
+    assert_eq!(
+        txs[0].transaction.transfer.as_ref().unwrap().spender,
+        Some(spender)
+    );
+}
+
+#[test]
+fn test_balances() {
+    let env = StateMachine::new();
+    let ledger_id = install_ledger(
+        &env,
+        vec![
+            (account(1), 1_000_000),
+            (account_with_subaccount(1, 1), 500_000),
+        ],
+        default_archive_options(),
+    );
+    let index_id = install_index(&env, ledger_id);
+
+    let spender = account(4).owner;
+    mint(&env, ledger_id, account(2), 200_000); // block=2
+    transfer(&env, ledger_id, account(1), account(2), 1); // block=3
+    transfer(&env, ledger_id, account(2), account(1), 10); // block=4
+    transfer(&env, ledger_id, account(1), account(1), 100); // block=5
+    transfer(
+        &env,
+        ledger_id,
+        account_with_subaccount(1, 1),
+        account(3),
+        50_000,
+    ); // block=6
+    approve(&env, ledger_id, account(1), spender, 300_000); // block=7
+    transfer_from(&env, ledger_id, spender, account(1), account(3), 40_000); // block=8
+    burn(&env, ledger_id, account(2), 20_000); // block=9
+
+    env.advance_time(Duration::from_secs(60));
+    env.tick(); // trigger index timer
+
+    assert_eq!(Nat::from(10), status(&env, index_id).num_blocks_synced);
+    assert_balances_match(
+        &env,
+        ledger_id,
+        index_id,
+        &[
+            account(1),
+            account_with_subaccount(1, 1),
+            account(2),
+            account(3),
+            account(4),
+            account(5),
+            MINTER,
+        ],
+    );
+}
+
+#[test]
+fn test_upgrade_preserves_balances() {
+    let env = StateMachine::new();
+    let ledger_id = install_ledger(&env, vec![], default_archive_options());
+    let index_id = install_index(&env, ledger_id);
+
+    mint(&env, ledger_id, account(1), 100_000); // block=0
+    transfer(&env, ledger_id, account(1), account(2), 1_000); // block=1
+
+    env.advance_time(Duration::from_secs(60));
+    env.tick(); // trigger index timer
+    assert_eq!(Nat::from(2), status(&env, index_id).num_blocks_synced);
+
+    env.upgrade_canister(index_id, index_wasm(), vec![])
+        .expect("Failed to upgrade the Index canister");
+
+    // the Index doesn't need to re-index the Ledger after an upgrade
+    assert_eq!(Nat::from(2), status(&env, index_id).num_blocks_synced);
+    assert_balances_match(&env, ledger_id, index_id, &[account(1), account(2)]);
+    assert_eq!(
+        Some(Nat::from(0)),
+        get_account_transactions(&env, index_id, account(1), None, u64::MAX).oldest_tx_id
+    );
+
+    // the Index keeps indexing new blocks after the upgrade
+    transfer(&env, ledger_id, account(2), account(3), 500); // block=2
+    env.advance_time(Duration::from_secs(60));
+    env.tick(); // trigger index timer
+    assert_eq!(Nat::from(3), status(&env, index_id).num_blocks_synced);
+    assert_balances_match(
+        &env,
+        ledger_id,
+        index_id,
+        &[account(1), account(2), account(3)],
+    );
+}
+
+#[test]
+fn test_balances_match_ledger_with_archived_blocks() {
+    let env = StateMachine::new();
+    let accounts: Vec<Account> = (1..=5)
+        .map(account)
+        .chain((1..=3).map(|s| account_with_subaccount(1, s)))
+        .collect();
+    let ledger_id = install_ledger(
+        &env,
+        accounts
+            .iter()
+            .map(|account| (*account, 1_000_000))
+            .collect(),
+        default_archive_options(),
+    );
+    let index_id = install_index(&env, ledger_id);
+
+    for i in 0..40 {
+        let from = accounts[i % accounts.len()];
+        let to = accounts[(i * 7 + 3) % accounts.len()];
+        match i % 5 {
+            0 => {
+                mint(&env, ledger_id, to, 1_000 * (i as u64 + 1));
+            }
+            1 => {
+                burn(&env, ledger_id, from, 500 + i as u64);
+            }
+            _ => {
+                transfer(&env, ledger_id, from, to, 100 * i as u64);
+            }
+        }
+    }
+    assert!(!archives(&env, ledger_id).is_empty());
+
+    env.advance_time(Duration::from_secs(60));
+    env.tick(); // trigger index timer
+
+    assert_eq!(
+        Nat::from(accounts.len() + 40),
+        status(&env, index_id).num_blocks_synced
+    );
+    assert_balances_match(&env, ledger_id, index_id, &accounts);
+}
+
+// The state that an Index predating the stable structures kept in the
+// stable memory across upgrades.
+#[derive(Serialize)]
+struct LegacyIndex {
+    ledger_id: CanisterId,
+    next_txid: u64,
+    is_heartbeat_running: bool,
+    next_build_index_time: u64,
+    account_index: BTreeMap<PrincipalId, BTreeMap<Subaccount, Vec<u64>>>,
+    accounts_num: u64,
+}
+
+#[test]
+fn test_upgrade_from_legacy_state() {
+    let env = StateMachine::new();
+    let ledger_id = install_ledger(&env, vec![], default_archive_options());
+    let index_id = install_index(&env, ledger_id);
+
+    mint(&env, ledger_id, account(1), 100_000); // block=0
+    transfer(&env, ledger_id, account(1), account(2), 1_000); // block=1
+
+    // Replace the stable memory of the Index with the state of a legacy Index
+    // that indexed both blocks.
+    let legacy_index = LegacyIndex {
+        ledger_id,
+        next_txid: 2,
+        is_heartbeat_running: false,
+        next_build_index_time: 0,
+        account_index: BTreeMap::from([
+            (account(1).owner, BTreeMap::from([([0u8; 32], vec![0, 1])])),
+            (account(2).owner, BTreeMap::from([([0u8; 32], vec![1])])),
+        ]),
+        accounts_num: 2,
+    };
+    let mut legacy_state = vec![];
+    ciborium::ser::into_writer(&legacy_index, &mut legacy_state)
+        .expect("failed to encode the legacy index state");
+    env.set_stable_memory(index_id, &legacy_state);
+
+    env.upgrade_canister(index_id, index_wasm(), vec![])
+        .expect("Failed to upgrade the Index canister");
+    assert_eq!(ledger_id, index_ledger_id(&env, index_id));
+
+    // The Index re-indexes the Ledger from block 0, after which it agrees
+    // with the Ledger and keeps indexing new blocks.
+    transfer(&env, ledger_id, account(2), account(3), 500); // block=2
+    env.advance_time(Duration::from_secs(60));
+    env.tick(); // trigger index timer
+
+    assert_eq!(Nat::from(3), status(&env, index_id).num_blocks_synced);
+    assert_balances_match(
+        &env,
+        ledger_id,
+        index_id,
+        &[account(1), account(2), account(3)],
+    );
+    let txs = get_account_transactions(&env, index_id, account(2), None, u64::MAX);
+    check_transfer(2, account(2), account(3), 500, &txs.transactions[0]);
+    check_transfer(1, account(1), account(2), 1_000, &txs.transactions[1]);
+    assert_eq!(Some(Nat::from(1)), txs.oldest_tx_id);
+}
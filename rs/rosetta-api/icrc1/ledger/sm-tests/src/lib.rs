@@ -4,8 +4,9 @@ use ic_icrc1::blocks::Icrc1Block;
 use ic_icrc1::endpoints::{BlockRange, Transaction as Tx, TransactionRange};
 use ic_icrc1::{
     endpoints::{
-        ArchiveInfo, GetBlocksResponse, GetTransactionsRequest, GetTransactionsResponse,
-        StandardRecord, Transfer, TransferArg, TransferError, Value,
+        Allowance, AllowanceArgs, ApproveArgs, ApproveError, ArchiveInfo, GetBlocksResponse,
+        GetTransactionsRequest, GetTransactionsResponse, StandardRecord, Transfer, TransferArg,
+        TransferError, TransferFromArgs, TransferFromError, Value,
     },
     hash::Hash,
     Account, Block, Memo, Operation, Transaction,
@@ -189,6 +190,109 @@ fn transfer(
     )
 }
 
+fn send_approval(
+    env: &StateMachine,
+    ledger: CanisterId,
+    from: PrincipalId,
+    arg: &ApproveArgs,
+) -> Result<BlockIndex, ApproveError> {
+    Decode!(
+        &env.execute_ingress_as(from, ledger, "icrc2_approve", Encode!(arg).unwrap())
+            .expect("failed to approve")
+            .bytes(),
+        Result<Nat, ApproveError>
+    )
+    .expect("failed to decode approve response")
+    .map(|n| n.0.to_u64().unwrap())
+}
+
+fn approve(
+    env: &StateMachine,
+    ledger: CanisterId,
+    from: impl Into<Account>,
+    spender: PrincipalId,
+    amount: u64,
+    expires_at: Option<u64>,
+) -> Result<BlockIndex, ApproveError> {
+    let from = from.into();
+    send_approval(
+        env,
+        ledger,
+        from.owner,
+        &ApproveArgs {
+            from_subaccount: from.subaccount,
+            spender,
+            amount: Nat::from(amount),
+            expires_at,
+            fee: None,
+            memo: None,
+            created_at_time: None,
+        },
+    )
+}
+
+fn send_transfer_from(
+    env: &StateMachine,
+    ledger: CanisterId,
+    spender: PrincipalId,
+    arg: &TransferFromArgs,
+) -> Result<BlockIndex, TransferFromError> {
+    Decode!(
+        &env.execute_ingress_as(spender, ledger, "icrc2_transfer_from", Encode!(arg).unwrap())
+            .expect("failed to transfer funds")
+            .bytes(),
+        Result<Nat, TransferFromError>
+    )
+    .expect("failed to decode transfer_from response")
+    .map(|n| n.0.to_u64().unwrap())
+}
+
+fn transfer_from(
+    env: &StateMachine,
+    ledger: CanisterId,
+    spender: PrincipalId,
+    from: impl Into<Account>,
+    to: impl Into<Account>,
+    amount: u64,
+) -> Result<BlockIndex, TransferFromError> {
+    send_transfer_from(
+        env,
+        ledger,
+        spender,
+        &TransferFromArgs {
+            from: from.into(),
+            to: to.into(),
+            amount: Nat::from(amount),
+            fee: None,
+            memo: None,
+            created_at_time: None,
+        },
+    )
+}
+
+pub fn allowance(
+    env: &StateMachine,
+    ledger: CanisterId,
+    account: impl Into<Account>,
+    spender: PrincipalId,
+) -> Allowance {
+    Decode!(
+        &env.query(
+            ledger,
+            "icrc2_allowance",
+            Encode!(&AllowanceArgs {
+                account: account.into(),
+                spender
+            })
+            .unwrap()
+        )
+        .expect("failed to query allowance")
+        .bytes(),
+        Allowance
+    )
+    .expect("failed to decode allowance response")
+}
+
 fn list_archives(env: &StateMachine, ledger: CanisterId) -> Vec<ArchiveInfo> {
     Decode!(
         &env.query(ledger, "archives", Encode!().unwrap())
@@ -356,18 +460,16 @@ fn arb_amount() -> impl Strategy<Value = u64> {
     any::<u64>()
 }
 
+fn arb_principal() -> impl Strategy<Value = PrincipalId> {
+    proptest::collection::vec(any::<u8>(), 28).prop_map(|mut principal| {
+        principal.push(0x00);
+        PrincipalId::try_from(&principal[..]).unwrap()
+    })
+}
+
 fn arb_account() -> impl Strategy<Value = Account> {
-    (
-        proptest::collection::vec(any::<u8>(), 28),
-        any::<Option<[u8; 32]>>(),
-    )
-        .prop_map(|(mut principal, subaccount)| {
-            principal.push(0x00);
-            Account {
-                owner: PrincipalId::try_from(&principal[..]).unwrap(),
-                subaccount,
-            }
-        })
+    (arb_principal(), any::<Option<[u8; 32]>>())
+        .prop_map(|(owner, subaccount)| Account { owner, subaccount })
 }
 
 fn arb_transfer() -> impl Strategy<Value = Operation> {
@@ -376,12 +478,14 @@ fn arb_transfer() -> impl Strategy<Value = Operation> {
         arb_account(),
         arb_amount(),
         proptest::option::of(arb_amount()),
+        proptest::option::of(arb_principal()),
     )
-        .prop_map(|(from, to, amount, fee)| Operation::Transfer {
+        .prop_map(|(from, to, amount, fee, spender)| Operation::Transfer {
             from,
             to,
             amount,
             fee,
+            spender,
         })
 }
 
@@ -393,8 +497,27 @@ fn arb_burn() -> impl Strategy<Value = Operation> {
     (arb_account(), arb_amount()).prop_map(|(from, amount)| Operation::Burn { from, amount })
 }
 
+fn arb_approve() -> impl Strategy<Value = Operation> {
+    (
+        arb_account(),
+        arb_principal(),
+        arb_amount(),
+        any::<Option<u64>>(),
+        proptest::option::of(arb_amount()),
+    )
+        .prop_map(
+            |(from, spender, amount, expires_at, fee)| Operation::Approve {
+                from,
+                spender,
+                amount,
+                expires_at,
+                fee,
+            },
+        )
+}
+
 fn arb_operation() -> impl Strategy<Value = Operation> {
-    prop_oneof![arb_transfer(), arb_mint(), arb_burn()]
+    prop_oneof![arb_transfer(), arb_mint(), arb_burn(), arb_approve()]
 }
 
 fn arb_transaction() -> impl Strategy<Value = Transaction> {
@@ -623,10 +746,16 @@ where
     let standards = supported_standards(&env, canister_id);
     assert_eq!(
         standards,
-        vec![StandardRecord {
-            name: "ICRC-1".to_string(),
-            url: "https://github.com/dfinity/ICRC-1".to_string(),
-        }]
+        vec![
+            StandardRecord {
+                name: "ICRC-1".to_string(),
+                url: "https://github.com/dfinity/ICRC-1".to_string(),
+            },
+            StandardRecord {
+                name: "ICRC-2".to_string(),
+                url: "https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-2".to_string(),
+            }
+        ]
     );
 }
 
@@ -1128,6 +1257,7 @@ pub fn test_archiving<T>(
             fee: Some(Nat::from(FEE)),
             memo: None,
             created_at_time: None,
+            spender: None,
         };
         assert_eq!(
             get_archive_transaction(&env, archive_canister_id, i)
@@ -1164,6 +1294,7 @@ pub fn test_archiving<T>(
                 fee: Some(Nat::from(FEE)),
                 memo: None,
                 created_at_time: None,
+                spender: None,
             })
         );
     }
@@ -1417,3 +1548,169 @@ where
     .unwrap();
     assert_eq!(token_fee_after_upgrade, NEW_FEE);
 }
+
+pub fn test_approve_and_transfer_from<T>(ledger_wasm: Vec<u8>, encode_init_args: fn(InitArgs) -> T)
+where
+    T: CandidType,
+{
+    let p1 = PrincipalId::new_user_test_id(1);
+    let p2 = PrincipalId::new_user_test_id(2);
+    let p3 = PrincipalId::new_user_test_id(3);
+    let (env, canister_id) = setup(
+        ledger_wasm,
+        encode_init_args,
+        vec![(Account::from(p1), 10_000_000)],
+    );
+
+    assert_eq!(allowance(&env, canister_id, p1, p2).allowance, Nat::from(0));
+
+    // Approvals burn the fee from the approver and are cumulative.
+    approve(&env, canister_id, p1, p2, 1_000_000, None).expect("approve failed");
+    approve(&env, canister_id, p1, p2, 500_000, None).expect("approve failed");
+    assert_eq!(10_000_000 - 2 * FEE, balance_of(&env, canister_id, p1));
+    assert_eq!(
+        allowance(&env, canister_id, p1, p2),
+        Allowance {
+            allowance: Nat::from(1_500_000),
+            expires_at: None,
+        }
+    );
+
+    // The spender pays both the amount and the fee out of the allowance.
+    transfer_from(&env, canister_id, p2, p1, p3, 1_000_000).expect("transfer_from failed");
+    assert_eq!(
+        10_000_000 - 3 * FEE - 1_000_000,
+        balance_of(&env, canister_id, p1)
+    );
+    assert_eq!(0, balance_of(&env, canister_id, p2));
+    assert_eq!(1_000_000, balance_of(&env, canister_id, p3));
+    assert_eq!(
+        allowance(&env, canister_id, p1, p2).allowance,
+        Nat::from(500_000 - FEE)
+    );
+
+    assert_eq!(
+        transfer_from(&env, canister_id, p2, p1, p3, 500_000),
+        Err(TransferFromError::InsufficientAllowance {
+            allowance: Nat::from(500_000 - FEE)
+        })
+    );
+
+    // Allowances are scoped to subaccounts.
+    let p1_sub = Account {
+        owner: p1,
+        subaccount: Some([1; 32]),
+    };
+    assert_eq!(
+        transfer_from(&env, canister_id, p2, p1_sub, p3, 1),
+        Err(TransferFromError::InsufficientAllowance {
+            allowance: Nat::from(0)
+        })
+    );
+
+    // Only the spender can use the allowance.
+    assert_eq!(
+        transfer_from(&env, canister_id, p3, p1, p3, 1),
+        Err(TransferFromError::InsufficientAllowance {
+            allowance: Nat::from(0)
+        })
+    );
+
+    // The account owner does not need an allowance.
+    transfer_from(&env, canister_id, p1, p1, p3, 1_000).expect("transfer_from failed");
+    assert_eq!(1_001_000, balance_of(&env, canister_id, p3));
+
+    let txs = get_transactions(&env, canister_id, 0, 10).transactions;
+    assert_eq!(txs[1].kind, "approve");
+    assert_eq!(
+        txs[1]
+            .approve
+            .as_ref()
+            .map(|a| (a.spender, a.amount.clone())),
+        Some((p2, Nat::from(1_000_000)))
+    );
+    assert_eq!(txs[3].kind, "transfer");
+    assert_eq!(txs[3].transfer.as_ref().unwrap().spender, Some(p2));
+}
+
+pub fn test_approval_expiration<T>(ledger_wasm: Vec<u8>, encode_init_args: fn(InitArgs) -> T)
+where
+    T: CandidType,
+{
+    let p1 = PrincipalId::new_user_test_id(1);
+    let p2 = PrincipalId::new_user_test_id(2);
+    let (env, canister_id) = setup(
+        ledger_wasm,
+        encode_init_args,
+        vec![(Account::from(p1), 10_000_000)],
+    );
+
+    let now = system_time_to_nanos(env.time());
+
+    assert!(matches!(
+        approve(&env, canister_id, p1, p2, 1_000_000, Some(now)),
+        Err(ApproveError::Expired { .. })
+    ));
+    // Failed approvals do not charge the fee.
+    assert_eq!(10_000_000, balance_of(&env, canister_id, p1));
+
+    let expires_at = now + Duration::from_secs(3600).as_nanos() as u64;
+    approve(&env, canister_id, p1, p2, 1_000_000, Some(expires_at)).expect("approve failed");
+    assert_eq!(
+        allowance(&env, canister_id, p1, p2),
+        Allowance {
+            allowance: Nat::from(1_000_000),
+            expires_at: Some(expires_at),
+        }
+    );
+
+    env.advance_time(Duration::from_secs(3600));
+
+    assert_eq!(allowance(&env, canister_id, p1, p2).allowance, Nat::from(0));
+    assert_eq!(
+        transfer_from(&env, canister_id, p2, p1, p2, 1_000),
+        Err(TransferFromError::InsufficientAllowance {
+            allowance: Nat::from(0)
+        })
+    );
+}
+
+pub fn test_approve_rejects_self_approval<T>(
+    ledger_wasm: Vec<u8>,
+    encode_init_args: fn(InitArgs) -> T,
+) where
+    T: CandidType,
+{
+    let p1 = PrincipalId::new_user_test_id(1);
+    let (env, canister_id) = setup(
+        ledger_wasm,
+        encode_init_args,
+        vec![(Account::from(p1), 10_000_000)],
+    );
+
+    let result = env.execute_ingress_as(
+        p1,
+        canister_id,
+        "icrc2_approve",
+        Encode!(&ApproveArgs {
+            from_subaccount: None,
+            spender: p1,
+            amount: Nat::from(1_000),
+            expires_at: None,
+            fee: None,
+            memo: None,
+            created_at_time: None,
+        })
+        .unwrap(),
+    );
+    match result {
+        Err(user_error) => assert_eq!(
+            user_error.code(),
+            ErrorCode::CanisterCalledTrap,
+            "unexpected error: {}",
+            user_error
+        ),
+        Ok(result) => panic!("expected a reject for a self approval, got {:?}", result),
+    }
+    assert_eq!(10_000_000, balance_of(&env, canister_id, p1));
+}
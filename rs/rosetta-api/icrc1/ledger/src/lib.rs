@@ -19,7 +19,7 @@ use ic_ledger_canister_core::{
     range_utils,
 };
 use ic_ledger_core::{
-    approvals::AllowanceTable,
+    approvals::{Allowance, AllowanceTable, Approvals, PrunableApprovals},
     balances::Balances,
     block::{BlockIndex, BlockType, EncodedBlock, HashOf},
     timestamp::TimeStamp,
@@ -39,6 +39,9 @@ const MAX_TRANSACTIONS_PER_REQUEST: usize = 2_000;
 const ACCOUNTS_OVERFLOW_TRIM_QUANTITY: usize = 100_000;
 const MAX_TRANSACTIONS_IN_WINDOW: usize = 3_000_000;
 const MAX_TRANSACTIONS_TO_PURGE: usize = 100_000;
+/// The maximum number of expired approvals the ledger removes on each
+/// approve call.
+const MAX_APPROVALS_TO_PRUNE: usize = 100;
 
 #[derive(Debug, Clone)]
 pub struct Icrc1ArchiveWasm;
@@ -126,6 +129,8 @@ pub enum LedgerArgument {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Ledger {
     balances: LedgerBalances,
+    #[serde(default)]
+    approvals: AllowanceTable<ApprovalKey, Account, PrincipalId>,
     blockchain: Blockchain<CdkRuntime, Icrc1ArchiveWasm>,
 
     minting_account: Account,
@@ -154,6 +159,7 @@ impl Ledger {
     ) -> Self {
         let mut ledger = Self {
             balances: LedgerBalances::default(),
+            approvals: Default::default(),
             blockchain: Blockchain::new_with_archive(archive_options),
             transactions_by_hash: BTreeMap::new(),
             transactions_by_height: VecDeque::new(),
@@ -183,7 +189,7 @@ impl Ledger {
     }
 }
 
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub struct ApprovalKey(Account, PrincipalId);
 
 impl From<(&Account, &PrincipalId)> for ApprovalKey {
@@ -207,11 +213,11 @@ impl LedgerContext for Ledger {
     }
 
     fn approvals(&self) -> &Self::Approvals {
-        unimplemented!()
+        &self.approvals
     }
 
     fn approvals_mut(&mut self) -> &mut Self::Approvals {
-        unimplemented!()
+        &mut self.approvals
     }
 }
 
@@ -285,6 +291,16 @@ impl Ledger {
         self.transfer_fee
     }
 
+    /// Returns the amount the spender can still transfer from the account.
+    pub fn allowance(&self, account: &Account, spender: &PrincipalId, now: TimeStamp) -> Allowance {
+        self.approvals.allowance(account, spender, now)
+    }
+
+    /// Removes approvals that expired before `now`.
+    pub fn prune_expired_approvals(&mut self, now: TimeStamp) -> usize {
+        self.approvals.prune(now, MAX_APPROVALS_TO_PRUNE)
+    }
+
     pub fn metadata(&self) -> Vec<(String, Value)> {
         let mut records: Vec<(String, Value)> = self
             .metadata
@@ -7,8 +7,9 @@ use ic_cdk::api::stable::{StableReader, StableWriter};
 use ic_cdk_macros::{init, post_upgrade, pre_upgrade, query, update};
 use ic_icrc1::{
     endpoints::{
-        ArchiveInfo, GetBlocksArgs, GetBlocksResponse, GetTransactionsRequest,
-        GetTransactionsResponse, StandardRecord, TransferArg, TransferError, Value,
+        Allowance, AllowanceArgs, ApproveArgs, ApproveError, ArchiveInfo, GetBlocksArgs,
+        GetBlocksResponse, GetTransactionsRequest, GetTransactionsResponse, StandardRecord,
+        TransferArg, TransferError, TransferFromArgs, TransferFromError, Value,
     },
     Account, Operation, Transaction,
 };
@@ -336,6 +337,120 @@ async fn icrc1_transfer(arg: TransferArg) -> Result<Nat, TransferError> {
     Ok(Nat::from(block_idx))
 }
 
+#[update]
+#[candid_method(update)]
+async fn icrc2_approve(arg: ApproveArgs) -> Result<Nat, ApproveError> {
+    let block_idx = Access::with_ledger_mut(|ledger| {
+        let now = TimeStamp::from_nanos_since_unix_epoch(ic_cdk::api::time());
+        let caller = PrincipalId::from(ic_cdk::api::caller());
+
+        let from_account = Account {
+            owner: caller,
+            subaccount: arg.from_subaccount,
+        };
+        if arg.spender == caller {
+            ic_cdk::trap("self approval is not allowed");
+        }
+        if &from_account == ledger.minting_account() {
+            ic_cdk::trap("the minting account cannot delegate mints");
+        }
+
+        // No account can hold more than u64::MAX tokens, so larger
+        // allowances are equivalent to an unlimited one.
+        let amount = Tokens::from_e8s(arg.amount.0.to_u64().unwrap_or(u64::MAX));
+
+        let expected_fee_tokens = ledger.transfer_fee();
+        let expected_fee = Nat::from(expected_fee_tokens.get_e8s());
+        if arg.fee.is_some() && arg.fee.as_ref() != Some(&expected_fee) {
+            return Err(ApproveError::BadFee { expected_fee });
+        }
+
+        let tx = Transaction::approve(
+            from_account,
+            arg.spender,
+            amount,
+            arg.expires_at.map(TimeStamp::from_nanos_since_unix_epoch),
+            arg.fee.map(|_| expected_fee_tokens),
+            arg.created_at_time
+                .map(TimeStamp::from_nanos_since_unix_epoch),
+            arg.memo,
+        );
+
+        ledger.prune_expired_approvals(now);
+        let (block_idx, _) = apply_transaction(ledger, tx, now, expected_fee_tokens)?;
+        Ok(block_idx)
+    })?;
+
+    ic_cdk::api::set_certified_data(&Access::with_ledger(Ledger::root_hash));
+
+    archive_blocks::<Access>(&LOG, MAX_MESSAGE_SIZE).await;
+    Ok(Nat::from(block_idx))
+}
+
+#[update]
+#[candid_method(update)]
+async fn icrc2_transfer_from(arg: TransferFromArgs) -> Result<Nat, TransferFromError> {
+    let block_idx = Access::with_ledger_mut(|ledger| {
+        let now = TimeStamp::from_nanos_since_unix_epoch(ic_cdk::api::time());
+        let spender = PrincipalId::from(ic_cdk::api::caller());
+
+        if &arg.from == ledger.minting_account() || &arg.to == ledger.minting_account() {
+            return Err(TransferFromError::GenericError {
+                error_code: Nat::from(0u64),
+                message: "icrc2_transfer_from cannot mint or burn tokens".to_string(),
+            });
+        }
+
+        let amount = match arg.amount.0.to_u64() {
+            Some(n) => Tokens::from_e8s(n),
+            None => {
+                // No one can have so many tokens
+                let balance = Nat::from(ledger.balances().account_balance(&arg.from).get_e8s());
+                assert!(balance < arg.amount);
+                return Err(TransferFromError::InsufficientFunds { balance });
+            }
+        };
+
+        let expected_fee_tokens = ledger.transfer_fee();
+        let expected_fee = Nat::from(expected_fee_tokens.get_e8s());
+        if arg.fee.is_some() && arg.fee.as_ref() != Some(&expected_fee) {
+            return Err(TransferFromError::BadFee { expected_fee });
+        }
+
+        let tx = Transaction::transfer_from(
+            spender,
+            arg.from,
+            arg.to,
+            amount,
+            arg.fee.map(|_| expected_fee_tokens),
+            arg.created_at_time
+                .map(TimeStamp::from_nanos_since_unix_epoch),
+            arg.memo,
+        );
+
+        let (block_idx, _) = apply_transaction(ledger, tx, now, expected_fee_tokens)?;
+        Ok(block_idx)
+    })?;
+
+    ic_cdk::api::set_certified_data(&Access::with_ledger(Ledger::root_hash));
+
+    archive_blocks::<Access>(&LOG, MAX_MESSAGE_SIZE).await;
+    Ok(Nat::from(block_idx))
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc2_allowance(arg: AllowanceArgs) -> Allowance {
+    Access::with_ledger(|ledger| {
+        let now = TimeStamp::from_nanos_since_unix_epoch(ic_cdk::api::time());
+        let allowance = ledger.allowance(&arg.account, &arg.spender, now);
+        Allowance {
+            allowance: Nat::from(allowance.amount.get_e8s()),
+            expires_at: allowance.expires_at.map(|t| t.as_nanos_since_unix_epoch()),
+        }
+    })
+}
+
 #[query]
 fn archives() -> Vec<ArchiveInfo> {
     Access::with_ledger(|ledger| {
@@ -363,10 +478,16 @@ fn archives() -> Vec<ArchiveInfo> {
 #[query(name = "icrc1_supported_standards")]
 #[candid_method(query, rename = "icrc1_supported_standards")]
 fn supported_standards() -> Vec<StandardRecord> {
-    vec![StandardRecord {
-        name: "ICRC-1".to_string(),
-        url: "https://github.com/dfinity/ICRC-1".to_string(),
-    }]
+    vec![
+        StandardRecord {
+            name: "ICRC-1".to_string(),
+            url: "https://github.com/dfinity/ICRC-1".to_string(),
+        },
+        StandardRecord {
+            name: "ICRC-2".to_string(),
+            url: "https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-2".to_string(),
+        },
+    ]
 }
 
 #[query]
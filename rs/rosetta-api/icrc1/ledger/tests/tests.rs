@@ -130,3 +130,18 @@ fn block_hashes_are_stable() {
 fn check_transfer_model() {
     ic_icrc1_ledger_sm_tests::check_transfer_model(ledger_wasm(), encode_init_args);
 }
+
+#[test]
+fn test_approve_and_transfer_from() {
+    ic_icrc1_ledger_sm_tests::test_approve_and_transfer_from(ledger_wasm(), encode_init_args);
+}
+
+#[test]
+fn test_approval_expiration() {
+    ic_icrc1_ledger_sm_tests::test_approval_expiration(ledger_wasm(), encode_init_args);
+}
+
+#[test]
+fn test_approve_rejects_self_approval() {
+    ic_icrc1_ledger_sm_tests::test_approve_rejects_self_approval(ledger_wasm(), encode_init_args);
+}
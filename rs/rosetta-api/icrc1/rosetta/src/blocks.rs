@@ -0,0 +1,224 @@
+use ic_icrc1::{Account, Block, Operation, Transaction};
+use ic_ledger_canister_core::ledger::LedgerTransaction;
+use ic_ledger_core::block::{BlockType, EncodedBlock, HashOf};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+
+/// A decoded ledger block together with its position in the chain.
+#[derive(Clone, Debug)]
+pub struct HashedBlock {
+    pub index: u64,
+    pub hash: HashOf<EncodedBlock>,
+    pub tx_hash: HashOf<Transaction>,
+    pub block: Block,
+}
+
+/// The blocks downloaded from the ledger and its archives.
+///
+/// Blocks are appended in order and only the prefix of the chain that was
+/// checked against the certified tip of the ledger (the first
+/// `verified_len` blocks) is visible through the getters.
+#[derive(Default)]
+pub struct Blocks {
+    blocks: Vec<HashedBlock>,
+    verified_len: u64,
+    block_idx_by_hash: HashMap<HashOf<EncodedBlock>, u64>,
+    block_idxs_by_tx_hash: HashMap<HashOf<Transaction>, Vec<u64>>,
+    /// For each account, the list of `(block index, balance after the block)`
+    /// pairs, ordered by block index.
+    balance_history: HashMap<Account, Vec<(u64, u64)>>,
+}
+
+impl Blocks {
+    /// Returns the number of downloaded blocks, verified or not.
+    pub fn len(&self) -> u64 {
+        self.blocks.len() as u64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    pub fn verified_len(&self) -> u64 {
+        self.verified_len
+    }
+
+    /// Returns the hash of the last downloaded block.
+    pub fn last_hash(&self) -> Option<HashOf<EncodedBlock>> {
+        self.blocks.last().map(|hb| hb.hash)
+    }
+
+    /// Appends the next block of the chain.
+    ///
+    /// Fails if the block cannot be decoded, does not point to the current
+    /// last block, or would make the balance of an account negative. The
+    /// store is left unchanged on failure.
+    pub fn push(&mut self, encoded_block: EncodedBlock) -> Result<(), String> {
+        let index = self.len();
+        let hash = Block::block_hash(&encoded_block);
+        let block = Block::decode(encoded_block)
+            .map_err(|e| format!("failed to decode block {}: {}", index, e))?;
+
+        if block.parent_hash != self.last_hash() {
+            return Err(format!(
+                "block {} has parent hash {:?}, expected {:?}",
+                index,
+                block.parent_hash,
+                self.last_hash()
+            ));
+        }
+
+        let mut new_balances = Vec::new();
+        for (account, change) in balance_changes(&block) {
+            let balance = i128::from(self.latest_balance(&account)) + change;
+            let balance = u64::try_from(balance).map_err(|_| {
+                format!(
+                    "block {} sets the balance of account {} to {}",
+                    index, account, balance
+                )
+            })?;
+            new_balances.push((account, balance));
+        }
+
+        let tx_hash = block.transaction.hash();
+        for (account, balance) in new_balances {
+            self.balance_history
+                .entry(account)
+                .or_default()
+                .push((index, balance));
+        }
+        self.block_idx_by_hash.insert(hash, index);
+        self.block_idxs_by_tx_hash
+            .entry(tx_hash)
+            .or_default()
+            .push(index);
+        self.blocks.push(HashedBlock {
+            index,
+            hash,
+            tx_hash,
+            block,
+        });
+        Ok(())
+    }
+
+    /// Marks the first `len` blocks as verified.
+    pub fn set_verified_len(&mut self, len: u64) {
+        assert!(
+            len <= self.len(),
+            "cannot verify {} blocks, only {} downloaded",
+            len,
+            self.len()
+        );
+        self.verified_len = len;
+    }
+
+    pub fn get_verified(&self, index: u64) -> Option<&HashedBlock> {
+        if index < self.verified_len {
+            self.blocks.get(index as usize)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_verified_by_hash(&self, hash: &HashOf<EncodedBlock>) -> Option<&HashedBlock> {
+        self.block_idx_by_hash
+            .get(hash)
+            .and_then(|index| self.get_verified(*index))
+    }
+
+    /// Returns the verified blocks in the given range, the range is clamped
+    /// to the verified part of the chain.
+    pub fn get_verified_range(&self, range: Range<u64>) -> &[HashedBlock] {
+        let end = range.end.min(self.verified_len);
+        let start = range.start.min(end);
+        &self.blocks[start as usize..end as usize]
+    }
+
+    pub fn verified_tip(&self) -> Option<&HashedBlock> {
+        self.verified_len
+            .checked_sub(1)
+            .and_then(|index| self.get_verified(index))
+    }
+
+    /// Returns the indices of the verified blocks containing a transaction
+    /// with the given hash, in ascending order.
+    pub fn get_verified_idxs_by_tx_hash(&self, tx_hash: &HashOf<Transaction>) -> Vec<u64> {
+        self.block_idxs_by_tx_hash
+            .get(tx_hash)
+            .map(|idxs| {
+                idxs.iter()
+                    .copied()
+                    .filter(|index| *index < self.verified_len)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the indices of the verified blocks that changed the balance of
+    /// the account, in ascending order.
+    pub fn get_verified_account_history(&self, account: &Account) -> Vec<u64> {
+        self.balance_history
+            .get(account)
+            .map(|history| {
+                history
+                    .iter()
+                    .map(|(index, _)| *index)
+                    .take_while(|index| *index < self.verified_len)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the balance of the account right after the block at `index`
+    /// was applied.
+    pub fn get_account_balance(&self, account: &Account, index: u64) -> u64 {
+        let history = match self.balance_history.get(account) {
+            Some(history) => history,
+            None => return 0,
+        };
+        match history.partition_point(|(i, _)| *i <= index) {
+            0 => 0,
+            n => history[n - 1].1,
+        }
+    }
+
+    fn latest_balance(&self, account: &Account) -> u64 {
+        self.balance_history
+            .get(account)
+            .and_then(|history| history.last())
+            .map(|(_, balance)| *balance)
+            .unwrap_or(0)
+    }
+}
+
+/// Returns the fee the block charged to the source account, if the
+/// operation pays a fee.
+pub fn charged_fee(block: &Block) -> Option<u64> {
+    match &block.transaction.operation {
+        Operation::Transfer { fee, .. } | Operation::Approve { fee, .. } => {
+            Some(fee.or(block.effective_fee).unwrap_or(0))
+        }
+        Operation::Mint { .. } | Operation::Burn { .. } => None,
+    }
+}
+
+/// Returns the balance change of each account touched by the block.
+fn balance_changes(block: &Block) -> BTreeMap<Account, i128> {
+    let mut changes = BTreeMap::new();
+    let fee = i128::from(charged_fee(block).unwrap_or(0));
+    let mut add = |account: &Account, change: i128| {
+        *changes.entry(*account).or_insert(0) += change;
+    };
+    match &block.transaction.operation {
+        Operation::Mint { to, amount } => add(to, i128::from(*amount)),
+        Operation::Burn { from, amount } => add(from, -i128::from(*amount)),
+        Operation::Transfer {
+            from, to, amount, ..
+        } => {
+            add(from, -i128::from(*amount) - fee);
+            add(to, i128::from(*amount));
+        }
+        Operation::Approve { from, .. } => add(from, -fee),
+    }
+    changes
+}
@@ -0,0 +1,319 @@
+//! Conversions between ICRC-1 ledger data structures and Rosetta models.
+
+use crate::blocks::{charged_fee, HashedBlock};
+use ic_icrc1::{Account, Operation as LedgerOperation, Subaccount, DEFAULT_SUBACCOUNT};
+use ic_ledger_core::timestamp::TimeStamp;
+use ic_rosetta_api::convert::{from_hash, from_hex};
+use ic_rosetta_api::errors::ApiError;
+use ic_rosetta_api::models::amount::Amount;
+use ic_rosetta_api::models::operation::{Operation, OperationType};
+use ic_rosetta_api::models::{
+    self, AccountIdentifier, BlockIdentifier, Currency, Object, SubAccountIdentifier,
+};
+use ic_rosetta_api::request_types::STATUS_COMPLETED;
+use ic_rosetta_api::transaction_id::TransactionIdentifier;
+use ic_types::PrincipalId;
+use serde_json::{json, Value};
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+/// An `icrc1_transfer` call described by Rosetta operations.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transfer {
+    pub from: Account,
+    pub to: Account,
+    pub amount: u64,
+    /// The fee the caller agrees to pay, `None` if the ledger should charge
+    /// its current fee.
+    pub fee: Option<u64>,
+}
+
+/// Maps an account to a Rosetta account identifier.
+///
+/// The address is the textual representation of the owner principal and the
+/// sub-account is the hex-encoded subaccount. The default subaccount is
+/// omitted so that each account has a single identifier.
+pub fn to_model_account_identifier(account: &Account) -> AccountIdentifier {
+    AccountIdentifier {
+        address: account.owner.to_string(),
+        sub_account: Some(account.effective_subaccount())
+            .filter(|subaccount| *subaccount != DEFAULT_SUBACCOUNT)
+            .map(|subaccount| SubAccountIdentifier::new(hex::encode(subaccount))),
+        metadata: None,
+    }
+}
+
+pub fn from_model_account_identifier(aid: &AccountIdentifier) -> Result<Account, ApiError> {
+    let owner = PrincipalId::from_str(&aid.address).map_err(|e| {
+        ApiError::invalid_account_id(format!("Invalid owner {}: {}", aid.address, e))
+    })?;
+    let subaccount = match &aid.sub_account {
+        None => None,
+        Some(sub_account) => Some(
+            Subaccount::try_from(from_hex(&sub_account.address)?.as_slice()).map_err(|_| {
+                ApiError::invalid_account_id(format!(
+                    "Invalid subaccount {}: expected 32 bytes",
+                    sub_account.address
+                ))
+            })?,
+        ),
+    };
+    Ok(Account { owner, subaccount })
+}
+
+pub fn block_id(hb: &HashedBlock) -> Result<BlockIdentifier, ApiError> {
+    let index = i64::try_from(hb.index).map_err(|_| {
+        ApiError::internal_error("block index is too large to be converted from a u64 to an i64")
+    })?;
+    Ok(BlockIdentifier::new(index, from_hash(&hb.hash)))
+}
+
+pub fn timestamp(nanos: u64) -> Result<models::timestamp::Timestamp, ApiError> {
+    models::timestamp::from_system_time(TimeStamp::from_nanos_since_unix_epoch(nanos).into())
+}
+
+fn amount(value: i128, currency: &Currency) -> Amount {
+    Amount::new(value.to_string(), currency.clone())
+}
+
+fn operation(
+    index: usize,
+    op_type: OperationType,
+    account: &Account,
+    value: i128,
+    currency: &Currency,
+    status: &Option<String>,
+) -> Operation {
+    Operation::new(
+        index as i64,
+        op_type,
+        status.clone(),
+        Some(to_model_account_identifier(account)),
+        Some(amount(value, currency)),
+        None,
+    )
+}
+
+/// Returns the operations describing a transfer: a debit of the source, a
+/// credit of the destination and, if known, the fee paid by the source.
+pub fn transfer_to_operations(
+    transfer: &Transfer,
+    currency: &Currency,
+    status: Option<String>,
+) -> Vec<Operation> {
+    let amount = i128::from(transfer.amount);
+    let mut ops = vec![
+        operation(
+            0,
+            OperationType::Transaction,
+            &transfer.from,
+            -amount,
+            currency,
+            &status,
+        ),
+        operation(
+            1,
+            OperationType::Transaction,
+            &transfer.to,
+            amount,
+            currency,
+            &status,
+        ),
+    ];
+    if let Some(fee) = transfer.fee {
+        ops.push(operation(
+            2,
+            OperationType::Fee,
+            &transfer.from,
+            -i128::from(fee),
+            currency,
+            &status,
+        ));
+    }
+    ops
+}
+
+fn operation_value(op: &Operation, currency: &Currency) -> Result<i128, ApiError> {
+    let amount = op
+        .amount
+        .as_ref()
+        .ok_or_else(|| ApiError::invalid_request("Operations must have an amount"))?;
+    if &amount.currency != currency {
+        return Err(ApiError::invalid_request(format!(
+            "Unsupported currency {}, expected {}",
+            amount.currency.symbol, currency.symbol
+        )));
+    }
+    amount
+        .value
+        .parse::<i128>()
+        .map_err(|e| ApiError::invalid_request(format!("Invalid amount {}: {}", amount.value, e)))
+}
+
+fn operation_account(op: &Operation) -> Result<Account, ApiError> {
+    op.account
+        .as_ref()
+        .ok_or_else(|| ApiError::invalid_request("Operations must have an account"))
+        .and_then(from_model_account_identifier)
+}
+
+/// Parses the operations of a transfer produced by a Rosetta client.
+///
+/// Expects exactly two TRANSACTION operations, debiting the source and
+/// crediting the destination with the same amount, and optionally a FEE
+/// operation debiting the source.
+pub fn operations_to_transfer(
+    ops: &[Operation],
+    currency: &Currency,
+) -> Result<Transfer, ApiError> {
+    let mut debit = None;
+    let mut credit = None;
+    let mut fee = None;
+
+    for op in ops {
+        let account = operation_account(op)?;
+        let value = operation_value(op, currency)?;
+        let slot = match (&op._type, value < 0) {
+            (OperationType::Transaction, true) => &mut debit,
+            (OperationType::Transaction, false) => &mut credit,
+            (OperationType::Fee, true) => &mut fee,
+            (op_type, _) => {
+                return Err(ApiError::invalid_request(format!(
+                    "Unsupported operation {} with amount {}",
+                    op_type, value
+                )))
+            }
+        };
+        if slot.replace((account, value)).is_some() {
+            return Err(ApiError::invalid_request(format!(
+                "Duplicate {} operation",
+                op._type
+            )));
+        }
+    }
+
+    let (from, debit) =
+        debit.ok_or_else(|| ApiError::invalid_request("Missing the debit operation"))?;
+    let (to, credit) =
+        credit.ok_or_else(|| ApiError::invalid_request("Missing the credit operation"))?;
+    if debit + credit != 0 {
+        return Err(ApiError::invalid_request(
+            "The debit and credit amounts do not match",
+        ));
+    }
+    let amount = u64::try_from(credit)
+        .map_err(|_| ApiError::invalid_request(format!("Amount {} is too large", credit)))?;
+
+    let fee = match fee {
+        None => None,
+        Some((payer, _)) if payer != from => {
+            return Err(ApiError::invalid_request(
+                "The fee must be paid by the source account",
+            ))
+        }
+        Some((_, value)) => Some(
+            u64::try_from(-value)
+                .map_err(|_| ApiError::invalid_request(format!("Fee {} is too large", -value)))?,
+        ),
+    };
+
+    Ok(Transfer {
+        from,
+        to,
+        amount,
+        fee,
+    })
+}
+
+/// Converts a verified block to a Rosetta transaction.
+pub fn block_to_transaction(
+    hb: &HashedBlock,
+    currency: &Currency,
+) -> Result<models::Transaction, ApiError> {
+    let status = Some(STATUS_COMPLETED.to_string());
+    let transaction = &hb.block.transaction;
+    let mut metadata = Object::new();
+
+    let operations = match &transaction.operation {
+        LedgerOperation::Mint { to, amount } => vec![operation(
+            0,
+            OperationType::Mint,
+            to,
+            i128::from(*amount),
+            currency,
+            &status,
+        )],
+        LedgerOperation::Burn { from, amount } => vec![operation(
+            0,
+            OperationType::Burn,
+            from,
+            -i128::from(*amount),
+            currency,
+            &status,
+        )],
+        LedgerOperation::Transfer {
+            from,
+            to,
+            amount,
+            spender,
+            ..
+        } => {
+            if let Some(spender) = spender {
+                metadata.insert("spender".to_string(), json!(spender.to_string()));
+            }
+            let transfer = Transfer {
+                from: *from,
+                to: *to,
+                amount: *amount,
+                fee: charged_fee(&hb.block),
+            };
+            transfer_to_operations(&transfer, currency, status)
+        }
+        LedgerOperation::Approve {
+            from,
+            spender,
+            amount,
+            expires_at,
+            ..
+        } => {
+            metadata.insert(
+                "approve".to_string(),
+                json!({
+                    "spender": spender.to_string(),
+                    "allowance": amount,
+                    "expires_at": expires_at,
+                }),
+            );
+            let fee = charged_fee(&hb.block).unwrap_or(0);
+            vec![operation(
+                0,
+                OperationType::Fee,
+                from,
+                -i128::from(fee),
+                currency,
+                &status,
+            )]
+        }
+    };
+
+    metadata.insert("block_height".to_string(), json!(hb.index));
+    metadata.insert("timestamp".to_string(), json!(hb.block.timestamp));
+    if let Some(memo) = &transaction.memo {
+        metadata.insert(
+            "memo".to_string(),
+            Value::String(hex::encode(memo.0.as_slice())),
+        );
+    }
+    if let Some(created_at_time) = transaction.created_at_time {
+        metadata.insert("created_at_time".to_string(), json!(created_at_time));
+    }
+
+    let mut t = models::Transaction::new(
+        TransactionIdentifier {
+            hash: from_hash(&hb.tx_hash),
+        },
+        operations,
+    );
+    t.metadata = Some(metadata);
+    Ok(t)
+}
@@ -0,0 +1,160 @@
+use async_trait::async_trait;
+use ic_agent::agent::http_transport::ReqwestHttpReplicaV2Transport;
+use ic_agent::Agent;
+use ic_icrc1_agent::{
+    BlockRange, CallMode, GetBlocksArgs, GetBlocksResponse, Icrc1Agent, Icrc1AgentError,
+    QueryBlockArchiveFn,
+};
+use ic_rosetta_api::errors::{ApiError, ICError};
+use ic_types::messages::{HttpCallContent, HttpRequestEnvelope, SignedRequestBytes};
+use ic_types::CanisterId;
+use log::debug;
+use num_traits::ToPrimitive;
+use std::convert::TryFrom;
+use std::time::Duration;
+use url::Url;
+
+/// Access to an ICRC-1 ledger and its archives.
+///
+/// The futures returned by the methods are not required to be `Send`, so
+/// that tests can implement the trait on top of a `StateMachine`.
+#[async_trait(?Send)]
+pub trait LedgerAccess {
+    fn ledger_canister_id(&self) -> CanisterId;
+
+    /// Fetches blocks from the ledger together with the certificate of
+    /// the tip of the chain, see the ledger `get_blocks` endpoint.
+    async fn get_blocks(&self, args: GetBlocksArgs) -> Result<GetBlocksResponse, ApiError>;
+
+    /// Fetches blocks from the archive referenced by the callback.
+    async fn get_blocks_from_archive(
+        &self,
+        callback: &QueryBlockArchiveFn,
+        args: GetBlocksArgs,
+    ) -> Result<BlockRange, ApiError>;
+
+    async fn symbol(&self) -> Result<String, ApiError>;
+
+    async fn decimals(&self) -> Result<u8, ApiError>;
+
+    async fn fee(&self) -> Result<u64, ApiError>;
+
+    /// Submits a signed update call to the ledger without waiting for its
+    /// execution. Clients learn about the outcome from the synchronized
+    /// blocks.
+    async fn submit(&self, envelope: HttpRequestEnvelope<HttpCallContent>) -> Result<(), ApiError>;
+}
+
+pub struct LedgerClient {
+    agent: Icrc1Agent,
+    canister_id: CanisterId,
+    ic_url: Url,
+    http_client: reqwest::Client,
+}
+
+impl LedgerClient {
+    const SUBMIT_TIMEOUT: Duration = Duration::from_secs(20);
+
+    pub fn new(ic_url: Url, canister_id: CanisterId) -> Result<Self, ApiError> {
+        let transport = ReqwestHttpReplicaV2Transport::create(ic_url.clone())
+            .map_err(|e| ApiError::internal_error(format!("Cannot create transport: {}", e)))?;
+        let agent = Agent::builder()
+            .with_transport(transport)
+            .build()
+            .map_err(|e| ApiError::internal_error(format!("Cannot create agent: {}", e)))?;
+        Ok(Self {
+            agent: Icrc1Agent {
+                agent,
+                ledger_canister_id: canister_id.get().0,
+            },
+            canister_id,
+            ic_url,
+            http_client: reqwest::Client::new(),
+        })
+    }
+}
+
+fn agent_error(e: Icrc1AgentError) -> ApiError {
+    ApiError::internal_error(format!("Ledger query failed: {:?}", e))
+}
+
+#[async_trait(?Send)]
+impl LedgerAccess for LedgerClient {
+    fn ledger_canister_id(&self) -> CanisterId {
+        self.canister_id
+    }
+
+    async fn get_blocks(&self, args: GetBlocksArgs) -> Result<GetBlocksResponse, ApiError> {
+        self.agent.get_blocks(args).await.map_err(agent_error)
+    }
+
+    async fn get_blocks_from_archive(
+        &self,
+        callback: &QueryBlockArchiveFn,
+        args: GetBlocksArgs,
+    ) -> Result<BlockRange, ApiError> {
+        self.agent
+            .get_blocks_from_archive(callback, args)
+            .await
+            .map_err(agent_error)
+    }
+
+    async fn symbol(&self) -> Result<String, ApiError> {
+        self.agent
+            .symbol(CallMode::Query)
+            .await
+            .map_err(agent_error)
+    }
+
+    async fn decimals(&self) -> Result<u8, ApiError> {
+        self.agent
+            .decimals(CallMode::Query)
+            .await
+            .map_err(agent_error)
+    }
+
+    async fn fee(&self) -> Result<u64, ApiError> {
+        let fee = self.agent.fee(CallMode::Query).await.map_err(agent_error)?;
+        fee.0
+            .to_u64()
+            .ok_or_else(|| ApiError::internal_error(format!("Fee {} does not fit into u64", fee)))
+    }
+
+    async fn submit(&self, envelope: HttpRequestEnvelope<HttpCallContent>) -> Result<(), ApiError> {
+        let http_body = SignedRequestBytes::try_from(envelope).map_err(|e| {
+            ApiError::internal_error(format!(
+                "Cannot serialize the submit request in CBOR format because of: {}",
+                e
+            ))
+        })?;
+        let url = self
+            .ic_url
+            .join(&ic_canister_client::update_path(self.canister_id))
+            .expect("URL join failed");
+        debug!("Submitting a transaction to {}", url);
+
+        let response = self
+            .http_client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/cbor")
+            .body(Vec::<u8>::from(http_body))
+            .timeout(Self::SUBMIT_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| ApiError::internal_error(format!("Sending post request failed: {}", e)))?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let error_message = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "<undecodable>".to_owned());
+        Err(ApiError::ICError(ICError {
+            retriable: status.is_server_error(),
+            error_message,
+            ic_http_status: status.as_u16(),
+        }))
+    }
+}
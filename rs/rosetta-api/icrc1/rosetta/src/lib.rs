@@ -0,0 +1,17 @@
+//! A Rosetta API node for ICRC-1 ledgers.
+//!
+//! The node reuses the Rosetta models of the ICP node (`ic-rosetta-api`) but
+//! understands the ICRC-1 block format: blocks are fetched with the
+//! `get_blocks` endpoint of the ledger and its archives, the tip of the chain
+//! is checked against the certified data of the ledger, and accounts are
+//! mapped to Rosetta account identifiers as `(owner, subaccount)` pairs.
+
+pub mod blocks;
+pub mod convert;
+pub mod ledger_client;
+pub mod models;
+pub mod request_handler;
+pub mod rosetta_server;
+pub mod synchronizer;
+
+pub const NODE_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -0,0 +1,103 @@
+use clap::Parser;
+use ic_crypto_utils_threshold_sig_der::{
+    parse_threshold_sig_key, parse_threshold_sig_key_from_der,
+};
+use ic_icrc1_rosetta::ledger_client::LedgerClient;
+use ic_icrc1_rosetta::request_handler::RosettaRequestHandler;
+use ic_icrc1_rosetta::rosetta_server::RosettaApiServer;
+use ic_rosetta_api::DEFAULT_BLOCKCHAIN;
+use ic_types::{CanisterId, PrincipalId};
+use std::{path::PathBuf, str::FromStr, sync::Arc};
+use url::Url;
+
+const MAINNET_ROOT_KEY: &str = "MIGCMB0GDSsGAQQBgtx8BQMBAgEGDCsGAQQBgtx8BQMCAQNhAIFMDm7HH6tYOwi9gTc8JVw8NxsuhIY8mKTx4It0I10U+12cDNVG2WhfkToMCyzFNBWDv0tDkuRn25bWW5u0y3FxEvhHLg1aTRRQX/10hLASkQkcX4e5iINGP5gJGguqrg==";
+
+/// Hosts of the IC mainnet boundary nodes, the mainnet root key is used when
+/// `--ic-url` points to one of them and no `--root-key` is given.
+const MAINNET_HOSTS: &[&str] = &["ic0.app", "icp0.io", "icp-api.io"];
+
+#[derive(Debug, Parser)]
+#[clap(version)]
+struct Opt {
+    #[clap(short = 'a', long = "address", default_value = "0.0.0.0")]
+    listen_address: String,
+    #[clap(short = 'p', long = "port", default_value = "8082")]
+    listen_port: u16,
+    /// Id of the ICRC-1 ledger canister.
+    #[clap(short = 'c', long = "canister-id")]
+    ic_canister_id: String,
+    #[clap(long = "ic-url", default_value = "https://ic0.app")]
+    ic_url: String,
+    #[clap(
+        short = 'l',
+        long = "log-config-file",
+        default_value = "log_config.yml"
+    )]
+    log_config_file: PathBuf,
+    /// Path to the DER-encoded root key used to verify the certified tip of
+    /// the chain. Defaults to the mainnet root key if `--ic-url` points to
+    /// the mainnet, and is required otherwise.
+    #[clap(long = "root-key")]
+    root_key: Option<PathBuf>,
+    #[clap(long = "exit-on-sync")]
+    exit_on_sync: bool,
+    /// The name of the blockchain reported in the network identifier.
+    #[clap(long = "blockchain", default_value = DEFAULT_BLOCKCHAIN)]
+    blockchain: String,
+    #[clap(long = "expose-metrics")]
+    expose_metrics: bool,
+}
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    let opt = Opt::parse();
+
+    if let Err(e) = log4rs::init_file(opt.log_config_file.as_path(), Default::default()) {
+        panic!(
+            "icrc1-rosetta failed to load log configuration file: {}, error: {}. (current_dir is: {:?})",
+            &opt.log_config_file.as_path().display(),
+            e,
+            std::env::current_dir()
+        );
+    }
+
+    let pkg_name = env!("CARGO_PKG_NAME");
+    let pkg_version = env!("CARGO_PKG_VERSION");
+    log::info!("Starting {}, pkg_version: {}", pkg_name, pkg_version);
+    log::info!("Listening on {}:{}", opt.listen_address, opt.listen_port);
+    let addr = format!("{}:{}", opt.listen_address, opt.listen_port);
+
+    let canister_id =
+        CanisterId::new(PrincipalId::from_str(&opt.ic_canister_id[..]).unwrap()).unwrap();
+    let url = Url::parse(&opt.ic_url[..]).unwrap();
+    let root_key = match opt.root_key {
+        Some(root_key_path) => parse_threshold_sig_key(root_key_path.as_path())?,
+        None if is_mainnet(&url) => parse_threshold_sig_key_from_der(
+            &base64::decode(MAINNET_ROOT_KEY).expect("invalid Mainnet root key"),
+        )?,
+        None => panic!(
+            "--root-key is required to verify the blocks of a ledger outside of the mainnet ({})",
+            url
+        ),
+    };
+
+    let client = LedgerClient::new(url, canister_id)
+        .unwrap_or_else(|e| panic!("Failed to initialize ledger client: {:?}", e));
+    let req_handler = RosettaRequestHandler::new(opt.blockchain, Arc::new(client), root_key)
+        .await
+        .unwrap_or_else(|e| panic!("Failed to fetch the ledger metadata: {:?}", e));
+    log::info!("Network id: {:?}", req_handler.network_id());
+    log::info!("Currency: {:?}", req_handler.currency());
+
+    let serv = RosettaApiServer::new(req_handler, addr, opt.expose_metrics)
+        .expect("Error creating RosettaApiServer");
+    serv.run(opt.exit_on_sync).await
+}
+
+fn is_mainnet(url: &Url) -> bool {
+    url.host_str().map_or(false, |host| {
+        MAINNET_HOSTS
+            .iter()
+            .any(|mainnet| host == *mainnet || host.ends_with(&format!(".{}", mainnet)))
+    })
+}
@@ -0,0 +1,38 @@
+//! Transaction blobs exchanged with clients of the construction API.
+//!
+//! All other request and response types are the ones of the ICP Rosetta
+//! node, see [`ic_rosetta_api::models`].
+
+use ic_rosetta_api::convert::from_hex;
+use ic_rosetta_api::errors::ApiError;
+use ic_types::messages::{HttpCallContent, HttpCanisterUpdate, HttpRequestEnvelope};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// An `icrc1_transfer` call waiting for signatures.
+///
+/// The call is signed once per ingress expiry so that the client can submit
+/// it at any time between the requested ingress start and end.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UnsignedTransaction {
+    pub update: HttpCanisterUpdate,
+    pub ingress_expiries: Vec<u64>,
+}
+
+/// The signed envelopes of an `icrc1_transfer` call, one per ingress expiry.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignedTransaction {
+    pub envelopes: Vec<HttpRequestEnvelope<HttpCallContent>>,
+}
+
+/// Encodes a transaction blob as hex-encoded CBOR.
+pub fn to_cbor_hex<T: Serialize>(value: &T) -> Result<String, ApiError> {
+    serde_cbor::to_vec(value)
+        .map(hex::encode)
+        .map_err(|e| ApiError::internal_error(format!("Serialization failed: {}", e)))
+}
+
+/// Decodes a hex-encoded CBOR transaction blob.
+pub fn from_cbor_hex<T: DeserializeOwned>(blob: &str) -> Result<T, ApiError> {
+    serde_cbor::from_slice(&from_hex(blob)?)
+        .map_err(|e| ApiError::invalid_request(format!("Could not decode the transaction: {}", e)))
+}
@@ -0,0 +1,426 @@
+mod construction;
+
+use crate::blocks::{Blocks, HashedBlock};
+use crate::convert::{self, from_model_account_identifier};
+use crate::ledger_client::LedgerAccess;
+use crate::synchronizer;
+use crate::NODE_VERSION;
+use ic_icrc1::Transaction;
+use ic_ledger_core::block::{EncodedBlock, HashOf};
+use ic_rosetta_api::errors::ApiError;
+use ic_rosetta_api::models::amount::Amount;
+use ic_rosetta_api::models::operation::OperationType;
+use ic_rosetta_api::models::{
+    self, AccountBalanceRequest, AccountBalanceResponse, Allow, BlockResponse, BlockTransaction,
+    BlockTransactionResponse, Currency, Error, MempoolResponse, MempoolTransactionResponse,
+    NetworkIdentifier, NetworkListResponse, NetworkOptionsResponse, NetworkStatusResponse,
+    OperationStatus, Operator, PartialBlockIdentifier, SearchTransactionsResponse, SyncStatus,
+    Version,
+};
+use ic_rosetta_api::request_types::STATUS_COMPLETED;
+use ic_rosetta_api::API_VERSION;
+use ic_types::crypto::threshold_sig::ThresholdSigPublicKey;
+use ic_types::CanisterId;
+use std::convert::{TryFrom, TryInto};
+use std::sync::{Arc, RwLock};
+
+/// The maximum amount of blocks to retrieve in a single search.
+const MAX_SEARCH_LIMIT: usize = 10_000;
+
+pub struct RosettaRequestHandler<L> {
+    blockchain: String,
+    ledger: Arc<L>,
+    blocks: Arc<RwLock<Blocks>>,
+    root_key: ThresholdSigPublicKey,
+    currency: Currency,
+}
+
+impl<L> Clone for RosettaRequestHandler<L> {
+    fn clone(&self) -> Self {
+        Self {
+            blockchain: self.blockchain.clone(),
+            ledger: self.ledger.clone(),
+            blocks: self.blocks.clone(),
+            root_key: self.root_key,
+            currency: self.currency.clone(),
+        }
+    }
+}
+
+// construction requests are implemented in their own module.
+impl<L: LedgerAccess> RosettaRequestHandler<L> {
+    /// Creates a handler serving the blocks of the given ledger. The token
+    /// symbol and decimals are fetched from the ledger.
+    pub async fn new(
+        blockchain: String,
+        ledger: Arc<L>,
+        root_key: ThresholdSigPublicKey,
+    ) -> Result<Self, ApiError> {
+        let symbol = ledger.symbol().await?;
+        let decimals = ledger.decimals().await?;
+        Ok(Self {
+            blockchain,
+            ledger,
+            blocks: Arc::new(RwLock::new(Blocks::default())),
+            root_key,
+            currency: Currency::new(symbol, u32::from(decimals)),
+        })
+    }
+
+    pub fn network_id(&self) -> NetworkIdentifier {
+        let canister_id = self.ledger.ledger_canister_id();
+        let net_id = hex::encode(canister_id.get().into_vec());
+        NetworkIdentifier::new(self.blockchain.clone(), net_id)
+    }
+
+    pub fn currency(&self) -> &Currency {
+        &self.currency
+    }
+
+    /// Downloads and verifies the blocks appended to the ledger since the
+    /// last call.
+    pub async fn sync_blocks(&self) -> Result<(), ApiError> {
+        synchronizer::sync_blocks(&*self.ledger, &self.blocks, &self.root_key).await
+    }
+
+    /// Get an Account Balance
+    pub fn account_balance(
+        &self,
+        msg: AccountBalanceRequest,
+    ) -> Result<AccountBalanceResponse, ApiError> {
+        self.verify_network_id(&msg.network_identifier)?;
+        let account = from_model_account_identifier(&msg.account_identifier)?;
+        let blocks = self.blocks.read().unwrap();
+        let hb = get_block(&blocks, msg.block_identifier)?;
+        let balance = blocks.get_account_balance(&account, hb.index);
+        Ok(AccountBalanceResponse::new(
+            convert::block_id(hb)?,
+            vec![Amount::new(balance.to_string(), self.currency.clone())],
+        ))
+    }
+
+    /// Get a Block
+    pub fn block(&self, msg: models::BlockRequest) -> Result<BlockResponse, ApiError> {
+        self.verify_network_id(&msg.network_identifier)?;
+        let blocks = self.blocks.read().unwrap();
+        let hb = get_block(&blocks, Some(msg.block_identifier))?;
+        let parent = match hb.index.checked_sub(1) {
+            // For the first block, we return the block itself as its parent
+            None => hb,
+            Some(parent_index) => blocks
+                .get_verified(parent_index)
+                .ok_or(ApiError::InvalidBlockId(true, Default::default()))?,
+        };
+        let block = models::Block::new(
+            convert::block_id(hb)?,
+            convert::block_id(parent)?,
+            convert::timestamp(hb.block.timestamp)?,
+            vec![convert::block_to_transaction(hb, &self.currency)?],
+        );
+        Ok(BlockResponse {
+            block: Some(block),
+            other_transactions: None,
+        })
+    }
+
+    /// Get a Block Transaction
+    pub fn block_transaction(
+        &self,
+        msg: models::BlockTransactionRequest,
+    ) -> Result<BlockTransactionResponse, ApiError> {
+        self.verify_network_id(&msg.network_identifier)?;
+        let blocks = self.blocks.read().unwrap();
+        let hb = get_block(
+            &blocks,
+            Some(PartialBlockIdentifier {
+                index: Some(msg.block_identifier.index),
+                hash: Some(msg.block_identifier.hash),
+            }),
+        )?;
+        let transaction = convert::block_to_transaction(hb, &self.currency)?;
+        if transaction.transaction_identifier != msg.transaction_identifier {
+            return Err(ApiError::InvalidTransactionId(
+                false,
+                "the block does not contain the transaction".into(),
+            ));
+        }
+        Ok(BlockTransactionResponse::new(transaction))
+    }
+
+    /// Get All Mempool Transactions
+    pub fn mempool(&self, msg: models::NetworkRequest) -> Result<MempoolResponse, ApiError> {
+        self.verify_network_id(&msg.network_identifier)?;
+        Ok(MempoolResponse::new(vec![]))
+    }
+
+    /// Get a Mempool Transaction
+    pub fn mempool_transaction(
+        &self,
+        msg: models::MempoolTransactionRequest,
+    ) -> Result<MempoolTransactionResponse, ApiError> {
+        self.verify_network_id(&msg.network_identifier)?;
+        Err(ApiError::MempoolTransactionMissing(
+            false,
+            Default::default(),
+        ))
+    }
+
+    /// Get List of Available Networks
+    pub fn network_list(
+        &self,
+        _metadata_request: models::MetadataRequest,
+    ) -> Result<NetworkListResponse, ApiError> {
+        Ok(NetworkListResponse::new(vec![self.network_id()]))
+    }
+
+    /// Get Network Options
+    pub fn network_options(
+        &self,
+        msg: models::NetworkRequest,
+    ) -> Result<NetworkOptionsResponse, ApiError> {
+        self.verify_network_id(&msg.network_identifier)?;
+
+        let mut errors = vec![
+            Error::new(&ApiError::InternalError(true, Default::default())),
+            Error::new(&ApiError::InvalidRequest(false, Default::default())),
+            Error::new(&ApiError::InvalidNetworkId(false, Default::default())),
+            Error::new(&ApiError::InvalidAccountId(false, Default::default())),
+            Error::new(&ApiError::InvalidBlockId(false, Default::default())),
+            Error::new(&ApiError::InvalidPublicKey(false, Default::default())),
+            Error::new(&ApiError::InvalidTransactionId(false, Default::default())),
+            Error::new(&ApiError::MempoolTransactionMissing(
+                false,
+                Default::default(),
+            )),
+            Error::new(&ApiError::BlockchainEmpty(false, Default::default())),
+            Error::new(&ApiError::InvalidTransaction(false, Default::default())),
+            Error::new(&ApiError::ICError(Default::default())),
+            Error::new(&ApiError::TransactionExpired),
+        ];
+        // We don't want to return any schema for details.
+        for e in errors.iter_mut() {
+            e.details = Default::default();
+        }
+
+        Ok(NetworkOptionsResponse::new(
+            Version::new(
+                API_VERSION.to_string(),
+                NODE_VERSION.to_string(),
+                None,
+                None,
+            ),
+            Allow::new(
+                vec![OperationStatus::new(STATUS_COMPLETED.to_string(), true)],
+                [
+                    OperationType::Transaction,
+                    OperationType::Mint,
+                    OperationType::Burn,
+                    OperationType::Fee,
+                ]
+                .iter()
+                .map(|op| op.to_string())
+                .collect(),
+                errors,
+                true,
+            ),
+        ))
+    }
+
+    /// Get Network Status
+    pub fn network_status(
+        &self,
+        msg: models::NetworkRequest,
+    ) -> Result<NetworkStatusResponse, ApiError> {
+        self.verify_network_id(&msg.network_identifier)?;
+        let blocks = self.blocks.read().unwrap();
+        let tip = blocks
+            .verified_tip()
+            .ok_or(ApiError::BlockchainEmpty(true, Default::default()))?;
+        let genesis = blocks
+            .get_verified(0)
+            .expect("bug: the genesis block must be verified before the tip");
+        let mut sync_status = SyncStatus::new(tip.index as i64, None);
+        sync_status.target_index = i64::try_from(blocks.len().saturating_sub(1)).ok();
+        Ok(NetworkStatusResponse::new(
+            convert::block_id(tip)?,
+            convert::timestamp(tip.block.timestamp)?,
+            convert::block_id(genesis)?,
+            None,
+            sync_status,
+            vec![],
+        ))
+    }
+
+    /// Search for transactions by transaction hash or account, most recent
+    /// first.
+    pub fn search_transactions(
+        &self,
+        msg: models::SearchTransactionsRequest,
+    ) -> Result<SearchTransactionsResponse, ApiError> {
+        self.verify_network_id(&msg.network_identifier)?;
+
+        if let Some(Operator::Or) = msg.operator {
+            return Err(ApiError::invalid_request("Operator OR not supported"));
+        }
+        if msg.coin_identifier.is_some() {
+            return Err(ApiError::invalid_request("coin_identifier not supported"));
+        }
+        if msg.currency.is_some() {
+            return Err(ApiError::invalid_request("currency not supported"));
+        }
+        if msg.status.is_some() {
+            return Err(ApiError::invalid_request("status not supported"));
+        }
+        if msg._type.is_some() {
+            return Err(ApiError::invalid_request("type not supported"));
+        }
+        if msg.address.is_some() {
+            return Err(ApiError::invalid_request("address not supported"));
+        }
+        if msg.success.is_some() {
+            return Err(ApiError::invalid_request("success not supported"));
+        }
+
+        let offset = match msg.offset {
+            Some(x) => usize::try_from(x)
+                .map_err(|e| ApiError::invalid_request(format!("Invalid offset: {}", e)))?,
+            None => 0,
+        };
+        let limit = match msg.limit {
+            Some(x) => usize::try_from(x)
+                .map_err(|e| ApiError::invalid_request(format!("Invalid limit: {}", e)))?,
+            None => usize::MAX,
+        }
+        .min(MAX_SEARCH_LIMIT);
+
+        let blocks = self.blocks.read().unwrap();
+        let tip_index = match blocks.verified_tip() {
+            Some(tip) => tip.index,
+            None => return Ok(SearchTransactionsResponse::new(vec![], 0, None)),
+        };
+        let max_block = match msg.max_block {
+            Some(x) => u64::try_from(x)
+                .map_err(|e| ApiError::invalid_request(format!("Invalid max_block: {}", e)))?
+                .min(tip_index),
+            None => tip_index,
+        };
+
+        let matching =
+            match (&msg.transaction_identifier, &msg.account_identifier) {
+                (Some(_), Some(_)) => return Err(ApiError::invalid_request(
+                    "Only one of transaction_identifier and account_identifier should be populated",
+                )),
+                (Some(tid), None) => {
+                    let tx_hash: HashOf<Transaction> = tid
+                        .hash
+                        .parse()
+                        .map_err(|e: String| ApiError::InvalidTransactionId(false, e.into()))?;
+                    Some(blocks.get_verified_idxs_by_tx_hash(&tx_hash))
+                }
+                (None, Some(aid)) => {
+                    let account = from_model_account_identifier(aid)?;
+                    Some(blocks.get_verified_account_history(&account))
+                }
+                (None, None) => None,
+            };
+
+        let (total_count, indices): (u64, Vec<u64>) = match matching {
+            Some(indices) => {
+                let indices: Vec<u64> = indices
+                    .into_iter()
+                    .filter(|index| *index <= max_block)
+                    .rev()
+                    .collect();
+                let total_count = indices.len() as u64;
+                (
+                    total_count,
+                    indices.into_iter().skip(offset).take(limit).collect(),
+                )
+            }
+            None => {
+                let total_count = max_block + 1;
+                let end = total_count.saturating_sub(offset as u64);
+                let start = end.saturating_sub(limit as u64);
+                (total_count, (start..end).rev().collect())
+            }
+        };
+
+        let mut txs = Vec::with_capacity(indices.len());
+        for index in indices.iter() {
+            let hb = blocks
+                .get_verified(*index)
+                .ok_or(ApiError::InvalidBlockId(true, Default::default()))?;
+            txs.push(BlockTransaction::new(
+                convert::block_id(hb)?,
+                convert::block_to_transaction(hb, &self.currency)?,
+            ));
+        }
+
+        let returned = (offset as u64).saturating_add(indices.len() as u64);
+        let next_offset = if returned < total_count {
+            Some(i64::try_from(returned).map_err(|e| {
+                ApiError::internal_error(format!("Next offset cannot fit in i64: {}", e))
+            })?)
+        } else {
+            None
+        };
+        let total_count = i64::try_from(total_count).map_err(|e| {
+            ApiError::internal_error(format!("Total count does not fit in i64: {}", e))
+        })?;
+
+        Ok(SearchTransactionsResponse::new(
+            txs,
+            total_count,
+            next_offset,
+        ))
+    }
+
+    fn verify_network_id(&self, net_id: &NetworkIdentifier) -> Result<(), ApiError> {
+        if net_id.blockchain != self.blockchain {
+            return Err(ApiError::InvalidNetworkId(
+                false,
+                "unknown blockchain".into(),
+            ));
+        }
+        let id: CanisterId = net_id.try_into()?;
+        if id != self.ledger.ledger_canister_id() {
+            return Err(ApiError::InvalidNetworkId(false, "unknown network".into()));
+        }
+        Ok(())
+    }
+}
+
+fn get_block(
+    blocks: &Blocks,
+    block_id: Option<PartialBlockIdentifier>,
+) -> Result<&HashedBlock, ApiError> {
+    let index = match block_id.as_ref().and_then(|id| id.index) {
+        Some(index) => Some(u64::try_from(index).map_err(|_| {
+            ApiError::InvalidBlockId(false, format!("Invalid block index {}", index).into())
+        })?),
+        None => None,
+    };
+    let hash: Option<HashOf<EncodedBlock>> = match block_id.and_then(|id| id.hash) {
+        Some(hash) => Some(
+            hash.parse()
+                .map_err(|e: String| ApiError::InvalidBlockId(false, e.into()))?,
+        ),
+        None => None,
+    };
+
+    let block = match (index, hash) {
+        (Some(index), _) => blocks.get_verified(index),
+        (None, Some(hash)) => blocks.get_verified_by_hash(&hash),
+        (None, None) => {
+            return blocks
+                .verified_tip()
+                .ok_or(ApiError::BlockchainEmpty(true, Default::default()))
+        }
+    }
+    .ok_or(ApiError::InvalidBlockId(true, Default::default()))?;
+
+    if hash.map_or(false, |hash| hash != block.hash) {
+        return Err(ApiError::InvalidBlockId(false, Default::default()));
+    }
+    Ok(block)
+}
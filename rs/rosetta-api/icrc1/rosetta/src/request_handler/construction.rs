@@ -0,0 +1,397 @@
+use super::RosettaRequestHandler;
+use crate::convert::{self, Transfer};
+use crate::ledger_client::LedgerAccess;
+use crate::models::{from_cbor_hex, to_cbor_hex, SignedTransaction, UnsignedTransaction};
+use candid::{Decode, Encode, Nat};
+use ic_canister_client_sender::{Ed25519KeyPair as EdKeypair, Secp256k1KeyPair};
+use ic_icrc1::endpoints::TransferArg;
+use ic_icrc1::{Account, Memo, Operation as LedgerOperation, Transaction};
+use ic_ledger_canister_core::ledger::LedgerTransaction;
+use ic_rosetta_api::convert::{from_hash, from_hex, principal_id_from_public_key};
+use ic_rosetta_api::errors::ApiError;
+use ic_rosetta_api::models::amount::Amount;
+use ic_rosetta_api::models::{
+    self, ConstructionCombineResponse, ConstructionDeriveResponse, ConstructionHashResponse,
+    ConstructionMetadataResponse, ConstructionParseResponse, ConstructionPayloadsRequestMetadata,
+    ConstructionPayloadsResponse, ConstructionPreprocessResponse, CurveType, PublicKey,
+    RosettaSupportedKeyPair, SignatureType, SigningPayload, TransactionIdentifierResponse,
+};
+use ic_rosetta_api::request_handler::make_sig_data;
+use ic_rosetta_api::transaction_id::TransactionIdentifier;
+use ic_types::messages::{Blob, HttpCallContent, HttpCanisterUpdate, HttpRequestEnvelope};
+use ic_types::PrincipalId;
+use num_traits::ToPrimitive;
+use serde_json::map::Map;
+use std::collections::HashMap;
+use std::time::Duration;
+
+const TRANSFER_METHOD: &str = "icrc1_transfer";
+
+impl<L: LedgerAccess> RosettaRequestHandler<L> {
+    /// Derive an AccountIdentifier from a PublicKey.
+    /// The derived account is the default subaccount of the self-authenticating
+    /// principal of the key.
+    pub fn construction_derive(
+        &self,
+        msg: models::ConstructionDeriveRequest,
+    ) -> Result<ConstructionDeriveResponse, ApiError> {
+        self.verify_network_id(&msg.network_identifier)?;
+        let owner = principal_id_from_public_key(&msg.public_key)?;
+        Ok(ConstructionDeriveResponse {
+            account_identifier: Some(convert::to_model_account_identifier(&Account::from(owner))),
+            address: None,
+            metadata: None,
+        })
+    }
+
+    /// Create a Request to Fetch Metadata.
+    /// The only key required to sign a transfer is the key of the owner of
+    /// the source account.
+    pub fn construction_preprocess(
+        &self,
+        msg: models::ConstructionPreprocessRequest,
+    ) -> Result<ConstructionPreprocessResponse, ApiError> {
+        self.verify_network_id(&msg.network_identifier)?;
+        let transfer = convert::operations_to_transfer(&msg.operations, &self.currency)?;
+        Ok(ConstructionPreprocessResponse {
+            options: None,
+            required_public_keys: Some(vec![convert::to_model_account_identifier(&Account::from(
+                transfer.from.owner,
+            ))]),
+        })
+    }
+
+    /// Get Metadata for Transaction Construction.
+    /// The suggested fee is the current transfer fee of the ledger.
+    pub async fn construction_metadata(
+        &self,
+        msg: models::ConstructionMetadataRequest,
+    ) -> Result<ConstructionMetadataResponse, ApiError> {
+        self.verify_network_id(&msg.network_identifier)?;
+        let fee = self.ledger.fee().await?;
+        Ok(ConstructionMetadataResponse {
+            metadata: ConstructionPayloadsRequestMetadata::default(),
+            suggested_fee: Some(vec![Amount::new(fee.to_string(), self.currency.clone())]),
+        })
+    }
+
+    /// Generate an Unsigned Transaction and Signing Payloads.
+    /// The unsigned transaction is an `icrc1_transfer` call, signed once per
+    /// ingress expiry between the requested ingress start and end.
+    pub fn construction_payloads(
+        &self,
+        msg: models::ConstructionPayloadsRequest,
+    ) -> Result<ConstructionPayloadsResponse, ApiError> {
+        self.verify_network_id(&msg.network_identifier)?;
+        let transfer = convert::operations_to_transfer(&msg.operations, &self.currency)?;
+
+        let pks = msg.public_keys.clone().ok_or_else(|| {
+            ApiError::internal_error("Expected field 'public_keys' to be populated")
+        })?;
+        let pks_map = pks
+            .iter()
+            .map(|pk| Ok((principal_id_from_public_key(pk)?, pk)))
+            .collect::<Result<HashMap<PrincipalId, &PublicKey>, ApiError>>()?;
+        let pk = pks_map.get(&transfer.from.owner).ok_or_else(|| {
+            ApiError::internal_error(format!(
+                "Cannot find public key for principal {}",
+                transfer.from.owner
+            ))
+        })?;
+        let signature_type = match pk.curve_type {
+            CurveType::Edwards25519 => SignatureType::Ed25519,
+            CurveType::Secp256K1 => SignatureType::Ecdsa,
+            curve_type => {
+                return Err(ApiError::InvalidPublicKey(
+                    false,
+                    format!("Curve Type {} is not supported", curve_type).into(),
+                ))
+            }
+        };
+
+        let interval = ic_constants::MAX_INGRESS_TTL
+            - ic_constants::PERMITTED_DRIFT
+            - Duration::from_secs(120);
+
+        let meta = msg.metadata.as_ref();
+
+        let ingress_start = meta
+            .and_then(|meta| meta.ingress_start)
+            .map(ic_types::time::Time::from_nanos_since_unix_epoch)
+            .unwrap_or_else(ic_types::time::current_time);
+
+        let ingress_end = meta
+            .and_then(|meta| meta.ingress_end)
+            .map(ic_types::time::Time::from_nanos_since_unix_epoch)
+            .unwrap_or_else(|| ingress_start + interval);
+
+        // The ledger deduplicates transactions with created_at_time set, so a
+        // transfer submitted with several envelopes is executed only once.
+        let created_at_time = meta
+            .and_then(|meta| meta.created_at_time)
+            .unwrap_or_else(|| ic_types::time::current_time().as_nanos_since_unix_epoch());
+
+        let mut ingress_expiries = vec![];
+        let mut now = ingress_start;
+        while now < ingress_end {
+            let ingress_expiry = (now + ic_constants::MAX_INGRESS_TTL
+                - ic_constants::PERMITTED_DRIFT)
+                .as_nanos_since_unix_epoch();
+            ingress_expiries.push(ingress_expiry);
+            now += interval;
+        }
+
+        let arg = TransferArg {
+            from_subaccount: transfer.from.subaccount,
+            to: transfer.to,
+            fee: transfer.fee.map(Nat::from),
+            created_at_time: Some(created_at_time),
+            memo: meta.and_then(|meta| meta.memo).map(Memo::from),
+            amount: Nat::from(transfer.amount),
+        };
+        let update = HttpCanisterUpdate {
+            canister_id: Blob(self.ledger.ledger_canister_id().get().to_vec()),
+            method_name: TRANSFER_METHOD.to_string(),
+            arg: Blob(Encode!(&arg).map_err(|e| {
+                ApiError::internal_error(format!("Cannot encode the transfer argument: {}", e))
+            })?),
+            // Retries are deduplicated through created_at_time, we don't need
+            // a nonce.
+            nonce: None,
+            sender: Blob(transfer.from.owner.to_vec()),
+            ingress_expiry: 0,
+        };
+
+        let account_identifier = convert::to_model_account_identifier(&transfer.from);
+        let payloads = ingress_expiries
+            .iter()
+            .map(|ingress_expiry| {
+                let mut update = update.clone();
+                update.ingress_expiry = *ingress_expiry;
+                SigningPayload {
+                    address: None,
+                    account_identifier: Some(account_identifier.clone()),
+                    hex_bytes: hex::encode(make_sig_data(&update.id())),
+                    signature_type: Some(signature_type),
+                }
+            })
+            .collect();
+
+        Ok(ConstructionPayloadsResponse {
+            unsigned_transaction: to_cbor_hex(&UnsignedTransaction {
+                update,
+                ingress_expiries,
+            })?,
+            payloads,
+        })
+    }
+
+    /// Create Network Transaction from Signatures.
+    /// The signed transaction contains one envelope per ingress expiry.
+    pub fn construction_combine(
+        &self,
+        msg: models::ConstructionCombineRequest,
+    ) -> Result<ConstructionCombineResponse, ApiError> {
+        self.verify_network_id(&msg.network_identifier)?;
+
+        let mut signatures_by_sig_data = HashMap::new();
+        for sig in &msg.signatures {
+            let sig_data = from_hex(&sig.signing_payload.hex_bytes)?;
+            signatures_by_sig_data.insert(sig_data, sig);
+        }
+
+        let unsigned: UnsignedTransaction = from_cbor_hex(&msg.unsigned_transaction)?;
+        let mut envelopes = vec![];
+        for ingress_expiry in &unsigned.ingress_expiries {
+            let mut update = unsigned.update.clone();
+            update.ingress_expiry = *ingress_expiry;
+
+            let signature = signatures_by_sig_data
+                .get(&make_sig_data(&update.id()))
+                .ok_or_else(|| {
+                    ApiError::internal_error("Could not find signature for transaction")
+                })?;
+            let sender_pubkey = match signature.signature_type {
+                SignatureType::Ed25519 => EdKeypair::der_encode_pk(EdKeypair::hex_decode_pk(
+                    &signature.public_key.hex_bytes,
+                )?)?,
+                SignatureType::Ecdsa => Secp256k1KeyPair::der_encode_pk(
+                    Secp256k1KeyPair::hex_decode_pk(&signature.public_key.hex_bytes)?,
+                )?,
+                sig_type => {
+                    return Err(ApiError::InvalidRequest(
+                        false,
+                        format!("Signature Type {} not supported by rosetta", sig_type).into(),
+                    ))
+                }
+            };
+            envelopes.push(HttpRequestEnvelope::<HttpCallContent> {
+                content: HttpCallContent::Call { update },
+                sender_pubkey: Some(Blob(sender_pubkey)),
+                sender_sig: Some(Blob(from_hex(&signature.hex_bytes)?)),
+                sender_delegation: None,
+            });
+        }
+
+        Ok(ConstructionCombineResponse {
+            signed_transaction: to_cbor_hex(&SignedTransaction { envelopes })?,
+        })
+    }
+
+    /// Parse a Transaction.
+    /// Works on both the unsigned transaction returned by
+    /// `/construction/payloads` and the signed one returned by
+    /// `/construction/combine`.
+    pub fn construction_parse(
+        &self,
+        msg: models::ConstructionParseRequest,
+    ) -> Result<ConstructionParseResponse, ApiError> {
+        self.verify_network_id(&msg.network_identifier)?;
+
+        let update = if msg.signed {
+            let signed: SignedTransaction = from_cbor_hex(&msg.transaction)?;
+            signed_update(&signed)?.clone()
+        } else {
+            let unsigned: UnsignedTransaction = from_cbor_hex(&msg.transaction)?;
+            unsigned.update
+        };
+        let (transfer, arg) = parse_transfer(&update)?;
+
+        let mut metadata = Map::new();
+        if let Some(memo) = &arg.memo {
+            metadata.insert(
+                "memo".to_string(),
+                serde_json::Value::String(hex::encode(memo.0.as_slice())),
+            );
+        }
+        if let Some(created_at_time) = arg.created_at_time {
+            metadata.insert("created_at_time".to_string(), created_at_time.into());
+        }
+
+        let account_identifier_signers = msg.signed.then(|| {
+            vec![convert::to_model_account_identifier(&Account::from(
+                transfer.from.owner,
+            ))]
+        });
+        Ok(ConstructionParseResponse {
+            operations: convert::transfer_to_operations(&transfer, &self.currency, None),
+            signers: None,
+            account_identifier_signers,
+            metadata: Some(metadata),
+        })
+    }
+
+    /// Get the Hash of a Signed Transaction.
+    /// This is the hash of the transaction the ledger records if it accepts
+    /// the transfer.
+    pub fn construction_hash(
+        &self,
+        msg: models::ConstructionHashRequest,
+    ) -> Result<ConstructionHashResponse, ApiError> {
+        self.verify_network_id(&msg.network_identifier)?;
+        let signed: SignedTransaction = from_cbor_hex(&msg.signed_transaction)?;
+        let (transfer, arg) = parse_transfer(signed_update(&signed)?)?;
+        Ok(ConstructionHashResponse {
+            transaction_identifier: transaction_identifier(&transfer, arg),
+            metadata: Map::new(),
+        })
+    }
+
+    /// Submit a Signed Transaction.
+    /// Submits the envelope valid at the current time and returns without
+    /// waiting for the transfer to be executed. The outcome is visible in
+    /// the synchronized blocks.
+    pub async fn construction_submit(
+        &self,
+        msg: models::ConstructionSubmitRequest,
+    ) -> Result<TransactionIdentifierResponse, ApiError> {
+        self.verify_network_id(&msg.network_identifier)?;
+        let signed: SignedTransaction = from_cbor_hex(&msg.signed_transaction)?;
+
+        let now = ic_types::time::current_time();
+        let envelope = signed
+            .envelopes
+            .into_iter()
+            .find(|envelope| {
+                let HttpCallContent::Call { update } = &envelope.content;
+                let ingress_expiry =
+                    ic_types::time::Time::from_nanos_since_unix_epoch(update.ingress_expiry);
+                let ingress_start = ingress_expiry
+                    - (ic_constants::MAX_INGRESS_TTL - ic_constants::PERMITTED_DRIFT);
+                ingress_start <= now && ingress_expiry > now
+            })
+            .ok_or(ApiError::TransactionExpired)?;
+
+        let HttpCallContent::Call { update } = &envelope.content;
+        if update.canister_id.0 != self.ledger.ledger_canister_id().get().to_vec() {
+            return Err(ApiError::invalid_request(
+                "The transaction does not call the ledger of this network",
+            ));
+        }
+        let (transfer, arg) = parse_transfer(update)?;
+        let transaction_identifier = transaction_identifier(&transfer, arg);
+
+        self.ledger.submit(envelope).await?;
+        Ok(TransactionIdentifierResponse::new(transaction_identifier))
+    }
+}
+
+fn signed_update(signed: &SignedTransaction) -> Result<&HttpCanisterUpdate, ApiError> {
+    match signed.envelopes.first() {
+        Some(HttpRequestEnvelope {
+            content: HttpCallContent::Call { update },
+            ..
+        }) => Ok(update),
+        None => Err(ApiError::invalid_request(
+            "The signed transaction has no envelopes",
+        )),
+    }
+}
+
+/// Decodes the `icrc1_transfer` call of a transaction.
+fn parse_transfer(update: &HttpCanisterUpdate) -> Result<(Transfer, TransferArg), ApiError> {
+    if update.method_name != TRANSFER_METHOD {
+        return Err(ApiError::invalid_request(format!(
+            "Unsupported method {}",
+            update.method_name
+        )));
+    }
+    let owner = PrincipalId::try_from(update.sender.0.as_slice())
+        .map_err(|e| ApiError::invalid_request(format!("Invalid sender: {}", e)))?;
+    let arg = Decode!(&update.arg.0, TransferArg).map_err(|e| {
+        ApiError::invalid_request(format!("Cannot decode the transfer argument: {}", e))
+    })?;
+    let to_u64 = |n: &Nat, what: &str| {
+        n.0.to_u64()
+            .ok_or_else(|| ApiError::invalid_request(format!("{} {} is too large", what, n)))
+    };
+    let transfer = Transfer {
+        from: Account {
+            owner,
+            subaccount: arg.from_subaccount,
+        },
+        to: arg.to,
+        amount: to_u64(&arg.amount, "Amount")?,
+        fee: arg.fee.as_ref().map(|fee| to_u64(fee, "Fee")).transpose()?,
+    };
+    Ok((transfer, arg))
+}
+
+/// Returns the identifier of the transaction the ledger records for a
+/// transfer, see `icrc1_transfer` in the ICRC-1 ledger.
+fn transaction_identifier(transfer: &Transfer, arg: TransferArg) -> TransactionIdentifier {
+    let tx = Transaction {
+        operation: LedgerOperation::Transfer {
+            from: transfer.from,
+            to: transfer.to,
+            amount: transfer.amount,
+            fee: transfer.fee,
+            spender: None,
+        },
+        created_at_time: arg.created_at_time,
+        memo: arg.memo,
+    };
+    TransactionIdentifier {
+        hash: from_hash(&tx.hash()),
+    }
+}
@@ -0,0 +1,305 @@
+use crate::ledger_client::LedgerAccess;
+use crate::request_handler::RosettaRequestHandler;
+use actix_rt::time::interval;
+use actix_web::{dev::ServerHandle, web, App, HttpResponse, HttpServer};
+use ic_rosetta_api::errors::{self, ApiError};
+use ic_rosetta_api::models::*;
+use lazy_static::lazy_static;
+use log::{error, info};
+use prometheus::{register_gauge, register_int_counter, Encoder, Gauge, IntCounter};
+use std::io;
+use std::time::{Duration, Instant};
+
+lazy_static! {
+    pub static ref SYNC_ERR_COUNTER: IntCounter = register_int_counter!(
+        "blockchain_sync_errors_total",
+        "Number of times synchronization failed"
+    )
+    .unwrap();
+    pub static ref OUT_OF_SYNC_TIME: Gauge = register_gauge!(
+        "ledger_sync_attempt_duration_seconds",
+        "Number of seconds since the last successful sync"
+    )
+    .unwrap();
+}
+
+type Handler<L> = web::Data<RosettaRequestHandler<L>>;
+
+async fn account_balance<L: LedgerAccess>(
+    msg: web::Json<AccountBalanceRequest>,
+    req_handler: Handler<L>,
+) -> HttpResponse {
+    to_rosetta_response(req_handler.account_balance(msg.into_inner()))
+}
+
+async fn block<L: LedgerAccess>(
+    msg: web::Json<BlockRequest>,
+    req_handler: Handler<L>,
+) -> HttpResponse {
+    to_rosetta_response(req_handler.block(msg.into_inner()))
+}
+
+async fn block_transaction<L: LedgerAccess>(
+    msg: web::Json<BlockTransactionRequest>,
+    req_handler: Handler<L>,
+) -> HttpResponse {
+    to_rosetta_response(req_handler.block_transaction(msg.into_inner()))
+}
+
+async fn construction_combine<L: LedgerAccess>(
+    msg: web::Json<ConstructionCombineRequest>,
+    req_handler: Handler<L>,
+) -> HttpResponse {
+    to_rosetta_response(req_handler.construction_combine(msg.into_inner()))
+}
+
+async fn construction_derive<L: LedgerAccess>(
+    msg: web::Json<ConstructionDeriveRequest>,
+    req_handler: Handler<L>,
+) -> HttpResponse {
+    to_rosetta_response(req_handler.construction_derive(msg.into_inner()))
+}
+
+async fn construction_hash<L: LedgerAccess>(
+    msg: web::Json<ConstructionHashRequest>,
+    req_handler: Handler<L>,
+) -> HttpResponse {
+    to_rosetta_response(req_handler.construction_hash(msg.into_inner()))
+}
+
+async fn construction_metadata<L: LedgerAccess>(
+    msg: web::Json<ConstructionMetadataRequest>,
+    req_handler: Handler<L>,
+) -> HttpResponse {
+    to_rosetta_response(req_handler.construction_metadata(msg.into_inner()).await)
+}
+
+async fn construction_parse<L: LedgerAccess>(
+    msg: web::Json<ConstructionParseRequest>,
+    req_handler: Handler<L>,
+) -> HttpResponse {
+    to_rosetta_response(req_handler.construction_parse(msg.into_inner()))
+}
+
+async fn construction_payloads<L: LedgerAccess>(
+    msg: web::Json<ConstructionPayloadsRequest>,
+    req_handler: Handler<L>,
+) -> HttpResponse {
+    to_rosetta_response(req_handler.construction_payloads(msg.into_inner()))
+}
+
+async fn construction_preprocess<L: LedgerAccess>(
+    msg: web::Json<ConstructionPreprocessRequest>,
+    req_handler: Handler<L>,
+) -> HttpResponse {
+    to_rosetta_response(req_handler.construction_preprocess(msg.into_inner()))
+}
+
+async fn construction_submit<L: LedgerAccess>(
+    msg: web::Json<ConstructionSubmitRequest>,
+    req_handler: Handler<L>,
+) -> HttpResponse {
+    to_rosetta_response(req_handler.construction_submit(msg.into_inner()).await)
+}
+
+async fn mempool<L: LedgerAccess>(
+    msg: web::Json<NetworkRequest>,
+    req_handler: Handler<L>,
+) -> HttpResponse {
+    to_rosetta_response(req_handler.mempool(msg.into_inner()))
+}
+
+async fn mempool_transaction<L: LedgerAccess>(
+    msg: web::Json<MempoolTransactionRequest>,
+    req_handler: Handler<L>,
+) -> HttpResponse {
+    to_rosetta_response(req_handler.mempool_transaction(msg.into_inner()))
+}
+
+async fn network_list<L: LedgerAccess>(
+    msg: web::Json<MetadataRequest>,
+    req_handler: Handler<L>,
+) -> HttpResponse {
+    to_rosetta_response(req_handler.network_list(msg.into_inner()))
+}
+
+async fn network_options<L: LedgerAccess>(
+    msg: web::Json<NetworkRequest>,
+    req_handler: Handler<L>,
+) -> HttpResponse {
+    to_rosetta_response(req_handler.network_options(msg.into_inner()))
+}
+
+async fn network_status<L: LedgerAccess>(
+    msg: web::Json<NetworkRequest>,
+    req_handler: Handler<L>,
+) -> HttpResponse {
+    to_rosetta_response(req_handler.network_status(msg.into_inner()))
+}
+
+async fn search_transactions<L: LedgerAccess>(
+    msg: web::Json<SearchTransactionsRequest>,
+    req_handler: Handler<L>,
+) -> HttpResponse {
+    to_rosetta_response(req_handler.search_transactions(msg.into_inner()))
+}
+
+async fn rosetta_metrics() -> HttpResponse {
+    let metrics = prometheus::gather();
+    let mut buffer = Vec::<u8>::new();
+    let encoder = prometheus::TextEncoder::new();
+    encoder.encode(&metrics, &mut buffer).unwrap();
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(String::from_utf8(buffer).unwrap())
+}
+
+fn to_rosetta_response<S: serde::Serialize>(result: Result<S, ApiError>) -> HttpResponse {
+    match result {
+        Ok(x) => match serde_json::to_string(&x) {
+            Ok(resp) => HttpResponse::Ok()
+                .content_type("application/json")
+                .body(resp),
+            Err(_) => HttpResponse::InternalServerError()
+                .content_type("application/json")
+                .body(Error::serialization_error_json_str()),
+        },
+        Err(err) => {
+            let err = errors::convert_to_error(&err);
+            match serde_json::to_string(&err) {
+                Ok(resp) => HttpResponse::InternalServerError()
+                    .content_type("application/json")
+                    .body(resp),
+                Err(_) => HttpResponse::InternalServerError()
+                    .content_type("application/json")
+                    .body(Error::serialization_error_json_str()),
+            }
+        }
+    }
+}
+
+pub struct RosettaApiServer<L> {
+    req_handler: RosettaRequestHandler<L>,
+    server: actix_web::dev::Server,
+    server_handle: ServerHandle,
+}
+
+impl<L: LedgerAccess + Send + Sync + 'static> RosettaApiServer<L> {
+    pub fn new(
+        req_handler: RosettaRequestHandler<L>,
+        addr: String,
+        expose_metrics: bool,
+    ) -> io::Result<Self> {
+        let handler = req_handler.clone();
+        let server = HttpServer::new(move || {
+            let app = App::new()
+                .app_data(web::Data::new(
+                    web::JsonConfig::default()
+                        .limit(4 * 1024 * 1024)
+                        .error_handler(move |e, _| {
+                            errors::convert_to_error(&ApiError::invalid_request(format!(
+                                "{:#?}",
+                                e
+                            )))
+                            .into()
+                        }),
+                ))
+                .app_data(web::Data::new(handler.clone()))
+                .route("/account/balance", web::post().to(account_balance::<L>))
+                .route("/block", web::post().to(block::<L>))
+                .route("/block/transaction", web::post().to(block_transaction::<L>))
+                .route(
+                    "/construction/combine",
+                    web::post().to(construction_combine::<L>),
+                )
+                .route(
+                    "/construction/derive",
+                    web::post().to(construction_derive::<L>),
+                )
+                .route("/construction/hash", web::post().to(construction_hash::<L>))
+                .route(
+                    "/construction/metadata",
+                    web::post().to(construction_metadata::<L>),
+                )
+                .route(
+                    "/construction/parse",
+                    web::post().to(construction_parse::<L>),
+                )
+                .route(
+                    "/construction/payloads",
+                    web::post().to(construction_payloads::<L>),
+                )
+                .route(
+                    "/construction/preprocess",
+                    web::post().to(construction_preprocess::<L>),
+                )
+                .route(
+                    "/construction/submit",
+                    web::post().to(construction_submit::<L>),
+                )
+                .route("/mempool", web::post().to(mempool::<L>))
+                .route(
+                    "/mempool/transaction",
+                    web::post().to(mempool_transaction::<L>),
+                )
+                .route("/network/list", web::post().to(network_list::<L>))
+                .route("/network/options", web::post().to(network_options::<L>))
+                .route("/network/status", web::post().to(network_status::<L>))
+                .route(
+                    "/search/transactions",
+                    web::post().to(search_transactions::<L>),
+                );
+            if expose_metrics {
+                app.route("/metrics", web::get().to(rosetta_metrics))
+            } else {
+                app
+            }
+        })
+        .bind(addr)?
+        .run();
+
+        Ok(Self {
+            req_handler,
+            server_handle: server.handle(),
+            server,
+        })
+    }
+
+    /// Serves requests and synchronizes the blocks of the ledger every second
+    /// until the server is stopped. If `exit_on_sync` is set, the server stops
+    /// after the first synchronization.
+    pub async fn run(self, exit_on_sync: bool) -> io::Result<()> {
+        info!("Starting Rosetta API server");
+        let req_handler = self.req_handler;
+        let server_handle = self.server_handle;
+        // The ledger futures are not Send, the sync loop runs on the local
+        // task set of the actix system.
+        actix_rt::spawn(async move {
+            let mut interval = interval(Duration::from_secs(1));
+            let mut synced_at = Instant::now();
+            loop {
+                interval.tick().await;
+
+                match req_handler.sync_blocks().await {
+                    Ok(()) => {
+                        synced_at = Instant::now();
+                        OUT_OF_SYNC_TIME.set(0.0);
+                    }
+                    Err(err) => {
+                        error!("Error in syncing blocks: {:?}", err);
+                        SYNC_ERR_COUNTER.inc();
+                        OUT_OF_SYNC_TIME.set(synced_at.elapsed().as_secs_f64());
+                    }
+                }
+
+                if exit_on_sync {
+                    info!("Blockchain synced, exiting");
+                    server_handle.stop(true).await;
+                    break;
+                }
+            }
+            info!("Blockchain sync loop finished");
+        });
+        self.server.await
+    }
+}
@@ -0,0 +1,183 @@
+use crate::blocks::Blocks;
+use crate::ledger_client::LedgerAccess;
+use candid::Nat;
+use ic_certification::verify_certified_data;
+use ic_crypto_tree_hash::{Label, MixedHashTree};
+use ic_icrc1::blocks::Icrc1Block;
+use ic_icrc1::endpoints::ArchivedRange;
+use ic_icrc1_agent::{GetBlocksArgs, GetBlocksResponse, QueryBlockArchiveFn};
+use ic_ledger_core::block::{EncodedBlock, HashOf};
+use ic_rosetta_api::errors::ApiError;
+use ic_types::crypto::threshold_sig::ThresholdSigPublicKey;
+use log::{debug, info};
+use num_traits::ToPrimitive;
+use std::sync::RwLock;
+
+/// The maximum number of blocks requested from the ledger or an archive in
+/// a single call.
+const MAX_BLOCKS_PER_REQUEST: u64 = 2_000;
+
+fn get_blocks_args(start: u64, length: u64) -> GetBlocksArgs {
+    GetBlocksArgs {
+        start: Nat::from(start),
+        length: Nat::from(length),
+    }
+}
+
+fn to_u64(n: &Nat, what: &str) -> Result<u64, ApiError> {
+    n.0.to_u64()
+        .ok_or_else(|| ApiError::internal_error(format!("{} {} does not fit into u64", what, n)))
+}
+
+/// Returns the data the ledger certifies when the last block of the chain
+/// has the given hash, see `Ledger::root_hash` in the ICRC-1 ledger.
+fn certified_data(tip_hash: &HashOf<EncodedBlock>) -> [u8; 32] {
+    MixedHashTree::Labeled(
+        Label::from("tip_hash"),
+        Box::new(MixedHashTree::Leaf(tip_hash.as_slice().to_vec())),
+    )
+    .digest()
+    .0
+}
+
+/// Downloads the blocks the store is missing and marks them as verified once
+/// the hash of the last one matches the tip certified by the ledger.
+pub async fn sync_blocks<L: LedgerAccess + ?Sized>(
+    ledger: &L,
+    blocks: &RwLock<Blocks>,
+    root_key: &ThresholdSigPublicKey,
+) -> Result<(), ApiError> {
+    let start = blocks.read().unwrap().len();
+    let tip = ledger
+        .get_blocks(get_blocks_args(start, MAX_BLOCKS_PER_REQUEST))
+        .await?;
+    let chain_length = tip.chain_length;
+    if chain_length < start {
+        return Err(ApiError::internal_error(format!(
+            "The ledger has {} blocks but {} blocks were already downloaded",
+            chain_length, start
+        )));
+    }
+    let certificate = tip.certificate.clone();
+
+    // Blocks appended after the first response are not covered by its
+    // certificate, we only download the chain up to `chain_length` and pick
+    // the remaining blocks at the next synchronization.
+    let mut response = Some(tip);
+    while blocks.read().unwrap().len() < chain_length {
+        let downloaded = blocks.read().unwrap().len();
+        let GetBlocksResponse {
+            first_index,
+            blocks: ledger_blocks,
+            mut archived_blocks,
+            ..
+        } = match response.take() {
+            Some(response) => response,
+            None => {
+                ledger
+                    .get_blocks(get_blocks_args(downloaded, MAX_BLOCKS_PER_REQUEST))
+                    .await?
+            }
+        };
+
+        archived_blocks.sort_by(|a, b| a.start.cmp(&b.start));
+        for range in archived_blocks {
+            fetch_archived_range(ledger, blocks, range, chain_length).await?;
+        }
+        append_blocks(
+            blocks,
+            to_u64(&first_index, "block index")?,
+            ledger_blocks,
+            chain_length,
+        )?;
+
+        if blocks.read().unwrap().len() == downloaded {
+            return Err(ApiError::internal_error(format!(
+                "The ledger returned no blocks starting from {}",
+                downloaded
+            )));
+        }
+    }
+
+    let mut blocks = blocks.write().unwrap();
+    if blocks.verified_len() == chain_length {
+        return Ok(());
+    }
+    let tip_hash = blocks
+        .last_hash()
+        .expect("bug: the chain cannot be empty at this point");
+    let certificate = certificate
+        .ok_or_else(|| ApiError::internal_error("The ledger did not return a data certificate"))?;
+    verify_certified_data(
+        certificate.as_slice(),
+        &ledger.ledger_canister_id(),
+        root_key,
+        &certified_data(&tip_hash),
+    )
+    .map_err(|e| {
+        ApiError::internal_error(format!(
+            "Failed to verify the tip of the chain at block {}: {:?}",
+            chain_length - 1,
+            e
+        ))
+    })?;
+    blocks.set_verified_len(chain_length);
+    info!("Synced up to block {}", chain_length - 1);
+    Ok(())
+}
+
+async fn fetch_archived_range<L: LedgerAccess + ?Sized>(
+    ledger: &L,
+    blocks: &RwLock<Blocks>,
+    range: ArchivedRange<QueryBlockArchiveFn>,
+    chain_length: u64,
+) -> Result<(), ApiError> {
+    let mut start = to_u64(&range.start, "archived range start")?;
+    let end = start + to_u64(&range.length, "archived range length")?;
+    debug!(
+        "Fetching blocks [{}, {}) from archive {}",
+        start, end, range.callback.canister_id
+    );
+    while start < end {
+        let length = (end - start).min(MAX_BLOCKS_PER_REQUEST);
+        let fetched = ledger
+            .get_blocks_from_archive(&range.callback, get_blocks_args(start, length))
+            .await?
+            .blocks;
+        if fetched.is_empty() {
+            return Err(ApiError::internal_error(format!(
+                "Archive {} returned no blocks starting from {}",
+                range.callback.canister_id, start
+            )));
+        }
+        start += append_blocks(blocks, start, fetched, chain_length)?;
+    }
+    Ok(())
+}
+
+/// Appends blocks starting at index `first_index` to the store, ignoring the
+/// blocks at or past `chain_length`. Returns the number of blocks received.
+fn append_blocks(
+    blocks: &RwLock<Blocks>,
+    first_index: u64,
+    new_blocks: Vec<Icrc1Block>,
+    chain_length: u64,
+) -> Result<u64, ApiError> {
+    let received = new_blocks.len() as u64;
+    let mut blocks = blocks.write().unwrap();
+    if received > 0 && first_index != blocks.len() {
+        return Err(ApiError::internal_error(format!(
+            "Expected blocks starting from {}, got blocks starting from {}",
+            blocks.len(),
+            first_index
+        )));
+    }
+    for block in new_blocks {
+        if blocks.len() >= chain_length {
+            break;
+        }
+        let encoded = block.encode().map_err(ApiError::internal_error)?;
+        blocks.push(encoded).map_err(ApiError::internal_error)?;
+    }
+    Ok(received)
+}
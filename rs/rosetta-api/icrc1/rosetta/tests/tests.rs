@@ -0,0 +1,436 @@
+use async_trait::async_trait;
+use candid::{CandidType, Decode, Encode, Nat};
+use futures::executor::block_on;
+use ic_canister_client_sender::Ed25519KeyPair as EdKeypair;
+use ic_icrc1::endpoints::{TransferArg, TransferError};
+use ic_icrc1::Account;
+use ic_icrc1_agent::{BlockRange, GetBlocksArgs, GetBlocksResponse, QueryBlockArchiveFn};
+use ic_icrc1_ledger::{InitArgs as LedgerInitArgs, LedgerArgument};
+use ic_icrc1_rosetta::convert::to_model_account_identifier;
+use ic_icrc1_rosetta::ledger_client::LedgerAccess;
+use ic_icrc1_rosetta::request_handler::RosettaRequestHandler;
+use ic_ledger_canister_core::archive::ArchiveOptions;
+use ic_rosetta_api::errors::ApiError;
+use ic_rosetta_api::models::amount::Amount;
+use ic_rosetta_api::models::operation::{Operation, OperationType};
+use ic_rosetta_api::models::{
+    AccountBalanceRequest, BlockRequest, ConstructionCombineRequest, ConstructionHashRequest,
+    ConstructionParseRequest, ConstructionPayloadsRequest, ConstructionPayloadsRequestMetadata,
+    ConstructionSubmitRequest, NetworkRequest, PartialBlockIdentifier, PublicKey,
+    RosettaSupportedKeyPair, SearchTransactionsRequest, Signature,
+};
+use ic_rosetta_api::DEFAULT_BLOCKCHAIN;
+use ic_state_machine_tests::{CanisterId, PrincipalId, StateMachine, ThresholdSigPublicKey};
+use ic_types::messages::{HttpCallContent, HttpRequestEnvelope};
+use num_traits::ToPrimitive;
+use serde::de::DeserializeOwned;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+const FEE: u64 = 10_000;
+const ARCHIVE_TRIGGER_THRESHOLD: usize = 10;
+const NUM_BLOCKS_TO_ARCHIVE: usize = 5;
+const TOKEN_SYMBOL: &str = "XTST";
+
+const MINTER: Account = Account {
+    owner: PrincipalId::new(0, [0u8; 29]),
+    subaccount: None,
+};
+
+fn ledger_wasm() -> Vec<u8> {
+    ic_test_utilities_load_wasm::load_wasm(
+        PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap())
+            .parent()
+            .unwrap()
+            .join("ledger"),
+        "ic-icrc1-ledger",
+        &[],
+    )
+}
+
+fn account(owner: u64, subaccount: Option<u8>) -> Account {
+    Account {
+        owner: PrincipalId::new_user_test_id(owner),
+        subaccount: subaccount.map(|n| [n; 32]),
+    }
+}
+
+fn install_ledger(env: &StateMachine, initial_balances: Vec<(Account, u64)>) -> CanisterId {
+    let args = LedgerArgument::Init(LedgerInitArgs {
+        minting_account: MINTER,
+        initial_balances,
+        transfer_fee: FEE,
+        token_name: "Test Token".to_string(),
+        token_symbol: TOKEN_SYMBOL.to_string(),
+        metadata: vec![],
+        archive_options: ArchiveOptions {
+            trigger_threshold: ARCHIVE_TRIGGER_THRESHOLD,
+            num_blocks_to_archive: NUM_BLOCKS_TO_ARCHIVE,
+            node_max_memory_size_bytes: None,
+            max_message_size_bytes: None,
+            controller_id: PrincipalId::new_user_test_id(100),
+            cycles_for_archive_creation: None,
+            max_transactions_per_response: None,
+        },
+    });
+    env.install_canister(ledger_wasm(), Encode!(&args).unwrap(), None)
+        .unwrap()
+}
+
+fn transfer(env: &StateMachine, ledger: CanisterId, from: Account, to: Account, amount: u64) {
+    let arg = TransferArg {
+        from_subaccount: from.subaccount,
+        to,
+        fee: None,
+        created_at_time: None,
+        memo: None,
+        amount: Nat::from(amount),
+    };
+    Decode!(
+        &env.execute_ingress_as(from.owner, ledger, "icrc1_transfer", Encode!(&arg).unwrap())
+            .expect("failed to transfer funds")
+            .bytes(),
+        Result<Nat, TransferError>
+    )
+    .expect("failed to decode transfer response")
+    .expect("transfer failed");
+}
+
+fn balance_of(env: &StateMachine, ledger: CanisterId, account: Account) -> u64 {
+    let balance = Decode!(
+        &env.query(ledger, "icrc1_balance_of", Encode!(&account).unwrap())
+            .expect("failed to query balance")
+            .bytes(),
+        Nat
+    )
+    .expect("failed to decode balance_of response");
+    balance.0.to_u64().unwrap()
+}
+
+/// A ledger client backed by a state machine. Submitted envelopes are
+/// executed as ingress messages from the principal of their public key.
+struct StateMachineLedger {
+    env: StateMachine,
+    canister_id: CanisterId,
+}
+
+impl StateMachineLedger {
+    fn query<T: CandidType + DeserializeOwned>(
+        &self,
+        canister_id: CanisterId,
+        method: &str,
+        arg: Vec<u8>,
+    ) -> Result<T, ApiError> {
+        let result = self
+            .env
+            .query(canister_id, method, arg)
+            .map_err(|e| ApiError::internal_error(format!("{:?}", e)))?;
+        Decode!(&result.bytes(), T).map_err(|e| ApiError::internal_error(format!("{}", e)))
+    }
+}
+
+#[async_trait(?Send)]
+impl LedgerAccess for StateMachineLedger {
+    fn ledger_canister_id(&self) -> CanisterId {
+        self.canister_id
+    }
+
+    async fn get_blocks(&self, args: GetBlocksArgs) -> Result<GetBlocksResponse, ApiError> {
+        self.query(self.canister_id, "get_blocks", Encode!(&args).unwrap())
+    }
+
+    async fn get_blocks_from_archive(
+        &self,
+        callback: &QueryBlockArchiveFn,
+        args: GetBlocksArgs,
+    ) -> Result<BlockRange, ApiError> {
+        self.query(
+            callback.canister_id,
+            &callback.method,
+            Encode!(&args).unwrap(),
+        )
+    }
+
+    async fn symbol(&self) -> Result<String, ApiError> {
+        self.query(self.canister_id, "icrc1_symbol", Encode!().unwrap())
+    }
+
+    async fn decimals(&self) -> Result<u8, ApiError> {
+        self.query(self.canister_id, "icrc1_decimals", Encode!().unwrap())
+    }
+
+    async fn fee(&self) -> Result<u64, ApiError> {
+        let fee: Nat = self.query(self.canister_id, "icrc1_fee", Encode!().unwrap())?;
+        Ok(fee.0.to_u64().unwrap())
+    }
+
+    async fn submit(&self, envelope: HttpRequestEnvelope<HttpCallContent>) -> Result<(), ApiError> {
+        let HttpCallContent::Call { update } = envelope.content;
+        let sender = PrincipalId::try_from(update.sender.0.as_slice()).unwrap();
+        let pubkey = envelope.sender_pubkey.expect("unsigned envelope");
+        assert_eq!(sender, PrincipalId::new_self_authenticating(&pubkey.0));
+        self.env
+            .execute_ingress_as(
+                sender,
+                CanisterId::new(PrincipalId::try_from(update.canister_id.0.as_slice()).unwrap())
+                    .unwrap(),
+                update.method_name,
+                update.arg.0,
+            )
+            .map(|_| ())
+            .map_err(|e| ApiError::internal_error(format!("{:?}", e)))
+    }
+}
+
+fn setup(
+    initial_balances: Vec<(Account, u64)>,
+) -> (Arc<StateMachineLedger>, ThresholdSigPublicKey) {
+    let env = StateMachine::new();
+    let canister_id = install_ledger(&env, initial_balances);
+    let root_key = env.root_key();
+    (Arc::new(StateMachineLedger { env, canister_id }), root_key)
+}
+
+fn handler(
+    ledger: Arc<StateMachineLedger>,
+    root_key: ThresholdSigPublicKey,
+) -> RosettaRequestHandler<StateMachineLedger> {
+    block_on(RosettaRequestHandler::new(
+        DEFAULT_BLOCKCHAIN.to_string(),
+        ledger,
+        root_key,
+    ))
+    .expect("failed to create the request handler")
+}
+
+fn account_balance(
+    req_handler: &RosettaRequestHandler<StateMachineLedger>,
+    account: Account,
+) -> u64 {
+    let response = req_handler
+        .account_balance(AccountBalanceRequest {
+            network_identifier: req_handler.network_id(),
+            account_identifier: to_model_account_identifier(&account),
+            block_identifier: None,
+            metadata: None,
+        })
+        .expect("failed to get the account balance");
+    response.balances[0].value.parse().unwrap()
+}
+
+#[test]
+fn test_sync_with_archives() {
+    let accounts: Vec<Account> = (1..4)
+        .flat_map(|owner| [account(owner, None), account(owner, Some(owner as u8))])
+        .collect();
+    let (ledger, root_key) = setup(
+        accounts
+            .iter()
+            .map(|account| (*account, 1_000_000_000))
+            .collect(),
+    );
+    for (i, from) in accounts.iter().enumerate() {
+        let to = accounts[(i + 1) % accounts.len()];
+        transfer(
+            &ledger.env,
+            ledger.canister_id,
+            *from,
+            to,
+            1_000 * (i as u64 + 1),
+        );
+        transfer(&ledger.env, ledger.canister_id, *from, MINTER, 10_000);
+    }
+
+    let req_handler = handler(ledger.clone(), root_key);
+    block_on(req_handler.sync_blocks()).expect("failed to sync blocks");
+
+    let chain_length = (3 * accounts.len()) as i64;
+    let status = req_handler
+        .network_status(NetworkRequest::new(req_handler.network_id()))
+        .unwrap();
+    assert_eq!(status.current_block_identifier.index, chain_length - 1);
+    assert_eq!(status.genesis_block_identifier.index, 0);
+
+    for account in accounts.iter().chain(std::iter::once(&MINTER)) {
+        assert_eq!(
+            account_balance(&req_handler, *account),
+            balance_of(&ledger.env, ledger.canister_id, *account),
+            "balance mismatch for account {}",
+            account
+        );
+    }
+
+    let genesis = req_handler
+        .block(BlockRequest::new(
+            req_handler.network_id(),
+            PartialBlockIdentifier {
+                index: Some(0),
+                hash: None,
+            },
+        ))
+        .unwrap()
+        .block
+        .unwrap();
+    assert_eq!(genesis.block_identifier, genesis.parent_block_identifier);
+    assert_eq!(
+        genesis.transactions[0].operations[0]._type,
+        OperationType::Mint
+    );
+
+    let history = req_handler
+        .search_transactions(SearchTransactionsRequest {
+            account_identifier: Some(to_model_account_identifier(&accounts[0])),
+            limit: Some(2),
+            ..SearchTransactionsRequest::new(req_handler.network_id(), None, None)
+        })
+        .unwrap();
+    // The initial mint, the outgoing transfer, the burn and the incoming
+    // transfer from the last account.
+    assert_eq!(history.total_count, 4);
+    assert_eq!(history.transactions.len(), 2);
+    assert_eq!(history.next_offset, Some(2));
+    assert!(
+        history.transactions[0].block_identifier.index
+            > history.transactions[1].block_identifier.index
+    );
+
+    // New blocks are picked up by the next synchronization.
+    transfer(&ledger.env, ledger.canister_id, accounts[0], accounts[1], 1);
+    block_on(req_handler.sync_blocks()).expect("failed to sync blocks");
+    let status = req_handler
+        .network_status(NetworkRequest::new(req_handler.network_id()))
+        .unwrap();
+    assert_eq!(status.current_block_identifier.index, chain_length);
+    assert_eq!(
+        account_balance(&req_handler, accounts[1]),
+        balance_of(&ledger.env, ledger.canister_id, accounts[1])
+    );
+}
+
+#[test]
+fn test_sync_rejects_wrong_root_key() {
+    let (ledger, _) = setup(vec![(account(1, None), 1_000_000)]);
+    let other_root_key = StateMachine::new().root_key();
+
+    let req_handler = handler(ledger, other_root_key);
+    assert!(block_on(req_handler.sync_blocks()).is_err());
+    assert!(req_handler
+        .network_status(NetworkRequest::new(req_handler.network_id()))
+        .is_err());
+}
+
+#[test]
+fn test_construction_transfer() {
+    let keypair = EdKeypair::generate_from_u64(1);
+    let owner = keypair.generate_principal_id().unwrap();
+    let from = Account {
+        owner,
+        subaccount: Some([1; 32]),
+    };
+    let to = account(2, None);
+
+    let (ledger, root_key) = setup(vec![(from, 1_000_000)]);
+    // Envelopes and created_at_time are based on the wall clock.
+    ledger.env.set_time(SystemTime::now());
+    let req_handler = handler(ledger.clone(), root_key);
+    let network_identifier = req_handler.network_id();
+    let currency = req_handler.currency().clone();
+
+    let operation = |index: i64, op_type, account: &Account, value: i64| {
+        Operation::new(
+            index,
+            op_type,
+            None,
+            Some(to_model_account_identifier(account)),
+            Some(Amount::new(value.to_string(), currency.clone())),
+            None,
+        )
+    };
+    let operations = vec![
+        operation(0, OperationType::Transaction, &from, -100_000),
+        operation(1, OperationType::Transaction, &to, 100_000),
+        operation(2, OperationType::Fee, &from, -(FEE as i64)),
+    ];
+    let public_key = PublicKey::new(keypair.hex_encode_pk(), keypair.get_curve_type());
+
+    let payloads = req_handler
+        .construction_payloads(ConstructionPayloadsRequest {
+            network_identifier: network_identifier.clone(),
+            operations: operations.clone(),
+            metadata: Some(ConstructionPayloadsRequestMetadata {
+                memo: Some(7),
+                ..Default::default()
+            }),
+            public_keys: Some(vec![public_key.clone()]),
+        })
+        .expect("failed to construct payloads");
+
+    let parsed = req_handler
+        .construction_parse(ConstructionParseRequest::new(
+            network_identifier.clone(),
+            false,
+            payloads.unsigned_transaction.clone(),
+        ))
+        .unwrap();
+    assert_eq!(parsed.operations.len(), operations.len());
+    for (parsed, expected) in parsed.operations.iter().zip(operations.iter()) {
+        assert_eq!(parsed._type, expected._type);
+        assert_eq!(parsed.account, expected.account);
+        assert_eq!(parsed.amount, expected.amount);
+    }
+
+    let signatures = payloads
+        .payloads
+        .iter()
+        .map(|payload| Signature {
+            signing_payload: payload.clone(),
+            public_key: public_key.clone(),
+            signature_type: payload.signature_type.unwrap(),
+            hex_bytes: hex::encode(keypair.sign(&hex::decode(&payload.hex_bytes).unwrap())),
+        })
+        .collect();
+    let signed = req_handler
+        .construction_combine(ConstructionCombineRequest {
+            network_identifier: network_identifier.clone(),
+            unsigned_transaction: payloads.unsigned_transaction,
+            signatures,
+        })
+        .expect("failed to combine signatures");
+
+    let hash = req_handler
+        .construction_hash(ConstructionHashRequest {
+            network_identifier: network_identifier.clone(),
+            signed_transaction: signed.signed_transaction.clone(),
+        })
+        .unwrap();
+    let submitted = block_on(req_handler.construction_submit(ConstructionSubmitRequest {
+        network_identifier: network_identifier.clone(),
+        signed_transaction: signed.signed_transaction,
+    }))
+    .expect("failed to submit the transaction");
+    assert_eq!(
+        hash.transaction_identifier,
+        submitted.transaction_identifier
+    );
+
+    block_on(req_handler.sync_blocks()).expect("failed to sync blocks");
+    let found = req_handler
+        .search_transactions(SearchTransactionsRequest {
+            transaction_identifier: Some(submitted.transaction_identifier.clone()),
+            ..SearchTransactionsRequest::new(network_identifier, None, None)
+        })
+        .unwrap();
+    assert_eq!(found.total_count, 1);
+    assert_eq!(found.transactions[0].block_identifier.index, 1);
+    assert_eq!(
+        found.transactions[0].transaction.metadata.as_ref().unwrap()["memo"],
+        hex::encode(7u64.to_be_bytes())
+    );
+
+    assert_eq!(
+        account_balance(&req_handler, from),
+        1_000_000 - 100_000 - FEE
+    );
+    assert_eq!(account_balance(&req_handler, to), 100_000);
+}
@@ -7,6 +7,9 @@ use serde::Deserialize;
 use serde_bytes::ByteBuf;
 use std::collections::BTreeMap;
 
+// See https://www.rfc-editor.org/rfc/rfc8949.html#name-self-described-cbor
+const SELF_DESCRIBE_CBOR_TAG: u64 = 55799;
+
 /// Variant type for the block endpoint values.
 #[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub enum BlockValue {
@@ -25,6 +28,18 @@ impl BlockValue {
             .expect("bug: cannot compute hash of a valid block")
     }
 
+    /// Returns the CBOR encoding of the block, as stored by the Ledger.
+    ///
+    /// The encoding is not guaranteed to be byte-to-byte identical to the
+    /// Ledger's one, but it decodes to the same block and has the same hash.
+    pub fn encode(&self) -> Result<EncodedBlock, String> {
+        let value = Value::Tag(SELF_DESCRIBE_CBOR_TAG, Box::new(self.to_cbor()?));
+        let mut bytes = vec![];
+        ciborium::ser::into_writer(&value, &mut bytes)
+            .map_err(|e| format!("failed to encode block: {}", e))?;
+        Ok(EncodedBlock::from_vec(bytes))
+    }
+
     fn to_cbor(&self) -> Result<Value, String> {
         match self {
             Icrc1Block::Int(int) => {
@@ -77,8 +92,13 @@ impl From<&EncodedBlock> for Icrc1Block {
 fn icrc1_block_from_value(value: &Value) -> Icrc1Block {
     match value {
         Value::Integer(int) => {
+            // Blocks only contain non-negative integers (amounts, fees and
+            // timestamps), which the generic block schema encodes as Nat.
             let v: i128 = (*int).into();
-            BlockValue::Int(Int::from(v))
+            match u128::try_from(v) {
+                Ok(n) => BlockValue::Nat(Nat::from(n)),
+                Err(_) => BlockValue::Int(Int::from(v)),
+            }
         }
         Value::Bytes(bytes) => BlockValue::Blob(ByteBuf::from(bytes.to_vec())),
         Value::Text(text) => BlockValue::Text(text.to_string()),
@@ -108,3 +128,48 @@ fn icrc1_block_from_value(value: &Value) -> Icrc1Block {
         _ => panic!("unsupported value type: {:?}", value),
     }
 }
+
+#[test]
+fn encode_roundtrip() {
+    use crate::{Account, Block, Memo, Transaction};
+    use ic_base_types::PrincipalId;
+    use ic_ledger_core::block::{BlockType, HashOf};
+    use ic_ledger_core::{timestamp::TimeStamp, tokens::Tokens};
+
+    let from = Account {
+        owner: PrincipalId::new_user_test_id(1),
+        subaccount: Some([1; 32]),
+    };
+    let to = Account::from(PrincipalId::new_user_test_id(2));
+    let block = Block::from_transaction(
+        Some(HashOf::new([7; 32])),
+        Transaction::transfer(
+            from,
+            to,
+            Tokens::from_e8s(1_000_000),
+            None,
+            Some(TimeStamp::from_nanos_since_unix_epoch(1_000)),
+            Some(Memo::from(42)),
+        ),
+        TimeStamp::from_nanos_since_unix_epoch(2_000),
+        Tokens::from_e8s(10_000),
+    );
+    let encoded = block.clone().encode();
+    let value = Icrc1Block::from(&encoded);
+    let reencoded = value.encode().expect("failed to encode block value");
+
+    assert_eq!(Block::decode(reencoded.clone()).unwrap(), block);
+    assert_eq!(Block::block_hash(&reencoded), Block::block_hash(&encoded));
+    assert_eq!(value.hash(), Block::block_hash(&encoded).into_bytes());
+
+    match &value {
+        Icrc1Block::Map(block) => match &block["tx"] {
+            Icrc1Block::Map(tx) => {
+                assert_eq!(tx["amt"], Icrc1Block::Nat(Nat::from(1_000_000u64)));
+                assert_eq!(tx["ts"], Icrc1Block::Nat(Nat::from(1_000u64)));
+            }
+            tx => panic!("expected a Map transaction, got {:?}", tx),
+        },
+        block => panic!("expected a Map block, got {:?}", block),
+    }
+}
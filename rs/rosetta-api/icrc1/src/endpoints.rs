@@ -2,7 +2,7 @@ use crate::blocks::Icrc1Block;
 use crate::{Account, Block, Memo, Subaccount};
 use candid::types::number::{Int, Nat};
 use candid::CandidType;
-use ic_base_types::CanisterId;
+use ic_base_types::{CanisterId, PrincipalId};
 use ic_ledger_canister_core::ledger::TransferError as CoreTransferError;
 use serde::Deserialize;
 use serde_bytes::ByteBuf;
@@ -44,8 +44,12 @@ impl From<CoreTransferError> for TransferError {
             LTE::TxDuplicate { duplicate_of } => TE::Duplicate {
                 duplicate_of: Nat::from(duplicate_of),
             },
-            LTE::InsufficientAllowance { .. } => todo!(),
-            LTE::ExpiredApproval { .. } => todo!(),
+            LTE::InsufficientAllowance { .. } => {
+                unreachable!("icrc1_transfer does not use allowances")
+            }
+            LTE::ExpiredApproval { .. } => {
+                unreachable!("icrc1_transfer does not create approvals")
+            }
         }
     }
 }
@@ -64,6 +68,131 @@ pub struct TransferArg {
     pub amount: NumTokens,
 }
 
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ApproveArgs {
+    #[serde(default)]
+    pub from_subaccount: Option<Subaccount>,
+    pub spender: PrincipalId,
+    pub amount: NumTokens,
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+    #[serde(default)]
+    pub fee: Option<NumTokens>,
+    #[serde(default)]
+    pub memo: Option<Memo>,
+    #[serde(default)]
+    pub created_at_time: Option<u64>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ApproveError {
+    BadFee { expected_fee: NumTokens },
+    InsufficientFunds { balance: NumTokens },
+    Expired { ledger_time: u64 },
+    TooOld,
+    CreatedInFuture { ledger_time: u64 },
+    Duplicate { duplicate_of: BlockIndex },
+    TemporarilyUnavailable,
+    GenericError { error_code: Nat, message: String },
+}
+
+impl From<CoreTransferError> for ApproveError {
+    fn from(err: CoreTransferError) -> Self {
+        use ic_ledger_canister_core::ledger::TransferError as LTE;
+        use ApproveError as AE;
+
+        match err {
+            LTE::BadFee { expected_fee } => AE::BadFee {
+                expected_fee: Nat::from(expected_fee.get_e8s()),
+            },
+            LTE::InsufficientFunds { balance } => AE::InsufficientFunds {
+                balance: Nat::from(balance.get_e8s()),
+            },
+            LTE::TxTooOld { .. } => AE::TooOld,
+            LTE::TxCreatedInFuture { ledger_time } => AE::CreatedInFuture {
+                ledger_time: ledger_time.as_nanos_since_unix_epoch(),
+            },
+            LTE::TxThrottled => AE::TemporarilyUnavailable,
+            LTE::TxDuplicate { duplicate_of } => AE::Duplicate {
+                duplicate_of: Nat::from(duplicate_of),
+            },
+            LTE::ExpiredApproval { ledger_time } => AE::Expired {
+                ledger_time: ledger_time.as_nanos_since_unix_epoch(),
+            },
+            LTE::InsufficientAllowance { .. } => {
+                unreachable!("icrc2_approve does not use allowances")
+            }
+        }
+    }
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TransferFromArgs {
+    pub from: Account,
+    pub to: Account,
+    pub amount: NumTokens,
+    #[serde(default)]
+    pub fee: Option<NumTokens>,
+    #[serde(default)]
+    pub memo: Option<Memo>,
+    #[serde(default)]
+    pub created_at_time: Option<u64>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum TransferFromError {
+    BadFee { expected_fee: NumTokens },
+    InsufficientFunds { balance: NumTokens },
+    InsufficientAllowance { allowance: NumTokens },
+    TooOld,
+    CreatedInFuture { ledger_time: u64 },
+    Duplicate { duplicate_of: BlockIndex },
+    TemporarilyUnavailable,
+    GenericError { error_code: Nat, message: String },
+}
+
+impl From<CoreTransferError> for TransferFromError {
+    fn from(err: CoreTransferError) -> Self {
+        use ic_ledger_canister_core::ledger::TransferError as LTE;
+        use TransferFromError as TFE;
+
+        match err {
+            LTE::BadFee { expected_fee } => TFE::BadFee {
+                expected_fee: Nat::from(expected_fee.get_e8s()),
+            },
+            LTE::InsufficientFunds { balance } => TFE::InsufficientFunds {
+                balance: Nat::from(balance.get_e8s()),
+            },
+            LTE::InsufficientAllowance { allowance } => TFE::InsufficientAllowance {
+                allowance: Nat::from(allowance.get_e8s()),
+            },
+            LTE::TxTooOld { .. } => TFE::TooOld,
+            LTE::TxCreatedInFuture { ledger_time } => TFE::CreatedInFuture {
+                ledger_time: ledger_time.as_nanos_since_unix_epoch(),
+            },
+            LTE::TxThrottled => TFE::TemporarilyUnavailable,
+            LTE::TxDuplicate { duplicate_of } => TFE::Duplicate {
+                duplicate_of: Nat::from(duplicate_of),
+            },
+            LTE::ExpiredApproval { .. } => {
+                unreachable!("icrc2_transfer_from does not create approvals")
+            }
+        }
+    }
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AllowanceArgs {
+    pub account: Account,
+    pub spender: PrincipalId,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Allowance {
+    pub allowance: NumTokens,
+    pub expires_at: Option<u64>,
+}
+
 /// Variant type for the `metadata` endpoint values.
 #[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub enum Value {
@@ -196,6 +325,18 @@ pub struct Transfer {
     pub memo: Option<Memo>,
     pub fee: Option<Nat>,
     pub created_at_time: Option<u64>,
+    pub spender: Option<PrincipalId>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Approve {
+    pub from: Account,
+    pub spender: PrincipalId,
+    pub amount: Nat,
+    pub expires_at: Option<u64>,
+    pub fee: Option<Nat>,
+    pub memo: Option<Memo>,
+    pub created_at_time: Option<u64>,
 }
 
 #[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -204,6 +345,7 @@ pub struct Transaction {
     pub mint: Option<Mint>,
     pub burn: Option<Burn>,
     pub transfer: Option<Transfer>,
+    pub approve: Option<Approve>,
     pub timestamp: u64,
 }
 
@@ -325,6 +467,7 @@ impl From<Block> for Transaction {
             mint: None,
             burn: None,
             transfer: None,
+            approve: None,
             timestamp: b.timestamp,
         };
         let created_at_time = b.transaction.created_at_time;
@@ -354,6 +497,7 @@ impl From<Block> for Transaction {
                 to,
                 amount,
                 fee,
+                spender,
             } => {
                 tx.kind = "transfer".to_string();
                 tx.transfer = Some(Transfer {
@@ -365,6 +509,27 @@ impl From<Block> for Transaction {
                         .or_else(|| b.effective_fee.map(Nat::from)),
                     created_at_time,
                     memo,
+                    spender,
+                });
+            }
+            Operation::Approve {
+                from,
+                spender,
+                amount,
+                expires_at,
+                fee,
+            } => {
+                tx.kind = "approve".to_string();
+                tx.approve = Some(Approve {
+                    from,
+                    spender,
+                    amount: Nat::from(amount),
+                    expires_at,
+                    fee: fee
+                        .map(Nat::from)
+                        .or_else(|| b.effective_fee.map(Nat::from)),
+                    memo,
+                    created_at_time,
                 });
             }
         }
@@ -179,6 +179,11 @@ pub enum Operation {
         amount: u64,
         #[serde(skip_serializing_if = "Option::is_none")]
         fee: Option<u64>,
+        /// The principal that initiated the transfer on behalf of `from`
+        /// via `icrc2_transfer_from`, if any.
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        spender: Option<PrincipalId>,
     },
     #[serde(rename = "burn")]
     Burn {
@@ -188,6 +193,20 @@ pub enum Operation {
         #[serde(rename = "amt")]
         amount: u64,
     },
+    #[serde(rename = "approve")]
+    Approve {
+        #[serde(serialize_with = "ser_compact_account")]
+        #[serde(deserialize_with = "de_compact_account")]
+        from: Account,
+        spender: PrincipalId,
+        #[serde(rename = "amt")]
+        amount: u64,
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        expires_at: Option<u64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        fee: Option<u64>,
+    },
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -314,11 +333,11 @@ impl LedgerTransaction for Transaction {
     fn apply<C>(
         &self,
         context: &mut C,
-        _now: TimeStamp,
+        now: TimeStamp,
         effective_fee: Tokens,
     ) -> Result<(), TxApplyError>
     where
-        C: LedgerContext<AccountId = Self::AccountId>,
+        C: LedgerContext<AccountId = Self::AccountId, SpenderId = Self::SpenderId>,
     {
         match &self.operation {
             Operation::Transfer {
@@ -326,18 +345,65 @@ impl LedgerTransaction for Transaction {
                 to,
                 amount,
                 fee,
-            } => context.balances_mut().transfer(
-                from,
-                to,
-                Tokens::from_e8s(*amount),
-                fee.map(Tokens::from_e8s).unwrap_or(effective_fee),
-            )?,
+                spender,
+            } => {
+                let amount = Tokens::from_e8s(*amount);
+                let fee = fee.map(Tokens::from_e8s).unwrap_or(effective_fee);
+                match spender {
+                    // NB. The account owner does not need an allowance to
+                    // spend its own tokens.
+                    Some(spender) if spender != &from.owner => {
+                        // The spender pays both the amount and the fee out of
+                        // the allowance.
+                        let debit = amount.saturating_add(fee);
+                        let allowance = context.approvals().allowance(from, spender, now);
+                        if allowance.amount < debit {
+                            return Err(TxApplyError::InsufficientAllowance {
+                                allowance: allowance.amount,
+                            });
+                        }
+                        context.balances_mut().transfer(from, to, amount, fee)?;
+                        context
+                            .approvals_mut()
+                            .use_allowance(from, spender, debit, now)
+                            .expect("bug: cannot use allowance");
+                    }
+                    _ => context.balances_mut().transfer(from, to, amount, fee)?,
+                }
+            }
             Operation::Burn { from, amount } => context
                 .balances_mut()
                 .burn(from, Tokens::from_e8s(*amount))?,
             Operation::Mint { to, amount } => {
                 context.balances_mut().mint(to, Tokens::from_e8s(*amount))?
             }
+            Operation::Approve {
+                from,
+                spender,
+                amount,
+                expires_at,
+                fee,
+            } => {
+                let fee = fee.map(Tokens::from_e8s).unwrap_or(effective_fee);
+                context.balances_mut().burn(from, fee)?;
+                let result = context
+                    .approvals_mut()
+                    .approve(
+                        from,
+                        spender,
+                        Tokens::from_e8s(*amount),
+                        expires_at.map(TimeStamp::from_nanos_since_unix_epoch),
+                        now,
+                    )
+                    .map_err(TxApplyError::from);
+                if let Err(e) = result {
+                    context
+                        .balances_mut()
+                        .mint(from, fee)
+                        .expect("bug: failed to refund approval fee");
+                    return Err(e);
+                }
+            }
         }
         Ok(())
     }
@@ -374,6 +440,51 @@ impl Transaction {
                 to,
                 amount: amount.get_e8s(),
                 fee: fee.map(Tokens::get_e8s),
+                spender: None,
+            },
+            created_at_time: created_at_time.map(|t| t.as_nanos_since_unix_epoch()),
+            memo,
+        }
+    }
+
+    pub fn transfer_from(
+        spender: PrincipalId,
+        from: Account,
+        to: Account,
+        amount: Tokens,
+        fee: Option<Tokens>,
+        created_at_time: Option<TimeStamp>,
+        memo: Option<Memo>,
+    ) -> Self {
+        Self {
+            operation: Operation::Transfer {
+                from,
+                to,
+                amount: amount.get_e8s(),
+                fee: fee.map(Tokens::get_e8s),
+                spender: Some(spender),
+            },
+            created_at_time: created_at_time.map(|t| t.as_nanos_since_unix_epoch()),
+            memo,
+        }
+    }
+
+    pub fn approve(
+        from: Account,
+        spender: PrincipalId,
+        amount: Tokens,
+        expires_at: Option<TimeStamp>,
+        fee: Option<Tokens>,
+        created_at_time: Option<TimeStamp>,
+        memo: Option<Memo>,
+    ) -> Self {
+        Self {
+            operation: Operation::Approve {
+                from,
+                spender,
+                amount: amount.get_e8s(),
+                expires_at: expires_at.map(|t| t.as_nanos_since_unix_epoch()),
+                fee: fee.map(Tokens::get_e8s),
             },
             created_at_time: created_at_time.map(|t| t.as_nanos_since_unix_epoch()),
             memo,
@@ -440,10 +551,11 @@ impl BlockType for Block {
         timestamp: TimeStamp,
         effective_fee: Tokens,
     ) -> Self {
-        let effective_fee = if let Operation::Transfer { fee, .. } = &transaction.operation {
-            fee.is_none().then_some(effective_fee.get_e8s())
-        } else {
-            None
+        let effective_fee = match &transaction.operation {
+            Operation::Transfer { fee, .. } | Operation::Approve { fee, .. } => {
+                fee.is_none().then_some(effective_fee.get_e8s())
+            }
+            _ => None,
         };
         Self {
             parent_hash,
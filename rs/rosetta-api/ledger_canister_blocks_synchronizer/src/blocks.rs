@@ -1,14 +1,22 @@
 use ic_ledger_core::block::{BlockIndex, BlockType, EncodedBlock, HashOf};
 use icp_ledger::{AccountIdentifier, Block, Tokens};
+use log::info;
 use rusqlite::params;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::convert::TryInto;
 use std::path::Path;
 use std::sync::Mutex;
 
+// Number of blocks read at once when replaying or verifying the stored chain.
+const REPLAY_BATCH_SIZE: usize = 10000;
+
+// Number of balance snapshots kept in the store, older ones are dropped.
+const MAX_BALANCE_SNAPSHOTS: u64 = 3;
+
 mod database_access {
-    use super::vec_into_array;
-    use crate::blocks::{BlockStoreError, HashedBlock};
+    use super::{vec_into_array, MAX_BALANCE_SNAPSHOTS, REPLAY_BATCH_SIZE};
+    use crate::blocks::{BlockStoreError, ChainAnchor, HashedBlock};
     use ic_ledger_canister_core::ledger::LedgerTransaction;
     use ic_ledger_core::{
         block::{BlockType, EncodedBlock, HashOf},
@@ -125,24 +133,26 @@ mod database_access {
     }
     pub fn get_all_block_indices_from_blocks_table(
         connection: &mut Connection,
+        from_idx: &u64,
     ) -> Result<Vec<u64>, BlockStoreError> {
         let mut stmt = connection
-            .prepare("SELECT idx from blocks")
+            .prepare("SELECT idx FROM blocks WHERE idx >= ?")
             .map_err(|e| BlockStoreError::Other(e.to_string()))?;
         let indices = stmt
-            .query_map(params![], |row| row.get(0))
+            .query_map(params![from_idx], |row| row.get(0))
             .map_err(|e| BlockStoreError::Other(e.to_string()))?;
         let block_indices: Vec<u64> = indices.map(|x| x.unwrap()).collect();
         Ok(block_indices)
     }
     pub fn get_all_block_indices_from_transactions_table(
         connection: &mut Connection,
+        from_idx: &u64,
     ) -> Result<Vec<u64>, BlockStoreError> {
         let mut stmt = connection
-            .prepare("SELECT block_idx FROM transactions")
+            .prepare("SELECT block_idx FROM transactions WHERE block_idx >= ?")
             .map_err(|e| BlockStoreError::Other(e.to_string()))?;
         let indices = stmt
-            .query_map(params![], |row| row.get(0))
+            .query_map(params![from_idx], |row| row.get(0))
             .map_err(|e| BlockStoreError::Other(e.to_string()))?;
         let block_indices: Vec<u64> = indices.map(|x| x.unwrap()).collect();
         Ok(block_indices)
@@ -150,12 +160,13 @@ mod database_access {
 
     pub fn get_all_block_indices_from_account_balances_table(
         connection: &mut Connection,
+        from_idx: &u64,
     ) -> Result<Vec<u64>, BlockStoreError> {
         let mut stmt = connection
-            .prepare("SELECT block_idx FROM account_balances")
+            .prepare("SELECT block_idx FROM account_balances WHERE block_idx >= ?")
             .map_err(|e| BlockStoreError::Other(e.to_string()))?;
         let indices = stmt
-            .query_map(params![], |row| row.get(0))
+            .query_map(params![from_idx], |row| row.get(0))
             .map_err(|e| BlockStoreError::Other(e.to_string()))?;
         let block_indices: Vec<u64> = indices.map(|x| x.unwrap()).collect();
         Ok(block_indices)
@@ -218,7 +229,7 @@ mod database_access {
     }
 
     fn read_hashed_block(
-        con: &mut Connection,
+        con: &Connection,
         command: &str,
     ) -> Result<Vec<Result<HashedBlock, Error>>, BlockStoreError> {
         let mut stmt = con
@@ -240,6 +251,22 @@ mod database_access {
         Ok(block.collect())
     }
 
+    /// Reads up to `limit` blocks in ascending order, starting at `block_idx`.
+    pub fn get_hashed_blocks_from(
+        con: &Connection,
+        block_idx: &u64,
+        limit: usize,
+    ) -> Result<Vec<HashedBlock>, BlockStoreError> {
+        let command = format!(
+            "SELECT hash, block, parent_hash, idx FROM blocks WHERE idx >= {} ORDER BY idx ASC LIMIT {}",
+            block_idx, limit
+        );
+        read_hashed_block(con, command.as_str())?
+            .into_iter()
+            .map(|hb| hb.map_err(|e| BlockStoreError::Other(e.to_string())))
+            .collect()
+    }
+
     pub fn get_transaction_hash(
         connection: &mut Connection,
         block_idx: &u64,
@@ -630,6 +657,149 @@ mod database_access {
             None => Ok(false),
         }
     }
+
+    pub fn get_latest_balance_snapshot_idx(
+        connection: &Connection,
+        max_block_idx: Option<u64>,
+    ) -> Result<Option<u64>, BlockStoreError> {
+        let result = match max_block_idx {
+            Some(max_block_idx) => connection.query_row(
+                "SELECT MAX(block_idx) FROM balance_snapshots WHERE block_idx <= ?",
+                params![max_block_idx],
+                |row| row.get(0),
+            ),
+            None => connection.query_row(
+                "SELECT MAX(block_idx) FROM balance_snapshots",
+                params![],
+                |row| row.get(0),
+            ),
+        };
+        result.map_err(|e| BlockStoreError::Other(e.to_string()))
+    }
+
+    /// Stores the balances of all accounts as of the block at `block_idx`.
+    /// Only the latest `MAX_BALANCE_SNAPSHOTS` snapshots are kept.
+    pub fn write_balance_snapshot(
+        connection: &Connection,
+        block_idx: &u64,
+    ) -> Result<(), BlockStoreError> {
+        // SQLite takes the bare column `tokens` from the row holding MAX(block_idx)
+        connection
+            .execute(
+                "INSERT OR REPLACE INTO balance_snapshots (block_idx, account, tokens) SELECT ?1, account, tokens FROM (SELECT account, tokens, MAX(block_idx) FROM account_balances WHERE block_idx <= ?1 GROUP BY account)",
+                params![block_idx],
+            )
+            .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        connection
+            .execute(
+                "DELETE FROM balance_snapshots WHERE block_idx < (SELECT MIN(block_idx) FROM (SELECT DISTINCT block_idx FROM balance_snapshots ORDER BY block_idx DESC LIMIT ?))",
+                params![MAX_BALANCE_SNAPSHOTS],
+            )
+            .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        Ok(())
+    }
+
+    pub fn get_balance_snapshot_indices(
+        connection: &Connection,
+    ) -> Result<Vec<u64>, BlockStoreError> {
+        let mut stmt = connection
+            .prepare("SELECT DISTINCT block_idx FROM balance_snapshots ORDER BY block_idx")
+            .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        let indices = stmt
+            .query_map(params![], |row| row.get(0))
+            .map_err(|e| BlockStoreError::Other(e.to_string()))?
+            .collect::<Result<Vec<u64>, Error>>()
+            .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        Ok(indices)
+    }
+
+    /// Recomputes the balances of all blocks after `snapshot_idx`, starting from
+    /// the balances stored in that snapshot, or of all blocks if `snapshot_idx`
+    /// is None. Returns the index of the last replayed block.
+    pub fn replay_account_balances(
+        connection: &Connection,
+        snapshot_idx: Option<u64>,
+    ) -> Result<Option<u64>, BlockStoreError> {
+        let mut next_idx = match snapshot_idx {
+            Some(snapshot_idx) => {
+                connection
+                    .execute(
+                        "DELETE FROM account_balances WHERE block_idx > ?",
+                        params![snapshot_idx],
+                    )
+                    .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+                // Only restore the balances that differ from the snapshot, so
+                // that the balance history of the accounts stays untouched.
+                connection
+                    .execute(
+                        "INSERT OR REPLACE INTO account_balances (block_idx, account, tokens) SELECT s.block_idx, s.account, s.tokens FROM balance_snapshots AS s WHERE s.block_idx = ?1 AND s.tokens IS NOT (SELECT a.tokens FROM account_balances AS a WHERE a.account = s.account AND a.block_idx <= ?1 ORDER BY a.block_idx DESC LIMIT 1)",
+                        params![snapshot_idx],
+                    )
+                    .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+                snapshot_idx + 1
+            }
+            None => {
+                connection
+                    .execute("DELETE FROM account_balances", params![])
+                    .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+                0
+            }
+        };
+        let mut stmt_select = connection
+        .prepare("SELECT block_idx,account,tokens FROM account_balances WHERE account=?1 AND block_idx<=?2 ORDER BY block_idx DESC LIMIT 1")
+        .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        let mut stmt_insert = connection
+            .prepare("INSERT INTO account_balances (block_idx,account,tokens) VALUES (?1,?2,?3)")
+            .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        let mut last_idx = None;
+        loop {
+            let batch = get_hashed_blocks_from(connection, &next_idx, REPLAY_BATCH_SIZE)?;
+            if batch.is_empty() {
+                break;
+            }
+            for hb in batch {
+                update_balance_book_execution(&hb, &mut stmt_select, &mut stmt_insert)?;
+                next_idx = hb.index + 1;
+                last_idx = Some(hb.index);
+            }
+        }
+        Ok(last_idx)
+    }
+
+    pub fn push_chain_anchor(
+        connection: &Connection,
+        block_idx: &u64,
+    ) -> Result<(), BlockStoreError> {
+        connection
+            .execute(
+                "INSERT OR IGNORE INTO chain_anchors (idx, hash, parent_hash) SELECT idx, hash, parent_hash FROM blocks WHERE idx = ?",
+                params![block_idx],
+            )
+            .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        Ok(())
+    }
+
+    pub fn get_chain_anchors(
+        connection: &mut Connection,
+    ) -> Result<Vec<ChainAnchor>, BlockStoreError> {
+        let mut stmt = connection
+            .prepare("SELECT idx, hash, parent_hash FROM chain_anchors ORDER BY idx ASC")
+            .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        let anchors = stmt
+            .query_map(params![], |row| {
+                Ok(ChainAnchor {
+                    index: row.get(0)?,
+                    hash: row.get(1).map(|bytes| HashOf::new(vec_into_array(bytes)))?,
+                    parent_hash: row.get(2).map(|opt_bytes: Option<Vec<u8>>| {
+                        opt_bytes.map(|bytes| HashOf::new(vec_into_array(bytes)))
+                    })?,
+                })
+            })
+            .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        anchors
+            .map(|anchor| anchor.map_err(|e| BlockStoreError::Other(e.to_string())))
+            .collect()
+    }
 }
 
 #[derive(candid::CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -655,6 +825,16 @@ impl HashedBlock {
     }
 }
 
+/// Hash chain link of a pruned block. An anchor is kept for the parent of the
+/// oldest block left after pruning, so that the retained chain can still be
+/// checked against the ledger.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChainAnchor {
+    pub index: BlockIndex,
+    pub hash: HashOf<EncodedBlock>,
+    pub parent_hash: Option<HashOf<EncodedBlock>>,
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum BlockStoreError {
     NotFound(BlockIndex),
@@ -672,6 +852,7 @@ fn vec_into_array(v: Vec<u8>) -> [u8; 32] {
 
 pub struct Blocks {
     connection: Mutex<rusqlite::Connection>,
+    balance_snapshot_interval: Option<u64>,
 }
 
 impl Blocks {
@@ -694,6 +875,7 @@ impl Blocks {
     fn new(connection: rusqlite::Connection) -> Result<Self, BlockStoreError> {
         let store = Self {
             connection: Mutex::new(connection),
+            balance_snapshot_interval: None,
         };
         store
             .connection
@@ -749,16 +931,64 @@ impl Blocks {
             "#,
             [],
         )?;
+        connection.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS balance_snapshots (
+                block_idx INTEGER NOT NULL,
+                account VARCHAR(64) NOT NULL,
+                tokens INTEGER NOT NULL,
+                PRIMARY KEY(block_idx,account)
+            )
+            "#,
+            [],
+        )?;
+        connection.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS chain_anchors (
+                idx INTEGER NOT NULL PRIMARY KEY,
+                hash BLOB NOT NULL,
+                parent_hash BLOB
+            )
+            "#,
+            [],
+        )?;
 
         Ok(())
     }
 
+    /// Sets the number of blocks between two snapshots of the balances of all
+    /// accounts. Snapshots are taken when blocks are pushed, no snapshots are
+    /// taken if the interval is None.
+    pub fn set_balance_snapshot_interval(&mut self, interval: Option<u64>) {
+        self.balance_snapshot_interval = interval;
+    }
+
+    fn is_balance_snapshot_block(&self, block_idx: BlockIndex) -> bool {
+        match self.balance_snapshot_interval {
+            Some(interval) => interval > 0 && block_idx > 0 && block_idx % interval == 0,
+            None => false,
+        }
+    }
+
+    /// Removes all blocks between the genesis block and `hb`. The balances as
+    /// of `hb` are kept as a snapshot and the parent of `hb` as a chain anchor.
     pub fn prune(&mut self, hb: &HashedBlock) -> Result<(), BlockStoreError> {
         let mut connection = self.connection.lock().unwrap();
         connection
             .execute_batch("BEGIN TRANSACTION;")
             .map_err(|e| BlockStoreError::Other(format!("{}", e)))?;
 
+        if hb.index > 1 {
+            database_access::push_chain_anchor(&connection, &(hb.index - 1))?;
+        }
+        database_access::write_balance_snapshot(&connection, &hb.index)?;
+        connection
+            .execute(
+                "DELETE FROM balance_snapshots WHERE block_idx < ?",
+                params![hb.index],
+            )
+            .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+
         connection
             .execute(
                 "DELETE FROM transactions WHERE block_idx > 0 AND block_idx < ?",
@@ -852,12 +1082,22 @@ impl Blocks {
     }
     fn check_table_coherence(&self) -> Result<(), BlockStoreError> {
         let mut connection = self.connection.lock().unwrap();
+        // A balance snapshot is written in the same transaction as the block it
+        // belongs to, so the tables are known to be coherent up to the latest one.
+        let snapshot_idx = database_access::get_latest_balance_snapshot_idx(&connection, None)?;
+        let from_idx = snapshot_idx.map(|idx| idx + 1).unwrap_or(0);
         let mut block_indices =
-            database_access::get_all_block_indices_from_blocks_table(&mut connection)?;
+            database_access::get_all_block_indices_from_blocks_table(&mut connection, &from_idx)?;
         let mut transaction_block_indices =
-            database_access::get_all_block_indices_from_transactions_table(&mut connection)?;
+            database_access::get_all_block_indices_from_transactions_table(
+                &mut connection,
+                &from_idx,
+            )?;
         let mut account_balances_block_indices =
-            database_access::get_all_block_indices_from_account_balances_table(&mut connection)?;
+            database_access::get_all_block_indices_from_account_balances_table(
+                &mut connection,
+                &from_idx,
+            )?;
         let vec_sorted_diff = |blocks_indices: &mut [u64],
                                other_indices: &mut [u64]|
          -> Result<Vec<u64>, BlockStoreError> {
@@ -923,14 +1163,152 @@ impl Blocks {
                 all_indices.as_mut_slice(),
                 account_balances_block_indices.as_mut_slice(),
             )?;
-            for missing_index in difference_account_balances_indices {
-                let missing_block =
-                    database_access::get_hashed_block(&mut connection, &missing_index)?;
-                database_access::update_balance_book(&mut connection, &missing_block)?;
+            let pruned = database_access::get_first_hashed_block(&mut connection, None)?.index > 1;
+            if snapshot_idx.is_none() && pruned {
+                // Stores pruned before snapshots were introduced have no base to
+                // replay the balances from.
+                for missing_index in difference_account_balances_indices {
+                    let missing_block =
+                        database_access::get_hashed_block(&mut connection, &missing_index)?;
+                    database_access::update_balance_book(&mut connection, &missing_block)?;
+                }
+            } else if !difference_account_balances_indices.is_empty() {
+                Self::rebuild_account_balances_from_snapshot(&mut connection, snapshot_idx)?;
             }
         }
         Ok(())
     }
+
+    /// Recomputes the balances of all blocks starting at `block_idx` by
+    /// replaying the blocks after the nearest balance snapshot.
+    pub fn rebuild_account_balances(&self, block_idx: &BlockIndex) -> Result<(), BlockStoreError> {
+        let mut connection = self.connection.lock().unwrap();
+        let snapshot_idx = match block_idx.checked_sub(1) {
+            Some(max_idx) => {
+                database_access::get_latest_balance_snapshot_idx(&connection, Some(max_idx))?
+            }
+            None => None,
+        };
+        if snapshot_idx.is_none()
+            && database_access::get_first_hashed_block(&mut connection, None)?.index > 1
+        {
+            return Err(BlockStoreError::Other(format!(
+                "No balance snapshot to rebuild the balances of block {} from",
+                block_idx
+            )));
+        }
+        Self::rebuild_account_balances_from_snapshot(&mut connection, snapshot_idx)
+    }
+
+    fn rebuild_account_balances_from_snapshot(
+        connection: &mut rusqlite::Connection,
+        snapshot_idx: Option<BlockIndex>,
+    ) -> Result<(), BlockStoreError> {
+        info!(
+            "Rebuilding account balances from the snapshot at block {:?}",
+            snapshot_idx
+        );
+        connection
+            .execute_batch("BEGIN TRANSACTION;")
+            .map_err(|e| BlockStoreError::Other(format!("{}", e)))?;
+        // The last replayed block becomes the new starting point for the
+        // coherence check on the next start.
+        let result = database_access::replay_account_balances(connection, snapshot_idx).and_then(
+            |last_idx| match last_idx {
+                Some(last_idx) => database_access::write_balance_snapshot(connection, &last_idx),
+                None => Ok(()),
+            },
+        );
+        match result {
+            Ok(()) => connection
+                .execute_batch("COMMIT TRANSACTION;")
+                .map_err(|e| BlockStoreError::Other(format!("{}", e))),
+            Err(e) => {
+                connection
+                    .execute_batch("ROLLBACK TRANSACTION;")
+                    .map_err(|e| BlockStoreError::Other(format!("{}", e)))?;
+                Err(e)
+            }
+        }
+    }
+
+    pub fn get_latest_balance_snapshot_idx(&self) -> Result<Option<BlockIndex>, BlockStoreError> {
+        let connection = self.connection.lock().unwrap();
+        database_access::get_latest_balance_snapshot_idx(&connection, None)
+    }
+
+    pub fn get_balance_snapshot_indices(&self) -> Result<Vec<BlockIndex>, BlockStoreError> {
+        let connection = self.connection.lock().unwrap();
+        database_access::get_balance_snapshot_indices(&connection)
+    }
+
+    pub fn get_chain_anchors(&self) -> Result<Vec<ChainAnchor>, BlockStoreError> {
+        let mut connection = self.connection.lock().unwrap();
+        database_access::get_chain_anchors(&mut connection)
+    }
+
+    /// Checks that every stored block matches its hash and links to the
+    /// previous one. Gaps left by pruning must be bridged by a chain anchor.
+    /// Returns the index of the last stored block.
+    pub fn verify_stored_chain(&self) -> Result<Option<BlockIndex>, BlockStoreError> {
+        let mut connection = self.connection.lock().unwrap();
+        let anchors: BTreeMap<BlockIndex, ChainAnchor> =
+            database_access::get_chain_anchors(&mut connection)?
+                .into_iter()
+                .map(|anchor| (anchor.index, anchor))
+                .collect();
+        let mut previous: Option<(BlockIndex, HashOf<EncodedBlock>)> = None;
+        let mut next_idx = 0;
+        loop {
+            let batch =
+                database_access::get_hashed_blocks_from(&connection, &next_idx, REPLAY_BATCH_SIZE)?;
+            if batch.is_empty() {
+                break;
+            }
+            for hb in batch {
+                let block = Block::decode(hb.block.clone()).map_err(|e| {
+                    BlockStoreError::Other(format!("Cannot decode block {}: {}", hb.index, e))
+                })?;
+                if Block::block_hash(&hb.block) != hb.hash {
+                    return Err(BlockStoreError::Other(format!(
+                        "Block at {}: hash mismatch. Stored: {}, computed: {}",
+                        hb.index,
+                        hb.hash,
+                        Block::block_hash(&hb.block)
+                    )));
+                }
+                if block.parent_hash != hb.parent_hash {
+                    return Err(BlockStoreError::Other(format!(
+                        "Block at {}: stored parent hash {:?} differs from the one in the block {:?}",
+                        hb.index, hb.parent_hash, block.parent_hash
+                    )));
+                }
+                let expected_parent_hash = match previous {
+                    None if hb.index == 0 => None,
+                    None => return Err(BlockStoreError::NotFound(0)),
+                    Some((prev_idx, prev_hash)) if prev_idx + 1 == hb.index => Some(prev_hash),
+                    Some(_) => match anchors.get(&(hb.index - 1)) {
+                        Some(anchor) => Some(anchor.hash),
+                        None => {
+                            return Err(BlockStoreError::Other(format!(
+                                "Block at {}: no chain anchor for the pruned parent block",
+                                hb.index
+                            )))
+                        }
+                    },
+                };
+                if hb.parent_hash != expected_parent_hash {
+                    return Err(BlockStoreError::Other(format!(
+                        "Block at {}: parent hash mismatch. Expected: {:?}, got: {:?}",
+                        hb.index, expected_parent_hash, hb.parent_hash
+                    )));
+                }
+                previous = Some((hb.index, hb.hash));
+                next_idx = hb.index + 1;
+            }
+        }
+        Ok(previous.map(|(idx, _)| idx))
+    }
     pub fn is_verified_by_hash(
         &self,
         hash: &HashOf<EncodedBlock>,
@@ -1033,6 +1411,9 @@ impl Blocks {
             &hb.index,
         )?;
         database_access::update_balance_book(&mut con, hb)?;
+        if self.is_balance_snapshot_block(hb.index) {
+            database_access::write_balance_snapshot(&con, &hb.index)?;
+        }
         con.execute_batch("COMMIT TRANSACTION;")
             .map_err(|e| BlockStoreError::Other(format!("{}", e)))?;
         drop(con);
@@ -1096,6 +1477,14 @@ impl Blocks {
                     return Err(e);
                 }
             }
+            if self.is_balance_snapshot_block(hb.index) {
+                if let Err(e) = database_access::write_balance_snapshot(&connection, &hb.index) {
+                    connection
+                        .execute_batch("ROLLBACK TRANSACTION;")
+                        .map_err(|e| BlockStoreError::Other(format!("{}", e)))?;
+                    return Err(e);
+                }
+            }
         }
         connection
             .execute_batch("COMMIT TRANSACTION;")
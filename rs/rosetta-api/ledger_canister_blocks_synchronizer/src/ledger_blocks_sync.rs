@@ -66,6 +66,7 @@ impl<B: BlocksAccess> LedgerBlocksSynchronizer<B> {
         blocks_access: Option<Arc<B>>,
         store_location: Option<&std::path::Path>,
         store_max_blocks: Option<u64>,
+        balance_snapshot_interval: Option<u64>,
         verification_info: Option<VerificationInfo>,
         metrics: Box<dyn LedgerBlocksSynchronizerMetrics + Send + Sync>,
     ) -> Result<LedgerBlocksSynchronizer<B>, Error> {
@@ -73,6 +74,7 @@ impl<B: BlocksAccess> LedgerBlocksSynchronizer<B> {
             Some(loc) => Blocks::new_persistent(loc)?,
             None => Blocks::new_in_memory()?,
         };
+        blocks.set_balance_snapshot_interval(balance_snapshot_interval);
 
         if let Some(blocks_access) = &blocks_access {
             Self::verify_store(&blocks, blocks_access).await?;
@@ -212,6 +214,90 @@ impl<B: BlocksAccess> LedgerBlocksSynchronizer<B> {
         Ok(())
     }
 
+    /// Re-validates the stored chain: the blocks must match their hashes and
+    /// link up to each other and to the chain anchors of pruned blocks, the
+    /// anchors must match the ledger, and the chain must lead to the certified
+    /// tip of the ledger. Returns the index of the tip.
+    pub async fn verify_chain(&self) -> Result<BlockIndex, Error> {
+        let canister = self.blocks_access.as_ref().ok_or_else(|| {
+            Error::InternalError("Cannot verify the store without access to the ledger".to_string())
+        })?;
+        let blockchain = self.blockchain.read().await;
+        let last_idx = blockchain
+            .verify_stored_chain()?
+            .ok_or_else(|| Error::InternalError("The store is empty".to_string()))?;
+        Self::verify_store(&blockchain, canister).await?;
+        for anchor in blockchain.get_chain_anchors()? {
+            let block = canister
+                .query_raw_block(anchor.index)
+                .await
+                .map_err(Error::InternalError)?;
+            if block.as_ref().map(Block::block_hash) != Some(anchor.hash) {
+                let msg = format!(
+                    "Chain anchor at {} does not match the block on the blockchain. \
+                    Anchor hash: {}, canister hash: {:?}",
+                    anchor.index,
+                    anchor.hash,
+                    block.as_ref().map(Block::block_hash)
+                );
+                error!("{}", msg);
+                return Err(Error::InternalError(msg));
+            }
+        }
+
+        let tip = self
+            .query_verified_tip()
+            .await
+            .map_err(Error::InternalError)?;
+        if last_idx > tip.index {
+            return Err(Error::InternalError(format!(
+                "The store has blocks up to {} but the tip of the ledger is at {}",
+                last_idx, tip.index
+            )));
+        }
+        let last_hb = blockchain.get_hashed_block(&last_idx)?;
+        let mut last_block = Block::decode(last_hb.block)
+            .map_err(|err| Error::InternalError(format!("Cannot decode block: {}", err)))?;
+        let mut last_block_hash = last_hb.hash;
+        let mut i = last_idx + 1;
+        while i <= tip.index {
+            let batch = canister
+                .clone()
+                .multi_query_blocks(Range {
+                    start: i,
+                    end: tip.index + 1,
+                })
+                .await
+                .map_err(Error::InternalError)?;
+            if batch.is_empty() {
+                return Err(Error::InternalError(format!(
+                    "Couldn't fetch blocks [{},{}) (batch result empty)",
+                    i,
+                    tip.index + 1
+                )));
+            }
+            for raw_block in batch {
+                let block = Block::decode(raw_block.clone())
+                    .map_err(|err| Error::InternalError(format!("Cannot decode block: {}", err)))?;
+                if block.parent_hash != Some(last_block_hash) {
+                    return Err(Error::InternalError(format!(
+                        "Block at {}: parent hash mismatch. Expected: {:?}, got: {:?}",
+                        i, last_block_hash, block.parent_hash
+                    )));
+                }
+                last_block_hash = Block::block_hash(&raw_block);
+                last_block = block;
+                i += 1;
+            }
+        }
+        if last_block != tip.block {
+            return Err(Error::invalid_tip_of_chain(
+                tip.index, tip.block, last_block,
+            ));
+        }
+        Ok(tip.index)
+    }
+
     pub async fn read_blocks(&self) -> Box<dyn Deref<Target = Blocks> + '_> {
         Box::new(self.blockchain.read().await)
     }
@@ -432,6 +518,7 @@ mod test {
     use crate::ledger_blocks_sync::LedgerBlocksSynchronizer;
 
     use super::NopMetrics;
+    use crate::blocks::HashedBlock;
 
     struct RangeOfBlocks {
         pub blocks: Vec<EncodedBlock>,
@@ -478,6 +565,7 @@ mod test {
             Some(Arc::new(RangeOfBlocks::new(blocks))),
             /* store_location = */ None,
             /* store_max_blocks = */ None,
+            /* balance_snapshot_interval = */ None,
             /* verification_info = */ None,
             Box::new(NopMetrics {}),
         )
@@ -596,4 +684,34 @@ mod test {
             );
         }
     }
+
+    #[tokio::test]
+    async fn verify_chain_up_to_tip() {
+        let blocks = dummy_blocks(3);
+        let blocks_sync = new_ledger_blocks_synchronizer(blocks.clone()).await;
+        blocks_sync
+            .sync_blocks(Arc::new(AtomicBool::new(false)), Some(1))
+            .await
+            .unwrap();
+        // the blocks missing from the store are checked against the tip too
+        assert_eq!(blocks_sync.verify_chain().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn verify_chain_rejects_different_ledger() {
+        let blocks = dummy_blocks(3);
+        let mut ledger_blocks = blocks[0..2].to_vec();
+        ledger_blocks.push(dummy_block(None));
+        let blocks_sync = new_ledger_blocks_synchronizer(ledger_blocks).await;
+        {
+            let mut blockchain = blocks_sync.blockchain.write().await;
+            let mut parent_hash = None;
+            for (idx, eb) in blocks.into_iter().enumerate() {
+                let hb = HashedBlock::hash_block(eb, parent_hash, idx as u64);
+                parent_hash = Some(hb.hash);
+                blockchain.push(&hb).unwrap();
+            }
+        }
+        assert!(blocks_sync.verify_chain().await.is_err());
+    }
 }
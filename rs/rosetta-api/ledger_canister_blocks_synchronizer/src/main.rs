@@ -0,0 +1,122 @@
+use clap::{Args, Parser, Subcommand};
+use ic_crypto_utils_threshold_sig_der::{
+    parse_threshold_sig_key, parse_threshold_sig_key_from_der, public_key_to_der,
+};
+use ic_ledger_canister_blocks_synchronizer::canister_access::CanisterAccess;
+use ic_ledger_canister_blocks_synchronizer::certification::VerificationInfo;
+use ic_ledger_canister_blocks_synchronizer::ledger_blocks_sync::{
+    LedgerBlocksSynchronizer, LedgerBlocksSynchronizerMetrics,
+};
+use ic_types::{CanisterId, PrincipalId};
+use std::{path::PathBuf, str::FromStr, sync::Arc};
+use url::Url;
+
+// The DER-encoded root key of the Internet Computer Mainnet
+const MAINNET_ROOT_KEY: &str = "MIGCMB0GDSsGAQQBgtx8BQMBAgEGDCsGAQQBgtx8BQMCAQNhAIFMDm7HH6tYOwi9gTc8JVw8NxsuhIY8mKTx4It0I10U+12cDNVG2WhfkToMCyzFNBWDv0tDkuRn25bWW5u0y3FxEvhHLg1aTRRQX/10hLASkQkcX4e5iINGP5gJGguqrg==";
+
+#[derive(Debug, Parser)]
+#[clap(version)]
+struct Opt {
+    #[clap(
+        short = 'l',
+        long = "log-config-file",
+        default_value = "log_config.yml"
+    )]
+    log_config_file: PathBuf,
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Re-validates the blocks of a store against the certified tip of the
+    /// ledger.
+    Verify(VerifyOpt),
+}
+
+#[derive(Debug, Args)]
+struct VerifyOpt {
+    #[clap(long = "store-location", default_value = "./data")]
+    store_location: PathBuf,
+    /// Id of the ledger canister the store was synchronized from.
+    #[clap(short = 'c', long = "canister-id")]
+    ic_canister_id: String,
+    #[clap(long = "ic-url", default_value = "https://ic0.app")]
+    ic_url: String,
+    /// Path to the PEM file of the root key used to verify the certified tip of
+    /// the chain. Defaults to the root key of the Mainnet.
+    #[clap(long = "root-key")]
+    root_key: Option<PathBuf>,
+}
+
+struct NoMetrics;
+
+impl LedgerBlocksSynchronizerMetrics for NoMetrics {
+    fn set_target_height(&self, _height: u64) {}
+    fn set_synced_height(&self, _height: u64) {}
+    fn set_verified_height(&self, _height: u64) {}
+}
+
+async fn verify(opt: VerifyOpt) -> Result<(), String> {
+    let root_key = match opt.root_key {
+        Some(path) => parse_threshold_sig_key(path.as_path()),
+        None => parse_threshold_sig_key_from_der(
+            &base64::decode(MAINNET_ROOT_KEY).expect("invalid Mainnet root key"),
+        ),
+    }
+    .map_err(|e| format!("Failed to read the root key: {}", e))?;
+    let canister_id = PrincipalId::from_str(&opt.ic_canister_id)
+        .map_err(|e| format!("Invalid canister id: {}", e))
+        .and_then(|id| CanisterId::new(id).map_err(|e| format!("Invalid canister id: {}", e)))?;
+    let url = Url::parse(&opt.ic_url).map_err(|e| format!("Invalid IC url: {}", e))?;
+
+    let der_root_key = public_key_to_der(&root_key.into_bytes())?;
+    let canister_access = CanisterAccess::new(url, canister_id, Some(der_root_key))
+        .await
+        .map_err(|e| format!("Failed to connect to the ledger: {}", e))?;
+    let verification_info = VerificationInfo {
+        root_key,
+        canister_id,
+    };
+    let synchronizer = LedgerBlocksSynchronizer::new(
+        Some(Arc::new(canister_access)),
+        Some(opt.store_location.as_path()),
+        /* store_max_blocks = */ None,
+        /* balance_snapshot_interval = */ None,
+        Some(verification_info),
+        Box::new(NoMetrics),
+    )
+    .await
+    .map_err(|e| format!("Failed to load the store: {:?}", e))?;
+    let tip_index = synchronizer
+        .verify_chain()
+        .await
+        .map_err(|e| format!("Verification failed: {:?}", e))?;
+    log::info!(
+        "The stored chain leads to the tip of the ledger at {}",
+        tip_index
+    );
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    let opt = Opt::parse();
+
+    if let Err(e) = log4rs::init_file(opt.log_config_file.as_path(), Default::default()) {
+        panic!(
+            "ledger-canister-blocks-synchronizer failed to load log configuration file: {}, error: {}. (current_dir is: {:?})",
+            &opt.log_config_file.as_path().display(),
+            e,
+            std::env::current_dir()
+        );
+    }
+
+    let result = match opt.command {
+        Command::Verify(verify_opt) => verify(verify_opt).await,
+    };
+    if let Err(e) = result {
+        log::error!("{}", e);
+        std::process::exit(1);
+    }
+}
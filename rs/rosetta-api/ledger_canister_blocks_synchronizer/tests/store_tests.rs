@@ -367,3 +367,127 @@ fn verify_balance_snapshot(scribe: &Scribe, store: &mut Blocks, prune_at: u64) {
     }
     assert_eq!(sum_icpt, total);
 }
+
+fn verify_balances_at(scribe: &Scribe, store: &Blocks, block_idx: u64) {
+    let scribe_balances = scribe.balance_history.get(block_idx as usize).unwrap();
+    for (acc, tokens) in scribe_balances {
+        assert_eq!(store.get_account_balance(acc, &block_idx).unwrap(), *tokens);
+    }
+}
+
+#[actix_rt::test]
+async fn store_balance_snapshots_test() {
+    init_test_logger();
+    let tmpdir = create_tmp_dir();
+    let location = tmpdir.path();
+    let mut store = sqlite_on_disk_store(location);
+    store.set_balance_snapshot_interval(Some(25));
+    let scribe = Scribe::new_with_sample_data(10, 100);
+
+    for hb in &scribe.blockchain {
+        store.push(hb).unwrap();
+    }
+    let last_idx = scribe.blockchain.back().unwrap().index;
+    store.set_hashed_block_to_verified(&last_idx).unwrap();
+    assert_eq!(store.get_latest_balance_snapshot_idx().unwrap(), Some(100));
+    drop(store);
+
+    // Losing the balances after the latest snapshot rebuilds them on load
+    let con = rusqlite::Connection::open(location.join("db.sqlite")).unwrap();
+    con.execute("DELETE FROM account_balances WHERE block_idx > 90", [])
+        .unwrap();
+    drop(con);
+    let store = sqlite_on_disk_store(location);
+    verify_balances_at(&scribe, &store, last_idx);
+    verify_balances_at(&scribe, &store, 60);
+    assert_eq!(
+        store.get_latest_balance_snapshot_idx().unwrap(),
+        Some(last_idx)
+    );
+
+    // Rebuilding from an older snapshot replays the blocks after it
+    store.rebuild_account_balances(&30).unwrap();
+    for idx in [25, 30, 75, last_idx] {
+        verify_balances_at(&scribe, &store, idx);
+    }
+}
+
+#[actix_rt::test]
+async fn store_prune_keeps_chain_anchors_test() {
+    init_test_logger();
+    let tmpdir = create_tmp_dir();
+    let location = tmpdir.path();
+    let mut store = sqlite_on_disk_store(location);
+    let scribe = Scribe::new_with_sample_data(10, 100);
+
+    for hb in &scribe.blockchain {
+        store.push(hb).unwrap();
+    }
+    let last_idx = scribe.blockchain.back().unwrap().index;
+    store.set_hashed_block_to_verified(&last_idx).unwrap();
+    assert_eq!(store.verify_stored_chain().unwrap(), Some(last_idx));
+
+    prune(&scribe, &mut store, 20);
+    prune(&scribe, &mut store, 40);
+    let anchors: Vec<u64> = store
+        .get_chain_anchors()
+        .unwrap()
+        .iter()
+        .map(|anchor| anchor.index)
+        .collect();
+    assert_eq!(anchors, vec![19, 39]);
+    for anchor in store.get_chain_anchors().unwrap() {
+        assert_eq!(anchor.hash, scribe.blockchain[anchor.index as usize].hash);
+    }
+    assert_eq!(store.verify_stored_chain().unwrap(), Some(last_idx));
+    assert_eq!(store.get_latest_balance_snapshot_idx().unwrap(), Some(40));
+    drop(store);
+
+    let con = rusqlite::Connection::open(location.join("db.sqlite")).unwrap();
+    con.execute(
+        "UPDATE chain_anchors SET hash = ?1 WHERE idx = 39",
+        params![scribe.blockchain[38].hash.into_bytes().to_vec()],
+    )
+    .unwrap();
+    drop(con);
+    let store = sqlite_on_disk_store(location);
+    assert!(store.verify_stored_chain().is_err());
+}
+
+#[actix_rt::test]
+async fn store_keeps_latest_balance_snapshots_test() {
+    init_test_logger();
+    let tmpdir = create_tmp_dir();
+    let mut store = sqlite_on_disk_store(tmpdir.path());
+    store.set_balance_snapshot_interval(Some(10));
+    let scribe = Scribe::new_with_sample_data(10, 100);
+
+    for hb in &scribe.blockchain {
+        store.push(hb).unwrap();
+    }
+    let last_idx = scribe.blockchain.back().unwrap().index;
+    let latest_snapshot_idx = last_idx - last_idx % 10;
+    let latest_snapshots = vec![
+        latest_snapshot_idx - 20,
+        latest_snapshot_idx - 10,
+        latest_snapshot_idx,
+    ];
+    assert_eq!(
+        store.get_balance_snapshot_indices().unwrap(),
+        latest_snapshots
+    );
+
+    // Rebuilding from a dropped snapshot replays all the blocks
+    store.rebuild_account_balances(&50).unwrap();
+    for idx in [10, 50, latest_snapshot_idx - 15, last_idx] {
+        verify_balances_at(&scribe, &store, idx);
+    }
+    assert_eq!(
+        store.get_latest_balance_snapshot_idx().unwrap(),
+        Some(last_idx)
+    );
+    assert_eq!(store.get_balance_snapshot_indices().unwrap().len(), 3);
+
+    prune(&scribe, &mut store, latest_snapshot_idx - 5);
+    verify_balance_snapshot(&scribe, &mut store, latest_snapshot_idx - 5);
+}
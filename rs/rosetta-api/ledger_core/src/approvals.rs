@@ -252,7 +252,6 @@ where
         for _ in 0..limit {
             match self.expiration_queue.peek() {
                 Some(Reverse((ts, _key))) => {
-                    println!("{:?}", ts);
                     if *ts > now {
                         return pruned;
                     }
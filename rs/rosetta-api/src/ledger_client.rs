@@ -143,6 +143,7 @@ impl LedgerClient {
         governance_canister_id: CanisterId,
         store_location: Option<&std::path::Path>,
         store_max_blocks: Option<u64>,
+        store_balance_snapshot_interval: Option<u64>,
         offline: bool,
         root_key: Option<ThresholdSigPublicKey>,
     ) -> Result<LedgerClient, ApiError> {
@@ -167,6 +168,7 @@ impl LedgerClient {
             canister_access.clone(),
             store_location,
             store_max_blocks,
+            store_balance_snapshot_interval,
             verification_info,
             Box::new(LedgerBlocksSynchronizerMetricsImpl {}),
         )
@@ -40,6 +40,10 @@ struct Opt {
     store_location: PathBuf,
     #[clap(long = "store-max-blocks")]
     store_max_blocks: Option<u64>,
+    /// Number of blocks between two snapshots of the account balances in the
+    /// store. Snapshots speed up loading the store on start.
+    #[clap(long = "store-balance-snapshot-interval")]
+    store_balance_snapshot_interval: Option<u64>,
     #[clap(long = "exit-on-sync")]
     exit_on_sync: bool,
     #[clap(long = "offline")]
@@ -155,6 +159,7 @@ async fn main() -> std::io::Result<()> {
 
     let Opt {
         store_max_blocks,
+        store_balance_snapshot_interval,
         offline,
         exit_on_sync,
         mainnet,
@@ -170,6 +175,7 @@ async fn main() -> std::io::Result<()> {
         governance_canister_id,
         store_location,
         store_max_blocks,
+        store_balance_snapshot_interval,
         offline,
         root_key,
     )
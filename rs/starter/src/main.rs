@@ -113,32 +113,31 @@ fn main() -> Result<()> {
             idkg_key_rotation_period_ms: None,
         });
 
-        let mut topology_config = TopologyConfig::default();
-        topology_config.insert_subnet(
+        let mut subnet_config = SubnetConfig::new(
             SUBNET_ID,
-            SubnetConfig::new(
-                SUBNET_ID,
-                subnet_nodes,
-                None,
-                None,
-                None,
-                None,
-                None,
-                config.unit_delay,
-                config.initial_notary_delay,
-                config.dkg_interval_length,
-                None,
-                config.subnet_type,
-                None,
-                None,
-                None,
-                Some(config.subnet_features),
-                ecdsa_config,
-                None,
-                vec![],
-                vec![],
-            ),
+            subnet_nodes,
+            None,
+            None,
+            None,
+            None,
+            None,
+            config.unit_delay,
+            config.initial_notary_delay,
+            config.dkg_interval_length,
+            None,
+            config.subnet_type,
+            None,
+            None,
+            None,
+            Some(config.subnet_features),
+            ecdsa_config,
+            None,
+            vec![],
+            vec![],
         );
+        subnet_config.adaptive_notarization_delays = config.adaptive_consensus_delays;
+        let mut topology_config = TopologyConfig::default();
+        topology_config.insert_subnet(SUBNET_ID, subnet_config);
 
         // N.B. it is safe to generate subnet records here, we only skip this
         // step for a specific deployment case in ic-prep: when we want to deploy
@@ -304,6 +303,11 @@ struct CliArgs {
     #[clap(long = "detect-consensus-starvation")]
     detect_consensus_starvation: Option<bool>,
 
+    /// Whether or not the subnet record lets consensus adapt the notarization
+    /// delays to the observed block rate.
+    #[clap(long = "adaptive-consensus-delays")]
+    adaptive_consensus_delays: bool,
+
     /// The backend DB used by Consensus, can be rocksdb or lmdb.
     #[clap(long = "consensus-pool-backend",
                 possible_values = &["lmdb", "rocksdb"])]
@@ -545,6 +549,7 @@ impl CliArgs {
             initial_notary_delay,
             dkg_interval_length: self.dkg_interval_length.map(Height::from),
             detect_consensus_starvation: self.detect_consensus_starvation,
+            adaptive_consensus_delays: self.adaptive_consensus_delays,
             consensus_pool_backend: self.consensus_pool_backend,
             subnet_features: to_subnet_features(&self.subnet_features),
             ecdsa_keyid,
@@ -676,6 +681,7 @@ struct ValidatedConfig {
     initial_notary_delay: Option<Duration>,
     dkg_interval_length: Option<Height>,
     detect_consensus_starvation: Option<bool>,
+    adaptive_consensus_delays: bool,
     consensus_pool_backend: Option<String>,
     subnet_features: SubnetFeatures,
     ecdsa_keyid: Option<EcdsaKeyId>,
@@ -16,7 +16,7 @@ pub use ic_error_types::{ErrorCode, UserError};
 use ic_execution_environment::ExecutionServices;
 use ic_ic00_types::{self as ic00, CanisterIdRecord, InstallCodeArgs, Method, Payload};
 pub use ic_ic00_types::{
-    CanisterInstallMode, CanisterSettingsArgs, EcdsaKeyId, UpdateSettingsArgs,
+    CanisterInstallMode, CanisterSettingsArgs, EcdsaCurve, EcdsaKeyId, UpdateSettingsArgs,
 };
 use ic_interfaces::{
     certification::{Verifier, VerifierError},
@@ -277,6 +277,14 @@ impl StateMachineConfig {
     }
 }
 
+/// SEC1 encoding of the secp256k1 generator.
+const SECP256K1_MASTER_ECDSA_PUBLIC_KEY: &str =
+    "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+
+/// SEC1 encoding of the secp256r1 generator.
+const SECP256R1_MASTER_ECDSA_PUBLIC_KEY: &str =
+    "036b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296";
+
 /// Represents a replicated state machine detached from the network layer that
 /// can be used to test this part of the stack in isolation.
 pub struct StateMachine {
@@ -587,11 +595,22 @@ impl StateMachine {
 
         let mut ecdsa_subnet_public_keys = BTreeMap::new();
         for ecdsa_key in ecdsa_keys {
+            // The generator of the curve serves as the master public key, so
+            // that `ecdsa_public_key` can derive canister public keys from it.
+            let (algorithm_id, public_key) = match ecdsa_key.curve {
+                EcdsaCurve::Secp256k1 => (
+                    AlgorithmId::EcdsaSecp256k1,
+                    SECP256K1_MASTER_ECDSA_PUBLIC_KEY,
+                ),
+                EcdsaCurve::Secp256r1 => {
+                    (AlgorithmId::EcdsaP256, SECP256R1_MASTER_ECDSA_PUBLIC_KEY)
+                }
+            };
             ecdsa_subnet_public_keys.insert(
                 ecdsa_key,
                 MasterEcdsaPublicKey {
-                    algorithm_id: AlgorithmId::EcdsaSecp256k1,
-                    public_key: b"master_ecdsa_public_key".to_vec(),
+                    algorithm_id,
+                    public_key: hex::decode(public_key).unwrap(),
                 },
             );
         }
@@ -754,6 +773,7 @@ impl StateMachine {
             payload,
             randomness: Randomness::from(seed),
             ecdsa_subnet_public_keys: self.ecdsa_subnet_public_keys.clone(),
+            vetkd_subnet_public_keys: BTreeMap::new(),
             registry_version: self.registry_client.get_latest_version(),
             time: self.time.get(),
             consensus_responses: vec![],
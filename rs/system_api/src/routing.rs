@@ -9,7 +9,7 @@ use ic_ic00_types::{
     BitcoinSendTransactionArgs, CanisterIdRecord, ComputeInitialEcdsaDealingsArgs,
     ECDSAPublicKeyArgs, EcdsaKeyId, InstallCodeArgs, Method as Ic00Method, Payload,
     ProvisionalTopUpCanisterArgs, SetControllerArgs, SignWithECDSAArgs, UninstallCodeArgs,
-    UpdateSettingsArgs,
+    UpdateSettingsArgs, VetKdEncryptedKeyArgs, VetKdKeyId, VetKdPublicKeyArgs,
 };
 use ic_replicated_state::NetworkTopology;
 
@@ -20,6 +20,7 @@ pub(super) enum ResolveDestinationError {
     SubnetNotFound(CanisterId, Ic00Method),
     AlreadyResolved(PrincipalId),
     EcdsaKeyError(String),
+    VetKdKeyError(String),
 }
 
 impl From<candid::Error> for ResolveDestinationError {
@@ -179,6 +180,14 @@ pub(super) fn resolve_destination(
                 EcdsaSubnetKind::HoldsAndSignWithKey,
             )
         }
+        Ok(Ic00Method::VetKdPublicKey) => {
+            let key_id = Decode!(payload, VetKdPublicKeyArgs)?.key_id;
+            route_vetkd_message(&key_id, network_topology)
+        }
+        Ok(Ic00Method::VetKdEncryptedKey) => {
+            let key_id = Decode!(payload, VetKdEncryptedKeyArgs)?.key_id;
+            route_vetkd_message(&key_id, network_topology)
+        }
         Ok(Ic00Method::ComputeInitialEcdsaDealings) => {
             let args = Decode!(payload, ComputeInitialEcdsaDealingsArgs)?;
             route_ecdsa_message(
@@ -194,6 +203,18 @@ pub(super) fn resolve_destination(
     }
 }
 
+fn format_keys<T: std::fmt::Display>(mut found_keys: impl Iterator<Item = T>) -> String {
+    let mut keys = "[".to_string();
+    if let Some(key) = found_keys.next() {
+        write!(keys, "{}", key).unwrap();
+    }
+    for key in found_keys {
+        write!(keys, ", {}", key).unwrap();
+    }
+    keys.push(']');
+    keys
+}
+
 enum EcdsaSubnetKind {
     OnlyHoldsKey,
     HoldsAndSignWithKey,
@@ -208,18 +229,6 @@ fn route_ecdsa_message(
     requested_subnet: &Option<SubnetId>,
     signing_must_be_enabled: EcdsaSubnetKind,
 ) -> Result<PrincipalId, ResolveDestinationError> {
-    fn format_keys<'a>(mut found_keys: impl Iterator<Item = &'a EcdsaKeyId>) -> String {
-        let mut keys = "[".to_string();
-        if let Some(key) = found_keys.next() {
-            write!(keys, "{}", key).unwrap();
-        }
-        for key in found_keys {
-            write!(keys, ", {}", key).unwrap();
-        }
-        keys.push(']');
-        keys
-    }
-
     match requested_subnet {
         Some(subnet_id) => match network_topology.subnets.get(subnet_id) {
             None => Err(ResolveDestinationError::EcdsaKeyError(format!(
@@ -273,6 +282,26 @@ fn route_ecdsa_message(
     }
 }
 
+/// Routes to the subnet holding the given vetKD key, and fails if no subnet
+/// holds it.
+fn route_vetkd_message(
+    key_id: &VetKdKeyId,
+    network_topology: &NetworkTopology,
+) -> Result<PrincipalId, ResolveDestinationError> {
+    let mut keys = BTreeSet::new();
+    for (subnet_id, topology) in &network_topology.subnets {
+        if topology.vetkd_keys_held.contains(key_id) {
+            return Ok((*subnet_id).get());
+        }
+        keys.extend(topology.vetkd_keys_held.iter().cloned());
+    }
+    Err(ResolveDestinationError::VetKdKeyError(format!(
+        "Requested vetKD key: {}, existing keys: {}",
+        key_id,
+        format_keys(keys.iter())
+    )))
+}
+
 fn route_bitcoin_message(
     network: BitcoinNetwork,
     network_topology: &NetworkTopology,
@@ -332,7 +361,7 @@ mod tests {
     use candid::Encode;
     use ic_base_types::RegistryVersion;
     use ic_ic00_types::{
-        ComputeInitialEcdsaDealingsArgs, EcdsaCurve, EcdsaKeyId, SignWithECDSAArgs,
+        ComputeInitialEcdsaDealingsArgs, EcdsaCurve, EcdsaKeyId, SignWithECDSAArgs, VetKdCurve,
     };
     use ic_replicated_state::SubnetTopology;
     use ic_test_utilities::types::ids::{canister_test_id, node_test_id, subnet_test_id};
@@ -560,4 +589,96 @@ mod tests {
             PrincipalId::new_subnet_test_id(0)
         )
     }
+
+    fn vetkd_key_id() -> VetKdKeyId {
+        VetKdKeyId {
+            curve: VetKdCurve::Bls12_381_G2,
+            name: "some_key".to_string(),
+        }
+    }
+
+    /// Subnet 1 holds the vetKD key.
+    fn network_with_vetkd_subnet() -> NetworkTopology {
+        let mut network_topology = network_with_ecdsa_subnets();
+        network_topology
+            .subnets
+            .get_mut(&subnet_test_id(1))
+            .unwrap()
+            .vetkd_keys_held
+            .insert(vetkd_key_id());
+        network_topology
+    }
+
+    #[test]
+    fn resolve_vetkd_encrypted_key_to_subnet_holding_key() {
+        let args = VetKdEncryptedKeyArgs {
+            derivation_path: vec![vec![0; 10]],
+            derivation_id: vec![1; 8],
+            key_id: vetkd_key_id(),
+            encryption_public_key: vec![2; 48],
+        };
+        assert_eq!(
+            resolve_destination(
+                &network_with_vetkd_subnet(),
+                &Ic00Method::VetKdEncryptedKey.to_string(),
+                &Encode!(&args).unwrap(),
+                subnet_test_id(2),
+            )
+            .unwrap(),
+            PrincipalId::new_subnet_test_id(1)
+        )
+    }
+
+    #[test]
+    fn resolve_vetkd_public_key_to_subnet_holding_key() {
+        let args = |canister_id| VetKdPublicKeyArgs {
+            canister_id,
+            derivation_path: vec![vec![0; 10]],
+            key_id: vetkd_key_id(),
+        };
+
+        for canister_id in [None, Some(canister_test_id(1))] {
+            assert_eq!(
+                resolve_destination(
+                    &network_with_vetkd_subnet(),
+                    &Ic00Method::VetKdPublicKey.to_string(),
+                    &Encode!(&args(canister_id)).unwrap(),
+                    subnet_test_id(2),
+                )
+                .unwrap(),
+                PrincipalId::new_subnet_test_id(1)
+            );
+        }
+    }
+
+    #[test]
+    fn resolve_vetkd_unknown_key_error() {
+        let unknown_key_id = VetKdKeyId {
+            curve: VetKdCurve::Bls12_381_G2,
+            name: "unknown_key".to_string(),
+        };
+        let args = VetKdEncryptedKeyArgs {
+            derivation_path: vec![vec![0; 10]],
+            derivation_id: vec![1; 8],
+            key_id: unknown_key_id.clone(),
+            encryption_public_key: vec![2; 48],
+        };
+        assert_matches!(
+            resolve_destination(
+                &network_with_vetkd_subnet(),
+                &Ic00Method::VetKdEncryptedKey.to_string(),
+                &Encode!(&args).unwrap(),
+                subnet_test_id(2),
+            )
+            .unwrap_err(),
+            ResolveDestinationError::VetKdKeyError(err) => assert_eq!(
+                err,
+                format!(
+                    "Requested vetKD key: {}, existing keys: [{}]",
+                    unknown_key_id,
+                    vetkd_key_id()
+                )
+            )
+        );
+    }
 }
@@ -13,6 +13,7 @@ use ic_config::{
     subnet_config::SubnetConfigs,
 };
 use ic_constants::SMALL_APP_SUBNET_MAX_SIZE;
+use ic_crypto_internal_types::sign::threshold_sig::public_key::bls12_381::PublicKeyBytes;
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_embedders::{wasm_utils::compile, WasmtimeEmbedder};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
@@ -25,7 +26,7 @@ use ic_execution_environment::{
 use ic_ic00_types::{
     CanisterIdRecord, CanisterInstallMode, CanisterSettingsArgs, CanisterStatusType, EcdsaKeyId,
     EmptyBlob, InstallCodeArgs, Method, Payload, ProvisionalCreateCanisterWithCyclesArgs,
-    UpdateSettingsArgs,
+    UpdateSettingsArgs, VetKdKeyId,
 };
 use ic_interfaces::{
     execution_environment::{
@@ -52,7 +53,10 @@ use ic_replicated_state::{
 use ic_replicated_state::{page_map::TestPageAllocatorFileDescriptorImpl, PageMap};
 use ic_system_api::InstructionLimits;
 use ic_types::{
-    crypto::{canister_threshold_sig::MasterEcdsaPublicKey, AlgorithmId},
+    crypto::{
+        canister_threshold_sig::MasterEcdsaPublicKey, threshold_sig::ThresholdSigPublicKey,
+        AlgorithmId,
+    },
     ingress::{IngressState, IngressStatus, WasmResult},
     messages::{
         AnonymousQuery, CallbackId, MessageId, RequestOrResponse, Response, UserQuery,
@@ -105,6 +109,7 @@ pub fn generate_subnets(
                 subnet_type,
                 subnet_features: SubnetFeatures::default(),
                 ecdsa_keys_held: BTreeSet::new(),
+                vetkd_keys_held: BTreeSet::new(),
             },
         );
     }
@@ -139,6 +144,7 @@ pub fn test_registry_settings() -> RegistryExecutionSettings {
         max_number_of_canisters: 0x2000,
         provisional_whitelist: ProvisionalWhitelist::Set(BTreeSet::new()),
         max_ecdsa_queue_size: 20,
+        max_vetkd_queue_size: 20,
         subnet_size: SMALL_APP_SUBNET_MAX_SIZE,
     }
 }
@@ -208,6 +214,7 @@ pub struct ExecutionTest {
     manual_execution: bool,
     caller_canister_id: Option<CanisterId>,
     ecdsa_subnet_public_keys: BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
+    vetkd_subnet_public_keys: BTreeMap<VetKdKeyId, ThresholdSigPublicKey>,
 
     // The actual implementation.
     exec_env: ExecutionEnvironment,
@@ -1011,6 +1018,7 @@ impl ExecutionTest {
             self.install_code_instruction_limits.clone(),
             &mut mock_random_number_generator(),
             &self.ecdsa_subnet_public_keys,
+            &self.vetkd_subnet_public_keys,
             &self.registry_settings,
             &mut round_limits,
         );
@@ -1398,6 +1406,8 @@ pub struct ExecutionTestBuilder {
     caller_canister_id: Option<CanisterId>,
     ecdsa_signature_fee: Option<Cycles>,
     ecdsa_key: Option<EcdsaKeyId>,
+    vetkd_fee: Option<Cycles>,
+    vetkd_key: Option<VetKdKeyId>,
     instruction_limit: NumInstructions,
     slice_instruction_limit: NumInstructions,
     install_code_instruction_limit: NumInstructions,
@@ -1447,6 +1457,8 @@ impl Default for ExecutionTestBuilder {
             caller_canister_id: None,
             ecdsa_signature_fee: None,
             ecdsa_key: None,
+            vetkd_fee: None,
+            vetkd_key: None,
             instruction_limit: scheduler_config.max_instructions_per_message,
             slice_instruction_limit: scheduler_config.max_instructions_per_slice,
             install_code_instruction_limit: scheduler_config.max_instructions_per_install_code,
@@ -1541,6 +1553,20 @@ impl ExecutionTestBuilder {
         }
     }
 
+    pub fn with_vetkd_fee(self, vetkd_fee: u128) -> Self {
+        Self {
+            vetkd_fee: Some(Cycles::new(vetkd_fee)),
+            ..self
+        }
+    }
+
+    pub fn with_vetkd_key(self, vetkd_key: VetKdKeyId) -> Self {
+        Self {
+            vetkd_key: Some(vetkd_key),
+            ..self
+        }
+    }
+
     pub fn with_instruction_limit(self, limit: u64) -> Self {
         Self {
             instruction_limit: NumInstructions::from(limit),
@@ -1752,6 +1778,9 @@ impl ExecutionTestBuilder {
         if let Some(ecdsa_signature_fee) = self.ecdsa_signature_fee {
             config.ecdsa_signature_fee = ecdsa_signature_fee;
         }
+        if let Some(vetkd_fee) = self.vetkd_fee {
+            config.vetkd_fee = vetkd_fee;
+        }
         if let Some(ecdsa_key) = &self.ecdsa_key {
             state
                 .metadata
@@ -1780,6 +1809,21 @@ impl ExecutionTestBuilder {
                 )
             })
             .collect();
+        if let Some(vetkd_key) = &self.vetkd_key {
+            state
+                .metadata
+                .network_topology
+                .subnets
+                .get_mut(&self.own_subnet_id)
+                .unwrap()
+                .vetkd_keys_held
+                .insert(vetkd_key.clone());
+        }
+        let vetkd_subnet_public_keys = self
+            .vetkd_key
+            .into_iter()
+            .map(|key| (key, ThresholdSigPublicKey::from(PublicKeyBytes([1; 96]))))
+            .collect();
         let cycles_account_manager = Arc::new(CyclesAccountManager::new(
             self.instruction_limit,
             self.subnet_type,
@@ -1899,6 +1943,7 @@ impl ExecutionTestBuilder {
             ingress_history_writer,
             manual_execution: self.manual_execution,
             ecdsa_subnet_public_keys,
+            vetkd_subnet_public_keys,
             log: self.log,
             checkpoint_files: vec![],
         }
@@ -172,6 +172,8 @@ pub fn test_subnet_record() -> SubnetRecord {
         ssh_readonly_access: vec![],
         ssh_backup_access: vec![],
         ecdsa_config: None,
+        adaptive_notarization_delays: false,
+        vetkd_config: None,
     }
 }
 
@@ -1,6 +1,6 @@
 use async_trait::async_trait;
 use ic_crypto_tls_interfaces::{
-    AllowedClients, AuthenticatedPeer, TlsClientHandshakeError, TlsHandshake,
+    rustls, AllowedClients, AuthenticatedPeer, TlsClientHandshakeError, TlsHandshake,
     TlsServerHandshakeError, TlsStream,
 };
 use ic_types::{NodeId, RegistryVersion};
@@ -49,4 +49,27 @@ impl TlsHandshake for FakeTlsHandshake {
     ) -> Result<Box<dyn TlsStream>, TlsClientHandshakeError> {
         unimplemented!()
     }
+
+    fn tls_server_config(
+        &self,
+        _allowed_clients: AllowedClients,
+        _registry_version: RegistryVersion,
+    ) -> Result<rustls::ServerConfig, TlsServerHandshakeError> {
+        unimplemented!()
+    }
+
+    fn tls_client_config(
+        &self,
+        _server: NodeId,
+        _registry_version: RegistryVersion,
+    ) -> Result<rustls::ClientConfig, TlsClientHandshakeError> {
+        unimplemented!()
+    }
+
+    fn authenticated_peer_from_client_certs(
+        &self,
+        _client_certs: &[rustls::Certificate],
+    ) -> Result<AuthenticatedPeer, TlsServerHandshakeError> {
+        unimplemented!()
+    }
 }
@@ -831,6 +831,7 @@ prop_compose! {
             end,
             signals_end,
             reject_signals,
+            capacity_bytes: None,
         }
     }
 }
@@ -13,6 +13,7 @@ impl Default for StreamHeaderBuilder {
             end: StreamIndex::from(0),
             signals_end: StreamIndex::from(0),
             reject_signals: VecDeque::default(),
+            capacity_bytes: None,
         })
     }
 }
@@ -37,6 +38,11 @@ impl StreamHeaderBuilder {
         self
     }
 
+    pub fn capacity_bytes(mut self, capacity_bytes: Option<u64>) -> Self {
+        self.0.capacity_bytes = capacity_bytes;
+        self
+    }
+
     /// Returns the built StreamHeader.
     pub fn build(self) -> StreamHeader {
         self.0
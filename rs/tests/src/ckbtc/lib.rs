@@ -183,6 +183,7 @@ fn empty_subnet_update() -> UpdateSubnetPayload {
         initial_notary_delay_millis: None,
         dkg_interval_length: None,
         dkg_dealings_per_block: None,
+        adaptive_notarization_delays: None,
         max_artifact_streams_per_peer: None,
         max_chunk_wait_ms: None,
         max_duplicity: None,
@@ -202,6 +203,8 @@ fn empty_subnet_update() -> UpdateSubnetPayload {
         ecdsa_config: None,
         ecdsa_key_signing_enable: None,
         ecdsa_key_signing_disable: None,
+        ecdsa_key_reshare_requests: None,
+        vetkd_config: None,
         max_number_of_canisters: None,
         ssh_readonly_access: None,
         ssh_backup_access: None,
@@ -295,6 +298,8 @@ pub(crate) async fn install_minter(
         max_time_in_queue_nanos,
         min_confirmations: Some(BTC_MIN_CONFIRMATIONS),
         mode: Mode::GeneralAvailability,
+        utxo_consolidation_threshold: None,
+        utxo_consolidation_max_fee_per_vbyte: None,
     };
 
     let minter_arg = MinterArg::Init(args);
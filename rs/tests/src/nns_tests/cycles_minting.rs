@@ -1205,6 +1205,7 @@ impl UserHandle {
             block_index: block,
             controller: *controller_id,
             subnet_type,
+            subnet_selection: None,
         };
 
         let result: Result<CanisterId, NotifyError> = self
@@ -133,6 +133,7 @@ pub(crate) fn get_updatesubnetpayload_with_keys(
         initial_notary_delay_millis: None,
         dkg_interval_length: None,
         dkg_dealings_per_block: None,
+        adaptive_notarization_delays: None,
         max_artifact_streams_per_peer: None,
         max_chunk_wait_ms: None,
         max_duplicity: None,
@@ -152,6 +153,8 @@ pub(crate) fn get_updatesubnetpayload_with_keys(
         ecdsa_config: None,
         ecdsa_key_signing_enable: None,
         ecdsa_key_signing_disable: None,
+        ecdsa_key_reshare_requests: None,
+        vetkd_config: None,
         max_number_of_canisters: None,
         ssh_readonly_access: readonly_keys,
         ssh_backup_access: backup_keys,
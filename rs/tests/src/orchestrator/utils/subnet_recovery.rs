@@ -401,6 +401,7 @@ pub(crate) fn disable_ecdsa_on_subnet(
     let disable_signing_payload = UpdateSubnetPayload {
         subnet_id,
         ecdsa_key_signing_disable: Some(vec![make_key(KEY_ID1)]),
+        ecdsa_key_reshare_requests: None,
         ..empty_subnet_update()
     };
     block_on(execute_update_subnet_proposal(
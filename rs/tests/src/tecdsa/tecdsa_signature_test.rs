@@ -89,6 +89,7 @@ pub(crate) fn empty_subnet_update() -> UpdateSubnetPayload {
         initial_notary_delay_millis: None,
         dkg_interval_length: None,
         dkg_dealings_per_block: None,
+        adaptive_notarization_delays: None,
         max_artifact_streams_per_peer: None,
         max_chunk_wait_ms: None,
         max_duplicity: None,
@@ -108,6 +109,8 @@ pub(crate) fn empty_subnet_update() -> UpdateSubnetPayload {
         ecdsa_config: None,
         ecdsa_key_signing_enable: None,
         ecdsa_key_signing_disable: None,
+        ecdsa_key_reshare_requests: None,
+        vetkd_config: None,
         max_number_of_canisters: None,
         ssh_readonly_access: None,
         ssh_backup_access: None,
@@ -741,6 +744,8 @@ pub fn test_threshold_ecdsa_life_cycle(env: TestEnv) {
         let disable_signing_payload = UpdateSubnetPayload {
             subnet_id: app_subnet.subnet_id,
             ecdsa_key_signing_disable: Some(vec![make_key(KEY_ID2)]),
+            ecdsa_key_reshare_requests: None,
+            vetkd_config: None,
             ..empty_subnet_update()
         };
         execute_update_subnet_proposal(&governance, disable_signing_payload).await;
@@ -38,7 +38,7 @@ enum TransportTlsHandshakeError {
 const DEFAULT_CHANNEL_ID: usize = 0;
 
 /// Time to wait before retrying an unsuccessful connection attempt
-const CONNECT_RETRY_SECONDS: u64 = 3;
+pub(crate) const CONNECT_RETRY_SECONDS: u64 = 3;
 
 /// Time to wait for the TLS handshake (for both client/server sides)
 const TLS_HANDSHAKE_TIMEOUT_SECONDS: u64 = 30;
@@ -52,9 +52,34 @@ impl TransportImpl {
     /// Stops connection to a peer
     pub(crate) fn stop_peer_connection(&self, peer_id: &NodeId) {
         self.allowed_clients.blocking_write().remove(peer_id);
+        if self.config.use_quic {
+            self.update_quic_server_config();
+        }
         self.peer_map.blocking_write().remove(peer_id);
     }
 
+    /// Returns the channels that are set up for each peer. Over TCP, all
+    /// messages share a single channel.
+    pub(crate) fn channel_ids(&self) -> Vec<TransportChannelId> {
+        if self.config.use_quic {
+            (0..self.config.max_streams)
+                .map(TransportChannelId::from)
+                .collect()
+        } else {
+            // TODO: P2P-514
+            vec![TransportChannelId::from(DEFAULT_CHANNEL_ID)]
+        }
+    }
+
+    /// Returns the index of the send queue for messages on the given channel
+    pub(crate) fn send_queue_index(&self, channel_id: TransportChannelId) -> usize {
+        if self.config.use_quic {
+            channel_id.get()
+        } else {
+            DEFAULT_CHANNEL_ID
+        }
+    }
+
     /// Starts connection(s) to a peer and initializes the corresponding data
     /// structures and tasks
     pub(crate) fn start_peer_connection(
@@ -69,6 +94,9 @@ impl TransportImpl {
             self.allowed_clients.blocking_write().insert(*peer_id);
         }
         *self.registry_version.blocking_write() = registry_version;
+        if self.config.use_quic {
+            self.update_quic_server_config();
+        }
         let mut peer_map = self.peer_map.blocking_write();
         if peer_map.get(peer_id).is_some() {
             return;
@@ -76,11 +104,12 @@ impl TransportImpl {
 
         // TODO: P2P-514
         let channel_id = TransportChannelId::from(DEFAULT_CHANNEL_ID);
+        let channel_ids = self.channel_ids();
         let peer_label = get_peer_label(&peer_addr.ip().to_string(), peer_id);
         let peer_state = match role {
             ConnectionRole::Server => PeerState::new(
                 self.log.clone(),
-                channel_id,
+                &channel_ids,
                 peer_label,
                 ConnectionState::Listening,
                 self.config.send_queue_size,
@@ -88,14 +117,18 @@ impl TransportImpl {
                 self.control_plane_metrics.clone(),
             ),
             ConnectionRole::Client => {
-                let connecting_task = self.spawn_connect_task(channel_id, *peer_id, peer_addr);
+                let connecting_task = if self.config.use_quic {
+                    self.spawn_quic_connect_task(*peer_id, peer_addr)
+                } else {
+                    self.spawn_connect_task(channel_id, *peer_id, peer_addr)
+                };
                 let connecting_state = Connecting {
                     peer_addr,
                     connecting_task,
                 };
                 PeerState::new(
                     self.log.clone(),
-                    channel_id,
+                    &channel_ids,
                     peer_label,
                     ConnectionState::Connecting(connecting_state),
                     self.config.send_queue_size,
@@ -206,7 +239,7 @@ impl TransportImpl {
                             if let Ok(connected_state) = create_connected_state(
                                 peer_id,
                                 channel_id,
-                                peer_state.send_queue.get_reader(),
+                                peer_state.send_queues[channel_id.get()].get_reader(),
                                 ConnectionRole::Server,
                                 peer_addr,
                                 tls_stream,
@@ -294,7 +327,7 @@ impl TransportImpl {
                                 if let Ok(connected_state) = create_connected_state(
                                     peer_id,
                                     channel_id,
-                                    peer_state.send_queue.get_reader(),
+                                    peer_state.send_queues[channel_id.get()].get_reader(),
                                     ConnectionRole::Client,
                                     peer_addr,
                                     tls_stream,
@@ -395,7 +428,11 @@ impl TransportImpl {
             ConnectionState::Listening
         } else {
             // reconnect if we have a listener
-            let connecting_task = self.spawn_connect_task(channel_id, peer_id, socket_addr);
+            let connecting_task = if self.config.use_quic {
+                self.spawn_quic_connect_task(peer_id, socket_addr)
+            } else {
+                self.spawn_connect_task(channel_id, peer_id, socket_addr)
+            };
             let connecting_state = Connecting {
                 peer_addr: socket_addr,
                 connecting_task,
@@ -469,10 +506,13 @@ impl TransportImpl {
         // Creating the listeners requres that we are within a tokio runtime context.
         let _rt_enter_guard = self.rt_handle.enter();
         let server_addr = SocketAddr::new(self.node_ip, self.config.listening_port);
-        let tcp_listener = start_tcp_listener(server_addr);
-
-        let channel_id = TransportChannelId::from(DEFAULT_CHANNEL_ID);
-        let accept_task = self.spawn_accept_task(channel_id, tcp_listener);
+        let accept_task = if self.config.use_quic {
+            self.init_quic_endpoint(server_addr)
+        } else {
+            let tcp_listener = start_tcp_listener(server_addr);
+            let channel_id = TransportChannelId::from(DEFAULT_CHANNEL_ID);
+            self.spawn_accept_task(channel_id, tcp_listener)
+        };
         *self.accept_port.blocking_lock() = Some(ServerPortState { accept_task });
         *self.event_handler.blocking_lock() = Some(event_handler);
     }
@@ -22,16 +22,18 @@ use crate::{
     types::{
         Connected, ConnectionRole, H2Reader, H2Writer, SendQueueReader, StreamReadError,
         StreamState, TransportHeader, TransportImpl, H2_FRAME_SIZE, H2_WINDOW_SIZE,
-        TRANSPORT_FLAGS_IS_HEARTBEAT, TRANSPORT_HEADER_SIZE,
+        QUIC_STREAM_HEADER_SIZE, TRANSPORT_FLAGS_IS_HEARTBEAT, TRANSPORT_HEADER_SIZE,
     },
     utils::get_peer_label,
 };
+use futures::StreamExt;
 use ic_base_types::NodeId;
 use ic_crypto_tls_interfaces::TlsStream;
 use ic_interfaces_transport::{
     TransportChannelId, TransportEvent, TransportEventHandler, TransportMessage, TransportPayload,
 };
 use ic_logger::{info, warn};
+use std::collections::BTreeMap;
 use std::convert::TryInto;
 use std::net::SocketAddr;
 use std::sync::Weak;
@@ -65,6 +67,9 @@ const TRANSPORT_HEARTBEAT_SEND_INTERVAL_MS: u64 = 200;
 /// Heartbeat wait interval (timeout on receiver side)
 const TRANSPORT_HEARTBEAT_WAIT_INTERVAL_MS: u64 = 5000;
 
+/// Time to wait for the peer to open the QUIC streams of all channels
+const QUIC_STREAMS_TIMEOUT_SECONDS: u64 = 10;
+
 const READ_RESULT_ERROR: &str = "error";
 const READ_RESULT_HEARTBEAT: &str = "heartbeat";
 const READ_RESULT_MESSAGE: &str = "message";
@@ -368,11 +373,12 @@ pub(crate) async fn create_connected_state(
 
         Ok(Connected {
             peer_addr,
-            stream_state: StreamState {
+            stream_states: vec![StreamState {
                 read_task,
                 write_task,
-            },
+            }],
             h2_conn: None,
+            quic_conn: None,
             role,
         })
     } else {
@@ -471,11 +477,12 @@ pub(crate) async fn create_connected_state_for_h2_client(
 
     Ok(Connected {
         peer_addr,
-        stream_state: StreamState {
+        stream_states: vec![StreamState {
             read_task,
             write_task,
-        },
+        }],
         h2_conn: Some(h2_conn),
+        quic_conn: None,
         role: ConnectionRole::Client,
     })
 }
@@ -543,11 +550,118 @@ pub(crate) async fn create_connected_state_for_h2_server(
 
     Ok(Connected {
         peer_addr,
-        stream_state: StreamState {
+        stream_states: vec![StreamState {
             read_task,
             write_task,
-        },
+        }],
         h2_conn: Some(h2_conn),
+        quic_conn: None,
         role: ConnectionRole::Server,
     })
 }
+
+/// Handle QUIC connection setup. Opens one outgoing stream per channel,
+/// accepts the peer's stream for every channel and starts the flow read and
+/// write tasks on each of them.
+pub(crate) async fn create_connected_state_for_quic(
+    peer_id: NodeId,
+    send_queue_readers: Vec<(TransportChannelId, Box<dyn SendQueueReader + Send + Sync>)>,
+    role: ConnectionRole,
+    peer_addr: SocketAddr,
+    new_connection: quinn::NewConnection,
+    event_handler: TransportEventHandler,
+    data_plane_metrics: DataPlaneMetrics,
+    weak_self: Weak<TransportImpl>,
+    rt_handle: tokio::runtime::Handle,
+) -> Result<Connected, BoxError> {
+    let quinn::NewConnection {
+        connection,
+        mut uni_streams,
+        ..
+    } = new_connection;
+
+    let mut send_streams = Vec::with_capacity(send_queue_readers.len());
+    for (channel_id, _) in send_queue_readers.iter() {
+        let mut send_stream = connection.open_uni().await?;
+        send_stream
+            .write_all(&pack_quic_stream_header(*channel_id))
+            .await?;
+        send_streams.push(send_stream);
+    }
+
+    // The peer opens its streams concurrently, in no particular order.
+    let mut recv_streams = BTreeMap::new();
+    let accept_streams = async {
+        while recv_streams.len() < send_queue_readers.len() {
+            let mut recv_stream = uni_streams
+                .next()
+                .await
+                .ok_or_else(|| BoxError::from("connection closed"))??;
+            let mut header_buffer = [0u8; QUIC_STREAM_HEADER_SIZE];
+            recv_stream.read_exact(&mut header_buffer).await?;
+            let channel_id = unpack_quic_stream_header(header_buffer);
+            if !send_queue_readers.iter().any(|(id, _)| *id == channel_id) {
+                return Err(BoxError::from(format!(
+                    "unexpected stream for channel {}",
+                    channel_id
+                )));
+            }
+            recv_streams.insert(channel_id, recv_stream);
+        }
+        Ok(())
+    };
+    tokio::time::timeout(
+        Duration::from_secs(QUIC_STREAMS_TIMEOUT_SECONDS),
+        accept_streams,
+    )
+    .await??;
+
+    let mut stream_states = Vec::with_capacity(send_streams.len());
+    for ((channel_id, send_queue_reader), send_stream) in
+        send_queue_readers.into_iter().zip(send_streams)
+    {
+        let recv_stream = recv_streams
+            .remove(&channel_id)
+            .expect("A stream was accepted for every channel");
+        let write_task = spawn_write_task(
+            peer_id,
+            channel_id,
+            send_queue_reader,
+            send_stream,
+            data_plane_metrics.clone(),
+            weak_self.clone(),
+            rt_handle.clone(),
+        );
+        let read_task = spawn_read_task(
+            peer_id,
+            channel_id,
+            event_handler.clone(),
+            recv_stream,
+            data_plane_metrics.clone(),
+            weak_self.clone(),
+            rt_handle.clone(),
+        );
+        stream_states.push(StreamState {
+            read_task,
+            write_task,
+        });
+    }
+
+    Ok(Connected {
+        peer_addr,
+        stream_states,
+        h2_conn: None,
+        quic_conn: Some(connection),
+        role,
+    })
+}
+
+/// Create the header that identifies the channel of a QUIC stream.
+fn pack_quic_stream_header(channel_id: TransportChannelId) -> [u8; QUIC_STREAM_HEADER_SIZE] {
+    (channel_id.get() as u32).to_le_bytes()
+}
+
+/// Read the channel id from the header of a QUIC stream.
+fn unpack_quic_stream_header(data: [u8; QUIC_STREAM_HEADER_SIZE]) -> TransportChannelId {
+    TransportChannelId::from(u32::from_le_bytes(data) as usize)
+}
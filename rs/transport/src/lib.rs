@@ -31,10 +31,16 @@
 //! messages (artifact chunks), for ingress manager, consensus (incl DKG and
 //! certification) and state sync. Thus, Transport has to handle 3 x 3 flows per
 //! peer for Gossip.
+//!
+//! Peers are connected over TCP/TLS by default, with all flows sharing one
+//! connection. With `TransportConfig::use_quic`, peers are connected over QUIC
+//! instead and every flow gets its own stream, so that flows do not block each
+//! other.
 
 mod control_plane;
 mod data_plane;
 mod metrics;
+mod quic;
 pub mod transport;
 mod types;
 mod utils;
@@ -0,0 +1,452 @@
+//! QUIC connection management.
+//!
+//! When `TransportConfig::use_quic` is set, this module takes the place of
+//! the TCP specific parts of the control plane. Each pair of peers is
+//! connected by a single QUIC connection, authenticated with the node TLS
+//! certificates the same way as the TCP/TLS connections. Every channel is
+//! then carried on its own unidirectional QUIC stream in each direction (see
+//! `create_connected_state_for_quic()`). QUIC flow controls these streams
+//! independently, so a channel moving large artifacts, e.g. state sync
+//! chunks, cannot hold back the messages on the other channels.
+//!
+//! Connection roles, reconnects and the connection state machine are shared
+//! with the TCP implementation.
+
+use crate::{
+    control_plane::CONNECT_RETRY_SECONDS,
+    data_plane::create_connected_state_for_quic,
+    metrics::{IntGaugeResource, STATUS_SUCCESS},
+    types::{ConnectionRole, ConnectionState, TransportImpl},
+};
+use futures::StreamExt;
+use ic_base_types::NodeId;
+use ic_crypto_tls_interfaces::{rustls, AllowedClients, AuthenticatedPeer};
+use ic_interfaces_transport::TransportEvent;
+use ic_logger::warn;
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+use strum::AsRefStr;
+use tokio::{task::JoinHandle, time::sleep};
+use tower::Service;
+
+#[derive(Debug, AsRefStr)]
+#[strum(serialize_all = "snake_case")]
+enum QuicConnectionError {
+    DeadlineExceeded,
+    EndpointNotReady,
+    TlsConfig(String),
+    Connection(String),
+    MalformedPeerCertificate(String),
+}
+
+/// Time to wait for the QUIC handshake (for both client/server sides)
+const QUIC_HANDSHAKE_TIMEOUT_SECONDS: u64 = 30;
+
+/// The application protocol negotiated on transport QUIC connections
+const QUIC_ALPN_PROTOCOL: &[u8] = b"ic-transport";
+
+/// Peers are authenticated by their node ID, so the server name is never
+/// checked. It only has to be a valid DNS name.
+const QUIC_SERVER_NAME: &str = "domain.is-irrelevant-as-hostname-verification-is.disabled";
+
+const QUIC_CONNECT_TASK_NAME: &str = "quic_connect";
+const QUIC_ACCEPT_TASK_NAME: &str = "quic_accept";
+const TRANSITION_FROM_QUIC_ACCEPT_TASK_NAME: &str = "transition_from_quic_accept";
+
+impl TransportImpl {
+    /// Binds the QUIC endpoint used for both incoming and outgoing
+    /// connections, and starts the task accepting incoming connections.
+    pub(crate) fn init_quic_endpoint(&self, server_addr: SocketAddr) -> JoinHandle<()> {
+        let (endpoint, incoming) = quinn::Endpoint::builder()
+            .bind(&server_addr)
+            .unwrap_or_else(|err| {
+                panic!(
+                    "Could not start QUIC endpoint at addr = {}: {:?}",
+                    server_addr, err
+                )
+            });
+        *self.quic_endpoint.blocking_lock() = Some(endpoint);
+        self.update_quic_server_config();
+        self.spawn_quic_accept_task(incoming)
+    }
+
+    /// Rebuilds the server configuration of the QUIC endpoint from the
+    /// current allowed clients and registry version. Only connections that
+    /// are accepted afterwards are affected.
+    pub(crate) fn update_quic_server_config(&self) {
+        let endpoint = match self.quic_endpoint.blocking_lock().as_ref() {
+            Some(endpoint) => endpoint.clone(),
+            None => return,
+        };
+        let registry_version = *self.registry_version.blocking_read();
+        let current_allowed_clients = self.allowed_clients.blocking_read().clone();
+        let allowed_clients = match AllowedClients::new_with_nodes(current_allowed_clients) {
+            Ok(allowed_clients) => allowed_clients,
+            // No peer is expected to connect to us.
+            Err(_) => {
+                endpoint.set_server_config(None);
+                return;
+            }
+        };
+        match self
+            .crypto
+            .tls_server_config(allowed_clients, registry_version)
+        {
+            Ok(tls_config) => endpoint.set_server_config(Some(quic_server_config(tls_config))),
+            Err(err) => warn!(
+                self.log,
+                "Quic::update_quic_server_config(): failed to create TLS server config: \
+                error = {:?}, registry_version = {:?}",
+                err,
+                registry_version,
+            ),
+        }
+    }
+
+    /// Starts the async task to accept the incoming QUIC connections.
+    fn spawn_quic_accept_task(&self, mut incoming: quinn::Incoming) -> JoinHandle<()> {
+        let weak_self = self.weak_self.read().unwrap().clone();
+        let rt_handle = self.rt_handle.clone();
+        let async_tasks_gauge_vec = self.control_plane_metrics.async_tasks.clone();
+        self.rt_handle.spawn(async move {
+            let task_gauge = async_tasks_gauge_vec.with_label_values(&[QUIC_ACCEPT_TASK_NAME]);
+            let _gauge_guard = IntGaugeResource::new(task_gauge);
+            while let Some(connecting) = incoming.next().await {
+                // If the TransportImpl has been deleted, abort.
+                let arc_self = match weak_self.upgrade() {
+                    Some(arc_self) => arc_self,
+                    _ => return,
+                };
+                rt_handle.spawn(async move {
+                    let task_gauge = arc_self
+                        .control_plane_metrics
+                        .async_tasks
+                        .with_label_values(&[TRANSITION_FROM_QUIC_ACCEPT_TASK_NAME]);
+                    let _gauge_guard = IntGaugeResource::new(task_gauge);
+                    let peer_addr = connecting.remote_address();
+                    let (peer_id, new_connection) = match arc_self.quic_accept(connecting).await {
+                        Ok((peer_id, new_connection)) => {
+                            arc_self
+                                .control_plane_metrics
+                                .tls_handshakes
+                                .with_label_values(&[
+                                    ConnectionRole::Server.as_ref(),
+                                    STATUS_SUCCESS,
+                                ])
+                                .inc();
+                            (peer_id, new_connection)
+                        }
+                        Err(err) => {
+                            arc_self
+                                .control_plane_metrics
+                                .tls_handshakes
+                                .with_label_values(&[ConnectionRole::Server.as_ref(), err.as_ref()])
+                                .inc();
+                            warn!(
+                                arc_self.log,
+                                "Quic::spawn_quic_accept_task(): quic_accept failed: error = {:?}, \
+                                peer_addr = {:?}",
+                                err,
+                                peer_addr,
+                            );
+                            return;
+                        }
+                    };
+                    arc_self
+                        .on_quic_connected(
+                            peer_id,
+                            ConnectionRole::Server,
+                            peer_addr,
+                            new_connection,
+                        )
+                        .await;
+                });
+            }
+        })
+    }
+
+    /// Spawn a task that tries to connect to a peer over QUIC (forever, or
+    /// until connection is established or peer is removed)
+    pub(crate) fn spawn_quic_connect_task(
+        &self,
+        peer_id: NodeId,
+        peer_addr: SocketAddr,
+    ) -> JoinHandle<()> {
+        let weak_self = self.weak_self.read().unwrap().clone();
+        let async_tasks_gauge_vec = self.control_plane_metrics.async_tasks.clone();
+        self.rt_handle.spawn(async move {
+            let gauge = async_tasks_gauge_vec.with_label_values(&[QUIC_CONNECT_TASK_NAME]);
+            let _raii_gauge_vec = IntGaugeResource::new(gauge);
+
+            let mut retries: u32 = 0;
+            // If the TransportImpl has been deleted, exit the loop and the task.
+            while let Some(arc_self) = weak_self.upgrade() {
+                retries += 1;
+                match arc_self.quic_connect(peer_id, peer_addr).await {
+                    Ok(new_connection) => {
+                        arc_self
+                            .control_plane_metrics
+                            .tls_handshakes
+                            .with_label_values(&[ConnectionRole::Client.as_ref(), STATUS_SUCCESS])
+                            .inc();
+                        if arc_self
+                            .on_quic_connected(
+                                peer_id,
+                                ConnectionRole::Client,
+                                peer_addr,
+                                new_connection,
+                            )
+                            .await
+                        {
+                            // Stop this task since we successfully spawned the read/write tasks
+                            return;
+                        }
+                    }
+                    Err(err) => {
+                        arc_self
+                            .control_plane_metrics
+                            .tls_handshakes
+                            .with_label_values(&[ConnectionRole::Client.as_ref(), err.as_ref()])
+                            .inc();
+                        warn!(
+                            arc_self.log,
+                            "Quic::spawn_quic_connect_task(): quic_connect failed: error = {:?}, \
+                            peer = {:?}/{:?}, retries = {}",
+                            err,
+                            peer_id,
+                            peer_addr,
+                            retries,
+                        );
+                    }
+                }
+                sleep(Duration::from_secs(CONNECT_RETRY_SECONDS)).await;
+            }
+        })
+    }
+
+    /// Performs the client side QUIC handshake with the peer
+    async fn quic_connect(
+        &self,
+        peer_id: NodeId,
+        peer_addr: SocketAddr,
+    ) -> Result<quinn::NewConnection, QuicConnectionError> {
+        let endpoint = self
+            .quic_endpoint
+            .lock()
+            .await
+            .clone()
+            .ok_or(QuicConnectionError::EndpointNotReady)?;
+        let registry_version = *self.registry_version.read().await;
+        let tls_config = self
+            .crypto
+            .tls_client_config(peer_id, registry_version)
+            .map_err(|err| QuicConnectionError::TlsConfig(format!("{:?}", err)))?;
+        let connecting = endpoint
+            .connect_with(quic_client_config(tls_config), &peer_addr, QUIC_SERVER_NAME)
+            .map_err(|err| QuicConnectionError::Connection(format!("{:?}", err)))?;
+        match tokio::time::timeout(
+            Duration::from_secs(QUIC_HANDSHAKE_TIMEOUT_SECONDS),
+            connecting,
+        )
+        .await
+        {
+            Err(_) => Err(QuicConnectionError::DeadlineExceeded),
+            Ok(Ok(new_connection)) => Ok(new_connection),
+            Ok(Err(err)) => Err(QuicConnectionError::Connection(format!("{:?}", err))),
+        }
+    }
+
+    /// Performs the server side QUIC handshake processing and determines
+    /// the authenticated peer
+    async fn quic_accept(
+        &self,
+        connecting: quinn::Connecting,
+    ) -> Result<(NodeId, quinn::NewConnection), QuicConnectionError> {
+        let new_connection = match tokio::time::timeout(
+            Duration::from_secs(QUIC_HANDSHAKE_TIMEOUT_SECONDS),
+            connecting,
+        )
+        .await
+        {
+            Err(_) => Err(QuicConnectionError::DeadlineExceeded),
+            Ok(Ok(new_connection)) => Ok(new_connection),
+            Ok(Err(err)) => Err(QuicConnectionError::Connection(format!("{:?}", err))),
+        }?;
+        let client_certs: Vec<rustls::Certificate> = new_connection
+            .connection
+            .peer_identity()
+            .map(|cert_chain| cert_chain.iter().cloned().collect())
+            .unwrap_or_default();
+        let AuthenticatedPeer::Node(peer_id) = self
+            .crypto
+            .authenticated_peer_from_client_certs(&client_certs)
+            .map_err(|err| QuicConnectionError::MalformedPeerCertificate(format!("{:?}", err)))?;
+        Ok((peer_id, new_connection))
+    }
+
+    /// Moves the peer to the connected state once the QUIC connection is
+    /// established. Returns false if the connection was not used, e.g.
+    /// because the peer was removed or is already connected.
+    async fn on_quic_connected(
+        &self,
+        peer_id: NodeId,
+        role: ConnectionRole,
+        peer_addr: SocketAddr,
+        new_connection: quinn::NewConnection,
+    ) -> bool {
+        let peer_map = self.peer_map.read().await;
+        let peer_state_mu = match peer_map.get(&peer_id) {
+            Some(peer_state) => peer_state,
+            None => return false,
+        };
+        let mut peer_state = peer_state_mu.write().await;
+        if peer_state.get_connected().is_some() {
+            // TODO: P2P-516
+            return false;
+        }
+        let mut event_handler = match self.event_handler.lock().await.as_ref() {
+            Some(event_handler) => event_handler.clone(),
+            None => return false,
+        };
+        let send_queue_readers = self
+            .channel_ids()
+            .into_iter()
+            .zip(peer_state.send_queues.iter_mut())
+            .map(|(channel_id, send_queue)| (channel_id, send_queue.get_reader()))
+            .collect();
+        let weak_self = self.weak_self.read().unwrap().clone();
+        match create_connected_state_for_quic(
+            peer_id,
+            send_queue_readers,
+            role,
+            peer_addr,
+            new_connection,
+            event_handler.clone(),
+            self.data_plane_metrics.clone(),
+            weak_self,
+            self.rt_handle.clone(),
+        )
+        .await
+        {
+            Ok(connected_state) => {
+                event_handler
+                    .call(TransportEvent::PeerUp(peer_id))
+                    .await
+                    .expect("Can't panic on infallible");
+                peer_state.update(ConnectionState::Connected(connected_state));
+                true
+            }
+            Err(err) => {
+                warn!(
+                    self.log,
+                    "Quic::on_quic_connected(): failed to set up the channel streams: \
+                    error = {:?}, peer = {:?}/{:?}, role = {:?}",
+                    err,
+                    peer_id,
+                    peer_addr,
+                    role,
+                );
+                false
+            }
+        }
+    }
+}
+
+/// Wraps the node's TLS server configuration for use by a QUIC endpoint
+fn quic_server_config(mut tls_config: rustls::ServerConfig) -> quinn::ServerConfig {
+    tls_config.alpn_protocols = vec![QUIC_ALPN_PROTOCOL.to_vec()];
+    let mut server_config = quinn::ServerConfig::default();
+    server_config.crypto = Arc::new(tls_config);
+    server_config
+}
+
+/// Wraps the node's TLS client configuration for use by a QUIC endpoint
+fn quic_client_config(mut tls_config: rustls::ClientConfig) -> quinn::ClientConfig {
+    tls_config.alpn_protocols = vec![QUIC_ALPN_PROTOCOL.to_vec()];
+    quinn::ClientConfig {
+        crypto: Arc::new(tls_config),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_crypto_tls_interfaces::TlsHandshake;
+    use ic_transport_test_utils::{
+        temp_crypto_component_with_tls_keys_in_registry, RegistryAndDataProvider, NODE_ID_1,
+        NODE_ID_2, REG_V1,
+    };
+    use std::collections::BTreeSet;
+
+    const MESSAGE: &[u8] = b"message sent over a QUIC stream";
+
+    // Test scenario: Two QUIC endpoints on the loopback interface connect using
+    // the node TLS configurations and the client sends a message on a
+    // unidirectional stream.
+    // Test expectation: The server receives the message and authenticates the
+    // client as its node.
+    #[test]
+    fn quic_loopback_send_receive() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let registry_and_data = RegistryAndDataProvider::new();
+        let server_crypto =
+            temp_crypto_component_with_tls_keys_in_registry(&registry_and_data, NODE_ID_1);
+        let client_crypto =
+            temp_crypto_component_with_tls_keys_in_registry(&registry_and_data, NODE_ID_2);
+        registry_and_data.registry.update_to_latest_version();
+
+        let allowed_clients = AllowedClients::new_with_nodes(BTreeSet::from([NODE_ID_2])).unwrap();
+        let server_tls_config = server_crypto
+            .tls_server_config(allowed_clients, REG_V1)
+            .unwrap();
+        let client_tls_config = client_crypto.tls_client_config(NODE_ID_1, REG_V1).unwrap();
+
+        let (client_certs, received) = rt.block_on(async move {
+            let mut server_builder = quinn::Endpoint::builder();
+            server_builder.listen(quic_server_config(server_tls_config));
+            let (server_endpoint, mut incoming) = server_builder
+                .bind(&"127.0.0.1:0".parse().unwrap())
+                .unwrap();
+            let server_addr = server_endpoint.local_addr().unwrap();
+            let (client_endpoint, _) = quinn::Endpoint::builder()
+                .bind(&"127.0.0.1:0".parse().unwrap())
+                .unwrap();
+
+            let server_task = tokio::spawn(async move {
+                let new_connection = incoming.next().await.unwrap().await.unwrap();
+                let client_certs: Vec<rustls::Certificate> = new_connection
+                    .connection
+                    .peer_identity()
+                    .map(|cert_chain| cert_chain.iter().cloned().collect())
+                    .unwrap_or_default();
+                let mut uni_streams = new_connection.uni_streams;
+                let recv_stream = uni_streams.next().await.unwrap().unwrap();
+                let received = recv_stream.read_to_end(MESSAGE.len()).await.unwrap();
+                (client_certs, received)
+            });
+
+            let new_connection = client_endpoint
+                .connect_with(
+                    quic_client_config(client_tls_config),
+                    &server_addr,
+                    QUIC_SERVER_NAME,
+                )
+                .unwrap()
+                .await
+                .unwrap();
+            let mut send_stream = new_connection.connection.open_uni().await.unwrap();
+            send_stream.write_all(MESSAGE).await.unwrap();
+            send_stream.finish().await.unwrap();
+
+            server_task.await.unwrap()
+        });
+
+        assert_eq!(received, MESSAGE);
+        assert_eq!(
+            server_crypto
+                .authenticated_peer_from_client_certs(&client_certs)
+                .unwrap(),
+            AuthenticatedPeer::Node(NODE_ID_2)
+        );
+    }
+}
@@ -114,6 +114,7 @@ impl TransportImpl {
             log,
             peer_map: tokio::sync::RwLock::new(HashMap::new()),
             accept_port: Mutex::new(None),
+            quic_endpoint: Mutex::new(None),
             event_handler: Mutex::new(None),
             weak_self: std::sync::RwLock::new(Weak::new()),
             use_h2,
@@ -191,7 +192,7 @@ impl Transport for TransportImpl {
     fn send(
         &self,
         peer_id: &NodeId,
-        _channel_id: TransportChannelId,
+        channel_id: TransportChannelId,
         message: TransportPayload,
     ) -> Result<(), TransportError> {
         let peer_map = self.peer_map.blocking_read();
@@ -200,7 +201,14 @@ impl Transport for TransportImpl {
             None => return Err(TransportError::NotFound),
         };
         let peer_state = peer_state_mu.blocking_read();
-        match peer_state.send_queue.enqueue(message) {
+        let send_queue = match peer_state
+            .send_queues
+            .get(self.send_queue_index(channel_id))
+        {
+            Some(send_queue) => send_queue,
+            None => return Err(TransportError::NotFound),
+        };
+        match send_queue.enqueue(message) {
             Some(unsent) => Err(TransportError::SendQueueFull(unsent)),
             None => Ok(()),
         }
@@ -211,6 +219,8 @@ impl Transport for TransportImpl {
         let peer_state = peer_map
             .get_mut(peer_id)
             .expect("Transport client not found");
-        peer_state.blocking_write().send_queue.clear();
+        for send_queue in peer_state.blocking_write().send_queues.iter_mut() {
+            send_queue.clear();
+        }
     }
 }
@@ -66,6 +66,9 @@ pub(crate) struct TransportHeader {
     pub(crate) payload_length: u32, // Serialized little endian.
 }
 
+/// The size (in bytes) of the channel id that starts every QUIC stream
+pub const QUIC_STREAM_HEADER_SIZE: usize = 4;
+
 /// This is the max frame size that H2 supports
 pub const H2_FRAME_SIZE: u32 = 16_777_215;
 
@@ -84,6 +87,9 @@ pub(crate) struct TransportImpl {
 
     /// Port used to accept connections for this transport-client
     pub accept_port: Mutex<Option<ServerPortState>>,
+    /// QUIC endpoint used to accept and initiate connections, if the
+    /// transport runs over QUIC
+    pub quic_endpoint: Mutex<Option<quinn::Endpoint>>,
     /// Mapping of peers to their corresponding state
     pub peer_map: RwLock<HashMap<NodeId, RwLock<PeerState>>>,
     /// Event handler to report back to the transport client
@@ -325,8 +331,8 @@ pub(crate) struct PeerState {
     pub peer_label: String,
     /// Connection state
     connection_state: ConnectionState,
-    /// The send queue of this flow
-    pub send_queue: Box<dyn SendQueue + Send + Sync>,
+    /// The send queues of this peer, indexed by channel id
+    pub send_queues: Vec<Box<dyn SendQueue + Send + Sync>>,
     /// Metrics
     control_plane_metrics: ControlPlaneMetrics,
 }
@@ -334,24 +340,29 @@ pub(crate) struct PeerState {
 impl PeerState {
     pub(crate) fn new(
         log: ReplicaLogger,
-        channel_id: TransportChannelId,
+        channel_ids: &[TransportChannelId],
         peer_label: String,
         connection_state: ConnectionState,
         queue_size: usize,
         send_queue_metrics: SendQueueMetrics,
         control_plane_metrics: ControlPlaneMetrics,
     ) -> Self {
-        let send_queue = Box::new(SendQueueImpl::new(
-            peer_label.clone(),
-            channel_id,
-            queue_size,
-            send_queue_metrics,
-        ));
+        let send_queues = channel_ids
+            .iter()
+            .map(|channel_id| {
+                Box::new(SendQueueImpl::new(
+                    peer_label.clone(),
+                    *channel_id,
+                    queue_size,
+                    send_queue_metrics.clone(),
+                )) as Box<dyn SendQueue + Send + Sync>
+            })
+            .collect();
         let ret = Self {
             log,
             peer_label,
             connection_state,
-            send_queue,
+            send_queues,
             control_plane_metrics,
         };
         ret.report_connection_state();
@@ -445,14 +456,17 @@ pub(crate) struct Connected {
     /// Peer node
     pub peer_addr: SocketAddr,
 
-    /// Per stream state data. The field is not dead code because
-    /// Drop is implemented for StreamState.
+    /// Per stream state data, one entry per channel. The field is not dead
+    /// code because Drop is implemented for StreamState.
     #[allow(dead_code)]
-    pub stream_state: StreamState,
+    pub stream_states: Vec<StreamState>,
 
     /// H2 connection polling task
     pub h2_conn: Option<JoinHandle<()>>,
 
+    /// QUIC connection carrying the streams
+    pub quic_conn: Option<quinn::Connection>,
+
     /// Our role
     pub role: ConnectionRole,
 }
@@ -462,6 +476,9 @@ impl Drop for Connected {
         if let Some(h2_conn) = self.h2_conn.take() {
             h2_conn.abort();
         }
+        if let Some(quic_conn) = self.quic_conn.take() {
+            quic_conn.close(0u32.into(), b"");
+        }
     }
 }
 
@@ -604,11 +621,12 @@ mod tests {
         let write_task = tokio::task::spawn(async {});
         ConnectionState::Connected(Connected {
             peer_addr: "127.0.0.1:8080".parse().unwrap(),
-            stream_state: StreamState {
+            stream_states: vec![StreamState {
                 read_task,
                 write_task,
-            },
+            }],
             h2_conn: None,
+            quic_conn: None,
             role,
         })
     }
@@ -17,9 +17,11 @@
 /// cargo run --bin transport_client --
 ///     --node <node_id>
 ///     --message_count <count>
+///     [--use_quic]
 ///
 /// If not specified, message_count = 100 (default, applies only for the source
-/// node)
+/// node). With --use_quic, the nodes are connected over QUIC instead of TCP;
+/// all three nodes must agree on it.
 use clap::{Arg, ArgMatches, Command};
 use crossbeam_channel::{self, Receiver, RecvTimeoutError, Sender};
 use rand::Rng;
@@ -65,6 +67,7 @@ type MpscReceiver = Receiver<TestMessage>;
 
 const ARG_NODE_ID: &str = "node";
 const ARG_MSG_COUNT: &str = "count";
+const ARG_USE_QUIC: &str = "use_quic";
 
 const REG_V1: RegistryVersion = RegistryVersion::new(1);
 const SUBNET_ID: u8 = 100;
@@ -400,6 +403,11 @@ fn cmd_line_matches() -> ArgMatches {
                 .default_value("100")
                 .takes_value(true),
         )
+        .arg(
+            Arg::new(ARG_USE_QUIC)
+                .long("use_quic")
+                .help("Connect the nodes over QUIC instead of TCP"),
+        )
         .get_matches()
 }
 
@@ -412,7 +420,7 @@ struct ConfigAndPeerSockets {
 // Generates the config and the registry node records for the three nodes
 // Returns a map of NodeId -> (TransportConfig, NodeRecord)
 // TODO: P2P-517 read from a config file
-fn generate_config_and_registry(node_id: &NodeId) -> ConfigAndPeerSockets {
+fn generate_config_and_registry(node_id: &NodeId, use_quic: bool) -> ConfigAndPeerSockets {
     // Tuples: (NodeId, IP, server port 1, server port 2)
     let node_info = vec![
         (to_node_id(1), "127.0.0.1".to_string(), 4100),
@@ -428,6 +436,7 @@ fn generate_config_and_registry(node_id: &NodeId) -> ConfigAndPeerSockets {
                 node_ip: n.1.clone(),
                 listening_port: n.2,
                 send_queue_size: 1024,
+                use_quic,
                 ..Default::default()
             });
         }
@@ -485,6 +494,7 @@ fn do_work_source(
 fn task_main(
     node_id_val: u8,
     message_count: usize,
+    use_quic: bool,
     active_flag: Arc<AtomicBool>,
 ) -> Result<(), TestClientErrorCode> {
     let v: Vec<u8> = vec![SUBNET_ID];
@@ -505,7 +515,7 @@ fn task_main(
         format!("transport_test_client [node {}]", node_id_val),
     );
     let log = ReplicaLogger::new(logger.root.clone().into());
-    let config_and_records = generate_config_and_registry(&node_id);
+    let config_and_records = generate_config_and_registry(&node_id, use_quic);
 
     let (prev, next, role) = parse_topology(config_and_records.peer_sockets.as_slice(), &node_id);
     info!(log, "subnet_id = {:?} node_id = {:?}", subnet_id, node_id,);
@@ -585,7 +595,14 @@ fn main() {
         .unwrap()
         .parse::<usize>()
         .unwrap();
-    task_main(node_id_val, message_count, Arc::new(AtomicBool::new(true))).unwrap()
+    let use_quic = matches.is_present(ARG_USE_QUIC);
+    task_main(
+        node_id_val,
+        message_count,
+        use_quic,
+        Arc::new(AtomicBool::new(true)),
+    )
+    .unwrap()
 }
 
 #[cfg(test)]
@@ -593,15 +610,16 @@ const TEST_NODE_COUNT: u8 = 3;
 #[cfg(test)]
 const TEST_MESSAGE_COUNT: usize = 10;
 
-#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
-async fn test_transport_spawn_tasks() {
+#[cfg(test)]
+fn run_test_nodes(use_quic: bool) {
     let active_flag = Arc::new(AtomicBool::new(true));
     let mut handles = Vec::new();
 
     // Spawn tokio tasks
     for node_id in 1..(TEST_NODE_COUNT + 1) {
         let flag = active_flag.clone();
-        let handle = std::thread::spawn(move || task_main(node_id, TEST_MESSAGE_COUNT, flag));
+        let handle =
+            std::thread::spawn(move || task_main(node_id, TEST_MESSAGE_COUNT, use_quic, flag));
         handles.push(handle);
     }
 
@@ -610,3 +628,13 @@ async fn test_transport_spawn_tasks() {
         assert!(res.is_ok());
     }
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_transport_spawn_tasks() {
+    run_test_nodes(false);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_transport_spawn_tasks_quic() {
+    run_test_nodes(true);
+}
@@ -38,6 +38,10 @@ pub enum Method {
     SetController,
     SetupInitialDKG,
     SignWithECDSA,
+    #[strum(serialize = "vetkd_public_key")]
+    VetKdPublicKey,
+    #[strum(serialize = "vetkd_encrypted_key")]
+    VetKdEncryptedKey,
     StartCanister,
     StopCanister,
     UninstallCode,
@@ -230,6 +234,7 @@ impl Payload<'_> for CanisterStatusResult {}
 ///     memory_size: nat;
 ///     cycles: nat;
 ///     idle_cycles_burned_per_day: nat;
+///     outstanding_xnet_bytes: opt vec outstanding_xnet_bytes;
 /// })`
 #[derive(CandidType, Debug, Deserialize, Eq, PartialEq)]
 pub struct CanisterStatusResultV2 {
@@ -243,6 +248,7 @@ pub struct CanisterStatusResultV2 {
     balance: Vec<(Vec<u8>, candid::Nat)>,
     freezing_threshold: candid::Nat,
     idle_cycles_burned_per_day: candid::Nat,
+    outstanding_xnet_bytes: Option<Vec<OutstandingXNetBytes>>,
 }
 
 impl CanisterStatusResultV2 {
@@ -277,9 +283,28 @@ impl CanisterStatusResultV2 {
             ),
             freezing_threshold: candid::Nat::from(freezing_threshold),
             idle_cycles_burned_per_day: candid::Nat::from(idle_cycles_burned_per_day),
+            outstanding_xnet_bytes: None,
         }
     }
 
+    /// Sets the byte size of the canister's messages not yet routed to each
+    /// remote subnet. Omitted from the result if empty.
+    pub fn with_outstanding_xnet_bytes(
+        mut self,
+        outstanding_xnet_bytes: Vec<(SubnetId, u64)>,
+    ) -> Self {
+        self.outstanding_xnet_bytes = (!outstanding_xnet_bytes.is_empty()).then(|| {
+            outstanding_xnet_bytes
+                .into_iter()
+                .map(|(subnet_id, bytes)| OutstandingXNetBytes {
+                    subnet_id,
+                    bytes: candid::Nat::from(bytes),
+                })
+                .collect()
+        });
+        self
+    }
+
     pub fn status(&self) -> CanisterStatusType {
         self.status.clone()
     }
@@ -311,6 +336,28 @@ impl CanisterStatusResultV2 {
     pub fn idle_cycles_burned_per_day(&self) -> u128 {
         self.idle_cycles_burned_per_day.0.to_u128().unwrap()
     }
+
+    pub fn outstanding_xnet_bytes(&self) -> Vec<(SubnetId, u64)> {
+        self.outstanding_xnet_bytes
+            .iter()
+            .flatten()
+            .map(|entry| (entry.subnet_id, entry.bytes.0.to_u64().unwrap()))
+            .collect()
+    }
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     subnet_id: principal;
+///     bytes: nat;
+/// })`
+///
+/// Byte size of a canister's output messages not yet routed into the stream to
+/// a remote subnet, e.g. because that subnet has not advertised capacity.
+#[derive(CandidType, Debug, Deserialize, Eq, PartialEq)]
+pub struct OutstandingXNetBytes {
+    subnet_id: SubnetId,
+    bytes: candid::Nat,
 }
 
 /// Indicates whether the canister is running, stopping, or stopped.
@@ -751,7 +798,7 @@ impl SetupInitialDKGResponse {
 
 /// Types of curves that can be used for ECDSA signing.
 /// ```text
-/// (variant { secp256k1; })
+/// (variant { secp256k1; secp256r1; })
 /// ```
 #[derive(
     CandidType, Copy, Clone, Debug, PartialOrd, Ord, PartialEq, Eq, Serialize, Deserialize, Hash,
@@ -759,6 +806,8 @@ impl SetupInitialDKGResponse {
 pub enum EcdsaCurve {
     #[serde(rename = "secp256k1")]
     Secp256k1,
+    #[serde(rename = "secp256r1")]
+    Secp256r1,
 }
 
 impl TryFrom<pb_registry_crypto::EcdsaCurve> for EcdsaCurve {
@@ -767,6 +816,7 @@ impl TryFrom<pb_registry_crypto::EcdsaCurve> for EcdsaCurve {
     fn try_from(item: pb_registry_crypto::EcdsaCurve) -> Result<Self, Self::Error> {
         match item {
             pb_registry_crypto::EcdsaCurve::Secp256k1 => Ok(EcdsaCurve::Secp256k1),
+            pb_registry_crypto::EcdsaCurve::Secp256r1 => Ok(EcdsaCurve::Secp256r1),
             pb_registry_crypto::EcdsaCurve::Unspecified => Err(ProxyDecodeError::ValueOutOfRange {
                 typ: "EcdsaCurve",
                 err: format!("Unable to convert {:?} to an EcdsaCurve", item),
@@ -779,6 +829,7 @@ impl From<EcdsaCurve> for pb_registry_crypto::EcdsaCurve {
     fn from(item: EcdsaCurve) -> Self {
         match item {
             EcdsaCurve::Secp256k1 => pb_registry_crypto::EcdsaCurve::Secp256k1,
+            EcdsaCurve::Secp256r1 => pb_registry_crypto::EcdsaCurve::Secp256r1,
         }
     }
 }
@@ -795,6 +846,7 @@ impl FromStr for EcdsaCurve {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "Secp256k1" => Ok(Self::Secp256k1),
+            "Secp256r1" => Ok(Self::Secp256r1),
             _ => Err(format!("{} is not a recognized ECDSA curve", s)),
         }
     }
@@ -802,12 +854,9 @@ impl FromStr for EcdsaCurve {
 
 #[test]
 fn ecdsa_curve_round_trip() {
-    assert_eq!(
-        format!("{}", EcdsaCurve::Secp256k1)
-            .parse::<EcdsaCurve>()
-            .unwrap(),
-        EcdsaCurve::Secp256k1
-    );
+    for curve in [EcdsaCurve::Secp256k1, EcdsaCurve::Secp256r1] {
+        assert_eq!(format!("{}", curve).parse::<EcdsaCurve>().unwrap(), curve);
+    }
 }
 
 /// Unique identifier for a key that can be used for ECDSA signatures. The name
@@ -937,6 +986,200 @@ pub struct ECDSAPublicKeyResponse {
 
 impl Payload<'_> for ECDSAPublicKeyResponse {}
 
+/// Types of curves that can be used for vetKD key derivation.
+/// ```text
+/// (variant { bls12_381_g2; })
+/// ```
+#[derive(
+    CandidType, Copy, Clone, Debug, PartialOrd, Ord, PartialEq, Eq, Serialize, Deserialize, Hash,
+)]
+#[allow(non_camel_case_types)]
+pub enum VetKdCurve {
+    #[serde(rename = "bls12_381_g2")]
+    Bls12_381_G2,
+}
+
+impl TryFrom<pb_registry_crypto::VetKdCurve> for VetKdCurve {
+    type Error = ProxyDecodeError;
+
+    fn try_from(item: pb_registry_crypto::VetKdCurve) -> Result<Self, Self::Error> {
+        match item {
+            pb_registry_crypto::VetKdCurve::Bls12381G2 => Ok(VetKdCurve::Bls12_381_G2),
+            pb_registry_crypto::VetKdCurve::Unspecified => Err(ProxyDecodeError::ValueOutOfRange {
+                typ: "VetKdCurve",
+                err: format!("Unable to convert {:?} to a VetKdCurve", item),
+            }),
+        }
+    }
+}
+
+impl From<VetKdCurve> for pb_registry_crypto::VetKdCurve {
+    fn from(item: VetKdCurve) -> Self {
+        match item {
+            VetKdCurve::Bls12_381_G2 => pb_registry_crypto::VetKdCurve::Bls12381G2,
+        }
+    }
+}
+
+impl std::fmt::Display for VetKdCurve {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl FromStr for VetKdCurve {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Bls12_381_G2" => Ok(Self::Bls12_381_G2),
+            _ => Err(format!("{} is not a recognized vetKD curve", s)),
+        }
+    }
+}
+
+/// Unique identifier for a key that can be used for vetKD key derivation.
+/// ```text
+/// (record { curve: vetkd_curve; name: text})
+/// ```
+#[derive(
+    CandidType, Clone, Debug, PartialOrd, Ord, PartialEq, Eq, Serialize, Deserialize, Hash,
+)]
+pub struct VetKdKeyId {
+    pub curve: VetKdCurve,
+    pub name: String,
+}
+
+impl TryFrom<pb_registry_crypto::VetKdKeyId> for VetKdKeyId {
+    type Error = ProxyDecodeError;
+    fn try_from(item: pb_registry_crypto::VetKdKeyId) -> Result<Self, Self::Error> {
+        Ok(Self {
+            curve: VetKdCurve::try_from(
+                pb_registry_crypto::VetKdCurve::from_i32(item.curve).ok_or(
+                    ProxyDecodeError::ValueOutOfRange {
+                        typ: "VetKdKeyId",
+                        err: format!("Unable to convert {} to a VetKdCurve", item.curve),
+                    },
+                )?,
+            )?,
+            name: item.name,
+        })
+    }
+}
+
+impl From<&VetKdKeyId> for pb_registry_crypto::VetKdKeyId {
+    fn from(item: &VetKdKeyId) -> Self {
+        Self {
+            curve: pb_registry_crypto::VetKdCurve::from(item.curve) as i32,
+            name: item.name.clone(),
+        }
+    }
+}
+
+impl std::fmt::Display for VetKdKeyId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.curve, self.name)
+    }
+}
+
+impl FromStr for VetKdKeyId {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (curve, name) = s
+            .split_once(':')
+            .ok_or_else(|| format!("vetKD key id {} does not contain a ':'", s))?;
+        Ok(VetKdKeyId {
+            curve: curve.parse::<VetKdCurve>()?,
+            name: name.to_string(),
+        })
+    }
+}
+
+#[test]
+fn vetkd_key_id_round_trip() {
+    for name in ["bls12_381_g2", "", "other_key", "other key", "other:key"] {
+        let key = VetKdKeyId {
+            curve: VetKdCurve::Bls12_381_G2,
+            name: name.to_string(),
+        };
+        assert_eq!(format!("{}", key).parse::<VetKdKeyId>().unwrap(), key);
+        assert_eq!(
+            VetKdKeyId::try_from(pb_registry_crypto::VetKdKeyId::from(&key)).unwrap(),
+            key
+        );
+    }
+}
+
+/// Represents the argument of the vetkd_public_key API.
+/// ```text
+/// (record {
+///   canister_id : opt canister_id;
+///   derivation_path : vec blob;
+///   key_id : vetkd_key_id;
+/// })
+/// ```
+#[derive(CandidType, Deserialize, Debug)]
+pub struct VetKdPublicKeyArgs {
+    pub canister_id: Option<CanisterId>,
+    pub derivation_path: Vec<Vec<u8>>,
+    pub key_id: VetKdKeyId,
+}
+
+impl Payload<'_> for VetKdPublicKeyArgs {}
+
+/// Represents the response of the vetkd_public_key API.
+/// ```text
+/// (record {
+///   public_key : blob;
+/// })
+/// ```
+#[derive(CandidType, Deserialize, Debug)]
+pub struct VetKdPublicKeyResult {
+    #[serde(with = "serde_bytes")]
+    pub public_key: Vec<u8>,
+}
+
+impl Payload<'_> for VetKdPublicKeyResult {}
+
+/// Represents the argument of the vetkd_encrypted_key API.
+///
+/// The derived key is encrypted under `encryption_public_key`, a transport
+/// public key chosen by the user, so that neither the canister nor any single
+/// replica learns it.
+/// ```text
+/// (record {
+///   derivation_path : vec blob;
+///   derivation_id : blob;
+///   key_id : vetkd_key_id;
+///   encryption_public_key : blob;
+/// })
+/// ```
+#[derive(CandidType, Deserialize, Debug)]
+pub struct VetKdEncryptedKeyArgs {
+    pub derivation_path: Vec<Vec<u8>>,
+    #[serde(with = "serde_bytes")]
+    pub derivation_id: Vec<u8>,
+    pub key_id: VetKdKeyId,
+    #[serde(with = "serde_bytes")]
+    pub encryption_public_key: Vec<u8>,
+}
+
+impl Payload<'_> for VetKdEncryptedKeyArgs {}
+
+/// Represents the response of the vetkd_encrypted_key API.
+/// ```text
+/// (record {
+///   encrypted_key : blob;
+/// })
+/// ```
+#[derive(CandidType, Deserialize, Debug)]
+pub struct VetKdEncryptedKeyResult {
+    #[serde(with = "serde_bytes")]
+    pub encrypted_key: Vec<u8>,
+}
+
+impl Payload<'_> for VetKdEncryptedKeyResult {}
+
 /// Argument of the compute_initial_ecdsa_dealings API.
 /// `(record {
 ///     key_id: ecdsa_key_id;
@@ -106,7 +106,9 @@ pub struct Summary {
     #[serde_as(as = "Vec<(_, _)>")]
     pub configs: BTreeMap<NiDkgId, NiDkgConfig>,
     /// Current transcripts indexed by their tags. The values are guaranteed
-    /// to be present, if a DKG is being computed for a given tag.
+    /// to be present for the low and high threshold tags. The `VetKd`
+    /// transcript is only present once the first vetKD DKG of the subnet
+    /// succeeded.
     #[serde_as(as = "Vec<(_, _)>")]
     current_transcripts: BTreeMap<NiDkgTag, NiDkgTranscript>,
     /// Transcripts for the next DKG interval. The values are not guaranteed to
@@ -171,7 +173,8 @@ impl Summary {
 
     /// Returns a reference to the current transcript for the given tag. Note
     /// that currently we expect that a valid summary contains the current
-    /// transcript for any DKG tag.
+    /// transcript for the low and high threshold tags. Use
+    /// [`Self::current_transcripts`] to look up the `VetKd` transcript.
     pub fn current_transcript(&self, tag: &NiDkgTag) -> &NiDkgTranscript {
         self.current_transcripts
             .get(tag)
@@ -206,13 +209,18 @@ impl Summary {
 
     /// Return the set of next transcripts for all tags. If for some tag
     /// the next transcript is not available, the current transcript is used.
+    /// Tags without a current transcript, e.g., the first `VetKd` transcript,
+    /// are included if their next transcript is available.
     /// This function avoids expensive copying when transcripts are large.
     pub fn into_next_transcripts(self) -> BTreeMap<NiDkgTag, NiDkgTranscript> {
         let mut next_transcripts = self.next_transcripts;
-        self.current_transcripts
+        let mut transcripts: BTreeMap<_, _> = self
+            .current_transcripts
             .into_iter()
             .map(|(tag, current)| (tag, next_transcripts.remove(&tag).unwrap_or(current)))
-            .collect()
+            .collect();
+        transcripts.append(&mut next_transcripts);
+        transcripts
     }
 
     /// Returns `true` if the provided height is included in the DKG interval
@@ -492,7 +500,7 @@ impl NiDkgTag {
         let f = crate::consensus::get_faults_tolerated(committee_size);
         match self {
             NiDkgTag::LowThreshold => f + 1,
-            NiDkgTag::HighThreshold => committee_size - f,
+            NiDkgTag::HighThreshold | NiDkgTag::VetKd => committee_size - f,
         }
     }
 }
@@ -528,6 +536,58 @@ mod tests {
         assert_eq!(high_threshold_tag.threshold_for_subnet_of_size(64), 43);
     }
 
+    #[test]
+    fn should_calculate_same_threshold_for_vetkd_and_high_threshold() {
+        for subnet_size in [0, 1, 4, 13, 28, 64] {
+            assert_eq!(
+                NiDkgTag::VetKd.threshold_for_subnet_of_size(subnet_size),
+                NiDkgTag::HighThreshold.threshold_for_subnet_of_size(subnet_size)
+            );
+        }
+    }
+
+    #[test]
+    fn should_include_next_transcripts_without_current_transcript() {
+        let transcript = |tag| {
+            NiDkgTranscript::dummy_transcript_for_tests_with_params(
+                vec![NodeId::from(PrincipalId::new_node_test_id(0))],
+                tag,
+                1,
+                0,
+            )
+        };
+        let current_transcripts = [NiDkgTag::LowThreshold, NiDkgTag::HighThreshold]
+            .into_iter()
+            .map(|tag| (tag, transcript(tag)))
+            .collect();
+        let next_transcripts = [NiDkgTag::HighThreshold, NiDkgTag::VetKd]
+            .into_iter()
+            .map(|tag| (tag, transcript(tag)))
+            .collect();
+        let summary = Summary::new(
+            vec![],
+            current_transcripts,
+            next_transcripts,
+            vec![],
+            RegistryVersion::from(1),
+            Height::from(9),
+            Height::from(9),
+            Height::from(0),
+            BTreeMap::new(),
+        );
+
+        let transcripts = summary.into_next_transcripts();
+
+        assert_eq!(
+            transcripts.keys().copied().collect::<Vec<_>>(),
+            vec![
+                NiDkgTag::LowThreshold,
+                NiDkgTag::HighThreshold,
+                NiDkgTag::VetKd
+            ]
+        );
+    }
+
     #[test]
     fn should_correctly_calculate_faults_tolerated_for_committee_of_size() {
         use crate::consensus::get_faults_tolerated;
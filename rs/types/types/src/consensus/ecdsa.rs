@@ -24,8 +24,11 @@ use crate::crypto::{
         IDkgComplaint, IDkgDealingSupport, IDkgOpening, IDkgTranscript, IDkgTranscriptId,
         IDkgTranscriptParams, InitialIDkgDealings, SignedIDkgDealing,
     },
-    canister_threshold_sig::ThresholdEcdsaSigShare,
-    crypto_hash, AlgorithmId, CryptoHash, CryptoHashOf, CryptoHashable, Signed,
+    canister_threshold_sig::{ExtendedDerivationPath, ThresholdEcdsaSigShare},
+    crypto_hash,
+    threshold_sig::ni_dkg::NiDkgId,
+    vetkd::{VetKdArgs, VetKdEncryptedKeyShare},
+    AlgorithmId, CryptoHash, CryptoHashOf, CryptoHashable, Signed,
     SignedBytesWithoutDomainSeparator,
 };
 use crate::{node_id_into_protobuf, node_id_try_from_protobuf};
@@ -74,6 +77,12 @@ pub struct EcdsaPayload {
     /// Completed resharing requests.
     pub xnet_reshare_agreements: BTreeMap<EcdsaReshareRequest, CompletedReshareRequest>,
 
+    /// The vetKD requests for which we are currently creating encrypted keys.
+    pub ongoing_vetkd_requests: BTreeMap<PseudoRandomId, VetKdArgs>,
+
+    /// Collection of completed vetKD encrypted keys.
+    pub vetkd_agreements: BTreeMap<PseudoRandomId, CompletedSignature>,
+
     /// State of the key transcripts.
     pub key_transcript: EcdsaKeyTranscript,
 }
@@ -657,6 +666,7 @@ pub enum EcdsaMessage {
     EcdsaSigShare(EcdsaSigShare),
     EcdsaComplaint(EcdsaComplaint),
     EcdsaOpening(EcdsaOpening),
+    VetKdKeyShare(VetKdKeyShare),
 }
 
 /// EcdsaArtifactId is the unique identifier for the artifacts. It is made of a prefix + crypto
@@ -787,6 +797,19 @@ pub fn opening_prefix(
     EcdsaPrefixOf::new(EcdsaPrefix::new(transcript_id.id(), hasher.finish()))
 }
 
+pub fn vetkd_key_share_prefix(
+    pseudo_random_id: &PseudoRandomId,
+    signer_id: &NodeId,
+) -> EcdsaPrefixOf<VetKdKeyShare> {
+    // Group_tag: leading bytes of the pseudo random Id, Meta info: <pseudo random Id + signer>
+    let mut hasher = Sha256::new();
+    pseudo_random_id.hash(&mut hasher);
+    signer_id.hash(&mut hasher);
+
+    let group_tag = u64::from_be_bytes((&pseudo_random_id[0..8]).try_into().unwrap());
+    EcdsaPrefixOf::new(EcdsaPrefix::new(group_tag, hasher.finish()))
+}
+
 /// The identifier for artifacts/messages.
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Hash)]
 pub enum EcdsaArtifactId {
@@ -801,6 +824,7 @@ pub enum EcdsaArtifactId {
     SigShare(EcdsaPrefixOf<EcdsaSigShare>, CryptoHashOf<EcdsaSigShare>),
     Complaint(EcdsaPrefixOf<EcdsaComplaint>, CryptoHashOf<EcdsaComplaint>),
     Opening(EcdsaPrefixOf<EcdsaOpening>, CryptoHashOf<EcdsaOpening>),
+    VetKdKeyShare(EcdsaPrefixOf<VetKdKeyShare>, CryptoHashOf<VetKdKeyShare>),
 }
 
 impl EcdsaArtifactId {
@@ -811,6 +835,7 @@ impl EcdsaArtifactId {
             EcdsaArtifactId::SigShare(prefix, _) => prefix.as_ref().clone(),
             EcdsaArtifactId::Complaint(prefix, _) => prefix.as_ref().clone(),
             EcdsaArtifactId::Opening(prefix, _) => prefix.as_ref().clone(),
+            EcdsaArtifactId::VetKdKeyShare(prefix, _) => prefix.as_ref().clone(),
         }
     }
 
@@ -821,6 +846,7 @@ impl EcdsaArtifactId {
             EcdsaArtifactId::SigShare(_, hash) => hash.as_ref().clone(),
             EcdsaArtifactId::Complaint(_, hash) => hash.as_ref().clone(),
             EcdsaArtifactId::Opening(_, hash) => hash.as_ref().clone(),
+            EcdsaArtifactId::VetKdKeyShare(_, hash) => hash.as_ref().clone(),
         }
     }
 
@@ -855,6 +881,10 @@ impl From<(EcdsaMessageType, EcdsaPrefix, CryptoHash)> for EcdsaArtifactId {
             EcdsaMessageType::Opening => {
                 EcdsaArtifactId::Opening(EcdsaPrefixOf::new(prefix), CryptoHashOf::new(crypto_hash))
             }
+            EcdsaMessageType::VetKdKeyShare => EcdsaArtifactId::VetKdKeyShare(
+                EcdsaPrefixOf::new(prefix),
+                CryptoHashOf::new(crypto_hash),
+            ),
         }
     }
 }
@@ -868,6 +898,7 @@ pub enum EcdsaMessageType {
     SigShare,
     Complaint,
     Opening,
+    VetKdKeyShare,
 }
 
 impl From<&EcdsaMessage> for EcdsaMessageType {
@@ -878,6 +909,7 @@ impl From<&EcdsaMessage> for EcdsaMessageType {
             EcdsaMessage::EcdsaSigShare(_) => EcdsaMessageType::SigShare,
             EcdsaMessage::EcdsaComplaint(_) => EcdsaMessageType::Complaint,
             EcdsaMessage::EcdsaOpening(_) => EcdsaMessageType::Opening,
+            EcdsaMessage::VetKdKeyShare(_) => EcdsaMessageType::VetKdKeyShare,
         }
     }
 }
@@ -890,6 +922,7 @@ impl From<&EcdsaArtifactId> for EcdsaMessageType {
             EcdsaArtifactId::SigShare(..) => EcdsaMessageType::SigShare,
             EcdsaArtifactId::Complaint(..) => EcdsaMessageType::Complaint,
             EcdsaArtifactId::Opening(..) => EcdsaMessageType::Opening,
+            EcdsaArtifactId::VetKdKeyShare(..) => EcdsaMessageType::VetKdKeyShare,
         }
     }
 }
@@ -902,6 +935,7 @@ impl EcdsaMessageType {
             Self::SigShare => "sig_share",
             Self::Complaint => "complaint",
             Self::Opening => "opening",
+            Self::VetKdKeyShare => "vetkd_key_share",
         }
     }
 }
@@ -929,6 +963,33 @@ impl Display for EcdsaSigShare {
     }
 }
 
+/// The vetKD encrypted key share
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub struct VetKdKeyShare {
+    /// The node that created the share
+    pub signer_id: NodeId,
+
+    /// The request this encrypted key share belongs to
+    pub pseudo_random_id: PseudoRandomId,
+
+    /// The NI-DKG transcript whose threshold key the share was created with
+    pub ni_dkg_id: NiDkgId,
+
+    /// The encrypted key share
+    pub share: VetKdEncryptedKeyShare,
+}
+
+impl Display for VetKdKeyShare {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "VetKdKeyShare[pseudo_random_id = {}, signer_id = {:?}]",
+            hex::encode(self.pseudo_random_id),
+            self.signer_id,
+        )
+    }
+}
+
 /// Complaint related defines
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Hash)]
 pub struct EcdsaComplaintContent {
@@ -1015,6 +1076,7 @@ pub enum EcdsaMessageAttribute {
     EcdsaSigShare(RequestId),
     EcdsaComplaint(IDkgTranscriptId),
     EcdsaOpening(IDkgTranscriptId),
+    VetKdKeyShare(PseudoRandomId),
 }
 
 impl From<&EcdsaMessage> for EcdsaMessageAttribute {
@@ -1035,6 +1097,9 @@ impl From<&EcdsaMessage> for EcdsaMessageAttribute {
             EcdsaMessage::EcdsaOpening(opening) => {
                 EcdsaMessageAttribute::EcdsaOpening(opening.content.idkg_opening.transcript_id)
             }
+            EcdsaMessage::VetKdKeyShare(share) => {
+                EcdsaMessageAttribute::VetKdKeyShare(share.pseudo_random_id)
+            }
         }
     }
 }
@@ -1047,6 +1112,7 @@ impl EcdsaMessageAttribute {
             Self::EcdsaSigShare(_) => "sig_share",
             Self::EcdsaComplaint(_) => "complaint",
             Self::EcdsaOpening(_) => "opening",
+            Self::VetKdKeyShare(_) => "vetkd_key_share",
         }
     }
 }
@@ -1101,6 +1167,16 @@ impl TryFrom<EcdsaMessage> for EcdsaOpening {
     }
 }
 
+impl TryFrom<EcdsaMessage> for VetKdKeyShare {
+    type Error = EcdsaMessage;
+    fn try_from(msg: EcdsaMessage) -> Result<Self, Self::Error> {
+        match msg {
+            EcdsaMessage::VetKdKeyShare(x) => Ok(x),
+            _ => Err(msg),
+        }
+    }
+}
+
 pub type Summary = Option<EcdsaPayload>;
 
 pub type Payload = Option<EcdsaPayload>;
@@ -1184,6 +1260,32 @@ impl From<&EcdsaPayload> for pb::EcdsaPayload {
             });
         }
 
+        // ongoing_vetkd_requests
+        let mut ongoing_vetkd_requests = Vec::new();
+        for (pseudo_random_id, args) in &payload.ongoing_vetkd_requests {
+            ongoing_vetkd_requests.push(pb::OngoingVetKdRequest {
+                pseudo_random_id: pseudo_random_id.to_vec(),
+                ni_dkg_id: Some(args.ni_dkg_id.into()),
+                derivation_path: Some(args.derivation_path.clone().into()),
+                derivation_id: args.derivation_id.clone(),
+                encryption_public_key: args.encryption_public_key.clone(),
+            });
+        }
+
+        // vetkd_agreements
+        let mut vetkd_agreements = Vec::new();
+        for (pseudo_random_id, completed) in &payload.vetkd_agreements {
+            let unreported = match completed {
+                CompletedSignature::Unreported(response) => Some(response.into()),
+                CompletedSignature::ReportedToExecution => None,
+            };
+            vetkd_agreements.push(pb::CompletedSignature {
+                request_id: None,
+                pseudo_random_id: pseudo_random_id.to_vec(),
+                unreported,
+            });
+        }
+
         let current_key_transcript = payload
             .key_transcript
             .current
@@ -1205,6 +1307,8 @@ impl From<&EcdsaPayload> for pb::EcdsaPayload {
             current_key_transcript,
             next_key_in_creation,
             key_id,
+            ongoing_vetkd_requests,
+            vetkd_agreements,
         }
     }
 }
@@ -1362,6 +1466,49 @@ impl TryFrom<&pb::EcdsaPayload> for EcdsaPayload {
             xnet_reshare_agreements.insert(request, completed);
         }
 
+        // ongoing_vetkd_requests
+        let mut ongoing_vetkd_requests = BTreeMap::new();
+        for request in &payload.ongoing_vetkd_requests {
+            let pseudo_random_id = pseudo_random_id_try_from_protobuf(&request.pseudo_random_id)?;
+            let ni_dkg_id = NiDkgId::from_option_protobuf(
+                request.ni_dkg_id.clone(),
+                "pb::EcdsaPayload::ongoing_vetkd_requests",
+            )?;
+            let proto = request
+                .derivation_path
+                .clone()
+                .ok_or("pb::EcdsaPayload:: Missing vetkd derivation path")?;
+            let derivation_path = ExtendedDerivationPath::try_from(proto).map_err(|err| {
+                format!(
+                    "pb::EcdsaPayload:: Failed to convert vetkd derivation path: {:?}",
+                    err
+                )
+            })?;
+            ongoing_vetkd_requests.insert(
+                pseudo_random_id,
+                VetKdArgs {
+                    ni_dkg_id,
+                    derivation_path,
+                    derivation_id: request.derivation_id.clone(),
+                    encryption_public_key: request.encryption_public_key.clone(),
+                },
+            );
+        }
+
+        // vetkd_agreements
+        let mut vetkd_agreements = BTreeMap::new();
+        for completed in &payload.vetkd_agreements {
+            let pseudo_random_id = pseudo_random_id_try_from_protobuf(&completed.pseudo_random_id)?;
+            let key = if let Some(unreported) = &completed.unreported {
+                let response = crate::messages::Response::try_from(unreported.clone())
+                    .map_err(|err| format!("{:?}", err))?;
+                CompletedSignature::Unreported(response)
+            } else {
+                CompletedSignature::ReportedToExecution
+            };
+            vetkd_agreements.insert(pseudo_random_id, key);
+        }
+
         // Key transcript state
         let current_key_transcript: Option<UnmaskedTranscriptWithAttributes> =
             if let Some(proto) = &payload.current_key_transcript {
@@ -1402,6 +1549,8 @@ impl TryFrom<&pb::EcdsaPayload> for EcdsaPayload {
             idkg_transcripts,
             ongoing_xnet_reshares,
             xnet_reshare_agreements,
+            ongoing_vetkd_requests,
+            vetkd_agreements,
             uid_generator,
             key_transcript: EcdsaKeyTranscript {
                 current: current_key_transcript,
@@ -1412,6 +1561,12 @@ impl TryFrom<&pb::EcdsaPayload> for EcdsaPayload {
     }
 }
 
+fn pseudo_random_id_try_from_protobuf(bytes: &[u8]) -> Result<PseudoRandomId, String> {
+    bytes
+        .try_into()
+        .map_err(|_| "Expects 32 bytes of pseudo_random_id".to_string())
+}
+
 ///
 /// Processing/updates for a particular entity like TranscriptId is scattered across
 /// several paths, called from different contexts (e.g)
@@ -1547,6 +1702,16 @@ impl EcdsaObject for EcdsaOpening {
     }
 }
 
+impl EcdsaObject for VetKdKeyShare {
+    fn message_prefix(&self) -> EcdsaPrefixOf<Self> {
+        vetkd_key_share_prefix(&self.pseudo_random_id, &self.signer_id)
+    }
+
+    fn message_id(&self) -> EcdsaArtifactId {
+        EcdsaArtifactId::VetKdKeyShare(self.message_prefix(), crypto_hash(self))
+    }
+}
+
 pub fn ecdsa_msg_id(msg: &EcdsaMessage) -> EcdsaArtifactId {
     match msg {
         EcdsaMessage::EcdsaSignedDealing(object) => object.message_id(),
@@ -1554,5 +1719,6 @@ pub fn ecdsa_msg_id(msg: &EcdsaMessage) -> EcdsaArtifactId {
         EcdsaMessage::EcdsaSigShare(object) => object.message_id(),
         EcdsaMessage::EcdsaComplaint(object) => object.message_id(),
         EcdsaMessage::EcdsaOpening(object) => object.message_id(),
+        EcdsaMessage::VetKdKeyShare(object) => object.message_id(),
     }
 }
@@ -17,6 +17,7 @@ use crate::crypto::{
     canister_threshold_sig::{
         ExtendedDerivationPath, PreSignatureQuadruple, ThresholdEcdsaSigInputs,
     },
+    vetkd::VetKdArgs,
     AlgorithmId,
 };
 use crate::{Height, Randomness, RegistryVersion};
@@ -902,6 +903,9 @@ pub trait EcdsaBlockReader: Send + Sync {
         &self,
     ) -> Box<dyn Iterator<Item = (&RequestId, &ThresholdEcdsaSigInputsRef)> + '_>;
 
+    /// Returns the vetKD encrypted keys requested by the tip.
+    fn requested_vetkd_keys(&self) -> Box<dyn Iterator<Item = (&PseudoRandomId, &VetKdArgs)> + '_>;
+
     /// Returns the set of all the active references.
     fn active_transcripts(&self) -> BTreeSet<TranscriptRef>;
 
@@ -15,6 +15,7 @@ pub use sign::{Signable, SignableMock};
 
 pub mod error;
 pub mod threshold_sig;
+pub mod vetkd;
 
 use crate::crypto::threshold_sig::ni_dkg::DkgId;
 use crate::registry::RegistryClientError;
@@ -142,6 +143,8 @@ pub enum AlgorithmId {
     RsaSha256 = 14,
     ThresholdEcdsaSecp256k1 = 15,
     MegaSecp256k1 = 16,
+    ThresholdSchnorrBip340 = 17,
+    ThresholdEcdsaSecp256r1 = 18,
 }
 
 impl AlgorithmId {
@@ -198,6 +201,8 @@ impl From<i32> for AlgorithmId {
             14 => AlgorithmId::RsaSha256,
             15 => AlgorithmId::ThresholdEcdsaSecp256k1,
             16 => AlgorithmId::MegaSecp256k1,
+            17 => AlgorithmId::ThresholdSchnorrBip340,
+            18 => AlgorithmId::ThresholdEcdsaSecp256r1,
             _ => AlgorithmId::Placeholder,
         }
     }
@@ -321,6 +321,9 @@ pub struct ThresholdEcdsaSigInputs {
 // The byte length of an hashed message for ECDSA signatures over the curve secp256k1.
 pub const ECDSA_SECP256K1_HASH_BYTE_LENGTH: usize = 32;
 
+// The byte length of an hashed message for ECDSA signatures over the curve secp256r1.
+pub const ECDSA_SECP256R1_HASH_BYTE_LENGTH: usize = 32;
+
 impl Display for ThresholdEcdsaSigInputs {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(f, "{:?}", self)
@@ -437,6 +440,12 @@ impl ThresholdEcdsaSigInputs {
                 }
                 Ok(())
             }
+            AlgorithmId::ThresholdEcdsaSecp256r1 => {
+                if hashed_message.len() != ECDSA_SECP256R1_HASH_BYTE_LENGTH {
+                    return Err(error::ThresholdEcdsaSigInputsCreationError::InvalidHashLength);
+                }
+                Ok(())
+            }
             _ => Err(error::ThresholdEcdsaSigInputsCreationError::UnsupportedAlgorithm),
         }
     }
@@ -10,6 +10,7 @@ use crate::consensus::{
     certification::{Certification, CertificationContent, CertificationShare},
     ecdsa::{
         EcdsaComplaintContent, EcdsaMessage, EcdsaOpeningContent, EcdsaSigShare, EcdsaTranscript,
+        VetKdKeyShare,
     },
     Block, BlockPayload, CatchUpContent, CatchUpContentProtobufBytes, CatchUpShareContent,
     ConsensusMessage, FinalizationContent, HashedBlock, NotarizationContent, RandomBeaconContent,
@@ -88,6 +89,7 @@ pub(crate) const DOMAIN_SIGNED_IDKG_DEALING: &str = "ic-idkg-signed-dealing-doma
 const DOMAIN_IDKG_DEALING_SUPPORT: &str = "ic-idkg-dealing-support-domain";
 const DOMAIN_ECDSA_TRANSCRIPT: &str = "ic-idkg-transcript-domain";
 const DOMAIN_ECDSA_SIG_SHARE: &str = "ic-threshold-ecdsa-sig-share-domain";
+const DOMAIN_VETKD_KEY_SHARE: &str = "ic-vetkd-key-share-domain";
 pub(crate) const DOMAIN_ECDSA_COMPLAINT_CONTENT: &str =
     "ic-threshold-ecdsa-complaint-content-domain";
 pub const DOMAIN_ECDSA_COMPLAINT: &str = "ic-threshold-ecdsa-complaint-domain";
@@ -186,6 +188,7 @@ mod private {
 
     impl CryptoHashDomainSeal for EcdsaTranscript {}
     impl CryptoHashDomainSeal for EcdsaSigShare {}
+    impl CryptoHashDomainSeal for VetKdKeyShare {}
 
     impl CryptoHashDomainSeal for EcdsaComplaintContent {}
     impl CryptoHashDomainSeal for Signed<EcdsaComplaintContent, BasicSignature<EcdsaComplaintContent>> {}
@@ -442,6 +445,12 @@ impl CryptoHashDomain for EcdsaSigShare {
     }
 }
 
+impl CryptoHashDomain for VetKdKeyShare {
+    fn domain(&self) -> String {
+        DOMAIN_VETKD_KEY_SHARE.to_string()
+    }
+}
+
 impl CryptoHashDomain for EcdsaComplaintContent {
     fn domain(&self) -> String {
         DOMAIN_ECDSA_COMPLAINT_CONTENT.to_string()
@@ -28,13 +28,17 @@ pub use id::NiDkgId;
 mod tests;
 
 /// Allows to distinguish protocol executions in high and low threshold
-/// settings.
+/// settings, and the high threshold setting of the subnet's vetKD key.
 #[derive(
     Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, EnumIter,
 )]
 pub enum NiDkgTag {
     LowThreshold = 1,
     HighThreshold = 2,
+    /// The transcript from which the subnet derives vetKD keys. It is kept
+    /// separate from the `HighThreshold` transcript so that derived keys are
+    /// never signatures under the subnet's threshold signing key.
+    VetKd = 3,
 }
 
 impl From<&NiDkgTag> for pb::NiDkgTag {
@@ -42,6 +46,7 @@ impl From<&NiDkgTag> for pb::NiDkgTag {
         match tag {
             NiDkgTag::LowThreshold => pb::NiDkgTag::LowThreshold,
             NiDkgTag::HighThreshold => pb::NiDkgTag::HighThreshold,
+            NiDkgTag::VetKd => pb::NiDkgTag::VetKd,
         }
     }
 }
@@ -104,6 +109,7 @@ impl TryFrom<i32> for NiDkgTag {
         match ni_dkg_tag {
             1 => Ok(NiDkgTag::LowThreshold),
             2 => Ok(NiDkgTag::HighThreshold),
+            3 => Ok(NiDkgTag::VetKd),
             _ => Err(()),
         }
     }
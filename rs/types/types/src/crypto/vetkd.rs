@@ -0,0 +1,182 @@
+//! Defines types used for verifiably encrypted threshold key derivation (vetKD).
+use crate::crypto::canister_threshold_sig::ExtendedDerivationPath;
+use crate::crypto::threshold_sig::errors::threshold_sig_data_not_found_error::ThresholdSigDataNotFoundError;
+use crate::crypto::threshold_sig::ni_dkg::NiDkgId;
+use crate::PrincipalId;
+use ic_ic00_types::VetKdKeyId;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The arguments of a vetKD key derivation.
+///
+/// The derived key is bound to `derivation_path` and `derivation_id`, and is
+/// encrypted under `encryption_public_key`. The master key is the threshold
+/// key of the NI-DKG transcript identified by `ni_dkg_id`.
+#[derive(Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct VetKdArgs {
+    pub ni_dkg_id: NiDkgId,
+    pub derivation_path: ExtendedDerivationPath,
+    #[serde(with = "serde_bytes")]
+    pub derivation_id: Vec<u8>,
+    #[serde(with = "serde_bytes")]
+    pub encryption_public_key: Vec<u8>,
+}
+
+impl fmt::Debug for VetKdArgs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VetKdArgs")
+            .field("ni_dkg_id", &self.ni_dkg_id)
+            .field("derivation_path", &self.derivation_path)
+            .field("derivation_id", &hex::encode(&self.derivation_id))
+            .field(
+                "encryption_public_key",
+                &hex::encode(&self.encryption_public_key),
+            )
+            .finish()
+    }
+}
+
+/// Returns the derivation path under which `caller` derives keys for
+/// `key_id`.
+///
+/// All vetKD keys of a subnet are derived from the subnet's threshold key,
+/// so the key name is prepended to the caller's derivation path to make keys
+/// with different names independent of each other.
+pub fn vetkd_derivation_path(
+    caller: PrincipalId,
+    key_id: &VetKdKeyId,
+    derivation_path: &[Vec<u8>],
+) -> ExtendedDerivationPath {
+    let mut path = Vec::with_capacity(derivation_path.len() + 1);
+    path.push(key_id.name.as_bytes().to_vec());
+    path.extend_from_slice(derivation_path);
+    ExtendedDerivationPath {
+        caller,
+        derivation_path: path,
+    }
+}
+
+/// A node's share of a vetKD encrypted key.
+#[derive(Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct VetKdEncryptedKeyShare {
+    #[serde(with = "serde_bytes")]
+    pub encrypted_key_share: Vec<u8>,
+}
+
+impl fmt::Debug for VetKdEncryptedKeyShare {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "VetKdEncryptedKeyShare {{ encrypted_key_share: {} }}",
+            hex::encode(&self.encrypted_key_share)
+        )
+    }
+}
+
+impl fmt::Display for VetKdEncryptedKeyShare {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// A vetKD encrypted key, i.e., the combination of a threshold number of
+/// encrypted key shares.
+#[derive(Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct VetKdEncryptedKey {
+    #[serde(with = "serde_bytes")]
+    pub encrypted_key: Vec<u8>,
+}
+
+impl fmt::Debug for VetKdEncryptedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "VetKdEncryptedKey {{ encrypted_key: {} }}",
+            hex::encode(&self.encrypted_key)
+        )
+    }
+}
+
+impl fmt::Display for VetKdEncryptedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum VetKdKeyShareCreationError {
+    ThresholdSigDataNotFound(ThresholdSigDataNotFoundError),
+    SecretKeyNotFound { dkg_id: NiDkgId, key_id: String },
+    InvalidArgument(String),
+    InternalError(String),
+    TransientInternalError(String),
+}
+
+impl fmt::Display for VetKdKeyShareCreationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for VetKdKeyShareCreationError {}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum VetKdKeyShareVerificationError {
+    ThresholdSigDataNotFound(ThresholdSigDataNotFoundError),
+    SignerNotInTranscript,
+    InvalidArgument(String),
+    VerificationError,
+}
+
+impl fmt::Display for VetKdKeyShareVerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for VetKdKeyShareVerificationError {}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum VetKdKeyShareCombinationError {
+    ThresholdSigDataNotFound(ThresholdSigDataNotFoundError),
+    UnsatisfiedReconstructionThreshold { threshold: u32, share_count: usize },
+    SignerNotInTranscript(String),
+    InvalidArgument(String),
+    CombinationError(String),
+}
+
+impl fmt::Display for VetKdKeyShareCombinationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for VetKdKeyShareCombinationError {}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum VetKdKeyVerificationError {
+    ThresholdSigDataNotFound(ThresholdSigDataNotFoundError),
+    InvalidArgument(String),
+    VerificationError,
+}
+
+impl fmt::Display for VetKdKeyVerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for VetKdKeyVerificationError {}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum VetKdDerivePublicKeyError {
+    InvalidArgument(String),
+}
+
+impl fmt::Display for VetKdDerivePublicKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for VetKdDerivePublicKeyError {}
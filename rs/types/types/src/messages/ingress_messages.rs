@@ -465,6 +465,8 @@ pub fn extract_effective_canister_id(
         | Ok(Method::RawRand)
         | Ok(Method::ECDSAPublicKey)
         | Ok(Method::SignWithECDSA)
+        | Ok(Method::VetKdPublicKey)
+        | Ok(Method::VetKdEncryptedKey)
         | Ok(Method::ComputeInitialEcdsaDealings)
         | Ok(Method::BitcoinGetBalance)
         | Ok(Method::BitcoinGetUtxos)
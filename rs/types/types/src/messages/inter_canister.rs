@@ -106,6 +106,8 @@ impl Request {
             | Ok(Method::RawRand)
             | Ok(Method::ECDSAPublicKey)
             | Ok(Method::SignWithECDSA)
+            | Ok(Method::VetKdPublicKey)
+            | Ok(Method::VetKdEncryptedKey)
             | Ok(Method::ComputeInitialEcdsaDealings)
             | Ok(Method::BitcoinGetBalance)
             | Ok(Method::BitcoinGetUtxos)
@@ -166,6 +166,11 @@ pub struct StreamHeader {
 
     /// Stream indices of rejected messages, in ascending order.
     pub reject_signals: VecDeque<StreamIndex>,
+
+    /// Byte size of messages that the subnet producing this header is willing
+    /// to accept from the destination subnet beyond `signals_end`; or `None`
+    /// if it does not advertise a capacity.
+    pub capacity_bytes: Option<u64>,
 }
 
 /// A continuous slice of messages pulled from a remote subnet.  The slice also